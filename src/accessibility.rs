@@ -0,0 +1,117 @@
+//! System accessibility preferences: reduced motion and reduced transparency
+//!
+//! Windows exposes "Animation effects" as SPI_GETCLIENTAREAANIMATION and
+//! "Transparency effects" as the `EnableTransparency` value under the Personalize
+//! key. Both are cached here and refreshed on WM_SETTINGCHANGE; the config can
+//! force either preference on or off.
+//!
+//! Reduced motion turns pulsing and flashing effects into steady ones. Reduced
+//! transparency drops the acrylic backdrop and makes the bar and its popups opaque.
+//!
+//! Whether a screen reader is running (SPI_GETSCREENREADER) is tracked too, so audio
+//! cues can leave room for speech.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_CURRENT_USER, KEY_READ,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SPI_GETSCREENREADER,
+    SYSTEM_PARAMETERS_INFO_ACTION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+use crate::config::{AppearanceConfig, SystemPreference};
+
+static SYSTEM_REDUCED_MOTION: AtomicBool = AtomicBool::new(false);
+static SYSTEM_REDUCED_TRANSPARENCY: AtomicBool = AtomicBool::new(false);
+static SCREEN_READER: AtomicBool = AtomicBool::new(false);
+
+/// Re-read the Windows settings; returns true if either one changed
+pub fn refresh() -> bool {
+    let motion = !client_area_animation_enabled();
+    let transparency = !transparency_enabled();
+    SCREEN_READER.store(system_flag(SPI_GETSCREENREADER, false), Ordering::Relaxed);
+    let prev_motion = SYSTEM_REDUCED_MOTION.swap(motion, Ordering::Relaxed);
+    let prev_transparency = SYSTEM_REDUCED_TRANSPARENCY.swap(transparency, Ordering::Relaxed);
+    prev_motion != motion || prev_transparency != transparency
+}
+
+/// Whether animations (pulses, flashing, scrolling text) should be replaced by static states
+pub fn reduce_motion(config: &AppearanceConfig) -> bool {
+    !config.animations_enabled
+        || config
+            .reduce_motion
+            .resolve(SYSTEM_REDUCED_MOTION.load(Ordering::Relaxed))
+}
+
+/// Whether a screen reader (Narrator, NVDA, JAWS...) has announced itself
+pub fn screen_reader_active() -> bool {
+    SCREEN_READER.load(Ordering::Relaxed)
+}
+
+/// Whether the bar should be opaque, without the acrylic backdrop
+pub fn reduce_transparency(config: &AppearanceConfig) -> bool {
+    config
+        .reduce_transparency
+        .resolve(SYSTEM_REDUCED_TRANSPARENCY.load(Ordering::Relaxed))
+}
+
+impl SystemPreference {
+    /// Apply the override to the system's value
+    fn resolve(&self, system: bool) -> bool {
+        match self {
+            Self::Auto => system,
+            Self::On => true,
+            Self::Off => false,
+        }
+    }
+}
+
+/// "Show animations in Windows" (Settings > Accessibility > Visual effects)
+fn client_area_animation_enabled() -> bool {
+    system_flag(SPI_GETCLIENTAREAANIMATION, true)
+}
+
+/// Read a BOOL system parameter, with a fallback if the call fails
+fn system_flag(action: SYSTEM_PARAMETERS_INFO_ACTION, default: bool) -> bool {
+    let mut value = BOOL::from(default);
+    unsafe {
+        let _ = SystemParametersInfoW(
+            action,
+            0,
+            Some(&mut value as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+    }
+    value.as_bool()
+}
+
+/// "Transparency effects" (Settings > Personalization > Colors); on when the value is missing
+fn transparency_enabled() -> bool {
+    unsafe {
+        let mut key = HKEY::default();
+        let subkey: Vec<u16> = "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\0"
+            .encode_utf16()
+            .collect();
+        if RegOpenKeyExW(HKEY_CURRENT_USER, PCWSTR::from_raw(subkey.as_ptr()), 0, KEY_READ, &mut key).is_err() {
+            return true;
+        }
+
+        let value_name: Vec<u16> = "EnableTransparency\0".encode_utf16().collect();
+        let mut data: u32 = 1;
+        let mut data_size = std::mem::size_of::<u32>() as u32;
+        let result = RegQueryValueExW(
+            key,
+            PCWSTR::from_raw(value_name.as_ptr()),
+            None,
+            None,
+            Some(&mut data as *mut u32 as *mut u8),
+            Some(&mut data_size),
+        );
+        let _ = RegCloseKey(key);
+
+        result.is_err() || data != 0
+    }
+}
@@ -0,0 +1,407 @@
+//! Named actions that can be triggered from config
+//!
+//! An action spec is a name with an optional argument after a colon, e.g.
+//! `refresh:weather`, `run:backup.cmd`, `screenshot` or `profile:work`. Handlers
+//! touch the renderer and window state, so actions must run on the UI thread.
+
+use anyhow::{anyhow, Result};
+use log::info;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+
+use crate::window::renderer::with_renderer;
+use crate::window::state::get_window_state;
+
+/// Runs an action; receives the argument after the colon (empty if none)
+pub type ActionHandler = fn(HWND, &str) -> Result<()>;
+
+struct RegisteredAction {
+    handler: ActionHandler,
+    description: &'static str,
+}
+
+/// Maps action names to handlers
+pub struct ActionRegistry {
+    actions: HashMap<&'static str, RegisteredAction>,
+}
+
+static REGISTRY: OnceCell<Mutex<ActionRegistry>> = OnceCell::new();
+
+/// Get the global action registry (built-in actions are registered on first use)
+pub fn registry() -> &'static Mutex<ActionRegistry> {
+    REGISTRY.get_or_init(|| Mutex::new(ActionRegistry::with_builtins()))
+}
+
+/// Run an action spec on the UI thread
+pub fn execute(hwnd: HWND, spec: &str) -> Result<()> {
+    let (name, arg) = split_spec(spec);
+    // Copy the handler out so it can use the registry itself
+    let handler = registry()
+        .lock()
+        .actions
+        .get(name)
+        .map(|a| a.handler)
+        .ok_or_else(|| anyhow!("Unknown action: {}", name))?;
+    info!("Running action {}", spec);
+    handler(hwnd, arg)
+}
+
+/// Run a user binding such as a module's `on_click`: an action spec, else a URL
+/// (anything starting with a scheme like `https:` or `ms-settings:`) to open,
+/// else a command line to run
+pub fn execute_binding(hwnd: HWND, spec: &str) -> Result<()> {
+    let spec = spec.trim();
+    if registry().lock().contains(spec) {
+        return execute(hwnd, spec);
+    }
+    info!("Running binding {}", spec);
+    if is_url(spec) {
+        open_target(hwnd, spec)
+    } else {
+        run_command(hwnd, spec)
+    }
+}
+
+/// Whether a spec starts with a URL scheme; a drive letter ("C:\...") doesn't count
+fn is_url(spec: &str) -> bool {
+    spec.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Split "name:argument" into its parts
+fn split_spec(spec: &str) -> (&str, &str) {
+    match spec.trim().split_once(':') {
+        Some((name, arg)) => (name.trim(), arg.trim()),
+        None => (spec.trim(), ""),
+    }
+}
+
+impl ActionRegistry {
+    fn with_builtins() -> Self {
+        let mut registry = Self {
+            actions: HashMap::new(),
+        };
+        registry.register("refresh", "Refresh a module now (refresh:<module id>)", refresh_module);
+        registry.register("run", "Run a command (run:<command line>)", run_command);
+        registry.register("open", "Open a URL, file or folder (open:<target>)", open_target);
+        registry.register("screenshot", "Save a full-screen screenshot", take_screenshot);
+        registry.register(
+            "share_setup",
+            "Save a picture of the bar and a copy of the config without secrets",
+            share_setup,
+        );
+        registry.register("profile", "Switch to a config profile (profile:<name>)", switch_profile);
+        registry.register("reload", "Reload the config file", reload_config);
+        registry.register("toggle_bar", "Show or hide the bar", toggle_bar);
+        registry.register("show_bar", "Show the bar", show_bar);
+        registry.register("hide_bar", "Hide the bar", hide_bar);
+        registry.register("open_menu", "Open the app menu", open_menu);
+        registry.register("quick_search", "Open quick search", quick_search);
+        registry.register("command_palette", "Open the command palette", command_palette);
+        registry.register("toggle_theme", "Switch between light and dark", toggle_theme);
+        registry.register("focus_bar", "Move the keyboard to the bar's modules", focus_bar);
+        registry.register("theme", "Switch to a theme mode (theme:light|dark|transparent|high_contrast|auto)", set_theme);
+        registry.register("toggle_module", "Show or hide a module (toggle_module:<module id>)", toggle_module);
+        registry.register(
+            "media",
+            "Press a media key (media:play_pause|next|previous|stop|volume_up|volume_down|mute)",
+            media_key,
+        );
+        registry.register("snap", "Snap the focused window (snap:left_half, snap:maximize, ...)", snap_window);
+        registry.register(
+            "translate",
+            "Translate the selected text, or the clipboard (translate:<language> for another language)",
+            translate_selection,
+        );
+        registry.register("osd", "Show a message next to the bar (osd:<text>)", show_osd);
+        registry.register("define", "Look up the selected word in the dictionary (define:<word>)", define_word);
+        registry.register("tile", "Tile the windows on a monitor (tile:grid|columns|main_stack)", tile_windows);
+        registry.register("briefing", "Show the morning briefing", show_briefing);
+        registry.register("show_desktop", "Show the desktop, or bring the windows back", show_desktop);
+        registry.register("control_center", "Open Windows quick settings", control_center);
+        registry.register("toggle_auto_hide", "Turn auto-hiding the bar on or off", toggle_auto_hide);
+        registry.register("toggle_offline", "Turn offline mode on or off", toggle_offline);
+        registry.register(
+            "power",
+            "Lock, sleep or restart the PC (power:lock|sleep|hibernate|restart|shutdown|sign_out)",
+            power_action,
+        );
+        registry.register(
+            "alarm",
+            "Set a one-off alarm (alarm:07:30 or alarm:+10 for ten minutes, optionally followed by a label)",
+            set_alarm,
+        );
+        registry
+    }
+
+    /// Register (or replace) an action
+    pub fn register(&mut self, name: &'static str, description: &'static str, handler: ActionHandler) {
+        self.actions.insert(name, RegisteredAction { handler, description });
+    }
+
+    /// Whether a spec names a registered action
+    pub fn contains(&self, spec: &str) -> bool {
+        self.actions.contains_key(split_spec(spec).0)
+    }
+
+    /// All action names with their descriptions, sorted by name
+    pub fn list(&self) -> Vec<(&'static str, &'static str)> {
+        let mut list: Vec<_> = self
+            .actions
+            .iter()
+            .map(|(name, a)| (*name, a.description))
+            .collect();
+        list.sort();
+        list
+    }
+}
+
+fn request_redraw(hwnd: HWND) {
+    if let Some(state) = get_window_state() {
+        state.write().needs_redraw = true;
+    }
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+}
+
+fn refresh_module(hwnd: HWND, module_id: &str) -> Result<()> {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let found = with_renderer(|renderer| {
+        renderer
+            .module_registry
+            .get_mut(module_id)
+            .map(|m| m.force_refresh(&config))
+            .is_some()
+    })
+    .unwrap_or(false);
+    if !found {
+        return Err(anyhow!("Unknown module: {}", module_id));
+    }
+    request_redraw(hwnd);
+    Ok(())
+}
+
+fn run_command(_hwnd: HWND, command: &str) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    if command.is_empty() {
+        return Err(anyhow!("No command given"));
+    }
+    std::process::Command::new("cmd")
+        .args(["/c", command])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .spawn()?;
+    Ok(())
+}
+
+fn open_target(_hwnd: HWND, target: &str) -> Result<()> {
+    if target.is_empty() {
+        return Err(anyhow!("Nothing to open"));
+    }
+    crate::utils::open_url(target);
+    Ok(())
+}
+
+fn take_screenshot(_hwnd: HWND, _arg: &str) -> Result<()> {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    crate::modules::capture::capture_full_screen(&config.modules.capture)?;
+    Ok(())
+}
+
+fn share_setup(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::config_handlers::share_setup(hwnd)?;
+    Ok(())
+}
+
+fn switch_profile(hwnd: HWND, name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(anyhow!("No profile name given"));
+    }
+    // Profiles are laid over config.toml when it's loaded, so only the name is
+    // saved and "default" goes back to config.toml alone
+    let profile = (name != "default").then_some(name);
+    crate::config::Config::set_active_profile(profile)?;
+    crate::window::config_handlers::reload_config(hwnd);
+    info!("Switched to profile {}", name);
+    Ok(())
+}
+
+fn reload_config(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::config_handlers::reload_config(hwnd);
+    Ok(())
+}
+
+fn toggle_bar(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::WindowManager::toggle_bar(hwnd);
+    Ok(())
+}
+
+fn show_bar(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::WindowManager::set_bar_visible(hwnd, true);
+    Ok(())
+}
+
+fn hide_bar(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::WindowManager::set_bar_visible(hwnd, false);
+    Ok(())
+}
+
+fn open_menu(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::module_handlers::show_module_menu(hwnd, "app_menu", 12, 28);
+    Ok(())
+}
+
+fn quick_search(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::render::show_quick_search(hwnd)
+}
+
+fn command_palette(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::render::show_command_palette(hwnd)
+}
+
+fn focus_bar(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::focus::focus_bar(hwnd);
+    Ok(())
+}
+
+fn toggle_theme(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::config_handlers::toggle_theme(hwnd);
+    Ok(())
+}
+
+fn set_theme(hwnd: HWND, mode: &str) -> Result<()> {
+    use crate::theme::ThemeMode;
+
+    let mode = match mode.trim().to_lowercase().as_str() {
+        "light" => ThemeMode::Light,
+        "dark" => ThemeMode::Dark,
+        "transparent" => ThemeMode::Transparent,
+        "high_contrast" | "highcontrast" => ThemeMode::HighContrast,
+        "auto" => ThemeMode::Auto,
+        other => return Err(anyhow!("Unknown theme mode: {}", other)),
+    };
+    crate::window::config_handlers::set_theme_mode(hwnd, mode);
+    Ok(())
+}
+
+fn toggle_module(hwnd: HWND, module_id: &str) -> Result<()> {
+    let known = with_renderer(|renderer| renderer.module_registry.get(module_id).is_some()).unwrap_or(false);
+    if !known {
+        return Err(anyhow!("Unknown module: {}", module_id));
+    }
+    crate::window::config_handlers::toggle_module(hwnd, module_id);
+    Ok(())
+}
+
+fn media_key(_hwnd: HWND, key: &str) -> Result<()> {
+    use crate::modules::media::{send_media_key, MediaKey};
+
+    let key = MediaKey::from_name(key).ok_or_else(|| anyhow!("Unknown media key: {}", key))?;
+    send_media_key(key);
+    Ok(())
+}
+
+fn snap_window(_hwnd: HWND, layout: &str) -> Result<()> {
+    use crate::window::snap::SnapLayout;
+
+    let layout = SnapLayout::ALL
+        .into_iter()
+        .find(|l| l.key() == layout)
+        .ok_or_else(|| anyhow!("Unknown snap layout: {}", layout))?;
+    crate::window::snap::snap_foreground(layout);
+    Ok(())
+}
+
+fn translate_selection(_hwnd: HWND, target: &str) -> Result<()> {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    // The bar never takes focus, so this is the app the text is selected in
+    let origin = unsafe { GetForegroundWindow() };
+    let text = crate::utils::copy_selection().ok_or_else(|| anyhow!("No text selected or on the clipboard"))?;
+    crate::render::show_translation(text, origin, target)
+}
+
+fn define_word(_hwnd: HWND, word: &str) -> Result<()> {
+    let word = match word {
+        "" => crate::utils::copy_selection().ok_or_else(|| anyhow!("No word selected or on the clipboard"))?,
+        word => word.to_string(),
+    };
+    crate::render::show_definition(&word)
+}
+
+fn show_osd(_hwnd: HWND, text: &str) -> Result<()> {
+    if text.is_empty() {
+        return Err(anyhow!("osd needs a message (osd:<text>)"));
+    }
+    crate::render::show_osd(text, "");
+    Ok(())
+}
+
+fn tile_windows(_hwnd: HWND, layout: &str) -> Result<()> {
+    use crate::window::tile::TileLayout;
+
+    let layout = TileLayout::ALL
+        .into_iter()
+        .find(|l| l.key() == layout)
+        .ok_or_else(|| anyhow!("Unknown tile layout: {}", layout))?;
+    crate::window::tile::tile_windows(layout);
+    Ok(())
+}
+
+fn show_briefing(_hwnd: HWND, _arg: &str) -> Result<()> {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    crate::briefing::show(&config);
+    Ok(())
+}
+
+fn show_desktop(_hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::utils::send_win_key(b'D');
+    Ok(())
+}
+
+fn control_center(_hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::utils::send_win_key(b'A');
+    Ok(())
+}
+
+fn toggle_auto_hide(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::config_handlers::toggle_auto_hide(hwnd);
+    Ok(())
+}
+
+fn toggle_offline(hwnd: HWND, _arg: &str) -> Result<()> {
+    crate::window::config_handlers::toggle_config_bool(hwnd, |c| &mut c.privacy.offline_mode);
+    Ok(())
+}
+
+fn power_action(_hwnd: HWND, name: &str) -> Result<()> {
+    let action = crate::config::PowerAction::from_name(name).ok_or_else(|| anyhow!("Unknown power action: {}", name))?;
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    crate::modules::power::request(action, &config.modules.power);
+    Ok(())
+}
+
+fn set_alarm(hwnd: HWND, arg: &str) -> Result<()> {
+    let (time, label) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+    let at = crate::alarms::parse_time(time)
+        .ok_or_else(|| anyhow!("Expected a time like 07:30 or +10, got \"{}\"", time))?;
+    crate::alarms::add(crate::config::Alarm::new(label.trim(), &at, ""))?;
+    request_redraw(hwnd);
+    Ok(())
+}
@@ -0,0 +1,477 @@
+//! Alarm clock
+//!
+//! Rings the `[[scheduler.alarms]]` entries at their time of day, once or on a
+//! weekday pattern. A ringing alarm shows a banner on the active monitor (above
+//! full-screen apps, without taking focus, and whether or not the bar is visible)
+//! and repeats its sound until it's snoozed, dismissed or rings out. It also
+//! raises a notification (unless the alarm's `notify` is off), and the clock
+//! shows a badge while any alarm is armed. Alarms can be added from the clock's
+//! menu or with the `alarm` action as well as in the config.
+//!
+//! Like scheduled tasks, alarms are checked by the main window's one-second timer.
+//! The next alarm is also armed as a waitable timer that wakes the PC from sleep,
+//! so the tick gets to run on time. When each alarm last rang and any pending
+//! snooze are saved, so a restart neither loses a snooze nor rings an alarm twice.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::time::Instant;
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, WPARAM};
+
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::config::{Alarm, Config};
+use crate::sounds::SoundEvent;
+
+/// An alarm still rings if the tick came this late (e.g. just after resuming)
+const GRACE_SECONDS: i64 = 10 * 60;
+/// Furthest ahead a "+N" alarm time can be
+const MAX_AHEAD_MINUTES: i64 = 24 * 60;
+/// Time between repeats of the alarm sound
+const REPEAT_SOUND: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// What survives a restart (Unix timestamps by alarm key)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Saved {
+    last_rang: HashMap<String, i64>,
+    snoozed_until: HashMap<String, i64>,
+}
+
+struct Ringing {
+    key: String,
+    /// Rings once; turned off when dismissed or rung out, but not when snoozed
+    one_off: bool,
+    sound: bool,
+    snooze_min: u32,
+    ring_min: u32,
+    volume: f32,
+    since: Instant,
+    last_sound: Instant,
+}
+
+struct Alarms {
+    saved: Saved,
+    ringing: Option<Ringing>,
+    /// Time the wake timer is set for
+    armed: Option<DateTime<Local>>,
+}
+
+static ALARMS: OnceCell<Mutex<Alarms>> = OnceCell::new();
+/// Waitable timer handle used to wake from sleep
+static WAKE_TIMER: AtomicIsize = AtomicIsize::new(0);
+/// Main window, poked when the wake timer fires
+static MAIN_HWND: AtomicIsize = AtomicIsize::new(0);
+
+fn alarms() -> &'static Mutex<Alarms> {
+    ALARMS.get_or_init(|| {
+        let saved = std::fs::read_to_string(state_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Mutex::new(Alarms {
+            saved,
+            ringing: None,
+            armed: None,
+        })
+    })
+}
+
+fn state_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("alarms.json")
+}
+
+fn save(saved: &Saved) {
+    let result = serde_json::to_string(saved)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            let path = state_path();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, json)?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        log::warn!("Failed to save alarm state: {}", e);
+    }
+}
+
+/// Key identifying an alarm across config reloads
+fn alarm_key(alarm: &Alarm) -> String {
+    format!("{}\u{1f}{}", alarm.label, alarm.at)
+}
+
+/// Days the alarm repeats on (Monday first), or None for a one-off alarm
+fn repeat_days(repeat: &str) -> Option<[bool; 7]> {
+    let mut days = [false; 7];
+    for part in repeat.split(',').map(|p| p.trim().to_lowercase()) {
+        match part.as_str() {
+            "" => {}
+            "daily" | "everyday" | "every day" => days = [true; 7],
+            "weekdays" => days[..5].iter_mut().for_each(|d| *d = true),
+            "weekends" => days[5..].iter_mut().for_each(|d| *d = true),
+            day => {
+                if let Ok(weekday) = day.parse::<Weekday>() {
+                    days[weekday.num_days_from_monday() as usize] = true;
+                }
+            }
+        }
+    }
+    days.contains(&true).then_some(days)
+}
+
+/// When the alarm rings next, given when it last rang
+fn next_ring_after(alarm: &Alarm, last: Option<DateTime<Local>>, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let time = NaiveTime::parse_from_str(alarm.at.trim(), "%H:%M").ok()?;
+    let days = repeat_days(&alarm.repeat);
+    (0..=7)
+        .filter_map(|offset| {
+            let date = now.date_naive() + Duration::days(offset);
+            let allowed = days
+                .map(|d| d[date.weekday().num_days_from_monday() as usize])
+                .unwrap_or(true);
+            allowed
+                .then(|| date.and_time(time).and_local_timezone(Local).earliest())
+                .flatten()
+        })
+        .find(|at| last.map(|l| *at > l).unwrap_or(true) && (now - *at).num_seconds() <= GRACE_SECONDS)
+}
+
+fn timestamp(secs: i64) -> Option<DateTime<Local>> {
+    Local.timestamp_opt(secs, 0).single()
+}
+
+/// When the alarm rings next, including a pending snooze
+pub fn next_ring(alarm: &Alarm) -> Option<DateTime<Local>> {
+    let state = alarms().lock();
+    next_ring_locked(&state.saved, alarm, Local::now())
+}
+
+fn next_ring_locked(saved: &Saved, alarm: &Alarm, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let key = alarm_key(alarm);
+    if let Some(until) = saved.snoozed_until.get(&key).and_then(|t| timestamp(*t)) {
+        return Some(until);
+    }
+    let last = saved.last_rang.get(&key).and_then(|t| timestamp(*t));
+    next_ring_after(alarm, last, now)
+}
+
+/// Whether the alarm can ring: it's enabled, or it was snoozed and rings again
+fn is_armed(saved: &Saved, alarm: &Alarm) -> bool {
+    alarm.enabled || saved.snoozed_until.contains_key(&alarm_key(alarm))
+}
+
+/// The armed alarm that rings soonest, with its time
+pub fn next_armed(alarms_config: &[Alarm]) -> Option<(String, DateTime<Local>)> {
+    let state = alarms().lock();
+    let now = Local::now();
+    alarms_config
+        .iter()
+        .filter(|a| is_armed(&state.saved, a))
+        .filter_map(|a| Some((a.label.clone(), next_ring_locked(&state.saved, a, now)?)))
+        .min_by_key(|(_, at)| *at)
+}
+
+/// Parse an alarm time: "HH:MM", or "+N" for N minutes from now (up to a day,
+/// since only the time of day is kept)
+pub fn parse_time(spec: &str) -> Option<String> {
+    let spec = spec.trim();
+    if let Some(minutes) = spec.strip_prefix('+') {
+        let minutes: i64 = minutes.trim().parse().ok().filter(|m| (1..=MAX_AHEAD_MINUTES).contains(m))?;
+        let at = Local::now().checked_add_signed(Duration::minutes(minutes))?;
+        return Some(at.format("%H:%M").to_string());
+    }
+    NaiveTime::parse_from_str(spec, "%H:%M")
+        .ok()
+        .map(|t| t.format("%H:%M").to_string())
+}
+
+/// Add an alarm to the config and save it
+pub fn add(alarm: Alarm) -> anyhow::Result<()> {
+    let state = crate::window::state::get_window_state().ok_or_else(|| anyhow::anyhow!("The bar isn't ready"))?;
+    // A new alarm counts from now, so a time earlier today waits for tomorrow
+    // rather than ringing within the grace period
+    {
+        let mut alarms = alarms().lock();
+        let key = alarm_key(&alarm);
+        alarms.saved.last_rang.insert(key.clone(), Local::now().timestamp());
+        alarms.saved.snoozed_until.remove(&key);
+        save(&alarms.saved);
+    }
+    log::info!("Alarm '{}' set for {} ({})", alarm.label, alarm.at, if alarm.repeat.is_empty() { "once" } else { &alarm.repeat });
+    let mut new_config = (*state.read().config).clone();
+    new_config.scheduler.alarms.push(alarm);
+    new_config.save()?;
+    state.write().config = std::sync::Arc::new(new_config);
+    Ok(())
+}
+
+/// Whether an alarm is ringing now
+pub fn is_ringing() -> bool {
+    alarms().lock().ringing.is_some()
+}
+
+/// Ring due alarms, repeat the sound of a ringing one, and keep the wake timer armed
+pub fn tick(hwnd: HWND, config: &Config) {
+    MAIN_HWND.store(hwnd.0 as isize, Ordering::SeqCst);
+    let now = Local::now();
+    let alarms_config = &config.scheduler.alarms;
+
+    let mut state = alarms().lock();
+    let due = alarms_config
+        .iter()
+        .filter(|a| is_armed(&state.saved, a))
+        .find(|a| next_ring_locked(&state.saved, a, now).map(|t| t <= now).unwrap_or(false));
+
+    let mut banner = None;
+    // One-off alarms that stopped ringing for good, to turn off below
+    let mut finished = Vec::new();
+    if let Some(alarm) = due {
+        let key = alarm_key(alarm);
+        log::info!("Alarm '{}' ringing", alarm.label);
+        state.saved.last_rang.insert(key.clone(), now.timestamp());
+        state.saved.snoozed_until.remove(&key);
+        save(&state.saved);
+        // Another alarm ringing over this one ends it
+        if let Some(previous) = state.ringing.take().filter(|r| r.one_off) {
+            finished.push(previous.key);
+        }
+        state.ringing = Some(Ringing {
+            one_off: repeat_days(&alarm.repeat).is_none(),
+            key,
+            sound: alarm.sound,
+            snooze_min: alarm.snooze_min.max(1),
+            ring_min: alarm.ring_min.max(1),
+            volume: alarm.volume,
+            since: Instant::now(),
+            // Play right away below
+            last_sound: Instant::now().checked_sub(REPEAT_SOUND).unwrap_or_else(Instant::now),
+        });
+        banner = Some((alarm.label.clone(), now.format("%H:%M").to_string(), alarm.notify));
+    }
+
+    let mut rang_out = false;
+    if let Some(ringing) = &mut state.ringing {
+        if ringing.since.elapsed() >= std::time::Duration::from_secs(ringing.ring_min as u64 * 60) {
+            rang_out = true;
+        } else if ringing.last_sound.elapsed() >= REPEAT_SOUND {
+            ringing.last_sound = Instant::now();
+            if ringing.sound {
+                crate::sounds::play_at(&config.sounds, SoundEvent::Alarm, ringing.volume);
+            }
+            // Keep the display on (and the PC awake) while ringing
+            unsafe {
+                use windows::Win32::System::Power::*;
+                SetThreadExecutionState(ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED);
+            }
+        }
+    }
+    if rang_out {
+        if let Some(ringing) = state.ringing.take().filter(|r| r.one_off) {
+            finished.push(ringing.key);
+        }
+    }
+
+    let next_wake = alarms_config
+        .iter()
+        .filter(|a| a.wake && is_armed(&state.saved, a))
+        .filter_map(|a| next_ring_locked(&state.saved, a, now))
+        .filter(|t| *t > now)
+        .min();
+    if next_wake != state.armed {
+        state.armed = next_wake;
+        arm_wake_timer(next_wake);
+    }
+    drop(state);
+
+    if let Some((label, time, notify)) = banner {
+        crate::render::show_alarm_banner(&label, &time);
+        if notify {
+            crate::attention::request(
+                config,
+                AttentionRequest::new("clock", AttentionLevel::Warning, &label, &format!("Alarm for {}", time)),
+            );
+        }
+    }
+    if rang_out {
+        crate::render::hide_alarm_banner();
+    }
+    for key in finished {
+        disable_alarm(&key);
+    }
+}
+
+/// Stop ringing and ring again after the alarm's snooze time
+pub fn snooze() {
+    let mut state = alarms().lock();
+    if let Some(ringing) = state.ringing.take() {
+        let until = Local::now() + Duration::minutes(ringing.snooze_min as i64);
+        log::info!("Alarm snoozed until {}", until.format("%H:%M"));
+        state.saved.snoozed_until.insert(ringing.key, until.timestamp());
+        save(&state.saved);
+    }
+    drop(state);
+    crate::render::hide_alarm_banner();
+}
+
+/// Stop ringing
+pub fn dismiss() {
+    let ringing = alarms().lock().ringing.take();
+    crate::render::hide_alarm_banner();
+    if let Some(ringing) = ringing.filter(|r| r.one_off) {
+        disable_alarm(&ringing.key);
+    }
+}
+
+/// Turn a one-off alarm off in the config once it's done ringing
+fn disable_alarm(key: &str) {
+    let Some(state) = crate::window::state::get_window_state() else {
+        return;
+    };
+    let config = state.read().config.clone();
+    let mut new_config = (*config).clone();
+    let Some(alarm) = new_config.scheduler.alarms.iter_mut().find(|a| alarm_key(a) == key) else {
+        return;
+    };
+    alarm.enabled = false;
+    if let Err(e) = new_config.save() {
+        log::warn!("Failed to save config: {}", e);
+    }
+    state.write().config = std::sync::Arc::new(new_config);
+}
+
+/// Set the wake timer for `at`, or cancel it
+fn arm_wake_timer(at: Option<DateTime<Local>>) {
+    use windows::Win32::System::Threading::*;
+
+    unsafe {
+        let mut handle = HANDLE(WAKE_TIMER.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+        if handle.is_invalid() {
+            let Some(at) = at else {
+                return;
+            };
+            match CreateWaitableTimerExW(None, windows::core::PCWSTR::null(), 0, TIMER_ALL_ACCESS.0) {
+                Ok(h) => handle = h,
+                Err(e) => {
+                    log::warn!("Failed to create alarm wake timer: {}", e);
+                    return;
+                }
+            }
+            WAKE_TIMER.store(handle.0 as isize, Ordering::SeqCst);
+            std::thread::spawn(wait_for_wake);
+            log::debug!("Alarm wake timer created for {}", at);
+        }
+
+        let Some(at) = at else {
+            let _ = CancelWaitableTimer(handle);
+            return;
+        };
+        // Absolute due time in 100 ns units since 1601 (UTC)
+        let due = (at.timestamp() + 11_644_473_600) * 10_000_000;
+        if let Err(e) = SetWaitableTimer(handle, &due, 0, None, None, true) {
+            log::warn!("Failed to arm alarm wake timer: {}", e);
+        }
+    }
+}
+
+/// Run the tick as soon as the wake timer fires, instead of waiting for the next one
+fn wait_for_wake() {
+    use windows::Win32::System::Threading::{WaitForSingleObject, INFINITE};
+    use windows::Win32::UI::WindowsAndMessaging::{PostMessageW, WM_TIMER};
+
+    loop {
+        let handle = HANDLE(WAKE_TIMER.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+        unsafe {
+            if WaitForSingleObject(handle, INFINITE).0 != 0 {
+                return;
+            }
+            let hwnd = HWND(MAIN_HWND.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+            let _ = PostMessageW(hwnd, WM_TIMER, WPARAM(1), LPARAM(0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-12 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_repeat_days() {
+        assert_eq!(repeat_days("Daily"), Some([true; 7]));
+        assert_eq!(repeat_days("weekdays"), Some([true, true, true, true, true, false, false]));
+        assert_eq!(repeat_days("Weekends"), Some([false, false, false, false, false, true, true]));
+        assert_eq!(repeat_days("Mon, fri"), Some([true, false, false, false, true, false, false]));
+        assert_eq!(repeat_days(""), None);
+        assert_eq!(repeat_days("someday"), None);
+    }
+
+    #[test]
+    fn one_off_rings_at_the_next_time_of_day() {
+        let alarm = Alarm::new("Wake", "07:30", "");
+        assert_eq!(next_ring_after(&alarm, None, at(12, 6, 0)), Some(at(12, 7, 30)));
+        // Within the grace period it still rings today
+        assert_eq!(next_ring_after(&alarm, None, at(12, 7, 35)), Some(at(12, 7, 30)));
+        // Past it, it waits for tomorrow
+        assert_eq!(next_ring_after(&alarm, None, at(12, 9, 0)), Some(at(13, 7, 30)));
+        // Having rung, it doesn't ring again today
+        assert_eq!(next_ring_after(&alarm, Some(at(12, 7, 30)), at(12, 7, 31)), Some(at(13, 7, 30)));
+    }
+
+    #[test]
+    fn repeating_alarm_skips_other_days() {
+        let weekdays = Alarm::new("Work", "07:30", "Weekdays");
+        // Friday after it rang, next is Monday
+        assert_eq!(next_ring_after(&weekdays, Some(at(16, 7, 30)), at(16, 8, 0)), Some(at(19, 7, 30)));
+        let some_days = Alarm::new("Gym", "18:00", "Mon,Fri");
+        assert_eq!(next_ring_after(&some_days, None, at(13, 12, 0)), Some(at(16, 18, 0)));
+    }
+
+    #[test]
+    fn rejects_bad_time() {
+        let alarm = Alarm::new("Wake", "7.30am", "");
+        assert_eq!(next_ring_after(&alarm, None, at(12, 6, 0)), None);
+    }
+
+    #[test]
+    fn parses_alarm_times() {
+        assert_eq!(parse_time(" 07:30 ").as_deref(), Some("07:30"));
+        assert_eq!(parse_time("25:00"), None);
+        assert!(parse_time("+5").is_some());
+        assert!(parse_time("+1440").is_some());
+        assert_eq!(parse_time("+0"), None);
+        assert_eq!(parse_time("+1441"), None);
+        assert_eq!(parse_time("+99999999999999"), None);
+        assert_eq!(parse_time("+-5"), None);
+    }
+
+    #[test]
+    fn snooze_comes_before_the_schedule() {
+        let alarm = Alarm::new("Wake", "07:30", "");
+        let key = alarm_key(&alarm);
+        let mut saved = Saved::default();
+        saved.last_rang.insert(key.clone(), at(12, 7, 30).timestamp());
+        assert_eq!(next_ring_locked(&saved, &alarm, at(12, 7, 31)), Some(at(13, 7, 30)));
+
+        saved.snoozed_until.insert(key, at(12, 7, 39).timestamp());
+        assert_eq!(next_ring_locked(&saved, &alarm, at(12, 7, 31)), Some(at(12, 7, 39)));
+    }
+
+    #[test]
+    fn snoozed_alarm_stays_armed_when_disabled() {
+        let mut alarm = Alarm::new("Wake", "07:30", "");
+        alarm.enabled = false;
+        let mut saved = Saved::default();
+        assert!(!is_armed(&saved, &alarm));
+        saved.snoozed_until.insert(alarm_key(&alarm), at(12, 7, 39).timestamp());
+        assert!(is_armed(&saved, &alarm));
+    }
+}
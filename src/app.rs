@@ -9,6 +9,7 @@ use parking_lot::RwLock;
 
 use crate::config::Config;
 use crate::quicklook;
+use crate::runtime::Runtime;
 use crate::tray::TrayIcon;
 use crate::utils::enable_dark_mode_for_app;
 use crate::window::WindowManager;
@@ -21,6 +22,8 @@ pub struct Application {
     config: Arc<Config>,
     window_manager: WindowManager,
     tray_icon: Option<TrayIcon>,
+    /// Shared runtime for network fetches; jobs get their own thread without it
+    runtime: Option<Runtime>,
     is_running: bool,
     /// Optional search index built in background
     search_index: Arc<RwLock<Option<SearchIndex>>>,
@@ -34,8 +37,20 @@ impl Application {
         // Enable dark mode for Windows context menus
         enable_dark_mode_for_app(true);
 
+        // Start the background runtime first: modules fetch as they're created
+        let runtime = match Runtime::start() {
+            Ok(runtime) => Some(runtime),
+            Err(e) => {
+                warn!("Failed to start background runtime: {}", e);
+                None
+            }
+        };
+
         // Create the main window
         let window_manager = WindowManager::new(config.clone())?;
+        if let Some(runtime) = &runtime {
+            runtime.notify(window_manager.hwnd());
+        }
 
         // Create tray icon (optional, might fail)
         let tray_icon = match TrayIcon::new(window_manager.hwnd()) {
@@ -49,32 +64,24 @@ impl Application {
             }
         };
 
+        // Check start with Windows against the config; only the menu elevates
+        crate::startup::apply(&config.general);
+
+        // Take commands from scripts and topbarctl
+        if config.ipc.enabled {
+            crate::ipc::start(window_manager.hwnd());
+        }
+
+        // Read the login items ahead of the first time the app menu opens
+        crate::login_items::refresh();
+
         // Start search index builder in background
         let search_index: Arc<RwLock<Option<SearchIndex>>> = Arc::new(RwLock::new(None));
         crate::search::set_global_index(search_index.clone());
 
         {
-            let si_clone = search_index.clone();
-            let roots: Vec<std::path::PathBuf> = config.search.index_paths.clone();
-            let config_clone = config.clone();
-
-            std::thread::spawn(move || {
-                log::info!("Starting background search indexing...");
-                log::info!("Indexing {} root directories:", roots.len());
-                for root in &roots {
-                    log::info!("  - {}", root.display());
-                }
-                match SearchIndex::build_with_excludes(&roots, &config_clone.search.exclude_patterns) {
-                    Ok(idx) => {
-                        let len = idx.count();
-                        *si_clone.write() = Some(idx);
-                        log::info!("Search index built with {} entries", len);
-                    }
-                    Err(e) => {
-                        log::error!("Failed to build search index: {}", e);
-                    }
-                }
-            });
+            let search_config = config.search.clone();
+            std::thread::spawn(move || crate::search::start(&search_config));
         }
 
         // Start QuickLook hook if enabled
@@ -89,6 +96,7 @@ impl Application {
         Ok(Self {
             config,
             window_manager,
+            runtime,
             tray_icon,
             is_running: false,
             search_index,
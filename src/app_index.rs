@@ -0,0 +1,202 @@
+//! Installed application list for quick search
+//!
+//! Collects Start Menu shortcuts (all users and the current user) and the apps in
+//! installed UWP/MSIX packages. Packaged apps have no file to open; they're
+//! launched by their AppUserModelID through `shell:AppsFolder`.
+//!
+//! The list is small (a few hundred entries), so it's rebuilt from scratch in the
+//! background rather than watched.
+
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// Rebuild the list when quick search opens and it's older than this
+const REFRESH_AFTER: Duration = Duration::from_secs(10 * 60);
+
+static APPS: RwLock<Vec<App>> = RwLock::new(Vec::new());
+static REFRESHED: RwLock<Option<Instant>> = RwLock::new(None);
+static REFRESHING: AtomicBool = AtomicBool::new(false);
+
+/// Where an app comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppKind {
+    /// A Start Menu `.lnk`; the target is its path
+    Shortcut,
+    /// An app in a UWP/MSIX package; the target is its AppUserModelID
+    Packaged,
+}
+
+/// An installed application
+#[derive(Debug, Clone, PartialEq)]
+pub struct App {
+    pub name: String,
+    pub target: String,
+    pub kind: AppKind,
+}
+
+impl App {
+    /// What to pass to `ShellExecuteW` to start the app
+    pub fn launch_path(&self) -> String {
+        match self.kind {
+            AppKind::Shortcut => self.target.clone(),
+            AppKind::Packaged => format!("shell:AppsFolder\\{}", self.target),
+        }
+    }
+}
+
+/// Rebuild the app list (blocks; call from a background thread)
+pub fn refresh() {
+    if REFRESHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut apps = start_menu_apps();
+    let mut names: HashSet<String> = apps.iter().map(|a| a.name.to_lowercase()).collect();
+    match packaged_apps() {
+        Ok(packaged) => {
+            // Desktop apps installed from MSIX often have a Start Menu shortcut too
+            for app in packaged {
+                if names.insert(app.name.to_lowercase()) {
+                    apps.push(app);
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to list packaged apps: {}", e),
+    }
+    apps.sort_by_key(|a| a.name.to_lowercase());
+    log::info!("App index built with {} apps", apps.len());
+
+    *APPS.write() = apps;
+    *REFRESHED.write() = Some(Instant::now());
+    REFRESHING.store(false, Ordering::SeqCst);
+}
+
+/// Rebuild the list in the background if it's missing or old
+pub fn refresh_if_stale() {
+    let stale = REFRESHED.read().map(|t| t.elapsed() >= REFRESH_AFTER).unwrap_or(true);
+    if stale && !REFRESHING.load(Ordering::SeqCst) {
+        std::thread::spawn(refresh);
+    }
+}
+
+/// Apps matching `query`, best first
+pub fn search(query: &str, limit: usize) -> Vec<App> {
+    let q: String = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if q.is_empty() {
+        return Vec::new();
+    }
+    let apps = APPS.read();
+    let mut scored: Vec<(f32, &App)> = apps
+        .iter()
+        .filter_map(|app| {
+            let mut score = crate::search::fuzzy_score(&app.name, &q)? as f32;
+            if app.name.to_lowercase() == q {
+                score += 50.0;
+            }
+            score += crate::search::history_boost(&app.launch_path());
+            Some((score, app))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    scored.into_iter().take(limit).map(|(_, app)| app.clone()).collect()
+}
+
+/// Display name of the first app in a package, by package family name
+pub fn package_display_name(family: &str) -> Option<String> {
+    let prefix = format!("{}!", family);
+    APPS.read()
+        .iter()
+        .find(|app| app.kind == AppKind::Packaged && app.target.starts_with(&prefix))
+        .map(|app| app.name.clone())
+}
+
+/// Whether `path` is a shortcut already listed as an app
+pub fn contains_shortcut(path: &str) -> bool {
+    APPS.read()
+        .iter()
+        .any(|app| app.kind == AppKind::Shortcut && app.target.eq_ignore_ascii_case(path))
+}
+
+/// Start Menu folders for all users and the current user
+fn start_menu_dirs() -> Vec<PathBuf> {
+    let mut folders = Vec::new();
+    if let Ok(data) = std::env::var("ProgramData") {
+        folders.push(PathBuf::from(data).join("Microsoft\\Windows\\Start Menu\\Programs"));
+    }
+    if let Some(roaming) = dirs::config_dir() {
+        folders.push(roaming.join("Microsoft\\Windows\\Start Menu\\Programs"));
+    }
+    folders
+}
+
+fn start_menu_apps() -> Vec<App> {
+    let mut apps = Vec::new();
+    for dir in start_menu_dirs() {
+        for entry in WalkDir::new(&dir).max_depth(4).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let is_link = path
+                .extension()
+                .map(|e| e.eq_ignore_ascii_case("lnk"))
+                .unwrap_or(false);
+            if !entry.file_type().is_file() || !is_link {
+                continue;
+            }
+            let Some(name) = path.file_stem().map(|n| n.to_string_lossy().to_string()) else {
+                continue;
+            };
+            // Uninstallers and help links clutter results without being apps
+            let lower = name.to_lowercase();
+            if lower.starts_with("uninstall") || lower.contains("readme") {
+                continue;
+            }
+            apps.push(App {
+                name,
+                target: path.to_string_lossy().to_string(),
+                kind: AppKind::Shortcut,
+            });
+        }
+    }
+    apps
+}
+
+/// Apps in the current user's UWP/MSIX packages, from `PackageManager`
+fn packaged_apps() -> windows::core::Result<Vec<App>> {
+    use windows::core::HSTRING;
+    use windows::Management::Deployment::{PackageManager, PackageTypes};
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    }
+    let manager = PackageManager::new()?;
+    // An empty SID means the current user
+    let packages = manager.FindPackagesByUserSecurityIdWithPackageTypes(&HSTRING::new(), PackageTypes::Main)?;
+
+    let mut apps = Vec::new();
+    for package in packages {
+        if package.IsFramework().unwrap_or(false) {
+            continue;
+        }
+        let Ok(entries) = package.GetAppListEntriesAsync().and_then(|op| op.get()) else {
+            continue;
+        };
+        for entry in entries {
+            let (Ok(aumid), Ok(info)) = (entry.AppUserModelId(), entry.DisplayInfo()) else {
+                continue;
+            };
+            let name = info.DisplayName().map(|n| n.to_string()).unwrap_or_default();
+            if name.is_empty() || name.starts_with("ms-resource:") || aumid.is_empty() {
+                continue;
+            }
+            apps.push(App {
+                name,
+                target: aumid.to_string(),
+                kind: AppKind::Packaged,
+            });
+        }
+    }
+    Ok(apps)
+}
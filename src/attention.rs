@@ -0,0 +1,199 @@
+//! Attention requests from modules
+//!
+//! Modules call [`request`] for events the user should notice right away (a watched
+//! process exited, a service stopped, a temperature crossed its threshold). Depending
+//! on `[attention]`, a request shows a tray notification, flashes the tray icon,
+//! pulses the bar's bottom edge and makes the module glow until it expires or is
+//! clicked.
+//!
+//! The interruptive effects (tray and edge pulse) are rate limited globally and per
+//! module, and suppressed in Do Not Disturb. The glow is always kept so the event is
+//! still visible afterwards.
+
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::config::{AttentionConfig, Config};
+use crate::theme::{Color, Theme};
+
+/// How urgent an attention request is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttentionLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl AttentionLevel {
+    /// Theme color used for the glow and edge pulse
+    pub fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Self::Info => theme.info,
+            Self::Warning => theme.warning,
+            Self::Error => theme.error,
+        }
+    }
+}
+
+/// A module's request for attention
+#[derive(Debug, Clone)]
+pub struct AttentionRequest {
+    pub module_id: String,
+    pub level: AttentionLevel,
+    pub title: String,
+    pub text: String,
+}
+
+impl AttentionRequest {
+    pub fn new(module_id: &str, level: AttentionLevel, title: &str, text: &str) -> Self {
+        Self {
+            module_id: module_id.to_string(),
+            level,
+            title: title.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// A request that is still being shown
+#[derive(Debug, Clone)]
+pub struct ActiveAttention {
+    pub module_id: String,
+    pub level: AttentionLevel,
+    pub since: Instant,
+    until: Instant,
+    /// Whether the edge pulse and tray flash run for this request
+    interrupt: bool,
+}
+
+#[derive(Default)]
+struct AttentionState {
+    active: Vec<ActiveAttention>,
+    /// Times of recent interruptive requests, for the global rate limit
+    recent: VecDeque<Instant>,
+    last_by_module: HashMap<String, Instant>,
+    tray_flashing: bool,
+}
+
+static ATTENTION: OnceCell<Mutex<AttentionState>> = OnceCell::new();
+
+fn state() -> &'static Mutex<AttentionState> {
+    ATTENTION.get_or_init(|| Mutex::new(AttentionState::default()))
+}
+
+/// Whether Do Not Disturb is on (manually, or because Windows reports the user as busy)
+pub fn is_do_not_disturb(config: &AttentionConfig) -> bool {
+    config.do_not_disturb || (config.respect_focus_assist && windows_is_busy())
+}
+
+/// Full-screen apps, presentation mode and quiet hours
+fn windows_is_busy() -> bool {
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS};
+    unsafe {
+        SHQueryUserNotificationState()
+            .map(|s| s != QUNS_ACCEPTS_NOTIFICATIONS)
+            .unwrap_or(false)
+    }
+}
+
+/// Request attention for a module event.
+///
+/// Returns true if the request interrupted the user (tray notification or pulse),
+/// false if it was only recorded as a glow or ignored.
+pub fn request(config: &Config, req: AttentionRequest) -> bool {
+    let cfg = &config.attention;
+    if !cfg.enabled {
+        return false;
+    }
+
+    let now = Instant::now();
+    let duration = Duration::from_secs(cfg.duration_secs.max(1));
+    let interrupt = {
+        let mut state = state().lock();
+        let window = Duration::from_secs(60);
+        while state.recent.front().is_some_and(|t| now.duration_since(*t) > window) {
+            state.recent.pop_front();
+        }
+        let cooling_down = state
+            .last_by_module
+            .get(&req.module_id)
+            .is_some_and(|t| now.duration_since(*t) < Duration::from_secs(cfg.module_cooldown_secs));
+        let rate_limited = state.recent.len() >= cfg.max_per_minute as usize;
+        let dnd = is_do_not_disturb(cfg);
+        let interrupt = !cooling_down && !rate_limited && !dnd;
+
+        if interrupt {
+            state.recent.push_back(now);
+            state.last_by_module.insert(req.module_id.clone(), now);
+        } else {
+            info!(
+                "Attention from {} not shown (cooldown: {}, rate limited: {}, do not disturb: {})",
+                req.module_id, cooling_down, rate_limited, dnd
+            );
+        }
+
+        // One entry per module; a new request replaces the old one
+        state.active.retain(|a| a.module_id != req.module_id);
+        state.active.push(ActiveAttention {
+            module_id: req.module_id.clone(),
+            level: req.level,
+            since: now,
+            until: now + duration,
+            interrupt,
+        });
+        interrupt
+    };
+
+    if interrupt && cfg.tray_notification {
+        if let Some(hwnd) = crate::window::get_main_hwnd() {
+            if let Err(e) = crate::tray::show_balloon(hwnd, &req.title, &req.text, req.level) {
+                warn!("Failed to show attention notification: {}", e);
+            }
+        }
+    }
+    interrupt
+}
+
+/// Clear a module's attention (e.g. when the user clicks it)
+pub fn dismiss(module_id: &str) {
+    state().lock().active.retain(|a| a.module_id != module_id);
+}
+
+/// Requests that haven't expired, most urgent first
+pub fn active() -> Vec<ActiveAttention> {
+    let now = Instant::now();
+    let mut state = state().lock();
+    state.active.retain(|a| a.until > now);
+    let mut active = state.active.clone();
+    active.sort_by_key(|a| std::cmp::Reverse(a.level));
+    active
+}
+
+/// The most urgent request that should still pulse the bar edge
+pub fn pulsing() -> Option<ActiveAttention> {
+    active().into_iter().find(|a| a.interrupt)
+}
+
+/// Pulse intensity (0.0-1.0) for animating attention effects, about one cycle per second
+pub fn pulse_phase(since: Instant) -> f32 {
+    let t = since.elapsed().as_secs_f32();
+    0.5 - 0.5 * (t * std::f32::consts::TAU).cos()
+}
+
+/// Flash the tray icon while an interruptive request is active (called once a second)
+pub fn tick(hwnd: windows::Win32::Foundation::HWND, config: &Config) {
+    let flashing = config.attention.tray_flash && pulsing().is_some();
+    let mut state = state().lock();
+    if flashing {
+        // Alternate between the warning and normal icon on each tick; with reduced
+        // motion the warning icon just stays up
+        state.tray_flashing = !state.tray_flashing || crate::accessibility::reduce_motion(&config.appearance);
+        let _ = crate::tray::set_attention_icon(hwnd, state.tray_flashing);
+    } else if state.tray_flashing {
+        state.tray_flashing = false;
+        let _ = crate::tray::set_attention_icon(hwnd, false);
+    }
+}
@@ -0,0 +1,95 @@
+//! Auto-hide
+//!
+//! With `[behavior] auto_hide` on, the bar shrinks to a thin strip at the screen
+//! edge once the pointer has been away from it for `auto_hide_delay_ms`, and
+//! comes back as soon as the pointer touches the strip. Unlike the screen-share
+//! strip it still takes the pointer. The bar doesn't reserve screen space while
+//! auto-hide is on, so maximized windows get the full height and the bar opens
+//! over them.
+
+use log::debug;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{HWND, POINT, RECT};
+use windows::Win32::Graphics::Gdi::{InvalidateRect, PtInRect};
+use windows::Win32::UI::WindowsAndMessaging::{GetCursorPos, GetWindowRect};
+
+use crate::config::Config;
+use crate::window::state::get_window_state;
+
+/// Whether the bar is currently shrunk
+static COLLAPSED: AtomicBool = AtomicBool::new(false);
+/// Since when the pointer has been away from the expanded bar
+static AWAY_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Whether the bar is shrunk by auto-hide
+pub fn is_collapsed() -> bool {
+    COLLAPSED.load(Ordering::Relaxed)
+}
+
+/// Shrink or restore the bar from where the pointer is (called every 100ms)
+pub fn tick(hwnd: HWND, config: &Config) {
+    let behavior = &config.behavior;
+    let collapsed = is_collapsed();
+    let mut away = AWAY_SINCE.lock();
+
+    // Turning auto-hide off restores the bar right away
+    if !behavior.auto_hide {
+        *away = None;
+        drop(away);
+        if collapsed {
+            set_collapsed(hwnd, false);
+        }
+        return;
+    }
+
+    if pointer_over(hwnd) {
+        *away = None;
+        drop(away);
+        if collapsed {
+            set_collapsed(hwnd, false);
+        }
+        return;
+    }
+    // Stay open while a module is pressed or its menu is up
+    if collapsed || is_busy() {
+        *away = None;
+        return;
+    }
+    let since = *away.get_or_insert_with(Instant::now);
+    if since.elapsed() < Duration::from_millis(behavior.auto_hide_delay_ms as u64) {
+        return;
+    }
+    *away = None;
+    drop(away);
+    set_collapsed(hwnd, true);
+}
+
+fn pointer_over(hwnd: HWND) -> bool {
+    unsafe {
+        let mut point = POINT::default();
+        let mut rect = RECT::default();
+        GetCursorPos(&mut point).is_ok()
+            && GetWindowRect(hwnd, &mut rect).is_ok()
+            && PtInRect(&rect, point).as_bool()
+    }
+}
+
+fn is_busy() -> bool {
+    get_window_state()
+        .map(|s| {
+            let s = s.read();
+            s.clicked_module.is_some() || s.active_menu.is_some() || s.gesture_start.is_some()
+        })
+        .unwrap_or(false)
+}
+
+fn set_collapsed(hwnd: HWND, collapsed: bool) {
+    debug!("Auto-hide: {}", if collapsed { "shrinking the bar" } else { "restoring the bar" });
+    COLLAPSED.store(collapsed, Ordering::Relaxed);
+    crate::window::manager::WindowManager::relayout(hwnd);
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, true);
+    }
+}
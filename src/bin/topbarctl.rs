@@ -0,0 +1,120 @@
+//! topbarctl - send commands to a running TopBar
+//!
+//! Connects to the bar's command pipe (see `ipc` in the main binary), sends the
+//! arguments as one command and prints the answer:
+//!
+//! ```text
+//! topbarctl toggle_module:clock
+//! topbarctl show_bar
+//! topbarctl theme:dark
+//! topbarctl quick_search
+//! topbarctl get battery
+//! topbarctl watch 2 system_info network media
+//! topbarctl actions
+//! ```
+//!
+//! `watch` keeps printing one JSON object per line until interrupted, for
+//! piping into overlays and dashboards.
+//!
+//! Results are printed as JSON; errors go to stderr with exit code 1, and exit
+//! code 2 means the bar isn't running (or its command pipe is turned off).
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+/// How long to keep retrying while the bar is busy with another client
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// ERROR_PIPE_BUSY
+const PIPE_BUSY: i32 = 231;
+
+const USAGE: &str = "Usage: topbarctl <command>
+
+Commands:
+  get [module id...]
+                    Print the current value of one or more modules, or of all of them
+  watch [seconds] [module id...]
+                    Print module values as a line of JSON every few seconds
+  actions           List the actions that can be run
+  <action>          Run an action, e.g. toggle_module:clock, show_bar, hide_bar,
+                    toggle_bar, theme:dark, quick_search, refresh:weather";
+
+fn pipe_name() -> String {
+    format!("\\\\.\\pipe\\topbar-{}", std::env::var("USERNAME").unwrap_or_default())
+}
+
+fn main() -> ExitCode {
+    let command = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    if command.trim().is_empty() || command == "--help" || command == "-h" {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    // The bar serves one client at a time
+    let started = Instant::now();
+    let mut pipe = loop {
+        match std::fs::OpenOptions::new().read(true).write(true).open(pipe_name()) {
+            Ok(pipe) => break pipe,
+            Err(e) if e.raw_os_error() == Some(PIPE_BUSY) && started.elapsed() < BUSY_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => {
+                eprintln!("topbarctl: can't reach TopBar ({}). Is it running?", e);
+                return ExitCode::from(2);
+            }
+        }
+    };
+
+    if let Err(e) = writeln!(pipe, "{}", command.trim()) {
+        eprintln!("topbarctl: failed to send the command: {}", e);
+        return ExitCode::FAILURE;
+    }
+    let mut reader = BufReader::new(pipe);
+    if command.split_whitespace().next() == Some("watch") {
+        return watch(reader);
+    }
+    let mut line = String::new();
+    if let Err(e) = reader.read_line(&mut line) {
+        eprintln!("topbarctl: failed to read the answer: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let response: serde_json::Value = match serde_json::from_str(&line) {
+        Ok(response) => response,
+        Err(_) => {
+            eprintln!("topbarctl: unexpected answer: {}", line.trim());
+            return ExitCode::FAILURE;
+        }
+    };
+    if response["ok"].as_bool() != Some(true) {
+        eprintln!("topbarctl: {}", response["error"].as_str().unwrap_or("command failed"));
+        return ExitCode::FAILURE;
+    }
+    match &response["result"] {
+        serde_json::Value::Null => {}
+        result => println!("{}", serde_json::to_string_pretty(result).unwrap_or_default()),
+    }
+    ExitCode::SUCCESS
+}
+
+/// Pass streamed lines through until the bar hangs up (or reports an error)
+fn watch(reader: BufReader<std::fs::File>) -> ExitCode {
+    let stdout = std::io::stdout();
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if let Ok(response) = serde_json::from_str::<serde_json::Value>(&line) {
+            if response["ok"].as_bool() == Some(false) {
+                eprintln!("topbarctl: {}", response["error"].as_str().unwrap_or("command failed"));
+                return ExitCode::FAILURE;
+            }
+        }
+        let mut out = stdout.lock();
+        // Stop quietly when whatever reads the output goes away
+        if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+    ExitCode::SUCCESS
+}
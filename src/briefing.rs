@@ -0,0 +1,185 @@
+//! Morning briefing
+//!
+//! Once a day at `[briefing] at`, a banner sums up the day ahead: the weather,
+//! today's alarms and scheduled tasks, and the current value of any modules
+//! listed in `modules`, optionally read aloud. It's put together from the
+//! modules' own data, so it shows what the bar already knows and fetches
+//! nothing itself. A briefing missed while the PC was off or asleep still shows
+//! when the bar next runs, until `until`.
+//!
+//! The main window's one-second timer calls [`tick`]; the day of the last
+//! briefing is saved, so restarting the bar doesn't show it twice.
+
+use chrono::{DateTime, Local, NaiveTime, Timelike};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::window::renderer::with_renderer;
+
+/// What survives a restart
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Saved {
+    /// Day of the last scheduled briefing ("YYYY-MM-DD")
+    last_shown: Option<String>,
+}
+
+static SAVED: OnceCell<Mutex<Saved>> = OnceCell::new();
+
+fn saved() -> &'static Mutex<Saved> {
+    SAVED.get_or_init(|| {
+        let saved = std::fs::read_to_string(state_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Mutex::new(saved)
+    })
+}
+
+fn state_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("briefing.json")
+}
+
+fn save(saved: &Saved) {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(saved) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log::warn!("Failed to save briefing state: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to save briefing state: {}", e),
+    }
+}
+
+/// A composed briefing
+#[derive(Debug, Clone)]
+pub struct Briefing {
+    pub title: String,
+    /// Headed groups of lines; groups with nothing to say are left out
+    pub sections: Vec<(String, Vec<String>)>,
+}
+
+impl Briefing {
+    /// The briefing as one text for the voice
+    fn spoken(&self) -> String {
+        let mut parts = vec![self.title.clone()];
+        for (_, lines) in &self.sections {
+            parts.extend(lines.iter().cloned());
+        }
+        parts.join(". ")
+    }
+}
+
+fn parse_time(at: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(at.trim(), "%H:%M").ok()
+}
+
+/// Show the briefing if it's due today and hasn't been shown yet
+pub fn tick(config: &Config) {
+    let briefing = &config.briefing;
+    if !briefing.enabled {
+        return;
+    }
+    let Some(at) = parse_time(&briefing.at) else {
+        return;
+    };
+    let until = parse_time(&briefing.until).unwrap_or(NaiveTime::MIN).max(at);
+    let now = Local::now();
+    if now.time() < at || now.time() > until {
+        return;
+    }
+
+    let today = now.format("%Y-%m-%d").to_string();
+    let mut saved = saved().lock();
+    if saved.last_shown.as_deref() == Some(today.as_str()) {
+        return;
+    }
+    saved.last_shown = Some(today);
+    save(&saved);
+    drop(saved);
+
+    show(config);
+}
+
+/// Show the briefing now
+pub fn show(config: &Config) {
+    let briefing = compose(config, Local::now());
+    crate::render::show_briefing(&briefing, config.briefing.duration_secs);
+    if config.briefing.speak {
+        crate::sounds::speak(&briefing.spoken(), config.briefing.volume, std::time::Duration::ZERO);
+    }
+}
+
+/// Put the briefing together from the modules' current data
+fn compose(config: &Config, now: DateTime<Local>) -> Briefing {
+    let greeting = match now.hour() {
+        5..=11 => "Good morning",
+        12..=17 => "Good afternoon",
+        _ => "Good evening",
+    };
+    let title = format!("{}. It's {}", greeting, now.format("%A, %B %-d"));
+
+    let (weather, modules) = with_renderer(|renderer| {
+        let weather = renderer
+            .module_registry
+            .get("weather")
+            .and_then(|m| m.as_any().downcast_ref::<crate::modules::weather::WeatherModule>())
+            .and_then(|w| w.summary());
+        let modules: Vec<String> = config
+            .briefing
+            .modules
+            .iter()
+            .filter_map(|id| {
+                let module = renderer.module_registry.get(id)?;
+                let text = module.display_text(config);
+                let text = text.trim();
+                (!text.is_empty()).then(|| format!("{}: {}", module.name(), text))
+            })
+            .collect();
+        (weather, modules)
+    })
+    .unwrap_or_default();
+
+    let mut sections = Vec::new();
+    if let Some(weather) = weather {
+        sections.push(("Weather".to_string(), vec![weather]));
+    }
+    let agenda = agenda(config, now);
+    if !agenda.is_empty() {
+        sections.push(("Today".to_string(), agenda));
+    }
+    if !modules.is_empty() {
+        sections.push(("At a glance".to_string(), modules));
+    }
+    Briefing { title, sections }
+}
+
+/// The rest of today's alarms and daily scheduled tasks, in time order
+fn agenda(config: &Config, now: DateTime<Local>) -> Vec<String> {
+    let today = now.date_naive();
+    let mut items: Vec<(DateTime<Local>, String)> = Vec::new();
+    for alarm in config.scheduler.alarms.iter().filter(|a| a.enabled) {
+        if let Some(at) = crate::alarms::next_ring(alarm).filter(|at| at.date_naive() == today) {
+            items.push((at, format!("{} alarm: {}", at.format("%H:%M"), alarm.label)));
+        }
+    }
+    // Interval tasks would list every few minutes; only daily ones make the agenda
+    let daily = config
+        .scheduler
+        .tasks
+        .iter()
+        .filter(|t| t.enabled && t.at.is_some() && t.every_minutes.unwrap_or(0) == 0);
+    for task in daily {
+        if let Some(at) = crate::scheduler::next_run(task).filter(|at| at.date_naive() == today) {
+            items.push((at, format!("{} {}", at.format("%H:%M"), task.name)));
+        }
+    }
+    items.sort_by_key(|(at, _)| *at);
+    items.into_iter().map(|(_, text)| text).collect()
+}
@@ -0,0 +1,76 @@
+//! Display brightness
+//!
+//! External monitors are dimmed over DDC/CI, a laptop's built-in panel through
+//! WMI (`WmiMonitorBrightness`). Setting one value sets every display that
+//! takes it. DDC/CI round trips take tens of milliseconds and the WMI method is
+//! reached through PowerShell, so both belong off the UI thread.
+
+use log::{debug, warn};
+use windows::Win32::Devices::Display::{GetMonitorBrightness, SetMonitorBrightness};
+
+use crate::modules::monitor_input::with_physical_monitors;
+
+/// Brightness in percent: the first monitor that reports it over DDC/CI, else
+/// the built-in panel; None when no display can be dimmed
+pub fn get() -> Option<u32> {
+    let external = with_physical_monitors(|monitors| {
+        monitors.iter().find_map(|monitor| {
+            let (mut min, mut current, mut max) = (0u32, 0u32, 0u32);
+            let ok = unsafe { GetMonitorBrightness(monitor.hPhysicalMonitor, &mut min, &mut current, &mut max) };
+            (ok != 0 && max > min).then(|| (current.saturating_sub(min)) * 100 / (max - min))
+        })
+    });
+    external.or_else(panel_brightness)
+}
+
+/// Set every display that can be dimmed to `percent`
+pub fn set(percent: u32) {
+    let percent = percent.min(100);
+    let external = with_physical_monitors(|monitors| {
+        let mut set = 0;
+        for monitor in monitors {
+            let (mut min, mut current, mut max) = (0u32, 0u32, 0u32);
+            unsafe {
+                if GetMonitorBrightness(monitor.hPhysicalMonitor, &mut min, &mut current, &mut max) != 0
+                    && max > min
+                    && SetMonitorBrightness(monitor.hPhysicalMonitor, min + (max - min) * percent / 100) != 0
+                {
+                    set += 1;
+                }
+            }
+        }
+        set
+    });
+    debug!("Brightness {}% set on {} external monitor(s)", percent, external);
+
+    if panel_brightness().is_none() {
+        return;
+    }
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let script = format!(
+        "Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightnessMethods | \
+         Invoke-CimMethod -MethodName WmiSetBrightness -Arguments @{{Timeout=1; Brightness={}}}",
+        percent
+    );
+    let result = std::process::Command::new("powershell")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output();
+    match result {
+        Ok(out) if out.status.success() => {}
+        Ok(out) => warn!("Failed to set panel brightness: {}", String::from_utf8_lossy(&out.stderr).trim()),
+        Err(e) => warn!("Failed to start PowerShell for brightness: {}", e),
+    }
+}
+
+/// The built-in panel's brightness, if there's one WMI can dim
+fn panel_brightness() -> Option<u32> {
+    crate::modules::metrics::query_wmi_numbers(
+        "ROOT\\WMI",
+        "SELECT CurrentBrightness FROM WmiMonitorBrightness",
+        "CurrentBrightness",
+    )
+    .first()
+    .map(|value| value.round().clamp(0.0, 100.0) as u32)
+}
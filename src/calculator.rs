@@ -0,0 +1,492 @@
+//! Inline answers for quick search: math, unit and currency conversion
+//!
+//! `2^10 / 4`, `sqrt(2)*pi`, `10 km to mi`, `72 f in c`, `1.5 gb to mb` and
+//! `100 usd to eur` are answered directly in the results list. Exchange rates come
+//! from open.er-api.com; they are fetched in the background on first use and cached
+//! for a few hours, so a currency query answers "fetching rates" until they arrive.
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long fetched exchange rates are used before fetching again
+const RATES_MAX_AGE: Duration = Duration::from_secs(6 * 3600);
+
+/// Exchange rates against USD by currency code
+type Rates = HashMap<String, f64>;
+
+/// Exchange rates, with the time they were fetched
+static RATES: OnceCell<Mutex<Option<(Instant, Rates)>>> = OnceCell::new();
+static FETCHING_RATES: AtomicBool = AtomicBool::new(false);
+
+/// An inline answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answer {
+    /// The result as shown, e.g. "6.21371 mi"
+    pub text: String,
+    /// What the query was understood as, e.g. "10 km to mi"
+    pub expression: String,
+    /// Plain value copied to the clipboard
+    pub value: String,
+    /// Waiting for data (exchange rates); ask again later
+    pub pending: bool,
+}
+
+/// Answer a query, or None if it isn't math or a conversion
+pub fn evaluate(query: &str) -> Option<Answer> {
+    let q = query.trim().trim_start_matches('=').trim();
+    let first = q.chars().next()?;
+    if !(first.is_ascii_digit() || "+-.(".contains(first) || query.trim_start().starts_with('=')) {
+        return None;
+    }
+
+    if let Some(answer) = convert(q) {
+        return Some(answer);
+    }
+
+    // A bare number isn't worth answering; require an operator or function
+    let value = Parser::new(q).parse()?;
+    if q.parse::<f64>().is_ok() || !value.is_finite() {
+        return None;
+    }
+    let text = format_number(value);
+    Some(Answer {
+        text: text.clone(),
+        expression: q.to_string(),
+        value: text,
+        pending: false,
+    })
+}
+
+/// `<expr> <unit> to|in|as <unit>`
+fn convert(q: &str) -> Option<Answer> {
+    let lower = q.to_lowercase();
+    let (left, target) = [" to ", " in ", " as ", " -> "]
+        .iter()
+        .find_map(|sep| lower.rsplit_once(sep))?;
+    let target = target.trim();
+
+    // Split the amount from its unit at the start of the trailing word
+    let left = left.trim();
+    let split = left
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphabetic() || "/°$€£¥".contains(*c))
+        .last()
+        .map(|(i, _)| i)?;
+    let (amount, unit) = left.split_at(split);
+    let amount = amount.trim();
+    let amount = if amount.is_empty() { 1.0 } else { Parser::new(amount).parse()? };
+
+    if let (Some(from), Some(to)) = (unit_info(unit), unit_info(target)) {
+        if from.kind != to.kind {
+            return None;
+        }
+        let value = match from.kind {
+            UnitKind::Temperature => from_kelvin(to_kelvin(amount, unit)?, target)?,
+            _ => amount * from.factor / to.factor,
+        };
+        let text = format!("{} {}", format_number(value), to.symbol);
+        return Some(Answer {
+            text,
+            expression: format!("{} {} to {}", format_number(amount), from.symbol, to.symbol),
+            value: format_number(value),
+            pending: false,
+        });
+    }
+
+    convert_currency(amount, unit, target)
+}
+
+fn convert_currency(amount: f64, from: &str, to: &str) -> Option<Answer> {
+    let from = currency_code(from)?;
+    let to = currency_code(to)?;
+    let expression = format!("{} {} to {}", format_number(amount), from, to);
+
+    let rates = rates();
+    let Some(rates) = rates else {
+        return Some(Answer {
+            text: "Fetching exchange rates...".to_string(),
+            expression,
+            value: String::new(),
+            pending: true,
+        });
+    };
+    let (from_rate, to_rate) = (rates.get(&from)?, rates.get(&to)?);
+    let value = amount / from_rate * to_rate;
+    let rounded = format!("{:.2}", value);
+    Some(Answer {
+        text: format!("{} {}", rounded, to),
+        expression,
+        value: rounded,
+        pending: false,
+    })
+}
+
+/// Three-letter currency code, also accepting the common symbols
+fn currency_code(s: &str) -> Option<String> {
+    let code = match s.trim() {
+        "$" => "usd",
+        "€" => "eur",
+        "£" => "gbp",
+        "¥" => "jpy",
+        other => other,
+    };
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then(|| code.to_uppercase())
+}
+
+/// Cached rates, starting a background fetch if they are missing or old
+fn rates() -> Option<Rates> {
+    let slot = RATES.get_or_init(|| Mutex::new(None));
+    let cached = slot.lock().clone();
+    let fresh = cached.as_ref().map(|(at, _)| at.elapsed() < RATES_MAX_AGE).unwrap_or(false);
+    if !fresh && !crate::modules::offline_mode() && !FETCHING_RATES.swap(true, Ordering::SeqCst) {
+        crate::runtime::spawn(|| {
+            match fetch_rates() {
+                Ok(rates) => {
+                    log::info!("Fetched {} exchange rates", rates.len());
+                    *RATES.get_or_init(|| Mutex::new(None)).lock() = Some((Instant::now(), rates));
+                }
+                Err(e) => log::warn!("Failed to fetch exchange rates: {}", e),
+            }
+            FETCHING_RATES.store(false, Ordering::SeqCst);
+        });
+    }
+    cached.map(|(_, rates)| rates)
+}
+
+fn fetch_rates() -> Result<Rates, String> {
+    let response = ureq::get("https://open.er-api.com/v6/latest/USD")
+        .set("User-Agent", "TopBar/1.0")
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| format!("HTTP error: {}", e))?;
+    let json: serde_json::Value = response
+        .into_json()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let rates = json["rates"].as_object().ok_or("Missing rates in response")?;
+    Ok(rates
+        .iter()
+        .filter_map(|(code, rate)| Some((code.to_uppercase(), rate.as_f64()?)))
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnitKind {
+    Length,
+    Mass,
+    Volume,
+    Data,
+    Time,
+    Speed,
+    Temperature,
+}
+
+struct UnitInfo {
+    kind: UnitKind,
+    /// Size in the kind's base unit (meters, grams, liters, bytes, seconds, m/s)
+    factor: f64,
+    symbol: &'static str,
+}
+
+fn unit_info(name: &str) -> Option<UnitInfo> {
+    use UnitKind::*;
+    let (kind, factor, symbol) = match name.trim() {
+        "mm" | "millimeter" | "millimeters" => (Length, 0.001, "mm"),
+        "cm" | "centimeter" | "centimeters" => (Length, 0.01, "cm"),
+        "m" | "meter" | "meters" | "metre" | "metres" => (Length, 1.0, "m"),
+        "km" | "kilometer" | "kilometers" | "kilometre" | "kilometres" => (Length, 1000.0, "km"),
+        "in" | "inch" | "inches" => (Length, 0.0254, "in"),
+        "ft" | "foot" | "feet" => (Length, 0.3048, "ft"),
+        "yd" | "yard" | "yards" => (Length, 0.9144, "yd"),
+        "mi" | "mile" | "miles" => (Length, 1609.344, "mi"),
+        "nmi" => (Length, 1852.0, "nmi"),
+
+        "mg" | "milligram" | "milligrams" => (Mass, 0.001, "mg"),
+        "g" | "gram" | "grams" => (Mass, 1.0, "g"),
+        "kg" | "kilogram" | "kilograms" | "kilo" | "kilos" => (Mass, 1000.0, "kg"),
+        "t" | "tonne" | "tonnes" => (Mass, 1_000_000.0, "t"),
+        "oz" | "ounce" | "ounces" => (Mass, 28.349523125, "oz"),
+        "lb" | "lbs" | "pound" | "pounds" => (Mass, 453.59237, "lb"),
+        "st" | "stone" => (Mass, 6350.29318, "st"),
+
+        "ml" | "milliliter" | "milliliters" => (Volume, 0.001, "ml"),
+        "l" | "liter" | "liters" | "litre" | "litres" => (Volume, 1.0, "l"),
+        "floz" => (Volume, 0.0295735295625, "fl oz"),
+        "cup" | "cups" => (Volume, 0.2365882365, "cup"),
+        "pt" | "pint" | "pints" => (Volume, 0.473176473, "pt"),
+        "qt" | "quart" | "quarts" => (Volume, 0.946352946, "qt"),
+        "gal" | "gallon" | "gallons" => (Volume, 3.785411784, "gal"),
+
+        "b" | "byte" | "bytes" => (Data, 1.0, "B"),
+        "kb" => (Data, 1e3, "KB"),
+        "mb" => (Data, 1e6, "MB"),
+        "gb" => (Data, 1e9, "GB"),
+        "tb" => (Data, 1e12, "TB"),
+        "kib" => (Data, 1024.0, "KiB"),
+        "mib" => (Data, 1024.0 * 1024.0, "MiB"),
+        "gib" => (Data, 1024.0 * 1024.0 * 1024.0, "GiB"),
+        "tib" => (Data, 1024.0 * 1024.0 * 1024.0 * 1024.0, "TiB"),
+
+        "ms" | "millisecond" | "milliseconds" => (Time, 0.001, "ms"),
+        "s" | "sec" | "secs" | "second" | "seconds" => (Time, 1.0, "s"),
+        "min" | "mins" | "minute" | "minutes" => (Time, 60.0, "min"),
+        "h" | "hr" | "hrs" | "hour" | "hours" => (Time, 3600.0, "h"),
+        "d" | "day" | "days" => (Time, 86_400.0, "d"),
+        "wk" | "week" | "weeks" => (Time, 604_800.0, "wk"),
+
+        "m/s" | "mps" => (Speed, 1.0, "m/s"),
+        "km/h" | "kmh" | "kph" => (Speed, 1000.0 / 3600.0, "km/h"),
+        "mph" => (Speed, 0.44704, "mph"),
+        "kn" | "knot" | "knots" => (Speed, 1852.0 / 3600.0, "kn"),
+
+        "c" | "°c" | "celsius" => (Temperature, 1.0, "°C"),
+        "f" | "°f" | "fahrenheit" => (Temperature, 1.0, "°F"),
+        "k" | "kelvin" => (Temperature, 1.0, "K"),
+        _ => return None,
+    };
+    Some(UnitInfo { kind, factor, symbol })
+}
+
+fn to_kelvin(value: f64, unit: &str) -> Option<f64> {
+    match unit_info(unit)?.symbol {
+        "°C" => Some(value + 273.15),
+        "°F" => Some((value - 32.0) * 5.0 / 9.0 + 273.15),
+        "K" => Some(value),
+        _ => None,
+    }
+}
+
+fn from_kelvin(kelvin: f64, unit: &str) -> Option<f64> {
+    match unit_info(unit)?.symbol {
+        "°C" => Some(kelvin - 273.15),
+        "°F" => Some((kelvin - 273.15) * 9.0 / 5.0 + 32.0),
+        "K" => Some(kelvin),
+        _ => None,
+    }
+}
+
+/// Up to 10 significant digits, without trailing zeros
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let decimals = (9 - magnitude).clamp(0, 12) as usize;
+    let text = format!("{:.*}", decimals, value);
+    // Only zeros after the decimal point are padding; whole numbers keep theirs
+    let text = if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.') } else { &text };
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+/// Recursive-descent parser for `+ - * / % ^`, parentheses, constants and functions
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { chars: input.chars().peekable() }
+    }
+
+    /// Parse the whole input; None on any syntax error
+    fn parse(mut self) -> Option<f64> {
+        let value = self.expr()?;
+        self.skip_ws();
+        self.chars.peek().is_none().then_some(value)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace() || *c == ',') {
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') || self.eat('x') || self.eat('×') {
+                value *= self.unary()?;
+            } else if self.eat('/') || self.eat('÷') {
+                value /= self.unary()?;
+            } else if self.eat('%') {
+                value %= self.unary()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        if self.eat('-') {
+            return Some(-self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Option<f64> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            // Right-associative, and binds tighter than unary minus on the left
+            return Some(base.powf(self.unary()?));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        self.skip_ws();
+        let c = *self.chars.peek()?;
+        if c == '(' {
+            self.chars.next();
+            let value = self.expr()?;
+            return self.eat(')').then_some(value);
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&d) = self.chars.peek() {
+                if d.is_ascii_digit() || d == '.' {
+                    number.push(d);
+                    self.chars.next();
+                } else if d == '_' {
+                    self.chars.next();
+                } else {
+                    break;
+                }
+            }
+            return number.parse().ok();
+        }
+        if c.is_alphabetic() {
+            let mut name = String::new();
+            while let Some(&d) = self.chars.peek() {
+                if d.is_alphanumeric() {
+                    name.push(d.to_ascii_lowercase());
+                    self.chars.next();
+                } else {
+                    break;
+                }
+            }
+            return match name.as_str() {
+                "pi" => Some(std::f64::consts::PI),
+                "e" => Some(std::f64::consts::E),
+                "tau" => Some(std::f64::consts::TAU),
+                _ => {
+                    let arg = self.atom()?;
+                    match name.as_str() {
+                        "sqrt" => Some(arg.sqrt()),
+                        "abs" => Some(arg.abs()),
+                        "ln" => Some(arg.ln()),
+                        "log" => Some(arg.log10()),
+                        "sin" => Some(arg.sin()),
+                        "cos" => Some(arg.cos()),
+                        "tan" => Some(arg.tan()),
+                        "round" => Some(arg.round()),
+                        "floor" => Some(arg.floor()),
+                        "ceil" => Some(arg.ceil()),
+                        _ => None,
+                    }
+                }
+            };
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(query: &str) -> String {
+        evaluate(query).map(|a| a.text).unwrap_or_default()
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        assert_eq!(answer("2^10 / 4"), "256");
+        assert_eq!(answer("(1 + 2) * 3"), "9");
+        assert_eq!(answer("2^3^2"), "512");
+        assert_eq!(answer("-2^2"), "-4");
+        assert_eq!(answer("1/3"), "0.3333333333");
+        assert_eq!(answer("=sqrt(16) + abs(-2)"), "6");
+        assert_eq!(answer("10 % 4"), "2");
+    }
+
+    #[test]
+    fn ignores_non_math() {
+        assert!(evaluate("42").is_none());
+        assert!(evaluate("notepad").is_none());
+        assert!(evaluate("2 +").is_none());
+        assert!(evaluate("(1 + 2").is_none());
+        assert!(evaluate("1/0").is_none());
+    }
+
+    #[test]
+    fn converts_units() {
+        assert_eq!(convert("10 km to mi").unwrap().text, "6.213711922 mi");
+        assert_eq!(convert("1.5 gb to mb").unwrap().text, "1500 MB");
+        assert_eq!(convert("72 f in c").unwrap().text, "22.22222222 °C");
+        assert_eq!(convert("0 c to k").unwrap().text, "273.15 K");
+        assert_eq!(convert("2 * 3 ft to in").unwrap().text, "72 in");
+        assert_eq!(convert("10 km to mi").unwrap().expression, "10 km to mi");
+    }
+
+    #[test]
+    fn rejects_mismatched_units() {
+        assert!(convert("10 km to kg").is_none());
+        assert!(convert("10 km to parsecs").is_none());
+        assert!(convert("10 km").is_none());
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(1500.0), "1500");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(2.5), "2.5");
+        assert_eq!(format_number(-1.25), "-1.25");
+        assert_eq!(format_number(1e-5), "0.00001");
+    }
+
+    #[test]
+    fn formats_large_numbers() {
+        assert_eq!(format_number(1e15), "1000000000000000");
+        assert_eq!(format_number(1e16), "10000000000000000");
+        assert_eq!(format_number(1e20), "100000000000000000000");
+        assert_eq!(format_number(1_000_000_000.5), "1000000000");
+        assert_eq!(format_number(1_000_000_001.5), "1000000002");
+        assert_eq!(format_number(12_345_678_900.25), "12345678900");
+    }
+
+    #[test]
+    fn formats_negative_zero() {
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(-1e-13), "0");
+        assert_eq!(answer("0 * -1 + 0.5 - 0.5"), "0");
+    }
+}
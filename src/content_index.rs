@@ -0,0 +1,224 @@
+//! Content index for small text files
+//!
+//! Optional companion to the file-name index: text files under
+//! `search.content_paths` are split into lowercase word tokens, so
+//! "content:todo" in quick search finds files that contain "todo". Only the
+//! tokens are kept in memory; the matching line is read back from the file when
+//! a result is shown.
+//!
+//! The index is rebuilt in the background at startup and again when quick search
+//! opens and it's older than `REFRESH_AFTER`.
+
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+use crate::config::SearchConfig;
+
+/// Prefix that switches quick search to content search
+pub const PREFIX: &str = "content:";
+
+const MAX_FILES: usize = 5000;
+const MAX_DEPTH: usize = 8;
+const MIN_TOKEN_LEN: usize = 2;
+const REFRESH_AFTER: Duration = Duration::from_secs(30 * 60);
+/// Longest snippet shown under a result
+const SNIPPET_CHARS: usize = 90;
+
+static INDEX: RwLock<Option<ContentIndex>> = RwLock::new(None);
+static BUILDING: AtomicBool = AtomicBool::new(false);
+
+/// A file whose contents matched, with the first matching line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentMatch {
+    pub path: String,
+    /// 1-based line number
+    pub line: usize,
+    pub snippet: String,
+}
+
+struct ContentIndex {
+    files: Vec<String>,
+    /// `content_max_kb` when built; a file that has grown since is only read this far
+    max_bytes: u64,
+    /// Token -> indexes into `files`
+    tokens: HashMap<String, Vec<u32>>,
+    built: Instant,
+}
+
+/// Lowercase alphanumeric words of at least `MIN_TOKEN_LEN` characters
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.chars().count() >= MIN_TOKEN_LEN)
+        .map(|w| w.to_lowercase())
+}
+
+impl ContentIndex {
+    fn build(config: &SearchConfig) -> Self {
+        let max_bytes = config.content_max_kb * 1024;
+        let mut index = Self {
+            files: Vec::new(),
+            max_bytes,
+            tokens: HashMap::new(),
+            built: Instant::now(),
+        };
+        let exts: Vec<String> = config.content_extensions.iter().map(|e| e.trim_start_matches('.').to_lowercase()).collect();
+
+        'roots: for root in &config.content_paths {
+            for entry in WalkDir::new(root).max_depth(MAX_DEPTH).into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let path = entry.path();
+                let wanted = path
+                    .extension()
+                    .map(|e| exts.contains(&e.to_string_lossy().to_lowercase()))
+                    .unwrap_or(false);
+                let small = entry.metadata().map(|m| m.len() <= max_bytes).unwrap_or(false);
+                if !wanted || !small {
+                    continue;
+                }
+                // Not UTF-8 (or binary despite the extension): skip it
+                let Ok(text) = std::fs::read_to_string(path) else {
+                    continue;
+                };
+
+                let id = index.files.len() as u32;
+                index.files.push(path.to_string_lossy().to_string());
+                let unique: HashSet<String> = tokenize(&text).collect();
+                for token in unique {
+                    index.tokens.entry(token).or_default().push(id);
+                }
+                if index.files.len() >= MAX_FILES {
+                    log::info!("Content index reached {} files, stopping early", MAX_FILES);
+                    break 'roots;
+                }
+            }
+        }
+        log::info!(
+            "Content index built with {} files and {} tokens",
+            index.files.len(),
+            index.tokens.len()
+        );
+        index
+    }
+
+    /// Files containing every query word; the last word may be a prefix, since it's still being typed
+    fn candidates(&self, words: &[String]) -> Vec<u32> {
+        let mut result: Option<HashSet<u32>> = None;
+        for (i, word) in words.iter().enumerate() {
+            let mut files: HashSet<u32> = self.tokens.get(word).into_iter().flatten().copied().collect();
+            if i == words.len() - 1 {
+                for (token, ids) in &self.tokens {
+                    if token.starts_with(word.as_str()) {
+                        files.extend(ids);
+                    }
+                }
+            }
+            result = Some(match result {
+                Some(acc) => acc.intersection(&files).copied().collect(),
+                None => files,
+            });
+        }
+        let mut ids: Vec<u32> = result.unwrap_or_default().into_iter().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+/// Rebuild the content index (blocks; call from a background thread)
+pub fn rebuild(config: &SearchConfig) {
+    if config.content_paths.is_empty() {
+        *INDEX.write() = None;
+        return;
+    }
+    if BUILDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let index = ContentIndex::build(config);
+    *INDEX.write() = Some(index);
+    BUILDING.store(false, Ordering::SeqCst);
+}
+
+/// Rebuild in the background if the index is old
+pub fn refresh_if_stale(config: &SearchConfig) {
+    if config.content_paths.is_empty() || BUILDING.load(Ordering::SeqCst) {
+        return;
+    }
+    let stale = INDEX.read().as_ref().map(|i| i.built.elapsed() >= REFRESH_AFTER).unwrap_or(true);
+    if stale {
+        let config = config.clone();
+        std::thread::spawn(move || rebuild(&config));
+    }
+}
+
+/// Whether the index is being built
+pub fn is_building() -> bool {
+    BUILDING.load(Ordering::SeqCst)
+}
+
+/// Files whose contents match `query`, with the first line containing the words
+///
+/// Reads the candidate files, so it runs in the background rather than on a keystroke.
+pub fn search(query: &str, limit: usize) -> Vec<ContentMatch> {
+    let words: Vec<String> = tokenize(query).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let index = INDEX.read();
+    let Some(index) = index.as_ref() else {
+        return Vec::new();
+    };
+
+    index
+        .candidates(&words)
+        .into_iter()
+        .filter_map(|id| {
+            let path = &index.files[id as usize];
+            // The file may have changed since it was indexed; then it's dropped here
+            let mut bytes = Vec::new();
+            std::fs::File::open(path)
+                .ok()?
+                .take(index.max_bytes)
+                .read_to_end(&mut bytes)
+                .ok()?;
+            let (line, snippet) = find_line(&String::from_utf8_lossy(&bytes), &words)?;
+            Some(ContentMatch {
+                path: path.clone(),
+                line,
+                snippet,
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+/// First line containing all words (or else the first word), trimmed around the match
+fn find_line(text: &str, words: &[String]) -> Option<(usize, String)> {
+    let lines: Vec<(usize, String)> = text.lines().enumerate().map(|(i, l)| (i, l.to_lowercase())).collect();
+    let (number, lower) = lines
+        .iter()
+        .find(|(_, l)| words.iter().all(|w| l.contains(w.as_str())))
+        .or_else(|| lines.iter().find(|(_, l)| l.contains(words[0].as_str())))?;
+    let original = text.lines().nth(*number)?.trim();
+
+    // Start shortly before the match so long lines still show it
+    let at = lower.trim_start().find(words[0].as_str()).unwrap_or(0);
+    let start_char = original
+        .char_indices()
+        .take_while(|(i, _)| *i < at)
+        .count()
+        .saturating_sub(20);
+    let mut snippet: String = original.chars().skip(start_char).take(SNIPPET_CHARS).collect();
+    if start_char > 0 {
+        snippet.insert(0, '…');
+    }
+    if original.chars().count() > start_char + SNIPPET_CHARS {
+        snippet.push('…');
+    }
+    Some((number + 1, snippet))
+}
+
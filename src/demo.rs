@@ -0,0 +1,155 @@
+//! Demo mode (`--demo`) - synthetic data for screenshots and UI work
+//!
+//! When enabled, modules read from these generators instead of the live system:
+//! CPU and memory drift, the battery slowly drains and recharges, the media module
+//! cycles through sample tracks and modules raise the occasional attention request.
+//! Values are smooth functions of the time since startup with a little jitter, so
+//! graphs look plausible and a given moment is reproducible across runs.
+
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::modules::metrics::{FanReading, ThermalSnapshot};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceCell<Instant> = OnceCell::new();
+/// Index of the last fake notification that was raised
+static LAST_NOTIFICATION: AtomicU64 = AtomicU64::new(0);
+
+/// Seconds between fake notifications
+const NOTIFICATION_INTERVAL: u64 = 75;
+
+const TRACKS: &[(&str, &str, &str)] = &[
+    ("Midnight City", "M83", "Hurry Up, We're Dreaming"),
+    ("Breathe", "Télépopmusik", "Genetic World"),
+    ("Intro", "The xx", "xx"),
+    ("Teardrop", "Massive Attack", "Mezzanine"),
+    ("Roygbiv", "Boards of Canada", "Music Has the Right to Children"),
+];
+
+const NOTIFICATIONS: &[(&str, AttentionLevel, &str, &str)] = &[
+    ("services", AttentionLevel::Warning, "Service stopped", "Print Spooler is stopped"),
+    ("watchdog", AttentionLevel::Error, "Process stopped", "Syncthing is no longer running"),
+    ("capture", AttentionLevel::Info, "Recording saved", "Recording 2024-05-01 10-30-00.mp4"),
+];
+
+/// Turn demo mode on (before the window is created)
+pub fn enable() {
+    STARTED.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether modules should use synthetic data
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Seconds since demo mode started
+fn elapsed() -> f32 {
+    STARTED.get_or_init(Instant::now).elapsed().as_secs_f32()
+}
+
+/// Smooth pseudo-random wave in 0.0-1.0 (a few incommensurate sines)
+fn wave(t: f32, seed: f32) -> f32 {
+    let v = (t * 0.31 + seed).sin() * 0.5 + (t * 0.117 + seed * 2.3).sin() * 0.3 + (t * 1.73 + seed * 0.7).sin() * 0.2;
+    (v + 1.0) / 2.0
+}
+
+/// CPU usage in percent
+pub fn cpu_usage() -> f32 {
+    let t = elapsed();
+    // Mostly idle with periodic bursts, like a desktop doing occasional work
+    let base = 8.0 + wave(t, 0.0) * 25.0;
+    let burst = ((t / 40.0).sin().max(0.0)).powi(8) * 55.0;
+    (base + burst).min(100.0)
+}
+
+/// Memory (used, total) in bytes
+pub fn memory() -> (u64, u64) {
+    let total: u64 = 32 * 1024 * 1024 * 1024;
+    let fraction = 0.42 + wave(elapsed(), 1.7) as f64 * 0.12;
+    ((total as f64 * fraction) as u64, total)
+}
+
+/// Battery state: (percent, charging, seconds remaining)
+///
+/// Drains from 85% to 15% at one percent every 20 seconds, then charges back up.
+pub fn battery() -> (u32, bool, Option<u32>) {
+    const SECONDS_PER_PERCENT: f32 = 20.0;
+    let steps = (elapsed() / SECONDS_PER_PERCENT) as u32 % 140;
+    if steps < 70 {
+        let percent = 85 - steps;
+        // Roughly six minutes per percent of real-world runtime
+        (percent, false, Some(percent * 360))
+    } else {
+        (15 + (steps - 70), true, None)
+    }
+}
+
+/// Current track: (title, artist, album, playing)
+pub fn track() -> (&'static str, &'static str, &'static str, bool) {
+    let t = elapsed() as usize;
+    let (title, artist, album) = TRACKS[(t / 180) % TRACKS.len()];
+    // Paused for the last 20 seconds of each three-minute slot
+    (title, artist, album, t % 180 < 160)
+}
+
+/// Network speeds (download, upload) in bytes per second
+pub fn network_speeds() -> (u64, u64) {
+    let t = elapsed();
+    let down = 40_000.0 + wave(t, 3.1).powi(3) * 4_500_000.0;
+    let up = 8_000.0 + wave(t, 4.2).powi(4) * 600_000.0;
+    (down as u64, up as u64)
+}
+
+/// Disk (read, write) in bytes per second
+pub fn disk_speeds() -> (u64, u64) {
+    let t = elapsed();
+    let read = 120_000.0 + wave(t, 3.7).powi(3) * 85_000_000.0;
+    let write = 60_000.0 + wave(t, 5.1).powi(4) * 40_000_000.0;
+    (read as u64, write as u64)
+}
+
+/// GPU state: (usage percent, memory used, memory total, temperature)
+pub fn gpu() -> (f32, u64, u64, f32) {
+    let t = elapsed();
+    let usage = 3.0 + wave(t, 5.5) * 30.0;
+    let total: u64 = 8 * 1024 * 1024 * 1024;
+    let used = (total as f64 * (0.18 + wave(t, 6.6) as f64 * 0.1)) as u64;
+    (usage, used, total, 38.0 + usage * 0.5)
+}
+
+/// Temperatures and fan speeds, loosely following the CPU and GPU load
+pub fn thermal() -> ThermalSnapshot {
+    let cpu = 42.0 + cpu_usage() * 0.45;
+    let gpu = gpu().3;
+    ThermalSnapshot {
+        cpu_temp: Some(cpu),
+        gpu_temp: Some(gpu),
+        fans: vec![
+            FanReading {
+                name: "CPU Fan".to_string(),
+                rpm: 700.0 + (cpu - 40.0) * 25.0,
+            },
+            FanReading {
+                name: "GPU Fan".to_string(),
+                rpm: if gpu > 50.0 { 900.0 + (gpu - 50.0) * 30.0 } else { 0.0 },
+            },
+        ],
+    }
+}
+
+/// Raise a fake notification every so often (called from the one-second timer)
+pub fn tick(config: &crate::config::Config) {
+    if !is_enabled() {
+        return;
+    }
+    let index = elapsed() as u64 / NOTIFICATION_INTERVAL;
+    if index == 0 || LAST_NOTIFICATION.swap(index, Ordering::SeqCst) == index {
+        return;
+    }
+    let (module, level, title, text) = NOTIFICATIONS[(index as usize - 1) % NOTIFICATIONS.len()];
+    crate::attention::request(config, AttentionRequest::new(module, level, title, text));
+}
@@ -0,0 +1,220 @@
+//! Dictionary lookups
+//!
+//! Fetches definitions and synonyms for a word from a dictionary API in the
+//! format of dictionaryapi.dev (`[dictionary]` in the config). Used by the
+//! `define` action, which looks up the selected word, and by quick search, which
+//! offers a "Define" row when the query is a single word. Recent lookups are
+//! kept so reopening a word doesn't go back to the network.
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::config::DictionaryConfig;
+
+/// Lookups kept for reuse
+const CACHE_SIZE: usize = 30;
+
+/// A word's definitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub word: String,
+    /// Pronunciation, e.g. "/həˈləʊ/"
+    pub phonetic: Option<String>,
+    pub meanings: Vec<Meaning>,
+}
+
+/// Definitions for one part of speech
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meaning {
+    /// "noun", "verb", ...
+    pub part_of_speech: String,
+    pub definitions: Vec<String>,
+    pub synonyms: Vec<String>,
+}
+
+static CACHE: OnceCell<Mutex<VecDeque<Entry>>> = OnceCell::new();
+
+fn cache() -> &'static Mutex<VecDeque<Entry>> {
+    CACHE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Whether `query` is something to offer a definition for: a single word
+pub fn is_word(query: &str) -> bool {
+    let query = query.trim();
+    query.chars().count() >= 2 && query.chars().all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+}
+
+/// Look up a word. Blocks on the network unless it was looked up recently.
+pub fn lookup(config: &DictionaryConfig, word: &str) -> Result<Entry, String> {
+    let word = word.trim().to_lowercase();
+    {
+        let mut cache = cache().lock();
+        if let Some(pos) = cache.iter().position(|e| e.word.to_lowercase() == word) {
+            let hit = cache.remove(pos).expect("position is in range");
+            cache.push_front(hit.clone());
+            return Ok(hit);
+        }
+    }
+
+    let entry = request(config, &word)?;
+    let mut cache = cache().lock();
+    cache.push_front(entry.clone());
+    cache.truncate(CACHE_SIZE);
+    Ok(entry)
+}
+
+fn request(config: &DictionaryConfig, word: &str) -> Result<Entry, String> {
+    if config.url.is_empty() {
+        return Err("No dictionary set in [dictionary]".to_string());
+    }
+    if crate::modules::offline_mode() {
+        return Err("Offline mode is on".to_string());
+    }
+    let url = config.url.replace("{word}", &crate::utils::url_encode(word));
+    let json: serde_json::Value = match ureq::get(&url)
+        .set("User-Agent", "TopBar/1.0")
+        .timeout(Duration::from_secs(10))
+        .call()
+    {
+        Ok(response) => response
+            .into_json()
+            .map_err(|e| format!("Failed to read response: {}", e))?,
+        Err(ureq::Error::Status(404, _)) => return Err(format!("No definitions found for \"{}\"", word)),
+        Err(e) => return Err(format!("HTTP error: {}", e)),
+    };
+    parse(word, &json).ok_or_else(|| format!("No definitions found for \"{}\"", word))
+}
+
+/// Merge the entries of a dictionaryapi.dev response (one per etymology) into one
+fn parse(word: &str, json: &serde_json::Value) -> Option<Entry> {
+    let entries = json.as_array()?;
+    let strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|items| items.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let mut entry = Entry {
+        word: entries.first()?["word"].as_str().unwrap_or(word).to_string(),
+        phonetic: None,
+        meanings: Vec::new(),
+    };
+    for item in entries {
+        if entry.phonetic.is_none() {
+            entry.phonetic = item["phonetic"]
+                .as_str()
+                .filter(|p| !p.is_empty())
+                .or_else(|| {
+                    item["phonetics"]
+                        .as_array()?
+                        .iter()
+                        .find_map(|p| p["text"].as_str().filter(|t| !t.is_empty()))
+                })
+                .map(str::to_string);
+        }
+        for meaning in item["meanings"].as_array().into_iter().flatten() {
+            let part_of_speech = meaning["partOfSpeech"].as_str().unwrap_or_default().to_string();
+            let definitions: Vec<&serde_json::Value> = meaning["definitions"].as_array().into_iter().flatten().collect();
+            let mut synonyms = strings(&meaning["synonyms"]);
+            synonyms.extend(definitions.iter().flat_map(|d| strings(&d["synonyms"])));
+            let definitions: Vec<String> = definitions
+                .iter()
+                .filter_map(|d| d["definition"].as_str().map(str::to_string))
+                .collect();
+
+            // Same part of speech from another etymology goes under the first
+            let target = match entry.meanings.iter().position(|m| m.part_of_speech == part_of_speech) {
+                Some(pos) => &mut entry.meanings[pos],
+                None => {
+                    entry.meanings.push(Meaning {
+                        part_of_speech,
+                        definitions: Vec::new(),
+                        synonyms: Vec::new(),
+                    });
+                    entry.meanings.last_mut().expect("just pushed")
+                }
+            };
+            target.definitions.extend(definitions);
+            for synonym in synonyms {
+                if !target.synonyms.contains(&synonym) {
+                    target.synonyms.push(synonym);
+                }
+            }
+        }
+    }
+    (!entry.meanings.is_empty()).then_some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_words() {
+        assert!(is_word(" hello "));
+        assert!(is_word("well-being"));
+        assert!(is_word("don't"));
+        assert!(!is_word("a"));
+        assert!(!is_word("two words"));
+        assert!(!is_word("abc123"));
+    }
+
+    #[test]
+    fn merges_entries() {
+        let json = serde_json::json!([
+            {
+                "word": "Bank",
+                "phonetics": [{ "text": "" }, { "text": "/bæŋk/" }],
+                "meanings": [{
+                    "partOfSpeech": "noun",
+                    "synonyms": ["shore"],
+                    "definitions": [
+                        { "definition": "The edge of a river.", "synonyms": ["shore", "edge"] },
+                    ],
+                }],
+            },
+            {
+                "word": "bank",
+                "phonetic": "/baŋk/",
+                "meanings": [
+                    { "partOfSpeech": "noun", "definitions": [{ "definition": "A place that keeps money." }] },
+                    { "partOfSpeech": "verb", "definitions": [{ "definition": "To deposit money." }] },
+                ],
+            },
+        ]);
+        let entry = parse("bank", &json).expect("entry");
+        assert_eq!(entry.word, "Bank");
+        assert_eq!(entry.phonetic.as_deref(), Some("/bæŋk/"));
+        assert_eq!(entry.meanings.len(), 2);
+        assert_eq!(entry.meanings[0].definitions, ["The edge of a river.", "A place that keeps money."]);
+        assert_eq!(entry.meanings[0].synonyms, ["shore", "edge"]);
+        assert_eq!(entry.meanings[1].part_of_speech, "verb");
+    }
+
+    #[test]
+    fn no_entry_without_meanings() {
+        assert_eq!(parse("xyzzy", &serde_json::json!({ "title": "No Definitions Found" })), None);
+        assert_eq!(parse("xyzzy", &serde_json::json!([])), None);
+        assert_eq!(parse("xyzzy", &serde_json::json!([{ "word": "xyzzy", "meanings": [] }])), None);
+    }
+
+    #[test]
+    fn reuses_recent_lookups() {
+        // No dictionary, so anything not in the cache fails
+        let config = DictionaryConfig {
+            url: String::new(),
+            ..Default::default()
+        };
+        let entry = Entry {
+            word: "Cachetest".to_string(),
+            phonetic: None,
+            meanings: Vec::new(),
+        };
+        cache().lock().push_front(entry.clone());
+        assert_eq!(lookup(&config, " CACHETEST "), Ok(entry));
+        assert!(lookup(&config, "uncached").is_err());
+    }
+}
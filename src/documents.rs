@@ -0,0 +1,876 @@
+//! Document readers for QuickLook
+//!
+//! Turns Markdown, Word (.docx) and Excel (.xlsx) files into a small block
+//! model that the preview window can paint with real formatting. Office files
+//! are zip archives of XML parts; only the parts needed for a readable preview
+//! are decoded (paragraph text, bold runs, heading styles, tables and the first
+//! worksheet), so layout, images and formulas are left out.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Largest Office file we'll open for a preview
+const MAX_ARCHIVE_SIZE: u64 = 50 * 1024 * 1024;
+/// Largest decompressed XML part we'll read
+const MAX_PART_SIZE: u64 = 32 * 1024 * 1024;
+/// Worksheet rows and columns shown in a preview
+const MAX_SHEET_ROWS: usize = 500;
+const MAX_SHEET_COLS: usize = 40;
+
+/// A run of text with inline formatting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+/// A block of a rendered document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// Heading level 1-6
+    Heading(u8, Vec<Span>),
+    Paragraph(Vec<Span>),
+    /// List item with its marker ("•" or "1.") and nesting depth
+    ListItem { marker: String, depth: u8, spans: Vec<Span> },
+    Quote(Vec<Span>),
+    Code(String),
+    Rule,
+    /// Rows of cells; `header` styles the first row as column headings
+    Table { rows: Vec<Vec<String>>, header: bool },
+}
+
+/// Append text to a span list, merging with the last span when the formatting matches
+fn push_span(spans: &mut Vec<Span>, text: &str, bold: bool, italic: bool, code: bool) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(last) = spans.last_mut() {
+        if last.bold == bold && last.italic == italic && last.code == code {
+            last.text.push_str(text);
+            return;
+        }
+    }
+    spans.push(Span { text: text.to_string(), bold, italic, code });
+}
+
+// ---------------------------------------------------------------------------
+// Markdown
+// ---------------------------------------------------------------------------
+
+/// Parse Markdown into blocks
+///
+/// Covers the common subset: ATX headings, paragraphs, fenced code, bullet and
+/// numbered lists, block quotes, rules and pipe tables, with **bold**, *italic*,
+/// `code` and [links](url) inline.
+pub fn parse_markdown(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut table: Vec<Vec<String>> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    fn flush_paragraph(blocks: &mut Vec<Block>, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+    fn flush_table(blocks: &mut Vec<Block>, table: &mut Vec<Vec<String>>) {
+        if !table.is_empty() {
+            blocks.push(Block::Table { rows: std::mem::take(table), header: true });
+        }
+    }
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                blocks.push(Block::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if !trimmed.starts_with('|') {
+            flush_table(&mut blocks, &mut table);
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            code = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            flush_paragraph(&mut blocks, &mut paragraph);
+        } else if let Some(level) = heading_level(trimmed) {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            let title = trimmed[level as usize..].trim().trim_end_matches('#').trim_end();
+            blocks.push(Block::Heading(level, parse_inline(title)));
+        } else if is_rule(trimmed) {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            blocks.push(Block::Rule);
+        } else if let Some((marker, rest)) = list_marker(trimmed) {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            let indent = line.len() - line.trim_start().len();
+            blocks.push(Block::ListItem {
+                marker,
+                depth: (indent / 2).min(6) as u8,
+                spans: parse_inline(rest),
+            });
+        } else if let Some(rest) = trimmed.strip_prefix('>') {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            blocks.push(Block::Quote(parse_inline(rest.trim_start())));
+        } else if trimmed.starts_with('|') {
+            flush_paragraph(&mut blocks, &mut paragraph);
+            let cells: Vec<String> = trimmed
+                .trim_matches('|')
+                .split('|')
+                .map(|c| c.trim().to_string())
+                .collect();
+            // Skip the |---|:---:| alignment row
+            let separator = cells
+                .iter()
+                .all(|c| !c.is_empty() && c.chars().all(|ch| matches!(ch, '-' | ':' | ' ')));
+            if !separator {
+                table.push(cells);
+            }
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    flush_paragraph(&mut blocks, &mut paragraph);
+    flush_table(&mut blocks, &mut table);
+    blocks
+}
+
+/// `#` count of an ATX heading ("## Title" -> 2)
+fn heading_level(line: &str) -> Option<u8> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[hashes..];
+    ((1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' '))).then_some(hashes as u8)
+}
+
+/// "---", "***" or "___" (spaces allowed)
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|&c| c == chars[0])
+}
+
+/// Marker and text of a list item ("- item" -> ("•", "item"))
+fn list_marker(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some(("•".to_string(), rest));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits < 10 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((format!("{}.", &line[..digits]), rest));
+        }
+    }
+    None
+}
+
+/// Parse inline Markdown formatting into spans
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let (mut bold, mut italic) = (false, false);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                current.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '`' => {
+                if let Some(end) = chars[i + 1..].iter().position(|&ch| ch == '`') {
+                    push_span(&mut spans, &current, bold, italic, false);
+                    current.clear();
+                    let literal: String = chars[i + 1..i + 1 + end].iter().collect();
+                    push_span(&mut spans, &literal, false, false, true);
+                    i += end + 2;
+                    continue;
+                }
+            }
+            '*' | '_' if next == Some(c) => {
+                push_span(&mut spans, &current, bold, italic, false);
+                current.clear();
+                bold = !bold;
+                i += 2;
+                continue;
+            }
+            // snake_case words keep their underscores
+            '*' | '_'
+                if c == '*'
+                    || !(prev.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric)) =>
+            {
+                push_span(&mut spans, &current, bold, italic, false);
+                current.clear();
+                italic = !italic;
+                i += 1;
+                continue;
+            }
+            '[' => {
+                // [label](url) shows the label
+                if let Some(close) = chars[i + 1..].iter().position(|&ch| ch == ']').map(|p| p + i + 1) {
+                    if chars.get(close + 1) == Some(&'(') {
+                        if let Some(paren) = chars[close + 1..].iter().position(|&ch| ch == ')') {
+                            current.extend(&chars[i + 1..close]);
+                            i = close + 1 + paren + 1;
+                            continue;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        current.push(c);
+        i += 1;
+    }
+    push_span(&mut spans, &current, bold, italic, false);
+    spans
+}
+
+// ---------------------------------------------------------------------------
+// Office Open XML
+// ---------------------------------------------------------------------------
+
+/// Read a Word document's body
+pub fn read_docx(path: &Path) -> Result<Vec<Block>> {
+    let xml = Zip::open(path)?.read_text("word/document.xml")?;
+    Ok(docx_blocks(&xml))
+}
+
+/// Blocks of a Word document's `document.xml`
+fn docx_blocks(xml: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style: Option<String> = None;
+    let mut numbered = false;
+    let (mut in_run, mut in_text, mut bold, mut italic) = (false, false, false, false);
+    // Table being read: rows, the current row and the current cell's text
+    let mut table: Option<Vec<Vec<String>>> = None;
+    // Nested tables are flattened into the cell that holds them
+    let mut table_depth = 0;
+    let mut row: Vec<String> = Vec::new();
+    let mut cell = String::new();
+
+    for event in XmlReader::new(xml) {
+        match event {
+            Xml::Open { name: "w:p", empty: false, .. } => {
+                spans.clear();
+                style = None;
+                numbered = false;
+            }
+            Xml::Open { name: "w:pStyle", attrs, .. } => style = attr(attrs, "w:val").map(str::to_string),
+            Xml::Open { name: "w:numPr", .. } => numbered = true,
+            Xml::Open { name: "w:r", empty: false, .. } => {
+                in_run = true;
+                bold = false;
+                italic = false;
+            }
+            Xml::Close("w:r") => in_run = false,
+            Xml::Open { name: "w:b", attrs, .. } if in_run => bold = toggle_on(attrs),
+            Xml::Open { name: "w:i", attrs, .. } if in_run => italic = toggle_on(attrs),
+            Xml::Open { name: "w:t", empty: false, .. } if in_run => in_text = true,
+            Xml::Close("w:t") => in_text = false,
+            Xml::Open { name: "w:tab", .. } if in_run => push_span(&mut spans, "\t", bold, italic, false),
+            Xml::Open { name: "w:br" | "w:cr", .. } if in_run => push_span(&mut spans, " ", bold, italic, false),
+            Xml::Text(text) if in_text => push_span(&mut spans, &unescape(text), bold, italic, false),
+            Xml::Close("w:p") => {
+                let text: String = spans.iter().map(|s| s.text.as_str()).collect();
+                if table.is_some() {
+                    if !cell.is_empty() {
+                        cell.push(' ');
+                    }
+                    cell.push_str(text.trim());
+                } else if !text.trim().is_empty() {
+                    blocks.push(docx_paragraph(style.as_deref(), numbered, std::mem::take(&mut spans)));
+                }
+            }
+            Xml::Open { name: "w:tbl", empty: false, .. } => {
+                table_depth += 1;
+                if table_depth == 1 {
+                    table = Some(Vec::new());
+                }
+            }
+            Xml::Open { name: "w:tr", empty: false, .. } if table_depth == 1 => row.clear(),
+            Xml::Close("w:tc") if table_depth == 1 => row.push(std::mem::take(&mut cell)),
+            Xml::Close("w:tr") if table_depth == 1 => {
+                if let Some(rows) = table.as_mut() {
+                    rows.push(std::mem::take(&mut row));
+                }
+            }
+            Xml::Close("w:tbl") => {
+                table_depth -= 1;
+                if table_depth == 0 {
+                    if let Some(rows) = table.take().filter(|rows| !rows.is_empty()) {
+                        blocks.push(Block::Table { rows, header: false });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+/// `<w:b/>` is on unless its value says otherwise
+fn toggle_on(attrs: &str) -> bool {
+    !matches!(attr(attrs, "w:val"), Some("0" | "false" | "off"))
+}
+
+/// Block for a Word paragraph, from its style ID and numbering
+fn docx_paragraph(style: Option<&str>, numbered: bool, spans: Vec<Span>) -> Block {
+    let style = style.unwrap_or_default().to_ascii_lowercase();
+    let level = match style.as_str() {
+        "title" => Some(1),
+        "subtitle" => Some(3),
+        s => s.strip_prefix("heading").and_then(|n| n.parse::<u8>().ok()),
+    };
+    match level {
+        Some(level) => Block::Heading(level.clamp(1, 6), spans),
+        None if numbered || style.starts_with("listparagraph") || style.starts_with("listbullet") => {
+            Block::ListItem { marker: "•".to_string(), depth: 0, spans }
+        }
+        None if style == "quote" || style == "intensequote" => Block::Quote(spans),
+        None => Block::Paragraph(spans),
+    }
+}
+
+/// Read the first worksheet of an Excel workbook
+///
+/// Returns the sheet name and a table with column letters across the top and
+/// row numbers down the side, like the grid in Excel.
+pub fn read_xlsx(path: &Path) -> Result<Vec<Block>> {
+    let zip = Zip::open(path)?;
+    let shared = zip
+        .read_text("xl/sharedStrings.xml")
+        .map(|xml| shared_strings(&xml))
+        .unwrap_or_default();
+
+    let workbook = zip.read_text("xl/workbook.xml")?;
+    let (sheet_name, sheet_rel) = XmlReader::new(&workbook)
+        .find_map(|event| match event {
+            Xml::Open { name: "sheet", attrs, .. } => Some((
+                attr(attrs, "name").map(unescape).unwrap_or_else(|| "Sheet1".to_string()),
+                attr(attrs, "r:id").map(str::to_string),
+            )),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("workbook has no sheets"))?;
+
+    // Resolve the sheet's relationship ID to its part name
+    let part = sheet_rel
+        .and_then(|id| {
+            let rels = zip.read_text("xl/_rels/workbook.xml.rels").ok()?;
+            XmlReader::new(&rels).find_map(|event| match event {
+                Xml::Open { name: "Relationship", attrs, .. } if attr(attrs, "Id") == Some(id.as_str()) => {
+                    attr(attrs, "Target").map(|target| match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("xl/{}", target),
+                    })
+                }
+                _ => None,
+            })
+        })
+        .unwrap_or_else(|| "xl/worksheets/sheet1.xml".to_string());
+
+    let sheet = zip.read_text(&part)?;
+    let mut grid: Vec<Vec<String>> = Vec::new();
+    let (mut row, mut col, mut next_row) = (0usize, 0usize, 0usize);
+    let mut kind = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut truncated = false;
+
+    for event in XmlReader::new(&sheet) {
+        match event {
+            Xml::Open { name: "row", attrs, .. } => {
+                row = attr(attrs, "r")
+                    .and_then(|r| r.parse::<usize>().ok())
+                    .map(|r| r.saturating_sub(1))
+                    .unwrap_or(next_row);
+                next_row = row + 1;
+                col = 0;
+            }
+            Xml::Open { name: "c", attrs, empty } => {
+                col = attr(attrs, "r").and_then(column_index).unwrap_or(col);
+                kind = attr(attrs, "t").unwrap_or_default().to_string();
+                value.clear();
+                if empty {
+                    col += 1;
+                }
+            }
+            Xml::Open { name: "v" | "t", empty: false, .. } => in_value = true,
+            Xml::Close("v" | "t") => in_value = false,
+            Xml::Text(text) if in_value => value.push_str(&unescape(text)),
+            Xml::Close("c") => {
+                let text = match kind.as_str() {
+                    "s" => value
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| shared.get(i).cloned())
+                        .unwrap_or_default(),
+                    "b" => (if value.trim() == "1" { "TRUE" } else { "FALSE" }).to_string(),
+                    _ => value.clone(),
+                };
+                if row >= MAX_SHEET_ROWS || col >= MAX_SHEET_COLS {
+                    truncated = true;
+                } else if !text.is_empty() {
+                    if grid.len() <= row {
+                        grid.resize(row + 1, Vec::new());
+                    }
+                    if grid[row].len() <= col {
+                        grid[row].resize(col + 1, String::new());
+                    }
+                    grid[row][col] = text;
+                }
+                col += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let mut rows = Vec::with_capacity(grid.len() + 1);
+    rows.push(
+        std::iter::once(String::new())
+            .chain((0..columns).map(column_name))
+            .collect::<Vec<_>>(),
+    );
+    for (i, mut cells) in grid.into_iter().enumerate() {
+        cells.resize(columns, String::new());
+        rows.push(std::iter::once((i + 1).to_string()).chain(cells).collect());
+    }
+
+    let mut blocks = vec![Block::Heading(3, vec![Span { text: sheet_name, ..Span::default() }])];
+    if columns == 0 {
+        blocks.push(Block::Paragraph(vec![Span {
+            text: "This sheet is empty".to_string(),
+            italic: true,
+            ..Span::default()
+        }]));
+    } else {
+        blocks.push(Block::Table { rows, header: true });
+    }
+    if truncated {
+        blocks.push(Block::Paragraph(vec![Span {
+            text: format!("Showing the first {} rows and {} columns", MAX_SHEET_ROWS, MAX_SHEET_COLS),
+            italic: true,
+            ..Span::default()
+        }]));
+    }
+    Ok(blocks)
+}
+
+/// Shared string table of a workbook (`<si>` entries, rich text runs joined)
+fn shared_strings(xml: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let (mut in_text, mut in_phonetic) = (false, false);
+    for event in XmlReader::new(xml) {
+        match event {
+            Xml::Open { name: "si", empty, .. } => {
+                current.clear();
+                if empty {
+                    strings.push(String::new());
+                }
+            }
+            Xml::Close("si") => strings.push(std::mem::take(&mut current)),
+            // Skip furigana runs
+            Xml::Open { name: "rPh", empty: false, .. } => in_phonetic = true,
+            Xml::Close("rPh") => in_phonetic = false,
+            Xml::Open { name: "t", empty: false, .. } => in_text = !in_phonetic,
+            Xml::Close("t") => in_text = false,
+            Xml::Text(text) if in_text => current.push_str(&unescape(text)),
+            _ => {}
+        }
+    }
+    strings
+}
+
+/// Zero-based column of a cell reference ("C7" -> 2)
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference.bytes().take_while(u8::is_ascii_alphabetic).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    // A run of letters too long for any real sheet overflows rather than wrapping
+    let index = letters.iter().try_fold(0usize, |acc, &b| {
+        acc.checked_mul(26)?
+            .checked_add((b.to_ascii_uppercase() - b'A') as usize + 1)
+    })?;
+    Some(index - 1)
+}
+
+/// Column letters for a zero-based index (27 -> "AB")
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Zip and XML
+// ---------------------------------------------------------------------------
+
+/// Where an entry's data is, from the central directory
+struct ZipEntry {
+    method: usize,
+    compressed: usize,
+    local: usize,
+}
+
+/// A zip archive held in memory, with its central directory read once
+struct Zip {
+    data: Vec<u8>,
+    entries: HashMap<String, ZipEntry>,
+}
+
+impl Zip {
+    fn open(path: &Path) -> Result<Self> {
+        if std::fs::metadata(path)?.len() > MAX_ARCHIVE_SIZE {
+            bail!("file is too large to preview");
+        }
+        Self::parse(std::fs::read(path)?)
+    }
+
+    fn parse(data: Vec<u8>) -> Result<Self> {
+        // The end-of-central-directory record is in the last 64 KiB (its comment is at most 65535 bytes)
+        let eocd = (0..data.len().saturating_sub(21))
+            .rev()
+            .take(65_536 + 22)
+            .find(|&i| data[i..i + 4] == [0x50, 0x4b, 0x05, 0x06])
+            .ok_or_else(|| anyhow!("not a zip archive"))?;
+        // A ZIP64 locator sits just before the record; Office never writes one
+        // for files small enough to preview
+        let zip64_locator = eocd >= 20 && data[eocd - 20..eocd - 16] == [0x50, 0x4b, 0x06, 0x07];
+        let count = u16_at(&data, eocd + 10)?;
+        let mut at = u32_at(&data, eocd + 16)?;
+        if zip64_locator || count == 0xffff || at == 0xffff_ffff {
+            bail!("ZIP64 archives aren't supported");
+        }
+
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if u32_at(&data, at)? != 0x0201_4b50 {
+                bail!("corrupt zip directory");
+            }
+            let method = u16_at(&data, at + 10)?;
+            let compressed = u32_at(&data, at + 20)?;
+            let name_len = u16_at(&data, at + 28)?;
+            let extra_len = u16_at(&data, at + 30)?;
+            let comment_len = u16_at(&data, at + 32)?;
+            let local = u32_at(&data, at + 42)?;
+            if compressed == 0xffff_ffff || local == 0xffff_ffff {
+                bail!("ZIP64 archives aren't supported");
+            }
+            let name = data.get(at + 46..at + 46 + name_len).context("truncated zip archive")?;
+            entries.insert(
+                String::from_utf8_lossy(name).into_owned(),
+                ZipEntry { method, compressed, local },
+            );
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Self { data, entries })
+    }
+
+    /// Read one entry (stored or deflated)
+    fn read(&self, entry: &str) -> Result<Vec<u8>> {
+        let ZipEntry { method, compressed, local } = self
+            .entries
+            .get(entry)
+            .ok_or_else(|| anyhow!("{} not found in archive", entry))?;
+        let data = &self.data;
+        let start = local + 30 + u16_at(data, local + 26)? + u16_at(data, local + 28)?;
+        let raw = data.get(start..start + compressed).context("truncated zip archive")?;
+        match method {
+            0 => Ok(raw.to_vec()),
+            8 => {
+                let mut out = Vec::new();
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(MAX_PART_SIZE)
+                    .read_to_end(&mut out)?;
+                Ok(out)
+            }
+            other => bail!("unsupported zip compression method {}", other),
+        }
+    }
+
+    /// Read one entry as text
+    fn read_text(&self, entry: &str) -> Result<String> {
+        let bytes = self.read(entry)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<usize> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .context("truncated zip archive")
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<usize> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .context("truncated zip archive")
+}
+
+/// A piece of XML: a start (or empty-element) tag, an end tag or text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Xml<'a> {
+    Open { name: &'a str, attrs: &'a str, empty: bool },
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Minimal pull reader for the well-formed XML inside Office files
+///
+/// Declarations, comments and processing instructions are skipped, and text is
+/// returned still escaped (see `unescape`).
+struct XmlReader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for XmlReader<'a> {
+    type Item = Xml<'a>;
+
+    fn next(&mut self) -> Option<Xml<'a>> {
+        loop {
+            let rest = self.src.get(self.pos..).filter(|r| !r.is_empty())?;
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                return Some(Xml::Text(&rest[..end]));
+            }
+
+            let end = rest.find('>')?;
+            let tag = &rest[1..end];
+            self.pos += end + 1;
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Xml::Close(name.trim()));
+            }
+            let empty = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            return Some(Xml::Open { name, attrs, empty });
+        }
+    }
+}
+
+/// Value of an attribute in a tag's attribute text (still escaped)
+fn attr<'a>(attrs: &'a str, key: &str) -> Option<&'a str> {
+    let mut search = attrs;
+    while let Some(found) = search.find(key) {
+        let before = &search[..found];
+        let after = &search[found + key.len()..];
+        search = after;
+        if !before.is_empty() && !before.ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(after) = after.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|&c| c == '"' || c == '\'')?;
+        let value = &after[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+/// Decode XML character and entity references
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';').filter(|&s| s <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed zip archive holding `files`
+    fn stored_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in files {
+            let local = data.len() as u32;
+            data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            data.extend_from_slice(&[0; 22]);
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(content.as_bytes());
+
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&[0; 16]);
+            directory.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&local.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = data.len() as u32;
+        data.extend_from_slice(&directory);
+        data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn reads_zip_entries() {
+        let zip = Zip::parse(stored_zip(&[("a.xml", "<a/>"), ("dir/b.xml", "hello")])).expect("parse");
+        assert_eq!(zip.read_text("a.xml").unwrap(), "<a/>");
+        assert_eq!(zip.read_text("dir/b.xml").unwrap(), "hello");
+        assert!(zip.read_text("missing.xml").is_err());
+        assert!(Zip::parse(b"not a zip".to_vec()).is_err());
+    }
+
+    #[test]
+    fn rejects_zip64() {
+        let mut data = stored_zip(&[("a.xml", "<a/>")]);
+        let eocd = data.len() - 22;
+        data[eocd + 8..eocd + 12].copy_from_slice(&[0xff; 4]);
+        let error = Zip::parse(data).err().expect("ZIP64 accepted");
+        assert!(error.to_string().contains("ZIP64"));
+    }
+
+    #[test]
+    fn column_index_and_name() {
+        assert_eq!(column_index("A1"), Some(0));
+        assert_eq!(column_index("c7"), Some(2));
+        assert_eq!(column_index("AB3"), Some(27));
+        assert_eq!(column_index("XFD1048576"), Some(16_383));
+        assert_eq!(column_index("12"), None);
+        assert_eq!(column_index(&"Z".repeat(40)), None);
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(16_383), "XFD");
+    }
+
+    #[test]
+    fn reads_xml() {
+        let events: Vec<Xml> = XmlReader::new("<?xml version=\"1.0\"?><a x=\"1\"><b/>t</a>").collect();
+        assert_eq!(
+            events,
+            vec![
+                Xml::Open { name: "a", attrs: "x=\"1\"", empty: false },
+                Xml::Open { name: "b", attrs: "", empty: true },
+                Xml::Text("t"),
+                Xml::Close("a"),
+            ]
+        );
+        assert_eq!(attr("w:val=\"Heading1\" r:id='x'", "r:id"), Some("x"));
+        assert_eq!(attr("xw:val=\"1\"", "w:val"), None);
+        assert_eq!(unescape("a &amp; b &lt;c&gt; &#x41;&#66; &bogus;"), "a & b <c> AB &bogus;");
+    }
+
+    #[test]
+    fn reads_shared_strings() {
+        let xml = "<sst><si><t>Plain</t></si><si/><si><r><t>Ri</t></r><r><t>ch</t></r><rPh><t>skip</t></rPh></si></sst>";
+        assert_eq!(shared_strings(xml), vec!["Plain", "", "Rich"]);
+    }
+
+    #[test]
+    fn reads_docx_text() {
+        let xml = concat!(
+            "<w:body>",
+            "<w:p><w:pPr><w:pStyle w:val=\"Heading2\"/></w:pPr><w:r><w:t>Title</w:t></w:r></w:p>",
+            "<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Bold</w:t></w:r><w:r><w:t xml:space=\"preserve\"> &amp; plain</w:t></w:r></w:p>",
+            "<w:tbl><w:tr><w:tc><w:p><w:r><w:t>A</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>B</w:t></w:r></w:p></w:tc></w:tr></w:tbl>",
+            "</w:body>"
+        );
+        let span = |text: &str, bold| Span { text: text.to_string(), bold, ..Span::default() };
+        assert_eq!(
+            docx_blocks(xml),
+            vec![
+                Block::Heading(2, vec![span("Title", false)]),
+                Block::Paragraph(vec![span("Bold", true), span(" & plain", false)]),
+                Block::Table { rows: vec![vec!["A".to_string(), "B".to_string()]], header: false },
+            ]
+        );
+    }
+}
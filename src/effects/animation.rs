@@ -0,0 +1,310 @@
+//! Animation engine for the bar's modules
+//!
+//! Keeps per-module state between frames so changes ease in instead of jumping:
+//! modules slide to their new place when a neighbor appears, disappears or
+//! changes width, newly shown modules fade in, a module whose text changed
+//! crossfades from how it looked before, and graphs interpolate toward new
+//! samples. State advances once per painted frame; while anything is still
+//! moving the bar repaints at a faster rate than its 100ms timer (see
+//! `is_running`).
+//!
+//! Fades work on the renderer's 32-bit back buffer, so they apply to every
+//! module without knowing how it draws itself.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use crate::utils::{Animator, Rect};
+
+/// The back buffer's pixels (top-down BGRA)
+pub struct Pixels<'a> {
+    pub data: &'a mut [u32],
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Pixels<'_> {
+    /// Part of `rect` inside the buffer
+    fn clip(&self, rect: Rect) -> Option<Rect> {
+        let left = rect.x.max(0);
+        let top = rect.y.max(0);
+        let right = rect.right().min(self.width);
+        let bottom = rect.bottom().min(self.height);
+        (right > left && bottom > top).then(|| Rect::new(left, top, right - left, bottom - top))
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+/// A module's pixels as drawn in an earlier frame
+struct Snapshot {
+    rect: Rect,
+    pixels: Vec<u32>,
+}
+
+impl Snapshot {
+    fn capture(buffer: &Pixels, rect: Rect) -> Option<Self> {
+        let rect = buffer.clip(rect)?;
+        let mut pixels = Vec::with_capacity((rect.width * rect.height) as usize);
+        for y in rect.y..rect.bottom() {
+            let start = buffer.index(rect.x, y);
+            pixels.extend_from_slice(&buffer.data[start..start + rect.width as usize]);
+        }
+        Some(Self { rect, pixels })
+    }
+
+    fn get(&self, x: i32, y: i32) -> Option<u32> {
+        if !self.rect.contains(x, y) {
+            return None;
+        }
+        let index = (y - self.rect.y) * self.rect.width + (x - self.rect.x);
+        self.pixels.get(index as usize).copied()
+    }
+}
+
+/// Crossfade from a module's previous look
+struct Fade {
+    from: Snapshot,
+    /// Opacity of the previous look, 1 to 0
+    opacity: Animator,
+}
+
+/// Interpolation between two sets of graph samples
+struct GraphTween {
+    from: Vec<f32>,
+    to: Vec<f32>,
+    progress: Animator,
+}
+
+impl GraphTween {
+    fn current(&self) -> Vec<f32> {
+        if self.from.len() != self.to.len() {
+            return self.to.clone();
+        }
+        let t = self.progress.value();
+        self.from
+            .iter()
+            .zip(&self.to)
+            .map(|(from, to)| from + (to - from) * t)
+            .collect()
+    }
+}
+
+/// Per-module animation state, owned by the renderer
+#[derive(Default)]
+pub struct Animations {
+    /// Length of each animation; `None` while animations are off (reduced motion)
+    duration_ms: Option<u32>,
+    last_frame: Option<Instant>,
+    /// Nothing has been drawn yet, so nothing counts as newly shown
+    first_frame: bool,
+    /// Layout position each module is moving toward, and where it is now
+    positions: HashMap<String, (i32, Animator)>,
+    /// Fade-in of newly shown modules, 0 to 1
+    appearing: HashMap<String, Animator>,
+    fades: HashMap<String, Fade>,
+    graphs: HashMap<(String, &'static str), GraphTween>,
+    /// Text and pixels of each module as last drawn
+    drawn: HashMap<String, (String, Snapshot)>,
+    /// Modules drawn this frame
+    seen: HashSet<String>,
+    /// Modules whose graphs interpolate this frame, which replaces their crossfade
+    graphed: HashSet<String>,
+    /// The back buffer before any module was drawn, for fading modules in
+    background: Vec<u32>,
+}
+
+impl Animations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a frame, advancing running animations by the time since the last
+    /// one. `duration_ms` is `None` to draw everything at rest; `buffer` holds the
+    /// bar's background, before modules are drawn.
+    pub fn begin_frame(&mut self, duration_ms: Option<u32>, buffer: Option<&Pixels>) {
+        let now = Instant::now();
+        let delta = self
+            .last_frame
+            .map(|last| now.duration_since(last).as_millis().min(u32::MAX as u128) as u32)
+            .unwrap_or(0);
+        if duration_ms.is_none() {
+            // Turning animations off drops whatever was in flight
+            *self = Self::default();
+            return;
+        }
+        self.last_frame = Some(now);
+        self.first_frame = self.duration_ms.is_none();
+        self.duration_ms = duration_ms;
+
+        for (_, animator) in self.positions.values_mut() {
+            animator.update(delta);
+        }
+        for animator in self.appearing.values_mut() {
+            animator.update(delta);
+        }
+        for fade in self.fades.values_mut() {
+            fade.opacity.update(delta);
+        }
+        for graph in self.graphs.values_mut() {
+            graph.progress.update(delta);
+        }
+        self.appearing.retain(|_, animator| animator.is_running());
+        self.fades.retain(|_, fade| fade.opacity.is_running());
+
+        self.seen.clear();
+        self.graphed.clear();
+        self.background.clear();
+        if let Some(buffer) = buffer {
+            self.background.extend_from_slice(buffer.data);
+        }
+    }
+
+    /// Where to draw a module this frame: `target` is its place in the layout,
+    /// which it eases toward when that moved since the last frame
+    pub fn slide(&mut self, id: &str, target: i32) -> i32 {
+        let Some(duration) = self.duration_ms else {
+            return target;
+        };
+        if !self.drawn.contains_key(id) {
+            // Newly shown modules start in place and fade in instead
+            self.positions
+                .insert(id.to_string(), (target, Animator::new(target as f32)));
+            return target;
+        }
+        let (to, animator) = self
+            .positions
+            .entry(id.to_string())
+            .or_insert_with(|| (target, Animator::new(target as f32)));
+        if *to != target {
+            *to = target;
+            animator.animate_to(target as f32, duration);
+        }
+        animator.value().round() as i32
+    }
+
+    /// Graph samples to draw for one series of a module, interpolated from the
+    /// previous samples toward `values`
+    pub fn graph(&mut self, id: &str, series: &'static str, values: Vec<f32>) -> Vec<f32> {
+        let Some(duration) = self.duration_ms else {
+            return values;
+        };
+        self.graphed.insert(id.to_string());
+        let key = (id.to_string(), series);
+        let Some(tween) = self.graphs.get_mut(&key) else {
+            self.graphs.insert(
+                key,
+                GraphTween {
+                    from: values.clone(),
+                    to: values.clone(),
+                    progress: Animator::new(1.0),
+                },
+            );
+            return values;
+        };
+        if tween.to != values {
+            tween.from = tween.current();
+            tween.to = values;
+            tween.progress = Animator::new(0.0);
+            tween.progress.animate_to(1.0, duration);
+        }
+        tween.current()
+    }
+
+    /// Keep a module that wasn't drawn this frame as it was, animations and all
+    pub fn keep_module(&mut self, id: &str) {
+        self.seen.insert(id.to_string());
+    }
+
+    /// Finish drawing a module at `rect`: fade it in if it was just shown,
+    /// crossfade from its previous look if its text changed, and remember how it
+    /// looks now
+    pub fn finish_module(&mut self, id: &str, text: &str, rect: Rect, buffer: Option<&mut Pixels>) {
+        self.seen.insert(id.to_string());
+        let (Some(duration), Some(buffer)) = (self.duration_ms, buffer) else {
+            return;
+        };
+
+        match self.drawn.remove(id) {
+            None if !self.first_frame => {
+                let mut animator = Animator::new(0.0);
+                animator.animate_to(1.0, duration);
+                self.appearing.insert(id.to_string(), animator);
+            }
+            Some((previous, from)) if previous != text && !self.graphed.contains(id) => {
+                let mut opacity = Animator::new(1.0);
+                opacity.animate_to(0.0, duration);
+                self.fades.insert(id.to_string(), Fade { from, opacity });
+            }
+            _ => {}
+        }
+
+        if let Some(clip) = buffer.clip(rect) {
+            if let Some(animator) = self.appearing.get(id) {
+                if self.background.len() == buffer.data.len() {
+                    let shown = animator.value();
+                    for y in clip.y..clip.bottom() {
+                        for x in clip.x..clip.right() {
+                            let i = buffer.index(x, y);
+                            buffer.data[i] = mix(self.background[i], buffer.data[i], shown);
+                        }
+                    }
+                }
+            }
+            if let Some(fade) = self.fades.get(id) {
+                // Line the previous look up by its center, so text that got
+                // wider or narrower fades in place
+                let dx = (rect.x + rect.width / 2) - (fade.from.rect.x + fade.from.rect.width / 2);
+                let dy = (rect.y + rect.height / 2) - (fade.from.rect.y + fade.from.rect.height / 2);
+                let opacity = fade.opacity.value();
+                for y in clip.y..clip.bottom() {
+                    for x in clip.x..clip.right() {
+                        if let Some(old) = fade.from.get(x - dx, y - dy) {
+                            let i = buffer.index(x, y);
+                            buffer.data[i] = mix(buffer.data[i], old, opacity);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(snapshot) = Snapshot::capture(buffer, rect) {
+            self.drawn.insert(id.to_string(), (text.to_string(), snapshot));
+        }
+    }
+
+    /// End a frame, forgetting modules that weren't drawn so they fade in again
+    /// when shown
+    pub fn end_frame(&mut self) {
+        let seen = &self.seen;
+        self.drawn.retain(|id, _| seen.contains(id));
+        self.positions.retain(|id, _| seen.contains(id));
+        self.appearing.retain(|id, _| seen.contains(id));
+        self.fades.retain(|id, _| seen.contains(id));
+        self.graphs.retain(|(id, _), _| seen.contains(id));
+        self.first_frame = false;
+    }
+
+    /// Whether anything is still moving, so the bar should keep repainting
+    pub fn is_running(&self) -> bool {
+        !self.appearing.is_empty()
+            || !self.fades.is_empty()
+            || self.positions.values().any(|(_, animator)| animator.is_running())
+            || self.graphs.values().any(|graph| graph.progress.is_running())
+    }
+}
+
+/// Mix two back buffer pixels, `t` of the way from `a` to `b`. GDI leaves alpha
+/// at 0 on what it draws, which counts as opaque here.
+fn mix(a: u32, b: u32, t: f32) -> u32 {
+    let opaque = |p: u32| if p >> 24 == 0 { p | 0xFF00_0000 } else { p };
+    let (a, b) = (opaque(a), opaque(b));
+    let weight = (t.clamp(0.0, 1.0) * 256.0) as u32;
+    [0, 8, 16, 24].iter().fold(0, |out, shift| {
+        let ca = (a >> shift) & 0xFF;
+        let cb = (b >> shift) & 0xFF;
+        out | (((ca * (256 - weight) + cb * weight) >> 8) << shift)
+    })
+}
@@ -1,11 +1,15 @@
 //! Blur and transparency effects for Windows 11
 //!
-//! Provides Mica, Acrylic, and other backdrop effects.
+//! Provides Mica, Acrylic, and other backdrop effects, and the animation engine
+//! for the bar's contents (`animation`).
 
 #![allow(dead_code, unused_unsafe)]
 
+pub mod animation;
+
 use anyhow::Result;
 use log::{debug, info};
+use serde::{Deserialize, Serialize};
 use windows::Win32::Foundation::HWND;
 use windows::Win32::Graphics::Dwm::{
     DwmExtendFrameIntoClientArea, DwmSetWindowAttribute, DWMWA_SYSTEMBACKDROP_TYPE,
@@ -15,13 +19,14 @@ use windows::Win32::Graphics::Dwm::{
 use windows::Win32::UI::Controls::MARGINS;
 
 /// Backdrop types available in Windows 11
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum BackdropType {
     /// No backdrop effect
     None = 1,
     /// Mica effect (recommended for main windows)
     Mica = 2,
     /// Acrylic effect (translucent blur)
+    #[default]
     Acrylic = 3,
     /// Mica Alt (more prominent)
     MicaAlt = 4,
@@ -57,6 +62,12 @@ pub struct EffectsManager;
 impl EffectsManager {
     /// Apply backdrop effect to window
     pub fn set_backdrop(hwnd: HWND, backdrop: BackdropType) -> Result<()> {
+        Self::try_set_backdrop(hwnd, backdrop);
+        Ok(())
+    }
+
+    /// Apply a backdrop effect, returning whether Windows supports it (11 22H2+)
+    fn try_set_backdrop(hwnd: HWND, backdrop: BackdropType) -> bool {
         unsafe {
             let backdrop_value: i32 = backdrop as i32;
 
@@ -72,6 +83,31 @@ impl EffectsManager {
             } else {
                 debug!("Failed to apply backdrop (may not be supported)");
             }
+            result.is_ok()
+        }
+    }
+
+    /// Let the backdrop show through the parts of the client area painted with
+    /// alpha below 255, or go back to an opaque client area. Where the system
+    /// backdrop isn't available, the older blur-behind accent is used instead.
+    pub fn set_translucent(hwnd: HWND, translucent: bool, backdrop: BackdropType) -> Result<()> {
+        let extent = if translucent { -1 } else { 0 };
+        let margins = MARGINS {
+            cxLeftWidth: extent,
+            cxRightWidth: extent,
+            cyTopHeight: extent,
+            cyBottomHeight: extent,
+        };
+        unsafe {
+            DwmExtendFrameIntoClientArea(hwnd, &margins)?;
+        }
+
+        let backdrop = if translucent { backdrop } else { BackdropType::None };
+        let supported = Self::try_set_backdrop(hwnd, backdrop);
+        if translucent && !supported && backdrop != BackdropType::None {
+            legacy::apply_blur(hwnd, legacy::AccentState::EnableBlurBehind, 0)?;
+        } else if !translucent {
+            legacy::apply_blur(hwnd, legacy::AccentState::Disabled, 0)?;
         }
         Ok(())
     }
@@ -191,9 +227,17 @@ pub mod legacy {
     }
 
     /// Apply blur effect using SetWindowCompositionAttribute
-    pub fn apply_blur(_hwnd: HWND, accent: AccentState, color: u32) -> Result<()> {
-        // This uses undocumented API and may not work on all Windows versions
-        // The modern DwmSetWindowAttribute approach is preferred
+    ///
+    /// Undocumented, but the only way to blur behind a window before Windows 11
+    /// 22H2; the modern DwmSetWindowAttribute approach is preferred where it works.
+    /// `color` is the tint as 0xAABBGGRR.
+    pub fn apply_blur(hwnd: HWND, accent: AccentState, color: u32) -> Result<()> {
+        use windows::core::{s, w};
+        use windows::Win32::Foundation::BOOL;
+        use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+
+        type SetWindowCompositionAttribute =
+            unsafe extern "system" fn(HWND, *mut WindowCompositionAttribData) -> BOOL;
 
         let mut policy = AccentPolicy {
             accent_state: accent as i32,
@@ -202,16 +246,22 @@ pub mod legacy {
             animation_id: 0,
         };
 
-        let data = WindowCompositionAttribData {
+        let mut data = WindowCompositionAttribData {
             attrib: 19, // WCA_ACCENT_POLICY
             data: &mut policy,
             size: std::mem::size_of::<AccentPolicy>(),
         };
 
-        // This would require loading user32.dll and finding SetWindowCompositionAttribute
-        // For now, we rely on the DWM methods which are more reliable
-        let _ = data;
-
+        unsafe {
+            let user32 = GetModuleHandleW(w!("user32.dll"))?;
+            let Some(proc) = GetProcAddress(user32, s!("SetWindowCompositionAttribute")) else {
+                return Err(anyhow::anyhow!("SetWindowCompositionAttribute is not available"));
+            };
+            let set_attribute: SetWindowCompositionAttribute = std::mem::transmute(proc);
+            if !set_attribute(hwnd, &mut data).as_bool() {
+                return Err(anyhow::anyhow!("SetWindowCompositionAttribute failed"));
+            }
+        }
         Ok(())
     }
 }
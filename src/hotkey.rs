@@ -1,6 +1,10 @@
 //! Hotkey system for TopBar
 //!
-//! Handles global hotkey registration and processing.
+//! Handles global hotkey registration and processing. Besides the named hotkeys
+//! in `[hotkeys]` (and its snap/tile tables), `[hotkeys.keymap]` binds any key
+//! chord to an action spec from `actions`. Everything is registered by
+//! `register_all`, which reports bad chords, unknown actions and chords bound
+//! twice, and is run again when the config is reloaded.
 
 #![allow(dead_code)]
 
@@ -13,17 +17,44 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{
 };
 
 /// Hotkey action
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HotkeyAction {
     ToggleBar,
     OpenMenu,
     QuickSearch,
+    CommandPalette,
     ToggleTheme,
+    /// Move the keyboard to the bar's modules
+    FocusBar,
     NextModule,
     PreviousModule,
     Refresh,
     Settings,
     Quit,
+    /// Snap the foreground window
+    SnapWindow(crate::window::snap::SnapLayout),
+    /// Tile all windows on the focused window's monitor
+    TileWindows(crate::window::tile::TileLayout),
+    /// Run an action spec from the keymap, e.g. "toggle_module:clock"
+    Action(String),
+}
+
+impl HotkeyAction {
+    /// The action spec that does the same thing, if there is one
+    pub fn spec(&self) -> Option<String> {
+        match self {
+            Self::ToggleBar => Some("toggle_bar".into()),
+            Self::OpenMenu => Some("open_menu".into()),
+            Self::QuickSearch => Some("quick_search".into()),
+            Self::CommandPalette => Some("command_palette".into()),
+            Self::ToggleTheme => Some("toggle_theme".into()),
+            Self::FocusBar => Some("focus_bar".into()),
+            Self::SnapWindow(layout) => Some(format!("snap:{}", layout.key())),
+            Self::TileWindows(layout) => Some(format!("tile:{}", layout.key())),
+            Self::Action(spec) => Some(spec.clone()),
+            _ => None,
+        }
+    }
 }
 
 /// Parsed hotkey
@@ -109,6 +140,17 @@ impl Hotkey {
             "F10" => Some(0x79),
             "F11" => Some(0x7A),
             "F12" => Some(0x7B),
+            ";" => Some(0xBA),
+            "=" => Some(0xBB),
+            "," => Some(0xBC),
+            "-" => Some(0xBD),
+            "." => Some(0xBE),
+            "/" => Some(0xBF),
+            "`" => Some(0xC0),
+            "[" => Some(0xDB),
+            "\\" => Some(0xDC),
+            "]" => Some(0xDD),
+            "'" => Some(0xDE),
             _ => None,
         }
     }
@@ -187,7 +229,7 @@ impl HotkeyManager {
     /// Handle WM_HOTKEY message
     pub fn handle_hotkey(&self, wparam: WPARAM) -> Option<HotkeyAction> {
         let id = wparam.0 as i32;
-        self.hotkeys.get(&id).map(|h| h.action)
+        self.hotkeys.get(&id).map(|h| h.action.clone())
     }
 
     /// Get registered hotkeys
@@ -208,9 +250,9 @@ use parking_lot::Mutex as PLMutex;
 
 static GLOBAL_HOTKEY_MAP: OnceCell<PLMutex<HashMap<i32, HotkeyAction>>> = OnceCell::new();
 
-/// Set the global mapping of hotkey ids to actions (only first set wins)
+/// Set the global mapping of hotkey ids to actions, replacing any previous one
 pub fn set_global_hotkey_map(map: HashMap<i32, HotkeyAction>) {
-    let _ = GLOBAL_HOTKEY_MAP.set(PLMutex::new(map));
+    *GLOBAL_HOTKEY_MAP.get_or_init(|| PLMutex::new(HashMap::new())).lock() = map;
 }
 
 /// Get the global hotkey map (if set)
@@ -244,3 +286,215 @@ pub fn register_default_hotkeys(manager: &mut HotkeyManager, config: &crate::con
         }
     }
 }
+
+// Fixed ids for core hotkeys (keeps behavior deterministic)
+const HK_TOGGLE_BAR: i32 = 6000;
+const HK_OPEN_MENU: i32 = 6001;
+const HK_QUICK_SEARCH: i32 = 6002;
+const HK_TOGGLE_THEME: i32 = 6003;
+const HK_COMMAND_PALETTE: i32 = 6004;
+const HK_FOCUS_BAR: i32 = 6005;
+// Window snapping hotkeys take consecutive ids, tiling follows
+const HK_SNAP_BASE: i32 = 6010;
+const HK_TILE_BASE: i32 = 6020;
+// Keymap chords, in config order
+const HK_KEYMAP_BASE: i32 = 6100;
+
+/// Windows error when another program already holds a chord
+const ERROR_HOTKEY_ALREADY_REGISTERED: u32 = 1409;
+
+/// Named hotkeys as (id, chord if set, action, config key)
+fn named_hotkeys(config: &crate::config::Config) -> Vec<(i32, Option<&String>, HotkeyAction, String)> {
+    use crate::window::snap::SnapLayout;
+    use crate::window::tile::TileLayout;
+
+    let hotkeys = &config.hotkeys;
+    let mut named: Vec<(i32, Option<&String>, HotkeyAction, String)> = vec![
+        (HK_TOGGLE_BAR, hotkeys.toggle_bar.as_ref(), HotkeyAction::ToggleBar, "toggle_bar".into()),
+        (HK_OPEN_MENU, hotkeys.open_menu.as_ref(), HotkeyAction::OpenMenu, "open_menu".into()),
+        (HK_TOGGLE_THEME, hotkeys.toggle_theme.as_ref(), HotkeyAction::ToggleTheme, "toggle_theme".into()),
+        (
+            HK_COMMAND_PALETTE,
+            hotkeys.command_palette.as_ref(),
+            HotkeyAction::CommandPalette,
+            "command_palette".into(),
+        ),
+        (HK_FOCUS_BAR, hotkeys.focus_bar.as_ref(), HotkeyAction::FocusBar, "focus_bar".into()),
+    ];
+    // Only register quick-search hotkey if search is enabled
+    if config.search.enabled {
+        named.push((HK_QUICK_SEARCH, hotkeys.quick_search.as_ref(), HotkeyAction::QuickSearch, "quick_search".into()));
+    }
+    for (i, layout) in SnapLayout::ALL.iter().enumerate() {
+        named.push((
+            HK_SNAP_BASE + i as i32,
+            hotkeys.snap.get(*layout),
+            HotkeyAction::SnapWindow(*layout),
+            format!("snap.{}", layout.key()),
+        ));
+    }
+    for (i, layout) in TileLayout::ALL.iter().enumerate() {
+        named.push((
+            HK_TILE_BASE + i as i32,
+            hotkeys.tile.get(*layout),
+            HotkeyAction::TileWindows(*layout),
+            format!("tile.{}", layout.key()),
+        ));
+    }
+    named
+}
+
+/// Register every configured hotkey, replacing the previous registrations.
+///
+/// Returns the problems worth telling the user about: chords that don't parse,
+/// keymap entries naming unknown actions, chords bound to two things (the first
+/// binding wins) and chords another program has already taken.
+pub fn register_all(hwnd: HWND, config: &crate::config::Config) -> Vec<String> {
+    // Drop whatever the last config registered
+    if let Some(map) = global_hotkey_map() {
+        for id in map.lock().keys() {
+            unsafe {
+                let _ = UnregisterHotKey(hwnd, *id);
+            }
+        }
+    }
+
+    let hotkeys = &config.hotkeys;
+    // Every chord to register as (id, chord, action, where it came from)
+    let mut bindings: Vec<(i32, &String, HotkeyAction, String)> = named_hotkeys(config)
+        .into_iter()
+        .filter_map(|(id, chord, action, key)| Some((id, chord?, action, format!("hotkeys.{}", key))))
+        .collect();
+
+    let mut problems = Vec::new();
+    for (i, (chord, spec)) in hotkeys.keymap.iter().enumerate() {
+        if crate::actions::registry().lock().contains(spec) {
+            bindings.push((
+                HK_KEYMAP_BASE + i as i32,
+                chord,
+                HotkeyAction::Action(spec.clone()),
+                format!("keymap \"{}\"", spec),
+            ));
+        } else {
+            problems.push(format!("{}: unknown action \"{}\"", chord, spec));
+        }
+    }
+
+    let mut map = HashMap::new();
+    let mut taken: HashMap<(u32, u32), String> = HashMap::new();
+    for (id, chord, action, source) in bindings {
+        let Some(hk) = Hotkey::parse(chord, action) else {
+            problems.push(format!("{}: \"{}\" is not a key chord", source, chord));
+            continue;
+        };
+        if let Some(first) = taken.get(&(hk.modifiers, hk.key)) {
+            problems.push(format!("{} is bound to both {} and {}; keeping {}", chord, first, source, first));
+            continue;
+        }
+        taken.insert((hk.modifiers, hk.key), source.clone());
+
+        unsafe {
+            if RegisterHotKey(hwnd, id, HOT_KEY_MODIFIERS(hk.modifiers), hk.key).is_ok() {
+                info!("Registered hotkey '{}' -> id={} modifiers={} key=0x{:X}", chord, id, hk.modifiers, hk.key);
+                map.insert(id, hk.action);
+            } else {
+                let err = windows::Win32::Foundation::GetLastError();
+                if err.0 == ERROR_HOTKEY_ALREADY_REGISTERED {
+                    problems.push(format!("{} ({}) is already used by another program", chord, source));
+                } else {
+                    problems.push(format!("{} ({}) could not be registered (error {})", chord, source, err.0));
+                }
+            }
+        }
+    }
+
+    info!("Global hotkey map configured: {:?}", map);
+    set_global_hotkey_map(map);
+    for problem in &problems {
+        warn!("Hotkey: {}", problem);
+    }
+    problems
+}
+
+/// Chords bound to action specs, for showing next to actions (e.g. in the command palette)
+///
+/// Named hotkeys come first, then the keymap; an action bound twice shows its first chord.
+pub fn bound_chords(config: &crate::config::Config) -> HashMap<String, String> {
+    let mut chords = HashMap::new();
+    for (_, chord, action, _) in named_hotkeys(config) {
+        if let (Some(chord), Some(spec)) = (chord, action.spec()) {
+            chords.entry(spec).or_insert_with(|| chord.clone());
+        }
+    }
+    for (chord, spec) in &config.hotkeys.keymap {
+        chords.entry(spec.trim().to_string()).or_insert_with(|| chord.clone());
+    }
+    chords
+}
+
+/// Register all hotkeys and raise an alert on the app menu if any of them failed
+pub fn register_and_report(hwnd: HWND, config: &crate::config::Config) {
+    let problems = register_all(hwnd, config);
+    if let Some(first) = problems.first() {
+        let text = match problems.len() {
+            1 => first.clone(),
+            n => format!("{} (and {} more; see the log)", first, n - 1),
+        };
+        crate::attention::request(
+            config,
+            crate::attention::AttentionRequest::new(
+                "app_menu",
+                crate::attention::AttentionLevel::Warning,
+                "Hotkey problem",
+                &text,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Option<(u32, u32)> {
+        Hotkey::parse(s, HotkeyAction::Refresh).map(|hk| (hk.modifiers, hk.key))
+    }
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        assert_eq!(parse("Alt+T"), Some((MOD_ALT.0, 'T' as u32)));
+        assert_eq!(parse("ctrl + shift + s"), Some((MOD_CONTROL.0 | MOD_SHIFT.0, 'S' as u32)));
+        assert_eq!(parse("Win+Alt+Space"), Some((MOD_WIN.0 | MOD_ALT.0, 0x20)));
+        assert_eq!(parse("Control+F12"), Some((MOD_CONTROL.0, 0x7B)));
+        assert_eq!(parse("Alt+`"), Some((MOD_ALT.0, 0xC0)));
+        assert_eq!(parse("7"), Some((0, '7' as u32)));
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("Alt+"), None);
+        assert_eq!(parse("Hyper+T"), None);
+        assert_eq!(parse("Alt+F13"), None);
+        assert_eq!(parse("Alt+Shift"), None);
+    }
+
+    #[test]
+    fn actions_map_to_specs() {
+        use crate::window::tile::TileLayout;
+        assert_eq!(HotkeyAction::ToggleBar.spec().as_deref(), Some("toggle_bar"));
+        assert_eq!(HotkeyAction::TileWindows(TileLayout::MainStack).spec().as_deref(), Some("tile:main_stack"));
+        assert_eq!(HotkeyAction::Action("lock".into()).spec().as_deref(), Some("lock"));
+        assert_eq!(HotkeyAction::Quit.spec(), None);
+    }
+
+    #[test]
+    fn first_chord_wins_for_an_action() {
+        let mut config = crate::config::Config::default();
+        config.hotkeys.keymap.insert("Ctrl+Alt+T".into(), "toggle_bar".into());
+        config.hotkeys.keymap.insert("Ctrl+Alt+L".into(), " lock ".into());
+        let chords = bound_chords(&config);
+        assert_eq!(chords.get("toggle_bar").map(String::as_str), Some("Alt+T"));
+        assert_eq!(chords.get("lock").map(String::as_str), Some("Ctrl+Alt+L"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Translations for the bar's labels, menus, tooltips and popups
+//!
+//! Strings are looked up by their English text, so anything without a
+//! translation shows as written. A locale is a TOML table of English text to
+//! translated text; German, Spanish and French are built in, and a file at
+//! `%APPDATA%\topbar\locales\<language>.toml` adds to or overrides them (or
+//! adds a new language). `[general] language` picks the locale: a code such
+//! as "de" or "pt-BR", or "auto" to follow the Windows locale. A `{}` in a
+//! string stands for a value filled in after translation.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+/// Locales compiled into the binary: (language code, TOML)
+const BUILT_IN: &[(&str, &str)] = &[
+    ("de", include_str!("../resources/locales/de.toml")),
+    ("es", include_str!("../resources/locales/es.toml")),
+    ("fr", include_str!("../resources/locales/fr.toml")),
+];
+
+/// Translations for the current language, by English text
+static STRINGS: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// `text` in the current language, or as written when there's no translation
+pub fn tr(text: &str) -> String {
+    STRINGS.read().get(text).cloned().unwrap_or_else(|| text.to_string())
+}
+
+/// Switch to `language` ("auto" for the Windows locale)
+pub fn set_language(language: &str) {
+    let language = if language.trim().is_empty() || language.eq_ignore_ascii_case("auto") {
+        system_language()
+    } else {
+        language.trim().to_string()
+    };
+
+    // "pt-BR" falls back to "pt", with the regional file's strings on top
+    let mut strings = HashMap::new();
+    let primary = language.split(['-', '_']).next().unwrap_or_default().to_string();
+    let mut codes = vec![primary];
+    if !codes.contains(&language) {
+        codes.push(language.clone());
+    }
+    for code in &codes {
+        load_locale(code, &mut strings);
+    }
+
+    info!("Interface language {} ({} translated strings)", language, strings.len());
+    *STRINGS.write() = strings;
+}
+
+/// Add the built-in and user strings for `code` to `strings`
+fn load_locale(code: &str, strings: &mut HashMap<String, String>) {
+    if let Some((_, toml)) = BUILT_IN.iter().find(|(c, _)| c.eq_ignore_ascii_case(code)) {
+        merge(toml, code, strings);
+    }
+    let path = user_locale_path(code);
+    if let Ok(toml) = std::fs::read_to_string(&path) {
+        debug!("Loading locale file {:?}", path);
+        merge(&toml, code, strings);
+    }
+}
+
+fn merge(toml: &str, code: &str, strings: &mut HashMap<String, String>) {
+    match toml::from_str::<HashMap<String, String>>(toml) {
+        Ok(table) => strings.extend(table.into_iter().filter(|(_, v)| !v.is_empty())),
+        Err(e) => warn!("Ignoring locale {}: {}", code, e),
+    }
+}
+
+fn user_locale_path(code: &str) -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("topbar")
+        .join("locales")
+        .join(format!("{}.toml", code))
+}
+
+/// The Windows locale as a tag like "de-DE"
+fn system_language() -> String {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    if len <= 1 {
+        return "en".to_string();
+    }
+    String::from_utf16_lossy(&buffer[..len as usize - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Calls whose argument at the given position is looked up with `tr`
+    const TRANSLATED: &[(&str, usize)] = &[("tr", 0), ("append_menu_item", 2), ("append_disabled_item", 1)];
+
+    /// The arguments of the call whose `(` is at the start of `source`
+    fn arguments(source: &str) -> Vec<&str> {
+        let mut args = Vec::new();
+        let (mut depth, mut start, mut in_string, mut escaped) = (0, 1, false, false);
+        for (i, c) in source.char_indices().skip(1) {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => {
+                    args.push(&source[start..i]);
+                    break;
+                }
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    args.push(&source[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        args
+    }
+
+    /// `expr` if it's a single string literal
+    fn literal(expr: &str) -> Option<String> {
+        let inner = expr.trim().strip_prefix('"')?.strip_suffix('"')?;
+        if inner.replace("\\\\", "").replace("\\\"", "").contains('"') {
+            return None;
+        }
+        Some(inner.replace("\\\"", "\"").replace("\\\\", "\\"))
+    }
+
+    /// The literals an argument can evaluate to: the literal itself, or both
+    /// branches of `if cond { "a" } else { "b" }`
+    fn literals(expr: &str) -> Vec<String> {
+        let expr = expr.trim();
+        if let Some(text) = literal(expr) {
+            return vec![text];
+        }
+        if !expr.starts_with("if ") {
+            return Vec::new();
+        }
+        expr.split('{')
+            .skip(1)
+            .filter_map(|block| literal(block.split('}').next()?))
+            .collect()
+    }
+
+    fn collect_literals(dir: &Path, found: &mut Vec<(String, String)>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                collect_literals(&path, found);
+                continue;
+            }
+            if path.extension().is_none_or(|e| e != "rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            for &(name, position) in TRANSLATED {
+                let call = format!("{}(", name);
+                for (at, _) in source.match_indices(&call) {
+                    let preceded_by_ident = source[..at]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_');
+                    if preceded_by_ident {
+                        continue;
+                    }
+                    let args = arguments(&source[at + name.len()..]);
+                    for text in args.get(position).map(|arg| literals(arg)).unwrap_or_default() {
+                        found.push((path.display().to_string(), text));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_translated_literal_is_in_each_built_in_locale() {
+        let mut found = Vec::new();
+        collect_literals(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut found);
+        assert!(found.len() > 100, "only found {} literals", found.len());
+
+        for (code, toml) in BUILT_IN {
+            let table: HashMap<String, String> = toml::from_str(toml).unwrap();
+            let missing: Vec<_> = found
+                .iter()
+                .filter(|(_, text)| !table.contains_key(text))
+                .map(|(path, text)| format!("{:?} ({})", text, path))
+                .collect();
+            assert!(missing.is_empty(), "{}.toml is missing:\n{}", code, missing.join("\n"));
+        }
+    }
+
+    #[test]
+    fn finds_literals_in_calls() {
+        assert_eq!(arguments("(menu, ID, \"a, (b)\", true)"), vec!["menu", " ID", " \"a, (b)\"", " true"]);
+        assert_eq!(literals(" \"Say \\\"hi\\\"\""), vec!["Say \"hi\""]);
+        assert_eq!(literals("if on { \"Stop\" } else { \"Start\" }"), vec!["Stop", "Start"]);
+        assert!(literals("&format!(\"{}\", x)").is_empty());
+        assert!(literals("\"a\" + \"b\"").is_empty());
+    }
+}
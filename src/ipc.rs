@@ -0,0 +1,309 @@
+//! Command interface for scripts
+//!
+//! A named pipe (`\\.\pipe\topbar-<user name>`) that takes one command per
+//! connection and answers with one line of JSON. `topbarctl` is the client, but
+//! anything that can write to a pipe (AutoHotkey, PowerShell) works too.
+//!
+//! Commands:
+//! - `get` / `get <module id>...`: the modules' current text, tooltip and metrics
+//! - `watch [seconds] [module id...]`: the same values streamed as one JSON
+//!   object per line (`{"time":...,"modules":{...}}`) until the client hangs up,
+//!   for overlays and dashboards
+//! - `actions`: the actions that can be run, with their descriptions
+//! - anything else is run as an action spec (`toggle_module:clock`, `show_bar`,
+//!   `theme:dark`, `quick_search`, ...)
+//!
+//! Answers are `{"ok":true,"result":...}` or `{"ok":false,"error":"..."}`. Each
+//! client gets its own pipe instance and thread, so a watching client doesn't
+//! hold up commands.
+//! Commands run on the UI thread: the pipe thread hands each one to the bar
+//! window with a `WM_TOPBAR_IPC` message and waits for it to be handled.
+
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::time::Duration;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{CloseHandle, ERROR_ACCESS_DENIED, ERROR_PIPE_CONNECTED, HANDLE, HWND, LPARAM, WPARAM};
+use windows::Win32::Storage::FileSystem::{
+    FlushFileBuffers, ReadFile, WriteFile, FILE_FLAGS_AND_ATTRIBUTES, FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+};
+use windows::Win32::System::Pipes::*;
+use windows::Win32::UI::WindowsAndMessaging::SendMessageW;
+
+use crate::utils::to_wide_string;
+use crate::window::renderer::with_renderer;
+use crate::window::state::get_window_state;
+use crate::window::WM_TOPBAR_IPC;
+
+/// Longest command accepted
+const MAX_REQUEST: usize = 4096;
+/// Shortest interval between `watch` updates, in seconds
+const MIN_WATCH_INTERVAL: f64 = 0.25;
+/// How long to wait before trying again when the pipe can't be created
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Pipe for the current user, so each signed-in user talks to their own bar
+pub fn pipe_name() -> String {
+    format!("\\\\.\\pipe\\topbar-{}", std::env::var("USERNAME").unwrap_or_default())
+}
+
+/// A command handed to the UI thread; `lparam` of `WM_TOPBAR_IPC` points at it
+struct Request {
+    command: String,
+    response: Value,
+}
+
+/// Start serving commands on a background thread
+pub fn start(hwnd: HWND) {
+    // HWND isn't Send; the handle is only used to send the window messages
+    let hwnd = hwnd.0 as isize;
+    let spawned = std::thread::Builder::new().name("ipc".into()).spawn(move || {
+        let hwnd = HWND(hwnd as *mut std::ffi::c_void);
+        serve(hwnd);
+    });
+    if let Err(e) = spawned {
+        warn!("Failed to start the command pipe: {}", e);
+    }
+}
+
+fn serve(hwnd: HWND) {
+    let name = pipe_name();
+    let wide = to_wide_string(&name);
+    let mut listening = false;
+    let mut failing = false;
+    loop {
+        // Until we're listening, the first instance has to be ours: if another
+        // process already holds the name, it would get the commands meant for the bar
+        let first = if listening { FILE_FLAGS_AND_ATTRIBUTES(0) } else { FILE_FLAG_FIRST_PIPE_INSTANCE };
+        // A fresh instance for each client, so a client that's watching doesn't hold up the rest
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                PCWSTR(wide.as_ptr()),
+                PIPE_ACCESS_DUPLEX | first,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                MAX_REQUEST as u32,
+                MAX_REQUEST as u32,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            let error = windows::core::Error::from_win32();
+            // Logged once per outage; the pipe is retried until it can be created
+            if !failing {
+                if !listening && error.code() == ERROR_ACCESS_DENIED.to_hresult() {
+                    warn!(
+                        "Command pipe {} is already in use by another process (another TopBar?); retrying every {}s",
+                        name,
+                        RETRY_DELAY.as_secs()
+                    );
+                } else {
+                    warn!("Failed to create the command pipe: {}; retrying every {}s", error, RETRY_DELAY.as_secs());
+                }
+                failing = true;
+            }
+            std::thread::sleep(RETRY_DELAY);
+            continue;
+        }
+        if !listening || failing {
+            info!("Listening for commands on {}", name);
+            listening = true;
+            failing = false;
+        }
+
+        // A client that connects before ConnectNamedPipe is called shows up as ERROR_PIPE_CONNECTED
+        let connected = match unsafe { ConnectNamedPipe(pipe, None) } {
+            Ok(()) => true,
+            Err(e) => e.code() == ERROR_PIPE_CONNECTED.to_hresult(),
+        };
+        if !connected {
+            unsafe {
+                let _ = CloseHandle(pipe);
+            }
+            std::thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+
+        // Neither handle is Send; both are only passed on to Win32 calls
+        let (hwnd_value, pipe_value) = (hwnd.0 as isize, pipe.0 as isize);
+        let spawned = std::thread::Builder::new().name("ipc client".into()).spawn(move || {
+            let hwnd = HWND(hwnd_value as *mut std::ffi::c_void);
+            let pipe = HANDLE(pipe_value as *mut std::ffi::c_void);
+            serve_client(hwnd, pipe);
+        });
+        if spawned.is_err() {
+            serve_client(hwnd, pipe);
+        }
+    }
+}
+
+/// Answer one client's command, then hang up
+fn serve_client(hwnd: HWND, pipe: HANDLE) {
+    if let Some(command) = read_command(pipe) {
+        debug!("IPC command: {}", command);
+        match command.strip_prefix("watch").filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            Some(args) => watch(hwnd, pipe, args),
+            None => {
+                let response = dispatch(hwnd, command);
+                write_line(pipe, &response);
+            }
+        }
+    }
+    unsafe {
+        let _ = FlushFileBuffers(pipe);
+        let _ = DisconnectNamedPipe(pipe);
+        let _ = CloseHandle(pipe);
+    }
+}
+
+/// Stream module values as one JSON object per line until the client hangs up.
+/// `args` is an optional interval in seconds (default 1) followed by module ids
+/// (default all).
+fn watch(hwnd: HWND, pipe: HANDLE, args: &str) {
+    let mut args = args.split_whitespace().peekable();
+    let interval = args
+        .peek()
+        .and_then(|arg| arg.parse::<f64>().ok())
+        .filter(|secs| secs.is_finite());
+    if interval.is_some() {
+        args.next();
+    }
+    let interval = Duration::from_secs_f64(interval.unwrap_or(1.0).max(MIN_WATCH_INTERVAL));
+    let mut ids: Vec<&str> = args.collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let command = format!("get {}", ids.join(" "));
+
+    loop {
+        let response = dispatch(hwnd, command.clone());
+        let line = if response["ok"].as_bool() == Some(true) {
+            let mut modules = response["result"].clone();
+            // A single module comes back on its own; a stream always keys by id
+            if ids.len() == 1 {
+                modules = json!({ ids[0]: modules });
+            }
+            json!({ "time": chrono::Local::now().to_rfc3339(), "modules": modules })
+        } else {
+            // Unknown module or a stuck bar: report it and stop
+            write_line(pipe, &response);
+            return;
+        };
+        if !write_line(pipe, &line) {
+            return;
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Read up to the first newline (or until the client stops writing)
+fn read_command(pipe: HANDLE) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 512];
+    while !request.contains(&b'\n') && request.len() < MAX_REQUEST {
+        let mut read = 0u32;
+        if unsafe { ReadFile(pipe, Some(&mut buffer), Some(&mut read), None) }.is_err() || read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read as usize]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let command = request.lines().next().unwrap_or("").trim();
+    (!command.is_empty()).then(|| command.to_string())
+}
+
+/// Write a JSON line; false once the client has gone
+fn write_line(pipe: HANDLE, value: &Value) -> bool {
+    let mut line = value.to_string();
+    line.push('\n');
+    unsafe { WriteFile(pipe, Some(line.as_bytes()), None, None).is_ok() }
+}
+
+/// Run a command on the UI thread and wait for its answer
+fn dispatch(hwnd: HWND, command: String) -> Value {
+    let mut request = Request {
+        command,
+        response: Value::Null,
+    };
+    // SendMessage blocks until the window procedure returns, so the pointer stays valid
+    let handled = unsafe { SendMessageW(hwnd, WM_TOPBAR_IPC, WPARAM(0), LPARAM(&mut request as *mut Request as isize)) };
+    if handled.0 == 0 {
+        return json!({ "ok": false, "error": "The bar isn't responding" });
+    }
+    request.response
+}
+
+/// Handle `WM_TOPBAR_IPC` on the UI thread; returns nonzero once answered
+pub fn handle_message(hwnd: HWND, lparam: LPARAM) -> isize {
+    let Some(request) = (unsafe { (lparam.0 as *mut Request).as_mut() }) else {
+        return 0;
+    };
+    request.response = match run(hwnd, &request.command) {
+        Ok(Value::Null) => json!({ "ok": true }),
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(e) => json!({ "ok": false, "error": e.to_string() }),
+    };
+    1
+}
+
+fn run(hwnd: HWND, command: &str) -> anyhow::Result<Value> {
+    let (verb, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let arg = arg.trim();
+    match verb {
+        "get" => module_values(&arg.split_whitespace().collect::<Vec<_>>()),
+        "actions" => {
+            let actions: serde_json::Map<String, Value> = crate::actions::registry()
+                .lock()
+                .list()
+                .into_iter()
+                .map(|(name, description)| (name.to_string(), Value::from(description)))
+                .collect();
+            Ok(Value::Object(actions))
+        }
+        _ => crate::actions::execute(hwnd, command).map(|_| Value::Null),
+    }
+}
+
+/// Current values of one module, or of several (or every) module keyed by id
+fn module_values(ids: &[&str]) -> anyhow::Result<Value> {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    with_renderer(|renderer| {
+        let describe = |module: &dyn crate::modules::Module| {
+            let metrics: serde_json::Map<String, Value> = module
+                .metrics()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), json!(value)))
+                .collect();
+            json!({
+                "name": module.name(),
+                "text": module.display_text(&config),
+                "tooltip": module.tooltip(),
+                "visible": module.is_visible(),
+                "metrics": metrics,
+            })
+        };
+        let registry = &renderer.module_registry;
+        if ids.is_empty() {
+            return Ok(Value::Object(
+                registry
+                    .all()
+                    .into_iter()
+                    .map(|module| (module.id().to_string(), describe(module)))
+                    .collect(),
+            ));
+        }
+        let mut values = serde_json::Map::new();
+        for id in ids {
+            let module = registry.get(id).ok_or_else(|| anyhow::anyhow!("Unknown module: {}", id))?;
+            values.insert(id.to_string(), describe(module));
+        }
+        // One module is answered on its own
+        match values.len() {
+            1 => Ok(values.into_iter().next().map(|(_, value)| value).unwrap_or_default()),
+            _ => Ok(Value::Object(values)),
+        }
+    })
+    .unwrap_or_else(|| Err(anyhow::anyhow!("The bar isn't ready")))
+}
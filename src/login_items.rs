@@ -0,0 +1,376 @@
+//! Login items - programs that start when the user signs in
+//!
+//! Collects the same entries Task Manager's Startup tab shows: `Run` registry
+//! values (current user and machine, 64- and 32-bit), shortcuts in the Startup
+//! folders, and the startup tasks of packaged apps. Turning an item off doesn't
+//! remove it; like Task Manager, it's marked disabled under `StartupApproved`
+//! (or in the package's task state), so it can be turned back on.
+
+use parking_lot::Mutex;
+use std::path::PathBuf;
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::*;
+
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+const RUN32_KEY: &str = "Software\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Run";
+const APPROVED_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved";
+const STARTUP_TASKS_KEY: &str =
+    "Software\\Classes\\Local Settings\\Software\\Microsoft\\Windows\\CurrentVersion\\AppModel\\SystemAppData";
+
+/// The list as last read; reading it walks the registry and the package store,
+/// too slow to do each time the app menu opens
+static CACHE: Mutex<Option<Vec<LoginItem>>> = Mutex::new(None);
+
+/// Packaged app startup task states (`Windows.ApplicationModel.StartupTaskState`)
+const TASK_DISABLED_BY_USER: u32 = 1;
+const TASK_ENABLED: u32 = 2;
+const TASK_DISABLED_BY_POLICY: u32 = 3;
+const TASK_ENABLED_BY_POLICY: u32 = 4;
+
+/// Where a login item is registered
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginSource {
+    /// A `Run` key value; `machine` for all users, `wow64` for 32-bit programs
+    RunKey { machine: bool, wow64: bool },
+    /// A file in a Startup folder
+    StartupFolder { machine: bool },
+    /// A packaged app's startup task, by package family and task id
+    StartupTask { family: String, task: String },
+}
+
+/// A program that starts at sign-in
+#[derive(Debug, Clone)]
+pub struct LoginItem {
+    pub name: String,
+    /// Command line, shortcut path, or package family
+    pub command: String,
+    pub source: LoginSource,
+    pub enabled: bool,
+    /// Set by group policy; can't be toggled here
+    pub locked: bool,
+}
+
+impl LoginItem {
+    /// Changing it needs administrator rights
+    pub fn needs_admin(&self) -> bool {
+        matches!(
+            self.source,
+            LoginSource::RunKey { machine: true, .. } | LoginSource::StartupFolder { machine: true }
+        )
+    }
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn root(machine: bool) -> HKEY {
+    if machine {
+        HKEY_LOCAL_MACHINE
+    } else {
+        HKEY_CURRENT_USER
+    }
+}
+
+fn open_key(root: HKEY, path: &str, access: REG_SAM_FLAGS) -> Option<HKEY> {
+    let path = to_wide(path);
+    let mut key = HKEY::default();
+    unsafe {
+        RegOpenKeyExW(root, PCWSTR(path.as_ptr()), 0, access | KEY_WOW64_64KEY, &mut key)
+            .ok()
+            .ok()?;
+    }
+    Some(key)
+}
+
+/// Every value under a key as (name, type, data)
+fn values(key: HKEY) -> Vec<(String, u32, Vec<u8>)> {
+    let mut result = Vec::new();
+    for index in 0.. {
+        let mut name = [0u16; 512];
+        let mut name_len = name.len() as u32;
+        let mut kind = 0u32;
+        let mut data = vec![0u8; 4096];
+        let mut data_len = data.len() as u32;
+        let status = unsafe {
+            RegEnumValueW(
+                key,
+                index,
+                PWSTR(name.as_mut_ptr()),
+                &mut name_len,
+                None,
+                Some(&mut kind),
+                Some(data.as_mut_ptr()),
+                Some(&mut data_len),
+            )
+        };
+        if status != ERROR_SUCCESS {
+            break;
+        }
+        data.truncate(data_len as usize);
+        result.push((String::from_utf16_lossy(&name[..name_len as usize]), kind, data));
+    }
+    result
+}
+
+/// Names of every subkey
+fn subkeys(key: HKEY) -> Vec<String> {
+    let mut result = Vec::new();
+    for index in 0.. {
+        let mut name = [0u16; 512];
+        let mut name_len = name.len() as u32;
+        let status = unsafe {
+            RegEnumKeyExW(key, index, PWSTR(name.as_mut_ptr()), &mut name_len, None, PWSTR::null(), None, None)
+        };
+        if status != ERROR_SUCCESS {
+            break;
+        }
+        result.push(String::from_utf16_lossy(&name[..name_len as usize]));
+    }
+    result
+}
+
+/// REG_SZ / REG_EXPAND_SZ data as a string
+fn string_data(data: &[u8]) -> String {
+    let wide: Vec<u16> = data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&wide).trim_end_matches('\0').to_string()
+}
+
+/// `StartupApproved` subkey that records the state of items from `source`
+fn approved_subkey(source: &LoginSource) -> Option<(HKEY, String)> {
+    let (machine, name) = match source {
+        LoginSource::RunKey { machine, wow64: false } => (*machine, "Run"),
+        LoginSource::RunKey { machine, wow64: true } => (*machine, "Run32"),
+        LoginSource::StartupFolder { machine } => (*machine, "StartupFolder"),
+        LoginSource::StartupTask { .. } => return None,
+    };
+    Some((root(machine), format!("{}\\{}", APPROVED_KEY, name)))
+}
+
+/// Disabled entries under a `StartupApproved` key (the first byte is odd when disabled)
+fn disabled_names(source: &LoginSource) -> Vec<String> {
+    let Some((root, path)) = approved_subkey(source) else {
+        return Vec::new();
+    };
+    let Some(key) = open_key(root, &path, KEY_READ) else {
+        return Vec::new();
+    };
+    let disabled = values(key)
+        .into_iter()
+        .filter(|(_, _, data)| data.first().map(|b| b & 1 == 1).unwrap_or(false))
+        .map(|(name, _, _)| name)
+        .collect();
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    disabled
+}
+
+fn run_key_items(machine: bool, wow64: bool) -> Vec<LoginItem> {
+    let Some(key) = open_key(root(machine), if wow64 { RUN32_KEY } else { RUN_KEY }, KEY_READ) else {
+        return Vec::new();
+    };
+    let source = LoginSource::RunKey { machine, wow64 };
+    let disabled = disabled_names(&source);
+    let items = values(key)
+        .into_iter()
+        .filter(|(name, kind, _)| !name.is_empty() && (*kind == REG_SZ.0 || *kind == REG_EXPAND_SZ.0))
+        .map(|(name, _, data)| LoginItem {
+            enabled: !disabled.contains(&name),
+            name,
+            command: string_data(&data),
+            source: source.clone(),
+            locked: false,
+        })
+        .collect();
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    items
+}
+
+fn startup_folder(machine: bool) -> Option<PathBuf> {
+    let base = if machine {
+        PathBuf::from(std::env::var("ProgramData").ok()?)
+    } else {
+        dirs::config_dir()?
+    };
+    Some(base.join("Microsoft\\Windows\\Start Menu\\Programs\\Startup"))
+}
+
+fn startup_folder_items(machine: bool) -> Vec<LoginItem> {
+    let Some(folder) = startup_folder(machine) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&folder) else {
+        return Vec::new();
+    };
+    let source = LoginSource::StartupFolder { machine };
+    let disabled = disabled_names(&source);
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().eq_ignore_ascii_case("desktop.ini"))
+        .map(|e| {
+            let file_name = e.file_name().to_string_lossy().to_string();
+            let name = e
+                .path()
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_name.clone());
+            LoginItem {
+                name,
+                command: e.path().to_string_lossy().to_string(),
+                source: source.clone(),
+                enabled: !disabled.contains(&file_name),
+                locked: false,
+            }
+        })
+        .collect()
+}
+
+fn read_dword(key: HKEY, value: &str) -> Option<u32> {
+    let value = to_wide(value);
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        RegGetValueW(
+            key,
+            PCWSTR::null(),
+            PCWSTR(value.as_ptr()),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut data as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+        .ok()
+        .ok()?;
+    }
+    Some(data)
+}
+
+fn startup_task_items() -> Vec<LoginItem> {
+    let Some(apps) = open_key(HKEY_CURRENT_USER, STARTUP_TASKS_KEY, KEY_READ) else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    for family in subkeys(apps) {
+        let Some(package) = open_key(apps, &family, KEY_READ) else {
+            continue;
+        };
+        // Startup tasks are the package's subkeys with a State value
+        for task in subkeys(package) {
+            let Some(task_key) = open_key(package, &task, KEY_READ) else {
+                continue;
+            };
+            if let Some(state) = read_dword(task_key, "State") {
+                let name = crate::app_index::package_display_name(&family)
+                    .unwrap_or_else(|| family.split('_').next().unwrap_or(&family).to_string());
+                items.push(LoginItem {
+                    name,
+                    command: family.clone(),
+                    source: LoginSource::StartupTask {
+                        family: family.clone(),
+                        task: task.clone(),
+                    },
+                    enabled: state == TASK_ENABLED || state == TASK_ENABLED_BY_POLICY,
+                    locked: state == TASK_DISABLED_BY_POLICY || state == TASK_ENABLED_BY_POLICY,
+                });
+            }
+            unsafe {
+                let _ = RegCloseKey(task_key);
+            }
+        }
+        unsafe {
+            let _ = RegCloseKey(package);
+        }
+    }
+    unsafe {
+        let _ = RegCloseKey(apps);
+    }
+    items
+}
+
+/// Every login item, sorted by name
+pub fn list() -> Vec<LoginItem> {
+    let mut items = Vec::new();
+    for machine in [false, true] {
+        items.extend(run_key_items(machine, false));
+        items.extend(startup_folder_items(machine));
+    }
+    items.extend(run_key_items(true, true));
+    items.extend(startup_task_items());
+    items.sort_by_key(|i| i.name.to_lowercase());
+    items
+}
+
+/// The login items as last read, or None before the first read is done
+pub fn cached() -> Option<Vec<LoginItem>> {
+    CACHE.lock().clone()
+}
+
+/// Read the login items again in the background
+pub fn refresh() {
+    crate::runtime::spawn(|| *CACHE.lock() = Some(list()));
+}
+
+/// Turn a login item on or off
+pub fn set_enabled(item: &LoginItem, enabled: bool) -> windows::core::Result<()> {
+    if let LoginSource::StartupTask { family, task } = &item.source {
+        let key = open_key(HKEY_CURRENT_USER, &format!("{}\\{}\\{}", STARTUP_TASKS_KEY, family, task), KEY_SET_VALUE)
+            .ok_or_else(windows::core::Error::from_win32)?;
+        let state = if enabled { TASK_ENABLED } else { TASK_DISABLED_BY_USER };
+        let name = to_wide("State");
+        let result = unsafe { RegSetValueExW(key, PCWSTR(name.as_ptr()), 0, REG_DWORD, Some(&state.to_le_bytes())) };
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        return result.ok();
+    }
+
+    let Some((root, path)) = approved_subkey(&item.source) else {
+        return Ok(());
+    };
+    // Entries are keyed by value name, or by file name for Startup folder shortcuts
+    let value_name = match item.source {
+        LoginSource::StartupFolder { .. } => PathBuf::from(&item.command)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        _ => item.name.clone(),
+    };
+
+    // 12 bytes: state (2 = enabled, 3 = disabled), then when it was disabled as a FILETIME
+    let mut data = [0u8; 12];
+    if enabled {
+        data[0] = 2;
+    } else {
+        data[0] = 3;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        data[4..].copy_from_slice(&((now + 11_644_473_600) * 10_000_000).to_le_bytes());
+    }
+
+    let path = to_wide(&path);
+    let mut key = HKEY::default();
+    unsafe {
+        RegCreateKeyExW(
+            root,
+            PCWSTR(path.as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_SET_VALUE | KEY_WOW64_64KEY,
+            None,
+            &mut key,
+            None,
+        )
+        .ok()?;
+        let name = to_wide(&value_name);
+        let result = RegSetValueExW(key, PCWSTR(name.as_ptr()), 0, REG_BINARY, Some(&data));
+        let _ = RegCloseKey(key);
+        result.ok()
+    }
+}
@@ -5,18 +5,44 @@
 
 #![cfg_attr(not(test), windows_subsystem = "windows")]
 
+mod accessibility;
+mod actions;
+mod alarms;
 mod app;
+mod app_index;
+mod attention;
+mod auto_hide;
+mod brightness;
+mod briefing;
+mod calculator;
 mod config;
+mod content_index;
+mod demo;
+mod dictionary;
+mod documents;
 mod effects;
 mod error;
 mod hotkey;
+mod i18n;
+mod ipc;
+mod login_items;
 mod quicklook;
+mod radios;
+mod recent_items;
 mod search;
+mod share_guard;
+mod translate;
+mod shelf;
 mod modules;
 mod render;
+mod runtime;
+mod scheduler;
+mod sounds;
+mod startup;
 mod theme;
 mod tray;
 mod utils;
+mod visibility;
 mod window;
 
 use anyhow::Result;
@@ -35,6 +61,16 @@ fn main() -> Result<()> {
 
     info!("Starting TopBar v{}", env!("CARGO_PKG_VERSION"));
 
+    // Log panics with where they happened; a module's is then caught and the
+    // bar carries on without it (see `modules::sandbox`)
+    std::panic::set_hook(Box::new(|info| log::error!("{}", info)));
+
+    // --demo feeds the modules synthetic data (screenshots, theme work, UI testing)
+    if std::env::args().skip(1).any(|a| a == "--demo") {
+        demo::enable();
+        info!("Demo mode: modules show synthetic data");
+    }
+
     // Load configuration
     let config = Arc::new(Config::load_or_default()?);
     info!("Configuration loaded successfully");
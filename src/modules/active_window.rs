@@ -1,16 +1,23 @@
 //! Active window module - shows the currently focused application
+//!
+//! With `modules.active_window.per_monitor` the module follows the monitor its
+//! bar is on: when focus is on another monitor it shows the window that was
+//! last active on this one. Clicking lists the app's other windows; right-click
+//! minimizes, maximizes or closes the window.
 
 #![allow(dead_code)]
 
 use std::time::Instant;
 use windows::core::PWSTR;
-use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::Graphics::Gdi::{MonitorFromWindow, HMONITOR, MONITOR_DEFAULTTONEAREST};
 use windows::Win32::System::ProcessStatus::GetModuleBaseNameW;
 use windows::Win32::System::Threading::{
     OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId,
+    GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow,
+    IsZoomed, PostMessageW, SetForegroundWindow, ShowWindow, SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE, WM_CLOSE,
 };
 use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
 use windows::Win32::System::Threading::GetCurrentProcessId;
@@ -31,11 +38,16 @@ pub struct ActiveWindowModule {
     // Store current process path and pid for icon lookup
     process_path: String,
     process_pid: u32,
+    // Window being shown (stored as an integer so the module stays Send)
+    window: isize,
+    // Monitor of the bar this module renders on; 0 follows focus on any monitor
+    monitor: isize,
     // Debounce candidate focus changes to avoid showing transient windows like Explorer during Alt-Tab
     candidate_title: String,
     candidate_process: String,
     candidate_process_path: String,
     candidate_pid: u32,
+    candidate_window: isize,
     candidate_since: Option<Instant>,
     focus_debounce_ms: u64,
 }
@@ -52,10 +64,13 @@ impl ActiveWindowModule {
             last_non_topbar_process: String::new(),
             process_path: String::new(),
             process_pid: 0,
+            window: 0,
+            monitor: 0,
             candidate_title: String::new(),
             candidate_process: String::new(),
             candidate_process_path: String::new(),
             candidate_pid: 0,
+            candidate_window: 0,
             candidate_since: None,
             focus_debounce_ms: 200, // ms
         };
@@ -66,7 +81,7 @@ impl ActiveWindowModule {
     /// Force an immediate update
     fn force_update(&mut self) {
         // Get title, process name and process id for the foreground window
-        let (title, process, pid, path) = self.get_active_window_info();
+        let (title, process, pid, path, window) = self.get_active_window_info();
 
         // Compare to our own process id when possible
         let own_pid = unsafe { GetCurrentProcessId() };
@@ -98,6 +113,7 @@ impl ActiveWindowModule {
                 self.process_name = process.clone();
                 self.process_path = path.clone();
                 self.process_pid = pid;
+                self.window = window;
                 self.candidate_since = None;
             } else {
                 // New candidate focus
@@ -109,6 +125,7 @@ impl ActiveWindowModule {
                     self.process_name = process.clone();
                     self.process_path = path.clone();
                     self.process_pid = pid;
+                    self.window = window;
                     self.candidate_since = None;
                 } else {
                     // If candidate changed, reset timer
@@ -118,6 +135,7 @@ impl ActiveWindowModule {
                         // Store the current process path from this foreground window (do not commit yet)
                         self.candidate_process_path = path.clone();
                         self.candidate_pid = pid;
+                        self.candidate_window = window;
                         self.candidate_since = Some(now);
                     } else if let Some(since) = self.candidate_since {
                         if now.duration_since(since).as_millis() as u64 >= self.focus_debounce_ms {
@@ -129,6 +147,7 @@ impl ActiveWindowModule {
                             // Commit stored candidate pid/path
                             self.process_pid = self.candidate_pid;
                             self.process_path = self.candidate_process_path.clone();
+                            self.window = self.candidate_window;
                             self.candidate_since = None;
                         }
                     }
@@ -159,11 +178,11 @@ impl ActiveWindowModule {
     }
 
     /// Get active window information
-    fn get_active_window_info(&mut self) -> (String, String, u32, String) {
+    fn get_active_window_info(&mut self) -> (String, String, u32, String, isize) {
         unsafe {
-            let hwnd = GetForegroundWindow();
+            let hwnd = self.focused_window();
             if hwnd.0.is_null() {
-                return (String::new(), String::new(), 0, String::new());
+                return (String::new(), String::new(), 0, String::new(), 0);
             }
 
             // Get window title
@@ -193,10 +212,27 @@ impl ActiveWindowModule {
             // Note: do not store `self.process_path` here; the caller will decide when to commit it
             // Store current pid
             // (we still return path so the caller can use it when committing)
-            (title, display_name, process_id, path)
+            (title, display_name, process_id, path, hwnd.0 as isize)
         }
     }
 
+    /// The foreground window, or with a monitor set, the most recently active
+    /// app window on that monitor (the first in Z order)
+    fn focused_window(&self) -> HWND {
+        let foreground = unsafe { GetForegroundWindow() };
+        if self.monitor == 0 || foreground.0.is_null() {
+            return foreground;
+        }
+        let on_monitor = |hwnd: HWND| unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST).0 as isize == self.monitor };
+        if on_monitor(foreground) {
+            return foreground;
+        }
+        super::tasks::enumerate_task_windows()
+            .into_iter()
+            .find(|&hwnd| on_monitor(hwnd))
+            .unwrap_or(foreground)
+    }
+
     /// Get window title
     fn get_window_title(&self, hwnd: HWND) -> String {
         unsafe {
@@ -218,34 +254,7 @@ impl ActiveWindowModule {
 
     /// Try to get full process path from window handle for icon lookup
     fn try_get_process_path(&self, hwnd: HWND) -> String {
-        unsafe {
-            let mut process_id: u32 = 0;
-            GetWindowThreadProcessId(hwnd, Some(&mut process_id));
-
-            if process_id == 0 {
-                return String::new();
-            }
-
-            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id);
-            if let Ok(handle) = handle {
-                let mut buffer: Vec<u16> = vec![0; 260];
-                let mut size: u32 = buffer.len() as u32;
-                let result = QueryFullProcessImageNameW(
-                    handle,
-                    PROCESS_NAME_FORMAT(0),
-                    PWSTR(buffer.as_mut_ptr()),
-                    &mut size,
-                );
-
-                let _ = windows::Win32::Foundation::CloseHandle(handle);
-
-                if result.is_ok() && size > 0 {
-                    return String::from_utf16_lossy(&buffer[..size as usize]);
-                }
-            }
-
-            String::new()
-        }
+        window_process_path(hwnd)
     }
 
     /// Get process name from window handle
@@ -315,6 +324,86 @@ impl ActiveWindowModule {
     pub fn process_id(&self) -> u32 {
         self.process_pid
     }
+
+    /// Follow the focused window on one monitor only, or on any with `None`
+    pub fn set_monitor(&mut self, monitor: Option<HMONITOR>) {
+        let monitor = monitor.map(|m| m.0 as isize).unwrap_or(0);
+        if self.monitor != monitor {
+            self.monitor = monitor;
+            self.force_update();
+        }
+    }
+
+    /// The window being shown, if it's still open
+    pub fn window(&self) -> Option<HWND> {
+        let hwnd = HWND(self.window as *mut std::ffi::c_void);
+        (self.window != 0 && unsafe { IsWindow(hwnd).as_bool() }).then_some(hwnd)
+    }
+
+    /// Open windows of the app being shown, in Z order, with their titles
+    pub fn app_windows(&self) -> Vec<(HWND, String)> {
+        if self.process_path.is_empty() && self.process_pid == 0 {
+            return Vec::new();
+        }
+        super::tasks::enumerate_task_windows()
+            .into_iter()
+            .filter(|&hwnd| {
+                // Apps like browsers run several processes, so the path decides when known
+                if self.process_path.is_empty() {
+                    let mut pid = 0u32;
+                    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+                    pid == self.process_pid
+                } else {
+                    window_process_path(hwnd).eq_ignore_ascii_case(&self.process_path)
+                }
+            })
+            .map(|hwnd| (hwnd, self.get_window_title(hwnd)))
+            .collect()
+    }
+
+    /// Whether the window being shown is maximized
+    pub fn is_maximized(&self) -> bool {
+        self.window().map(|hwnd| unsafe { IsZoomed(hwnd).as_bool() }).unwrap_or(false)
+    }
+
+    /// Minimize the window being shown
+    pub fn minimize(&self) {
+        if let Some(hwnd) = self.window() {
+            unsafe {
+                let _ = ShowWindow(hwnd, SW_MINIMIZE);
+            }
+        }
+    }
+
+    /// Maximize the window being shown, or restore it if it's maximized
+    pub fn toggle_maximize(&self) {
+        if let Some(hwnd) = self.window() {
+            let show = if self.is_maximized() { SW_RESTORE } else { SW_MAXIMIZE };
+            unsafe {
+                let _ = ShowWindow(hwnd, show);
+                let _ = SetForegroundWindow(hwnd);
+            }
+        }
+    }
+
+    /// Ask the window being shown to close (the app may still prompt to save)
+    pub fn close(&self) {
+        if let Some(hwnd) = self.window() {
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+            }
+        }
+    }
+}
+
+/// Bring a window to the front, restoring it if it's minimized
+pub fn activate(hwnd: HWND) {
+    unsafe {
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        let _ = SetForegroundWindow(hwnd);
+    }
 }
 
 impl Default for ActiveWindowModule {
@@ -343,10 +432,6 @@ impl Module for ActiveWindowModule {
         }
     }
 
-    fn on_click(&mut self) {
-        // Could show window list or app switcher
-    }
-
     fn tooltip(&self) -> Option<String> {
         if self.window_title.is_empty() {
             None
@@ -363,3 +448,35 @@ impl Module for ActiveWindowModule {
         self
     }
 }
+
+/// Full executable path of the process that owns a window (empty if it can't be read)
+pub fn window_process_path(hwnd: HWND) -> String {
+    unsafe {
+        let mut process_id: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+
+        if process_id == 0 {
+            return String::new();
+        }
+
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id);
+        if let Ok(handle) = handle {
+            let mut buffer: Vec<u16> = vec![0; 260];
+            let mut size: u32 = buffer.len() as u32;
+            let result = QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_FORMAT(0),
+                PWSTR(buffer.as_mut_ptr()),
+                &mut size,
+            );
+
+            let _ = windows::Win32::Foundation::CloseHandle(handle);
+
+            if result.is_ok() && size > 0 {
+                return String::from_utf16_lossy(&buffer[..size as usize]);
+            }
+        }
+
+        String::new()
+    }
+}
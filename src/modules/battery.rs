@@ -1,11 +1,39 @@
 //! Battery module for displaying battery status
+//!
+//! The bar shows the combined charge Windows reports. The dropdown lists each
+//! battery on its own (laptops and docks can have two) and the battery levels of
+//! connected Bluetooth devices, which the registry passes on from the Bluetooth
+//! module. Without a system battery, a low device battery still shows.
 
 use std::time::{Instant, Duration};
-use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
-
+use windows::core::PCWSTR;
+use windows::Win32::Devices::DeviceAndDriverInstallation::{
+    SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInterfaces, SetupDiGetClassDevsW, SetupDiGetDeviceInterfaceDetailW,
+    DIGCF_DEVICEINTERFACE, DIGCF_PRESENT, SP_DEVICE_INTERFACE_DATA, SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+};
+use windows::Win32::Foundation::{CloseHandle, HANDLE, GENERIC_READ, GENERIC_WRITE};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows::Win32::System::Power::*;
+use windows::Win32::System::IO::DeviceIoControl;
+
+use super::bluetooth::DeviceBattery;
 use super::Module;
+use crate::i18n::tr;
 use crate::utils::format_duration;
 
+/// One of the system's batteries
+#[derive(Debug, Clone)]
+pub struct SystemBattery {
+    /// Name the battery reports, e.g. "DELL 7FJ9261"
+    pub name: String,
+    pub percent: u32,
+    pub charging: bool,
+    /// Running on AC power
+    pub on_ac: bool,
+}
+
 /// Battery module
 pub struct BatteryModule {
     cached_text: String,
@@ -14,6 +42,10 @@ pub struct BatteryModule {
     is_plugged_in: bool,
     seconds_remaining: Option<u32>,
     has_battery: bool,
+    /// Each battery on its own
+    batteries: Vec<SystemBattery>,
+    /// Connected devices' batteries, from the Bluetooth module
+    devices: Vec<DeviceBattery>,
     last_update: Instant,
 }
 
@@ -26,6 +58,8 @@ impl BatteryModule {
             is_plugged_in: false,
             seconds_remaining: None,
             has_battery: true,
+            batteries: Vec::new(),
+            devices: Vec::new(),
             // Set last_update in the past so the first call to update() will
             // trigger an immediate force_update and populate the UI promptly.
             last_update: Instant::now() - Duration::from_secs(30),
@@ -34,6 +68,25 @@ impl BatteryModule {
 
     /// Force an immediate update
     fn force_update(&mut self, config: &crate::config::Config) {
+        if crate::demo::is_enabled() {
+            let (percent, charging, remaining) = crate::demo::battery();
+            self.has_battery = true;
+            self.battery_percent = percent;
+            self.is_charging = charging;
+            self.is_plugged_in = charging;
+            self.seconds_remaining = remaining;
+        } else {
+            self.read_power_status();
+            self.batteries = if self.has_battery { enumerate_batteries() } else { Vec::new() };
+        }
+
+        // Build display text
+        self.cached_text = self.build_display_text(config);
+        self.last_update = Instant::now();
+    }
+
+    /// Read the live battery state from Windows
+    fn read_power_status(&mut self) {
         unsafe {
             let mut status = SYSTEM_POWER_STATUS::default();
             if GetSystemPowerStatus(&mut status).is_ok() {
@@ -63,16 +116,16 @@ impl BatteryModule {
                 }
             }
         }
-
-        // Build display text
-        self.cached_text = self.build_display_text(config);
-        self.last_update = Instant::now();
     }
 
     /// Build the display text
     fn build_display_text(&self, config: &crate::config::Config) -> String {
         if !self.has_battery {
-            return String::new();
+            // A desktop still warns about a device running low
+            return match self.low_device(config) {
+                Some(device) => format!("{} {}%", device.kind.icon(), device.percent),
+                None => String::new(),
+            };
         }
 
         let icon = self.get_battery_icon();
@@ -111,6 +164,38 @@ impl BatteryModule {
         }
     }
 
+    /// The emptiest connected device at or below the low threshold, if shown
+    fn low_device(&self, config: &crate::config::Config) -> Option<&DeviceBattery> {
+        let battery = &config.modules.battery;
+        if !battery.show_devices {
+            return None;
+        }
+        self.devices
+            .iter()
+            .filter(|d| u32::from(d.percent) <= battery.low_threshold)
+            .min_by_key(|d| d.percent)
+    }
+
+    /// Take the connected devices' battery levels from the Bluetooth module
+    pub fn set_device_batteries(&mut self, devices: &[DeviceBattery], config: &crate::config::Config) {
+        let changed = devices.len() != self.devices.len()
+            || devices.iter().zip(&self.devices).any(|(a, b)| a.name != b.name || a.percent != b.percent);
+        if changed {
+            self.devices = devices.to_vec();
+            self.cached_text = self.build_display_text(config);
+        }
+    }
+
+    /// Each of the system's batteries
+    pub fn batteries(&self) -> &[SystemBattery] {
+        &self.batteries
+    }
+
+    /// Connected devices that report a battery level
+    pub fn devices(&self) -> &[DeviceBattery] {
+        &self.devices
+    }
+
     /// Get battery percentage
     pub fn battery_percent(&self) -> u32 {
         self.battery_percent
@@ -152,6 +237,14 @@ impl Module for BatteryModule {
         self.cached_text.clone()
     }
 
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        if self.has_battery() {
+            vec![("percent", self.battery_percent() as f32)]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn update(&mut self, config: &crate::config::Config) {
         // Update every 30 seconds
         if self.last_update.elapsed().as_secs() >= 30 {
@@ -159,14 +252,26 @@ impl Module for BatteryModule {
         }
     }
 
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
     fn on_click(&mut self) {
         // Open power settings
         crate::utils::open_url("ms-settings:batterysaver");
     }
 
     fn tooltip(&self) -> Option<String> {
+        let devices: String = self
+            .devices
+            .iter()
+            .map(|d| format!("\n{} {}: {}%", d.kind.icon(), d.name, d.percent))
+            .collect();
         if !self.has_battery {
-            return Some("No battery detected".to_string());
+            if !devices.is_empty() {
+                return Some(format!("{}:{}", tr("Devices"), devices));
+            }
+            return Some(tr("No battery detected"));
         }
 
         let status = if self.is_charging {
@@ -177,22 +282,30 @@ impl Module for BatteryModule {
             "On battery"
         };
 
-        let mut tooltip = format!("Battery: {}%\nStatus: {}", self.battery_percent, status);
+        let mut tooltip = format!("{}: {}%\n{}: {}", tr("Battery"), self.battery_percent, tr("Status"), tr(status));
 
         if let Some(secs) = self.seconds_remaining {
             if !self.is_charging {
                 tooltip.push_str(&format!(
-                    "\nTime remaining: {}",
+                    "\n{}: {}",
+                    tr("Time remaining"),
                     format_duration(secs as u64)
                 ));
             }
         }
 
+        if self.batteries.len() > 1 {
+            for (i, battery) in self.batteries.iter().enumerate() {
+                tooltip.push_str(&format!("\n{} {}: {}%", tr("Battery"), i + 1, battery.percent));
+            }
+        }
+        tooltip.push_str(&devices);
+
         Some(tooltip)
     }
 
     fn is_visible(&self) -> bool {
-        self.has_battery
+        self.has_battery || !self.cached_text.is_empty()
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -203,3 +316,146 @@ impl Module for BatteryModule {
         self
     }
 }
+
+/// Every battery in the system, read from the battery class driver
+fn enumerate_batteries() -> Vec<SystemBattery> {
+    let mut batteries = Vec::new();
+    unsafe {
+        let Ok(devices) = SetupDiGetClassDevsW(
+            Some(&GUID_DEVICE_BATTERY),
+            PCWSTR::null(),
+            None,
+            DIGCF_PRESENT | DIGCF_DEVICEINTERFACE,
+        ) else {
+            return batteries;
+        };
+        let mut interface = SP_DEVICE_INTERFACE_DATA {
+            cbSize: std::mem::size_of::<SP_DEVICE_INTERFACE_DATA>() as u32,
+            ..Default::default()
+        };
+        let mut index = 0;
+        while SetupDiEnumDeviceInterfaces(devices, None, &GUID_DEVICE_BATTERY, index, &mut interface).is_ok() {
+            index += 1;
+            let mut size = 0u32;
+            let _ = SetupDiGetDeviceInterfaceDetailW(devices, &interface, None, 0, Some(&mut size), None);
+            if size == 0 {
+                continue;
+            }
+            // u32s keep the detail struct aligned
+            let mut buffer = vec![0u32; (size as usize).div_ceil(4)];
+            let detail = buffer.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+            (*detail).cbSize = std::mem::size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() as u32;
+            if SetupDiGetDeviceInterfaceDetailW(devices, &interface, Some(detail), size, None, None).is_err() {
+                continue;
+            }
+            let path = PCWSTR((*detail).DevicePath.as_ptr());
+            if let Some(battery) = read_battery(path) {
+                batteries.push(battery);
+            }
+        }
+        let _ = SetupDiDestroyDeviceInfoList(devices);
+    }
+    batteries
+}
+
+/// Query one battery's name, capacity and charge state
+unsafe fn read_battery(path: PCWSTR) -> Option<SystemBattery> {
+    let handle = CreateFileW(
+        path,
+        GENERIC_READ.0 | GENERIC_WRITE.0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        None,
+        OPEN_EXISTING,
+        FILE_ATTRIBUTE_NORMAL,
+        HANDLE::default(),
+    )
+    .ok()?;
+    let battery = query_battery(handle);
+    let _ = CloseHandle(handle);
+    battery
+}
+
+unsafe fn query_battery(handle: HANDLE) -> Option<SystemBattery> {
+    let ioctl = |code: u32, input: *const std::ffi::c_void, input_size: usize, output: *mut std::ffi::c_void, output_size: usize| {
+        let mut returned = 0u32;
+        DeviceIoControl(
+            handle,
+            code,
+            Some(input),
+            input_size as u32,
+            Some(output),
+            output_size as u32,
+            Some(&mut returned),
+            None,
+        )
+        .is_ok()
+    };
+
+    // A tag of 0 means the slot is empty
+    let wait = 0u32;
+    let mut tag = 0u32;
+    if !ioctl(IOCTL_BATTERY_QUERY_TAG, &wait as *const u32 as _, 4, &mut tag as *mut u32 as _, 4) || tag == 0 {
+        return None;
+    }
+
+    let mut query = BATTERY_QUERY_INFORMATION {
+        BatteryTag: tag,
+        InformationLevel: BatteryInformation,
+        ..Default::default()
+    };
+    let mut info = BATTERY_INFORMATION::default();
+    if !ioctl(
+        IOCTL_BATTERY_QUERY_INFORMATION,
+        &query as *const _ as _,
+        std::mem::size_of_val(&query),
+        &mut info as *mut _ as _,
+        std::mem::size_of_val(&info),
+    ) {
+        return None;
+    }
+
+    query.InformationLevel = BatteryDeviceName;
+    let mut name = [0u16; 128];
+    let name = if ioctl(
+        IOCTL_BATTERY_QUERY_INFORMATION,
+        &query as *const _ as _,
+        std::mem::size_of_val(&query),
+        name.as_mut_ptr() as _,
+        std::mem::size_of_val(&name),
+    ) {
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        String::from_utf16_lossy(&name[..len]).trim().to_string()
+    } else {
+        String::new()
+    };
+
+    let wait_status = BATTERY_WAIT_STATUS {
+        BatteryTag: tag,
+        ..Default::default()
+    };
+    let mut status = BATTERY_STATUS::default();
+    if !ioctl(
+        IOCTL_BATTERY_QUERY_STATUS,
+        &wait_status as *const _ as _,
+        std::mem::size_of_val(&wait_status),
+        &mut status as *mut _ as _,
+        std::mem::size_of_val(&status),
+    ) {
+        return None;
+    }
+
+    // Relative batteries report percent directly; the rest report mWh
+    let percent = if info.Capabilities & BATTERY_CAPACITY_RELATIVE != 0 {
+        status.Capacity
+    } else if status.Capacity != BATTERY_UNKNOWN_CAPACITY && info.FullChargedCapacity > 0 {
+        (status.Capacity as u64 * 100 / info.FullChargedCapacity as u64) as u32
+    } else {
+        return None;
+    };
+    Some(SystemBattery {
+        name,
+        percent: percent.min(100),
+        charging: status.PowerState & BATTERY_CHARGING != 0,
+        on_ac: status.PowerState & BATTERY_POWER_ON_LINE != 0,
+    })
+}
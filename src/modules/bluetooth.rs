@@ -1,10 +1,15 @@
 //! Bluetooth module - shows Bluetooth status
+//!
+//! Also reads the battery level Windows reports for connected devices (mice,
+//! keyboards, headsets), which the battery module lists alongside the system
+//! batteries.
 
 #![allow(dead_code)]
 
 use log::debug;
 use std::time::Instant;
 
+use super::events;
 use super::Module;
 
 /// Bluetooth state
@@ -16,11 +21,52 @@ pub enum BluetoothState {
     Unavailable,
 }
 
+/// What kind of device a Bluetooth peripheral is, from its class of device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceKind {
+    Mouse,
+    Keyboard,
+    Headset,
+    Other,
+}
+
+impl DeviceKind {
+    /// Major/minor class bits of a Bluetooth class of device
+    fn from_class(class: u32) -> Self {
+        match ((class >> 8) & 0x1F, (class >> 6) & 0x3) {
+            (0x05, 1) => DeviceKind::Keyboard,
+            (0x05, 2) | (0x05, 3) => DeviceKind::Mouse,
+            (0x04, _) => DeviceKind::Headset,
+            _ => DeviceKind::Other,
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            DeviceKind::Mouse => "🖱",
+            DeviceKind::Keyboard => "⌨",
+            DeviceKind::Headset => "🎧",
+            DeviceKind::Other => "📱",
+        }
+    }
+}
+
+/// Battery level of a connected Bluetooth device
+#[derive(Debug, Clone)]
+pub struct DeviceBattery {
+    pub name: String,
+    pub kind: DeviceKind,
+    pub percent: u8,
+}
+
 /// Bluetooth module
 pub struct BluetoothModule {
     cached_text: String,
     state: BluetoothState,
     connected_devices: Vec<String>,
+    /// Class of device for each connected device, in the same order
+    device_classes: Vec<u32>,
+    device_batteries: Vec<DeviceBattery>,
     last_update: Instant,
 }
 
@@ -30,6 +76,8 @@ impl BluetoothModule {
             cached_text: String::new(),
             state: BluetoothState::Unavailable,
             connected_devices: Vec::new(),
+            device_classes: Vec::new(),
+            device_batteries: Vec::new(),
             last_update: Instant::now(),
         };
         module.force_update();
@@ -39,6 +87,11 @@ impl BluetoothModule {
     /// Force an immediate update
     fn force_update(&mut self) {
         self.query_bluetooth_status();
+        self.device_batteries = if self.state == BluetoothState::Connected {
+            self.read_device_batteries()
+        } else {
+            Vec::new()
+        };
         self.cached_text = self.build_display_text();
         self.last_update = Instant::now();
     }
@@ -87,6 +140,7 @@ impl BluetoothModule {
     /// Check for connected Bluetooth devices
     fn check_connected_devices(&mut self) -> usize {
         self.connected_devices.clear();
+        self.device_classes.clear();
 
         use windows::Win32::Devices::Bluetooth::{
             BluetoothFindDeviceClose, BluetoothFindFirstDevice, BluetoothFindNextDevice,
@@ -122,6 +176,7 @@ impl BluetoothModule {
                             String::from_utf16_lossy(&raw[..len])
                         };
                         self.connected_devices.push(name);
+                        self.device_classes.push(current.ulClassofDevice);
                     }
 
                     if BluetoothFindNextDevice(handle, &mut current).is_err() {
@@ -136,6 +191,66 @@ impl BluetoothModule {
         self.connected_devices.len()
     }
 
+    /// Battery levels of connected devices that report one. Windows keeps them as
+    /// a device property on the device's node, not in the Bluetooth APIs.
+    fn read_device_batteries(&self) -> Vec<DeviceBattery> {
+        use windows::Win32::Devices::DeviceAndDriverInstallation::{
+            SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInfo, SetupDiGetClassDevsW, SetupDiGetDevicePropertyW,
+            DIGCF_ALLCLASSES, DIGCF_PRESENT, SP_DEVINFO_DATA,
+        };
+        use windows::Win32::Devices::Properties::{DEVPKEY_Device_FriendlyName, DEVPROPKEY, DEVPROPTYPE};
+
+        const DEVPKEY_BLUETOOTH_BATTERY: DEVPROPKEY = DEVPROPKEY {
+            fmtid: windows::core::GUID::from_u128(0x104ea319_6ee2_4701_bd47_8ddbf425bbe5),
+            pid: 2,
+        };
+
+        let mut batteries: Vec<DeviceBattery> = Vec::new();
+        unsafe {
+            let Ok(devices) =
+                SetupDiGetClassDevsW(None, windows::core::PCWSTR::null(), None, DIGCF_ALLCLASSES | DIGCF_PRESENT)
+            else {
+                return batteries;
+            };
+            let mut info = SP_DEVINFO_DATA {
+                cbSize: std::mem::size_of::<SP_DEVINFO_DATA>() as u32,
+                ..Default::default()
+            };
+            let mut index = 0;
+            while SetupDiEnumDeviceInfo(devices, index, &mut info).is_ok() {
+                index += 1;
+                let mut kind = DEVPROPTYPE::default();
+                let mut level = [0u8; 1];
+                if SetupDiGetDevicePropertyW(devices, &info, &DEVPKEY_BLUETOOTH_BATTERY, &mut kind, Some(&mut level), None, 0)
+                    .is_err()
+                {
+                    continue;
+                }
+                let mut name = [0u16; 256];
+                let name_bytes = std::slice::from_raw_parts_mut(name.as_mut_ptr() as *mut u8, std::mem::size_of_val(&name));
+                let _ = SetupDiGetDevicePropertyW(devices, &info, &DEVPKEY_Device_FriendlyName, &mut kind, Some(name_bytes), None, 0);
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                let name = String::from_utf16_lossy(&name[..len]);
+
+                // A device shows up once per service it offers; keep only connected ones
+                let Some(position) = self.connected_devices.iter().position(|d| *d == name) else {
+                    continue;
+                };
+                if batteries.iter().any(|b| b.name == name) {
+                    continue;
+                }
+                batteries.push(DeviceBattery {
+                    name,
+                    kind: DeviceKind::from_class(self.device_classes.get(position).copied().unwrap_or(0)),
+                    percent: level[0].min(100),
+                });
+            }
+            let _ = SetupDiDestroyDeviceInfoList(devices);
+        }
+        debug!("Bluetooth device batteries: {:?}", batteries);
+        batteries
+    }
+
     /// Build the display text
     fn build_display_text(&self) -> String {
         match self.state {
@@ -164,6 +279,11 @@ impl BluetoothModule {
         &self.connected_devices
     }
 
+    /// Battery levels of connected devices that report one
+    pub fn device_batteries(&self) -> &[DeviceBattery] {
+        &self.device_batteries
+    }
+
     /// Toggle Bluetooth
     pub fn toggle(&mut self) {
         // Open Bluetooth settings - actual toggle requires admin privileges
@@ -225,6 +345,10 @@ impl Module for BluetoothModule {
         }
     }
 
+    fn force_refresh(&mut self, _config: &crate::config::Config) {
+        self.force_update();
+    }
+
     fn on_click(&mut self) {
         self.toggle();
     }
@@ -261,4 +385,12 @@ impl Module for BluetoothModule {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::DEVICES_CHANGED]
+    }
+
+    fn on_event(&mut self, _topic: &str, _config: &crate::config::Config) {
+        self.refresh();
+    }
 }
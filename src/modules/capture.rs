@@ -0,0 +1,224 @@
+//! Capture module - screenshots and screen recording
+//!
+//! Screenshots are grabbed with GDI, saved as PNG to the configured folder and
+//! optionally copied to the clipboard. Recording is handled by `screen_recorder`;
+//! while it runs the module shows a red indicator with the elapsed time.
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use std::path::PathBuf;
+
+use super::screen_recorder;
+use super::Module;
+use crate::config::CaptureConfig;
+use crate::utils::Rect;
+
+/// A captured screen area as top-down BGRA pixels
+pub struct ScreenImage {
+    /// Screen coordinates of the top-left pixel
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<u8>,
+}
+
+impl ScreenImage {
+    /// Copy out the part of the image covering `rect` (screen coordinates)
+    pub fn crop(&self, rect: Rect) -> Option<ScreenImage> {
+        let left = rect.x.max(self.x);
+        let top = rect.y.max(self.y);
+        let right = rect.right().min(self.x + self.width);
+        let bottom = rect.bottom().min(self.y + self.height);
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        let width = right - left;
+        let height = bottom - top;
+        let src_stride = self.width as usize * 4;
+        let row_len = width as usize * 4;
+        let mut pixels = Vec::with_capacity(row_len * height as usize);
+        for row in (top - self.y)..(bottom - self.y) {
+            let start = row as usize * src_stride + (left - self.x) as usize * 4;
+            pixels.extend_from_slice(&self.pixels[start..start + row_len]);
+        }
+
+        Some(ScreenImage { x: left, y: top, width, height, pixels })
+    }
+
+    /// Pixels converted to RGBA with an opaque alpha channel
+    fn to_rgba(&self) -> Vec<u8> {
+        self.pixels
+            .chunks_exact(4)
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], 255])
+            .collect()
+    }
+}
+
+/// Grab the whole virtual screen (all monitors)
+pub fn grab_virtual_screen() -> Option<ScreenImage> {
+    use windows::Win32::Graphics::Gdi::*;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+    };
+
+    unsafe {
+        let x = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let y = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+        let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        let screen_dc = GetDC(None);
+        let mem_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let old = SelectObject(mem_dc, bitmap);
+
+        let copied = BitBlt(mem_dc, 0, 0, width, height, screen_dc, x, y, SRCCOPY | CAPTUREBLT).is_ok();
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // top-down
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let _ = SelectObject(mem_dc, old);
+        let lines = GetDIBits(
+            mem_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(mem_dc);
+        ReleaseDC(None, screen_dc);
+
+        if !copied || lines == 0 {
+            warn!("Screen capture failed");
+            return None;
+        }
+
+        Some(ScreenImage { x, y, width, height, pixels })
+    }
+}
+
+/// Build a timestamped file path in the capture folder
+pub fn output_path(config: &CaptureConfig, prefix: &str, extension: &str) -> Result<PathBuf> {
+    let dir = config.output_dir();
+    std::fs::create_dir_all(&dir)?;
+    let stamp = chrono::Local::now().format("%Y-%m-%d at %H.%M.%S");
+    Ok(dir.join(format!("{} {}.{}", prefix, stamp, extension)))
+}
+
+/// Save a screenshot as PNG and copy it to the clipboard if configured
+pub fn save_screenshot(image: &ScreenImage, config: &CaptureConfig) -> Result<PathBuf> {
+    let path = output_path(config, "Screenshot", "png")?;
+    let rgba = image.to_rgba();
+
+    let buffer = image::RgbaImage::from_raw(image.width as u32, image.height as u32, rgba.clone())
+        .ok_or_else(|| anyhow!("Invalid screenshot dimensions"))?;
+    buffer.save(&path)?;
+    info!("Saved screenshot to {:?}", path);
+
+    if config.copy_to_clipboard {
+        let data = arboard::ImageData {
+            width: image.width as usize,
+            height: image.height as usize,
+            bytes: std::borrow::Cow::Owned(rgba),
+        };
+        match arboard::Clipboard::new() {
+            Ok(mut clipboard) => {
+                if let Err(e) = clipboard.set_image(data) {
+                    warn!("Failed to copy screenshot to clipboard: {}", e);
+                }
+            }
+            Err(e) => warn!("Clipboard unavailable: {}", e),
+        }
+    }
+
+    Ok(path)
+}
+
+/// Capture every monitor and save the result
+pub fn capture_full_screen(config: &CaptureConfig) -> Result<PathBuf> {
+    let image = grab_virtual_screen().ok_or_else(|| anyhow!("Screen capture failed"))?;
+    save_screenshot(&image, config)
+}
+
+/// Capture module
+pub struct CaptureModule {
+    cached_text: String,
+}
+
+impl CaptureModule {
+    pub fn new() -> Self {
+        Self {
+            cached_text: "📷".to_string(),
+        }
+    }
+
+    /// Whether a recording is in progress (the renderer draws it in red)
+    pub fn is_recording(&self) -> bool {
+        screen_recorder::is_recording()
+    }
+}
+
+impl Default for CaptureModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for CaptureModule {
+    fn id(&self) -> &str {
+        "capture"
+    }
+
+    fn name(&self) -> &str {
+        "Capture"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.cached_text.clone()
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {
+        self.cached_text = match screen_recorder::elapsed() {
+            Some(elapsed) => {
+                let secs = elapsed.as_secs();
+                format!("⏺ {:02}:{:02}", secs / 60, secs % 60)
+            }
+            None => "📷".to_string(),
+        };
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.is_recording() {
+            Some("Recording screen\nClick to stop".to_string())
+        } else {
+            Some("Screenshot / screen recording".to_string())
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
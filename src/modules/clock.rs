@@ -1,7 +1,12 @@
 //! Clock module for displaying time and date
+//!
+//! Also plays the optional periodic chime and spoken time. With
+//! `modules.clock.formats` set, the clock shows one custom format at a time and
+//! scrolling over it cycles through them.
 
-use chrono::Local;
-use std::time::Instant;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Timelike};
+use std::time::{Duration, Instant};
 
 use super::Module;
 
@@ -9,6 +14,19 @@ use super::Module;
 pub struct ClockModule {
     cached_text: String,
     last_update: std::time::Instant,
+    /// Date and minute of day last announced, so each slot is announced once
+    last_chime: Option<(NaiveDate, u32)>,
+    /// Custom formats from the config, kept so scrolling can redraw right away
+    formats: Vec<String>,
+    /// Custom format currently shown
+    format_index: usize,
+    /// Label and time of the next armed alarm
+    next_alarm: Option<(String, DateTime<Local>)>,
+}
+
+/// Whether chrono can format with `format` (formatting with a bad one panics)
+pub fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
 }
 
 impl ClockModule {
@@ -16,6 +34,81 @@ impl ClockModule {
         Self {
             cached_text: String::new(),
             last_update: std::time::Instant::now(),
+            last_chime: None,
+            formats: Vec::new(),
+            format_index: 0,
+            next_alarm: None,
+        }
+    }
+
+    /// Chime and/or speak the time when an announcement slot starts
+    fn check_chime(&mut self, config: &crate::config::Config) {
+        use crate::config::ChimeMode;
+
+        let chime = &config.modules.clock.chime;
+        if chime.mode == ChimeMode::Off {
+            self.last_chime = None;
+            return;
+        }
+
+        let now = Local::now();
+        let minute = now.hour() * 60 + now.minute();
+        let slot = (now.date_naive(), minute);
+        if !minute.is_multiple_of(chime.interval_min.clamp(1, 24 * 60)) || self.last_chime == Some(slot) {
+            return;
+        }
+        // Don't announce a slot that started long before the bar noticed it
+        if self.last_chime.is_none() && now.second() > 5 {
+            self.last_chime = Some(slot);
+            return;
+        }
+        self.last_chime = Some(slot);
+
+        if chime.quiet_hours && Self::in_quiet_hours(now.time(), &chime.quiet_start, &chime.quiet_end) {
+            return;
+        }
+        if chime.respect_do_not_disturb && crate::attention::is_do_not_disturb(&config.attention) {
+            return;
+        }
+
+        let mut delay = Duration::ZERO;
+        if matches!(chime.mode, ChimeMode::Chime | ChimeMode::Both) {
+            crate::sounds::play_at(&config.sounds, crate::sounds::SoundEvent::Chime, chime.volume);
+            delay = Duration::from_millis(1300);
+        }
+        if matches!(chime.mode, ChimeMode::Speak | ChimeMode::Both) {
+            let text = Self::spoken_time(&now, config.modules.clock.format_24h);
+            crate::sounds::speak(&text, chime.volume, delay);
+        }
+    }
+
+    /// Whether `now` falls in the quiet hours (which may span midnight)
+    fn in_quiet_hours(now: NaiveTime, start: &str, end: &str) -> bool {
+        let (Ok(start), Ok(end)) = (
+            NaiveTime::parse_from_str(start.trim(), "%H:%M"),
+            NaiveTime::parse_from_str(end.trim(), "%H:%M"),
+        ) else {
+            return false;
+        };
+        if start <= end {
+            now >= start && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+
+    /// The time as a sentence, e.g. "It's 3 o'clock" or "It's 3:30 PM"
+    fn spoken_time(now: &DateTime<Local>, format_24h: bool) -> String {
+        if format_24h {
+            if now.minute() == 0 {
+                format!("It's {} hundred hours", now.hour())
+            } else {
+                format!("It's {}", now.format("%H:%M"))
+            }
+        } else if now.minute() == 0 {
+            format!("It's {} o'clock", now.format("%-I"))
+        } else {
+            format!("It's {}", now.format("%-I:%M %p"))
         }
     }
 
@@ -23,6 +116,10 @@ impl ClockModule {
     fn format_time(&self, config: &crate::config::Config) -> String {
         let now = Local::now();
 
+        if let Some(format) = self.formats.get(self.format_index) {
+            return now.format(format).to_string();
+        }
+
         let time_str = if config.modules.clock.format_24h {
             if config.modules.clock.show_seconds {
                 now.format("%H:%M:%S").to_string()
@@ -51,6 +148,11 @@ impl ClockModule {
         result
     }
 
+    /// Whether any alarm is armed (the clock shows a badge)
+    pub fn has_alarm(&self) -> bool {
+        self.next_alarm.is_some()
+    }
+
     /// Build the display text
     fn build_display_text(&self, config: &crate::config::Config) -> String {
         self.format_time(config)
@@ -77,18 +179,52 @@ impl Module for ClockModule {
     }
 
     fn update(&mut self, config: &crate::config::Config) {
+        // Invalid formats are reported by config validation and skipped here
+        let formats: Vec<String> = config
+            .modules
+            .clock
+            .formats
+            .iter()
+            .filter(|f| is_valid_format(f))
+            .cloned()
+            .collect();
+        if formats != self.formats {
+            self.formats = formats;
+            self.format_index = 0;
+        }
+
         // Update cached text
         self.cached_text = self.build_display_text(config);
         self.last_update = Instant::now();
+        self.next_alarm = crate::alarms::next_armed(&config.scheduler.alarms);
+        self.check_chime(config);
     }
 
     fn on_click(&mut self) {
         // Could open calendar widget
     }
 
+    fn on_scroll(&mut self, delta: i32) {
+        let count = self.formats.len();
+        if count < 2 {
+            return;
+        }
+        // Scrolling down moves to the next format
+        self.format_index = if delta < 0 {
+            (self.format_index + 1) % count
+        } else {
+            (self.format_index + count - 1) % count
+        };
+        self.cached_text = Local::now().format(&self.formats[self.format_index]).to_string();
+    }
+
     fn tooltip(&self) -> Option<String> {
         let now = Local::now();
-        Some(now.format("%A, %B %d, %Y\n%I:%M:%S %p").to_string())
+        let mut text = now.format("%A, %B %d, %Y\n%I:%M:%S %p").to_string();
+        if let Some((label, at)) = &self.next_alarm {
+            text.push_str(&format!("\n\n⏰ {} at {}", label, at.format("%a %H:%M")));
+        }
+        Some(text)
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -0,0 +1,398 @@
+//! Connectivity module - latency to a host and the public IP address
+//!
+//! Pings a configurable host every few seconds, with ICMP echo or by timing a
+//! TCP connect for networks that drop ICMP, and shows the latest round trip in
+//! green, amber or red against the `warn_ms` and `bad_ms` thresholds. The last
+//! minute of pings gives the jitter and packet loss shown in the module's menu.
+//! The public IP address and where it's registered are looked up every few
+//! minutes for the tooltip. Pings and lookups run on the shared background runtime.
+
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::events;
+use super::Module;
+use crate::config::{ConnectivityConfig, PingMethod};
+
+/// Pings kept for the statistics and the tooltip graph
+const SAMPLE_COUNT: usize = 60;
+/// How long to wait for a reply
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often the public IP is looked up again
+const PUBLIC_IP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Where the public IP address is registered, from the lookup service
+#[derive(Debug, Clone, Default)]
+pub struct PublicIp {
+    pub ip: String,
+    pub city: String,
+    pub region: String,
+    pub country: String,
+    /// Network operator, e.g. "AS13335 Cloudflare, Inc."
+    pub org: String,
+}
+
+impl PublicIp {
+    /// "City, Region, Country", skipping the parts that are missing
+    pub fn location(&self) -> String {
+        [&self.city, &self.region, &self.country]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// How the latest ping compares to the thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Latency {
+    Good,
+    Slow,
+    /// Above `bad_ms`, or the ping was lost
+    Bad,
+}
+
+/// Summary of the recent pings
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PingStats {
+    /// Latest round trip in milliseconds; `None` if it was lost
+    pub last: Option<f32>,
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+    /// Average change between consecutive replies, in milliseconds
+    pub jitter: f32,
+    /// Share of pings lost, 0-100
+    pub loss: f32,
+    pub sent: usize,
+}
+
+/// Connectivity module
+pub struct ConnectivityModule {
+    /// Round trips in milliseconds, oldest first; `None` for lost pings
+    samples: Arc<Mutex<VecDeque<Option<f32>>>>,
+    public_ip: Arc<Mutex<Option<PublicIp>>>,
+    pinging: Arc<AtomicBool>,
+    looking_up: Arc<AtomicBool>,
+    last_ping: Option<Instant>,
+    last_lookup: Option<Instant>,
+    /// Host, method and port of the samples; they restart when it changes
+    target: (String, PingMethod, u16),
+    warn_ms: u32,
+    bad_ms: u32,
+}
+
+impl ConnectivityModule {
+    pub fn new() -> Self {
+        let defaults = ConnectivityConfig::default();
+        Self {
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(SAMPLE_COUNT))),
+            public_ip: Arc::new(Mutex::new(None)),
+            pinging: Arc::new(AtomicBool::new(false)),
+            looking_up: Arc::new(AtomicBool::new(false)),
+            last_ping: None,
+            last_lookup: None,
+            target: (defaults.host, defaults.method, defaults.port),
+            warn_ms: defaults.warn_ms,
+            bad_ms: defaults.bad_ms,
+        }
+    }
+
+    fn ping_async(&mut self) {
+        if self.pinging.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.last_ping = Some(Instant::now());
+        let (host, method, port) = self.target.clone();
+        let samples = Arc::clone(&self.samples);
+        let pinging = Arc::clone(&self.pinging);
+        crate::runtime::spawn(move || {
+            let rtt = ping(&host, method, port);
+            {
+                let mut samples = samples.lock();
+                if samples.len() >= SAMPLE_COUNT {
+                    samples.pop_front();
+                }
+                samples.push_back(rtt);
+            }
+            pinging.store(false, Ordering::SeqCst);
+        });
+    }
+
+    fn lookup_async(&mut self, url: &str) {
+        if self.looking_up.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.last_lookup = Some(Instant::now());
+        let url = url.to_string();
+        let public_ip = Arc::clone(&self.public_ip);
+        let looking_up = Arc::clone(&self.looking_up);
+        crate::runtime::spawn(move || {
+            match fetch_public_ip(&url) {
+                Ok(info) => {
+                    debug!("Public IP: {} ({})", info.ip, info.location());
+                    *public_ip.lock() = Some(info);
+                }
+                // Keep the last address; it's shown until the next lookup works
+                Err(e) => warn!("Failed to look up the public IP: {}", e),
+            }
+            looking_up.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Statistics over the recent pings, or None before the first one
+    pub fn stats(&self) -> Option<PingStats> {
+        let samples = self.samples.lock();
+        let last = *samples.back()?;
+        let replies: Vec<f32> = samples.iter().flatten().copied().collect();
+        let mut stats = PingStats {
+            last,
+            sent: samples.len(),
+            loss: (samples.len() - replies.len()) as f32 * 100.0 / samples.len() as f32,
+            ..Default::default()
+        };
+        if !replies.is_empty() {
+            stats.min = replies.iter().copied().fold(f32::MAX, f32::min);
+            stats.max = replies.iter().copied().fold(0.0, f32::max);
+            stats.avg = replies.iter().sum::<f32>() / replies.len() as f32;
+        }
+        if replies.len() > 1 {
+            let changes: f32 = replies.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum();
+            stats.jitter = changes / (replies.len() - 1) as f32;
+        }
+        Some(stats)
+    }
+
+    /// How the latest ping compares to the thresholds, or None before the first one
+    pub fn latency(&self) -> Option<Latency> {
+        let last = *self.samples.lock().back()?;
+        Some(match last {
+            Some(ms) if ms < self.warn_ms as f32 => Latency::Good,
+            Some(ms) if ms < self.bad_ms as f32 => Latency::Slow,
+            _ => Latency::Bad,
+        })
+    }
+
+    /// Latest public IP lookup, if one has finished
+    pub fn public_ip(&self) -> Option<PublicIp> {
+        self.public_ip.lock().clone()
+    }
+
+    /// The host being pinged and how, e.g. "1.1.1.1 (ICMP)"
+    pub fn target(&self) -> String {
+        match self.target.1 {
+            PingMethod::Icmp => format!("{} (ICMP)", self.target.0),
+            PingMethod::Tcp => format!("{}:{} (TCP)", self.target.0, self.target.2),
+        }
+    }
+}
+
+impl Default for ConnectivityModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for ConnectivityModule {
+    fn id(&self) -> &str {
+        "connectivity"
+    }
+
+    fn name(&self) -> &str {
+        "Connectivity"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        match self.stats().map(|s| s.last) {
+            Some(Some(ms)) => format!("🌐 {:.0} ms", ms),
+            Some(None) => "🌐 ✕".to_string(),
+            None => "🌐 --".to_string(),
+        }
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        let cfg = &config.modules.connectivity;
+        self.warn_ms = cfg.warn_ms;
+        self.bad_ms = cfg.bad_ms;
+        let target = (cfg.host.trim().to_string(), cfg.method, cfg.port);
+        if target != self.target {
+            self.target = target;
+            self.samples.lock().clear();
+            self.last_ping = None;
+        }
+
+        let interval = Duration::from_secs(cfg.interval_sec.max(1));
+        if !self.target.0.is_empty() && self.last_ping.map(|t| t.elapsed() >= interval).unwrap_or(true) {
+            self.ping_async();
+        }
+        if !cfg.show_public_ip {
+            *self.public_ip.lock() = None;
+            self.last_lookup = None;
+        } else if self.last_lookup.map(|t| t.elapsed() >= PUBLIC_IP_INTERVAL).unwrap_or(true) {
+            self.lookup_async(&cfg.public_ip_url);
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        if crate::modules::is_offline(self, config) {
+            return;
+        }
+        self.last_ping = None;
+        self.last_lookup = None;
+        self.update(config);
+    }
+
+    fn copy_value(&self, config: &crate::config::Config) -> String {
+        self.public_ip()
+            .map(|p| p.ip)
+            .unwrap_or_else(|| self.display_text(config))
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        let Some(stats) = self.stats() else {
+            return Vec::new();
+        };
+        let mut metrics = Vec::new();
+        if let Some(ms) = stats.last {
+            metrics.push(("ping", ms));
+        }
+        metrics.push(("jitter", stats.jitter));
+        metrics.push(("loss", stats.loss));
+        metrics
+    }
+
+    fn history(&self) -> Option<super::History> {
+        let samples = self.samples.lock();
+        (samples.len() > 1).then(|| super::History {
+            values: samples.iter().map(|s| s.unwrap_or(0.0)).collect(),
+            max: None,
+        })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let mut lines = vec![format!("Host: {}", self.target())];
+        match self.stats() {
+            Some(stats) => {
+                let last = match stats.last {
+                    Some(ms) => format!("{:.0} ms", ms),
+                    None => "Lost".to_string(),
+                };
+                lines.push(format!("Ping: {}", last));
+                lines.push(format!("Jitter: {:.1} ms", stats.jitter));
+                lines.push(format!("Loss: {:.0}%", stats.loss));
+            }
+            None => lines.push("Ping: waiting...".to_string()),
+        }
+        if let Some(public) = self.public_ip() {
+            lines.push(format!("Public IP: {}", public.ip));
+            let location = public.location();
+            if !location.is_empty() {
+                lines.push(format!("Location: {}", location));
+            }
+            if !public.org.is_empty() {
+                lines.push(format!("Provider: {}", public.org));
+            }
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::NETWORK_CONNECTED]
+    }
+
+    fn on_event(&mut self, _topic: &str, config: &crate::config::Config) {
+        // A new connection may well mean a new public address
+        self.force_refresh(config);
+    }
+}
+
+/// Round trip to `host` in milliseconds, or None if it timed out or failed
+fn ping(host: &str, method: PingMethod, port: u16) -> Option<f32> {
+    let addresses: Vec<SocketAddr> = (host, port).to_socket_addrs().ok()?.collect();
+    match method {
+        PingMethod::Icmp => {
+            // IcmpSendEcho is IPv4 only
+            let address = addresses.iter().find_map(|a| match a.ip() {
+                IpAddr::V4(v4) => Some(v4),
+                IpAddr::V6(_) => None,
+            })?;
+            icmp_echo(u32::from_ne_bytes(address.octets()))
+        }
+        PingMethod::Tcp => {
+            let address = addresses.first()?;
+            let started = Instant::now();
+            TcpStream::connect_timeout(address, PING_TIMEOUT).ok()?;
+            Some(started.elapsed().as_secs_f32() * 1000.0)
+        }
+    }
+}
+
+/// Send one ICMP echo to an IPv4 address (in network byte order)
+fn icmp_echo(address: u32) -> Option<f32> {
+    use windows::Win32::NetworkManagement::IpHelper::{IcmpCloseHandle, IcmpCreateFile, IcmpSendEcho, ICMP_ECHO_REPLY};
+
+    const PAYLOAD: &[u8] = b"topbar ping";
+    // Room for the reply, its echoed data and an ICMP error message
+    let mut reply = vec![0u8; std::mem::size_of::<ICMP_ECHO_REPLY>() + PAYLOAD.len() + 8];
+    unsafe {
+        let handle = IcmpCreateFile().ok()?;
+        let count = IcmpSendEcho(
+            handle,
+            address,
+            PAYLOAD.as_ptr() as *const std::ffi::c_void,
+            PAYLOAD.len() as u16,
+            None,
+            reply.as_mut_ptr() as *mut std::ffi::c_void,
+            reply.len() as u32,
+            PING_TIMEOUT.as_millis() as u32,
+        );
+        let _ = IcmpCloseHandle(handle);
+        if count == 0 {
+            return None;
+        }
+        let reply = &*(reply.as_ptr() as *const ICMP_ECHO_REPLY);
+        // IP_SUCCESS; anything else is an unreachable or expired reply
+        (reply.Status == 0).then_some(reply.RoundTripTime as f32)
+    }
+}
+
+fn fetch_public_ip(url: &str) -> Result<PublicIp, String> {
+    let body = ureq::get(url)
+        .set("Accept", "application/json")
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| format!("HTTP error: {}", e))?
+        .into_string()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| format!("JSON parse error: {}", e))?;
+    let field = |key: &str| json.get(key).and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+    let ip = field("ip");
+    if ip.is_empty() {
+        return Err("No \"ip\" in the response".to_string());
+    }
+    Ok(PublicIp {
+        ip,
+        city: field("city"),
+        region: field("region"),
+        country: field("country"),
+        org: field("org"),
+    })
+}
@@ -1,9 +1,16 @@
 //! Disk I/O module - shows disk read/write activity
+//!
+//! Also keeps each physical drive's SMART health for the disk menu, read in
+//! the background every few minutes.
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use sysinfo::Disks;
 
+use super::sampler;
+use super::smart::{self, DiskHealth, Health};
 use super::Module;
+use crate::runtime::Task;
+use crate::i18n::tr;
 use crate::utils::format_bytes;
 
 /// Disk usage information
@@ -16,6 +23,9 @@ pub struct DiskInfo {
     pub used_space: u64,
 }
 
+/// How often drive health is read again
+const HEALTH_INTERVAL: Duration = Duration::from_secs(600);
+
 /// Disk I/O module
 pub struct DiskModule {
     cached_text: String,
@@ -23,6 +33,11 @@ pub struct DiskModule {
     primary_disk_index: usize,
     last_update: Instant,
     update_interval_ms: u64,
+    health: Vec<DiskHealth>,
+    /// When health was last asked for; None until the first read
+    health_checked: Option<Instant>,
+    pending_health: Option<Task<Vec<DiskHealth>>>,
+    temperature_warning: f32,
 }
 
 impl DiskModule {
@@ -33,12 +48,23 @@ impl DiskModule {
             primary_disk_index: 0,
             last_update: Instant::now(),
             update_interval_ms: 5000,
+            health: Vec::new(),
+            health_checked: None,
+            pending_health: None,
+            temperature_warning: crate::config::DiskConfig::default().temperature_warning,
         }
     }
 
     /// Force an immediate update
     fn force_update(&mut self, config: &crate::config::Config) {
         self.query_disk_info();
+        self.temperature_warning = config.modules.disk.temperature_warning;
+        self.collect_health();
+        let due = self.health_checked.is_none_or(|checked| checked.elapsed() >= HEALTH_INTERVAL);
+        if due && self.pending_health.is_none() {
+            self.pending_health = Some(crate::runtime::fetch(smart::read_all));
+            self.health_checked = Some(Instant::now());
+        }
 
         // Respect configured primary disk if present (match by mount point or name)
         if !config.modules.disk.primary_disk.is_empty() {
@@ -118,6 +144,28 @@ impl DiskModule {
     pub fn get_disks(&self) -> &[DiskInfo] {
         &self.disks
     }
+
+    /// SMART health of each physical drive, as of the last read
+    pub fn health(&self) -> &[DiskHealth] {
+        &self.health
+    }
+
+    /// The verdict on a drive, with the configured temperature threshold
+    pub fn verdict(&self, disk: &DiskHealth) -> Health {
+        disk.verdict(self.temperature_warning)
+    }
+
+    /// Take the background health read once it's done
+    fn collect_health(&mut self) {
+        let Some(result) = self.pending_health.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.pending_health = None;
+        match result {
+            Ok(health) => self.health = health,
+            Err(e) => log::warn!("Failed to read drive health: {}", e),
+        }
+    }
 }
 
 impl Default for DiskModule {
@@ -150,12 +198,20 @@ impl Module for DiskModule {
         format!("💾 {}%", usage_percent)
     }
 
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        vec![("used", self.primary_usage_percent() as f32)]
+    }
+
     fn update(&mut self, config: &crate::config::Config) {
         if self.last_update.elapsed().as_millis() >= self.update_interval_ms as u128 {
             self.force_update(config);
         }
     }
 
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
     fn on_click(&mut self) {
         // Open File Explorer to This PC
         let _ = std::process::Command::new("explorer.exe")
@@ -173,7 +229,7 @@ impl Module for DiskModule {
             return None;
         }
 
-        let mut lines: Vec<String> = vec!["Disk Usage:".to_string()];
+        let mut lines: Vec<String> = vec![format!("{}:", tr("Disk Usage"))];
 
         for disk in &self.disks {
             let usage_percent = if disk.total_space > 0 {
@@ -195,9 +251,36 @@ impl Module for DiskModule {
             ));
         }
 
+        let latest = &sampler::samples().latest;
+        lines.push(format!(
+            "{}: {}/s  {}: {}/s",
+            tr("Read"),
+            format_bytes(latest.disk_read),
+            tr("Write"),
+            format_bytes(latest.disk_write)
+        ));
+
+        // Only drives that need attention; the menu lists every one
+        for disk in &self.health {
+            let verdict = self.verdict(disk);
+            if verdict != Health::Good {
+                lines.push(format!("⚠ {}: {}", disk.model, tr(verdict.label())));
+            }
+        }
+
         Some(lines.join("\n"))
     }
 
+    fn history(&self) -> Option<super::History> {
+        // Combined read + write throughput per sample, from the shared sampler
+        let samples = sampler::samples();
+        let (read, write) = (samples.disk_read.values(), samples.disk_write.values());
+        Some(super::History {
+            values: read.iter().zip(&write).map(|(read, write)| read + write).collect(),
+            max: None,
+        })
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,146 @@
+//! Events passed between modules
+//!
+//! Modules and the window publish named events ("network::connected",
+//! "power::on_battery", "theme::changed") and modules list the ones they want
+//! in `Module::subscriptions`, so one can react to another - the weather
+//! refetches when the network comes back - without the window downcasting to
+//! each. Events are queued and handed out by the registry on its next update,
+//! so publishing never reenters a module that's being updated.
+
+use log::debug;
+use parking_lot::Mutex;
+
+/// The network came back
+pub const NETWORK_CONNECTED: &str = "network::connected";
+/// The network went away
+pub const NETWORK_DISCONNECTED: &str = "network::disconnected";
+/// Unplugged from AC power
+pub const POWER_ON_BATTERY: &str = "power::on_battery";
+/// Plugged back in
+pub const POWER_ON_AC: &str = "power::on_ac";
+/// Battery saver switched on
+pub const POWER_SAVER_ON: &str = "power::saver_on";
+/// Battery saver switched off
+pub const POWER_SAVER_OFF: &str = "power::saver_off";
+/// The bar's theme changed (light/dark, accent, high contrast)
+pub const THEME_CHANGED: &str = "theme::changed";
+/// A device was attached or removed
+pub const DEVICES_CHANGED: &str = "devices::changed";
+/// A drive letter was added or removed (USB drive, memory card, network share)
+pub const VOLUMES_CHANGED: &str = "devices::volumes_changed";
+/// Night light was turned on or off
+pub const NIGHT_LIGHT_CHANGED: &str = "night_light::changed";
+
+static QUEUE: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// Held by tests that publish or take events, as the queue is shared
+#[cfg(test)]
+pub(super) static TEST_QUEUE: Mutex<()> = Mutex::new(());
+
+/// Queue an event for the modules subscribed to it
+pub fn publish(topic: &'static str) {
+    debug!("Event {}", topic);
+    let mut queue = QUEUE.lock();
+    // The same event twice in a row tells nobody anything new
+    if queue.last() != Some(&topic) {
+        queue.push(topic);
+    }
+}
+
+/// Events published since the last call, oldest first
+pub(super) fn take() -> Vec<&'static str> {
+    std::mem::take(&mut *QUEUE.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::modules::{Module, ModuleRegistry};
+    use std::any::Any;
+
+    /// Writes down the events it's handed
+    struct Listener {
+        id: &'static str,
+        received: Vec<String>,
+    }
+
+    impl Listener {
+        fn new(id: &'static str) -> Box<Self> {
+            Box::new(Self {
+                id,
+                received: Vec::new(),
+            })
+        }
+    }
+
+    impl Module for Listener {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn display_text(&self, _config: &Config) -> String {
+            String::new()
+        }
+
+        fn update(&mut self, _config: &Config) {}
+
+        fn subscriptions(&self) -> &'static [&'static str] {
+            &[NETWORK_CONNECTED, THEME_CHANGED]
+        }
+
+        fn on_event(&mut self, topic: &str, _config: &Config) {
+            self.received.push(topic.to_string());
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn received(registry: &ModuleRegistry, id: &str) -> Vec<String> {
+        registry.get(id).unwrap().as_any().downcast_ref::<Listener>().unwrap().received.clone()
+    }
+
+    #[test]
+    fn repeats_in_a_row_are_queued_once() {
+        let _queue = TEST_QUEUE.lock();
+        take();
+        publish(NETWORK_CONNECTED);
+        publish(NETWORK_CONNECTED);
+        publish(NETWORK_DISCONNECTED);
+        publish(NETWORK_CONNECTED);
+        assert_eq!(take(), vec![NETWORK_CONNECTED, NETWORK_DISCONNECTED, NETWORK_CONNECTED]);
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    fn dispatch_reaches_visible_subscribers() {
+        let config = Config::default();
+        let mut registry = ModuleRegistry::with_modules(vec![Listener::new("shown"), Listener::new("hidden")]);
+        registry.order_left.retain(|id| id != "hidden");
+        registry.dispatch(&[NETWORK_CONNECTED, POWER_ON_BATTERY, THEME_CHANGED], &config);
+        assert_eq!(received(&registry, "shown"), vec![NETWORK_CONNECTED, THEME_CHANGED]);
+        assert!(received(&registry, "hidden").is_empty());
+    }
+
+    #[test]
+    fn update_hands_out_published_events() {
+        let _queue = TEST_QUEUE.lock();
+        take();
+        let config = Config::default();
+        let mut registry = ModuleRegistry::with_modules(vec![Listener::new("listener")]);
+        publish(THEME_CHANGED);
+        registry.update_all(&config);
+        assert!(received(&registry, "listener").contains(&THEME_CHANGED.to_string()));
+        assert!(take().is_empty());
+    }
+}
@@ -1,12 +1,16 @@
 //! GPU module for displaying GPU usage and temperature
+//!
+//! Usage, video memory and the temperature all come from the shared sampler; the
+//! temperature is only read while it's shown.
 
-#![allow(unused_unsafe)]
-
-use std::collections::VecDeque;
 use std::time::Instant;
 
+use super::sampler::{self, Sensor};
 use super::Module;
-use windows::core::Interface;
+use crate::i18n::tr;
+
+/// Samples shown in the bar graph
+const GRAPH_LEN: usize = 60;
 
 /// GPU information
 #[derive(Debug, Clone, Default)]
@@ -22,10 +26,6 @@ pub struct GpuInfo {
 pub struct GpuModule {
     cached_text: String,
     gpu_info: GpuInfo,
-    // histories for moving graphs (percentages or scaled values)
-    usage_history: VecDeque<f32>,
-    memory_history: VecDeque<f32>,
-    history_len: usize,
     last_update: Instant,
     update_interval_ms: u64,
 }
@@ -35,20 +35,11 @@ impl GpuModule {
         let mut s = Self {
             cached_text: String::new(),
             gpu_info: GpuInfo::default(),
-            usage_history: VecDeque::with_capacity(60),
-            memory_history: VecDeque::with_capacity(60),
-            history_len: 60,
             last_update: Instant::now(),
             update_interval_ms: 2000,
         };
 
-        // Query once at startup for current values
         s.query_gpu_info();
-        
-        // Pre-fill histories with zeros so graphs start at zero and then draw up
-        s.usage_history = VecDeque::from(vec![0.0; s.history_len]);
-        s.memory_history = VecDeque::from(vec![0.0; s.history_len]);
-
         s.cached_text = s.build_display_text(&crate::config::Config::default());
 
         s
@@ -57,34 +48,33 @@ impl GpuModule {
     /// Force an immediate update
     fn force_update(&mut self, config: &crate::config::Config) {
         self.query_gpu_info();
-
-        // Update histories
-        self.usage_history.push_back(self.gpu_info.usage);
-        if self.usage_history.len() > self.history_len {
-            self.usage_history.pop_front();
-        }
-
-        if self.gpu_info.memory_total > 0 {
-            let mem_pct = (self.gpu_info.memory_used as f64 / self.gpu_info.memory_total as f64
-                * 100.0) as f32;
-            self.memory_history.push_back(mem_pct);
-            if self.memory_history.len() > self.history_len {
-                self.memory_history.pop_front();
-            }
-        }
-
         self.cached_text = self.build_display_text(config);
         self.last_update = Instant::now();
     }
 
     /// Get usage history (oldest to newest)
     pub fn usage_history(&self) -> Vec<f32> {
-        self.usage_history.iter().copied().collect()
+        sampler::samples().gpu.recent(GRAPH_LEN)
     }
 
     /// Get memory history (oldest to newest, percent)
     pub fn memory_history(&self) -> Vec<f32> {
-        self.memory_history.iter().copied().collect()
+        sampler::samples().gpu_memory.recent(GRAPH_LEN)
+    }
+
+    /// Usage history for the usage popup, oldest first (up to `sampler::HISTORY_LEN`)
+    pub fn full_usage_history(&self) -> Vec<f32> {
+        sampler::samples().gpu.values()
+    }
+
+    /// Processes using the GPU in the latest sample, busiest first
+    pub fn process_table(&self) -> Vec<sampler::GpuProcess> {
+        sampler::samples().latest.gpu_processes.clone()
+    }
+
+    /// Device name and current utilization
+    pub fn usage(&self) -> (&str, f32) {
+        (&self.gpu_info.name, self.gpu_info.usage)
     }
 
     /// Get current VRAM usage percent if available
@@ -96,215 +86,30 @@ impl GpuModule {
         }
     }
 
-    /// Query GPU information using Windows APIs
+    /// Take the latest GPU readings from the sampler
     fn query_gpu_info(&mut self) {
-        // First try PDH for usage
-        if !self.query_d3dkmt_info() {
-            // If PDH fails, at least get GPU names via DXGI
-            self.query_dxgi_adapter_info();
+        {
+            let samples = sampler::samples();
+            self.gpu_info.usage = samples.latest.gpu_usage;
+            self.gpu_info.memory_used = samples.latest.gpu_memory_used;
+            self.gpu_info.memory_total = samples.latest.gpu_memory_total;
+            self.gpu_info.name = samples.gpu_name.clone();
         }
-    }
-
-    /// Query D3DKMT for GPU information
-    fn query_d3dkmt_info(&mut self) -> bool {
-        // D3DKMT APIs require linking to gdi32.dll dynamically
-        // This is a simplified approach using performance counters
 
-        use windows::core::PCWSTR;
-        use windows::Win32::System::Performance::{
-            PdhAddEnglishCounterW, PdhCollectQueryData, PdhGetFormattedCounterValue, PdhOpenQueryW,
-            PDH_FMT_COUNTERVALUE, PDH_FMT_DOUBLE,
-        };
-
-        unsafe {
-            let mut query = 0isize;
-            let status = PdhOpenQueryW(PCWSTR::null(), 0, &mut query);
-            if status != 0 {
-                return false;
-            }
-
-            // Try multiple GPU Engine utilization counters
-            let counter_paths = [
-                "\\GPU Engine(*)\\Utilization Percentage",
-                "\\GPU Engine(pid_*)\\Utilization Percentage",
-                "\\GPU Engine(*)\\Utilization Percentage",
-            ];
-
-            for counter_path in &counter_paths {
-                let counter_path_wide = crate::utils::to_wide_string(counter_path);
-                let mut counter = 0isize;
-                let status = PdhAddEnglishCounterW(
-                    query,
-                    PCWSTR(counter_path_wide.as_ptr()),
-                    0,
-                    &mut counter,
-                );
-
-                if status == 0 {
-                    // Collect data
-                    let _ = PdhCollectQueryData(query);
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                    let _ = PdhCollectQueryData(query);
-
-                    // Try to get per-instance values via PdhGetFormattedCounterArrayW and sum them
-                    use windows::Win32::System::Performance::{
-                        PdhGetFormattedCounterArrayW, PDH_FMT_COUNTERVALUE_ITEM_W,
-                    };
-                    unsafe {
-                        let mut buf_size: u32 = 0;
-                        let mut item_count: u32 = 0;
-                        // First call to get required buffer size
-                        let status_array = PdhGetFormattedCounterArrayW(
-                            counter,
-                            PDH_FMT_DOUBLE,
-                            &mut buf_size,
-                            &mut item_count,
-                            Some(std::ptr::null_mut()),
-                        );
-                        if status_array == 0 && item_count > 0 {
-                            // Shouldn't happen since buffer is null, but handle anyway
-                        }
-
-                        if buf_size > 0 {
-                            // Allocate buffer
-                            let mut buffer: Vec<u8> = vec![0u8; buf_size as usize];
-                            let ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-                            let status_array2 = PdhGetFormattedCounterArrayW(
-                                counter,
-                                PDH_FMT_DOUBLE,
-                                &mut buf_size,
-                                &mut item_count,
-                                Some(ptr),
-                            );
-                            if status_array2 == 0 && item_count > 0 {
-                                let mut sum = 0.0f64;
-                                for i in 0..item_count as isize {
-                                    let item = ptr.offset(i);
-                                    let val = (*item).FmtValue.Anonymous.doubleValue;
-                                    sum += val;
-                                }
-                                // Average or clamp to 100
-                                let usage = sum.min(100.0) as f32;
-                                self.gpu_info.usage = usage;
-                                let _ = windows::Win32::System::Performance::PdhCloseQuery(query);
-                                return true;
-                            }
-                        }
-
-                        // Fallback to formatted counter value
-                        let mut value = PDH_FMT_COUNTERVALUE::default();
-                        if PdhGetFormattedCounterValue(counter, PDH_FMT_DOUBLE, None, &mut value)
-                            == 0
-                        {
-                            self.gpu_info.usage = value.Anonymous.doubleValue as f32;
-                            let _ = windows::Win32::System::Performance::PdhCloseQuery(query);
-                            return true;
-                        }
-                    }
-                }
-            }
-
-            // If all counters failed, use fallback
-            self.gpu_info.usage = self.estimate_gpu_usage();
-
-            // Try GPU adapter memory counter as fallback for memory used
-            let mem_path = crate::utils::to_wide_string("\\GPU Adapter Memory(*)\\Dedicated Bytes");
-            let mut mem_counter = 0isize;
-            if PdhAddEnglishCounterW(query, PCWSTR(mem_path.as_ptr()), 0, &mut mem_counter) == 0 {
-                let _ = PdhCollectQueryData(query);
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                let _ = PdhCollectQueryData(query);
-                let mut mem_value = PDH_FMT_COUNTERVALUE::default();
-                if PdhGetFormattedCounterValue(mem_counter, PDH_FMT_DOUBLE, None, &mut mem_value)
-                    == 0
-                {
-                    // mem_value is in bytes
-                    self.gpu_info.memory_used = mem_value.Anonymous.doubleValue as u64;
-                }
-            }
-
-            // Try GPU temperature counter if available
-            let temp_path = crate::utils::to_wide_string("\\GPU Temperature(*)\\Temperature");
-            let mut temp_counter = 0isize;
-            if PdhAddEnglishCounterW(query, PCWSTR(temp_path.as_ptr()), 0, &mut temp_counter) == 0 {
-                let _ = PdhCollectQueryData(query);
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                let _ = PdhCollectQueryData(query);
-                let mut temp_value = PDH_FMT_COUNTERVALUE::default();
-                if PdhGetFormattedCounterValue(temp_counter, PDH_FMT_DOUBLE, None, &mut temp_value)
-                    == 0
-                {
-                    self.gpu_info.temperature = Some(temp_value.Anonymous.doubleValue as f32);
-                }
-            }
-
-            // Close query
-            let _ = windows::Win32::System::Performance::PdhCloseQuery(query);
-            false
+        if crate::demo::is_enabled() {
+            let (_, _, _, temp) = crate::demo::gpu();
+            self.gpu_info.name = "Demo GPU".to_string();
+            self.gpu_info.temperature = Some(temp);
+            return;
         }
-    }
 
-    /// Estimate GPU usage from system metrics
-    fn estimate_gpu_usage(&self) -> f32 {
-        // For usage estimation, we can't easily get real-time usage without PDH
-        // Return 0 for now
-        0.0
+        // Read on the sampler thread while the module shows it (see `update`)
+        self.gpu_info.temperature = super::metrics::latest_thermal().gpu_temp;
     }
 
-    /// Query GPU adapter info using DXGI
-    fn query_dxgi_adapter_info(&mut self) {
-        use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1};
-
-        unsafe {
-            let factory: IDXGIFactory1 = match CreateDXGIFactory1() {
-                Ok(f) => f,
-                Err(_) => return,
-            };
-
-            for i in 0.. {
-                let adapter = match factory.EnumAdapters1(i) {
-                    Ok(a) => a,
-                    Err(_) => break,
-                };
-
-                if let Ok(desc) = adapter.GetDesc1() {
-                    // Convert the description to a string
-                    let name = String::from_utf16_lossy(&desc.Description);
-                    let name = name.trim_end_matches('\0').to_string();
-
-                    if self.gpu_info.name.is_empty() {
-                        self.gpu_info.name = name;
-                    }
-
-                    if self.gpu_info.memory_total == 0 {
-                        self.gpu_info.memory_total = desc.DedicatedVideoMemory as u64;
-                    }
-                }
-
-                // Try to query current video memory usage via IDXGIAdapter3 if available
-                if let Ok(adapter3) =
-                    adapter.cast::<windows::Win32::Graphics::Dxgi::IDXGIAdapter3>()
-                {
-                    use windows::Win32::Graphics::Dxgi::{
-                        DXGI_MEMORY_SEGMENT_GROUP, DXGI_QUERY_VIDEO_MEMORY_INFO,
-                    };
-                    unsafe {
-                        let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
-                        if adapter3
-                            .QueryVideoMemoryInfo(0, DXGI_MEMORY_SEGMENT_GROUP(0), &mut info)
-                            .is_ok()
-                        {
-                            // CurrentUsage is the number of bytes currently used
-                            self.gpu_info.memory_used = info.CurrentUsage;
-                            if self.gpu_info.memory_total == 0 {
-                                // If we didn't get dedicated memory earlier, set from budget
-                                self.gpu_info.memory_total = info.Budget;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    /// Name of the active temperature backend
+    pub fn temperature_source(&self) -> &'static str {
+        super::metrics::gpu_temperature_source()
     }
 
     /// Build the display text
@@ -313,23 +118,24 @@ impl GpuModule {
 
         // Usage remains configurable
         if config.modules.gpu.show_usage {
-            parts.push(format!("GPU {:.0}%", self.gpu_info.usage));
+            parts.push(format!("{} {:.0}%", tr("GPU"), self.gpu_info.usage));
         }
 
         // Always show VRAM percent if available
         if self.gpu_info.memory_total > 0 {
             let mem_percent = (self.gpu_info.memory_used as f64 / self.gpu_info.memory_total as f64
                 * 100.0) as u32;
-            parts.push(format!("VRAM {}%", mem_percent));
+            parts.push(format!("{} {}%", tr("VRAM"), mem_percent));
         }
 
-        // Always show temperature if available
-        if let Some(temp) = self.gpu_info.temperature {
-            parts.push(format!("{:.0}°C", temp));
+        if config.modules.gpu.show_temperature {
+            if let Some(temp) = self.gpu_info.temperature {
+                parts.push(format!("{:.0}°C", temp));
+            }
         }
 
         if parts.is_empty() {
-            "GPU".to_string()
+            tr("GPU")
         } else {
             parts.join("  ")
         }
@@ -356,7 +162,21 @@ impl Module for GpuModule {
         self.cached_text.clone()
     }
 
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        let mut metrics = vec![("usage", self.gpu_info.usage)];
+        if let Some(temp) = self.gpu_info.temperature {
+            metrics.push(("temperature", temp));
+        }
+        if let Some(memory) = self.memory_usage_percent() {
+            metrics.push(("memory", memory));
+        }
+        metrics
+    }
+
     fn update(&mut self, config: &crate::config::Config) {
+        if config.modules.gpu.show_temperature {
+            sampler::want(Sensor::GpuTemperature);
+        }
         // Use configurable update interval from config, with battery optimization
         let base_interval = config.modules.gpu.update_interval_ms;
         let effective_interval = base_interval * crate::utils::battery_update_multiplier();
@@ -366,6 +186,10 @@ impl Module for GpuModule {
         }
     }
 
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
     fn on_click(&mut self) {
         // Open task manager Performance tab
         let _ = std::process::Command::new("taskmgr.exe")
@@ -374,22 +198,29 @@ impl Module for GpuModule {
     }
 
     fn tooltip(&self) -> Option<String> {
-        let mut lines = vec![format!("GPU Usage: {:.1}%", self.gpu_info.usage)];
+        let mut lines = vec![format!("{}: {:.1}%", tr("GPU Usage"), self.gpu_info.usage)];
 
         if self.gpu_info.memory_total > 0 {
             lines.push(format!(
-                "VRAM: {} / {}",
+                "{}: {} / {}",
+                tr("VRAM"),
                 crate::utils::format_bytes(self.gpu_info.memory_used),
                 crate::utils::format_bytes(self.gpu_info.memory_total)
             ));
         }
 
         if let Some(temp) = self.gpu_info.temperature {
-            lines.push(format!("Temperature: {:.0}°C", temp));
+            let mut line = format!("{}: {:.0}°C", tr("Temperature"), temp);
+            // No backend in demo mode
+            let source = self.temperature_source();
+            if !source.is_empty() {
+                line.push_str(&format!(" ({})", source));
+            }
+            lines.push(line);
         }
 
         if !self.gpu_info.name.is_empty() {
-            lines.push(format!("Device: {}", self.gpu_info.name));
+            lines.push(format!("{}: {}", tr("Device"), self.gpu_info.name));
         }
 
         Some(lines.join("\n"))
@@ -409,6 +240,6 @@ impl Module for GpuModule {
 
     fn graph_values(&self) -> Option<Vec<f32>> {
         // Return GPU usage history (oldest to newest) so the renderer can draw a historical graph
-        Some(self.usage_history.iter().copied().collect())
+        Some(self.usage_history())
     }
 }
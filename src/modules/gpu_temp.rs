@@ -0,0 +1,417 @@
+//! GPU temperature providers
+//!
+//! Vendor libraries are loaded dynamically so TopBar still runs on machines without
+//! the corresponding driver installed:
+//! - NVIDIA: NVML (`nvml.dll`)
+//! - AMD: ADL (`atiadlxx.dll`)
+//! - Intel: IGCL (`ControlLib.dll`)
+//!
+//! When no vendor backend initializes, a WMI provider reads GPU sensors published by
+//! LibreHardwareMonitor / OpenHardwareMonitor if one of them is running.
+
+use log::{debug, info};
+use std::ffi::c_void;
+use windows::core::{PCSTR, PCWSTR};
+use windows::Win32::Foundation::{FreeLibrary, HMODULE};
+use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW};
+
+/// PCI vendor IDs reported by DXGI
+pub const VENDOR_NVIDIA: u32 = 0x10DE;
+pub const VENDOR_AMD: u32 = 0x1002;
+pub const VENDOR_INTEL: u32 = 0x8086;
+/// Microsoft Basic Render Driver (software adapter)
+const VENDOR_MICROSOFT: u32 = 0x1414;
+
+/// A source of GPU temperature readings
+pub trait GpuTempProvider: Send + Sync {
+    /// Short backend name (for logs and tooltips)
+    fn name(&self) -> &'static str;
+
+    /// Current GPU temperature in Celsius, if available
+    fn temperature(&mut self) -> Option<f32>;
+}
+
+/// Find the vendor ID of the first hardware adapter
+pub fn detect_primary_vendor() -> Option<u32> {
+    use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1};
+
+    unsafe {
+        let factory: IDXGIFactory1 = CreateDXGIFactory1().ok()?;
+        for i in 0.. {
+            let adapter = match factory.EnumAdapters1(i) {
+                Ok(a) => a,
+                Err(_) => break,
+            };
+            if let Ok(desc) = adapter.GetDesc1() {
+                if desc.VendorId != VENDOR_MICROSOFT {
+                    return Some(desc.VendorId);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Select the best temperature provider for the given adapter vendor.
+///
+/// Falls back to WMI when the vendor library is missing or fails to initialize.
+pub fn provider_for_vendor(vendor_id: Option<u32>) -> Box<dyn GpuTempProvider> {
+    let vendor: Option<Box<dyn GpuTempProvider>> = match vendor_id {
+        Some(VENDOR_NVIDIA) => NvmlProvider::load().map(|p| Box::new(p) as Box<dyn GpuTempProvider>),
+        Some(VENDOR_AMD) => AdlProvider::load().map(|p| Box::new(p) as Box<dyn GpuTempProvider>),
+        Some(VENDOR_INTEL) => IgclProvider::load().map(|p| Box::new(p) as Box<dyn GpuTempProvider>),
+        _ => None,
+    };
+
+    match vendor {
+        Some(p) => {
+            info!("GPU temperature: using {} backend", p.name());
+            p
+        }
+        None => {
+            info!("GPU temperature: no vendor backend for {:?}, using WMI fallback", vendor_id);
+            Box::new(WmiProvider)
+        }
+    }
+}
+
+/// Load a DLL by name, returning None if it's not installed
+unsafe fn load_library(name: &str) -> Option<HMODULE> {
+    let wide = crate::utils::to_wide_string(name);
+    LoadLibraryW(PCWSTR(wide.as_ptr())).ok()
+}
+
+/// Look up an export and cast it to the requested function pointer type
+unsafe fn load_symbol<T: Copy>(module: HMODULE, name: &[u8]) -> Option<T> {
+    debug_assert_eq!(name.last(), Some(&0));
+    let proc = GetProcAddress(module, PCSTR(name.as_ptr()))?;
+    Some(std::mem::transmute_copy(&proc))
+}
+
+// ---------------------------------------------------------------------------
+// NVIDIA NVML
+// ---------------------------------------------------------------------------
+
+type NvmlInit = unsafe extern "C" fn() -> i32;
+type NvmlShutdown = unsafe extern "C" fn() -> i32;
+type NvmlDeviceGetCount = unsafe extern "C" fn(*mut u32) -> i32;
+type NvmlDeviceGetHandleByIndex = unsafe extern "C" fn(u32, *mut *mut c_void) -> i32;
+type NvmlDeviceGetTemperature = unsafe extern "C" fn(*mut c_void, u32, *mut u32) -> i32;
+
+/// NVML_TEMPERATURE_GPU sensor
+const NVML_TEMPERATURE_GPU: u32 = 0;
+
+struct NvmlProvider {
+    module: usize,
+    device: usize,
+    shutdown: NvmlShutdown,
+    get_temperature: NvmlDeviceGetTemperature,
+}
+
+impl NvmlProvider {
+    fn load() -> Option<Self> {
+        unsafe {
+            let module = load_library("nvml.dll")?;
+            let provider = (|| {
+                let init: NvmlInit = load_symbol(module, b"nvmlInit_v2\0")?;
+                let shutdown: NvmlShutdown = load_symbol(module, b"nvmlShutdown\0")?;
+                let get_count: NvmlDeviceGetCount = load_symbol(module, b"nvmlDeviceGetCount_v2\0")?;
+                let get_handle: NvmlDeviceGetHandleByIndex =
+                    load_symbol(module, b"nvmlDeviceGetHandleByIndex_v2\0")?;
+                let get_temperature: NvmlDeviceGetTemperature =
+                    load_symbol(module, b"nvmlDeviceGetTemperature\0")?;
+
+                if init() != 0 {
+                    debug!("NVML: nvmlInit_v2 failed");
+                    return None;
+                }
+
+                let mut count = 0u32;
+                let mut device: *mut c_void = std::ptr::null_mut();
+                if get_count(&mut count) != 0 || count == 0 || get_handle(0, &mut device) != 0 {
+                    shutdown();
+                    return None;
+                }
+
+                Some(Self {
+                    module: module.0 as usize,
+                    device: device as usize,
+                    shutdown,
+                    get_temperature,
+                })
+            })();
+
+            if provider.is_none() {
+                let _ = FreeLibrary(module);
+            }
+            provider
+        }
+    }
+}
+
+impl GpuTempProvider for NvmlProvider {
+    fn name(&self) -> &'static str {
+        "NVML"
+    }
+
+    fn temperature(&mut self) -> Option<f32> {
+        let mut temp = 0u32;
+        let rc = unsafe {
+            (self.get_temperature)(self.device as *mut c_void, NVML_TEMPERATURE_GPU, &mut temp)
+        };
+        (rc == 0).then_some(temp as f32)
+    }
+}
+
+impl Drop for NvmlProvider {
+    fn drop(&mut self) {
+        unsafe {
+            (self.shutdown)();
+            let _ = FreeLibrary(HMODULE(self.module as *mut c_void));
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// AMD ADL
+// ---------------------------------------------------------------------------
+
+type AdlMallocCallback = unsafe extern "system" fn(i32) -> *mut c_void;
+type Adl2MainControlCreate = unsafe extern "C" fn(AdlMallocCallback, i32, *mut *mut c_void) -> i32;
+type Adl2MainControlDestroy = unsafe extern "C" fn(*mut c_void) -> i32;
+type Adl2AdapterNumberOfAdaptersGet = unsafe extern "C" fn(*mut c_void, *mut i32) -> i32;
+type Adl2Overdrive6TemperatureGet = unsafe extern "C" fn(*mut c_void, i32, *mut i32) -> i32;
+
+extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+}
+
+/// Allocation callback required by ADL; ADL never hands this memory back to us
+unsafe extern "system" fn adl_malloc(size: i32) -> *mut c_void {
+    malloc(size.max(0) as usize)
+}
+
+struct AdlProvider {
+    module: usize,
+    context: usize,
+    adapter_index: i32,
+    destroy: Adl2MainControlDestroy,
+    get_temperature: Adl2Overdrive6TemperatureGet,
+}
+
+impl AdlProvider {
+    fn load() -> Option<Self> {
+        unsafe {
+            let module = load_library("atiadlxx.dll").or_else(|| load_library("atiadlxy.dll"))?;
+            let provider = (|| {
+                let create: Adl2MainControlCreate = load_symbol(module, b"ADL2_Main_Control_Create\0")?;
+                let destroy: Adl2MainControlDestroy = load_symbol(module, b"ADL2_Main_Control_Destroy\0")?;
+                let adapter_count: Adl2AdapterNumberOfAdaptersGet =
+                    load_symbol(module, b"ADL2_Adapter_NumberOfAdapters_Get\0")?;
+                let get_temperature: Adl2Overdrive6TemperatureGet =
+                    load_symbol(module, b"ADL2_Overdrive6_Temperature_Get\0")?;
+
+                let mut context: *mut c_void = std::ptr::null_mut();
+                if create(adl_malloc, 1, &mut context) != 0 {
+                    debug!("ADL: ADL2_Main_Control_Create failed");
+                    return None;
+                }
+
+                // ADL lists one logical adapter per display output; use the first one that reports a temperature
+                let mut count = 0i32;
+                adapter_count(context, &mut count);
+                let adapter_index = (0..count).find(|&i| {
+                    let mut millidegrees = 0i32;
+                    get_temperature(context, i, &mut millidegrees) == 0 && millidegrees > 0
+                });
+
+                match adapter_index {
+                    Some(adapter_index) => Some(Self {
+                        module: module.0 as usize,
+                        context: context as usize,
+                        adapter_index,
+                        destroy,
+                        get_temperature,
+                    }),
+                    None => {
+                        destroy(context);
+                        None
+                    }
+                }
+            })();
+
+            if provider.is_none() {
+                let _ = FreeLibrary(module);
+            }
+            provider
+        }
+    }
+}
+
+impl GpuTempProvider for AdlProvider {
+    fn name(&self) -> &'static str {
+        "ADL"
+    }
+
+    fn temperature(&mut self) -> Option<f32> {
+        let mut millidegrees = 0i32;
+        let rc = unsafe {
+            (self.get_temperature)(self.context as *mut c_void, self.adapter_index, &mut millidegrees)
+        };
+        (rc == 0).then_some(millidegrees as f32 / 1000.0)
+    }
+}
+
+impl Drop for AdlProvider {
+    fn drop(&mut self) {
+        unsafe {
+            (self.destroy)(self.context as *mut c_void);
+            let _ = FreeLibrary(HMODULE(self.module as *mut c_void));
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Intel IGCL
+// ---------------------------------------------------------------------------
+
+/// ctl_init_args_t
+#[repr(C)]
+#[derive(Default)]
+struct CtlInitArgs {
+    size: u32,
+    version: u8,
+    app_version: u32,
+    flags: u32,
+    supported_version: u32,
+    application_uid: [u8; 16],
+}
+
+type CtlInit = unsafe extern "C" fn(*mut CtlInitArgs, *mut *mut c_void) -> i32;
+type CtlClose = unsafe extern "C" fn(*mut c_void) -> i32;
+type CtlEnumerateDevices = unsafe extern "C" fn(*mut c_void, *mut u32, *mut *mut c_void) -> i32;
+type CtlEnumTemperatureSensors = unsafe extern "C" fn(*mut c_void, *mut u32, *mut *mut c_void) -> i32;
+type CtlTemperatureGetState = unsafe extern "C" fn(*mut c_void, *mut f64) -> i32;
+
+/// CTL_MAKE_VERSION(1, 0)
+const CTL_IMPL_VERSION: u32 = 1 << 16;
+
+struct IgclProvider {
+    module: usize,
+    api_handle: usize,
+    sensors: Vec<usize>,
+    close: CtlClose,
+    get_state: CtlTemperatureGetState,
+}
+
+impl IgclProvider {
+    fn load() -> Option<Self> {
+        unsafe {
+            let module = load_library("ControlLib.dll")?;
+            let provider = (|| {
+                let init: CtlInit = load_symbol(module, b"ctlInit\0")?;
+                let close: CtlClose = load_symbol(module, b"ctlClose\0")?;
+                let enum_devices: CtlEnumerateDevices = load_symbol(module, b"ctlEnumerateDevices\0")?;
+                let enum_sensors: CtlEnumTemperatureSensors =
+                    load_symbol(module, b"ctlEnumTemperatureSensors\0")?;
+                let get_state: CtlTemperatureGetState = load_symbol(module, b"ctlTemperatureGetState\0")?;
+
+                let mut args = CtlInitArgs {
+                    size: std::mem::size_of::<CtlInitArgs>() as u32,
+                    app_version: CTL_IMPL_VERSION,
+                    ..Default::default()
+                };
+                let mut api_handle: *mut c_void = std::ptr::null_mut();
+                if init(&mut args, &mut api_handle) != 0 {
+                    debug!("IGCL: ctlInit failed");
+                    return None;
+                }
+
+                // Collect all temperature sensors of the first device
+                let mut sensors = Vec::new();
+                let mut device_count = 0u32;
+                if enum_devices(api_handle, &mut device_count, std::ptr::null_mut()) == 0 && device_count > 0 {
+                    let mut devices = vec![std::ptr::null_mut(); device_count as usize];
+                    if enum_devices(api_handle, &mut device_count, devices.as_mut_ptr()) == 0 {
+                        let mut sensor_count = 0u32;
+                        if enum_sensors(devices[0], &mut sensor_count, std::ptr::null_mut()) == 0 && sensor_count > 0 {
+                            let mut handles = vec![std::ptr::null_mut(); sensor_count as usize];
+                            if enum_sensors(devices[0], &mut sensor_count, handles.as_mut_ptr()) == 0 {
+                                sensors = handles.into_iter().map(|h| h as usize).collect();
+                            }
+                        }
+                    }
+                }
+
+                if sensors.is_empty() {
+                    close(api_handle);
+                    return None;
+                }
+
+                Some(Self {
+                    module: module.0 as usize,
+                    api_handle: api_handle as usize,
+                    sensors,
+                    close,
+                    get_state,
+                })
+            })();
+
+            if provider.is_none() {
+                let _ = FreeLibrary(module);
+            }
+            provider
+        }
+    }
+}
+
+impl GpuTempProvider for IgclProvider {
+    fn name(&self) -> &'static str {
+        "IGCL"
+    }
+
+    fn temperature(&mut self) -> Option<f32> {
+        // Report the hottest sensor (GPU core, memory or global)
+        self.sensors
+            .iter()
+            .filter_map(|&sensor| {
+                let mut temp = 0f64;
+                let rc = unsafe { (self.get_state)(sensor as *mut c_void, &mut temp) };
+                (rc == 0 && temp > 0.0).then_some(temp as f32)
+            })
+            .fold(None, |max: Option<f32>, t| Some(max.map_or(t, |m| m.max(t))))
+    }
+}
+
+impl Drop for IgclProvider {
+    fn drop(&mut self) {
+        unsafe {
+            (self.close)(self.api_handle as *mut c_void);
+            let _ = FreeLibrary(HMODULE(self.module as *mut c_void));
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// WMI fallback
+// ---------------------------------------------------------------------------
+
+/// Reads GPU temperature sensors published by hardware monitoring tools over WMI
+struct WmiProvider;
+
+impl GpuTempProvider for WmiProvider {
+    fn name(&self) -> &'static str {
+        "WMI"
+    }
+
+    fn temperature(&mut self) -> Option<f32> {
+        const QUERY: &str =
+            "SELECT Value FROM Sensor WHERE SensorType='Temperature' AND Identifier LIKE '%gpu%'";
+
+        super::metrics::HARDWARE_MONITOR_NAMESPACES
+            .iter()
+            .map(|ns| super::metrics::query_wmi_numbers(ns, QUERY, "Value"))
+            .find(|values| !values.is_empty())
+            .and_then(|values| values.into_iter().reduce(f32::max))
+    }
+}
@@ -1,10 +1,23 @@
 //! Keyboard layout module - shows current input language/layout
+//!
+//! Clicking opens a dropdown of the installed input locales. Picking one switches the
+//! bar's own thread with `ActivateKeyboardLayout` and asks the focused app to switch
+//! too, since a layout belongs to each thread. Labels can be replaced per layout in
+//! config, or prefixed with the flag of the locale's region.
 
 #![allow(dead_code)]
 
 use std::time::Instant;
-use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
-use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::Globalization::{
+    GetLocaleInfoW, LCIDToLocaleName, LOCALE_SISO639LANGNAME, LOCALE_SLOCALIZEDDISPLAYNAME,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    ActivateKeyboardLayout, GetKeyboardLayout, GetKeyboardLayoutList, HKL, KLF_SETFORPROCESS,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, PostMessageW, WM_INPUTLANGCHANGEREQUEST,
+};
 
 use super::Module;
 
@@ -39,13 +52,101 @@ const LANG_NORWEGIAN: u16 = 0x14;
 const LANG_DANISH: u16 = 0x06;
 const LANG_FINNISH: u16 = 0x0B;
 
+/// An installed input locale
+#[derive(Debug, Clone)]
+pub struct InputLayout {
+    pub hkl: Hkl,
+    /// Locale name, e.g. "en-US"
+    pub locale: String,
+    /// Short language code, e.g. "EN"
+    pub code: String,
+    /// Display name in the UI language, e.g. "English (United States)"
+    pub name: String,
+}
+
+impl InputLayout {
+    fn from_hkl(hkl: Hkl) -> Self {
+        // Low word of the HKL is the language identifier
+        let lang_id = (hkl as usize & 0xFFFF) as u32;
+        let locale = locale_string(|buf| unsafe { LCIDToLocaleName(lang_id, Some(buf), 0) });
+        let (mut code, mut name) = language_info((lang_id & 0x3FF) as u16);
+        if code == "??" {
+            let iso = locale_string(|buf| unsafe { GetLocaleInfoW(lang_id, LOCALE_SISO639LANGNAME, Some(buf)) });
+            if !iso.is_empty() {
+                code = iso.to_uppercase();
+            }
+        }
+        let display = locale_string(|buf| unsafe { GetLocaleInfoW(lang_id, LOCALE_SLOCALIZEDDISPLAYNAME, Some(buf)) });
+        if !display.is_empty() {
+            name = display;
+        }
+        Self { hkl, locale, code, name }
+    }
+
+    /// Flag emoji for the locale's region ("en-US" -> 🇺🇸), built from regional indicators
+    pub fn flag(&self) -> Option<String> {
+        let region = self.locale.rsplit('-').next()?;
+        if region.len() != 2 || !region.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        region
+            .to_ascii_uppercase()
+            .chars()
+            .map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+            .collect()
+    }
+
+    /// Text shown on the bar: a custom label if configured, otherwise flag and code/name
+    pub fn label(&self, config: &crate::config::KeyboardLayoutConfig) -> String {
+        if let Some(label) = config.labels.get(&self.locale).or_else(|| config.labels.get(&self.code)) {
+            return label.clone();
+        }
+        let text = if config.show_full_name { &self.name } else { &self.code };
+        match self.flag().filter(|_| config.show_flag) {
+            Some(flag) => format!("{} {}", flag, text),
+            None => format!("🌐 {}", text),
+        }
+    }
+}
+
+/// Read a string from a Win32 call that fills a UTF-16 buffer and returns its length
+/// including the terminator
+fn locale_string(fill: impl FnOnce(&mut [u16]) -> i32) -> String {
+    let mut buffer = [0u16; 128];
+    let len = fill(&mut buffer);
+    if len <= 1 {
+        return String::new();
+    }
+    String::from_utf16_lossy(&buffer[..len as usize - 1])
+}
+
+/// All input locales installed for the user, in the order Windows cycles them
+pub fn installed_layouts() -> Vec<InputLayout> {
+    unsafe {
+        let count = GetKeyboardLayoutList(None);
+        if count <= 0 {
+            return Vec::new();
+        }
+        let mut list = vec![HKL::default(); count as usize];
+        let count = GetKeyboardLayoutList(Some(&mut list));
+        list.truncate(count.max(0) as usize);
+        list.into_iter()
+            .map(|hkl| InputLayout::from_hkl(hkl.0 as isize))
+            .collect()
+    }
+}
+
 /// Keyboard layout module
 pub struct KeyboardLayoutModule {
     cached_text: String,
     current_layout: Hkl,
-    language_code: String,
-    language_name: String,
+    layout: InputLayout,
+    layouts: Vec<InputLayout>,
     last_update: Instant,
+    /// Show the new layout on screen after a switch from the bar
+    osd: bool,
+    /// A switch from the bar is waiting to take effect
+    announce: bool,
 }
 
 impl KeyboardLayoutModule {
@@ -53,9 +154,11 @@ impl KeyboardLayoutModule {
         let mut module = Self {
             cached_text: String::new(),
             current_layout: 0,
-            language_code: String::new(),
-            language_name: String::new(),
+            layout: InputLayout::from_hkl(0),
+            layouts: installed_layouts(),
             last_update: Instant::now(),
+            osd: true,
+            announce: false,
         };
         module.force_update();
         module
@@ -63,8 +166,14 @@ impl KeyboardLayoutModule {
 
     /// Force an immediate update
     fn force_update(&mut self) {
+        let previous = self.current_layout;
         self.query_keyboard_layout();
-        self.cached_text = self.language_code.clone();
+        if self.announce && self.current_layout != previous {
+            self.announce = false;
+            let icon = self.layout.flag().unwrap_or_else(|| "⌨".to_string());
+            crate::render::show_osd(&self.layout.name, &icon);
+        }
+        self.cached_text = self.layout.code.clone();
         self.last_update = Instant::now();
     }
 
@@ -80,70 +189,55 @@ impl KeyboardLayoutModule {
             };
 
             let layout = GetKeyboardLayout(thread_id);
+            if layout.0 as isize == self.current_layout {
+                return;
+            }
             self.current_layout = layout.0 as isize;
-
-            // Extract language ID (low word of HKL)
-            let lang_id = (self.current_layout as usize & 0xFFFF) as u16;
-            let primary_lang = lang_id & 0x3FF;
-
-            // Map language ID to code and name
-            let (code, name) = self.get_language_info(primary_lang);
-            self.language_code = code;
-            self.language_name = name;
-        }
-    }
-
-    /// Get language code and name from primary language ID
-    fn get_language_info(&self, primary_lang: u16) -> (String, String) {
-        match primary_lang {
-            LANG_ENGLISH => ("EN".to_string(), "English".to_string()),
-            LANG_SPANISH => ("ES".to_string(), "Spanish".to_string()),
-            LANG_FRENCH => ("FR".to_string(), "French".to_string()),
-            LANG_GERMAN => ("DE".to_string(), "German".to_string()),
-            LANG_ITALIAN => ("IT".to_string(), "Italian".to_string()),
-            LANG_PORTUGUESE => ("PT".to_string(), "Portuguese".to_string()),
-            LANG_RUSSIAN => ("RU".to_string(), "Russian".to_string()),
-            LANG_CHINESE => ("ZH".to_string(), "Chinese".to_string()),
-            LANG_JAPANESE => ("JA".to_string(), "Japanese".to_string()),
-            LANG_KOREAN => ("KO".to_string(), "Korean".to_string()),
-            LANG_ARABIC => ("AR".to_string(), "Arabic".to_string()),
-            LANG_HEBREW => ("HE".to_string(), "Hebrew".to_string()),
-            LANG_POLISH => ("PL".to_string(), "Polish".to_string()),
-            LANG_DUTCH => ("NL".to_string(), "Dutch".to_string()),
-            LANG_TURKISH => ("TR".to_string(), "Turkish".to_string()),
-            LANG_VIETNAMESE => ("VI".to_string(), "Vietnamese".to_string()),
-            LANG_THAI => ("TH".to_string(), "Thai".to_string()),
-            LANG_HINDI => ("HI".to_string(), "Hindi".to_string()),
-            LANG_UKRAINIAN => ("UK".to_string(), "Ukrainian".to_string()),
-            LANG_CZECH => ("CS".to_string(), "Czech".to_string()),
-            LANG_GREEK => ("EL".to_string(), "Greek".to_string()),
-            LANG_SWEDISH => ("SV".to_string(), "Swedish".to_string()),
-            LANG_NORWEGIAN => ("NO".to_string(), "Norwegian".to_string()),
-            LANG_DANISH => ("DA".to_string(), "Danish".to_string()),
-            LANG_FINNISH => ("FI".to_string(), "Finnish".to_string()),
-            LANG_LITHUANIAN => ("LT".to_string(), "Lithuanian".to_string()),
-            _ => ("??".to_string(), "Unknown".to_string()),
+            self.layout = InputLayout::from_hkl(self.current_layout);
         }
     }
 
     /// Get current language code
     pub fn language_code(&self) -> &str {
-        &self.language_code
+        &self.layout.code
     }
 
     /// Get current language name
     pub fn language_name(&self) -> &str {
-        &self.language_name
+        &self.layout.name
+    }
+
+    /// Installed layouts (refreshed when the dropdown is opened)
+    pub fn layouts(&mut self) -> &[InputLayout] {
+        self.layouts = installed_layouts();
+        &self.layouts
+    }
+
+    /// Whether a layout is the one the focused app is using
+    pub fn is_current(&self, hkl: Hkl) -> bool {
+        self.current_layout == hkl
+    }
+
+    /// Switch the focused app (and the bar) to a specific layout
+    pub fn activate_layout(&mut self, hkl: Hkl) {
+        unsafe {
+            let handle = HKL(hkl as *mut std::ffi::c_void);
+            let _ = ActivateKeyboardLayout(handle, KLF_SETFORPROCESS);
+            // The bar never takes focus, so the foreground window is still the user's app
+            let hwnd = GetForegroundWindow();
+            if !hwnd.0.is_null() {
+                let _ = PostMessageW(hwnd, WM_INPUTLANGCHANGEREQUEST, WPARAM(0), LPARAM(hkl));
+            }
+        }
+
+        // Update after a short delay
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        self.announce = self.osd;
+        self.force_update();
     }
 
     /// Switch to next keyboard layout
     pub fn switch_layout(&mut self) {
-        use windows::Win32::Foundation::LPARAM;
-        use windows::Win32::Foundation::WPARAM;
-        use windows::Win32::UI::WindowsAndMessaging::{
-            GetForegroundWindow, PostMessageW, WM_INPUTLANGCHANGEREQUEST,
-        };
-
         unsafe {
             let hwnd = GetForegroundWindow();
             if !hwnd.0.is_null() {
@@ -155,10 +249,44 @@ impl KeyboardLayoutModule {
 
         // Update after a short delay
         std::thread::sleep(std::time::Duration::from_millis(50));
+        self.announce = self.osd;
         self.force_update();
     }
 }
 
+/// Get language code and name from a primary language ID
+fn language_info(primary_lang: u16) -> (String, String) {
+    match primary_lang {
+        LANG_ENGLISH => ("EN".to_string(), "English".to_string()),
+        LANG_SPANISH => ("ES".to_string(), "Spanish".to_string()),
+        LANG_FRENCH => ("FR".to_string(), "French".to_string()),
+        LANG_GERMAN => ("DE".to_string(), "German".to_string()),
+        LANG_ITALIAN => ("IT".to_string(), "Italian".to_string()),
+        LANG_PORTUGUESE => ("PT".to_string(), "Portuguese".to_string()),
+        LANG_RUSSIAN => ("RU".to_string(), "Russian".to_string()),
+        LANG_CHINESE => ("ZH".to_string(), "Chinese".to_string()),
+        LANG_JAPANESE => ("JA".to_string(), "Japanese".to_string()),
+        LANG_KOREAN => ("KO".to_string(), "Korean".to_string()),
+        LANG_ARABIC => ("AR".to_string(), "Arabic".to_string()),
+        LANG_HEBREW => ("HE".to_string(), "Hebrew".to_string()),
+        LANG_POLISH => ("PL".to_string(), "Polish".to_string()),
+        LANG_DUTCH => ("NL".to_string(), "Dutch".to_string()),
+        LANG_TURKISH => ("TR".to_string(), "Turkish".to_string()),
+        LANG_VIETNAMESE => ("VI".to_string(), "Vietnamese".to_string()),
+        LANG_THAI => ("TH".to_string(), "Thai".to_string()),
+        LANG_HINDI => ("HI".to_string(), "Hindi".to_string()),
+        LANG_UKRAINIAN => ("UK".to_string(), "Ukrainian".to_string()),
+        LANG_CZECH => ("CS".to_string(), "Czech".to_string()),
+        LANG_GREEK => ("EL".to_string(), "Greek".to_string()),
+        LANG_SWEDISH => ("SV".to_string(), "Swedish".to_string()),
+        LANG_NORWEGIAN => ("NO".to_string(), "Norwegian".to_string()),
+        LANG_DANISH => ("DA".to_string(), "Danish".to_string()),
+        LANG_FINNISH => ("FI".to_string(), "Finnish".to_string()),
+        LANG_LITHUANIAN => ("LT".to_string(), "Lithuanian".to_string()),
+        _ => ("??".to_string(), "Unknown".to_string()),
+    }
+}
+
 impl Default for KeyboardLayoutModule {
     fn default() -> Self {
         Self::new()
@@ -175,17 +303,16 @@ impl Module for KeyboardLayoutModule {
     }
 
     fn display_text(&self, config: &crate::config::Config) -> String {
-        if config.modules.keyboard_layout.show_full_name {
-            format!("🌐 {}", self.language_name)
-        } else {
-            format!("🌐 {}", self.language_code)
-        }
+        self.layout.label(&config.modules.keyboard_layout)
     }
 
-    fn update(&mut self, _config: &crate::config::Config) {
+    fn update(&mut self, config: &crate::config::Config) {
+        self.osd = config.osd.keyboard_layout;
         // Update every 500ms to catch keyboard layout changes
         if self.last_update.elapsed().as_millis() >= 500 {
             self.force_update();
+            // A switch the app didn't take shouldn't announce a later one
+            self.announce = false;
         }
     }
 
@@ -200,8 +327,8 @@ impl Module for KeyboardLayoutModule {
 
     fn tooltip(&self) -> Option<String> {
         Some(format!(
-            "Keyboard Layout: {}\nClick to switch layout",
-            self.language_name
+            "Keyboard Layout: {}\nClick to choose a layout",
+            self.layout.name
         ))
     }
 
@@ -0,0 +1,116 @@
+//! App launcher module - a dock-like row of pinned app icons
+//!
+//! Apps come from `[[modules.launcher.apps]]`. The renderer draws one icon per app
+//! (using the same shell icon cache as the active app) and registers each icon's
+//! bounds as `launcher:<index>`, which the click handlers map back to an app.
+
+use log::{info, warn};
+use windows::core::PCWSTR;
+
+use super::Module;
+use crate::config::PinnedApp;
+use crate::utils::to_wide_string;
+
+/// Prefix of the per-icon ids used for hit testing
+pub const ITEM_PREFIX: &str = "launcher:";
+
+/// Index of the pinned app for a `launcher:<index>` hit-test id
+pub fn item_index(id: &str) -> Option<usize> {
+    id.strip_prefix(ITEM_PREFIX)?.parse().ok()
+}
+
+/// Launch a pinned app, optionally elevated (UAC prompt)
+pub fn launch(app: &PinnedApp, as_admin: bool) {
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    info!("Launcher: starting {}{}", app.path, if as_admin { " (admin)" } else { "" });
+    let verb = to_wide_string(if as_admin { "runas" } else { "open" });
+    let file = to_wide_string(&app.path);
+    let args = app.args.as_deref().map(to_wide_string);
+    // Start in the app's own folder, as Explorer does
+    let dir = std::path::Path::new(&app.path)
+        .parent()
+        .map(|d| to_wide_string(&d.to_string_lossy()));
+
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            PCWSTR(verb.as_ptr()),
+            PCWSTR(file.as_ptr()),
+            args.as_ref().map(|a| PCWSTR(a.as_ptr())).unwrap_or(PCWSTR::null()),
+            dir.as_ref().map(|d| PCWSTR(d.as_ptr())).unwrap_or(PCWSTR::null()),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values <= 32 are errors (including the user declining the UAC prompt)
+    if result.0 as isize <= 32 {
+        warn!("Launcher: failed to start {}", app.path);
+    }
+}
+
+/// App launcher module
+pub struct LauncherModule {
+    apps: Vec<PinnedApp>,
+}
+
+impl LauncherModule {
+    pub fn new() -> Self {
+        Self { apps: Vec::new() }
+    }
+
+    /// Pinned apps, in display order
+    pub fn apps(&self) -> &[PinnedApp] {
+        &self.apps
+    }
+}
+
+impl Default for LauncherModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for LauncherModule {
+    fn id(&self) -> &str {
+        "launcher"
+    }
+
+    fn name(&self) -> &str {
+        "App Launcher"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        // Icons only; the renderer draws them
+        String::new()
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        let apps = &config.modules.launcher.apps;
+        let changed = apps.len() != self.apps.len()
+            || apps
+                .iter()
+                .zip(&self.apps)
+                .any(|(a, b)| a.path != b.path || a.name != b.name || a.args != b.args);
+        if changed {
+            self.apps = apps.clone();
+        }
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let names: Vec<String> = self.apps.iter().map(|a| a.display_name()).collect();
+        Some(names.join(", "))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.apps.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
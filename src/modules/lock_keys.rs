@@ -0,0 +1,176 @@
+//! Lock keys module - Caps Lock, Num Lock and Scroll Lock state
+//!
+//! Polls the toggle bit of each key and shows a short label per lock; the renderer
+//! highlights the ones that are on. When a lock changes, an optional on-screen
+//! indicator briefly shows the new state, like a laptop's OSD.
+
+use std::time::{Duration, Instant};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, VIRTUAL_KEY, VK_CAPITAL, VK_NUMLOCK, VK_SCROLL,
+};
+
+use super::Module;
+
+/// How often the key states are polled
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A lock key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKey {
+    Caps,
+    Num,
+    Scroll,
+}
+
+impl LockKey {
+    pub const ALL: [LockKey; 3] = [Self::Caps, Self::Num, Self::Scroll];
+
+    /// Full name, used in the tooltip and on-screen indicator
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Caps => "Caps Lock",
+            Self::Num => "Num Lock",
+            Self::Scroll => "Scroll Lock",
+        }
+    }
+
+    /// Short label drawn on the bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Caps => "A",
+            Self::Num => "1",
+            Self::Scroll => "↧",
+        }
+    }
+
+    fn virtual_key(&self) -> VIRTUAL_KEY {
+        match self {
+            Self::Caps => VK_CAPITAL,
+            Self::Num => VK_NUMLOCK,
+            Self::Scroll => VK_SCROLL,
+        }
+    }
+
+    /// Whether the key is toggled on (low bit of the key state)
+    fn is_on(&self) -> bool {
+        unsafe { GetKeyState(self.virtual_key().0 as i32) & 1 != 0 }
+    }
+
+    /// Whether the key is shown with the given settings
+    fn is_shown(&self, config: &crate::config::LockKeysConfig) -> bool {
+        match self {
+            Self::Caps => config.show_caps_lock,
+            Self::Num => config.show_num_lock,
+            Self::Scroll => config.show_scroll_lock,
+        }
+    }
+}
+
+/// Lock keys module
+pub struct LockKeysModule {
+    /// On/off per key, in `LockKey::ALL` order (None until the first poll)
+    states: Option<[bool; 3]>,
+    /// Keys to draw, with their state
+    visible: Vec<(LockKey, bool)>,
+    last_update: Option<Instant>,
+}
+
+impl LockKeysModule {
+    pub fn new() -> Self {
+        Self {
+            states: None,
+            visible: Vec::new(),
+            last_update: None,
+        }
+    }
+
+    /// Force an immediate update
+    fn force_update(&mut self, config: &crate::config::Config) {
+        let cfg = &config.modules.lock_keys;
+        let states = LockKey::ALL.map(|key| key.is_on());
+
+        // Announce changes, but not the initial state at startup
+        if let Some(previous) = self.states {
+            for (i, key) in LockKey::ALL.iter().enumerate() {
+                if previous[i] != states[i] && cfg.on_screen_indicator && key.is_shown(cfg) {
+                    let text = format!("{} {}", key.name(), if states[i] { "On" } else { "Off" });
+                    crate::render::show_lock_indicator(&text, states[i], cfg.indicator_duration_ms);
+                }
+            }
+        }
+        self.states = Some(states);
+
+        self.visible = LockKey::ALL
+            .iter()
+            .zip(states)
+            .filter(|(key, on)| key.is_shown(cfg) && (*on || !cfg.only_active))
+            .map(|(key, on)| (*key, on))
+            .collect();
+        self.last_update = Some(Instant::now());
+    }
+
+    /// Keys to draw and whether each is on
+    pub fn keys(&self) -> &[(LockKey, bool)] {
+        &self.visible
+    }
+}
+
+impl Default for LockKeysModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for LockKeysModule {
+    fn id(&self) -> &str {
+        "lock_keys"
+    }
+
+    fn name(&self) -> &str {
+        "Lock Keys"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.visible
+            .iter()
+            .map(|(key, _)| key.label())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        let due = self
+            .last_update
+            .map(|t| t.elapsed() >= UPDATE_INTERVAL)
+            .unwrap_or(true);
+        if due {
+            self.force_update(config);
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let states = self.states?;
+        let lines: Vec<String> = LockKey::ALL
+            .iter()
+            .zip(states)
+            .map(|(key, on)| format!("{}: {}", key.name(), if on { "On" } else { "Off" }))
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.visible.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
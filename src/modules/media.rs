@@ -52,6 +52,18 @@ impl MediaModule {
         // (SystemMediaTransportControlsSessionManager) to get media info
         // from apps like Spotify, browser media, etc.
 
+        if crate::demo::is_enabled() {
+            let (title, artist, album, playing) = crate::demo::track();
+            self.track_title = Some(title.to_string());
+            self.track_artist = Some(artist.to_string());
+            self.track_album = Some(album.to_string());
+            self.playback_state = if playing {
+                PlaybackState::Playing
+            } else {
+                PlaybackState::Paused
+            };
+        }
+
         // For now, show placeholder when nothing is playing
         self.cached_text = self.build_display_text();
         self.last_update = Instant::now();
@@ -112,62 +124,17 @@ impl MediaModule {
         self.cached_text = self.build_display_text();
 
         // Send media key
-        self.send_media_key(MediaKey::PlayPause);
+        send_media_key(MediaKey::PlayPause);
     }
 
     /// Send previous track command
     pub fn previous(&mut self) {
-        self.send_media_key(MediaKey::Previous);
+        send_media_key(MediaKey::Previous);
     }
 
     /// Send next track command
     pub fn next(&mut self) {
-        self.send_media_key(MediaKey::Next);
-    }
-
-    /// Send a media key
-    fn send_media_key(&self, key: MediaKey) {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP,
-            VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK,
-        };
-
-        let vk = match key {
-            MediaKey::PlayPause => VK_MEDIA_PLAY_PAUSE,
-            MediaKey::Previous => VK_MEDIA_PREV_TRACK,
-            MediaKey::Next => VK_MEDIA_NEXT_TRACK,
-        };
-
-        unsafe {
-            let inputs = [
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: vk,
-                            wScan: 0,
-                            dwFlags: KEYEVENTF_EXTENDEDKEY,
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-                INPUT {
-                    r#type: INPUT_KEYBOARD,
-                    Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                        ki: KEYBDINPUT {
-                            wVk: vk,
-                            wScan: 0,
-                            dwFlags: KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP,
-                            time: 0,
-                            dwExtraInfo: 0,
-                        },
-                    },
-                },
-            ];
-
-            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-        }
+        send_media_key(MediaKey::Next);
     }
 
     /// Get playback state
@@ -182,10 +149,118 @@ impl MediaModule {
 }
 
 /// Media key types
-enum MediaKey {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKey {
     PlayPause,
     Previous,
     Next,
+    Stop,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+}
+
+impl MediaKey {
+    /// All keys, in menu order
+    pub const ALL: [MediaKey; 7] = [
+        Self::PlayPause,
+        Self::Previous,
+        Self::Next,
+        Self::Stop,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::Mute,
+    ];
+
+    /// Name used in keymap actions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PlayPause => "play_pause",
+            Self::Previous => "previous",
+            Self::Next => "next",
+            Self::Stop => "stop",
+            Self::VolumeUp => "volume_up",
+            Self::VolumeDown => "volume_down",
+            Self::Mute => "mute",
+        }
+    }
+
+    /// Menu label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play / Pause",
+            Self::Previous => "Previous Track",
+            Self::Next => "Next Track",
+            Self::Stop => "Stop Playback",
+            Self::VolumeUp => "Volume Up",
+            Self::VolumeDown => "Volume Down",
+            Self::Mute => "Mute",
+        }
+    }
+
+    /// Parse a key name as used in keymap actions, e.g. "play_pause"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "play_pause" => Some(Self::PlayPause),
+            "previous" | "prev" => Some(Self::Previous),
+            "next" => Some(Self::Next),
+            "stop" => Some(Self::Stop),
+            "volume_up" => Some(Self::VolumeUp),
+            "volume_down" => Some(Self::VolumeDown),
+            "mute" => Some(Self::Mute),
+            _ => None,
+        }
+    }
+}
+
+/// Send a media key as if it was pressed on the keyboard
+pub fn send_media_key(key: MediaKey) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP,
+        VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_MEDIA_STOP, VK_VOLUME_DOWN,
+        VK_VOLUME_MUTE, VK_VOLUME_UP,
+    };
+
+    let vk = match key {
+        MediaKey::PlayPause => VK_MEDIA_PLAY_PAUSE,
+        MediaKey::Previous => VK_MEDIA_PREV_TRACK,
+        MediaKey::Next => VK_MEDIA_NEXT_TRACK,
+        MediaKey::Stop => VK_MEDIA_STOP,
+        MediaKey::VolumeUp => VK_VOLUME_UP,
+        MediaKey::VolumeDown => VK_VOLUME_DOWN,
+        MediaKey::Mute => VK_VOLUME_MUTE,
+    };
+
+    unsafe {
+        let inputs = [
+            INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: vk,
+                        wScan: 0,
+                        dwFlags: KEYEVENTF_EXTENDEDKEY,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            },
+            INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: vk,
+                        wScan: 0,
+                        dwFlags: KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            },
+        ];
+
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
 }
 
 impl Default for MediaModule {
@@ -214,6 +289,15 @@ impl Module for MediaModule {
         }
     }
 
+    fn copy_value(&self, config: &crate::config::Config) -> String {
+        // Full title, without the play glyph or truncation
+        match (&self.track_title, &self.track_artist) {
+            (Some(title), Some(artist)) => format!("{} - {}", artist, title),
+            (Some(title), None) => title.clone(),
+            _ => self.display_text(config),
+        }
+    }
+
     fn on_click(&mut self) {
         self.toggle_playback();
     }
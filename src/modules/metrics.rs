@@ -0,0 +1,318 @@
+//! Shared hardware metrics collector
+//!
+//! Sensor backends (vendor GPU libraries, WMI hardware monitor sensors, ACPI thermal
+//! zones) are owned here so the GPU, system info and thermal modules read the same
+//! values without each opening their own handles or issuing duplicate WMI queries.
+//! The sensors are read on the sampler thread (see `sampler::want`), never on the
+//! window's; modules only see the latest `ThermalSnapshot`. The same goes for the
+//! shared process snapshot, so menus and modules that need a process list don't
+//! each walk the whole process table.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use windows::core::PCWSTR;
+use windows::Win32::System::Wmi::IWbemServices;
+
+use super::gpu_temp::GpuTempProvider;
+use super::sampler;
+
+/// WMI namespaces published by LibreHardwareMonitor / OpenHardwareMonitor
+pub(crate) const HARDWARE_MONITOR_NAMESPACES: [&str; 2] =
+    ["root\\LibreHardwareMonitor", "root\\OpenHardwareMonitor"];
+
+/// Time between process snapshots while they're wanted
+const PROCESS_REFRESH_INTERVAL: Duration = Duration::from_millis(2000);
+
+/// A single fan speed sensor
+#[derive(Debug, Clone)]
+pub struct FanReading {
+    pub name: String,
+    pub rpm: f32,
+}
+
+/// Latest temperature and fan readings
+#[derive(Debug, Clone, Default)]
+pub struct ThermalSnapshot {
+    pub cpu_temp: Option<f32>,
+    pub gpu_temp: Option<f32>,
+    pub fans: Vec<FanReading>,
+}
+
+impl ThermalSnapshot {
+    /// Hottest of the CPU and GPU readings
+    pub fn max_temp(&self) -> Option<f32> {
+        match (self.cpu_temp, self.gpu_temp) {
+            (Some(c), Some(g)) => Some(c.max(g)),
+            (c, g) => c.or(g),
+        }
+    }
+}
+
+/// One process in the shared process snapshot
+#[derive(Debug, Clone)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+    /// Working set in bytes
+    pub memory: u64,
+}
+
+/// Latest temperatures and fan speeds, as far as the modules on the bar asked for them
+pub fn latest_thermal() -> ThermalSnapshot {
+    sampler::samples().latest.thermal.clone()
+}
+
+/// The latest process snapshot (shared; cheap to clone); empty until a module
+/// has wanted it
+pub fn processes() -> Arc<Vec<ProcessSample>> {
+    Arc::clone(&sampler::samples().processes)
+}
+
+/// The `count` processes with the largest working set, largest first
+pub fn top_memory_processes(count: usize) -> Vec<ProcessSample> {
+    let mut list: Vec<ProcessSample> = processes().iter().cloned().collect();
+    list.sort_by_key(|p| std::cmp::Reverse(p.memory));
+    list.truncate(count);
+    list
+}
+
+/// Name of the GPU temperature backend, once the sampler has picked one
+pub fn gpu_temperature_source() -> &'static str {
+    sampler::samples().gpu_temperature_source
+}
+
+/// Sensor backends and the process table, owned by the sampler thread
+pub(super) struct Sensors {
+    /// Picked the first time the GPU temperature is wanted, as loading a vendor
+    /// library isn't free
+    gpu_provider: Option<Box<dyn GpuTempProvider>>,
+    system: sysinfo::System,
+    last_process_refresh: Option<Instant>,
+}
+
+impl Sensors {
+    pub(super) fn new() -> Self {
+        Self {
+            gpu_provider: None,
+            system: sysinfo::System::new(),
+            last_process_refresh: None,
+        }
+    }
+
+    /// A new process snapshot, or None if the last one is still fresh
+    pub(super) fn processes(&mut self) -> Option<Vec<ProcessSample>> {
+        use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, UpdateKind};
+
+        if self.last_process_refresh.is_some_and(|t| t.elapsed() < PROCESS_REFRESH_INTERVAL) {
+            return None;
+        }
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            ProcessRefreshKind::new()
+                .with_memory()
+                .with_exe(UpdateKind::OnlyIfNotSet),
+        );
+        self.last_process_refresh = Some(Instant::now());
+        let samples = self
+            .system
+            .processes()
+            .iter()
+            .map(|(pid, process)| ProcessSample {
+                pid: pid.as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                exe: process.exe().map(|p| p.to_path_buf()),
+                memory: process.memory(),
+            })
+            .collect();
+        Some(samples)
+    }
+
+    /// Name of the GPU temperature backend, or "" before one is picked
+    pub(super) fn gpu_source(&self) -> &'static str {
+        self.gpu_provider.as_ref().map_or("", |p| p.name())
+    }
+
+    /// Read the sensors asked for; the rest are left empty
+    pub(super) fn read(&mut self, cpu: bool, gpu: bool, fans: bool) -> ThermalSnapshot {
+        if crate::demo::is_enabled() {
+            return crate::demo::thermal();
+        }
+        let gpu_temp = if gpu {
+            self.gpu_provider
+                .get_or_insert_with(|| {
+                    let vendor = super::gpu_temp::detect_primary_vendor();
+                    super::gpu_temp::provider_for_vendor(vendor)
+                })
+                .temperature()
+        } else {
+            None
+        };
+        ThermalSnapshot {
+            cpu_temp: if cpu { cpu_temperature() } else { None },
+            gpu_temp,
+            fans: if fans { fan_speeds() } else { Vec::new() },
+        }
+    }
+}
+
+/// Read the CPU package temperature.
+///
+/// Prefers hardware monitor sensors and falls back to the ACPI thermal zone, which
+/// is coarser (and on many desktops not exposed at all).
+fn cpu_temperature() -> Option<f32> {
+    const QUERY: &str =
+        "SELECT Value FROM Sensor WHERE SensorType='Temperature' AND Identifier LIKE '%cpu%'";
+
+    let monitored = HARDWARE_MONITOR_NAMESPACES
+        .iter()
+        .map(|ns| query_wmi_numbers(ns, QUERY, "Value"))
+        .find(|values| !values.is_empty())
+        .and_then(|values| values.into_iter().reduce(f32::max));
+    if monitored.is_some() {
+        return monitored;
+    }
+
+    // MSAcpi_ThermalZoneTemperature reports tenths of a Kelvin
+    query_wmi_numbers(
+        "root\\WMI",
+        "SELECT CurrentTemperature FROM MSAcpi_ThermalZoneTemperature",
+        "CurrentTemperature",
+    )
+    .into_iter()
+    .map(|tenths_kelvin| tenths_kelvin / 10.0 - 273.15)
+    .filter(|c| *c > 0.0 && *c < 150.0)
+    .reduce(f32::max)
+}
+
+/// Read every fan speed sensor published by a hardware monitor
+fn fan_speeds() -> Vec<FanReading> {
+    const QUERY: &str = "SELECT Name, Value FROM Sensor WHERE SensorType='Fan'";
+
+    HARDWARE_MONITOR_NAMESPACES
+        .iter()
+        .map(|ns| query_wmi_named(ns, QUERY, "Name", "Value"))
+        .find(|rows| !rows.is_empty())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, rpm)| FanReading { name, rpm })
+        .collect()
+}
+
+/// Run a WQL query and collect a numeric property from every returned object.
+///
+/// Returns an empty list if the namespace doesn't exist or the query fails.
+pub(crate) fn query_wmi_numbers(namespace: &str, query: &str, property: &str) -> Vec<f32> {
+    query_wmi(namespace, query, None, property)
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// Like [`query_wmi_numbers`], but pairs each value with a string label property
+pub(crate) fn query_wmi_named(
+    namespace: &str,
+    query: &str,
+    label_property: &str,
+    value_property: &str,
+) -> Vec<(String, f32)> {
+    query_wmi(namespace, query, Some(label_property), value_property)
+}
+
+thread_local! {
+    /// WMI connections by namespace, opened once per thread
+    static CONNECTIONS: RefCell<HashMap<String, IWbemServices>> = RefCell::new(HashMap::new());
+}
+
+/// A connection to a WMI namespace, reusing the thread's if it has one
+unsafe fn connect_wmi(namespace: &str) -> Option<IWbemServices> {
+    use windows::core::BSTR;
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED};
+    use windows::Win32::System::Wmi::{IWbemLocator, WbemLocator};
+
+    if let Some(services) = CONNECTIONS.with(|c| c.borrow().get(namespace).cloned()) {
+        return Some(services);
+    }
+    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    let locator = CoCreateInstance::<_, IWbemLocator>(&WbemLocator, None, CLSCTX_INPROC_SERVER).ok()?;
+    let services = locator
+        .ConnectServer(
+            &BSTR::from(namespace),
+            &BSTR::new(),
+            &BSTR::new(),
+            &BSTR::new(),
+            0,
+            &BSTR::new(),
+            None,
+        )
+        .ok()?;
+    CONNECTIONS.with(|c| c.borrow_mut().insert(namespace.to_string(), services.clone()));
+    Some(services)
+}
+
+fn query_wmi(
+    namespace: &str,
+    query: &str,
+    label_property: Option<&str>,
+    value_property: &str,
+) -> Vec<(String, f32)> {
+    use windows::core::{BSTR, VARIANT};
+    use windows::Win32::System::Wmi::{
+        IWbemClassObject, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_INFINITE,
+    };
+
+    let mut rows = Vec::new();
+
+    unsafe {
+        let Some(services) = connect_wmi(namespace) else {
+            return rows;
+        };
+        let Ok(enumerator) = services.ExecQuery(
+            &BSTR::from("WQL"),
+            &BSTR::from(query),
+            WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+            None,
+        ) else {
+            // The provider may have gone away (hardware monitor closed); connect again next time
+            CONNECTIONS.with(|c| c.borrow_mut().remove(namespace));
+            return rows;
+        };
+
+        let value_property = crate::utils::to_wide_string(value_property);
+        let label_property = label_property.map(crate::utils::to_wide_string);
+        loop {
+            let mut objects: [Option<IWbemClassObject>; 1] = [None];
+            let mut returned = 0u32;
+            let _ = enumerator.Next(WBEM_INFINITE, &mut objects, &mut returned);
+            let Some(object) = objects[0].take().filter(|_| returned > 0) else {
+                break;
+            };
+
+            let mut value = VARIANT::default();
+            if object.Get(PCWSTR(value_property.as_ptr()), 0, &mut value, None, None).is_err() {
+                continue;
+            }
+            let Ok(number) = f64::try_from(&value) else {
+                continue;
+            };
+
+            let mut label = String::new();
+            if let Some(ref prop) = label_property {
+                let mut v = VARIANT::default();
+                if object.Get(PCWSTR(prop.as_ptr()), 0, &mut v, None, None).is_ok() {
+                    if let Ok(s) = BSTR::try_from(&v) {
+                        label = s.to_string();
+                    }
+                }
+            }
+
+            rows.push((label, number as f32));
+        }
+    }
+
+    rows
+}
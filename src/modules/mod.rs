@@ -9,18 +9,43 @@ pub mod active_window;
 pub mod app_menu;
 pub mod battery;
 pub mod bluetooth;
+pub mod capture;
 pub mod clipboard;
 pub mod clock;
+pub mod connectivity;
 pub mod disk;
+pub mod events;
 pub mod gpu;
+pub mod gpu_temp;
 pub mod keyboard_layout;
+pub mod launcher;
+pub mod lock_keys;
 pub mod media;
+pub mod metrics;
+pub mod monitor_input;
 pub mod network;
+pub mod power;
+pub mod printing;
+pub mod removable_media;
+pub mod sampler;
+pub mod sandbox;
+pub mod screen_recorder;
+pub mod security;
+pub mod services;
+pub mod smart;
+pub mod sports;
+pub mod stocks;
 pub mod night_light;
 pub mod system_info;
+pub mod tasks;
+pub mod thermal;
 pub mod uptime;
+pub mod user;
+pub mod voice_memo;
 pub mod volume;
+pub mod watchdog;
 pub mod weather;
+pub mod weather_station;
 
 use std::any::Any;
 use std::collections::HashMap;
@@ -42,6 +67,17 @@ pub trait Module: Send + Sync {
     /// Update module state (called periodically)
     fn update(&mut self, config: &crate::config::Config);
 
+    /// Refresh immediately, ignoring the update interval
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.update(config);
+    }
+
+    /// Text copied by Ctrl+click: the raw value behind the display (an IP address
+    /// rather than a Wi-Fi glyph); defaults to the display text
+    fn copy_value(&self, config: &crate::config::Config) -> String {
+        self.display_text(config)
+    }
+
     /// Handle click event
     fn on_click(&mut self) {}
 
@@ -72,16 +108,72 @@ pub trait Module: Send + Sync {
     /// Cast to Any mutably for downcasting
     fn as_any_mut(&mut self) -> &mut dyn Any;
 
+    /// Named values that color rules can test, main value first (e.g. battery
+    /// percent, or CPU and memory usage)
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        Vec::new()
+    }
+
     /// Optional helper for modules that provide graph data (percentages 0-100)
     fn graph_values(&self) -> Option<Vec<f32>> {
         None
     }
+
+    /// Recent values drawn as a sparkline in the tooltip; defaults to the graph
+    /// values on a 0-100 scale
+    fn history(&self) -> Option<History> {
+        self.graph_values().map(|values| History { values, max: Some(100.0) })
+    }
+
+    /// Whether the module fetches anything over the network, so offline mode
+    /// (`[privacy] offline_mode`) stops it
+    fn uses_network(&self) -> bool {
+        false
+    }
+
+    /// Events (see `events`) passed to `on_event`
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// React to an event the module subscribed to
+    fn on_event(&mut self, _topic: &str, _config: &crate::config::Config) {}
+}
+
+/// Whether offline mode has stopped a module: it isn't updated and is drawn
+/// greyed out with an "offline" tooltip
+pub fn is_offline(module: &dyn Module, config: &crate::config::Config) -> bool {
+    config.privacy.offline_mode && module.uses_network()
+}
+
+/// Whether offline mode is on, for the online lookups outside the modules
+/// (translation, definitions, currency rates)
+pub fn offline_mode() -> bool {
+    crate::window::state::get_window_state().is_some_and(|s| s.read().config.privacy.offline_mode)
+}
+
+/// A module's tooltip, or the offline note while offline mode has stopped it
+pub fn tooltip_text(module: &dyn Module, config: &crate::config::Config) -> Option<String> {
+    if is_offline(module, config) {
+        let name = crate::i18n::tr(module.name());
+        return Some(crate::i18n::tr("Offline mode is on; {} isn't updated").replacen("{}", &name, 1));
+    }
+    module.tooltip()
+}
+
+/// Recent values of a module's main metric, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    pub values: Vec<f32>,
+    /// Top of the scale; `None` scales to the largest value (e.g. throughput)
+    pub max: Option<f32>,
 }
 
 /// Render context for modules
 #[derive(Clone)]
 pub struct ModuleRenderContext {
     pub hdc: HDC,
+    /// The bar's theme, with the module's own colors applied when drawing one module
     pub theme: Theme,
     pub dpi: u32,
 }
@@ -92,6 +184,10 @@ pub struct ModuleRegistry {
     order_left: Vec<String>,
     order_center: Vec<String>,
     order_right: Vec<String>,
+    /// Panics in a row per module (see `sandbox`)
+    failures: HashMap<String, u32>,
+    /// Last seen (on battery, battery saver), to publish changes
+    power: Option<(bool, bool)>,
 }
 
 impl ModuleRegistry {
@@ -99,22 +195,45 @@ impl ModuleRegistry {
     pub fn new() -> Self {
         let mut registry = Self {
             modules: HashMap::new(),
-            order_left: vec!["app_menu".to_string(), "active_window".to_string()],
+            failures: HashMap::new(),
+            power: None,
+            order_left: vec![
+                "app_menu".to_string(),
+                "launcher".to_string(),
+                "active_window".to_string(),
+                "tasks".to_string(),
+            ],
             order_center: vec![],
             order_right: vec![
                 "weather".to_string(),
+                "stocks".to_string(),
+                "sports".to_string(),
                 "media".to_string(),
                 "clipboard".to_string(),
+                "capture".to_string(),
+                "voice_memo".to_string(),
+                "printing".to_string(),
+                "removable_media".to_string(),
+                "watchdog".to_string(),
+                "services".to_string(),
+                "security".to_string(),
+                "monitor_input".to_string(),
                 "keyboard_layout".to_string(),
+                "lock_keys".to_string(),
                 "gpu".to_string(),
                 "system_info".to_string(),
+                "thermal".to_string(),
                 "disk".to_string(),
                 "network".to_string(),
+                "connectivity".to_string(),
                 "bluetooth".to_string(),
+                "night_light".to_string(),
                 "volume".to_string(),
                 "battery".to_string(),
                 "uptime".to_string(),
+                "user".to_string(),
                 "clock".to_string(),
+                "power".to_string(),
             ],
         };
 
@@ -137,7 +256,41 @@ impl ModuleRegistry {
         registry.register(Box::new(bluetooth::BluetoothModule::new()));
         registry.register(Box::new(disk::DiskModule::new()));
         registry.register(Box::new(night_light::NightLightModule::new()));
+        registry.register(Box::new(thermal::ThermalModule::new()));
+        registry.register(Box::new(watchdog::WatchdogModule::new()));
+        registry.register(Box::new(capture::CaptureModule::new()));
+        registry.register(Box::new(services::ServicesModule::new()));
+        registry.register(Box::new(launcher::LauncherModule::new()));
+        registry.register(Box::new(tasks::TasksModule::new()));
+        registry.register(Box::new(lock_keys::LockKeysModule::new()));
+        registry.register(Box::new(stocks::StocksModule::new()));
+        registry.register(Box::new(sports::SportsModule::new()));
+        registry.register(Box::new(security::SecurityModule::new()));
+        registry.register(Box::new(monitor_input::MonitorInputModule::new()));
+        registry.register(Box::new(voice_memo::VoiceMemoModule::new()));
+        registry.register(Box::new(power::PowerModule::new()));
+        registry.register(Box::new(user::UserModule::new()));
+        registry.register(Box::new(connectivity::ConnectivityModule::new()));
+        registry.register(Box::new(printing::PrintingModule::new()));
+        registry.register(Box::new(removable_media::RemovableMediaModule::new()));
+
+        registry
+    }
 
+    /// A registry with only `modules`, all of them in the layout
+    #[cfg(test)]
+    fn with_modules(modules: Vec<Box<dyn Module>>) -> Self {
+        let mut registry = Self {
+            modules: HashMap::new(),
+            order_left: modules.iter().map(|m| m.id().to_string()).collect(),
+            order_center: Vec::new(),
+            order_right: Vec::new(),
+            failures: HashMap::new(),
+            power: None,
+        };
+        for module in modules {
+            registry.register(module);
+        }
         registry
     }
 
@@ -157,6 +310,32 @@ impl ModuleRegistry {
         self.modules.get_mut(id)
     }
 
+    /// Every registered module, shown or not, sorted by name
+    pub fn all(&self) -> Vec<&dyn Module> {
+        let mut modules: Vec<&dyn Module> = self.modules.values().map(|b| b.as_ref()).collect();
+        modules.sort_by(|a, b| a.name().cmp(b.name()));
+        modules
+    }
+
+    /// Hash of what a module is drawn from (its text, graph and values), so the
+    /// bar can tell whether it needs repainting
+    pub fn fingerprint(&self, id: &str, config: &crate::config::Config) -> Option<u64> {
+        use std::hash::{Hash, Hasher};
+
+        let module = self.modules.get(id)?;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        module.is_visible().hash(&mut hasher);
+        module.display_text(config).hash(&mut hasher);
+        for value in module.graph_values().unwrap_or_default() {
+            value.to_bits().hash(&mut hasher);
+        }
+        for (name, value) in module.metrics() {
+            name.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+        }
+        Some(hasher.finish())
+    }
+
     /// Update all modules
     pub fn update_all(&mut self, config: &crate::config::Config) {
         // Check if we're on battery power to adjust update frequencies
@@ -164,27 +343,137 @@ impl ModuleRegistry {
         let _battery_multiplier = if _is_on_battery { 2 } else { 1 }; // 2x slower on battery
 
         // Collect all visible module IDs to avoid updating hidden modules
-        let mut visible_ids = std::collections::HashSet::new();
-        visible_ids.extend(&self.order_left);
-        visible_ids.extend(&self.order_center);
-        visible_ids.extend(&self.order_right);
+        self.sync_sun_times();
+        self.sync_device_batteries(config);
+
+        // Events published since the last update, by the window or in the background
+        self.watch_power();
+        self.dispatch_events(config);
 
+        let visible_ids = self.visible_ids();
+        let mut errored = Vec::new();
         for (id, module) in self.modules.iter_mut() {
-            // Skip updating modules that are not in the current layout
-            if !visible_ids.contains(id) {
+            // Skip updating modules that are not in the current layout, or offline
+            if !visible_ids.contains(id) || is_offline(module.as_ref(), config) {
                 continue;
             }
 
-            // Add error boundary to prevent one failing module from crashing the app
-            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // Error boundary so one failing module can't crash the app; the text is
+            // produced here too, so a panic in it is caught before drawing
+            let result = sandbox::guard(|| {
                 // For battery optimization, we could modify the config temporarily
                 // but for now, modules handle their own update intervals
                 module.update(config);
-            }));
-            
-            if let Err(err) = result {
-                log::warn!("Module '{}' update failed: {:?}", id, err);
-                // Continue with other modules even if one fails
+                module.display_text(config);
+            });
+
+            match result {
+                Ok(()) => {
+                    self.failures.remove(id);
+                }
+                Err(message) => {
+                    let count = self.failures.entry(id.clone()).or_insert(0);
+                    *count += 1;
+                    log::error!(
+                        "Module '{}' panicked ({} of {}): {}",
+                        id, count, sandbox::MAX_FAILURES, message
+                    );
+                    if *count >= sandbox::MAX_FAILURES {
+                        errored.push((id.clone(), message));
+                    }
+                }
+            }
+        }
+
+        // Modules that kept failing are switched off for the rest of the session
+        for (id, message) in errored {
+            log::error!("Switching off module '{}' after repeated panics", id);
+            if let Some(module) = self.modules.get(&id) {
+                let stand_in = sandbox::ErroredModule::new(module.as_ref(), message);
+                self.modules.insert(id.clone(), Box::new(stand_in));
+            }
+            self.failures.remove(&id);
+        }
+
+        // And those modules just published
+        self.dispatch_events(config);
+    }
+
+    /// Ids of the modules in the current layout
+    fn visible_ids(&self) -> std::collections::HashSet<String> {
+        self.order_left
+            .iter()
+            .chain(&self.order_center)
+            .chain(&self.order_right)
+            .cloned()
+            .collect()
+    }
+
+    /// Hand out the events queued since the last update
+    fn dispatch_events(&mut self, config: &crate::config::Config) {
+        let topics = events::take();
+        if !topics.is_empty() {
+            self.dispatch(&topics, config);
+        }
+    }
+
+    /// Hand `topics` to the visible modules subscribed to them
+    fn dispatch(&mut self, topics: &[&'static str], config: &crate::config::Config) {
+        let visible_ids = self.visible_ids();
+        for &topic in topics {
+            for (id, module) in self.modules.iter_mut() {
+                if !visible_ids.contains(id)
+                    || !module.subscriptions().contains(&topic)
+                    || is_offline(module.as_ref(), config)
+                {
+                    continue;
+                }
+                if let Err(message) = sandbox::guard(|| module.on_event(topic, config)) {
+                    log::error!("Module '{}' panicked handling {}: {}", id, topic, message);
+                }
+            }
+        }
+    }
+
+    /// Publish changes of power source and battery saver; both count as off
+    /// before the first look
+    fn watch_power(&mut self) {
+        let (on_battery, saver) = (crate::utils::is_on_battery(), crate::utils::is_battery_saver_on());
+        let (was_on_battery, was_saver) = self.power.unwrap_or_default();
+        if on_battery != was_on_battery {
+            events::publish(if on_battery { events::POWER_ON_BATTERY } else { events::POWER_ON_AC });
+        }
+        if saver != was_saver {
+            events::publish(if saver { events::POWER_SAVER_ON } else { events::POWER_SAVER_OFF });
+        }
+        self.power = Some((on_battery, saver));
+    }
+
+    /// Hand today's sunrise/sunset from the weather module to the night light schedule
+    fn sync_sun_times(&mut self) {
+        let sun_times = self
+            .modules
+            .get("weather")
+            .and_then(|m| m.as_any().downcast_ref::<weather::WeatherModule>())
+            .and_then(|w| w.sun_times());
+        if let Some(module) = self.modules.get_mut("night_light") {
+            if let Some(nl) = module.as_any_mut().downcast_mut::<night_light::NightLightModule>() {
+                nl.set_sun_times(sun_times);
+            }
+        }
+    }
+
+    /// Hand connected devices' battery levels from the Bluetooth module to the battery module
+    fn sync_device_batteries(&mut self, config: &crate::config::Config) {
+        let devices = self
+            .modules
+            .get("bluetooth")
+            .and_then(|m| m.as_any().downcast_ref::<bluetooth::BluetoothModule>())
+            .map(|b| b.device_batteries().to_vec())
+            .unwrap_or_default();
+        if let Some(module) = self.modules.get_mut("battery") {
+            if let Some(battery) = module.as_any_mut().downcast_mut::<battery::BatteryModule>() {
+                battery.set_device_batteries(&devices, config);
             }
         }
     }
@@ -0,0 +1,288 @@
+//! Monitor input module - software KVM over DDC/CI
+//!
+//! Switches an external monitor between its inputs by writing the MCCS input
+//! source code (VCP 0x60), so one click hands the screen to the laptop on USB-C
+//! and another brings it back to the desktop on DisplayPort. Inputs can be named
+//! per monitor in the config; monitors without names get the standard list.
+//! DDC/CI round trips take tens of milliseconds each, so reads and switches run
+//! on a background thread.
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use windows::Win32::Devices::Display::{
+    DestroyPhysicalMonitors, GetNumberOfPhysicalMonitorsFromHMONITOR, GetPhysicalMonitorsFromHMONITOR,
+    GetVCPFeatureAndVCPFeatureReply, SetVCPFeature, PHYSICAL_MONITOR,
+};
+use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+
+use super::Module;
+use crate::config::{MonitorInputConfig, NamedInput};
+
+/// MCCS "Input Select" VCP code
+const VCP_INPUT_SOURCE: u8 = 0x60;
+
+/// Common MCCS input source values, offered for monitors without named inputs
+const STANDARD_INPUTS: &[(&str, u32)] = &[
+    ("DisplayPort 1", 0x0F),
+    ("DisplayPort 2", 0x10),
+    ("HDMI 1", 0x11),
+    ("HDMI 2", 0x12),
+    ("USB-C", 0x1B),
+    ("DVI 1", 0x03),
+    ("DVI 2", 0x04),
+    ("VGA", 0x01),
+];
+
+/// A monitor reachable over DDC/CI
+#[derive(Debug, Clone)]
+pub struct Display {
+    /// 1-based position in display enumeration order
+    pub number: usize,
+    /// Description reported by the driver
+    pub name: String,
+    /// Current input source, or None when the monitor didn't answer the read
+    /// (it may still take a switch)
+    pub current: Option<u32>,
+}
+
+impl Display {
+    /// Inputs to offer for this monitor: its configured ones, else the standard list
+    pub fn inputs(&self, config: &MonitorInputConfig) -> Vec<NamedInput> {
+        config
+            .monitors
+            .iter()
+            .find(|m| self.matches(&m.matches))
+            .map(|m| m.inputs.clone())
+            .unwrap_or_else(|| {
+                STANDARD_INPUTS
+                    .iter()
+                    .map(|(name, value)| NamedInput {
+                        name: name.to_string(),
+                        value: *value,
+                    })
+                    .collect()
+            })
+    }
+
+    /// Whether a config entry's `match` (a number or part of the name) refers to this monitor
+    fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+        match pattern.parse::<usize>() {
+            Ok(number) => number == self.number,
+            Err(_) => self.name.to_lowercase().contains(&pattern.to_lowercase()),
+        }
+    }
+
+    /// Name of the current input, configured or standard
+    pub fn current_name(&self, config: &MonitorInputConfig) -> Option<String> {
+        let current = self.current?;
+        Some(
+            self.inputs(config)
+                .into_iter()
+                .find(|input| input.value == current)
+                .map(|input| input.name)
+                .unwrap_or_else(|| format!("Input 0x{:02X}", current)),
+        )
+    }
+}
+
+unsafe extern "system" fn collect_monitor(monitor: HMONITOR, _: HDC, _: *mut RECT, lparam: LPARAM) -> BOOL {
+    let monitors = &mut *(lparam.0 as *mut Vec<HMONITOR>);
+    monitors.push(monitor);
+    BOOL(1)
+}
+
+/// Open every physical monitor, run `f` over them in order, then close them
+pub(crate) fn with_physical_monitors<T>(f: impl FnOnce(&[PHYSICAL_MONITOR]) -> T) -> T {
+    unsafe {
+        let mut handles: Vec<HMONITOR> = Vec::new();
+        let _ = EnumDisplayMonitors(None, None, Some(collect_monitor), LPARAM(&mut handles as *mut _ as isize));
+
+        let mut physical = Vec::new();
+        for handle in handles {
+            let mut count = 0u32;
+            if GetNumberOfPhysicalMonitorsFromHMONITOR(handle, &mut count).is_err() || count == 0 {
+                continue;
+            }
+            let mut monitors = vec![PHYSICAL_MONITOR::default(); count as usize];
+            if GetPhysicalMonitorsFromHMONITOR(handle, &mut monitors).is_ok() {
+                physical.extend(monitors);
+            }
+        }
+
+        let result = f(&physical);
+        if !physical.is_empty() {
+            let _ = DestroyPhysicalMonitors(&physical);
+        }
+        result
+    }
+}
+
+/// Read every monitor's description and current input
+fn read_displays() -> Vec<Display> {
+    with_physical_monitors(|monitors| {
+        monitors
+            .iter()
+            .enumerate()
+            .map(|(i, monitor)| {
+                // PHYSICAL_MONITOR is packed, so copy fields out rather than borrow them
+                let description = monitor.szPhysicalMonitorDescription;
+                let len = description.iter().position(|&c| c == 0).unwrap_or(description.len());
+                let mut value = 0u32;
+                let ok = unsafe {
+                    GetVCPFeatureAndVCPFeatureReply(monitor.hPhysicalMonitor, VCP_INPUT_SOURCE, None, &mut value, None)
+                };
+                Display {
+                    number: i + 1,
+                    name: String::from_utf16_lossy(&description[..len]),
+                    // Some monitors put vendor bits in the high byte
+                    current: (ok != 0).then_some(value & 0xFF),
+                }
+            })
+            .collect()
+    })
+}
+
+/// Switch a monitor (by number) to an input source
+fn set_input(number: usize, value: u32) -> bool {
+    with_physical_monitors(|monitors| {
+        monitors
+            .get(number.wrapping_sub(1))
+            .map(|monitor| unsafe { SetVCPFeature(monitor.hPhysicalMonitor, VCP_INPUT_SOURCE, value) != 0 })
+            .unwrap_or(false)
+    })
+}
+
+/// Monitor input switching module
+pub struct MonitorInputModule {
+    displays: Arc<Mutex<Vec<Display>>>,
+    reading: Arc<AtomicBool>,
+    last_update: Option<Instant>,
+    config: MonitorInputConfig,
+}
+
+impl MonitorInputModule {
+    pub fn new() -> Self {
+        Self {
+            displays: Arc::new(Mutex::new(Vec::new())),
+            reading: Arc::new(AtomicBool::new(false)),
+            last_update: None,
+            config: MonitorInputConfig::default(),
+        }
+    }
+
+    fn read_async(&mut self) {
+        if self.reading.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        let displays = Arc::clone(&self.displays);
+        let reading = Arc::clone(&self.reading);
+        std::thread::spawn(move || {
+            let mut fresh = read_displays();
+            let mut displays = displays.lock();
+            // A monitor switched away from this PC often stops answering; keep
+            // the input it was last known to be on
+            for display in fresh.iter_mut().filter(|d| d.current.is_none()) {
+                display.current = displays
+                    .iter()
+                    .find(|d| d.number == display.number && d.name == display.name)
+                    .and_then(|d| d.current);
+            }
+            *displays = fresh;
+            reading.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Monitors from the latest reading
+    pub fn displays(&self) -> Vec<Display> {
+        self.displays.lock().clone()
+    }
+
+    /// Switch a monitor to another input in the background
+    pub fn switch_input(&mut self, number: usize, value: u32) {
+        let displays = Arc::clone(&self.displays);
+        std::thread::spawn(move || {
+            if set_input(number, value) {
+                log::info!("Monitor {} switched to input 0x{:02X}", number, value);
+                // The monitor may stop answering once it's on another source, so
+                // record the switch rather than reading it back
+                if let Some(display) = displays.lock().iter_mut().find(|d| d.number == number) {
+                    display.current = Some(value);
+                }
+            } else {
+                log::warn!("Monitor {} did not accept input 0x{:02X}", number, value);
+            }
+        });
+    }
+}
+
+impl Default for MonitorInputModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for MonitorInputModule {
+    fn id(&self) -> &str {
+        "monitor_input"
+    }
+
+    fn name(&self) -> &str {
+        "Monitor Input"
+    }
+
+    fn display_text(&self, config: &crate::config::Config) -> String {
+        let cfg = &config.modules.monitor_input;
+        // Name the input of the first monitor with named inputs, the one the user switches
+        let current = self
+            .displays
+            .lock()
+            .iter()
+            .find(|d| cfg.monitors.iter().any(|m| d.matches(&m.matches)))
+            .and_then(|d| d.current_name(cfg));
+        match current {
+            Some(name) => format!("🖥 {}", name),
+            None => "🖥".to_string(),
+        }
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        self.config = config.modules.monitor_input.clone();
+        let interval = Duration::from_secs(self.config.update_interval_sec.max(5));
+        if self.last_update.map(|t| t.elapsed() >= interval).unwrap_or(true) {
+            self.read_async();
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.last_update = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let displays = self.displays.lock();
+        if displays.is_empty() {
+            return Some("No DDC/CI monitors found".to_string());
+        }
+        let lines: Vec<String> = displays
+            .iter()
+            .map(|d| {
+                let input = d.current_name(&self.config).unwrap_or_else(|| "input unknown".to_string());
+                format!("{}. {}: {}", d.number, d.name, input)
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
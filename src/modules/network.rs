@@ -1,10 +1,34 @@
 //! Network module for displaying network status with real WiFi info
+//!
+//! Throughput and its history come from the shared sampler; this module adds
+//! the connection details and the per-process breakdown.
 
 #![allow(dead_code)]
 
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 
+use super::events;
+use super::sampler;
 use super::Module;
+use crate::i18n::tr;
+
+/// Number of processes kept in the top-talkers list
+const TOP_TALKERS_COUNT: usize = 5;
+/// Number of processes kept for the usage popup's table
+const PROCESS_TABLE_COUNT: usize = 10;
+/// Throughput samples kept for the tooltip sparkline and usage graph (one per second)
+pub const SPEED_HISTORY_LEN: usize = sampler::HISTORY_LEN;
+
+/// Set while the usage popup is open, so processes are sampled even when the
+/// top-talkers list is turned off
+static BREAKDOWN_WANTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask for per-process sampling regardless of `show_top_talkers`
+pub fn set_breakdown_wanted(wanted: bool) {
+    BREAKDOWN_WANTED.store(wanted, Ordering::Relaxed);
+}
 
 /// Network connection type
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,6 +40,29 @@ pub enum NetworkType {
     Unknown,
 }
 
+/// Per-process network throughput, derived from TCP connection statistics
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTraffic {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    pub download_speed: u64, // bytes per second
+    pub upload_speed: u64,   // bytes per second
+    /// Estimated from the process's I/O counters: `download_speed` holds the
+    /// combined rate and `upload_speed` is 0
+    pub estimated: bool,
+}
+
+impl ProcessTraffic {
+    /// Combined download and upload rate
+    pub fn total_speed(&self) -> u64 {
+        self.download_speed + self.upload_speed
+    }
+}
+
+/// Connection key: (local addr, local port, remote addr, remote port)
+type ConnKey = (u32, u32, u32, u32);
+
 /// Network module
 pub struct NetworkModule {
     show_icon: bool,
@@ -24,14 +71,24 @@ pub struct NetworkModule {
     cached_text: String,
     network_type: NetworkType,
     network_name: Option<String>,
+    /// IPv4 address of the connected adapter
+    ip_address: Option<String>,
     signal_strength: u32, // 0-100 for WiFi
     is_connected: bool,
     download_speed: u64, // bytes per second
     upload_speed: u64,   // bytes per second
-    prev_total_in: u64,  // cumulative octets seen at last sample
-    prev_total_out: u64, // cumulative octets seen at last sample
     last_update: Instant,
     last_speed_update: Instant,
+    // Busiest processes (sorted by total throughput, highest first)
+    top_talkers: Vec<ProcessTraffic>,
+    // Cumulative (pid, bytes in, bytes out) per TCP connection at last sample
+    conn_bytes: HashMap<ConnKey, (u32, u64, u64)>,
+    // Cumulative "other" I/O bytes per process at last sample, for the estimate
+    // used when connection statistics aren't available
+    io_bytes: HashMap<u32, u64>,
+    // False when per-connection statistics could not be enabled (usually needs elevation)
+    estats_available: bool,
+    last_talker_sample: Instant,
 }
 
 impl NetworkModule {
@@ -43,14 +100,18 @@ impl NetworkModule {
             cached_text: String::new(),
             network_type: NetworkType::Unknown,
             network_name: None,
+            ip_address: None,
             signal_strength: 0,
             is_connected: false,
             download_speed: 0,
             upload_speed: 0,
-            prev_total_in: 0,
-            prev_total_out: 0,
             last_update: Instant::now(),
             last_speed_update: Instant::now(),
+            top_talkers: Vec::new(),
+            conn_bytes: HashMap::new(),
+            io_bytes: HashMap::new(),
+            estats_available: true,
+            last_talker_sample: Instant::now(),
         };
         module.force_update();
         module
@@ -58,6 +119,17 @@ impl NetworkModule {
 
     /// Force an immediate update
     fn force_update(&mut self) {
+        if crate::demo::is_enabled() {
+            self.network_type = NetworkType::WiFi;
+            self.network_name = Some("Demo Network".to_string());
+            self.ip_address = Some("192.168.1.24".to_string());
+            self.signal_strength = 82;
+            self.is_connected = true;
+            self.cached_text = self.build_display_text();
+            self.last_update = Instant::now();
+            return;
+        }
+
         // Check network connectivity
         self.check_network_status();
 
@@ -66,15 +138,6 @@ impl NetworkModule {
             self.get_wifi_info();
         }
 
-        // Initialize speed sampling to avoid a huge first delta
-        if let Some((total_in, total_out)) = self.sample_total_bytes() {
-            self.prev_total_in = total_in;
-            self.prev_total_out = total_out;
-            self.download_speed = 0;
-            self.upload_speed = 0;
-            self.last_speed_update = Instant::now();
-        }
-
         // Build display text
         self.cached_text = self.build_display_text();
         self.last_update = Instant::now();
@@ -94,47 +157,180 @@ impl NetworkModule {
         }
     }
 
-    /// Try to sample total interface bytes (received, transmitted) across adapters
-    fn sample_total_bytes(&self) -> Option<(u64, u64)> {
+    /// Pick up the latest speeds from the sampler
+    fn update_speeds(&mut self) {
+        let latest = &sampler::samples().latest;
+        self.download_speed = latest.download;
+        self.upload_speed = latest.upload;
+        self.last_speed_update = Instant::now();
+    }
+
+    /// Sample per-connection TCP statistics and rebuild the top-talkers list.
+    ///
+    /// Connections are enumerated with GetExtendedTcpTable (which reports the owning PID)
+    /// and per-connection byte counters are read with GetPerTcpConnectionEStats. Collection
+    /// has to be switched on for each connection first, which Windows only allows for
+    /// elevated processes; without elevation each process that owns a connection
+    /// is estimated from its "other" I/O counter instead, which includes socket
+    /// traffic but can't tell download from upload.
+    fn update_top_talkers(&mut self) {
+        use windows::Win32::Foundation::BOOLEAN;
+        use windows::Win32::NetworkManagement::IpHelper::{
+            GetExtendedTcpTable, GetPerTcpConnectionEStats, SetPerTcpConnectionEStats,
+            TcpConnectionEstatsData, MIB_TCPROW_LH, MIB_TCPROW_LH_0,
+            MIB_TCPTABLE_OWNER_PID, MIB_TCP_STATE_ESTAB, TCP_ESTATS_DATA_ROD_v0,
+            TCP_ESTATS_DATA_RW_v0, TCP_TABLE_OWNER_PID_ALL,
+        };
+        use windows::Win32::Networking::WinSock::AF_INET;
+
+        let elapsed = self.last_talker_sample.elapsed().as_secs_f64();
+        self.last_talker_sample = Instant::now();
+
+        let mut current: HashMap<ConnKey, (u32, u64, u64)> = HashMap::new();
+        let mut connected_pids: HashSet<u32> = HashSet::new();
+        let mut enabled_any = false;
+
         unsafe {
-            use windows::Win32::NetworkManagement::IpHelper::{
-                FreeMibTable, GetIfTable2, MIB_IF_TABLE2,
-            };
+            let mut size: u32 = 0;
+            let _ = GetExtendedTcpTable(None, &mut size, false, AF_INET.0 as u32, TCP_TABLE_OWNER_PID_ALL, 0);
+            if size == 0 {
+                return;
+            }
+
+            let mut buffer = vec![0u8; size as usize];
+            if GetExtendedTcpTable(
+                Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+                &mut size,
+                false,
+                AF_INET.0 as u32,
+                TCP_TABLE_OWNER_PID_ALL,
+                0,
+            ) != 0
+            {
+                return;
+            }
 
-            let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
-            if GetIfTable2(&mut table).0 == 0 && !table.is_null() {
-                let tbl = &*table;
-                let mut total_in: u64 = 0;
-                let mut total_out: u64 = 0;
-                for i in 0..(tbl.NumEntries as usize) {
-                    let row = &*(&tbl.Table as *const _
-                        as *const windows::Win32::NetworkManagement::IpHelper::MIB_IF_ROW2)
-                        .add(i);
-                    total_in = total_in.saturating_add(row.InOctets);
-                    total_out = total_out.saturating_add(row.OutOctets);
+            let table = &*(buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+            let rows = std::slice::from_raw_parts(
+                table.table.as_ptr(),
+                table.dwNumEntries as usize,
+            );
+
+            for r in rows {
+                if r.dwState != MIB_TCP_STATE_ESTAB.0 as u32 || r.dwOwningPid == 0 {
+                    continue;
+                }
+                connected_pids.insert(r.dwOwningPid);
+
+                let row = MIB_TCPROW_LH {
+                    Anonymous: MIB_TCPROW_LH_0 { dwState: r.dwState },
+                    dwLocalAddr: r.dwLocalAddr,
+                    dwLocalPort: r.dwLocalPort,
+                    dwRemoteAddr: r.dwRemoteAddr,
+                    dwRemotePort: r.dwRemotePort,
+                };
+                let key = (r.dwLocalAddr, r.dwLocalPort, r.dwRemoteAddr, r.dwRemotePort);
+
+                // New connections need data collection switched on before counters are populated
+                if !self.conn_bytes.contains_key(&key) {
+                    let rw = TCP_ESTATS_DATA_RW_v0 { EnableCollection: BOOLEAN(1) };
+                    let rw_bytes = std::slice::from_raw_parts(
+                        &rw as *const _ as *const u8,
+                        std::mem::size_of::<TCP_ESTATS_DATA_RW_v0>(),
+                    );
+                    if SetPerTcpConnectionEStats(&row, TcpConnectionEstatsData, rw_bytes, 0, 0) == 0 {
+                        enabled_any = true;
+                    }
+                }
+
+                let mut rod = TCP_ESTATS_DATA_ROD_v0::default();
+                let rod_bytes = std::slice::from_raw_parts_mut(
+                    &mut rod as *mut _ as *mut u8,
+                    std::mem::size_of::<TCP_ESTATS_DATA_ROD_v0>(),
+                );
+                if GetPerTcpConnectionEStats(&row, TcpConnectionEstatsData, None, 0, None, 0, Some(rod_bytes), 0) == 0 {
+                    current.insert(key, (r.dwOwningPid, rod.DataBytesIn, rod.DataBytesOut));
+                }
+            }
+        }
+
+        if enabled_any {
+            self.estats_available = true;
+        } else if current.values().all(|&(_, i, o)| i == 0 && o == 0) && !current.is_empty() {
+            // Every counter stayed at zero: collection could not be enabled
+            self.estats_available = false;
+        }
+
+        // Aggregate deltas per process
+        let mut per_pid: HashMap<u32, (u64, u64)> = HashMap::new();
+        if elapsed > 0.0 {
+            for (key, &(pid, bytes_in, bytes_out)) in &current {
+                if let Some(&(_, prev_in, prev_out)) = self.conn_bytes.get(key) {
+                    let entry = per_pid.entry(pid).or_insert((0, 0));
+                    entry.0 += (bytes_in.saturating_sub(prev_in) as f64 / elapsed) as u64;
+                    entry.1 += (bytes_out.saturating_sub(prev_out) as f64 / elapsed) as u64;
                 }
-                FreeMibTable(table as *mut _);
-                return Some((total_in, total_out));
             }
         }
-        None
+        self.conn_bytes = current;
+
+        let estimated = !self.estats_available;
+        if estimated {
+            per_pid = self.estimate_from_io(&connected_pids, elapsed);
+        } else {
+            self.io_bytes.clear();
+        }
+
+        let mut talkers: Vec<ProcessTraffic> = per_pid
+            .into_iter()
+            .filter(|(_, (down, up))| down + up > 0)
+            .map(|(pid, (down, up))| {
+                let path = process_image_path(pid);
+                let name = std::path::Path::new(&path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| format!("PID {}", pid));
+                ProcessTraffic { pid, name, path, download_speed: down, upload_speed: up, estimated }
+            })
+            .collect();
+        talkers.sort_by_key(|t| std::cmp::Reverse(t.total_speed()));
+        talkers.truncate(PROCESS_TABLE_COUNT);
+        self.top_talkers = talkers;
     }
 
-    /// Update upload/download speeds by sampling interface counters and computing deltas
-    fn update_speeds(&mut self) {
-        if let Some((total_in, total_out)) = self.sample_total_bytes() {
-            let elapsed = self.last_speed_update.elapsed().as_secs_f64();
-            if elapsed > 0.0 {
-                let delta_in = total_in.saturating_sub(self.prev_total_in);
-                let delta_out = total_out.saturating_sub(self.prev_total_out);
-                self.download_speed = (delta_in as f64 / elapsed) as u64; // bytes/sec
-                self.upload_speed = (delta_out as f64 / elapsed) as u64;
+    /// Per-process rates from the change in each process's "other" I/O bytes
+    /// (everything but file reads and writes, so mostly sockets), as
+    /// (combined, 0) for each of `pids`
+    fn estimate_from_io(&mut self, pids: &HashSet<u32>, elapsed: f64) -> HashMap<u32, (u64, u64)> {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::{
+            GetProcessIoCounters, OpenProcess, IO_COUNTERS, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        let mut current = HashMap::new();
+        for &pid in pids {
+            unsafe {
+                let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+                    continue;
+                };
+                let mut counters = IO_COUNTERS::default();
+                if GetProcessIoCounters(handle, &mut counters).is_ok() {
+                    current.insert(pid, counters.OtherTransferCount);
+                }
+                let _ = CloseHandle(handle);
             }
+        }
 
-            self.prev_total_in = total_in;
-            self.prev_total_out = total_out;
-            self.last_speed_update = Instant::now();
+        let mut per_pid = HashMap::new();
+        if elapsed > 0.0 {
+            for (pid, &bytes) in &current {
+                if let Some(&prev) = self.io_bytes.get(pid) {
+                    per_pid.insert(*pid, ((bytes.saturating_sub(prev) as f64 / elapsed) as u64, 0));
+                }
+            }
         }
+        self.io_bytes = current;
+        per_pid
     }
 
     /// Check network status using Windows API
@@ -142,6 +338,7 @@ impl NetworkModule {
         // Reset state before scanning
         self.is_connected = false;
         self.network_type = NetworkType::Unknown;
+        self.ip_address = None;
 
         // Simple connectivity check using IP helper
         unsafe {
@@ -194,12 +391,16 @@ impl NetworkModule {
                                 6 => {
                                     self.network_type = NetworkType::Ethernet;
                                     self.is_connected = true;
+                                    if self.ip_address.is_none() {
+                                        self.ip_address = first_ipv4(adapter);
+                                    }
                                     log::debug!("Adapter is Ethernet and up");
                                     // Don't break - prefer WiFi if available
                                 }
                                 71 => {
                                     self.network_type = NetworkType::WiFi;
                                     self.is_connected = true;
+                                    self.ip_address = first_ipv4(adapter);
                                     log::debug!("Adapter is WiFi and up");
                                     break; // WiFi found, stop looking
                                 }
@@ -385,6 +586,13 @@ impl NetworkModule {
         self.network_type
     }
 
+    /// Tell other modules when the connection comes or goes
+    fn announce(&self, was_connected: bool) {
+        if self.is_connected != was_connected {
+            events::publish(if self.is_connected { events::NETWORK_CONNECTED } else { events::NETWORK_DISCONNECTED });
+        }
+    }
+
     /// Check if connected
     pub fn is_connected(&self) -> bool {
         self.is_connected
@@ -399,6 +607,78 @@ impl NetworkModule {
     pub fn network_name(&self) -> Option<&str> {
         self.network_name.as_deref()
     }
+
+    /// Get the processes with the highest current throughput (highest first)
+    pub fn top_talkers(&self) -> &[ProcessTraffic] {
+        &self.top_talkers[..self.top_talkers.len().min(TOP_TALKERS_COUNT)]
+    }
+
+    /// Busiest processes for the usage popup, highest first
+    pub fn process_table(&self) -> &[ProcessTraffic] {
+        &self.top_talkers
+    }
+
+    /// Whether per-connection statistics are being collected
+    pub fn top_talkers_available(&self) -> bool {
+        self.estats_available
+    }
+
+    /// Current (download, upload) speeds in bytes per second
+    pub fn speeds(&self) -> (u64, u64) {
+        (self.download_speed, self.upload_speed)
+    }
+
+    /// Download and upload speed history, oldest first
+    pub fn speed_history(&self) -> (Vec<f32>, Vec<f32>) {
+        let samples = sampler::samples();
+        (samples.download.values(), samples.upload.values())
+    }
+}
+
+/// Resolve the full image path of a process (empty if access is denied)
+pub(crate) fn process_image_path(pid: u32) -> String {
+    use windows::core::PWSTR;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        if let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+            let mut buffer: Vec<u16> = vec![0; 260];
+            let mut size: u32 = buffer.len() as u32;
+            let result = QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_FORMAT(0),
+                PWSTR(buffer.as_mut_ptr()),
+                &mut size,
+            );
+            let _ = windows::Win32::Foundation::CloseHandle(handle);
+            if result.is_ok() && size > 0 {
+                return String::from_utf16_lossy(&buffer[..size as usize]);
+            }
+        }
+    }
+    String::new()
+}
+
+/// First IPv4 address assigned to an adapter
+unsafe fn first_ipv4(
+    adapter: &windows::Win32::NetworkManagement::IpHelper::IP_ADAPTER_ADDRESSES_LH,
+) -> Option<String> {
+    use windows::Win32::Networking::WinSock::{AF_INET, SOCKADDR_IN};
+
+    let mut unicast = adapter.FirstUnicastAddress;
+    while !unicast.is_null() {
+        let sockaddr = (*unicast).Address.lpSockaddr;
+        if !sockaddr.is_null() && (*sockaddr).sa_family == AF_INET {
+            let addr = &*(sockaddr as *const SOCKADDR_IN);
+            let octets = addr.sin_addr.S_un.S_addr.to_ne_bytes();
+            return Some(std::net::Ipv4Addr::from(octets).to_string());
+        }
+        unicast = (*unicast).Next;
+    }
+    None
 }
 
 impl Default for NetworkModule {
@@ -453,18 +733,48 @@ impl Module for NetworkModule {
         text
     }
 
-    fn update(&mut self, _config: &crate::config::Config) {
+    fn update(&mut self, config: &crate::config::Config) {
         // Update speeds every second
         if self.last_speed_update.elapsed().as_secs() >= 1 {
             self.update_speeds();
         }
 
+        // Per-process breakdown is more expensive; sample every 2 seconds when enabled
+        let wanted = config.modules.network.show_top_talkers || BREAKDOWN_WANTED.load(Ordering::Relaxed);
+        if wanted && self.last_talker_sample.elapsed().as_secs() >= 2 {
+            self.update_top_talkers();
+        }
+
         // Full refresh every 10 seconds
         if self.last_update.elapsed().as_secs() >= 10 {
+            let was_connected = self.is_connected;
             self.force_update();
+            self.announce(was_connected);
         }
     }
 
+    fn force_refresh(&mut self, _config: &crate::config::Config) {
+        let was_connected = self.is_connected;
+        self.force_update();
+        self.announce(was_connected);
+    }
+
+    fn copy_value(&self, config: &crate::config::Config) -> String {
+        self.ip_address
+            .clone()
+            .or_else(|| self.network_name.clone())
+            .unwrap_or_else(|| self.display_text(config))
+    }
+
+    fn history(&self) -> Option<super::History> {
+        // Combined download + upload speed per sample
+        let (download, upload) = self.speed_history();
+        Some(super::History {
+            values: download.iter().zip(&upload).map(|(down, up)| down + up).collect(),
+            max: None,
+        })
+    }
+
     fn on_click(&mut self) {
         // Open network settings
         crate::utils::open_url("ms-settings:network");
@@ -479,10 +789,10 @@ impl Module for NetworkModule {
             NetworkType::Unknown => "Unknown",
         };
 
-        let mut tooltip = format!("Network: {}", type_str);
+        let mut tooltip = format!("{}: {}", tr("Connection"), tr(type_str));
 
         if self.network_type == NetworkType::WiFi {
-            tooltip.push_str(&format!("\nSignal Strength: {}%", self.signal_strength));
+            tooltip.push_str(&format!("\n{}: {}%", tr("Signal"), self.signal_strength));
 
             // Signal quality description
             let quality = if self.signal_strength >= 80 {
@@ -494,11 +804,16 @@ impl Module for NetworkModule {
             } else {
                 "Weak"
             };
-            tooltip.push_str(&format!(" ({})", quality));
+            tooltip.push_str(&format!(" ({})", tr(quality)));
         }
 
         if let Some(ref name) = self.network_name {
-            tooltip.push_str(&format!("\nNetwork: {}", name));
+            let label = if self.network_type == NetworkType::WiFi { "SSID" } else { "Network" };
+            tooltip.push_str(&format!("\n{}: {}", tr(label), name));
+        }
+
+        if let Some(ref ip) = self.ip_address {
+            tooltip.push_str(&format!("\nIP: {}", ip));
         }
 
         // Show speeds in tooltip when we have samples
@@ -506,11 +821,7 @@ impl Module for NetworkModule {
             // Use values already sampled; convert to MB/s
             let down_mb = (self.download_speed as f64) / 1_000_000.0;
             let up_mb = (self.upload_speed as f64) / 1_000_000.0;
-            tooltip.push_str(&format!(
-                "\nSpeed: {down:.2} MB/s down / {up:.2} MB/s up",
-                down = down_mb,
-                up = up_mb
-            ));
+            tooltip.push_str(&format!("\nDownload: {:.2} MB/s\nUpload: {:.2} MB/s", down_mb, up_mb));
         }
 
         Some(tooltip)
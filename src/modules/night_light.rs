@@ -1,12 +1,22 @@
 //! Night Light module - toggle Windows Night Light feature
+//!
+//! Besides manual toggling, the module can follow a schedule (sunset to sunrise at the
+//! weather module's location, or fixed times) and watches the registry so changes made
+//! from Windows Settings or the Action Center show up immediately.
 
 use std::time::Instant;
 
+use chrono::{Local, NaiveTime};
+
+use super::events;
 use super::Module;
+use crate::config::NightLightSchedule;
 use windows::Win32::System::Registry::{
-    RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, RegCloseKey,
-    HKEY_CURRENT_USER, KEY_READ, KEY_WRITE, REG_BINARY, REG_VALUE_TYPE,
+    RegNotifyChangeKeyValue, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, RegCloseKey,
+    HKEY_CURRENT_USER, KEY_NOTIFY, KEY_READ, KEY_WRITE, REG_BINARY, REG_NOTIFY_CHANGE_LAST_SET,
+    REG_VALUE_TYPE,
 };
+use windows::Win32::Foundation::HANDLE;
 use windows::core::PCWSTR;
 
 /// Registry path for Night Light state
@@ -24,6 +34,13 @@ pub enum NightLightState {
 pub struct NightLightModule {
     state: NightLightState,
     last_update: Instant,
+    /// Today's (sunrise, sunset) from the weather module, if known
+    sun_times: Option<(NaiveTime, NaiveTime)>,
+    /// Last state the schedule asked for; the schedule only acts when this changes,
+    /// so a manual toggle sticks until the next scheduled transition
+    last_scheduled: Option<bool>,
+    /// Active schedule window (on, off) for the tooltip
+    schedule_window: Option<(NaiveTime, NaiveTime)>,
 }
 
 impl NightLightModule {
@@ -31,11 +48,116 @@ impl NightLightModule {
         let mut module = Self {
             state: NightLightState::Unknown,
             last_update: Instant::now(),
+            sun_times: None,
+            last_scheduled: None,
+            schedule_window: None,
         };
         module.refresh_state();
+        Self::spawn_registry_watcher();
         module
     }
 
+    /// Watch the Night Light registry value and notify the UI thread when it changes,
+    /// so toggles made outside TopBar are reflected without waiting for the next poll.
+    fn spawn_registry_watcher() {
+        std::thread::spawn(|| unsafe {
+            let key_path: Vec<u16> = NIGHT_LIGHT_KEY.encode_utf16().chain(std::iter::once(0)).collect();
+            let mut hkey = windows::Win32::System::Registry::HKEY::default();
+            let result = RegOpenKeyExW(HKEY_CURRENT_USER, PCWSTR(key_path.as_ptr()), 0, KEY_NOTIFY, &mut hkey);
+            if result.is_err() {
+                log::debug!("NightLight: registry watcher unavailable: {:?}", result);
+                return;
+            }
+
+            loop {
+                // Blocks until a value under the key is written
+                let rc = RegNotifyChangeKeyValue(hkey, false, REG_NOTIFY_CHANGE_LAST_SET, HANDLE::default(), false);
+                if rc.is_err() {
+                    log::debug!("NightLight: registry watcher stopped: {:?}", rc);
+                    break;
+                }
+                Self::notify_state_changed(true);
+            }
+
+            let _ = RegCloseKey(hkey);
+        });
+    }
+
+    /// Ask the main window to refresh this module's state and redraw
+    fn notify_state_changed(ok: bool) {
+        // Use stored main HWND instead of HWND_BROADCAST for reliable delivery
+        if let Some(main_hwnd) = crate::window::get_main_hwnd() {
+            unsafe {
+                let _ = windows::Win32::UI::WindowsAndMessaging::PostMessageW(
+                    main_hwnd,
+                    crate::window::WM_TOPBAR_NIGHTLIGHT_TOGGLED,
+                    windows::Win32::Foundation::WPARAM(if ok { 1 } else { 0 }),
+                    windows::Win32::Foundation::LPARAM(0),
+                );
+            }
+        }
+    }
+
+    /// Provide today's sunrise/sunset for the sunset-to-sunrise schedule
+    pub fn set_sun_times(&mut self, sun_times: Option<(NaiveTime, NaiveTime)>) {
+        if sun_times.is_some() {
+            self.sun_times = sun_times;
+        }
+    }
+
+    /// Resolve the (on, off) times for the configured schedule
+    fn schedule_times(&self, config: &crate::config::NightLightConfig) -> Option<(NaiveTime, NaiveTime)> {
+        let custom = || {
+            let start = NaiveTime::parse_from_str(config.start_time.trim(), "%H:%M").ok()?;
+            let end = NaiveTime::parse_from_str(config.end_time.trim(), "%H:%M").ok()?;
+            Some((start, end))
+        };
+
+        match config.schedule {
+            NightLightSchedule::Manual => None,
+            NightLightSchedule::SunsetToSunrise => self
+                .sun_times
+                .map(|(sunrise, sunset)| (sunset, sunrise))
+                .or_else(custom),
+            NightLightSchedule::Custom => custom(),
+        }
+    }
+
+    /// Whether `now` falls inside the [on, off) window, which may wrap past midnight
+    fn in_window(now: NaiveTime, on: NaiveTime, off: NaiveTime) -> bool {
+        if on <= off {
+            now >= on && now < off
+        } else {
+            now >= on || now < off
+        }
+    }
+
+    /// Switch Night Light when the schedule crosses a boundary
+    fn apply_schedule(&mut self, config: &crate::config::NightLightConfig) {
+        self.schedule_window = self.schedule_times(config);
+        let Some((on, off)) = self.schedule_window else {
+            self.last_scheduled = None;
+            return;
+        };
+
+        let wanted = Self::in_window(Local::now().time(), on, off);
+        if self.last_scheduled == Some(wanted) {
+            return;
+        }
+        self.last_scheduled = Some(wanted);
+
+        let current_on = self.state == NightLightState::On;
+        if self.state != NightLightState::Unknown && current_on == wanted {
+            return;
+        }
+
+        log::info!("NightLight: schedule switching {}", if wanted { "on" } else { "off" });
+        std::thread::spawn(move || {
+            let ok = Self::set_night_light_enabled(wanted);
+            Self::notify_state_changed(ok);
+        });
+    }
+
     /// Read current Night Light state from registry
     fn refresh_state(&mut self) {
         self.state = Self::read_night_light_state().unwrap_or(NightLightState::Unknown);
@@ -129,17 +251,7 @@ impl NightLightModule {
             log::info!("NightLight: background toggle completed -> {}", ok);
 
             // Notify main window to refresh and redraw (asynchronous)
-            // Use stored main HWND instead of HWND_BROADCAST for reliable delivery
-            if let Some(main_hwnd) = crate::window::get_main_hwnd() {
-                unsafe {
-                    let _ = windows::Win32::UI::WindowsAndMessaging::PostMessageW(
-                        main_hwnd,
-                        crate::window::WM_TOPBAR_NIGHTLIGHT_TOGGLED,
-                        windows::Win32::Foundation::WPARAM(if ok { 1 } else { 0 }),
-                        windows::Win32::Foundation::LPARAM(0),
-                    );
-                }
-            }
+            Self::notify_state_changed(ok);
         });
 
         self.last_update = Instant::now();
@@ -373,11 +485,12 @@ impl Module for NightLightModule {
         }
     }
 
-    fn update(&mut self, _config: &crate::config::Config) {
-        // Refresh state periodically (every 5 seconds)
-        if self.last_update.elapsed().as_secs() > 5 {
+    fn update(&mut self, config: &crate::config::Config) {
+        // The registry watcher covers external changes; poll occasionally as a fallback
+        if self.last_update.elapsed().as_secs() > 30 {
             self.refresh_state();
         }
+        self.apply_schedule(&config.modules.night_light);
     }
 
     fn on_click(&mut self) {
@@ -395,7 +508,14 @@ impl Module for NightLightModule {
             NightLightState::Off => "OFF",
             NightLightState::Unknown => "Unknown",
         };
-        Some(format!("Night Light: {}\nClick to toggle\nRight-click for settings", state_text))
+        let schedule_text = match self.schedule_window {
+            Some((on, off)) => format!("\nScheduled: {} – {}", on.format("%H:%M"), off.format("%H:%M")),
+            None => String::new(),
+        };
+        Some(format!(
+            "Night Light: {}{}\nClick to toggle\nRight-click for settings",
+            state_text, schedule_text
+        ))
     }
 
     fn is_visible(&self) -> bool {
@@ -409,4 +529,12 @@ impl Module for NightLightModule {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::NIGHT_LIGHT_CHANGED]
+    }
+
+    fn on_event(&mut self, _topic: &str, _config: &crate::config::Config) {
+        self.refresh();
+    }
 }
@@ -0,0 +1,163 @@
+//! Power menu module - lock, sleep, hibernate, restart, shut down or sign out
+//!
+//! The button opens a menu of power actions; the ones listed in
+//! `modules.power.confirm` ask first. The same actions can be run with
+//! `power:<action>` from a hotkey or `topbarctl`.
+
+use log::{info, warn};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{CloseHandle, GetLastError, BOOLEAN, ERROR_NOT_ALL_ASSIGNED, HANDLE, LUID};
+use windows::Win32::Security::{
+    AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES,
+    TOKEN_PRIVILEGES, TOKEN_QUERY,
+};
+use windows::Win32::System::Power::{GetPwrCapabilities, SetSuspendState, SYSTEM_POWER_CAPABILITIES};
+use windows::Win32::System::Shutdown::*;
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, IDYES, MB_ICONQUESTION, MB_YESNO};
+
+use super::Module;
+use crate::config::{PowerAction, PowerConfig};
+use crate::utils::to_wide_string;
+
+/// Power menu module
+pub struct PowerModule {
+    /// Whether hibernation is enabled, checked once at startup
+    can_hibernate: bool,
+}
+
+impl PowerModule {
+    pub fn new() -> Self {
+        Self {
+            can_hibernate: hibernate_available(),
+        }
+    }
+
+    /// Whether the Hibernate entry can be used
+    pub fn can_hibernate(&self) -> bool {
+        self.can_hibernate
+    }
+}
+
+impl Default for PowerModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for PowerModule {
+    fn id(&self) -> &str {
+        "power"
+    }
+
+    fn name(&self) -> &str {
+        "Power"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        "⏻".to_string()
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {}
+
+    fn tooltip(&self) -> Option<String> {
+        Some("Lock, sleep, restart or shut down".to_string())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Whether the PC supports hibernation and it's turned on
+fn hibernate_available() -> bool {
+    let mut caps = SYSTEM_POWER_CAPABILITIES::default();
+    unsafe { GetPwrCapabilities(&mut caps).as_bool() && caps.SystemS4.as_bool() && caps.HiberFilePresent.as_bool() }
+}
+
+/// Run a power action, asking first if the config says so. Returns false if it
+/// was cancelled or failed.
+pub fn request(action: PowerAction, config: &PowerConfig) -> bool {
+    if config.confirm.contains(&action) && !confirm(action) {
+        info!("Power action {:?} cancelled", action);
+        return false;
+    }
+    match run(action) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Power action {:?} failed: {}", action, e);
+            false
+        }
+    }
+}
+
+fn confirm(action: PowerAction) -> bool {
+    let question = match action {
+        PowerAction::Lock => "Lock the computer?",
+        PowerAction::Sleep => "Put the computer to sleep?",
+        PowerAction::Hibernate => "Hibernate the computer?",
+        PowerAction::Restart => "Restart the computer now?\n\nUnsaved work in open apps may be lost.",
+        PowerAction::Shutdown => "Shut down the computer now?\n\nUnsaved work in open apps may be lost.",
+        PowerAction::SignOut => "Sign out now?\n\nUnsaved work in open apps may be lost.",
+    };
+    let title = to_wide_string(action.label());
+    let text = to_wide_string(question);
+    unsafe { MessageBoxW(None, PCWSTR(text.as_ptr()), PCWSTR(title.as_ptr()), MB_YESNO | MB_ICONQUESTION) == IDYES }
+}
+
+/// Run a power action right away
+pub fn run(action: PowerAction) -> windows::core::Result<()> {
+    info!("Power action: {:?}", action);
+    let reason = SHTDN_REASON_MAJOR_OTHER | SHTDN_REASON_MINOR_OTHER | SHTDN_REASON_FLAG_PLANNED;
+    unsafe {
+        match action {
+            PowerAction::Lock => LockWorkStation(),
+            PowerAction::Sleep | PowerAction::Hibernate => {
+                enable_shutdown_privilege()?;
+                let hibernate = BOOLEAN::from(action == PowerAction::Hibernate);
+                if SetSuspendState(hibernate, BOOLEAN::from(false), BOOLEAN::from(false)).as_bool() {
+                    Ok(())
+                } else {
+                    Err(windows::core::Error::from_win32())
+                }
+            }
+            PowerAction::Restart => {
+                enable_shutdown_privilege()?;
+                ExitWindowsEx(EWX_REBOOT, reason)
+            }
+            PowerAction::Shutdown => {
+                enable_shutdown_privilege()?;
+                ExitWindowsEx(EWX_POWEROFF, reason)
+            }
+            PowerAction::SignOut => ExitWindowsEx(EWX_LOGOFF, reason),
+        }
+    }
+}
+
+/// Shutting down and suspending need SeShutdownPrivilege, which users hold but
+/// processes start with turned off
+fn enable_shutdown_privilege() -> windows::core::Result<()> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token)?;
+        let mut luid = LUID::default();
+        let lookup = LookupPrivilegeValueW(None, w!("SeShutdownPrivilege"), &mut luid);
+        let privileges = TOKEN_PRIVILEGES {
+            PrivilegeCount: 1,
+            Privileges: [LUID_AND_ATTRIBUTES { Luid: luid, Attributes: SE_PRIVILEGE_ENABLED }],
+        };
+        let adjusted = lookup.and_then(|_| AdjustTokenPrivileges(token, false, Some(&privileges), 0, None, None));
+        // AdjustTokenPrivileges succeeds even when the privilege isn't held
+        let not_assigned = GetLastError() == ERROR_NOT_ALL_ASSIGNED;
+        let _ = CloseHandle(token);
+        adjusted?;
+        if not_assigned {
+            return Err(ERROR_NOT_ALL_ASSIGNED.into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,322 @@
+//! Printing module - shows queued print jobs, like the macOS printer menu
+//!
+//! The spooler is asked for the jobs on every local and connected printer
+//! (EnumPrinters, then EnumJobs on each). The module only takes up space on the
+//! bar while something is queued; clicking it opens the printer's queue window.
+//! Network printers can be slow to answer, so queues are read off the UI thread.
+
+use std::time::Instant;
+
+use log::{info, warn};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Graphics::Printing::*;
+
+use super::Module;
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::i18n::tr;
+use crate::runtime::Task;
+use crate::utils::to_wide_string;
+
+/// Jobs asked for per printer
+const MAX_JOBS: u32 = 64;
+
+/// Status bits that need someone at the printer
+const PROBLEM_STATUS: u32 = JOB_STATUS_ERROR
+    | JOB_STATUS_OFFLINE
+    | JOB_STATUS_PAPEROUT
+    | JOB_STATUS_USER_INTERVENTION
+    | JOB_STATUS_BLOCKED_DEVQ;
+
+/// One queued print job
+#[derive(Debug, Clone)]
+pub struct PrintJob {
+    pub id: u32,
+    pub printer: String,
+    pub document: String,
+    pub owner: String,
+    /// The driver's own status text, else one made from the status bits
+    pub status: String,
+    /// Out of paper, offline or otherwise stuck
+    pub problem: bool,
+    pub pages_printed: u32,
+    /// 0 while the job is still spooling
+    pub total_pages: u32,
+}
+
+impl PrintJob {
+    /// Status and page progress, e.g. "Printing (3/10)"
+    pub fn progress(&self) -> String {
+        if self.total_pages > 0 {
+            format!("{} ({}/{})", tr(&self.status), self.pages_printed, self.total_pages)
+        } else {
+            tr(&self.status)
+        }
+    }
+}
+
+/// Print queue module
+pub struct PrintingModule {
+    jobs: Vec<PrintJob>,
+    pending: Option<Task<Vec<PrintJob>>>,
+    last_update: Option<Instant>,
+}
+
+impl PrintingModule {
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            pending: None,
+            last_update: None,
+        }
+    }
+
+    /// Queued jobs, grouped by printer in spooler order
+    pub fn jobs(&self) -> &[PrintJob] {
+        &self.jobs
+    }
+
+    /// Printers that have jobs queued, in the order they're listed
+    pub fn printers(&self) -> Vec<String> {
+        let mut printers: Vec<String> = Vec::new();
+        for job in &self.jobs {
+            if !printers.contains(&job.printer) {
+                printers.push(job.printer.clone());
+            }
+        }
+        printers
+    }
+
+    /// Whether any job is stuck
+    pub fn has_problem(&self) -> bool {
+        self.jobs.iter().any(|j| j.problem)
+    }
+
+    /// Take the background read once it's done
+    fn collect(&mut self, config: &crate::config::Config) {
+        let Some(result) = self.pending.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.pending = None;
+        let jobs = match result {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                warn!("Failed to read print queues: {}", e);
+                return;
+            }
+        };
+
+        // A job that's newly stuck, not one that was already
+        for job in jobs.iter().filter(|j| j.problem) {
+            let known = self
+                .jobs
+                .iter()
+                .any(|p| p.id == job.id && p.printer == job.printer && p.problem);
+            if !known {
+                crate::attention::request(
+                    config,
+                    AttentionRequest::new(
+                        "printing",
+                        AttentionLevel::Warning,
+                        "Printing problem",
+                        &format!("{} on {}: {}", job.document, job.printer, tr(&job.status)),
+                    ),
+                );
+            }
+        }
+        self.jobs = jobs;
+    }
+}
+
+impl Default for PrintingModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for PrintingModule {
+    fn id(&self) -> &str {
+        "printing"
+    }
+
+    fn name(&self) -> &str {
+        "Printing"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        match self.jobs.first() {
+            None => String::new(),
+            // The job at the head of the queue is the one the printer is on
+            Some(job) => format!("🖨 {} · {}", self.jobs.len(), tr(&job.status)),
+        }
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        self.collect(config);
+        let due = self
+            .last_update
+            .is_none_or(|t| t.elapsed().as_millis() as u64 >= config.modules.printing.update_interval_ms);
+        if due && self.pending.is_none() {
+            self.pending = Some(crate::runtime::fetch(list_jobs));
+            self.last_update = Some(Instant::now());
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.last_update = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.jobs.is_empty() {
+            return Some(tr("No print jobs"));
+        }
+        let lines: Vec<String> = self
+            .jobs
+            .iter()
+            .map(|j| {
+                let mark = if j.problem { "⚠" } else { "•" };
+                format!("{} {} — {}: {}", mark, j.document, j.printer, j.progress())
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.jobs.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Open a printer's queue window ("See what's printing")
+pub fn open_queue(printer: &str) {
+    info!("Opening print queue for {}", printer);
+    let _ = std::process::Command::new("rundll32.exe")
+        .args(["printui.dll,PrintUIEntry", "/o", "/n", printer])
+        .spawn();
+}
+
+/// Cancel a job; the spooler only allows it for the user's own jobs unless elevated
+pub fn cancel(printer: &str, job_id: u32) {
+    unsafe {
+        let Some(handle) = open_printer(printer) else {
+            return;
+        };
+        if !SetJobW(handle, job_id, 0, None, JOB_CONTROL_DELETE).as_bool() {
+            warn!("Failed to cancel print job {} on {}", job_id, printer);
+        }
+        let _ = ClosePrinter(handle);
+    }
+}
+
+/// Every job queued on a local or connected printer
+fn list_jobs() -> Vec<PrintJob> {
+    printer_names()
+        .iter()
+        .flat_map(|printer| unsafe { printer_jobs(printer) })
+        .collect()
+}
+
+fn printer_names() -> Vec<String> {
+    let flags = PRINTER_ENUM_LOCAL | PRINTER_ENUM_CONNECTIONS;
+    let mut needed = 0u32;
+    let mut count = 0u32;
+    unsafe {
+        // The first call only says how big the buffer must be
+        let _ = EnumPrintersW(flags, PCWSTR::null(), 4, None, &mut needed, &mut count);
+        if needed == 0 {
+            return Vec::new();
+        }
+        let mut buffer = vec![0u8; needed as usize];
+        if EnumPrintersW(flags, PCWSTR::null(), 4, Some(&mut buffer), &mut needed, &mut count).is_err() {
+            return Vec::new();
+        }
+        let printers = std::slice::from_raw_parts(buffer.as_ptr() as *const PRINTER_INFO_4W, count as usize);
+        printers.iter().map(|p| wide(p.pPrinterName)).filter(|n| !n.is_empty()).collect()
+    }
+}
+
+unsafe fn open_printer(printer: &str) -> Option<HANDLE> {
+    let name = to_wide_string(printer);
+    let mut handle = HANDLE::default();
+    OpenPrinterW(PCWSTR(name.as_ptr()), &mut handle, None).ok()?;
+    Some(handle)
+}
+
+unsafe fn printer_jobs(printer: &str) -> Vec<PrintJob> {
+    let Some(handle) = open_printer(printer) else {
+        return Vec::new();
+    };
+    let mut needed = 0u32;
+    let mut count = 0u32;
+    let _ = EnumJobsW(handle, 0, MAX_JOBS, 1, None, &mut needed, &mut count);
+    let mut jobs = Vec::new();
+    if needed > 0 {
+        let mut buffer = vec![0u8; needed as usize];
+        if EnumJobsW(handle, 0, MAX_JOBS, 1, Some(&mut buffer), &mut needed, &mut count).is_ok() {
+            let infos = std::slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_1W, count as usize);
+            jobs = infos
+                .iter()
+                // Printed and deleted jobs linger briefly; they aren't queued anymore
+                .filter(|j| j.Status & (JOB_STATUS_PRINTED | JOB_STATUS_DELETED | JOB_STATUS_COMPLETE) == 0)
+                .map(|j| {
+                    let driver_status = wide(j.pStatus);
+                    PrintJob {
+                        id: j.JobId,
+                        printer: printer.to_string(),
+                        document: wide(j.pDocument),
+                        owner: wide(j.pUserName),
+                        status: if driver_status.is_empty() {
+                            status_label(j.Status).to_string()
+                        } else {
+                            driver_status
+                        },
+                        problem: j.Status & PROBLEM_STATUS != 0,
+                        pages_printed: j.PagesPrinted,
+                        total_pages: j.TotalPages,
+                    }
+                })
+                .collect();
+        }
+    }
+    let _ = ClosePrinter(handle);
+    jobs
+}
+
+/// Describe the most pressing of a job's status bits
+fn status_label(status: u32) -> &'static str {
+    if status & JOB_STATUS_PAPEROUT != 0 {
+        "Out of paper"
+    } else if status & JOB_STATUS_OFFLINE != 0 {
+        "Printer offline"
+    } else if status & (JOB_STATUS_USER_INTERVENTION | JOB_STATUS_BLOCKED_DEVQ) != 0 {
+        "Needs attention"
+    } else if status & JOB_STATUS_ERROR != 0 {
+        "Error"
+    } else if status & JOB_STATUS_PAUSED != 0 {
+        "Paused"
+    } else if status & JOB_STATUS_DELETING != 0 {
+        "Cancelling"
+    } else if status & JOB_STATUS_PRINTING != 0 {
+        "Printing"
+    } else if status & JOB_STATUS_SPOOLING != 0 {
+        "Spooling"
+    } else {
+        "Queued"
+    }
+}
+
+fn wide(text: PWSTR) -> String {
+    if text.is_null() {
+        String::new()
+    } else {
+        unsafe { text.to_string().unwrap_or_default() }
+    }
+}
@@ -0,0 +1,445 @@
+//! Removable media module - USB drives and memory cards, with safe eject
+//!
+//! Drives are listed again whenever the bar hears of a device or volume arriving
+//! or leaving (WM_DEVICECHANGE), so the module shows up only while one is
+//! attached. A drive counts when Windows calls it removable or it's on the USB
+//! bus (external hard drives report as fixed). While any are attached, their
+//! read and write counters are sampled every second to tell when a copy is in
+//! progress. Ejecting asks Plug and Play to remove the whole device, the same
+//! request "Safely Remove Hardware" makes, which open files can veto.
+
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use windows::core::PCWSTR;
+use windows::Win32::Devices::DeviceAndDriverInstallation::{
+    CM_Get_Parent, CM_Request_Device_EjectW, SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInterfaces,
+    SetupDiGetClassDevsW, SetupDiGetDeviceInterfaceDetailW, CR_SUCCESS, DIGCF_DEVICEINTERFACE, DIGCF_PRESENT,
+    PNP_VETO_TYPE, PNP_VetoOutstandingOpen, PNP_VetoTypeUnknown, SP_DEVICE_INTERFACE_DATA,
+    SP_DEVICE_INTERFACE_DETAIL_DATA_W, SP_DEVINFO_DATA,
+};
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Storage::FileSystem::{
+    BusTypeUsb, CreateFileW, GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW,
+    FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows::Win32::System::Ioctl::{
+    PropertyStandardQuery, StorageDeviceProperty, DISK_PERFORMANCE, GUID_DEVINTERFACE_DISK, IOCTL_DISK_PERFORMANCE,
+    IOCTL_STORAGE_GET_DEVICE_NUMBER, IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_DEVICE_DESCRIPTOR, STORAGE_DEVICE_NUMBER,
+    STORAGE_PROPERTY_QUERY,
+};
+use windows::Win32::System::WindowsProgramming::{DRIVE_FIXED, DRIVE_REMOVABLE};
+
+use super::events;
+use super::smart::{as_bytes, ioctl};
+use super::Module;
+use crate::i18n::tr;
+use crate::runtime::Task;
+use crate::utils::{format_bytes, to_wide_string};
+
+/// How often attached drives are sampled for activity
+const ACTIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// How often drives are looked for when none are attached, in case a change was missed
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+/// Reads plus writes per second from which a drive counts as busy
+const BUSY_RATE: u64 = 64 * 1024;
+
+/// An attached removable volume
+#[derive(Debug, Clone)]
+pub struct RemovableVolume {
+    /// Drive letter with colon, e.g. "E:"
+    pub root: String,
+    pub label: String,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    /// Bytes read and written since the drive was attached, when it reports them
+    counters: Option<(u64, u64)>,
+    /// Read and write rates since the last sample, in bytes per second
+    pub read_rate: u64,
+    pub write_rate: u64,
+}
+
+impl RemovableVolume {
+    /// Label and drive letter, e.g. "BACKUP (E:)"
+    pub fn name(&self) -> String {
+        let label = if self.label.is_empty() { tr("USB Drive") } else { self.label.clone() };
+        format!("{} ({})", label, self.root)
+    }
+
+    /// Whether something is copying to or from the drive
+    pub fn is_busy(&self) -> bool {
+        self.read_rate + self.write_rate >= BUSY_RATE
+    }
+}
+
+/// Removable media module
+pub struct RemovableMediaModule {
+    volumes: Vec<RemovableVolume>,
+    scan: Option<Task<Vec<RemovableVolume>>>,
+    /// When the last scan was started; None to scan on the next update
+    last_scan: Option<Instant>,
+    /// When the volumes' counters were read, for the rates
+    sampled: Option<Instant>,
+    /// Drive being ejected, and the outcome once Plug and Play answers
+    ejecting: Option<(String, Task<Result<(), String>>)>,
+}
+
+impl RemovableMediaModule {
+    pub fn new() -> Self {
+        Self {
+            volumes: Vec::new(),
+            scan: None,
+            last_scan: None,
+            sampled: None,
+            ejecting: None,
+        }
+    }
+
+    /// Attached volumes, by drive letter
+    pub fn volumes(&self) -> &[RemovableVolume] {
+        &self.volumes
+    }
+
+    /// Drive letter of the volume being ejected, if any
+    pub fn ejecting(&self) -> Option<&str> {
+        self.ejecting.as_ref().map(|(root, _)| root.as_str())
+    }
+
+    /// Whether any drive is busy copying
+    pub fn is_busy(&self) -> bool {
+        self.volumes.iter().any(|v| v.is_busy())
+    }
+
+    /// Safely remove the device a volume is on, in the background
+    pub fn eject(&mut self, root: &str) {
+        if self.ejecting.is_some() {
+            return;
+        }
+        info!("Ejecting {}", root);
+        let drive = root.to_string();
+        self.ejecting = Some((root.to_string(), crate::runtime::fetch(move || eject(&drive))));
+    }
+
+    /// Take a finished scan, working out each drive's rates from its last counters
+    fn collect_scan(&mut self) {
+        let Some(result) = self.scan.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.scan = None;
+        let mut volumes = match result {
+            Ok(volumes) => volumes,
+            Err(e) => {
+                warn!("Failed to list removable drives: {}", e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        let seconds = self.sampled.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        for volume in &mut volumes {
+            let previous = self.volumes.iter().find(|v| v.root == volume.root).and_then(|v| v.counters);
+            if let (Some((read, written)), Some((last_read, last_written)), true) =
+                (volume.counters, previous, seconds > 0.0)
+            {
+                volume.read_rate = (read.saturating_sub(last_read) as f64 / seconds) as u64;
+                volume.write_rate = (written.saturating_sub(last_written) as f64 / seconds) as u64;
+            }
+        }
+        self.volumes = volumes;
+        self.sampled = Some(now);
+    }
+
+    /// Report how an eject went, once it has
+    fn collect_eject(&mut self) {
+        let Some((root, task)) = &self.ejecting else {
+            return;
+        };
+        let Some(result) = task.poll() else {
+            return;
+        };
+        let root = root.clone();
+        self.ejecting = None;
+        match result.and_then(|outcome| outcome) {
+            Ok(()) => {
+                crate::render::show_osd(&tr("{} can be safely removed").replacen("{}", &root, 1), "⏏");
+                self.volumes.retain(|v| v.root != root);
+            }
+            Err(reason) => {
+                warn!("Couldn't eject {}: {}", root, reason);
+                let text = tr("Couldn't eject {}: {}").replacen("{}", &root, 1).replacen("{}", &tr(&reason), 1);
+                crate::render::show_osd(&text, "⚠");
+            }
+        }
+        self.last_scan = None;
+    }
+}
+
+impl Default for RemovableMediaModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for RemovableMediaModule {
+    fn id(&self) -> &str {
+        "removable_media"
+    }
+
+    fn name(&self) -> &str {
+        "Removable Media"
+    }
+
+    fn display_text(&self, config: &crate::config::Config) -> String {
+        let text = match self.volumes.as_slice() {
+            [] => return String::new(),
+            [volume] => format!("⏏ {}", volume.root),
+            volumes => format!("⏏ {}", volumes.len()),
+        };
+        if self.ejecting.is_some() {
+            return format!("{} …", text);
+        }
+        let rate: u64 = self.volumes.iter().map(|v| v.read_rate + v.write_rate).sum();
+        if config.modules.removable_media.show_activity && self.is_busy() {
+            format!("{} ⇅ {}/s", text, format_bytes(rate))
+        } else {
+            text
+        }
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {
+        self.collect_eject();
+        self.collect_scan();
+        let interval = if self.volumes.is_empty() { IDLE_INTERVAL } else { ACTIVE_INTERVAL };
+        let due = self.last_scan.is_none_or(|t| t.elapsed() >= interval);
+        // Opening the drives during an eject could be what vetoes it
+        if due && self.scan.is_none() && self.ejecting.is_none() {
+            self.scan = Some(crate::runtime::fetch(list_volumes));
+            self.last_scan = Some(Instant::now());
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.last_scan = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.volumes.is_empty() {
+            return Some(tr("No removable drives"));
+        }
+        let lines: Vec<String> = self
+            .volumes
+            .iter()
+            .map(|v| {
+                let mut line = format!(
+                    "{}: {} {} {}",
+                    v.name(),
+                    format_bytes(v.free_bytes),
+                    tr("free of"),
+                    format_bytes(v.total_bytes)
+                );
+                if v.is_busy() {
+                    line.push_str(&format!(" · ↓{}/s ↑{}/s", format_bytes(v.read_rate), format_bytes(v.write_rate)));
+                }
+                line
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.volumes.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::DEVICES_CHANGED, events::VOLUMES_CHANGED]
+    }
+
+    fn on_event(&mut self, _topic: &str, _config: &crate::config::Config) {
+        self.last_scan = None;
+    }
+}
+
+/// Open a volume or device without read or write access, which queries allow
+unsafe fn open_device(path: &str) -> Option<HANDLE> {
+    let wide = to_wide_string(path);
+    CreateFileW(
+        PCWSTR(wide.as_ptr()),
+        0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        None,
+        OPEN_EXISTING,
+        FILE_ATTRIBUTE_NORMAL,
+        HANDLE::default(),
+    )
+    .ok()
+}
+
+/// Removable volumes that are attached and have media in them
+fn list_volumes() -> Vec<RemovableVolume> {
+    let drives = unsafe { GetLogicalDrives() };
+    ('A'..='Z')
+        .enumerate()
+        .filter(|(bit, _)| drives & (1 << bit) != 0)
+        .filter_map(|(_, letter)| unsafe { read_volume(letter) })
+        .collect()
+}
+
+unsafe fn read_volume(letter: char) -> Option<RemovableVolume> {
+    let root = format!("{}:", letter);
+    let path = to_wide_string(&format!("{}\\", root));
+    let drive_type = GetDriveTypeW(PCWSTR(path.as_ptr()));
+    if drive_type != DRIVE_REMOVABLE && drive_type != DRIVE_FIXED {
+        return None;
+    }
+
+    let device = open_device(&format!("\\\\.\\{}", root))?;
+    let removable = drive_type == DRIVE_REMOVABLE || on_usb(device);
+    let counters = removable.then(|| performance(device)).flatten();
+    let _ = CloseHandle(device);
+    if !removable {
+        return None;
+    }
+
+    // Card readers keep their letters with no card in; those have no volume
+    let mut label = [0u16; 261];
+    GetVolumeInformationW(PCWSTR(path.as_ptr()), Some(&mut label), None, None, None, None).ok()?;
+    let len = label.iter().position(|&c| c == 0).unwrap_or(label.len());
+    let mut free = 0u64;
+    let mut total = 0u64;
+    let _ = GetDiskFreeSpaceExW(PCWSTR(path.as_ptr()), None, Some(&mut total), Some(&mut free));
+
+    Some(RemovableVolume {
+        root,
+        label: String::from_utf16_lossy(&label[..len]),
+        total_bytes: total,
+        free_bytes: free,
+        counters,
+        read_rate: 0,
+        write_rate: 0,
+    })
+}
+
+/// Whether the volume's disk is attached over USB
+unsafe fn on_usb(device: HANDLE) -> bool {
+    let query = STORAGE_PROPERTY_QUERY {
+        PropertyId: StorageDeviceProperty,
+        QueryType: PropertyStandardQuery,
+        ..Default::default()
+    };
+    let mut buffer = vec![0u8; 1024];
+    if !ioctl(device, IOCTL_STORAGE_QUERY_PROPERTY, as_bytes(&query), &mut buffer) {
+        return false;
+    }
+    let descriptor = std::ptr::read_unaligned(buffer.as_ptr() as *const STORAGE_DEVICE_DESCRIPTOR);
+    descriptor.BusType == BusTypeUsb
+}
+
+/// Bytes read and written, from the volume's performance counters
+unsafe fn performance(device: HANDLE) -> Option<(u64, u64)> {
+    let mut counters = [0u8; std::mem::size_of::<DISK_PERFORMANCE>()];
+    if !ioctl(device, IOCTL_DISK_PERFORMANCE, &[], &mut counters) {
+        return None;
+    }
+    let counters = std::ptr::read_unaligned(counters.as_ptr() as *const DISK_PERFORMANCE);
+    Some((counters.BytesRead as u64, counters.BytesWritten as u64))
+}
+
+/// Disk number of the device a volume or disk is on
+unsafe fn device_number(device: HANDLE) -> Option<u32> {
+    let mut number = [0u8; std::mem::size_of::<STORAGE_DEVICE_NUMBER>()];
+    if !ioctl(device, IOCTL_STORAGE_GET_DEVICE_NUMBER, &[], &mut number) {
+        return None;
+    }
+    Some(std::ptr::read_unaligned(number.as_ptr() as *const STORAGE_DEVICE_NUMBER).DeviceNumber)
+}
+
+/// Plug and Play instance of the disk with this number
+unsafe fn disk_instance(number: u32) -> Option<u32> {
+    let devices = SetupDiGetClassDevsW(
+        Some(&GUID_DEVINTERFACE_DISK),
+        PCWSTR::null(),
+        None,
+        DIGCF_PRESENT | DIGCF_DEVICEINTERFACE,
+    )
+    .ok()?;
+    let mut interface = SP_DEVICE_INTERFACE_DATA {
+        cbSize: std::mem::size_of::<SP_DEVICE_INTERFACE_DATA>() as u32,
+        ..Default::default()
+    };
+    let mut found = None;
+    let mut index = 0;
+    while found.is_none()
+        && SetupDiEnumDeviceInterfaces(devices, None, &GUID_DEVINTERFACE_DISK, index, &mut interface).is_ok()
+    {
+        index += 1;
+        let mut size = 0u32;
+        let _ = SetupDiGetDeviceInterfaceDetailW(devices, &interface, None, 0, Some(&mut size), None);
+        if size == 0 {
+            continue;
+        }
+        // u32s keep the detail struct aligned
+        let mut buffer = vec![0u32; (size as usize).div_ceil(4)];
+        let detail = buffer.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+        (*detail).cbSize = std::mem::size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() as u32;
+        let mut info = SP_DEVINFO_DATA {
+            cbSize: std::mem::size_of::<SP_DEVINFO_DATA>() as u32,
+            ..Default::default()
+        };
+        if SetupDiGetDeviceInterfaceDetailW(devices, &interface, Some(detail), size, None, Some(&mut info)).is_err() {
+            continue;
+        }
+        let path = PCWSTR((*detail).DevicePath.as_ptr()).to_string().unwrap_or_default();
+        if let Some(disk) = open_device(&path) {
+            if device_number(disk) == Some(number) {
+                found = Some(info.DevInst);
+            }
+            let _ = CloseHandle(disk);
+        }
+    }
+    let _ = SetupDiDestroyDeviceInfoList(devices);
+    found
+}
+
+/// Ask Plug and Play to remove the device a volume is on. The reason it was
+/// refused comes back as the error, in words for the OSD.
+fn eject(root: &str) -> Result<(), String> {
+    unsafe {
+        let volume = open_device(&format!("\\\\.\\{}", root)).ok_or("Drive not found")?;
+        let number = device_number(volume);
+        let usb = on_usb(volume);
+        let _ = CloseHandle(volume);
+        let disk = number.and_then(|n| disk_instance(n)).ok_or("Drive not found")?;
+
+        // A USB disk's parent is the USB device itself; removing it takes every
+        // volume on it. Elsewhere the parent is a controller, so eject the disk.
+        let mut parent = 0u32;
+        let target = if usb && CM_Get_Parent(&mut parent, disk, 0) == CR_SUCCESS { parent } else { disk };
+        let mut veto = PNP_VETO_TYPE::default();
+        let mut veto_name = [0u16; 260];
+        let result = CM_Request_Device_EjectW(target, Some(&mut veto), Some(&mut veto_name), 0);
+        if result == CR_SUCCESS && veto == PNP_VetoTypeUnknown {
+            return Ok(());
+        }
+        let len = veto_name.iter().position(|&c| c == 0).unwrap_or(0);
+        warn!(
+            "Eject of {} refused: result {}, veto {} {}",
+            root,
+            result.0,
+            veto.0,
+            String::from_utf16_lossy(&veto_name[..len])
+        );
+        if veto == PNP_VetoOutstandingOpen {
+            Err("a program is still using it".to_string())
+        } else {
+            Err("Windows refused to remove it".to_string())
+        }
+    }
+}
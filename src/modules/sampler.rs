@@ -0,0 +1,654 @@
+//! Shared system sampler
+//!
+//! One background thread samples CPU and memory use, GPU load and video memory,
+//! and network and disk throughput once a second, keeping the last few minutes of each
+//! in a ring buffer. The system info, GPU, network and disk modules read the latest
+//! sample and their histories from here instead of each polling on the UI
+//! thread, so the PDH counters, DXGI adapters and interface table are opened once
+//! and queried once per second however many modules, tooltips and popups use
+//! them. Readers take a read lock and never wait on a query in progress.
+//!
+//! The GPU engine counter has an instance per process and engine, so the same
+//! collection also says which processes keep the GPU busy and on what.
+//!
+//! Temperatures, fan speeds and the process snapshot take WMI queries, vendor
+//! library calls or a walk of the process table, so they're only read while a
+//! module on the bar asks for them (`want`).
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use windows::core::{Interface, PCWSTR};
+use windows::Win32::Foundation::FILETIME;
+
+use super::metrics::{ProcessSample, Sensors, ThermalSnapshot};
+
+/// Samples kept in each history (one per second)
+pub const HISTORY_LEN: usize = 180;
+/// Time between samples; doubled on battery
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Processes kept in each sample's GPU breakdown
+const GPU_PROCESS_COUNT: usize = 10;
+
+/// A sensor no module has asked for in this long isn't read anymore
+const WANT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static SAMPLES: OnceCell<RwLock<Samples>> = OnceCell::new();
+
+/// Readings only taken while a module wants them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensor {
+    CpuTemperature,
+    GpuTemperature,
+    Fans,
+    /// The shared process snapshot (`metrics::processes`)
+    Processes,
+}
+
+/// When each `Sensor` was last asked for
+static WANTED: Mutex<[Option<Instant>; 4]> = Mutex::new([None; 4]);
+
+/// Keep reading `sensor`; modules call this on each update while they show it
+pub fn want(sensor: Sensor) {
+    WANTED.lock()[sensor as usize] = Some(Instant::now());
+}
+
+fn wanted(sensor: Sensor) -> bool {
+    WANTED.lock()[sensor as usize].is_some_and(|t| t.elapsed() < WANT_TIMEOUT)
+}
+
+/// The latest samples and their histories, starting the sampler on first use
+pub fn samples() -> RwLockReadGuard<'static, Samples> {
+    SAMPLES
+        .get_or_init(|| {
+            let spawned = std::thread::Builder::new().name("sampler".into()).spawn(run);
+            if let Err(e) = spawned {
+                warn!("Failed to start the system sampler: {}", e);
+            }
+            RwLock::new(Samples::new())
+        })
+        .read()
+}
+
+/// A fixed number of the most recent values, oldest first
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+    values: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, value: f32) {
+        if self.values.len() >= self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    /// Every value kept, oldest first
+    pub fn values(&self) -> Vec<f32> {
+        self.values.iter().copied().collect()
+    }
+
+    /// The last `count` values, padded with zeros at the start so a graph of
+    /// them fills in from the right
+    pub fn recent(&self, count: usize) -> Vec<f32> {
+        let kept = self.values.len().min(count);
+        let mut values = vec![0.0; count - kept];
+        values.extend(self.values.iter().skip(self.values.len() - kept));
+        values
+    }
+}
+
+/// How busy one process keeps each kind of GPU engine
+#[derive(Debug, Clone, Default)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    /// Utilization by engine type ("3D", "Copy", "VideoDecode", ...), 0-100
+    pub engines: Vec<(String, f32)>,
+}
+
+impl GpuProcess {
+    /// Utilization of one engine type, 0 if the process doesn't use it
+    pub fn engine(&self, engine: &str) -> f32 {
+        self.engines
+            .iter()
+            .find(|(name, _)| name == engine)
+            .map_or(0.0, |(_, value)| *value)
+    }
+
+    /// Overall use, as Task Manager counts it: the busiest engine type
+    pub fn usage(&self) -> f32 {
+        self.engines.iter().map(|(_, value)| *value).fold(0.0, f32::max)
+    }
+}
+
+/// One reading of everything the sampler watches
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    /// CPU usage across all cores, 0-100
+    pub cpu: f32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// GPU engine utilization, 0-100
+    pub gpu_usage: f32,
+    pub gpu_memory_used: u64,
+    pub gpu_memory_total: u64,
+    /// Processes using the GPU, busiest first
+    pub gpu_processes: Vec<GpuProcess>,
+    /// Bytes per second over all network adapters
+    pub download: u64,
+    pub upload: u64,
+    /// Bytes per second over all physical disks
+    pub disk_read: u64,
+    pub disk_write: u64,
+    /// Temperatures and fans that were wanted (see `want`)
+    pub thermal: ThermalSnapshot,
+}
+
+impl Sample {
+    /// Memory in use, 0-100
+    pub fn memory_percent(&self) -> f32 {
+        if self.memory_total == 0 {
+            return 0.0;
+        }
+        (self.memory_used as f64 / self.memory_total as f64 * 100.0) as f32
+    }
+
+    /// Video memory in use, 0-100, when the adapter reports it
+    pub fn gpu_memory_percent(&self) -> Option<f32> {
+        (self.gpu_memory_total > 0)
+            .then(|| (self.gpu_memory_used as f64 / self.gpu_memory_total as f64 * 100.0) as f32)
+    }
+}
+
+/// Everything the sampler has recorded
+pub struct Samples {
+    pub latest: Sample,
+    /// Description of the first GPU adapter
+    pub gpu_name: String,
+    /// GPU temperature backend, "" until the temperature is first wanted
+    pub gpu_temperature_source: &'static str,
+    /// Running processes, refreshed every couple of seconds while wanted
+    pub processes: Arc<Vec<ProcessSample>>,
+    pub cpu: RingBuffer,
+    pub memory: RingBuffer,
+    pub gpu: RingBuffer,
+    pub gpu_memory: RingBuffer,
+    pub download: RingBuffer,
+    pub upload: RingBuffer,
+    pub disk_read: RingBuffer,
+    pub disk_write: RingBuffer,
+    /// Samples taken so far, so readers can tell a new one has arrived
+    pub count: u64,
+}
+
+impl Samples {
+    fn new() -> Self {
+        Self {
+            latest: Sample::default(),
+            gpu_name: String::new(),
+            gpu_temperature_source: "",
+            processes: Arc::new(Vec::new()),
+            cpu: RingBuffer::new(HISTORY_LEN),
+            memory: RingBuffer::new(HISTORY_LEN),
+            gpu: RingBuffer::new(HISTORY_LEN),
+            gpu_memory: RingBuffer::new(HISTORY_LEN),
+            download: RingBuffer::new(HISTORY_LEN),
+            upload: RingBuffer::new(HISTORY_LEN),
+            disk_read: RingBuffer::new(HISTORY_LEN),
+            disk_write: RingBuffer::new(HISTORY_LEN),
+            count: 0,
+        }
+    }
+
+    fn record(&mut self, sample: Sample) {
+        self.cpu.push(sample.cpu);
+        self.memory.push(sample.memory_percent());
+        self.gpu.push(sample.gpu_usage);
+        if let Some(percent) = sample.gpu_memory_percent() {
+            self.gpu_memory.push(percent);
+        }
+        self.download.push(sample.download as f32);
+        self.upload.push(sample.upload as f32);
+        self.disk_read.push(sample.disk_read as f32);
+        self.disk_write.push(sample.disk_write as f32);
+        self.latest = sample;
+        self.count += 1;
+    }
+}
+
+fn run() {
+    let mut sources = Sources::new();
+    if let Some(samples) = SAMPLES.get() {
+        samples.write().gpu_name = sources.gpu_name.clone();
+    }
+    debug!("System sampler started (GPU: {})", sources.gpu_name);
+    loop {
+        std::thread::sleep(INTERVAL * crate::utils::battery_update_multiplier() as u32);
+        let sample = sources.sample();
+        let processes = if wanted(Sensor::Processes) { sources.sensors.processes() } else { None };
+        if let Some(samples) = SAMPLES.get() {
+            let mut samples = samples.write();
+            samples.gpu_temperature_source = sources.sensors.gpu_source();
+            if let Some(processes) = processes {
+                samples.processes = Arc::new(processes);
+            }
+            samples.record(sample);
+        }
+    }
+}
+
+/// Handles and previous counters the sampler keeps between samples
+struct Sources {
+    /// Idle and busy (kernel + user) CPU time at the last sample
+    cpu_times: Option<(u64, u64)>,
+    /// Received and sent bytes at the last sample
+    network_totals: Option<(u64, u64)>,
+    last_sample: Instant,
+    counters: Option<Counters>,
+    adapters: Vec<windows::Win32::Graphics::Dxgi::IDXGIAdapter3>,
+    gpu_name: String,
+    gpu_memory_total: u64,
+    /// Names and image paths of processes in the GPU breakdown, by ID
+    process_names: HashMap<u32, (String, String)>,
+    sensors: Sensors,
+}
+
+/// PDH query for GPU engine utilization and disk throughput, opened once and
+/// collected each sample
+struct Counters {
+    query: isize,
+    gpu: Option<isize>,
+    disk_read: Option<isize>,
+    disk_write: Option<isize>,
+}
+
+impl Drop for Counters {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::System::Performance::PdhCloseQuery(self.query);
+        }
+    }
+}
+
+impl Sources {
+    fn new() -> Self {
+        let mut sources = Self {
+            cpu_times: None,
+            network_totals: None,
+            last_sample: Instant::now(),
+            counters: Counters::open(),
+            adapters: Vec::new(),
+            gpu_name: String::new(),
+            gpu_memory_total: 0,
+            process_names: HashMap::new(),
+            sensors: Sensors::new(),
+        };
+        sources.open_adapters();
+        // Prime the counters so the first sample covers one interval
+        sources.cpu_times = cpu_times();
+        sources.network_totals = network_totals();
+        sources
+    }
+
+    fn sample(&mut self) -> Sample {
+        let thermal = self.sensors.read(
+            wanted(Sensor::CpuTemperature),
+            wanted(Sensor::GpuTemperature),
+            wanted(Sensor::Fans),
+        );
+        if crate::demo::is_enabled() {
+            let (memory_used, memory_total) = crate::demo::memory();
+            let (gpu_usage, gpu_memory_used, gpu_memory_total, _) = crate::demo::gpu();
+            let (download, upload) = crate::demo::network_speeds();
+            let (disk_read, disk_write) = crate::demo::disk_speeds();
+            return Sample {
+                cpu: crate::demo::cpu_usage(),
+                memory_used,
+                memory_total,
+                gpu_usage,
+                gpu_memory_used,
+                gpu_memory_total,
+                download,
+                upload,
+                disk_read,
+                disk_write,
+                thermal,
+                ..Default::default()
+            };
+        }
+
+        let elapsed = self.last_sample.elapsed().as_secs_f64();
+        self.last_sample = Instant::now();
+        let mut sample = Sample {
+            thermal,
+            ..Default::default()
+        };
+
+        let times = cpu_times();
+        if let (Some((idle, busy)), Some((prev_idle, prev_busy))) = (times, self.cpu_times) {
+            let busy_delta = busy.saturating_sub(prev_busy);
+            if busy_delta > 0 {
+                let idle_delta = idle.saturating_sub(prev_idle).min(busy_delta);
+                sample.cpu = ((busy_delta - idle_delta) as f64 / busy_delta as f64 * 100.0) as f32;
+            }
+        }
+        self.cpu_times = times;
+
+        if let Some((used, total)) = memory() {
+            sample.memory_used = used;
+            sample.memory_total = total;
+        }
+
+        let mut engines = Vec::new();
+        if let Some(counters) = self.counters.as_ref().filter(|c| c.collect()) {
+            engines = counters.gpu_engines();
+            (sample.disk_read, sample.disk_write) = counters.disk_speeds();
+        }
+        // Summed over every engine instance, capped at 100
+        sample.gpu_usage = engines.iter().map(|(_, value)| value).sum::<f64>().min(100.0) as f32;
+        sample.gpu_processes = self.gpu_processes(&engines);
+        sample.gpu_memory_used = self.gpu_memory_used();
+        sample.gpu_memory_total = self.gpu_memory_total;
+
+        let totals = network_totals();
+        if let (Some((total_in, total_out)), Some((prev_in, prev_out))) = (totals, self.network_totals) {
+            if elapsed > 0.0 {
+                sample.download = (total_in.saturating_sub(prev_in) as f64 / elapsed) as u64;
+                sample.upload = (total_out.saturating_sub(prev_out) as f64 / elapsed) as u64;
+            }
+        }
+        self.network_totals = totals;
+        sample
+    }
+
+    /// Add up engine instances ("pid_1234_luid_..._eng_0_engtype_3D") by
+    /// process and engine type
+    fn gpu_processes(&mut self, engines: &[(String, f64)]) -> Vec<GpuProcess> {
+        let mut by_pid: HashMap<u32, HashMap<&str, f64>> = HashMap::new();
+        for (instance, value) in engines {
+            let Some(pid) = instance
+                .strip_prefix("pid_")
+                .and_then(|rest| rest.split('_').next())
+                .and_then(|pid| pid.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let Some((_, engine)) = instance.split_once("_engtype_") else {
+                continue;
+            };
+            if pid != 0 && *value > 0.0 {
+                *by_pid.entry(pid).or_default().entry(engine).or_default() += value;
+            }
+        }
+
+        let mut processes: Vec<GpuProcess> = by_pid
+            .into_iter()
+            .map(|(pid, engines)| {
+                let (name, path) = self
+                    .process_names
+                    .entry(pid)
+                    .or_insert_with(|| {
+                        let path = super::network::process_image_path(pid);
+                        let name = std::path::Path::new(&path)
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| format!("PID {}", pid));
+                        (name, path)
+                    })
+                    .clone();
+                let mut engines: Vec<(String, f32)> = engines
+                    .into_iter()
+                    .map(|(engine, value)| (engine.to_string(), value.min(100.0) as f32))
+                    .collect();
+                engines.sort_by(|a, b| a.0.cmp(&b.0));
+                GpuProcess { pid, name, path, engines }
+            })
+            .collect();
+        processes.sort_by(|a, b| b.usage().total_cmp(&a.usage()));
+        processes.truncate(GPU_PROCESS_COUNT);
+
+        // Forget processes that have gone quiet, in case their IDs are reused
+        self.process_names.retain(|pid, _| processes.iter().any(|p| p.pid == *pid));
+        processes
+    }
+
+    /// Find the GPU adapters once; their names and sizes don't change
+    fn open_adapters(&mut self) {
+        use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIAdapter3, IDXGIFactory1};
+
+        unsafe {
+            let Ok(factory) = CreateDXGIFactory1::<IDXGIFactory1>() else {
+                return;
+            };
+            let mut index = 0;
+            while let Ok(adapter) = factory.EnumAdapters1(index) {
+                index += 1;
+                if let Ok(desc) = adapter.GetDesc1() {
+                    if self.gpu_name.is_empty() {
+                        let name = String::from_utf16_lossy(&desc.Description);
+                        self.gpu_name = name.trim_end_matches('\0').to_string();
+                    }
+                    if self.gpu_memory_total == 0 {
+                        self.gpu_memory_total = desc.DedicatedVideoMemory as u64;
+                    }
+                }
+                if let Ok(adapter3) = adapter.cast::<IDXGIAdapter3>() {
+                    self.adapters.push(adapter3);
+                }
+            }
+        }
+    }
+
+    /// Local video memory in use on the adapters, as the last one reporting it says
+    fn gpu_memory_used(&mut self) -> u64 {
+        use windows::Win32::Graphics::Dxgi::{DXGI_MEMORY_SEGMENT_GROUP, DXGI_QUERY_VIDEO_MEMORY_INFO};
+
+        let mut used = 0;
+        for adapter in &self.adapters {
+            let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
+            if unsafe { adapter.QueryVideoMemoryInfo(0, DXGI_MEMORY_SEGMENT_GROUP(0), &mut info) }.is_ok() {
+                used = info.CurrentUsage;
+                if self.gpu_memory_total == 0 {
+                    // Without a dedicated size the budget is the best total there is
+                    self.gpu_memory_total = info.Budget;
+                }
+            }
+        }
+        used
+    }
+}
+
+impl Counters {
+    fn open() -> Option<Self> {
+        use windows::Win32::System::Performance::{PdhCollectQueryData, PdhOpenQueryW};
+
+        unsafe {
+            let mut query = 0isize;
+            if PdhOpenQueryW(PCWSTR::null(), 0, &mut query) != 0 {
+                return None;
+            }
+            let counters = Counters {
+                query,
+                gpu: add_counter(query, "\\GPU Engine(*)\\Utilization Percentage"),
+                disk_read: add_counter(query, "\\PhysicalDisk(_Total)\\Disk Read Bytes/sec"),
+                disk_write: add_counter(query, "\\PhysicalDisk(_Total)\\Disk Write Bytes/sec"),
+            };
+            if counters.gpu.is_none() {
+                debug!("GPU engine counters aren't available");
+            }
+            // Rates need two collections; this one is the baseline for the first sample
+            let _ = PdhCollectQueryData(query);
+            Some(counters)
+        }
+    }
+
+    /// Collect every counter; false if the query failed
+    fn collect(&self) -> bool {
+        unsafe { windows::Win32::System::Performance::PdhCollectQueryData(self.query) == 0 }
+    }
+
+    /// Utilization of every GPU engine instance, by instance name
+    fn gpu_engines(&self) -> Vec<(String, f64)> {
+        use windows::Win32::System::Performance::{
+            PdhGetFormattedCounterArrayW, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE,
+        };
+
+        let Some(counter) = self.gpu else {
+            return Vec::new();
+        };
+        unsafe {
+            let mut size = 0u32;
+            let mut count = 0u32;
+            let _ = PdhGetFormattedCounterArrayW(counter, PDH_FMT_DOUBLE, &mut size, &mut count, None);
+            if size == 0 {
+                return Vec::new();
+            }
+            let mut buffer = vec![0u8; size as usize];
+            let items = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+            if PdhGetFormattedCounterArrayW(counter, PDH_FMT_DOUBLE, &mut size, &mut count, Some(items)) != 0 {
+                return Vec::new();
+            }
+            std::slice::from_raw_parts(items, count as usize)
+                .iter()
+                .map(|item| {
+                    let name = item.szName.to_string().unwrap_or_default();
+                    (name, item.FmtValue.Anonymous.doubleValue)
+                })
+                .collect()
+        }
+    }
+
+    /// Bytes per second read from and written to all physical disks
+    fn disk_speeds(&self) -> (u64, u64) {
+        (value(self.disk_read), value(self.disk_write))
+    }
+}
+
+/// Add a counter by its English path, or None when this PC doesn't have it
+unsafe fn add_counter(query: isize, path: &str) -> Option<isize> {
+    use windows::Win32::System::Performance::PdhAddEnglishCounterW;
+
+    let path = crate::utils::to_wide_string(path);
+    let mut counter = 0isize;
+    (PdhAddEnglishCounterW(query, PCWSTR(path.as_ptr()), 0, &mut counter) == 0).then_some(counter)
+}
+
+/// A single-instance counter's value from the last collection
+fn value(counter: Option<isize>) -> u64 {
+    use windows::Win32::System::Performance::{PdhGetFormattedCounterValue, PDH_FMT_COUNTERVALUE, PDH_FMT_DOUBLE};
+
+    let Some(counter) = counter else {
+        return 0;
+    };
+    let mut value = PDH_FMT_COUNTERVALUE::default();
+    unsafe {
+        if PdhGetFormattedCounterValue(counter, PDH_FMT_DOUBLE, None, &mut value) != 0 {
+            return 0;
+        }
+        value.Anonymous.doubleValue.max(0.0) as u64
+    }
+}
+
+fn filetime(time: FILETIME) -> u64 {
+    ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64
+}
+
+/// Idle and busy CPU time in 100 ns units; kernel time includes idle time
+fn cpu_times() -> Option<(u64, u64)> {
+    let (mut idle, mut kernel, mut user) = (FILETIME::default(), FILETIME::default(), FILETIME::default());
+    unsafe {
+        windows::Win32::System::Threading::GetSystemTimes(Some(&mut idle), Some(&mut kernel), Some(&mut user)).ok()?;
+    }
+    Some((filetime(idle), filetime(kernel) + filetime(user)))
+}
+
+/// Physical memory (used, total) in bytes
+fn memory() -> Option<(u64, u64)> {
+    use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    let mut status = MEMORYSTATUSEX {
+        dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        GlobalMemoryStatusEx(&mut status).ok()?;
+    }
+    Some((status.ullTotalPhys - status.ullAvailPhys, status.ullTotalPhys))
+}
+
+/// Bytes received and sent over all network interfaces since they came up
+fn network_totals() -> Option<(u64, u64)> {
+    use windows::Win32::NetworkManagement::IpHelper::{FreeMibTable, GetIfTable2, MIB_IF_ROW2, MIB_IF_TABLE2};
+
+    unsafe {
+        let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+        if GetIfTable2(&mut table).0 != 0 || table.is_null() {
+            return None;
+        }
+        let rows: &[MIB_IF_ROW2] = std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+        let totals = rows.iter().fold((0u64, 0u64), |(total_in, total_out), row| {
+            (total_in.saturating_add(row.InOctets), total_out.saturating_add(row.OutOctets))
+        });
+        FreeMibTable(table as *const std::ffi::c_void);
+        Some(totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer_drops_the_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        assert!(buffer.values().is_empty());
+        for value in [1.0, 2.0, 3.0, 4.0] {
+            buffer.push(value);
+        }
+        assert_eq!(buffer.values(), vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn recent_pads_at_the_start() {
+        let mut buffer = RingBuffer::new(5);
+        buffer.push(1.0);
+        buffer.push(2.0);
+        assert_eq!(buffer.recent(4), vec![0.0, 0.0, 1.0, 2.0]);
+        assert_eq!(buffer.recent(1), vec![2.0]);
+        assert!(buffer.recent(0).is_empty());
+    }
+
+    #[test]
+    fn record_keeps_histories() {
+        let mut samples = Samples::new();
+        samples.record(Sample {
+            cpu: 25.0,
+            memory_used: 4,
+            memory_total: 16,
+            download: 1024,
+            ..Default::default()
+        });
+        assert_eq!(samples.count, 1);
+        assert_eq!(samples.cpu.values(), vec![25.0]);
+        assert_eq!(samples.memory.values(), vec![25.0]);
+        assert_eq!(samples.download.values(), vec![1024.0]);
+        // No video memory total, so no video memory history
+        assert!(samples.gpu_memory.values().is_empty());
+        assert_eq!(samples.latest.gpu_memory_percent(), None);
+    }
+}
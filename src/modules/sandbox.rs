@@ -0,0 +1,160 @@
+//! Keeping a failing module from taking the bar down
+//!
+//! Module updates, and the text drawn from them, run under `catch_unwind`. A
+//! panic is logged with the module and its message; after `MAX_FAILURES` in a
+//! row the registry swaps the module for an `ErroredModule`, which shows a
+//! warning glyph, says what went wrong in its tooltip and isn't updated again
+//! until TopBar restarts.
+
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+
+use super::Module;
+
+/// Panics in a row before a module is switched off
+pub const MAX_FAILURES: u32 = 3;
+
+/// Run `f`, turning a panic into its message
+pub fn guard<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(payload.as_ref()))
+}
+
+/// A module's text for drawing; a panic is logged and drawn as the warning glyph
+pub fn display_text(module: &dyn Module, config: &crate::config::Config) -> String {
+    guard(|| module.display_text(config)).unwrap_or_else(|message| {
+        log::error!("Module '{}' panicked drawing its text: {}", module.id(), message);
+        "⚠".to_string()
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Stand-in for a module that kept panicking
+pub struct ErroredModule {
+    id: String,
+    name: String,
+    message: String,
+}
+
+impl ErroredModule {
+    pub fn new(module: &dyn Module, message: String) -> Self {
+        Self {
+            id: module.id().to_string(),
+            name: module.name().to_string(),
+            message,
+        }
+    }
+}
+
+impl Module for ErroredModule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        "⚠".to_string()
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {}
+
+    fn tooltip(&self) -> Option<String> {
+        let name = crate::i18n::tr(&self.name);
+        Some(format!(
+            "{}\n{}",
+            crate::i18n::tr("{} kept failing and is switched off until TopBar restarts").replacen("{}", &name, 1),
+            self.message
+        ))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::modules::{events, ModuleRegistry};
+
+    /// Panics on every update and when asked for its text
+    struct PanickyModule;
+
+    impl Module for PanickyModule {
+        fn id(&self) -> &str {
+            "panicky"
+        }
+
+        fn name(&self) -> &str {
+            "Panicky"
+        }
+
+        fn display_text(&self, _config: &Config) -> String {
+            panic!("no text today")
+        }
+
+        fn update(&mut self, _config: &Config) {
+            panic!("update failed: {}", 42)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn guard_passes_results_through() {
+        assert_eq!(guard(|| 7), Ok(7));
+    }
+
+    #[test]
+    fn guard_turns_panics_into_messages() {
+        assert_eq!(guard(|| -> u32 { panic!("static message") }), Err("static message".to_string()));
+        assert_eq!(guard(|| -> u32 { panic!("formatted {}", 1) }), Err("formatted 1".to_string()));
+        let other = guard(|| std::panic::panic_any(5u8));
+        assert_eq!(other, Err("unknown panic".to_string()));
+    }
+
+    #[test]
+    fn text_of_a_panicking_module_is_the_warning_glyph() {
+        assert_eq!(display_text(&PanickyModule, &Config::default()), "⚠");
+    }
+
+    #[test]
+    fn module_is_switched_off_after_repeated_panics() {
+        // Updates take the shared event queue
+        let _queue = events::TEST_QUEUE.lock();
+        let config = Config::default();
+        let mut registry = ModuleRegistry::with_modules(vec![Box::new(PanickyModule)]);
+        for _ in 1..MAX_FAILURES {
+            registry.update_all(&config);
+            assert!(registry.get("panicky").unwrap().as_any().downcast_ref::<PanickyModule>().is_some());
+        }
+        registry.update_all(&config);
+        let module = registry.get("panicky").unwrap();
+        assert!(module.as_any().downcast_ref::<ErroredModule>().is_some());
+        assert_eq!(module.display_text(&config), "⚠");
+        assert!(module.tooltip().unwrap().contains("update failed: 42"));
+        // Switched off, so further updates leave it alone
+        registry.update_all(&config);
+        assert!(registry.get("panicky").unwrap().as_any().downcast_ref::<ErroredModule>().is_some());
+    }
+}
@@ -0,0 +1,320 @@
+//! Screen recorder built on Windows.Graphics.Capture
+//!
+//! Frames of the primary monitor are copied to a CPU-readable staging texture and
+//! fed to a Media Foundation sink writer, which encodes them as H.264 into an MP4
+//! file. Recording runs on its own thread; the bar only polls the shared state.
+
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+
+/// An in-progress recording
+struct ActiveRecording {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    started: Instant,
+    path: PathBuf,
+}
+
+static RECORDER: OnceCell<Mutex<Option<ActiveRecording>>> = OnceCell::new();
+
+fn recorder() -> &'static Mutex<Option<ActiveRecording>> {
+    RECORDER.get_or_init(|| Mutex::new(None))
+}
+
+/// Whether a recording is in progress
+pub fn is_recording() -> bool {
+    recorder()
+        .lock()
+        .as_ref()
+        .map(|r| !r.thread.is_finished())
+        .unwrap_or(false)
+}
+
+/// Time since the current recording started
+pub fn elapsed() -> Option<Duration> {
+    recorder()
+        .lock()
+        .as_ref()
+        .filter(|r| !r.thread.is_finished())
+        .map(|r| r.started.elapsed())
+}
+
+/// Start recording the primary monitor to `path`
+pub fn start_recording(path: PathBuf, fps: u32) -> Result<()> {
+    let mut slot = recorder().lock();
+    if slot.as_ref().map(|r| !r.thread.is_finished()).unwrap_or(false) {
+        return Err(anyhow!("A recording is already in progress"));
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread_path = path.clone();
+    let fps = fps.clamp(1, 60);
+    let thread = std::thread::spawn(move || {
+        if let Err(e) = record(&thread_path, fps, &thread_stop) {
+            error!("Screen recording failed: {}", e);
+        }
+    });
+
+    info!("Started screen recording to {:?}", path);
+    *slot = Some(ActiveRecording {
+        stop,
+        thread,
+        started: Instant::now(),
+        path,
+    });
+    Ok(())
+}
+
+/// Stop the current recording and wait for the file to be finalized.
+///
+/// Returns the path of the finished video.
+pub fn stop_recording() -> Option<PathBuf> {
+    let active = recorder().lock().take()?;
+    active.stop.store(true, Ordering::SeqCst);
+    let _ = active.thread.join();
+    info!("Stopped screen recording ({:?})", active.path);
+    Some(active.path)
+}
+
+/// Recording thread body
+fn record(path: &Path, fps: u32, stop: &AtomicBool) -> Result<()> {
+    use windows::core::{Interface, HSTRING};
+    use windows::Graphics::Capture::{Direct3D11CaptureFramePool, GraphicsCaptureItem};
+    use windows::Graphics::DirectX::Direct3D11::IDirect3DDevice;
+    use windows::Graphics::DirectX::DirectXPixelFormat;
+    use windows::Win32::Foundation::{HMODULE, POINT};
+    use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+    use windows::Win32::Graphics::Direct3D11::*;
+    use windows::Win32::Graphics::Dxgi::IDXGIDevice;
+    use windows::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTOPRIMARY};
+    use windows::Win32::Media::MediaFoundation::{MFShutdown, MFStartup, MFSTARTUP_FULL, MF_VERSION};
+    use windows::Win32::System::WinRT::Direct3D11::{
+        CreateDirect3D11DeviceFromDXGIDevice, IDirect3DDxgiInterfaceAccess,
+    };
+    use windows::Win32::System::WinRT::Graphics::Capture::IGraphicsCaptureItemInterop;
+    use windows::Win32::System::WinRT::{RoInitialize, RO_INIT_MULTITHREADED};
+
+    unsafe {
+        let _ = RoInitialize(RO_INIT_MULTITHREADED);
+
+        // D3D device shared by the capture API and our staging copies
+        let mut device: Option<ID3D11Device> = None;
+        let mut context: Option<ID3D11DeviceContext> = None;
+        D3D11CreateDevice(
+            None,
+            D3D_DRIVER_TYPE_HARDWARE,
+            HMODULE::default(),
+            D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            Some(&mut context),
+        )?;
+        let device = device.ok_or_else(|| anyhow!("No D3D11 device"))?;
+        let context = context.ok_or_else(|| anyhow!("No D3D11 device context"))?;
+        let dxgi_device: IDXGIDevice = device.cast()?;
+        let winrt_device: IDirect3DDevice = CreateDirect3D11DeviceFromDXGIDevice(&dxgi_device)?.cast()?;
+
+        // Capture item for the primary monitor
+        let monitor = MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY);
+        let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
+        let item: GraphicsCaptureItem = interop.CreateForMonitor(monitor)?;
+        let size = item.Size()?;
+
+        // H.264 needs even dimensions
+        let width = (size.Width as u32) & !1;
+        let height = (size.Height as u32) & !1;
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Monitor has no capturable area"));
+        }
+
+        let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
+            &winrt_device,
+            DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            2,
+            size,
+        )?;
+        let session = frame_pool.CreateCaptureSession(&item)?;
+        // Not available on older Windows 10 builds; failures are harmless
+        let _ = session.SetIsBorderRequired(false);
+        let _ = session.SetIsCursorCaptureEnabled(true);
+
+        let staging = create_staging_texture(&device, size.Width as u32, size.Height as u32)?;
+
+        MFStartup(MF_VERSION, MFSTARTUP_FULL)?;
+        let result = (|| -> Result<()> {
+            let mut encoder = VideoEncoder::new(&HSTRING::from(path.as_os_str()), width, height, fps)?;
+            session.StartCapture()?;
+
+            let frame_interval = Duration::from_secs(1) / fps;
+            let mut next_frame = Instant::now();
+            let mut pixels: Option<Vec<u8>> = None;
+            while !stop.load(Ordering::SeqCst) {
+                // Only the newest pending frame matters; older ones are dropped. When the
+                // screen is static no frames arrive and the previous one is repeated.
+                let mut newest = None;
+                while let Ok(frame) = frame_pool.TryGetNextFrame() {
+                    newest = Some(frame);
+                }
+                if let Some(frame) = newest {
+                    let access: IDirect3DDxgiInterfaceAccess = frame.Surface()?.cast()?;
+                    let texture: ID3D11Texture2D = access.GetInterface()?;
+                    context.CopyResource(&staging, &texture);
+                    pixels = Some(read_staging(&context, &staging, width, height)?);
+                }
+                if let Some(ref pixels) = pixels {
+                    encoder.write_frame(pixels)?;
+                }
+                next_frame += frame_interval;
+                let now = Instant::now();
+                if next_frame > now {
+                    std::thread::sleep(next_frame - now);
+                } else {
+                    // Fell behind (slow encoder); don't try to catch up with a burst
+                    next_frame = now;
+                }
+            }
+
+            encoder.finish()
+        })();
+
+        let _ = session.Close();
+        let _ = frame_pool.Close();
+        let _ = MFShutdown();
+        result
+    }
+}
+
+/// Create a CPU-readable copy target matching the capture surface
+unsafe fn create_staging_texture(
+    device: &windows::Win32::Graphics::Direct3D11::ID3D11Device,
+    width: u32,
+    height: u32,
+) -> Result<windows::Win32::Graphics::Direct3D11::ID3D11Texture2D> {
+    use windows::Win32::Graphics::Direct3D11::*;
+    use windows::Win32::Graphics::Dxgi::Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_SAMPLE_DESC};
+
+    let desc = D3D11_TEXTURE2D_DESC {
+        Width: width,
+        Height: height,
+        MipLevels: 1,
+        ArraySize: 1,
+        Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+        SampleDesc: DXGI_SAMPLE_DESC { Count: 1, Quality: 0 },
+        Usage: D3D11_USAGE_STAGING,
+        BindFlags: 0,
+        CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+        MiscFlags: 0,
+    };
+    let mut texture = None;
+    device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+    texture.ok_or_else(|| anyhow!("Failed to create staging texture"))
+}
+
+/// Copy the staging texture into a tightly packed top-down BGRA buffer
+unsafe fn read_staging(
+    context: &windows::Win32::Graphics::Direct3D11::ID3D11DeviceContext,
+    staging: &windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>> {
+    use windows::Win32::Graphics::Direct3D11::{D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ};
+
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    context.Map(staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+    let row_len = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in 0..height as usize {
+        let src = (mapped.pData as *const u8).add(row * mapped.RowPitch as usize);
+        pixels.extend_from_slice(std::slice::from_raw_parts(src, row_len));
+    }
+    context.Unmap(staging, 0);
+    Ok(pixels)
+}
+
+/// H.264/MP4 writer fed with top-down BGRA frames
+struct VideoEncoder {
+    writer: windows::Win32::Media::MediaFoundation::IMFSinkWriter,
+    stream: u32,
+    frame_len: u32,
+    frame_duration: i64,
+    frame_index: i64,
+}
+
+impl VideoEncoder {
+    unsafe fn new(path: &windows::core::HSTRING, width: u32, height: u32, fps: u32) -> Result<Self> {
+        use windows::Win32::Media::MediaFoundation::*;
+
+        let writer = MFCreateSinkWriterFromURL(path, None, None)?;
+        let frame_size = ((width as u64) << 32) | height as u64;
+        let frame_rate = ((fps as u64) << 32) | 1;
+        let aspect = (1u64 << 32) | 1;
+
+        let output = MFCreateMediaType()?;
+        output.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+        output.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_H264)?;
+        // Roughly 0.15 bits per pixel per frame, which keeps desktop content sharp
+        output.SetUINT32(&MF_MT_AVG_BITRATE, (width * height * fps / 7).max(1_000_000))?;
+        output.SetUINT32(&MF_MT_INTERLACE_MODE, MFVideoInterlace_Progressive.0 as u32)?;
+        output.SetUINT64(&MF_MT_FRAME_SIZE, frame_size)?;
+        output.SetUINT64(&MF_MT_FRAME_RATE, frame_rate)?;
+        output.SetUINT64(&MF_MT_PIXEL_ASPECT_RATIO, aspect)?;
+        let stream = writer.AddStream(&output)?;
+
+        let input = MFCreateMediaType()?;
+        input.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+        input.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_RGB32)?;
+        input.SetUINT32(&MF_MT_INTERLACE_MODE, MFVideoInterlace_Progressive.0 as u32)?;
+        input.SetUINT64(&MF_MT_FRAME_SIZE, frame_size)?;
+        input.SetUINT64(&MF_MT_FRAME_RATE, frame_rate)?;
+        input.SetUINT64(&MF_MT_PIXEL_ASPECT_RATIO, aspect)?;
+        // Positive stride: rows are stored top-down
+        input.SetUINT32(&MF_MT_DEFAULT_STRIDE, width * 4)?;
+        writer.SetInputMediaType(stream, &input, None)?;
+
+        writer.BeginWriting()?;
+
+        Ok(Self {
+            writer,
+            stream,
+            frame_len: width * height * 4,
+            frame_duration: 10_000_000 / fps as i64,
+            frame_index: 0,
+        })
+    }
+
+    unsafe fn write_frame(&mut self, pixels: &[u8]) -> Result<()> {
+        use windows::Win32::Media::MediaFoundation::*;
+
+        let buffer = MFCreateMemoryBuffer(self.frame_len)?;
+        let mut data = std::ptr::null_mut();
+        buffer.Lock(&mut data, None, None)?;
+        let len = pixels.len().min(self.frame_len as usize);
+        std::ptr::copy_nonoverlapping(pixels.as_ptr(), data, len);
+        buffer.Unlock()?;
+        buffer.SetCurrentLength(len as u32)?;
+
+        let sample = MFCreateSample()?;
+        sample.AddBuffer(&buffer)?;
+        sample.SetSampleTime(self.frame_index * self.frame_duration)?;
+        sample.SetSampleDuration(self.frame_duration)?;
+        self.writer.WriteSample(self.stream, &sample)?;
+        self.frame_index += 1;
+        Ok(())
+    }
+
+    unsafe fn finish(self) -> Result<()> {
+        self.writer.Finalize()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+//! Security module - Windows Security health at a glance
+//!
+//! Reads antivirus and firewall products from the Security Center WMI namespace,
+//! real-time protection and scan age from Microsoft Defender, and the Windows
+//! Firewall profiles. The shield gets a red badge when protection or the firewall
+//! is off; WMI is slow, so readings are taken on a background thread.
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::metrics::query_wmi_named;
+use super::Module;
+use crate::attention::{AttentionLevel, AttentionRequest};
+
+/// Defender reports this scan age when it has never scanned
+const NEVER_SCANNED: f32 = 65_535.0;
+
+/// An antivirus or firewall product registered with Security Center
+#[derive(Debug, Clone)]
+pub struct SecurityProduct {
+    pub name: String,
+    pub enabled: bool,
+    /// Definitions are current (antivirus only)
+    pub up_to_date: bool,
+}
+
+impl SecurityProduct {
+    /// Decode Security Center's `productState` bit field
+    fn from_state(name: String, state: u32) -> Self {
+        Self {
+            name,
+            enabled: state & 0x1000 != 0,
+            up_to_date: state & 0x10 == 0,
+        }
+    }
+}
+
+/// One health reading
+#[derive(Debug, Clone, Default)]
+pub struct SecurityStatus {
+    pub antivirus: Vec<SecurityProduct>,
+    /// Third-party firewalls (Windows Firewall is read from its profiles)
+    pub firewalls: Vec<SecurityProduct>,
+    /// Microsoft Defender real-time protection, when Defender is installed
+    pub real_time: Option<bool>,
+    /// Days since the last quick or full scan
+    pub last_scan_days: Option<u32>,
+    /// Windows Firewall profiles (Domain/Private/Public) and whether each is on
+    pub firewall_profiles: Vec<(String, bool)>,
+}
+
+impl SecurityStatus {
+    fn read() -> Self {
+        let products = |class: &str| {
+            query_wmi_named(
+                "root\\SecurityCenter2",
+                &format!("SELECT displayName, productState FROM {}", class),
+                "displayName",
+                "productState",
+            )
+            .into_iter()
+            .map(|(name, state)| SecurityProduct::from_state(name, state as u32))
+            .collect::<Vec<_>>()
+        };
+
+        const DEFENDER: &str = "root\\Microsoft\\Windows\\Defender";
+        let defender = |property: &str| {
+            query_wmi_named(
+                DEFENDER,
+                &format!("SELECT ComputerID, {} FROM MSFT_MpComputerStatus", property),
+                "ComputerID",
+                property,
+            )
+            .first()
+            .map(|(_, v)| *v)
+        };
+        let last_scan_days = [defender("QuickScanAge"), defender("FullScanAge")]
+            .into_iter()
+            .flatten()
+            .filter(|age| *age < NEVER_SCANNED)
+            .reduce(f32::min)
+            .map(|age| age as u32);
+
+        Self {
+            antivirus: products("AntiVirusProduct"),
+            firewalls: products("FirewallProduct"),
+            // Booleans come back as VARIANT_TRUE (-1)
+            real_time: defender("RealTimeProtectionEnabled").map(|v| v != 0.0),
+            last_scan_days,
+            firewall_profiles: query_wmi_named(
+                "root\\StandardCimv2",
+                "SELECT Name, Enabled FROM MSFT_NetFirewallProfile",
+                "Name",
+                "Enabled",
+            )
+            .into_iter()
+            // 0 = off; 1 = on; 2 = not configured, which means on
+            .map(|(name, enabled)| (name, enabled != 0.0))
+            .collect(),
+        }
+    }
+
+    /// Antivirus protection is on, or None if nothing could be read
+    pub fn protected(&self) -> Option<bool> {
+        if self.antivirus.is_empty() {
+            return self.real_time;
+        }
+        Some(self.antivirus.iter().any(|a| a.enabled) || self.real_time == Some(true))
+    }
+
+    /// A firewall is on, or None if nothing could be read
+    pub fn firewall_on(&self) -> Option<bool> {
+        if self.firewalls.iter().any(|f| f.enabled) {
+            return Some(true);
+        }
+        if self.firewall_profiles.is_empty() {
+            return None;
+        }
+        Some(self.firewall_profiles.iter().all(|(_, on)| *on))
+    }
+
+    /// Protection or firewall is off
+    pub fn is_unprotected(&self) -> bool {
+        self.protected() == Some(false) || self.firewall_on() == Some(false)
+    }
+
+    /// Everything that needs attention, most serious first
+    pub fn problems(&self, scan_warning_days: u32) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.protected() == Some(false) {
+            problems.push("Real-time protection is off".to_string());
+        }
+        let off: Vec<&str> = self
+            .firewall_profiles
+            .iter()
+            .filter(|(_, on)| !on)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if self.firewall_on() == Some(false) {
+            problems.push(format!("Firewall is off ({})", off.join(", ")));
+        }
+        if self.antivirus.iter().any(|a| a.enabled && !a.up_to_date) {
+            problems.push("Virus definitions are out of date".to_string());
+        }
+        if let Some(days) = self.last_scan_days {
+            if scan_warning_days > 0 && days > scan_warning_days {
+                problems.push(format!("Last scan was {} days ago", days));
+            }
+        }
+        problems
+    }
+}
+
+/// Windows Security health module
+pub struct SecurityModule {
+    status: Arc<Mutex<Option<SecurityStatus>>>,
+    reading: Arc<AtomicBool>,
+    last_update: Option<Instant>,
+    scan_warning_days: u32,
+    /// Protection state at the last update, to alert only when it changes
+    was_unprotected: Option<bool>,
+}
+
+impl SecurityModule {
+    pub fn new() -> Self {
+        Self {
+            status: Arc::new(Mutex::new(None)),
+            reading: Arc::new(AtomicBool::new(false)),
+            last_update: None,
+            scan_warning_days: 14,
+            was_unprotected: None,
+        }
+    }
+
+    fn read_async(&mut self) {
+        if self.reading.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        let status = Arc::clone(&self.status);
+        let reading = Arc::clone(&self.reading);
+        std::thread::spawn(move || {
+            *status.lock() = Some(SecurityStatus::read());
+            reading.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Latest reading, if one has finished
+    pub fn status(&self) -> Option<SecurityStatus> {
+        self.status.lock().clone()
+    }
+
+    /// Protection or firewall is off (red badge)
+    pub fn is_unprotected(&self) -> bool {
+        self.status.lock().as_ref().map(|s| s.is_unprotected()).unwrap_or(false)
+    }
+
+    /// Something less serious needs attention (stale scan or definitions)
+    pub fn has_warning(&self) -> bool {
+        self.status
+            .lock()
+            .as_ref()
+            .map(|s| !s.problems(self.scan_warning_days).is_empty())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for SecurityModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for SecurityModule {
+    fn id(&self) -> &str {
+        "security"
+    }
+
+    fn name(&self) -> &str {
+        "Security"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        "🛡".to_string()
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        let cfg = &config.modules.security;
+        self.scan_warning_days = cfg.scan_warning_days;
+        let interval = Duration::from_secs(cfg.update_interval_sec.max(10));
+        if self.last_update.map(|t| t.elapsed() >= interval).unwrap_or(true) {
+            self.read_async();
+        }
+
+        let Some(status) = self.status() else {
+            return;
+        };
+        let unprotected = status.is_unprotected();
+        if unprotected && self.was_unprotected == Some(false) && cfg.notify {
+            let problems = status.problems(0);
+            crate::attention::request(
+                config,
+                AttentionRequest::new(
+                    "security",
+                    AttentionLevel::Error,
+                    "Windows Security",
+                    problems.first().map(String::as_str).unwrap_or("Protection is off"),
+                ),
+            );
+        }
+        self.was_unprotected = Some(unprotected);
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.last_update = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let status = self.status()?;
+        let problems = status.problems(self.scan_warning_days);
+        if problems.is_empty() {
+            Some("Windows Security: no problems".to_string())
+        } else {
+            Some(format!("Windows Security\n{}", problems.join("\n")))
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,348 @@
+//! Service status module - monitors configured Windows services through the SCM
+//!
+//! Shows how many of the `[modules.services]` entries are running and offers
+//! start/stop/restart actions. When the current user lacks the rights to control
+//! a service, the action is re-run through an elevated PowerShell (UAC prompt).
+
+use log::{info, warn};
+use std::time::{Duration, Instant};
+use windows::core::PCWSTR;
+use windows::Win32::System::Services::*;
+
+use super::Module;
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::utils::to_wide_string;
+
+/// How long a restart waits for the service to stop before starting it again
+const STOP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Simplified SCM service state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceState {
+    Running,
+    Stopped,
+    Paused,
+    /// Start, stop, pause or continue in progress
+    Pending,
+    /// Not installed, or the SCM couldn't be queried
+    Unknown,
+}
+
+impl ServiceState {
+    fn from_scm(state: SERVICE_STATUS_CURRENT_STATE) -> Self {
+        match state {
+            SERVICE_RUNNING => Self::Running,
+            SERVICE_STOPPED => Self::Stopped,
+            SERVICE_PAUSED => Self::Paused,
+            _ => Self::Pending,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Stopped => "stopped",
+            Self::Paused => "paused",
+            Self::Pending => "pending",
+            Self::Unknown => "not found",
+        }
+    }
+}
+
+/// Action to perform on a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ServiceAction {
+    fn powershell_cmdlet(&self) -> &'static str {
+        match self {
+            Self::Start => "Start-Service",
+            Self::Stop => "Stop-Service -Force",
+            Self::Restart => "Restart-Service -Force",
+        }
+    }
+}
+
+/// Current state of one monitored service
+#[derive(Debug, Clone)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub display_name: String,
+    pub state: ServiceState,
+}
+
+/// Service status module
+pub struct ServicesModule {
+    statuses: Vec<ServiceStatus>,
+    last_update: Option<Instant>,
+}
+
+impl ServicesModule {
+    pub fn new() -> Self {
+        Self {
+            statuses: Vec::new(),
+            last_update: None,
+        }
+    }
+
+    /// Force an immediate update
+    fn force_update(&mut self, config: &crate::config::Config) {
+        let names = &config.modules.services.services;
+        let previous = std::mem::take(&mut self.statuses);
+        self.statuses = if names.is_empty() {
+            Vec::new()
+        } else {
+            query_services(names)
+        };
+
+        // A service that was running and has stopped (not one that's restarting)
+        for status in &self.statuses {
+            let was_running = previous
+                .iter()
+                .any(|p| p.name == status.name && p.state == ServiceState::Running);
+            if was_running && matches!(status.state, ServiceState::Stopped | ServiceState::Unknown) {
+                crate::attention::request(
+                    config,
+                    AttentionRequest::new(
+                        "services",
+                        AttentionLevel::Warning,
+                        "Service stopped",
+                        &format!("{} is {}", status.display_name, status.state.label()),
+                    ),
+                );
+            }
+        }
+        self.last_update = Some(Instant::now());
+    }
+
+    /// Status of every monitored service, in config order
+    pub fn statuses(&self) -> &[ServiceStatus] {
+        &self.statuses
+    }
+
+    /// Number of services that are running
+    pub fn running_count(&self) -> usize {
+        self.statuses
+            .iter()
+            .filter(|s| s.state == ServiceState::Running)
+            .count()
+    }
+
+    /// Whether any monitored service is not running
+    pub fn has_problem(&self) -> bool {
+        self.running_count() < self.statuses.len()
+    }
+
+    /// Start, stop or restart a service on a background thread.
+    ///
+    /// Falls back to an elevated PowerShell when access is denied.
+    pub fn run_action(name: &str, action: ServiceAction) {
+        let name = name.to_string();
+        std::thread::spawn(move || {
+            info!("Services: {:?} {}", action, name);
+            match control_service(&name, action) {
+                Ok(()) => {}
+                Err(e) if e.code() == windows::Win32::Foundation::E_ACCESSDENIED => {
+                    info!("Services: access denied for {}, requesting elevation", name);
+                    run_elevated(&name, action);
+                }
+                Err(e) => warn!("Services: {:?} {} failed: {}", action, name, e),
+            }
+        });
+    }
+}
+
+impl Default for ServicesModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for ServicesModule {
+    fn id(&self) -> &str {
+        "services"
+    }
+
+    fn name(&self) -> &str {
+        "Services"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        format!("⚙ {}/{}", self.running_count(), self.statuses.len())
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        let due = self
+            .last_update
+            .map(|t| t.elapsed().as_millis() as u64 >= config.modules.services.update_interval_ms)
+            .unwrap_or(true);
+        // Config edits (reload) can change the list size; refresh right away
+        if due || self.statuses.len() != config.modules.services.services.len() {
+            self.force_update(config);
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.statuses.is_empty() {
+            return Some("Services\nAdd service names to [modules.services] in the config".to_string());
+        }
+        let lines: Vec<String> = self
+            .statuses
+            .iter()
+            .map(|s| {
+                let mark = if s.state == ServiceState::Running { "●" } else { "○" };
+                format!("{} {}: {}", mark, s.display_name, s.state.label())
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.statuses.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Open handle that is closed on drop
+struct ScHandle(SC_HANDLE);
+
+impl Drop for ScHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseServiceHandle(self.0);
+        }
+    }
+}
+
+fn open_manager() -> windows::core::Result<ScHandle> {
+    unsafe { OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT).map(ScHandle) }
+}
+
+fn open_service(manager: &ScHandle, name: &str, access: u32) -> windows::core::Result<ScHandle> {
+    let wide = to_wide_string(name);
+    unsafe { OpenServiceW(manager.0, PCWSTR(wide.as_ptr()), access).map(ScHandle) }
+}
+
+fn query_state(service: &ScHandle) -> ServiceState {
+    let mut status = SERVICE_STATUS::default();
+    unsafe {
+        match QueryServiceStatus(service.0, &mut status) {
+            Ok(()) => ServiceState::from_scm(status.dwCurrentState),
+            Err(_) => ServiceState::Unknown,
+        }
+    }
+}
+
+fn display_name(manager: &ScHandle, name: &str) -> Option<String> {
+    let wide = to_wide_string(name);
+    let mut buffer = [0u16; 256];
+    let mut len = buffer.len() as u32;
+    unsafe {
+        GetServiceDisplayNameW(
+            manager.0,
+            PCWSTR(wide.as_ptr()),
+            windows::core::PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        )
+        .ok()?;
+    }
+    Some(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+/// Query the state and display name of each service
+fn query_services(names: &[String]) -> Vec<ServiceStatus> {
+    let manager = open_manager().ok();
+    names
+        .iter()
+        .map(|name| {
+            let name = name.trim();
+            let (state, display) = match manager.as_ref() {
+                Some(manager) => (
+                    open_service(manager, name, SERVICE_QUERY_STATUS)
+                        .map(|s| query_state(&s))
+                        .unwrap_or(ServiceState::Unknown),
+                    display_name(manager, name),
+                ),
+                None => (ServiceState::Unknown, None),
+            };
+            ServiceStatus {
+                name: name.to_string(),
+                display_name: display.unwrap_or_else(|| name.to_string()),
+                state,
+            }
+        })
+        .collect()
+}
+
+/// Perform the action with the current user's rights
+fn control_service(name: &str, action: ServiceAction) -> windows::core::Result<()> {
+    let manager = open_manager()?;
+    let service = open_service(&manager, name, SERVICE_START | SERVICE_STOP | SERVICE_QUERY_STATUS)?;
+    let mut status = SERVICE_STATUS::default();
+
+    unsafe {
+        match action {
+            ServiceAction::Start => StartServiceW(service.0, None),
+            ServiceAction::Stop => ControlService(service.0, SERVICE_CONTROL_STOP, &mut status),
+            ServiceAction::Restart => {
+                if query_state(&service) != ServiceState::Stopped {
+                    ControlService(service.0, SERVICE_CONTROL_STOP, &mut status)?;
+                    let started = Instant::now();
+                    while query_state(&service) != ServiceState::Stopped {
+                        if started.elapsed() > STOP_TIMEOUT {
+                            warn!("Services: {} did not stop in time", name);
+                            break;
+                        }
+                        std::thread::sleep(Duration::from_millis(250));
+                    }
+                }
+                StartServiceW(service.0, None)
+            }
+        }
+    }
+}
+
+/// Re-run the action through an elevated, hidden PowerShell (shows a UAC prompt)
+fn run_elevated(name: &str, action: ServiceAction) {
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+
+    // Single quotes are doubled inside a PowerShell single-quoted string
+    let command = format!(
+        "-NoProfile -WindowStyle Hidden -Command \"{} -Name '{}'\"",
+        action.powershell_cmdlet(),
+        name.replace('\'', "''")
+    );
+    let verb = to_wide_string("runas");
+    let file = to_wide_string("powershell.exe");
+    let params = to_wide_string(&command);
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            PCWSTR(verb.as_ptr()),
+            PCWSTR(file.as_ptr()),
+            PCWSTR(params.as_ptr()),
+            PCWSTR::null(),
+            SW_HIDE,
+        )
+    };
+    // Values <= 32 are errors (including the user declining the UAC prompt)
+    if result.0 as isize <= 32 {
+        warn!("Services: elevated {:?} of {} was not started", action, name);
+    }
+}
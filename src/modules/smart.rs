@@ -0,0 +1,260 @@
+//! Physical disk health (SMART)
+//!
+//! Each `\\.\PhysicalDriveN` is opened without read or write access, which
+//! storage queries allow without elevation. The model and bus come from the
+//! device descriptor and the temperature from the storage temperature property.
+//! SATA drives report their SMART attribute table with the failure prediction
+//! (reallocated sectors, power-on hours); NVMe drives answer with the health log
+//! page (critical warnings, media errors, wear, power-on hours). Queries can
+//! wait on a drive spinning up, so they're made off the UI thread.
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Storage::FileSystem::{
+    BusTypeAta, BusTypeNvme, BusTypeSata, CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE,
+    OPEN_EXISTING,
+};
+use windows::Win32::System::Ioctl::{
+    NVMeDataTypeLogPage, PropertyStandardQuery, ProtocolTypeNvme, StorageDeviceProperty,
+    StorageDeviceProtocolSpecificProperty, StorageDeviceTemperatureProperty, IOCTL_STORAGE_PREDICT_FAILURE,
+    IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_DEVICE_DESCRIPTOR, STORAGE_PREDICT_FAILURE, STORAGE_PROPERTY_QUERY,
+    STORAGE_PROTOCOL_SPECIFIC_DATA, STORAGE_TEMPERATURE_DATA_DESCRIPTOR,
+};
+use windows::Win32::System::IO::DeviceIoControl;
+
+/// Physical drive numbers tried
+const MAX_DRIVES: u32 = 16;
+/// NVMe SMART / health information log page
+const NVME_HEALTH_LOG: u32 = 2;
+/// Bytes before `AdditionalParameters` in `STORAGE_PROPERTY_QUERY`
+const QUERY_HEADER: usize = 8;
+/// NVMe wear (percentage of rated endurance used) that counts as worn
+const WEAR_WARNING: u32 = 90;
+
+/// What a drive reports about its health; None where it doesn't say
+#[derive(Debug, Clone, Default)]
+pub struct DiskHealth {
+    /// Physical drive number
+    pub index: u32,
+    pub model: String,
+    pub nvme: bool,
+    /// Celsius
+    pub temperature: Option<f32>,
+    /// The drive's own alarm: SMART failure prediction, or an NVMe critical warning
+    pub failing: bool,
+    /// SATA reallocated sector count
+    pub reallocated_sectors: Option<u64>,
+    /// NVMe unrecovered data integrity errors
+    pub media_errors: Option<u64>,
+    pub power_on_hours: Option<u64>,
+    /// NVMe percentage of rated endurance used (may pass 100)
+    pub wear: Option<u32>,
+}
+
+/// Overall verdict on a drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Good,
+    /// Running hot, remapping sectors or worn
+    Caution,
+    /// The drive itself predicts failure
+    Bad,
+}
+
+impl Health {
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Good => "Good",
+            Health::Caution => "Caution",
+            Health::Bad => "Bad",
+        }
+    }
+}
+
+impl DiskHealth {
+    /// Judge the drive, treating `temperature_warning` (Celsius) and up as hot
+    pub fn verdict(&self, temperature_warning: f32) -> Health {
+        if self.failing {
+            return Health::Bad;
+        }
+        let hot = self.temperature.is_some_and(|t| t >= temperature_warning);
+        let remapped = self.reallocated_sectors.is_some_and(|n| n > 0) || self.media_errors.is_some_and(|n| n > 0);
+        let worn = self.wear.is_some_and(|w| w >= WEAR_WARNING);
+        if hot || remapped || worn {
+            Health::Caution
+        } else {
+            Health::Good
+        }
+    }
+}
+
+/// Health of every physical drive that answers
+pub fn read_all() -> Vec<DiskHealth> {
+    (0..MAX_DRIVES)
+        .filter_map(|index| unsafe { read_drive(index) })
+        .collect()
+}
+
+unsafe fn read_drive(index: u32) -> Option<DiskHealth> {
+    let path = crate::utils::to_wide_string(&format!("\\\\.\\PhysicalDrive{}", index));
+    let handle = CreateFileW(
+        PCWSTR(path.as_ptr()),
+        0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        None,
+        OPEN_EXISTING,
+        FILE_ATTRIBUTE_NORMAL,
+        HANDLE::default(),
+    )
+    .ok()?;
+    let health = query_drive(handle, index);
+    let _ = CloseHandle(handle);
+    health
+}
+
+pub(super) unsafe fn ioctl(handle: HANDLE, code: u32, input: &[u8], output: &mut [u8]) -> bool {
+    let mut returned = 0u32;
+    DeviceIoControl(
+        handle,
+        code,
+        Some(input.as_ptr() as _),
+        input.len() as u32,
+        Some(output.as_mut_ptr() as _),
+        output.len() as u32,
+        Some(&mut returned),
+        None,
+    )
+    .is_ok()
+}
+
+pub(super) fn as_bytes<T>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+unsafe fn query_drive(handle: HANDLE, index: u32) -> Option<DiskHealth> {
+    let mut query = STORAGE_PROPERTY_QUERY {
+        PropertyId: StorageDeviceProperty,
+        QueryType: PropertyStandardQuery,
+        ..Default::default()
+    };
+    let mut buffer = vec![0u8; 1024];
+    if !ioctl(handle, IOCTL_STORAGE_QUERY_PROPERTY, as_bytes(&query), &mut buffer) {
+        return None;
+    }
+    let descriptor = std::ptr::read_unaligned(buffer.as_ptr() as *const STORAGE_DEVICE_DESCRIPTOR);
+    let model = c_string(&buffer, descriptor.ProductIdOffset as usize);
+    let bus = descriptor.BusType;
+    let mut health = DiskHealth {
+        index,
+        model: if model.is_empty() { format!("Disk {}", index) } else { model },
+        nvme: bus == BusTypeNvme,
+        ..Default::default()
+    };
+
+    query.PropertyId = StorageDeviceTemperatureProperty;
+    let mut temperature = STORAGE_TEMPERATURE_DATA_DESCRIPTOR::default();
+    let output = std::slice::from_raw_parts_mut(
+        &mut temperature as *mut _ as *mut u8,
+        std::mem::size_of::<STORAGE_TEMPERATURE_DATA_DESCRIPTOR>(),
+    );
+    if ioctl(handle, IOCTL_STORAGE_QUERY_PROPERTY, as_bytes(&query), output) && temperature.InfoCount > 0 {
+        let celsius = temperature.TemperatureInfo[0].Temperature;
+        // Drives without a sensor report 0
+        if celsius > 0 {
+            health.temperature = Some(celsius as f32);
+        }
+    }
+
+    if health.nvme {
+        read_nvme_log(handle, &mut health);
+    } else {
+        let mut prediction = STORAGE_PREDICT_FAILURE {
+            PredictFailure: 0,
+            VendorSpecific: [0; 512],
+        };
+        let output = std::slice::from_raw_parts_mut(
+            &mut prediction as *mut _ as *mut u8,
+            std::mem::size_of::<STORAGE_PREDICT_FAILURE>(),
+        );
+        if ioctl(handle, IOCTL_STORAGE_PREDICT_FAILURE, &[], output) {
+            health.failing = prediction.PredictFailure != 0;
+            // The vendor data is the ATA attribute table only on ATA drives
+            if bus == BusTypeAta || bus == BusTypeSata {
+                read_ata_attributes(&prediction.VendorSpecific, &mut health);
+            }
+        }
+    }
+    Some(health)
+}
+
+/// Pick attributes out of the ATA SMART table: a 2-byte version, then 30
+/// entries of id, flags (2), current, worst and a 6-byte raw value
+fn read_ata_attributes(table: &[u8; 512], health: &mut DiskHealth) {
+    for entry in table[2..2 + 30 * 12].chunks_exact(12) {
+        let raw = entry[5..11].iter().rev().fold(0u64, |value, byte| value << 8 | *byte as u64);
+        match entry[0] {
+            5 => health.reallocated_sectors = Some(raw),
+            9 => health.power_on_hours = Some(raw & 0xFFFF_FFFF),
+            // Airflow (190) or drive (194) temperature, when the property had none
+            190 | 194 if health.temperature.is_none() && entry[5] > 0 => {
+                health.temperature = Some(entry[5] as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+unsafe fn read_nvme_log(handle: HANDLE, health: &mut DiskHealth) {
+    const LOG_SIZE: usize = 512;
+    let specific_size = std::mem::size_of::<STORAGE_PROTOCOL_SPECIFIC_DATA>();
+    let mut buffer = vec![0u8; QUERY_HEADER + specific_size + LOG_SIZE];
+
+    let query = STORAGE_PROPERTY_QUERY {
+        PropertyId: StorageDeviceProtocolSpecificProperty,
+        QueryType: PropertyStandardQuery,
+        ..Default::default()
+    };
+    buffer[..QUERY_HEADER].copy_from_slice(&as_bytes(&query)[..QUERY_HEADER]);
+    let specific = STORAGE_PROTOCOL_SPECIFIC_DATA {
+        ProtocolType: ProtocolTypeNvme,
+        DataType: NVMeDataTypeLogPage.0 as u32,
+        ProtocolDataRequestValue: NVME_HEALTH_LOG,
+        ProtocolDataOffset: specific_size as u32,
+        ProtocolDataLength: LOG_SIZE as u32,
+        ..Default::default()
+    };
+    buffer[QUERY_HEADER..QUERY_HEADER + specific_size].copy_from_slice(as_bytes(&specific));
+
+    let input = buffer.clone();
+    if !ioctl(handle, IOCTL_STORAGE_QUERY_PROPERTY, &input, &mut buffer) {
+        return;
+    }
+    // The reply has the same layout, with the log where the offset says
+    let returned = std::ptr::read_unaligned(buffer[QUERY_HEADER..].as_ptr() as *const STORAGE_PROTOCOL_SPECIFIC_DATA);
+    let start = QUERY_HEADER + returned.ProtocolDataOffset as usize;
+    let Some(log) = buffer.get(start..start + LOG_SIZE) else {
+        return;
+    };
+    let le = |range: std::ops::Range<usize>| log[range].iter().rev().fold(0u64, |value, byte| value << 8 | *byte as u64);
+
+    health.failing = log[0] != 0;
+    // Composite temperature, in kelvin
+    let kelvin = le(1..3);
+    if kelvin > 273 {
+        health.temperature = Some((kelvin - 273) as f32);
+    }
+    health.wear = Some(log[5] as u32);
+    // Both are 16-byte counters; the low half is plenty
+    health.power_on_hours = Some(le(128..136));
+    health.media_errors = Some(le(160..168));
+}
+
+/// A NUL-terminated ASCII string at `offset` in `buffer`, trimmed
+fn c_string(buffer: &[u8], offset: usize) -> String {
+    if offset == 0 || offset >= buffer.len() {
+        return String::new();
+    }
+    let bytes = &buffer[offset..];
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
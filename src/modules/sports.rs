@@ -0,0 +1,437 @@
+//! Sports module - live scores and this week's fixtures for followed teams
+//!
+//! Scores come from a `ScoreProvider`: ESPN's public scoreboard for the configured
+//! leagues, or a custom JSON endpoint. The module polls slowly until a followed
+//! match is about to start or live, then every `live_interval_sec` until it ends.
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime};
+use log::{info, warn};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use super::events;
+use super::Module;
+use crate::config::{SportsConfig, SportsProvider};
+use crate::runtime::Task;
+
+/// Poll at the live rate from this long before kick-off
+const PRE_MATCH_MINUTES: i64 = 5;
+
+/// Where a match is at
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchState {
+    Scheduled,
+    /// In progress, with the provider's clock or period (e.g. "67'", "Q3 4:12")
+    Live(String),
+    Final,
+}
+
+/// A fixture or result
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub league: String,
+    pub home: String,
+    pub away: String,
+    pub home_score: Option<u32>,
+    pub away_score: Option<u32>,
+    pub start: DateTime<Local>,
+    pub state: MatchState,
+}
+
+impl Match {
+    /// "Arsenal 2–1 Chelsea", or "Arsenal v Chelsea" before kick-off
+    pub fn summary(&self) -> String {
+        match (self.home_score, self.away_score, &self.state) {
+            (Some(h), Some(a), MatchState::Live(_) | MatchState::Final) => {
+                format!("{} {}–{} {}", self.home, h, a, self.away)
+            }
+            _ => format!("{} v {}", self.home, self.away),
+        }
+    }
+
+    /// Status after the score: clock while live, "FT", or the kick-off time
+    pub fn status(&self) -> String {
+        match &self.state {
+            MatchState::Live(detail) if !detail.is_empty() => detail.clone(),
+            MatchState::Live(_) => "Live".to_string(),
+            MatchState::Final => "FT".to_string(),
+            MatchState::Scheduled => self.start.format("%a %H:%M").to_string(),
+        }
+    }
+
+    fn involves(&self, teams: &[String]) -> bool {
+        teams.iter().any(|t| {
+            let t = t.trim().to_lowercase();
+            !t.is_empty() && (self.home.to_lowercase().contains(&t) || self.away.to_lowercase().contains(&t))
+        })
+    }
+}
+
+/// A source of fixtures and results
+pub trait ScoreProvider: Send {
+    /// Matches between `from` and `to` (inclusive); filtering to followed teams happens later
+    fn fetch(&self, teams: &[String], from: NaiveDate, to: NaiveDate) -> Result<Vec<Match>, String>;
+}
+
+fn get_json(url: &str) -> Result<serde_json::Value, String> {
+    let body = ureq::get(url)
+        .set("User-Agent", "TopBar/1.0")
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| format!("HTTP error: {}", e))?
+        .into_string()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    serde_json::from_str(&body).map_err(|e| format!("JSON parse error: {}", e))
+}
+
+/// ESPN's public scoreboard API, one request per league
+pub struct EspnProvider {
+    pub leagues: Vec<String>,
+}
+
+impl EspnProvider {
+    fn parse_time(text: &str) -> Option<DateTime<Local>> {
+        // ESPN leaves out the seconds ("2024-05-11T14:00Z")
+        DateTime::parse_from_rfc3339(text)
+            .map(|t| t.with_timezone(&Local))
+            .ok()
+            .or_else(|| {
+                NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%MZ")
+                    .ok()
+                    .map(|t| t.and_utc().with_timezone(&Local))
+            })
+    }
+
+    fn parse_event(event: &serde_json::Value, league: &str) -> Option<Match> {
+        let competition = event.pointer("/competitions/0")?;
+        let competitors = competition.get("competitors")?.as_array()?;
+        let side = |home_away: &str| {
+            competitors
+                .iter()
+                .find(|c| c.get("homeAway").and_then(|h| h.as_str()) == Some(home_away))
+        };
+        let (home, away) = (side("home")?, side("away")?);
+        let name = |c: &serde_json::Value| {
+            c.pointer("/team/shortDisplayName")
+                .or_else(|| c.pointer("/team/displayName"))
+                .and_then(|n| n.as_str())
+                .unwrap_or("?")
+                .to_string()
+        };
+        let score = |c: &serde_json::Value| c.get("score").and_then(|s| s.as_str()).and_then(|s| s.parse().ok());
+
+        let status = event.pointer("/status/type")?;
+        let state = match status.get("state").and_then(|s| s.as_str()).unwrap_or("pre") {
+            "in" => MatchState::Live(
+                event
+                    .pointer("/status/type/shortDetail")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            ),
+            "post" => MatchState::Final,
+            _ => MatchState::Scheduled,
+        };
+
+        Some(Match {
+            league: league.to_string(),
+            home: name(home),
+            away: name(away),
+            home_score: score(home),
+            away_score: score(away),
+            start: Self::parse_time(event.get("date")?.as_str()?)?,
+            state,
+        })
+    }
+}
+
+impl ScoreProvider for EspnProvider {
+    fn fetch(&self, _teams: &[String], from: NaiveDate, to: NaiveDate) -> Result<Vec<Match>, String> {
+        let mut matches = Vec::new();
+        for league in &self.leagues {
+            let url = format!(
+                "https://site.api.espn.com/apis/site/v2/sports/{}/scoreboard?dates={}-{}",
+                league.trim(),
+                from.format("%Y%m%d"),
+                to.format("%Y%m%d")
+            );
+            let parsed = get_json(&url)?;
+            let label = parsed
+                .pointer("/leagues/0/abbreviation")
+                .and_then(|l| l.as_str())
+                .unwrap_or(league)
+                .to_string();
+            let events = parsed.get("events").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+            matches.extend(events.iter().filter_map(|e| Self::parse_event(e, &label)));
+        }
+        Ok(matches)
+    }
+}
+
+/// A custom endpoint, called with `?from=YYYY-MM-DD&to=YYYY-MM-DD&teams=a,b`
+pub struct JsonProvider {
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct JsonMatches {
+    matches: Vec<JsonMatch>,
+}
+
+#[derive(Deserialize)]
+struct JsonMatch {
+    #[serde(default)]
+    league: String,
+    home: String,
+    away: String,
+    home_score: Option<u32>,
+    away_score: Option<u32>,
+    /// RFC 3339 kick-off time
+    start: String,
+    /// "scheduled", "live" or "final"
+    state: String,
+    #[serde(default)]
+    detail: String,
+}
+
+impl ScoreProvider for JsonProvider {
+    fn fetch(&self, teams: &[String], from: NaiveDate, to: NaiveDate) -> Result<Vec<Match>, String> {
+        if self.url.trim().is_empty() {
+            return Err("No custom_url configured".to_string());
+        }
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let url = format!(
+            "{}{}from={}&to={}&teams={}",
+            self.url.trim(),
+            separator,
+            from,
+            to,
+            crate::utils::url_encode(&teams.join(","))
+        );
+        let parsed: JsonMatches = serde_json::from_value(get_json(&url)?).map_err(|e| format!("Bad match data: {}", e))?;
+        Ok(parsed
+            .matches
+            .into_iter()
+            .filter_map(|m| {
+                let start = DateTime::parse_from_rfc3339(&m.start).ok()?.with_timezone(&Local);
+                let state = match m.state.to_lowercase().as_str() {
+                    "live" | "in" => MatchState::Live(m.detail),
+                    "final" | "post" | "ft" => MatchState::Final,
+                    _ => MatchState::Scheduled,
+                };
+                Some(Match {
+                    league: m.league,
+                    home: m.home,
+                    away: m.away,
+                    home_score: m.home_score,
+                    away_score: m.away_score,
+                    start,
+                    state,
+                })
+            })
+            .collect())
+    }
+}
+
+fn provider_for(config: &SportsConfig) -> Box<dyn ScoreProvider> {
+    match config.provider {
+        SportsProvider::Espn => Box::new(EspnProvider {
+            leagues: config.leagues.clone(),
+        }),
+        SportsProvider::Custom => Box::new(JsonProvider {
+            url: config.custom_url.clone(),
+        }),
+    }
+}
+
+/// Monday and Sunday of the current week
+fn this_week() -> (NaiveDate, NaiveDate) {
+    let today = Local::now().date_naive();
+    let monday = today - ChronoDuration::days(today.weekday().num_days_from_monday() as i64);
+    (monday, monday + ChronoDuration::days(6))
+}
+
+/// Sports scores module
+pub struct SportsModule {
+    cached_text: String,
+    config: SportsConfig,
+    matches: Vec<Match>,
+    error: Option<String>,
+    /// Followed matches, or why they couldn't be fetched, once the running fetch is done
+    pending: Option<Task<Result<Vec<Match>, String>>>,
+    last_fetch: Option<Instant>,
+    /// Teams the last fetch was for, to refetch when the config changes
+    fetched_teams: Vec<String>,
+}
+
+impl SportsModule {
+    pub fn new() -> Self {
+        Self {
+            cached_text: String::new(),
+            config: SportsConfig::default(),
+            matches: Vec::new(),
+            error: None,
+            pending: None,
+            last_fetch: None,
+            fetched_teams: Vec::new(),
+        }
+    }
+
+    fn fetch_async(&mut self) {
+        if self.pending.is_some() {
+            return;
+        }
+        self.last_fetch = Some(Instant::now());
+        self.fetched_teams = self.config.teams.clone();
+
+        let provider = provider_for(&self.config);
+        let teams = self.config.teams.clone();
+        self.pending = Some(crate::runtime::fetch(move || {
+            let (from, to) = this_week();
+            let all = provider.fetch(&teams, from, to)?;
+            let mut followed: Vec<Match> = all.into_iter().filter(|m| m.involves(&teams)).collect();
+            followed.sort_by_key(|m| m.start);
+            info!("Fetched {} matches for followed teams", followed.len());
+            Ok(followed)
+        }));
+    }
+
+    /// Take in the matches of a finished fetch
+    fn collect(&mut self) {
+        let Some(result) = self.pending.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.pending = None;
+        match result.and_then(|fetched| fetched) {
+            Ok(matches) => {
+                self.matches = matches;
+                self.error = None;
+            }
+            Err(e) => {
+                warn!("Failed to fetch scores: {}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Followed matches this week, by kick-off time
+    pub fn matches(&self) -> Vec<Match> {
+        self.matches.clone()
+    }
+
+    /// Last fetch error, if it failed
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    /// Whether a followed match is in progress
+    pub fn is_live(&self) -> bool {
+        self.matches.iter().any(|m| matches!(m.state, MatchState::Live(_)))
+    }
+
+    /// Live or about to start, so scores should be polled quickly
+    fn needs_live_updates(&self) -> bool {
+        let soon = Local::now() + ChronoDuration::minutes(PRE_MATCH_MINUTES);
+        self.matches.iter().any(|m| match m.state {
+            MatchState::Live(_) => true,
+            // Providers can be slow to flip a match to live; keep polling past kick-off
+            MatchState::Scheduled => m.start <= soon && m.start + ChronoDuration::hours(3) > Local::now(),
+            MatchState::Final => false,
+        })
+    }
+
+    /// The match the bar shows: a live one, else the next fixture, else the latest result
+    fn featured(&self) -> Option<Match> {
+        let matches = &self.matches;
+        matches
+            .iter()
+            .find(|m| matches!(m.state, MatchState::Live(_)))
+            .or_else(|| matches.iter().find(|m| m.state == MatchState::Scheduled))
+            .or_else(|| matches.iter().rev().find(|m| m.state == MatchState::Final))
+            .cloned()
+    }
+}
+
+impl Default for SportsModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for SportsModule {
+    fn id(&self) -> &str {
+        "sports"
+    }
+
+    fn name(&self) -> &str {
+        "Sports"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.cached_text.clone()
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        self.config = config.modules.sports.clone();
+        self.collect();
+        if self.config.teams.is_empty() {
+            self.cached_text.clear();
+            return;
+        }
+
+        let interval = if self.needs_live_updates() {
+            Duration::from_secs(self.config.live_interval_sec.max(10) as u64)
+        } else {
+            Duration::from_secs(self.config.update_interval_min.max(1) as u64 * 60)
+        };
+        let due = self.last_fetch.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if due || self.config.teams != self.fetched_teams {
+            self.fetch_async();
+        }
+
+        self.cached_text = match self.featured() {
+            Some(m) => format!("{} {}", m.summary(), m.status()),
+            // Nothing this week: keep an icon so the popup stays reachable
+            None if self.last_fetch.is_some() && self.pending.is_none() => "🏆".to_string(),
+            None => "🏆 ...".to_string(),
+        };
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        if crate::modules::is_offline(self, config) {
+            return;
+        }
+        self.last_fetch = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let featured = self.featured()?;
+        let mut text = format!("{}\n{} — {}", featured.league, featured.summary(), featured.status());
+        if let Some(e) = self.error() {
+            text.push_str(&format!("\n{}", e));
+        }
+        Some(text)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::NETWORK_CONNECTED]
+    }
+
+    fn on_event(&mut self, _topic: &str, config: &crate::config::Config) {
+        // Back online: scores may have moved on while disconnected
+        self.force_refresh(config);
+    }
+}
@@ -0,0 +1,493 @@
+//! Stocks module - quotes for a watchlist, or the value of a portfolio
+//!
+//! Quotes (last price and previous close) come from Yahoo Finance's public chart
+//! endpoint, fetched in the background. With `[[modules.stocks.holdings]]`
+//! configured the bar shows the portfolio's total value and today's change; the
+//! value, daily and total P&L per position are all worked out locally from the
+//! quotes and the configured quantities and cost basis.
+
+use log::{info, warn};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::events;
+use super::Module;
+use crate::config::{Holding, StocksConfig};
+use crate::runtime::Task;
+
+/// Latest quote for a symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub price: f64,
+    pub previous_close: f64,
+}
+
+impl Quote {
+    /// Change since the previous close
+    pub fn change(&self) -> f64 {
+        self.price - self.previous_close
+    }
+
+    /// Change since the previous close, in percent
+    pub fn change_pct(&self) -> f64 {
+        percent(self.change(), self.previous_close)
+    }
+}
+
+/// One holding valued at its latest quote
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub value: f64,
+    pub day_change: f64,
+    pub day_change_pct: f64,
+    /// Gain since purchase, when the cost basis is known
+    pub total_change: Option<f64>,
+    pub total_change_pct: Option<f64>,
+}
+
+/// The whole portfolio valued at the latest quotes
+#[derive(Debug, Clone, Default)]
+pub struct Portfolio {
+    pub positions: Vec<Position>,
+    pub value: f64,
+    pub day_change: f64,
+    pub day_change_pct: f64,
+    /// Gain since purchase over the positions with a known cost basis
+    pub total_change: Option<f64>,
+    pub total_change_pct: Option<f64>,
+    /// Symbols without a quote yet
+    pub missing: Vec<String>,
+}
+
+fn percent(change: f64, base: f64) -> f64 {
+    if base.abs() > f64::EPSILON {
+        change / base * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Value `holdings` at `quotes`
+pub fn portfolio(holdings: &[Holding], quotes: &HashMap<String, Quote>) -> Portfolio {
+    let mut result = Portfolio::default();
+    let mut cost = 0.0;
+    let mut costed_value = 0.0;
+
+    for holding in holdings {
+        let symbol = holding.symbol.trim().to_uppercase();
+        let Some(quote) = quotes.get(&symbol) else {
+            result.missing.push(symbol);
+            continue;
+        };
+        let value = holding.quantity * quote.price;
+        let day_change = holding.quantity * quote.change();
+        let total_change = (holding.cost_basis > 0.0).then_some(value - holding.quantity * holding.cost_basis);
+        if total_change.is_some() {
+            cost += holding.quantity * holding.cost_basis;
+            costed_value += value;
+        }
+
+        result.value += value;
+        result.day_change += day_change;
+        result.positions.push(Position {
+            symbol,
+            quantity: holding.quantity,
+            price: quote.price,
+            value,
+            day_change,
+            day_change_pct: quote.change_pct(),
+            total_change,
+            total_change_pct: total_change.map(|c| percent(c, holding.quantity * holding.cost_basis)),
+        });
+    }
+
+    result.day_change_pct = percent(result.day_change, result.value - result.day_change);
+    if cost > 0.0 {
+        result.total_change = Some(costed_value - cost);
+        result.total_change_pct = Some(percent(costed_value - cost, cost));
+    }
+    result
+}
+
+/// `1234567.891` as "1,234,567.89"
+pub fn format_amount(value: f64) -> String {
+    let text = format!("{:.2}", value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, "00"));
+    let mut grouped = String::new();
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{}{}.{}", sign, grouped, fraction)
+}
+
+/// Money with a leading + or − sign, e.g. "+$12.30"
+pub fn format_signed_money(value: f64, currency: &str) -> String {
+    let sign = if value < 0.0 { "−" } else { "+" };
+    format!("{}{}{}", sign, currency, format_amount(value.abs()))
+}
+
+/// Percentage with a leading + or − sign
+pub fn format_signed_pct(value: f64) -> String {
+    let sign = if value < 0.0 { "−" } else { "+" };
+    format!("{}{:.2}%", sign, value.abs())
+}
+
+fn arrow(change: f64) -> &'static str {
+    if change < 0.0 {
+        "▼"
+    } else {
+        "▲"
+    }
+}
+
+/// Quotes a fetch got, and the symbols it got none for
+type Fetched = (Vec<(String, Quote)>, Vec<String>);
+
+/// Stocks module
+pub struct StocksModule {
+    cached_text: String,
+    config: StocksConfig,
+    quotes: HashMap<String, Quote>,
+    error: Option<String>,
+    /// What the running fetch got, once it's done
+    pending: Option<Task<Fetched>>,
+    last_fetch: Option<Instant>,
+    /// Symbols the last fetch asked for, to refetch when the config changes
+    fetched_symbols: Vec<String>,
+}
+
+impl StocksModule {
+    pub fn new() -> Self {
+        Self {
+            cached_text: String::new(),
+            config: StocksConfig::default(),
+            quotes: HashMap::new(),
+            error: None,
+            pending: None,
+            last_fetch: None,
+            fetched_symbols: Vec::new(),
+        }
+    }
+
+    /// Every symbol to quote, uppercase and without duplicates
+    fn symbols(config: &StocksConfig) -> Vec<String> {
+        let mut symbols: Vec<String> = Vec::new();
+        let all = config.holdings.iter().map(|h| &h.symbol).chain(&config.symbols);
+        for symbol in all.map(|s| s.trim().to_uppercase()) {
+            if !symbol.is_empty() && !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        symbols
+    }
+
+    /// Fetch quotes in the background
+    fn fetch_async(&mut self, symbols: Vec<String>) {
+        if self.pending.is_some() {
+            return;
+        }
+        self.last_fetch = Some(Instant::now());
+        self.fetched_symbols = symbols.clone();
+
+        self.pending = Some(crate::runtime::fetch(move || {
+            let mut quotes = Vec::new();
+            let mut failed = Vec::new();
+            for symbol in &symbols {
+                match Self::fetch_quote(symbol) {
+                    Ok(quote) => quotes.push((symbol.clone(), quote)),
+                    Err(e) => {
+                        warn!("Failed to fetch quote for {}: {}", symbol, e);
+                        failed.push(symbol.clone());
+                    }
+                }
+            }
+            info!("Fetched {} of {} stock quotes", quotes.len(), symbols.len());
+            (quotes, failed)
+        }));
+    }
+
+    /// Take in the quotes of a finished fetch
+    fn collect(&mut self) {
+        let Some(result) = self.pending.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.pending = None;
+        match result {
+            Ok((quotes, failed)) => {
+                self.quotes.extend(quotes);
+                self.error = (!failed.is_empty()).then(|| format!("No quote for {}", failed.join(", ")));
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Last price and previous close from Yahoo Finance's chart endpoint
+    fn fetch_quote(symbol: &str) -> Result<Quote, String> {
+        let url = format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{}?range=1d&interval=1d",
+            crate::utils::url_encode(symbol)
+        );
+        let body = ureq::get(&url)
+            .set("User-Agent", "TopBar/1.0")
+            .timeout(Duration::from_secs(10))
+            .call()
+            .map_err(|e| format!("HTTP error: {}", e))?
+            .into_string()
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        Self::parse_quote(&body)
+    }
+
+    /// The quote in a chart endpoint response
+    fn parse_quote(body: &str) -> Result<Quote, String> {
+        let parsed: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| format!("JSON parse error: {}", e))?;
+        let meta = parsed
+            .pointer("/chart/result/0/meta")
+            .ok_or("Missing quote data")?;
+        let price = meta
+            .get("regularMarketPrice")
+            .and_then(|v| v.as_f64())
+            .ok_or("Missing price")?;
+        let previous_close = meta
+            .get("chartPreviousClose")
+            .or_else(|| meta.get("previousClose"))
+            .and_then(|v| v.as_f64())
+            .unwrap_or(price);
+        Ok(Quote { price, previous_close })
+    }
+
+    /// Latest quotes by symbol
+    pub fn quotes(&self) -> HashMap<String, Quote> {
+        self.quotes.clone()
+    }
+
+    /// The configured holdings valued at the latest quotes
+    pub fn portfolio(&self) -> Portfolio {
+        portfolio(&self.config.holdings, &self.quotes)
+    }
+
+    /// Currency prefix from the config
+    pub fn currency(&self) -> &str {
+        &self.config.currency_symbol
+    }
+
+    /// Whether a fetch is running
+    pub fn is_fetching(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Today's direction: true when the portfolio (or first symbol) is down
+    pub fn is_down(&self) -> bool {
+        if !self.config.holdings.is_empty() {
+            return self.portfolio().day_change < 0.0;
+        }
+        Self::symbols(&self.config)
+            .first()
+            .and_then(|s| self.quotes.get(s))
+            .map(|q| q.change() < 0.0)
+            .unwrap_or(false)
+    }
+
+    fn build_display_text(&self) -> String {
+        if !self.config.holdings.is_empty() {
+            let portfolio = self.portfolio();
+            if portfolio.positions.is_empty() {
+                return "📈 ...".to_string();
+            }
+            return format!(
+                "{} {}{} {}",
+                arrow(portfolio.day_change),
+                self.config.currency_symbol,
+                format_amount(portfolio.value),
+                format_signed_pct(portfolio.day_change_pct)
+            );
+        }
+
+        let parts: Vec<String> = Self::symbols(&self.config)
+            .iter()
+            .filter_map(|s| {
+                let quote = self.quotes.get(s)?;
+                Some(format!("{} {:.2} {}{:.1}%", s, quote.price, arrow(quote.change()), quote.change_pct().abs()))
+            })
+            .collect();
+        if parts.is_empty() && !self.config.symbols.is_empty() {
+            return "📈 ...".to_string();
+        }
+        parts.join("  ")
+    }
+}
+
+impl Default for StocksModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for StocksModule {
+    fn id(&self) -> &str {
+        "stocks"
+    }
+
+    fn name(&self) -> &str {
+        "Stocks"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.cached_text.clone()
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        self.config = config.modules.stocks.clone();
+        self.collect();
+        let symbols = Self::symbols(&self.config);
+        let interval = Duration::from_secs(self.config.update_interval_min.max(1) as u64 * 60);
+        let due = self.last_fetch.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if !symbols.is_empty() && (due || symbols != self.fetched_symbols) {
+            self.fetch_async(symbols);
+        }
+        self.cached_text = self.build_display_text();
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        if crate::modules::is_offline(self, config) {
+            return;
+        }
+        self.last_fetch = None;
+        self.update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let currency = &self.config.currency_symbol;
+        let mut lines = Vec::new();
+        if !self.config.holdings.is_empty() {
+            let portfolio = self.portfolio();
+            lines.push(format!("Portfolio: {}{}", currency, format_amount(portfolio.value)));
+            lines.push(format!(
+                "Today: {} ({})",
+                format_signed_money(portfolio.day_change, currency),
+                format_signed_pct(portfolio.day_change_pct)
+            ));
+            if let (Some(total), Some(pct)) = (portfolio.total_change, portfolio.total_change_pct) {
+                lines.push(format!("Total: {} ({})", format_signed_money(total, currency), format_signed_pct(pct)));
+            }
+            if !portfolio.missing.is_empty() {
+                lines.push(format!("Waiting for: {}", portfolio.missing.join(", ")));
+            }
+        } else {
+            for symbol in Self::symbols(&self.config) {
+                if let Some(quote) = self.quotes.get(&symbol) {
+                    lines.push(format!(
+                        "{}: {:.2} ({}, {})",
+                        symbol,
+                        quote.price,
+                        format_signed_money(quote.change(), ""),
+                        format_signed_pct(quote.change_pct())
+                    ));
+                }
+            }
+        }
+        if let Some(error) = &self.error {
+            lines.push(error.clone());
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::NETWORK_CONNECTED]
+    }
+
+    fn on_event(&mut self, _topic: &str, config: &crate::config::Config) {
+        // Back online: replace quotes that failed while disconnected
+        self.force_refresh(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(symbol: &str, quantity: f64, cost_basis: f64) -> Holding {
+        Holding {
+            symbol: symbol.to_string(),
+            quantity,
+            cost_basis,
+        }
+    }
+
+    #[test]
+    fn parses_chart_response() {
+        let body = r#"{"chart":{"result":[{"meta":{"regularMarketPrice":190.5,"chartPreviousClose":188.0}}]}}"#;
+        let quote = StocksModule::parse_quote(body).expect("quote");
+        assert_eq!(quote, Quote { price: 190.5, previous_close: 188.0 });
+        assert!((quote.change() - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn previous_close_falls_back() {
+        let body = r#"{"chart":{"result":[{"meta":{"regularMarketPrice":10.0,"previousClose":8.0}}]}}"#;
+        assert_eq!(StocksModule::parse_quote(body).unwrap().previous_close, 8.0);
+        let body = r#"{"chart":{"result":[{"meta":{"regularMarketPrice":10.0}}]}}"#;
+        assert_eq!(StocksModule::parse_quote(body).unwrap().previous_close, 10.0);
+    }
+
+    #[test]
+    fn rejects_bad_responses() {
+        assert!(StocksModule::parse_quote("not json").is_err());
+        assert!(StocksModule::parse_quote(r#"{"chart":{"result":[],"error":"Not Found"}}"#).is_err());
+        assert!(StocksModule::parse_quote(r#"{"chart":{"result":[{"meta":{}}]}}"#).is_err());
+    }
+
+    #[test]
+    fn symbols_are_uppercase_and_unique() {
+        let config = StocksConfig {
+            symbols: vec!["msft".to_string(), " AAPL ".to_string(), "".to_string()],
+            holdings: vec![holding("aapl", 1.0, 0.0)],
+            ..Default::default()
+        };
+        assert_eq!(StocksModule::symbols(&config), vec!["AAPL", "MSFT"]);
+    }
+
+    #[test]
+    fn values_portfolio() {
+        let quotes = HashMap::from([
+            ("AAPL".to_string(), Quote { price: 110.0, previous_close: 100.0 }),
+            ("MSFT".to_string(), Quote { price: 50.0, previous_close: 50.0 }),
+        ]);
+        let holdings = [holding("aapl", 2.0, 90.0), holding("MSFT", 4.0, 0.0), holding("TSLA", 1.0, 0.0)];
+        let result = portfolio(&holdings, &quotes);
+        assert_eq!(result.value, 420.0);
+        assert_eq!(result.day_change, 20.0);
+        assert!((result.day_change_pct - 5.0).abs() < 1e-9);
+        // Only AAPL has a cost basis
+        assert_eq!(result.total_change, Some(40.0));
+        assert_eq!(result.missing, vec!["TSLA"]);
+        assert_eq!(result.positions[1].total_change, None);
+    }
+
+    #[test]
+    fn formats_amounts() {
+        assert_eq!(format_amount(1234567.891), "1,234,567.89");
+        assert_eq!(format_amount(-999.5), "-999.50");
+        assert_eq!(format_signed_money(-12.3, "$"), "−$12.30");
+        assert_eq!(format_signed_pct(1.234), "+1.23%");
+    }
+}
@@ -1,18 +1,23 @@
 //! System information module for CPU, memory, and disk usage
+//!
+//! Readings and histories come from the shared sampler; this module only turns
+//! them into text at its own update interval.
 
 #![allow(dead_code)]
 
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 
+use super::events;
+use super::sampler::{self, Sensor};
 use super::Module;
+use crate::i18n::tr;
 use crate::utils::format_bytes;
 
+/// Samples shown in the bar graphs
+const GRAPH_LEN: usize = 60;
+
 /// System information module
 pub struct SystemInfoModule {
-    system: Arc<Mutex<System>>,
     show_cpu: bool,
     show_memory: bool,
     show_disk: bool,
@@ -21,24 +26,15 @@ pub struct SystemInfoModule {
     memory_usage: f32,
     memory_used: u64,
     memory_total: u64,
-    // Histories for sparkline graphs
-    cpu_history: VecDeque<f32>,
-    memory_history: VecDeque<f32>,
-    history_len: usize,
     last_update: Instant,
     update_interval_ms: u64,
+    /// The graph as it was when battery saver came on; it stays still until it goes off
+    paused_graph: Option<Vec<f32>>,
 }
 
 impl SystemInfoModule {
     pub fn new() -> Self {
-        let system = System::new_with_specifics(
-            RefreshKind::new()
-                .with_cpu(CpuRefreshKind::new().with_cpu_usage())
-                .with_memory(MemoryRefreshKind::everything()),
-        );
-
         let mut module = Self {
-            system: Arc::new(Mutex::new(system)),
             show_cpu: true,
             show_memory: true,
             show_disk: false,
@@ -47,64 +43,31 @@ impl SystemInfoModule {
             memory_usage: 0.0,
             memory_used: 0,
             memory_total: 0,
-            // history length for graph samples
-            cpu_history: VecDeque::with_capacity(60),
-            memory_history: VecDeque::with_capacity(60),
-            history_len: 60,
             last_update: Instant::now(),
             update_interval_ms: 2000,
+            paused_graph: None,
         };
         module.force_update();
-        
-        // Pre-fill histories with zeros so graphs start at zero
-        module.cpu_history = VecDeque::from(vec![0.0; module.history_len]);
-        module.memory_history = VecDeque::from(vec![0.0; module.history_len]);
-        
         module
     }
 
     /// Force an immediate update
     fn force_update(&mut self) {
-        if let Ok(mut sys) = self.system.lock() {
-            sys.refresh_cpu_usage();
-            sys.refresh_memory();
-
-            // Calculate CPU usage (average across all cores)
-            let cpus = sys.cpus();
-            if !cpus.is_empty() {
-                self.cpu_usage =
-                    cpus.iter().map(|c| c.cpu_usage()).sum::<f32>() / cpus.len() as f32;
-            }
-
-            // Calculate memory usage
-            self.memory_total = sys.total_memory();
-            self.memory_used = sys.used_memory();
-            if self.memory_total > 0 {
-                self.memory_usage =
-                    (self.memory_used as f64 / self.memory_total as f64 * 100.0) as f32;
-            }
-        }
+        let latest = sampler::samples().latest.clone();
+        self.cpu_usage = latest.cpu;
+        self.memory_used = latest.memory_used;
+        self.memory_total = latest.memory_total;
+        self.memory_usage = latest.memory_percent();
 
         // Build display text
         let mut parts = Vec::new();
 
         if self.show_cpu {
-            parts.push(format!("CPU {:.0}%", self.cpu_usage));
+            parts.push(format!("{} {:.0}%", tr("CPU"), self.cpu_usage));
         }
 
         if self.show_memory {
-            parts.push(format!("RAM {:.0}%", self.memory_usage));
-        }
-
-        // Update histories for graphs
-        self.cpu_history.push_back(self.cpu_usage);
-        if self.cpu_history.len() > self.history_len {
-            self.cpu_history.pop_front();
-        }
-
-        self.memory_history.push_back(self.memory_usage);
-        if self.memory_history.len() > self.history_len {
-            self.memory_history.pop_front();
+            parts.push(format!("{} {:.0}%", tr("RAM"), self.memory_usage));
         }
 
         self.cached_text = parts.join("  ");
@@ -123,15 +86,102 @@ impl SystemInfoModule {
 
     /// Get CPU history for graph (oldest to newest)
     pub fn cpu_history(&self) -> Vec<f32> {
-        self.cpu_history.iter().copied().collect()
+        sampler::samples().cpu.recent(GRAPH_LEN)
     }
 
     /// Get memory history for graph (oldest to newest)
     pub fn memory_history(&self) -> Vec<f32> {
-        self.memory_history.iter().copied().collect()
+        sampler::samples().memory.recent(GRAPH_LEN)
     }
 }
 
+/// System-wide memory figures beyond plain used/total
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDetails {
+    pub committed: u64,
+    pub commit_limit: u64,
+    pub cached: u64,
+    pub paged_pool: u64,
+    pub nonpaged_pool: u64,
+}
+
+/// Read commit charge, file cache and kernel pool sizes
+pub fn memory_details() -> Option<MemoryDetails> {
+    use windows::Win32::System::ProcessStatus::{GetPerformanceInfo, PERFORMANCE_INFORMATION};
+
+    let mut info = PERFORMANCE_INFORMATION::default();
+    unsafe {
+        GetPerformanceInfo(&mut info, std::mem::size_of::<PERFORMANCE_INFORMATION>() as u32).ok()?;
+    }
+
+    // All counts are in pages
+    let page = info.PageSize as u64;
+    Some(MemoryDetails {
+        committed: info.CommitTotal as u64 * page,
+        commit_limit: info.CommitLimit as u64 * page,
+        cached: info.SystemCache as u64 * page,
+        paged_pool: info.KernelPaged as u64 * page,
+        nonpaged_pool: info.KernelNonpaged as u64 * page,
+    })
+}
+
+/// Purge the standby list (the same thing RAMMap's "Empty Standby List" does).
+///
+/// Requires SeProfileSingleProcessPrivilege, which normally means running elevated.
+pub fn purge_standby_memory() -> Result<(), String> {
+    use windows::core::w;
+    use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_NOT_ALL_ASSIGNED, HANDLE, LUID};
+    use windows::Win32::Security::{
+        AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED,
+        TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+    };
+    use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    // SYSTEM_INFORMATION_CLASS::SystemMemoryListInformation / MemoryPurgeStandbyList
+    const SYSTEM_MEMORY_LIST_INFORMATION: u32 = 80;
+    const MEMORY_PURGE_STANDBY_LIST: u32 = 4;
+    type NtSetSystemInformation = unsafe extern "system" fn(u32, *mut std::ffi::c_void, u32) -> i32;
+
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token)
+            .map_err(|e| format!("Could not open process token: {}", e))?;
+
+        let mut luid = LUID::default();
+        let lookup = LookupPrivilegeValueW(None, w!("SeProfileSingleProcessPrivilege"), &mut luid);
+        let privileges = TOKEN_PRIVILEGES {
+            PrivilegeCount: 1,
+            Privileges: [LUID_AND_ATTRIBUTES { Luid: luid, Attributes: SE_PRIVILEGE_ENABLED }],
+        };
+        let adjusted = lookup.and_then(|_| AdjustTokenPrivileges(token, false, Some(&privileges), 0, None, None));
+        // AdjustTokenPrivileges succeeds even when the privilege isn't held
+        let not_assigned = GetLastError() == ERROR_NOT_ALL_ASSIGNED;
+        let _ = CloseHandle(token);
+        if adjusted.is_err() || not_assigned {
+            return Err("Freeing standby memory requires running TopBar as administrator.".to_string());
+        }
+
+        let ntdll = GetModuleHandleW(w!("ntdll.dll")).map_err(|e| e.to_string())?;
+        let Some(proc) = GetProcAddress(ntdll, windows::core::s!("NtSetSystemInformation")) else {
+            return Err("NtSetSystemInformation is not available".to_string());
+        };
+        let nt_set: NtSetSystemInformation = std::mem::transmute(proc);
+
+        let mut command = MEMORY_PURGE_STANDBY_LIST;
+        let status = nt_set(
+            SYSTEM_MEMORY_LIST_INFORMATION,
+            &mut command as *mut u32 as *mut std::ffi::c_void,
+            std::mem::size_of::<u32>() as u32,
+        );
+        if status < 0 {
+            return Err(format!("NtSetSystemInformation failed (0x{:08X})", status as u32));
+        }
+    }
+
+    Ok(())
+}
+
 impl Default for SystemInfoModule {
     fn default() -> Self {
         Self::new()
@@ -152,7 +202,13 @@ impl Module for SystemInfoModule {
         self.cached_text.clone()
     }
 
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        vec![("cpu", self.cpu_usage()), ("memory", self.memory_usage())]
+    }
+
     fn update(&mut self, config: &crate::config::Config) {
+        // For the top processes in the module's menu
+        sampler::want(Sensor::Processes);
         // Use configurable update interval from config, with battery optimization
         let base_interval = config.modules.system_info.update_interval_ms;
         let effective_interval = base_interval * crate::utils::battery_update_multiplier();
@@ -162,19 +218,30 @@ impl Module for SystemInfoModule {
         }
     }
 
+    fn force_refresh(&mut self, _config: &crate::config::Config) {
+        self.force_update();
+    }
+
     fn on_click(&mut self) {
         // Open task manager
         let _ = std::process::Command::new("taskmgr.exe").spawn();
     }
 
     fn tooltip(&self) -> Option<String> {
-        Some(format!(
-            "CPU Usage: {:.1}%\nRAM: {} / {} ({:.1}%)",
+        let mut text = format!(
+            "{}: {:.1}%\n{}: {} / {} ({:.1}%)",
+            tr("CPU Usage"),
             self.cpu_usage,
+            tr("RAM"),
             format_bytes(self.memory_used),
             format_bytes(self.memory_total),
             self.memory_usage
-        ))
+        );
+        // Only shown when another module has the sensor read; don't query it on hover
+        if let Some(temp) = super::metrics::latest_thermal().cpu_temp {
+            text.push_str(&format!("\n{}: {:.0}°C", tr("CPU Temperature"), temp));
+        }
+        Some(text)
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -187,6 +254,14 @@ impl Module for SystemInfoModule {
 
     fn graph_values(&self) -> Option<Vec<f32>> {
         // Return CPU usage history (oldest to newest) so the renderer can draw a historical graph
-        Some(self.cpu_history.iter().copied().collect())
+        Some(self.paused_graph.clone().unwrap_or_else(|| self.cpu_history()))
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::POWER_SAVER_ON, events::POWER_SAVER_OFF]
+    }
+
+    fn on_event(&mut self, topic: &str, _config: &crate::config::Config) {
+        self.paused_graph = (topic == events::POWER_SAVER_ON).then(|| self.cpu_history());
     }
 }
@@ -0,0 +1,238 @@
+//! Running apps module - a taskbar-style strip of open windows
+//!
+//! Lists the visible top-level app windows (the same ones Alt+Tab shows) as icons.
+//! Like the launcher, the renderer registers each icon's bounds as `task:<hwnd>`
+//! so the click handlers can focus, minimize or close that window. The strip keeps
+//! its own order: new windows are appended and drag-and-drop moves them.
+
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, WPARAM};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::Module;
+
+/// Prefix of the per-icon ids used for hit testing
+pub const ITEM_PREFIX: &str = "task:";
+
+/// How often the window list is refreshed
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Window handle for a `task:<hwnd>` hit-test id
+pub fn item_hwnd(id: &str) -> Option<HWND> {
+    let raw: isize = id.strip_prefix(ITEM_PREFIX)?.parse().ok()?;
+    Some(HWND(raw as *mut std::ffi::c_void))
+}
+
+/// Hit-test id for a window
+pub fn item_id(hwnd: isize) -> String {
+    format!("{}{}", ITEM_PREFIX, hwnd)
+}
+
+/// One open app window
+#[derive(Debug, Clone)]
+pub struct TaskWindow {
+    /// Window handle (stored as an integer so the module stays Send)
+    pub hwnd: isize,
+    pub title: String,
+    /// Executable path, used for the icon
+    pub path: String,
+}
+
+/// Running apps module
+pub struct TasksModule {
+    windows: Vec<TaskWindow>,
+    active: isize,
+    last_update: Option<Instant>,
+}
+
+impl TasksModule {
+    pub fn new() -> Self {
+        Self {
+            windows: Vec::new(),
+            active: 0,
+            last_update: None,
+        }
+    }
+
+    /// Force an immediate update
+    fn force_update(&mut self) {
+        let current = enumerate_task_windows();
+
+        // Keep the existing order, drop closed windows and append new ones
+        self.windows.retain(|w| current.contains(&HWND(w.hwnd as *mut std::ffi::c_void)));
+        for hwnd in current {
+            let title = window_title(hwnd);
+            match self.windows.iter_mut().find(|w| w.hwnd == hwnd.0 as isize) {
+                Some(existing) => existing.title = title,
+                None => self.windows.push(TaskWindow {
+                    hwnd: hwnd.0 as isize,
+                    title,
+                    // Looked up once; a window doesn't change owner process
+                    path: super::active_window::window_process_path(hwnd),
+                }),
+            }
+        }
+
+        let foreground = unsafe { GetForegroundWindow() };
+        // Keep the previous highlight while the bar itself or a menu has focus
+        if self.windows.iter().any(|w| w.hwnd == foreground.0 as isize) {
+            self.active = foreground.0 as isize;
+        }
+        self.last_update = Some(Instant::now());
+    }
+
+    /// Open windows, in strip order
+    pub fn windows(&self) -> &[TaskWindow] {
+        &self.windows
+    }
+
+    /// Whether a window is the one the user is working in
+    pub fn is_active(&self, hwnd: isize) -> bool {
+        self.active == hwnd
+    }
+
+    /// Title of a window in the strip
+    pub fn title(&self, hwnd: isize) -> Option<&str> {
+        self.windows
+            .iter()
+            .find(|w| w.hwnd == hwnd)
+            .map(|w| w.title.as_str())
+    }
+
+    /// Move a window to a new position in the strip
+    pub fn move_to(&mut self, hwnd: isize, index: usize) {
+        if let Some(pos) = self.windows.iter().position(|w| w.hwnd == hwnd) {
+            let window = self.windows.remove(pos);
+            let index = if index > pos { index - 1 } else { index };
+            self.windows.insert(index.min(self.windows.len()), window);
+        }
+    }
+
+    /// Focus a window, or minimize it if it's already focused (like the taskbar)
+    pub fn activate_or_minimize(&mut self, hwnd: HWND) {
+        unsafe {
+            if IsIconic(hwnd).as_bool() {
+                let _ = ShowWindow(hwnd, SW_RESTORE);
+                let _ = SetForegroundWindow(hwnd);
+            } else if self.active == hwnd.0 as isize {
+                let _ = ShowWindow(hwnd, SW_MINIMIZE);
+                self.active = 0;
+                return;
+            } else {
+                let _ = SetForegroundWindow(hwnd);
+            }
+        }
+        self.active = hwnd.0 as isize;
+    }
+
+    /// Ask a window to close (the app may still prompt to save)
+    pub fn close(hwnd: HWND) {
+        unsafe {
+            let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+impl Default for TasksModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for TasksModule {
+    fn id(&self) -> &str {
+        "tasks"
+    }
+
+    fn name(&self) -> &str {
+        "Running Apps"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        // Icons only; the renderer draws them
+        String::new()
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {
+        let due = self
+            .last_update
+            .map(|t| t.elapsed() >= UPDATE_INTERVAL)
+            .unwrap_or(true);
+        if due {
+            self.force_update();
+        }
+    }
+
+    fn force_refresh(&mut self, _config: &crate::config::Config) {
+        self.force_update();
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let titles: Vec<&str> = self.windows.iter().map(|w| w.title.as_str()).collect();
+        Some(titles.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Visible, uncloaked, unowned app windows with a title, in Z order
+pub fn enumerate_task_windows() -> Vec<HWND> {
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let list = &mut *(lparam.0 as *mut Vec<HWND>);
+        if is_task_window(hwnd) {
+            list.push(hwnd);
+        }
+        BOOL(1)
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut _ as isize));
+    }
+    handles
+}
+
+/// The Alt+Tab rules: visible, not cloaked (other virtual desktops, suspended UWP
+/// frames), not a tool window and not owned by another window
+fn is_task_window(hwnd: HWND) -> bool {
+    unsafe {
+        if !IsWindowVisible(hwnd).as_bool() || Some(hwnd) == crate::window::get_main_hwnd() {
+            return false;
+        }
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+        if ex_style & WS_EX_APPWINDOW.0 == 0 {
+            if ex_style & WS_EX_TOOLWINDOW.0 != 0 {
+                return false;
+            }
+            if GetWindow(hwnd, GW_OWNER).map(|o| !o.0.is_null()).unwrap_or(false) {
+                return false;
+            }
+        }
+
+        let mut cloaked: u32 = 0;
+        let _ = DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut _ as *mut _,
+            std::mem::size_of::<u32>() as u32,
+        );
+        cloaked == 0 && GetWindowTextLengthW(hwnd) > 0
+    }
+}
+
+fn window_title(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetWindowTextW(hwnd, &mut buffer) } as usize;
+    String::from_utf16_lossy(&buffer[..len])
+}
@@ -0,0 +1,204 @@
+//! Thermal module - summarizes CPU/GPU temperatures and fan speeds
+//!
+//! Readings come from the sampler thread, so this module doesn't open its own
+//! sensor backends or wait on them. Crossing the configured warning threshold tints the module
+//! and requests attention once per excursion.
+
+use std::time::Instant;
+
+use super::metrics::ThermalSnapshot;
+use super::sampler::{self, Sensor};
+use super::Module;
+use crate::attention::{AttentionLevel, AttentionRequest};
+
+/// Degrees below the threshold the temperature must drop before warning again
+const WARNING_HYSTERESIS: f32 = 5.0;
+
+/// Thermal module
+pub struct ThermalModule {
+    cached_text: String,
+    snapshot: ThermalSnapshot,
+    warning_threshold: f32,
+    is_warning: bool,
+    last_update: Option<Instant>,
+}
+
+impl ThermalModule {
+    pub fn new() -> Self {
+        Self {
+            cached_text: "🌡 --".to_string(),
+            snapshot: ThermalSnapshot::default(),
+            warning_threshold: crate::config::ThermalConfig::default().warning_threshold,
+            is_warning: false,
+            last_update: None,
+        }
+    }
+
+    /// Force an immediate update
+    fn force_update(&mut self, config: &crate::config::Config) {
+        self.snapshot = super::metrics::latest_thermal();
+        self.warning_threshold = config.modules.thermal.warning_threshold;
+        self.update_warning_state(config);
+        self.cached_text = self.build_display_text(config);
+        self.last_update = Some(Instant::now());
+    }
+
+    /// Track threshold crossings and raise the banner on the way up
+    fn update_warning_state(&mut self, config: &crate::config::Config) {
+        let Some(temp) = self.snapshot.max_temp() else {
+            self.is_warning = false;
+            return;
+        };
+
+        if !self.is_warning && temp >= self.warning_threshold {
+            self.is_warning = true;
+            if config.modules.thermal.notify {
+                self.show_warning_banner(config, temp);
+            }
+        } else if self.is_warning && temp < self.warning_threshold - WARNING_HYSTERESIS {
+            self.is_warning = false;
+        }
+    }
+
+    fn show_warning_banner(&self, config: &crate::config::Config, temp: f32) {
+        let source = match (self.snapshot.cpu_temp, self.snapshot.gpu_temp) {
+            (Some(c), Some(g)) if g > c => "GPU",
+            (None, Some(_)) => "GPU",
+            _ => "CPU",
+        };
+        let text = format!(
+            "{} temperature is {:.0}°C (warning threshold {:.0}°C)",
+            source, temp, self.warning_threshold
+        );
+        crate::attention::request(
+            config,
+            AttentionRequest::new("thermal", AttentionLevel::Warning, "High temperature", &text),
+        );
+    }
+
+    /// Build the display text
+    fn build_display_text(&self, config: &crate::config::Config) -> String {
+        let mut parts = Vec::new();
+        if let Some(cpu) = self.snapshot.cpu_temp {
+            parts.push(format!("CPU {:.0}°", cpu));
+        }
+        if let Some(gpu) = self.snapshot.gpu_temp {
+            parts.push(format!("GPU {:.0}°", gpu));
+        }
+        if config.modules.thermal.show_fans {
+            if let Some(rpm) = self.fastest_fan_rpm() {
+                parts.push(format!("{:.0} rpm", rpm));
+            }
+        }
+
+        if parts.is_empty() {
+            "🌡 --".to_string()
+        } else {
+            format!("🌡 {}", parts.join("  "))
+        }
+    }
+
+    fn fastest_fan_rpm(&self) -> Option<f32> {
+        self.snapshot.fans.iter().map(|f| f.rpm).reduce(f32::max)
+    }
+
+    /// Whether the hottest sensor is above the warning threshold
+    pub fn is_warning(&self) -> bool {
+        self.is_warning
+    }
+
+    /// Latest readings
+    pub fn snapshot(&self) -> &ThermalSnapshot {
+        &self.snapshot
+    }
+}
+
+impl Default for ThermalModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for ThermalModule {
+    fn id(&self) -> &str {
+        "thermal"
+    }
+
+    fn name(&self) -> &str {
+        "Thermals"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.cached_text.clone()
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        let (cpu, gpu) = (self.snapshot.cpu_temp, self.snapshot.gpu_temp);
+        let mut metrics = Vec::new();
+        if let Some(hottest) = cpu.into_iter().chain(gpu).reduce(f32::max) {
+            metrics.push(("temperature", hottest));
+        }
+        metrics.extend(cpu.map(|t| ("cpu", t)));
+        metrics.extend(gpu.map(|t| ("gpu", t)));
+        metrics
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        sampler::want(Sensor::CpuTemperature);
+        sampler::want(Sensor::GpuTemperature);
+        if config.modules.thermal.show_fans {
+            sampler::want(Sensor::Fans);
+        }
+        let due = self
+            .last_update
+            .map(|t| t.elapsed().as_millis() as u64 >= config.modules.thermal.update_interval_ms)
+            .unwrap_or(true);
+        if due {
+            self.force_update(config);
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
+    fn copy_value(&self, config: &crate::config::Config) -> String {
+        let mut parts = Vec::new();
+        if let Some(cpu) = self.snapshot.cpu_temp {
+            parts.push(format!("CPU {:.0}°C", cpu));
+        }
+        if let Some(gpu) = self.snapshot.gpu_temp {
+            parts.push(format!("GPU {:.0}°C", gpu));
+        }
+        if parts.is_empty() {
+            self.display_text(config)
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let mut lines = vec!["Thermals".to_string()];
+        match self.snapshot.cpu_temp {
+            Some(t) => lines.push(format!("CPU: {:.0}°C", t)),
+            None => lines.push("CPU: unavailable".to_string()),
+        }
+        match self.snapshot.gpu_temp {
+            Some(t) => lines.push(format!("GPU: {:.0}°C", t)),
+            None => lines.push("GPU: unavailable".to_string()),
+        }
+        for fan in &self.snapshot.fans {
+            lines.push(format!("{}: {:.0} RPM", fan.name, fan.rpm));
+        }
+        lines.push(format!("Warning at {:.0}°C", self.warning_threshold));
+        Some(lines.join("\n"))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
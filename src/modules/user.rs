@@ -0,0 +1,180 @@
+//! User module - the signed-in account's name and picture
+//!
+//! The picture is the account's user tile, found through the paths Windows
+//! keeps under `AccountPicture\Users\<SID>`. Clicking opens a menu to lock the
+//! PC, switch user, sign out or open the account settings.
+
+use std::path::PathBuf;
+
+use log::{debug, info};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL};
+use windows::Win32::Security::Authentication::Identity::{GetUserNameExW, NameDisplay};
+use windows::Win32::Security::Authorization::ConvertSidToStringSidW;
+use windows::Win32::Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER};
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+use windows::Win32::System::RemoteDesktop::{WTSDisconnectSession, WTS_CURRENT_SERVER_HANDLE, WTS_CURRENT_SESSION};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+use super::Module;
+use crate::utils::to_wide_string;
+
+const ACCOUNT_PICTURE_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\AccountPicture\\Users";
+/// User tile sizes Windows saves, smallest first
+const PICTURE_SIZES: [u32; 6] = [32, 40, 48, 64, 96, 192];
+
+/// User module
+pub struct UserModule {
+    /// Full name, or the account name when there isn't one
+    display_name: String,
+    account_name: String,
+    /// User tile images by pixel size
+    pictures: Vec<(u32, PathBuf)>,
+}
+
+impl UserModule {
+    pub fn new() -> Self {
+        let account_name = std::env::var("USERNAME").unwrap_or_default();
+        let display_name = display_name().unwrap_or_else(|| account_name.clone());
+        let pictures = current_user_sid().map(|sid| account_pictures(&sid)).unwrap_or_default();
+        debug!("User module: {} ({} pictures)", display_name, pictures.len());
+        Self {
+            display_name,
+            account_name,
+            pictures,
+        }
+    }
+
+    /// Full name of the signed-in user
+    pub fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    /// Account picture at least `size` pixels across, or the largest there is
+    pub fn picture(&self, size: u32) -> Option<&PathBuf> {
+        self.pictures
+            .iter()
+            .find(|(s, _)| *s >= size)
+            .or_else(|| self.pictures.last())
+            .map(|(_, path)| path)
+    }
+}
+
+impl Default for UserModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for UserModule {
+    fn id(&self) -> &str {
+        "user"
+    }
+
+    fn name(&self) -> &str {
+        "User"
+    }
+
+    fn display_text(&self, config: &crate::config::Config) -> String {
+        if config.modules.user.show_name {
+            self.display_name.clone()
+        } else {
+            String::new()
+        }
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {}
+
+    fn tooltip(&self) -> Option<String> {
+        if self.display_name == self.account_name {
+            Some(self.display_name.clone())
+        } else {
+            Some(format!("{}\n{}", self.display_name, self.account_name))
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Disconnect the session so another user can sign in, the same as "Switch
+/// user" on the Start menu; locks instead if that isn't possible
+pub fn switch_user() {
+    info!("Switching user");
+    if let Err(e) = unsafe { WTSDisconnectSession(WTS_CURRENT_SERVER_HANDLE, WTS_CURRENT_SESSION, false) } {
+        debug!("Disconnecting the session failed ({}), locking instead", e);
+        let _ = crate::modules::power::run(crate::config::PowerAction::Lock);
+    }
+}
+
+/// The user's full name, when the account has one
+fn display_name() -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut size = buffer.len() as u32;
+    unsafe {
+        if !GetUserNameExW(NameDisplay, PWSTR(buffer.as_mut_ptr()), &mut size).as_bool() {
+            return None;
+        }
+    }
+    let name = String::from_utf16_lossy(&buffer[..size as usize]).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// The signed-in user's SID as a string ("S-1-5-21-...")
+fn current_user_sid() -> Option<String> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).ok()?;
+        let mut buffer = vec![0u8; 256];
+        let mut needed = 0u32;
+        let got = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+            buffer.len() as u32,
+            &mut needed,
+        );
+        let _ = CloseHandle(token);
+        got.ok()?;
+
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid).ok()?;
+        let text = sid.to_string().ok();
+        let _ = LocalFree(HLOCAL(sid.0 as *mut std::ffi::c_void));
+        text
+    }
+}
+
+/// User tile images Windows has saved for the account
+fn account_pictures(sid: &str) -> Vec<(u32, PathBuf)> {
+    let key = to_wide_string(&format!("{}\\{}", ACCOUNT_PICTURE_KEY, sid));
+    PICTURE_SIZES
+        .iter()
+        .filter_map(|size| {
+            let name = to_wide_string(&format!("Image{}", size));
+            let mut data = [0u16; 520];
+            let mut len = std::mem::size_of_val(&data) as u32;
+            let result = unsafe {
+                RegGetValueW(
+                    HKEY_LOCAL_MACHINE,
+                    PCWSTR(key.as_ptr()),
+                    PCWSTR(name.as_ptr()),
+                    RRF_RT_REG_SZ,
+                    None,
+                    Some(data.as_mut_ptr() as *mut std::ffi::c_void),
+                    Some(&mut len),
+                )
+            };
+            result.ok().ok()?;
+            let chars = (len as usize / 2).min(data.len());
+            let path = PathBuf::from(String::from_utf16_lossy(&data[..chars]).trim_end_matches('\0'));
+            path.exists().then_some((*size, path))
+        })
+        .collect()
+}
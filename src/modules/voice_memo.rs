@@ -0,0 +1,306 @@
+//! Voice memo recorder
+//!
+//! Records the default microphone through WASAPI shared-mode capture into a WAV
+//! file in the memos folder. The audio engine converts whatever the device mixes
+//! at to 16-bit mono, so the samples go to disk as they arrive. Like the screen
+//! recorder, capture runs on its own thread and the bar polls the shared state;
+//! a click on the module starts or stops a memo and its menu lists past memos.
+
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use windows::Win32::Media::Audio::{IAudioCaptureClient, IAudioClient};
+
+use super::Module;
+use crate::config::VoiceMemoConfig;
+
+/// Memos are recorded at this rate, mono, 16-bit
+const SAMPLE_RATE: u32 = 48_000;
+/// Size of the WAV header written before the samples
+const WAV_HEADER_LEN: u32 = 44;
+/// How long to wait for the microphone to open before giving up
+const OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An in-progress memo
+struct ActiveMemo {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    started: Instant,
+    path: PathBuf,
+}
+
+static RECORDER: OnceCell<Mutex<Option<ActiveMemo>>> = OnceCell::new();
+
+fn recorder() -> &'static Mutex<Option<ActiveMemo>> {
+    RECORDER.get_or_init(|| Mutex::new(None))
+}
+
+/// Whether a memo is being recorded
+pub fn is_recording() -> bool {
+    recorder()
+        .lock()
+        .as_ref()
+        .map(|r| !r.thread.is_finished())
+        .unwrap_or(false)
+}
+
+/// Time since the current memo started
+pub fn elapsed() -> Option<Duration> {
+    recorder()
+        .lock()
+        .as_ref()
+        .filter(|r| !r.thread.is_finished())
+        .map(|r| r.started.elapsed())
+}
+
+/// Start recording a memo into the memos folder
+pub fn start_recording(config: &VoiceMemoConfig) -> Result<PathBuf> {
+    let mut slot = recorder().lock();
+    if slot.as_ref().map(|r| !r.thread.is_finished()).unwrap_or(false) {
+        return Err(anyhow!("A memo is already being recorded"));
+    }
+
+    let dir = config.output_dir();
+    std::fs::create_dir_all(&dir)?;
+    let stamp = chrono::Local::now().format("%Y-%m-%d at %H.%M.%S");
+    let path = dir.join(format!("Memo {}.wav", stamp));
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread_path = path.clone();
+    let (opened_tx, opened_rx) = mpsc::channel();
+    let thread = std::thread::spawn(move || {
+        if let Err(e) = record(&thread_path, &thread_stop, &opened_tx) {
+            error!("Voice memo recording failed: {}", e);
+        }
+    });
+
+    // A missing or busy microphone is reported here rather than only in the log
+    match opened_rx.recv_timeout(OPEN_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            let _ = thread.join();
+            return Err(anyhow!(e));
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            let _ = thread.join();
+            return Err(anyhow!("The recording thread stopped before the microphone opened"));
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            stop.store(true, Ordering::SeqCst);
+            return Err(anyhow!("The microphone didn't respond"));
+        }
+    }
+
+    info!("Started voice memo {:?}", path);
+    *slot = Some(ActiveMemo {
+        stop,
+        thread,
+        started: Instant::now(),
+        path: path.clone(),
+    });
+    Ok(path)
+}
+
+/// Stop the current memo and wait for the file to be finished.
+///
+/// Returns the path of the saved memo.
+pub fn stop_recording() -> Option<PathBuf> {
+    let active = recorder().lock().take()?;
+    active.stop.store(true, Ordering::SeqCst);
+    let _ = active.thread.join();
+    info!("Stopped voice memo ({:?})", active.path);
+    active.path.exists().then_some(active.path)
+}
+
+/// Saved memos, newest first
+pub fn memos(config: &VoiceMemoConfig) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(config.output_dir()) else {
+        return Vec::new();
+    };
+    let mut memos: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().map(|x| x.eq_ignore_ascii_case("wav")).unwrap_or(false))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    memos.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    memos.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Recording thread body; whether the microphone opened goes to `opened` first
+fn record(path: &Path, stop: &AtomicBool, opened: &Sender<Result<(), String>>) -> Result<()> {
+    use windows::Win32::Media::Audio::AUDCLNT_BUFFERFLAGS_SILENT;
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let (client, capture, mut file) = match open_capture(path) {
+            Ok(parts) => {
+                let _ = opened.send(Ok(()));
+                parts
+            }
+            Err(e) => {
+                // start_recording reports it
+                let _ = std::fs::remove_file(path);
+                let _ = opened.send(Err(e.to_string()));
+                return Ok(());
+            }
+        };
+
+        let mut data_len: u32 = 0;
+        let result = (|| -> Result<()> {
+            while !stop.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(20));
+                while capture.GetNextPacketSize()? > 0 {
+                    let mut data = std::ptr::null_mut();
+                    let mut frames = 0u32;
+                    let mut flags = 0u32;
+                    capture.GetBuffer(&mut data, &mut frames, &mut flags, None, None)?;
+                    let len = frames as usize * 2;
+                    if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0 || data.is_null() {
+                        file.write_all(&vec![0u8; len])?;
+                    } else {
+                        file.write_all(std::slice::from_raw_parts(data, len))?;
+                    }
+                    capture.ReleaseBuffer(frames)?;
+                    data_len = data_len.saturating_add(len as u32);
+                }
+            }
+            Ok(())
+        })();
+        let _ = client.Stop();
+
+        // Fill in the sizes now that the length is known
+        file.seek(SeekFrom::Start(0))?;
+        write_wav_header(&mut file, data_len)?;
+        file.flush()?;
+        result
+    }
+}
+
+/// Open the default microphone and the memo file, and start capturing
+unsafe fn open_capture(path: &Path) -> Result<(IAudioClient, IAudioCaptureClient, BufWriter<File>)> {
+    use windows::Win32::Media::Audio::*;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+    let device = enumerator
+        .GetDefaultAudioEndpoint(eCapture, eConsole)
+        .map_err(|_| anyhow!("No microphone found"))?;
+    let client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+
+    let format = WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_PCM as u16,
+        nChannels: 1,
+        nSamplesPerSec: SAMPLE_RATE,
+        nAvgBytesPerSec: SAMPLE_RATE * 2,
+        nBlockAlign: 2,
+        wBitsPerSample: 16,
+        cbSize: 0,
+    };
+    // One second of buffer, in 100ns units; the loop drains it far more often
+    client.Initialize(
+        AUDCLNT_SHAREMODE_SHARED,
+        AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+        10_000_000,
+        0,
+        &format,
+        None,
+    )?;
+    let capture: IAudioCaptureClient = client.GetService()?;
+
+    let mut file = BufWriter::new(File::create(path)?);
+    write_wav_header(&mut file, 0)?;
+    client.Start()?;
+    Ok((client, capture, file))
+}
+
+/// Write a 16-bit mono PCM WAV header for `data_len` bytes of samples
+fn write_wav_header(out: &mut impl Write, data_len: u32) -> std::io::Result<()> {
+    out.write_all(b"RIFF")?;
+    out.write_all(&(WAV_HEADER_LEN - 8 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&1u16.to_le_bytes())?; // mono
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?; // block align
+    out.write_all(&16u16.to_le_bytes())?; // bits per sample
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())
+}
+
+/// Voice memo module
+pub struct VoiceMemoModule {
+    cached_text: String,
+}
+
+impl VoiceMemoModule {
+    pub fn new() -> Self {
+        Self {
+            cached_text: "🎙".to_string(),
+        }
+    }
+
+    /// Whether a memo is being recorded (the renderer draws it in red)
+    pub fn is_recording(&self) -> bool {
+        is_recording()
+    }
+}
+
+impl Default for VoiceMemoModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for VoiceMemoModule {
+    fn id(&self) -> &str {
+        "voice_memo"
+    }
+
+    fn name(&self) -> &str {
+        "Voice Memo"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        self.cached_text.clone()
+    }
+
+    fn update(&mut self, _config: &crate::config::Config) {
+        self.cached_text = match elapsed() {
+            Some(elapsed) => {
+                let secs = elapsed.as_secs();
+                format!("🎙 {:02}:{:02}", secs / 60, secs % 60)
+            }
+            None => "🎙".to_string(),
+        };
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.is_recording() {
+            Some("Recording voice memo\nClick to stop".to_string())
+        } else {
+            Some("Voice memo\nClick to record, right-click for memos".to_string())
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
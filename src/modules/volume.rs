@@ -23,6 +23,8 @@ pub struct VolumeModule {
     com_initialized: bool,
     output_device_name: String,
     sound_feedback: bool,
+    /// Show the new level on screen after a change from the bar
+    osd: bool,
     previous_volume_level: u32,
     previous_is_muted: bool,
 }
@@ -39,6 +41,7 @@ impl VolumeModule {
             com_initialized: false,
             output_device_name: String::new(),
             sound_feedback: true, // Default to enabled
+            osd: true,
             previous_volume_level: 50,
             previous_is_muted: false,
         };
@@ -154,6 +157,7 @@ impl VolumeModule {
         // Update previous values to prevent duplicate sound playback
         self.previous_volume_level = self.volume_level;
         self.previous_is_muted = self.is_muted;
+        self.show_osd();
     }
 
     /// Change volume (now with real system integration)
@@ -165,11 +169,30 @@ impl VolumeModule {
         self.previous_is_muted = self.is_muted;
     }
 
+    /// Show the level (or "Muted") on screen
+    fn show_osd(&self) {
+        if !self.osd {
+            return;
+        }
+        let icon = self.get_volume_icon();
+        if self.is_muted {
+            crate::render::show_osd_level(&crate::i18n::tr("Muted"), icon, 0);
+        } else {
+            let text = crate::i18n::tr("Volume {}%").replacen("{}", &self.volume_level.to_string(), 1);
+            crate::render::show_osd_level(&text, icon, self.volume_level);
+        }
+    }
+
     /// Get volume level
     pub fn volume_level(&self) -> u32 {
         self.volume_level
     }
 
+    /// Check if the volume change sound is enabled
+    pub fn sound_feedback_enabled(&self) -> bool {
+        self.sound_feedback
+    }
+
     /// Check if muted
     pub fn is_muted(&self) -> bool {
         self.is_muted
@@ -201,6 +224,11 @@ impl Module for VolumeModule {
         self.cached_text.clone()
     }
 
+    fn metrics(&self) -> Vec<(&'static str, f32)> {
+        let level = if self.is_muted() { 0 } else { self.volume_level() };
+        vec![("volume", level as f32)]
+    }
+
     fn update(&mut self, config: &crate::config::Config) {
         // Use configurable update interval from config (in milliseconds)
         // Check more frequently for responsive volume changes
@@ -213,6 +241,7 @@ impl Module for VolumeModule {
             self.force_update(config);
             // Update sound feedback setting from config
             self.sound_feedback = config.modules.volume.sound_feedback;
+            self.osd = config.osd.volume;
             
             // Check if volume or mute state changed (from external sources)
             if self.sound_feedback && ((self.volume_level != prev_volume) || (self.is_muted != prev_muted)) {
@@ -243,6 +272,7 @@ impl Module for VolumeModule {
                 -(self.scroll_step as i32)
             };
             self.change_volume(step);
+            self.show_osd();
             // Play feedback sound when volume changes (if enabled)
             if self.sound_feedback {
                 crate::utils::play_volume_feedback_sound();
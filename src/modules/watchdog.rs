@@ -0,0 +1,180 @@
+//! Process watchdog module - shows whether configured critical processes are running
+//!
+//! Each `[[modules.watchdog.processes]]` entry gets a green (running) or red (missing)
+//! dot. Process state comes from the shared process snapshot read on the sampler thread.
+
+use std::time::Instant;
+
+use super::sampler::{self, Sensor};
+use super::Module;
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::config::WatchedProcess;
+
+/// Current state of one watched process
+#[derive(Debug, Clone)]
+pub struct WatchStatus {
+    pub name: String,
+    pub running: bool,
+    pub pid: Option<u32>,
+}
+
+/// Process watchdog module
+pub struct WatchdogModule {
+    statuses: Vec<WatchStatus>,
+    last_update: Option<Instant>,
+}
+
+impl WatchdogModule {
+    pub fn new() -> Self {
+        Self {
+            statuses: Vec::new(),
+            last_update: None,
+        }
+    }
+
+    /// Force an immediate update
+    fn force_update(&mut self, config: &crate::config::Config) {
+        let watched = &config.modules.watchdog.processes;
+        if watched.is_empty() {
+            self.statuses.clear();
+        } else {
+            let processes = super::metrics::processes();
+            if processes.is_empty() {
+                // Not read yet; the sampler reads it now that it's wanted
+                return;
+            }
+            let previous = std::mem::take(&mut self.statuses);
+            self.statuses = watched
+                .iter()
+                .map(|w| {
+                    let pid = processes
+                        .iter()
+                        .find(|p| p.name.eq_ignore_ascii_case(w.process.trim()))
+                        .map(|p| p.pid);
+                    WatchStatus {
+                        name: w.name.clone(),
+                        running: pid.is_some(),
+                        pid,
+                    }
+                })
+                .collect();
+
+            // Only flag processes that were seen running and then went away
+            if previous.len() == self.statuses.len() {
+                for (before, now) in previous.iter().zip(&self.statuses) {
+                    if before.running && !now.running {
+                        crate::attention::request(
+                            config,
+                            AttentionRequest::new(
+                                "watchdog",
+                                AttentionLevel::Error,
+                                "Process stopped",
+                                &format!("{} is no longer running", now.name),
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        self.last_update = Some(Instant::now());
+    }
+
+    /// Status of every watched process, in config order
+    pub fn statuses(&self) -> &[WatchStatus] {
+        &self.statuses
+    }
+
+    /// Start a watched process using its configured command
+    pub fn start_process(watched: &WatchedProcess) -> bool {
+        use std::os::windows::process::CommandExt;
+
+        let Some(cmd) = watched.start_command.as_deref().filter(|c| !c.trim().is_empty()) else {
+            return false;
+        };
+        log::info!("Watchdog: starting {} ({})", watched.name, cmd);
+        match std::process::Command::new("cmd")
+            .args(["/c", cmd])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+        {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Watchdog: failed to start {}: {}", watched.name, e);
+                false
+            }
+        }
+    }
+
+    /// Open a watched process's log file with the default viewer
+    pub fn open_log(watched: &WatchedProcess) {
+        if let Some(path) = watched.log_path.as_deref() {
+            crate::utils::open_url(path);
+        }
+    }
+}
+
+impl Default for WatchdogModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module for WatchdogModule {
+    fn id(&self) -> &str {
+        "watchdog"
+    }
+
+    fn name(&self) -> &str {
+        "Process Watchdog"
+    }
+
+    fn display_text(&self, _config: &crate::config::Config) -> String {
+        // The renderer colors each dot; this is used for measuring and drag previews
+        self.statuses.iter().map(|_| "●").collect::<Vec<_>>().join(" ")
+    }
+
+    fn update(&mut self, config: &crate::config::Config) {
+        if !config.modules.watchdog.processes.is_empty() {
+            sampler::want(Sensor::Processes);
+        }
+        let due = self
+            .last_update
+            .map(|t| t.elapsed().as_millis() as u64 >= config.modules.watchdog.update_interval_ms)
+            .unwrap_or(true);
+        // Config edits (reload) can change the list size; refresh right away
+        if due || self.statuses.len() != config.modules.watchdog.processes.len() {
+            self.force_update(config);
+        }
+    }
+
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        self.force_update(config);
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.statuses.is_empty() {
+            return Some("Process Watchdog\nAdd [[modules.watchdog.processes]] to the config".to_string());
+        }
+        let lines: Vec<String> = self
+            .statuses
+            .iter()
+            .map(|s| match s.pid {
+                Some(pid) => format!("● {}: running (PID {})", s.name, pid),
+                None => format!("○ {}: not running", s.name),
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.statuses.is_empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -2,17 +2,32 @@
 //!
 //! Uses wttr.in service for weather data - no API key required!
 //! Supports automatic location detection or custom city input.
+//! Readings from a local weather station (see `weather_station`) take precedence
+//! over the provider's current temperature, and indoor values are shown separately.
+//! Severe-weather alerts for the location are polled from the US National Weather
+//! Service (or a configured endpoint); active alerts tint the module, and new ones
+//! raise a notification.
 
 #![allow(dead_code)]
 
-use log::{error, info};
+use log::{error, info, warn};
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use super::weather_station::{StationReadings, WeatherStation};
+use super::events;
 use super::Module;
-use crate::config::TemperatureUnit;
-use chrono::{Local, NaiveDate};
+use crate::attention::{AttentionLevel, AttentionRequest};
+use crate::config::{TemperatureUnit, WeatherAlertsConfig};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+
+/// How often alerts are polled
+const ALERT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Alert endpoint used when none is configured (United States only)
+const NWS_ALERTS_URL: &str = "https://api.weather.gov/alerts/active?point={lat},{lon}";
+/// Country name wttr.in reports for locations the National Weather Service covers
+const NWS_COUNTRY: &str = "United States of America";
 
 /// Weather condition codes from wttr.in (WWO codes)
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -88,6 +103,14 @@ pub struct WeatherData {
     pub wind_speed: f32,
     pub wind_dir: String,
     pub forecast: Vec<DailyForecast>,
+    /// Today's sunrise at the reported location (local time)
+    pub sunrise: Option<NaiveTime>,
+    /// Today's sunset at the reported location (local time)
+    pub sunset: Option<NaiveTime>,
+    pub country: String,
+    /// Coordinates of the reported location
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
 impl Default for WeatherData {
@@ -104,6 +127,49 @@ impl Default for WeatherData {
             wind_speed: 0.0,
             wind_dir: String::new(),
             forecast: Vec::new(),
+            sunrise: None,
+            sunset: None,
+            country: String::new(),
+            latitude: None,
+            longitude: None,
+        }
+    }
+}
+
+/// An active severe-weather alert
+#[derive(Debug, Clone)]
+pub struct WeatherAlert {
+    pub id: String,
+    /// Kind of alert, e.g. "Tornado Warning"
+    pub event: String,
+    /// "Extreme", "Severe", "Moderate", "Minor" or "Unknown"
+    pub severity: String,
+    pub headline: String,
+    pub description: String,
+    pub instruction: String,
+    pub expires: Option<DateTime<Local>>,
+}
+
+impl WeatherAlert {
+    /// Whether the alert hasn't expired yet
+    pub fn is_active(&self) -> bool {
+        self.expires.map(|t| t > Local::now()).unwrap_or(true)
+    }
+
+    /// Extreme and severe alerts are shown as errors, the rest as warnings
+    pub fn level(&self) -> AttentionLevel {
+        if self.severity.eq_ignore_ascii_case("extreme") || self.severity.eq_ignore_ascii_case("severe") {
+            AttentionLevel::Error
+        } else {
+            AttentionLevel::Warning
+        }
+    }
+
+    /// One-line summary with the expiry time
+    pub fn summary(&self) -> String {
+        match self.expires {
+            Some(t) => format!("⚠ {} until {}", self.event, t.format("%a %H:%M")),
+            None => format!("⚠ {}", self.event),
         }
     }
 }
@@ -130,64 +196,38 @@ pub struct WeatherModule {
     update_interval_min: u32,
     fetch_status: Arc<Mutex<FetchStatus>>,
     is_fetching: Arc<Mutex<bool>>,
+    /// Local station reader, when configured
+    station: Option<WeatherStation>,
+    alerts_config: WeatherAlertsConfig,
+    alerts: Arc<Mutex<Vec<WeatherAlert>>>,
+    is_fetching_alerts: Arc<Mutex<bool>>,
+    /// When alerts were last polled, and for which coordinates
+    last_alerts: Option<(Instant, f64, f64)>,
+    /// Alerts already notified about
+    notified_alerts: HashSet<String>,
 }
 
 impl WeatherModule {
     pub fn new() -> Self {
-        let module = Self {
+        Self {
             cached_text: "...".to_string(), // Show loading indicator initially
             enabled: true,                     // Enabled by default - no API key needed!
             unit: TemperatureUnit::Celsius,
             show_icon: true,
             weather_data: Arc::new(Mutex::new(None)),
             location: "auto".to_string(), // Auto-detect by default
-            last_update: Instant::now() - std::time::Duration::from_secs(3600), // Force initial update
+            // The first update fetches, unless offline mode is on
+            last_update: Instant::now() - std::time::Duration::from_secs(3600),
             update_interval_min: 30,
             fetch_status: Arc::new(Mutex::new(FetchStatus::Idle)),
             is_fetching: Arc::new(Mutex::new(false)),
-        };
-
-        // Trigger initial fetch
-        module.fetch_weather_initial();
-
-        module
-    }
-
-    /// Initial weather fetch (called from new())
-    fn fetch_weather_initial(&self) {
-        let location = self.location.clone();
-        let weather_data = Arc::clone(&self.weather_data);
-        let fetch_status = Arc::clone(&self.fetch_status);
-        let is_fetching = Arc::clone(&self.is_fetching);
-
-        // Set fetching status
-        *fetch_status.lock().unwrap() = FetchStatus::Fetching;
-        *is_fetching.lock().unwrap() = true;
-
-        std::thread::spawn(move || {
-            let result = Self::fetch_weather_sync(&location);
-
-            match result {
-                Ok(data) => {
-                    info!(
-                        "Weather fetched for {}: {}°C, {}",
-                        data.location, data.temperature, data.description
-                    );
-                    *weather_data.lock().unwrap() = Some(data);
-                    *fetch_status.lock().unwrap() = FetchStatus::Success;
-                }
-                Err(e) => {
-                    error!("Failed to fetch weather: {}", e);
-                    if e.contains("not found") || e.contains("Unknown location") {
-                        *fetch_status.lock().unwrap() = FetchStatus::LocationNotFound;
-                    } else {
-                        *fetch_status.lock().unwrap() = FetchStatus::Error(e);
-                    }
-                }
-            }
-
-            *is_fetching.lock().unwrap() = false;
-        });
+            station: None,
+            alerts_config: WeatherAlertsConfig::default(),
+            alerts: Arc::new(Mutex::new(Vec::new())),
+            is_fetching_alerts: Arc::new(Mutex::new(false)),
+            last_alerts: None,
+            notified_alerts: HashSet::new(),
+        }
     }
 
     /// Enable/disable the module
@@ -243,7 +283,7 @@ impl WeatherModule {
         let fetch_status = Arc::clone(&self.fetch_status);
         let is_fetching = Arc::clone(&self.is_fetching);
 
-        thread::spawn(move || {
+        crate::runtime::spawn(move || {
             let result = Self::fetch_weather_sync(&location);
 
             match result {
@@ -341,6 +381,15 @@ impl WeatherModule {
             .and_then(|v| v.as_str())
             .unwrap_or("");
 
+        let coordinate = |key: &str| {
+            nearest_area
+                .get(key)
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse::<f64>().ok())
+        };
+        let latitude = coordinate("latitude");
+        let longitude = coordinate("longitude");
+
         // Get weather data
         let temp_c = current
             .get("temp_C")
@@ -409,6 +458,20 @@ impl WeatherModule {
             (temp_c, temp_c)
         };
 
+        // Sunrise/sunset come as "06:45 AM" in today's astronomy block
+        let astronomy = weather
+            .and_then(|today| today.get("astronomy"))
+            .and_then(|a| a.as_array())
+            .and_then(|arr| arr.first());
+        let sun_time = |key: &str| {
+            astronomy
+                .and_then(|a| a.get(key))
+                .and_then(|v| v.as_str())
+                .and_then(|s| NaiveTime::parse_from_str(s.trim(), "%I:%M %p").ok())
+        };
+        let sunrise = sun_time("sunrise");
+        let sunset = sun_time("sunset");
+
         // Build multi-day forecast list (use up to 5 days)
         let mut forecasts: Vec<DailyForecast> = Vec::new();
         if let Some(days) = parsed.get("weather").and_then(|w| w.as_array()) {
@@ -474,9 +537,183 @@ impl WeatherModule {
             wind_speed,
             wind_dir,
             forecast: forecasts,
+            sunrise,
+            sunset,
+            country: country.to_string(),
+            latitude,
+            longitude,
         })
     }
 
+    /// Poll alerts in the background when they're due, or the location moved
+    fn poll_alerts(&mut self) {
+        if !self.alerts_config.enabled {
+            self.alerts.lock().unwrap().clear();
+            self.last_alerts = None;
+            return;
+        }
+        let Some((lat, lon, country)) = self
+            .weather_data
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|d| Some((d.latitude?, d.longitude?, d.country.clone())))
+        else {
+            return;
+        };
+        let due = match self.last_alerts {
+            Some((at, last_lat, last_lon)) => at.elapsed() >= ALERT_INTERVAL || last_lat != lat || last_lon != lon,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+
+        let url = if !self.alerts_config.url.trim().is_empty() {
+            self.alerts_config.url.trim().to_string()
+        } else if country == NWS_COUNTRY {
+            NWS_ALERTS_URL.to_string()
+        } else {
+            // No provider for this country without a configured endpoint
+            self.alerts.lock().unwrap().clear();
+            self.last_alerts = Some((Instant::now(), lat, lon));
+            return;
+        };
+        let url = url
+            .replace("{lat}", &format!("{:.4}", lat))
+            .replace("{lon}", &format!("{:.4}", lon));
+
+        {
+            let mut is_fetching = self.is_fetching_alerts.lock().unwrap();
+            if *is_fetching {
+                return;
+            }
+            *is_fetching = true;
+        }
+        self.last_alerts = Some((Instant::now(), lat, lon));
+
+        let alerts = Arc::clone(&self.alerts);
+        let is_fetching = Arc::clone(&self.is_fetching_alerts);
+        crate::runtime::spawn(move || {
+            match Self::fetch_alerts_sync(&url) {
+                Ok(fetched) => {
+                    info!("Weather alerts: {} active", fetched.len());
+                    *alerts.lock().unwrap() = fetched;
+                }
+                // Keep the last known alerts; they drop out as they expire
+                Err(e) => warn!("Failed to fetch weather alerts: {}", e),
+            }
+            *is_fetching.lock().unwrap() = false;
+        });
+    }
+
+    fn fetch_alerts_sync(url: &str) -> Result<Vec<WeatherAlert>, String> {
+        info!("Fetching weather alerts from: {}", url);
+        // The National Weather Service turns away requests without a User-Agent
+        let response = ureq::get(url)
+            .set("User-Agent", "TopBar/1.0")
+            .set("Accept", "application/geo+json, application/json")
+            .timeout(std::time::Duration::from_secs(10))
+            .call()
+            .map_err(|e| format!("HTTP error: {}", e))?;
+        let body = response
+            .into_string()
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        Self::parse_alerts(&body)
+    }
+
+    /// Parse National Weather Service GeoJSON (`features[].properties`) or the
+    /// generic `{"alerts":[...]}` format
+    fn parse_alerts(json: &str) -> Result<Vec<WeatherAlert>, String> {
+        let parsed: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("JSON parse error: {}", e))?;
+        let entries: Vec<&serde_json::Value> = if let Some(features) = parsed.get("features").and_then(|f| f.as_array()) {
+            features.iter().filter_map(|f| f.get("properties")).collect()
+        } else if let Some(alerts) = parsed.get("alerts").and_then(|a| a.as_array()) {
+            alerts.iter().collect()
+        } else {
+            return Err("Missing features or alerts".to_string());
+        };
+
+        let alerts = entries
+            .into_iter()
+            .filter_map(|entry| {
+                let field = |key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+                let event = field("event");
+                if event.is_empty() {
+                    return None;
+                }
+                // NWS alerts carry "ends" for the hazard and "expires" for the message
+                let expires = ["ends", "expires"]
+                    .iter()
+                    .filter_map(|key| entry.get(*key).and_then(|v| v.as_str()))
+                    .find_map(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .map(|t| t.with_timezone(&Local));
+                let mut id = field("id");
+                if id.is_empty() {
+                    id = format!("{}|{}", event, expires.map(|t| t.to_rfc3339()).unwrap_or_default());
+                }
+                let severity = match field("severity") {
+                    s if s.is_empty() => "Unknown".to_string(),
+                    s => s,
+                };
+                Some(WeatherAlert {
+                    id,
+                    headline: match field("headline") {
+                        h if h.is_empty() => event.clone(),
+                        h => h,
+                    },
+                    event,
+                    severity,
+                    description: field("description"),
+                    instruction: field("instruction"),
+                    expires,
+                })
+            })
+            .filter(|alert| alert.is_active())
+            .collect();
+        Ok(alerts)
+    }
+
+    /// Active severe-weather alerts, most severe first
+    pub fn alerts(&self) -> Vec<WeatherAlert> {
+        let mut alerts: Vec<WeatherAlert> = self
+            .alerts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|a| a.is_active())
+            .cloned()
+            .collect();
+        alerts.sort_by_key(|a| std::cmp::Reverse(a.level()));
+        alerts
+    }
+
+    /// Whether any alert is in effect
+    pub fn has_alerts(&self) -> bool {
+        self.alerts.lock().unwrap().iter().any(|a| a.is_active())
+    }
+
+    /// Notify about alerts that weren't seen before
+    fn notify_new_alerts(&mut self, config: &crate::config::Config) {
+        let alerts = self.alerts();
+        let new: Vec<&WeatherAlert> = alerts
+            .iter()
+            .filter(|a| !self.notified_alerts.contains(&a.id))
+            .collect();
+        if !new.is_empty() && self.alerts_config.notify {
+            // Several alerts at once become one notification, led by the most severe
+            let title = match new.len() {
+                1 => new[0].event.clone(),
+                n => format!("{} and {} more", new[0].event, n - 1),
+            };
+            let text = new.iter().map(|a| a.headline.as_str()).collect::<Vec<_>>().join("\n");
+            crate::attention::request(config, AttentionRequest::new("weather", new[0].level(), &title, &text));
+        }
+        // Forget alerts that are gone, so the set doesn't grow
+        self.notified_alerts = alerts.into_iter().map(|a| a.id).collect();
+    }
+
     /// Force an immediate update
     fn force_update(&mut self) {
         if !self.enabled {
@@ -489,38 +726,102 @@ impl WeatherModule {
 
     /// Build the display text
     fn build_display_text(&self) -> String {
+        let station = self.station_readings();
+        let outdoor = station.as_ref().and_then(|r| r.outdoor_temperature);
         let data_guard = self.weather_data.lock().unwrap();
-        let Some(data) = data_guard.as_ref() else {
-            // Show status while loading
-            let status = self.fetch_status.lock().unwrap();
-            return match &*status {
-                FetchStatus::Fetching => "...".to_string(),
-                FetchStatus::LocationNotFound => "Set location".to_string(),
-                FetchStatus::Error(_) => "Error".to_string(),
-                _ => String::new(),
-            };
+
+        let unit_symbol = match self.unit {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
         };
 
         let mut text = String::new();
+        if self.has_alerts() {
+            text.push_str("⚠ ");
+        }
+        match (data_guard.as_ref(), outdoor) {
+            (Some(data), _) => {
+                if self.show_icon {
+                    text.push_str(data.condition.icon());
+                    text.push(' ');
+                }
+                // The station's own measurement beats the provider's estimate
+                let temp = self.convert_temp(outdoor.unwrap_or(data.temperature));
+                text.push_str(&format!("{:.0}{}", temp, unit_symbol));
+            }
+            // Station only (provider unreachable or still loading)
+            (None, Some(temp)) => {
+                text.push_str(&format!("{:.0}{}", self.convert_temp(temp), unit_symbol));
+            }
+            (None, None) if station.is_none() => {
+                // Show status while loading
+                let status = self.fetch_status.lock().unwrap();
+                return match &*status {
+                    FetchStatus::Fetching => "...".to_string(),
+                    FetchStatus::LocationNotFound => "Set location".to_string(),
+                    FetchStatus::Error(_) => "Error".to_string(),
+                    _ => String::new(),
+                };
+            }
+            (None, None) => {}
+        }
 
-        if self.show_icon {
-            text.push_str(data.condition.icon());
-            text.push(' ');
+        let show_indoor = self.station.as_ref().map(|s| s.config().show_indoor).unwrap_or(false);
+        if let Some(indoor) = station.and_then(|r| r.indoor_temperature).filter(|_| show_indoor) {
+            if !text.is_empty() {
+                text.push_str("  ");
+            }
+            text.push_str(&format!("⌂ {:.0}{}", self.convert_temp(indoor), unit_symbol));
         }
 
-        let temp = match self.unit {
-            TemperatureUnit::Celsius => data.temperature,
-            TemperatureUnit::Fahrenheit => data.temperature * 9.0 / 5.0 + 32.0,
-        };
+        text
+    }
 
-        let unit_symbol = match self.unit {
+    /// Keep the station reader in step with the config
+    fn sync_station(&mut self, config: &crate::config::WeatherStationConfig) {
+        if !config.enabled {
+            self.station = None;
+            return;
+        }
+        let current = self.station.as_ref().map(|s| s.config() == config).unwrap_or(false);
+        if !current {
+            self.station = Some(WeatherStation::start(config));
+        }
+    }
+
+    /// Latest local station readings, if a station is configured and reporting
+    pub fn station_readings(&self) -> Option<StationReadings> {
+        self.station.as_ref()?.readings()
+    }
+
+    /// Tooltip/menu lines for the station, labeled indoor and outdoor
+    pub fn station_lines(&self) -> Vec<String> {
+        let Some(readings) = self.station_readings() else {
+            return Vec::new();
+        };
+        let unit = match self.unit {
             TemperatureUnit::Celsius => "°C",
             TemperatureUnit::Fahrenheit => "°F",
         };
+        let describe = |temp: Option<f32>, humidity: Option<f32>| {
+            let mut parts = Vec::new();
+            if let Some(t) = temp {
+                parts.push(format!("{:.1}{}", self.convert_temp(t), unit));
+            }
+            if let Some(h) = humidity {
+                parts.push(format!("{:.0}% humidity", h));
+            }
+            parts.join(", ")
+        };
 
-        text.push_str(&format!("{:.0}{}", temp, unit_symbol));
-
-        text
+        let mut lines = Vec::new();
+        if readings.has_outdoor() {
+            lines.push(format!("Outdoor (station): {}", describe(readings.outdoor_temperature, readings.outdoor_humidity)));
+        }
+        if readings.has_indoor() {
+            lines.push(format!("Indoor (station): {}", describe(readings.indoor_temperature, readings.indoor_humidity)));
+        }
+        lines
     }
 
     /// Convert temperature to display unit
@@ -556,6 +857,31 @@ impl WeatherModule {
         self.weather_data.lock().unwrap().clone()
     }
 
+    /// One-sentence summary of today's weather, e.g. for the morning briefing
+    pub fn summary(&self) -> Option<String> {
+        let data = self.weather_data.lock().unwrap();
+        let data = data.as_ref()?;
+        let unit = match self.unit {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        };
+        Some(format!(
+            "{}, {:.0}{} in {}. High {:.0}{}, low {:.0}{}.",
+            data.description,
+            self.convert_temp(data.temperature), unit,
+            data.location,
+            self.convert_temp(data.high), unit,
+            self.convert_temp(data.low), unit,
+        ))
+    }
+
+    /// Today's (sunrise, sunset) for the current location, once weather has been fetched
+    pub fn sun_times(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let data = self.weather_data.lock().unwrap();
+        let data = data.as_ref()?;
+        Some((data.sunrise?, data.sunset?))
+    }
+
     /// Manually trigger a refresh
     pub fn refresh(&mut self) {
         self.fetch_weather_async();
@@ -581,7 +907,12 @@ impl Module for WeatherModule {
         self.cached_text.clone()
     }
 
-    fn update(&mut self, _config: &crate::config::Config) {
+    fn update(&mut self, config: &crate::config::Config) {
+        self.sync_station(&config.modules.weather.station);
+        self.alerts_config = config.modules.weather.alerts.clone();
+        self.poll_alerts();
+        self.notify_new_alerts(config);
+
         // Update cached text from weather data
         self.cached_text = self.build_display_text();
 
@@ -591,6 +922,13 @@ impl Module for WeatherModule {
         }
     }
 
+    fn force_refresh(&mut self, config: &crate::config::Config) {
+        if crate::modules::is_offline(self, config) {
+            return;
+        }
+        self.refresh();
+    }
+
     fn on_click(&mut self) {
         // Show an in-app forecast popup with upcoming days, fall back to opening wttr.in
         let data_opt = self.weather_data.lock().unwrap().clone();
@@ -629,7 +967,9 @@ impl Module for WeatherModule {
             }
         } else {
             // No data yet - request fetch and open website
-            self.fetch_weather_async();
+            if !crate::modules::offline_mode() {
+                self.fetch_weather_async();
+            }
             let location = if self.location.eq_ignore_ascii_case("auto") {
                 String::new()
             } else {
@@ -642,8 +982,12 @@ impl Module for WeatherModule {
     }
 
     fn tooltip(&self) -> Option<String> {
+        let station_lines = self.station_lines();
         let data_guard = self.weather_data.lock().unwrap();
         let Some(data) = data_guard.as_ref() else {
+            if !station_lines.is_empty() {
+                return Some(station_lines.join("\n"));
+            }
             let status = self.fetch_status.lock().unwrap();
             return match &*status {
                 FetchStatus::Fetching => Some("Fetching weather data...".to_string()),
@@ -658,8 +1002,20 @@ impl Module for WeatherModule {
             TemperatureUnit::Fahrenheit => "°F",
         };
 
+        let station = if station_lines.is_empty() {
+            String::new()
+        } else {
+            format!("\n\n{}", station_lines.join("\n"))
+        };
+
+        let alerts = self
+            .alerts()
+            .iter()
+            .map(|a| format!("\n\n{}\n{}", a.summary(), a.headline))
+            .collect::<String>();
+
         Some(format!(
-            "{}\n{}\n\nTemperature: {:.0}{}\nFeels like: {:.0}{}\nHumidity: {}%\nWind: {:.0} km/h {}\nHigh: {:.0}{} / Low: {:.0}{}",
+            "{}\n{}\n\nTemperature: {:.0}{}\nFeels like: {:.0}{}\nHumidity: {}%\nWind: {:.0} km/h {}\nHigh: {:.0}{} / Low: {:.0}{}{}{}",
             data.location,
             data.description,
             self.convert_temp(data.temperature), unit,
@@ -668,6 +1024,8 @@ impl Module for WeatherModule {
             data.wind_speed, data.wind_dir,
             self.convert_temp(data.high), unit,
             self.convert_temp(data.low), unit,
+            station,
+            alerts,
         ))
     }
 
@@ -682,4 +1040,17 @@ impl Module for WeatherModule {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+
+    fn subscriptions(&self) -> &'static [&'static str] {
+        &[events::NETWORK_CONNECTED]
+    }
+
+    fn on_event(&mut self, _topic: &str, config: &crate::config::Config) {
+        // Back online: don't wait out the interval with stale or missing weather
+        self.force_refresh(config);
+    }
 }
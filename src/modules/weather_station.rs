@@ -0,0 +1,348 @@
+//! Local weather station input for the weather module
+//!
+//! A background thread either polls an HTTP JSON endpoint or subscribes to an MQTT
+//! topic and keeps the latest values in one JSON document. JSON messages are merged
+//! into it; single-number MQTT messages are stored under their topic. The weather
+//! module then picks indoor and outdoor readings out of it by the configured paths.
+//!
+//! The MQTT side is a minimal MQTT 3.1.1 subscriber (QoS 0, plain TCP), which is all
+//! a home broker like Mosquitto needs for this.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::config::{TemperatureUnit, WeatherStationConfig};
+
+/// Wait before reconnecting to a broker or retrying a failed HTTP poll
+const RETRY_DELAY: Duration = Duration::from_secs(15);
+/// MQTT keep-alive announced to the broker; pings go out at half this
+const KEEP_ALIVE_SECS: u16 = 60;
+
+/// Latest values from the station, in Celsius and percent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StationReadings {
+    pub outdoor_temperature: Option<f32>,
+    pub outdoor_humidity: Option<f32>,
+    pub indoor_temperature: Option<f32>,
+    pub indoor_humidity: Option<f32>,
+}
+
+impl StationReadings {
+    pub fn has_outdoor(&self) -> bool {
+        self.outdoor_temperature.is_some() || self.outdoor_humidity.is_some()
+    }
+
+    pub fn has_indoor(&self) -> bool {
+        self.indoor_temperature.is_some() || self.indoor_humidity.is_some()
+    }
+}
+
+/// Received data: merged JSON document and when it last changed
+type Document = Arc<Mutex<(Value, Option<Instant>)>>;
+
+/// A running station reader; dropping it stops the background thread
+pub struct WeatherStation {
+    config: WeatherStationConfig,
+    document: Document,
+    stop: Arc<AtomicBool>,
+}
+
+impl WeatherStation {
+    /// Start reading from the configured source
+    pub fn start(config: &WeatherStationConfig) -> Self {
+        let document: Document = Arc::new(Mutex::new((Value::Object(Default::default()), None)));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let cfg = config.clone();
+        let doc = Arc::clone(&document);
+        let stop_flag = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            if !cfg.mqtt_broker.trim().is_empty() {
+                run_mqtt(&cfg, &doc, &stop_flag);
+            } else if !cfg.http_url.trim().is_empty() {
+                run_http(&cfg, &doc, &stop_flag);
+            } else {
+                warn!("Weather station enabled without an http_url or mqtt_broker");
+            }
+        });
+
+        Self {
+            config: config.clone(),
+            document,
+            stop,
+        }
+    }
+
+    /// Settings this reader was started with
+    pub fn config(&self) -> &WeatherStationConfig {
+        &self.config
+    }
+
+    /// Current readings, or None if nothing arrived yet or the data is too old
+    pub fn readings(&self) -> Option<StationReadings> {
+        let guard = self.document.lock();
+        let (doc, updated) = &*guard;
+        if (*updated)?.elapsed() > Duration::from_secs(self.config.max_age_min.max(1) * 60) {
+            return None;
+        }
+
+        let temperature = |path: &str| {
+            lookup(doc, path).map(|t| match self.config.unit {
+                TemperatureUnit::Celsius => t,
+                TemperatureUnit::Fahrenheit => (t - 32.0) * 5.0 / 9.0,
+            })
+        };
+        let readings = StationReadings {
+            outdoor_temperature: temperature(&self.config.outdoor_temperature),
+            outdoor_humidity: lookup(doc, &self.config.outdoor_humidity),
+            indoor_temperature: temperature(&self.config.indoor_temperature),
+            indoor_humidity: lookup(doc, &self.config.indoor_humidity),
+        };
+        (readings.has_outdoor() || readings.has_indoor()).then_some(readings)
+    }
+}
+
+impl Drop for WeatherStation {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Find a number by exact key (MQTT topics) or dotted path; numeric strings count too
+fn lookup(doc: &Value, path: &str) -> Option<f32> {
+    if path.is_empty() {
+        return None;
+    }
+    let value = doc.get(path).or_else(|| {
+        path.split('.').try_fold(doc, |v, part| match part.parse::<usize>() {
+            Ok(i) if v.is_array() => v.get(i),
+            _ => v.get(part),
+        })
+    })?;
+    match value {
+        Value::Number(n) => n.as_f64().map(|f| f as f32),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Merge a message into the document
+fn merge(document: &Document, topic: Option<&str>, payload: &[u8]) {
+    let Ok(text) = std::str::from_utf8(payload) else {
+        return;
+    };
+    let mut guard = document.lock();
+    let (doc, updated) = &mut *guard;
+    let Some(root) = doc.as_object_mut() else {
+        return;
+    };
+    let value = serde_json::from_str::<Value>(text.trim())
+        .unwrap_or_else(|_| Value::String(text.trim().to_string()));
+    match (value, topic) {
+        (Value::Object(map), _) => root.extend(map),
+        // Single values are only meaningful under their topic
+        (value, Some(topic)) => {
+            root.insert(topic.to_string(), value);
+        }
+        (_, None) => return,
+    }
+    *updated = Some(Instant::now());
+}
+
+/// Sleep in short steps so a stop request is noticed quickly; returns false if stopped
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) -> bool {
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+    !stop.load(Ordering::SeqCst)
+}
+
+fn run_http(config: &WeatherStationConfig, document: &Document, stop: &AtomicBool) {
+    info!("Polling weather station at {}", config.http_url);
+    while !stop.load(Ordering::SeqCst) {
+        let result = ureq::get(&config.http_url)
+            .set("User-Agent", "TopBar/1.0")
+            .timeout(Duration::from_secs(10))
+            .call()
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.into_string().map_err(|e| e.to_string()));
+        let delay = match result {
+            Ok(body) => {
+                merge(document, None, body.as_bytes());
+                Duration::from_secs(config.poll_interval_secs.max(5))
+            }
+            Err(e) => {
+                warn!("Weather station request failed: {}", e);
+                RETRY_DELAY.max(Duration::from_secs(config.poll_interval_secs))
+            }
+        };
+        if !sleep_unless_stopped(delay, stop) {
+            break;
+        }
+    }
+}
+
+fn run_mqtt(config: &WeatherStationConfig, document: &Document, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        if let Err(e) = mqtt_session(config, document, stop) {
+            warn!("Weather station MQTT connection to {} failed: {}", config.mqtt_broker, e);
+        }
+        if !sleep_unless_stopped(RETRY_DELAY, stop) {
+            break;
+        }
+    }
+}
+
+/// One broker connection: connect, subscribe, then read messages until an error or stop
+fn mqtt_session(config: &WeatherStationConfig, document: &Document, stop: &AtomicBool) -> std::io::Result<()> {
+    let broker = config.mqtt_broker.trim();
+    let address = if broker.contains(':') { broker.to_string() } else { format!("{}:1883", broker) };
+    let mut stream = TcpStream::connect(&address)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    stream.write_all(&connect_packet(config))?;
+    let (kind, body) = read_packet(&mut stream, stop)?;
+    if kind >> 4 != 2 || body.get(1) != Some(&0) {
+        return Err(std::io::Error::other(format!("connection refused (code {:?})", body.get(1))));
+    }
+    // Short timeouts from here on, so pings and stop requests aren't held up by a quiet topic
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+    stream.write_all(&subscribe_packet(&config.mqtt_topic))?;
+    info!("Subscribed to {} on {}", config.mqtt_topic, address);
+
+    let mut last_ping = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        if last_ping.elapsed() >= Duration::from_secs(KEEP_ALIVE_SECS as u64 / 2) {
+            stream.write_all(&[0xC0, 0x00])?;
+            last_ping = Instant::now();
+        }
+        let (kind, body) = match read_packet(&mut stream, stop) {
+            Ok(packet) => packet,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        };
+        // PUBLISH: topic length, topic, [packet id if QoS > 0], payload
+        if kind >> 4 == 3 && body.len() >= 2 {
+            let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+            let Some(topic) = body.get(2..2 + topic_len) else {
+                continue;
+            };
+            let qos = (kind >> 1) & 0x03;
+            let payload_start = 2 + topic_len + if qos > 0 { 2 } else { 0 };
+            if let Some(payload) = body.get(payload_start..) {
+                merge(document, Some(&String::from_utf8_lossy(topic)), payload);
+            }
+        }
+    }
+    // DISCONNECT
+    let _ = stream.write_all(&[0xE0, 0x00]);
+    Ok(())
+}
+
+/// Read one packet; times out (WouldBlock/TimedOut) only while waiting for its first byte
+fn read_packet(stream: &mut TcpStream, stop: &AtomicBool) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header)?;
+
+    let mut remaining = 0usize;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        read_fully(stream, &mut byte, stop)?;
+        remaining |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(std::io::Error::other("malformed packet length"));
+        }
+    }
+
+    let mut body = vec![0u8; remaining];
+    read_fully(stream, &mut body, stop)?;
+    Ok((header[0], body))
+}
+
+/// read_exact that keeps waiting through read timeouts (until stopped)
+fn read_fully(stream: &mut TcpStream, mut buf: &mut [u8], stop: &AtomicBool) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match stream.read(buf) {
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf = &mut buf[n..],
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                if stop.load(Ordering::SeqCst) {
+                    return Err(std::io::ErrorKind::Interrupted.into());
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Fixed header with the variable-length "remaining length" field
+fn packet(kind: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![kind];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend(body);
+    out
+}
+
+fn connect_packet(config: &WeatherStationConfig) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_string(&mut body, "MQTT");
+    body.push(4); // protocol level 3.1.1
+    let mut flags = 0x02; // clean session
+    if !config.mqtt_username.is_empty() {
+        flags |= 0x80;
+        if !config.mqtt_password.is_empty() {
+            flags |= 0x40;
+        }
+    }
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    push_string(&mut body, &format!("topbar-{}", std::process::id()));
+    if !config.mqtt_username.is_empty() {
+        push_string(&mut body, &config.mqtt_username);
+        if !config.mqtt_password.is_empty() {
+            push_string(&mut body, &config.mqtt_password);
+        }
+    }
+    packet(0x10, body)
+}
+
+fn subscribe_packet(topic: &str) -> Vec<u8> {
+    let mut body = vec![0x00, 0x01]; // packet id
+    push_string(&mut body, topic);
+    body.push(0); // QoS 0
+    packet(0x82, body)
+}
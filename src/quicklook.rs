@@ -1,6 +1,10 @@
 //! QuickLook - macOS-style file preview with spacebar
 //!
 //! Press spacebar when a file is selected in Explorer to get a quick preview.
+//! With several files selected the preview is a gallery: the arrow keys step
+//! through the selection, reloading the same window in place.
+//! Quick search uses the same preview for its results through `preview_beside`,
+//! which opens the window next to the search popup without taking focus.
 
 use anyhow::Result;
 use log::{debug, info};
@@ -14,6 +18,7 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{SetFocus, VK_SPACE};
 use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
 use windows::Win32::UI::WindowsAndMessaging::*;
 
+use crate::documents::{self, Block};
 use crate::theme::Color;
 
 // Use the `image` crate for decoding common image formats
@@ -34,16 +39,24 @@ static PREVIEW_HWND_RAW: AtomicIsize = AtomicIsize::new(0);
 enum PreviewContent {
     Image(PathBuf),
     Text(String),
+    /// Markdown, Word or Excel rendered with formatting
+    Document(Vec<Block>),
     Unsupported(String), // Extension name
 }
 
 /// QuickLook state
 struct QuickLookState {
     file_path: PathBuf,
+    /// Files the arrow keys step through (Explorer's selection); just `file_path` otherwise
+    gallery: Vec<PathBuf>,
+    /// Position of `file_path` in `gallery`
+    gallery_index: usize,
     content: PreviewContent,
     file_icon: Option<HICON>,
     scroll_offset: i32,
     image_data: Option<ImageData>,
+    /// Shown beside another popup (quick search) rather than for Explorer's selection
+    attached: bool,
 }
 
 /// Loaded image data for rendering
@@ -107,37 +120,38 @@ unsafe extern "system" fn keyboard_hook_proc(
         if kb_struct.vkCode == VK_SPACE.0 as u32 {
             // Check if Explorer or Desktop has focus
             if is_explorer_focused() {
-                // Get selected file
-                if let Some(file_path) = get_selected_file() {
-                    debug!("QuickLook: Spacebar pressed on file: {:?}", file_path);
+                // Get selected files
+                let files = get_selected_files();
+                if !files.is_empty() {
+                    debug!("QuickLook: Spacebar pressed on {} file(s): {:?}", files.len(), files[0]);
 
                     // Determine current preview state
                     let current_hwnd_raw = PREVIEW_HWND_RAW.load(Ordering::SeqCst);
                     if current_hwnd_raw != 0 {
-                        // There's an existing preview - check its file
+                        // There's an existing preview - check its selection
                         let hwnd = HWND(current_hwnd_raw as *mut std::ffi::c_void);
                         if let Some(state) = get_preview_state(hwnd) {
-                            if state.file_path == file_path {
-                                // Same file selected - toggle off
+                            if state.gallery == files {
+                                // Same selection - toggle off
                                 close_preview_window();
                                 return LRESULT(1); // consume
                             } else {
-                                // Different file selected - replace preview with new file
+                                // Different selection - replace preview with the new one
                                 close_preview_window();
-                                if show_preview(&file_path).is_ok() {
+                                if show_preview(files).is_ok() {
                                     return LRESULT(1); // consume
                                 }
                             }
                         } else {
                             // Couldn't read state - just recreate
                             close_preview_window();
-                            if show_preview(&file_path).is_ok() {
+                            if show_preview(files).is_ok() {
                                 return LRESULT(1);
                             }
                         }
                     } else {
                         // No preview open - open new one
-                        if show_preview(&file_path).is_ok() {
+                        if show_preview(files).is_ok() {
                             return LRESULT(1);
                         }
                     }
@@ -217,15 +231,16 @@ unsafe extern "system" fn check_shell_view_callback(hwnd: HWND, lparam: LPARAM)
     BOOL(1) // Continue enumeration
 }
 
-/// Get the currently selected file in Explorer using UI Automation
-fn get_selected_file() -> Option<PathBuf> {
+/// Get the files currently selected in Explorer or on the Desktop, in selection order
+fn get_selected_files() -> Vec<PathBuf> {
     // Try shell-based Explorer selection first, then desktop listview as a fallback
-    if let Some(p) = get_selected_file_via_shell() { return Some(p); }
-    get_desktop_selection()
+    let files = get_selected_files_via_shell().unwrap_or_default();
+    if !files.is_empty() { return files; }
+    get_desktop_selection().unwrap_or_default()
 }
 
-/// Get selected file via Shell COM interfaces
-fn get_selected_file_via_shell() -> Option<PathBuf> {
+/// Get selected files via Shell COM interfaces
+fn get_selected_files_via_shell() -> Option<Vec<PathBuf>> {
     unsafe {
         use windows::Win32::System::Com::*;
         use windows::Win32::UI::Shell::*;
@@ -269,16 +284,15 @@ fn get_selected_file_via_shell() -> Option<PathBuf> {
                             // Get selected items
                             if let Ok(selected_items) = folder_view.SelectedItems() {
                                 let item_count = selected_items.Count().ok()?;
-                                if item_count > 0 {
-                                    // Get first selected item
-                                    let variant_zero = windows::core::VARIANT::from(0i32);
-                                    // Item expects &VARIANT - pass reference
-                                    if let Ok(item) = selected_items.Item(&variant_zero) {
-                                        if let Ok(path) = item.Path() {
-                                            return Some(PathBuf::from(path.to_string()));
-                                        }
-                                    }
-                                }
+                                let files = (0..item_count)
+                                    .filter_map(|i| {
+                                        // Item expects &VARIANT - pass reference
+                                        let index = windows::core::VARIANT::from(i);
+                                        let path = selected_items.Item(&index).ok()?.Path().ok()?;
+                                        Some(PathBuf::from(path.to_string()))
+                                    })
+                                    .collect();
+                                return Some(files);
                             }
                         }
                     }
@@ -290,12 +304,73 @@ fn get_selected_file_via_shell() -> Option<PathBuf> {
     }
 }
 
-/// Get the selected file on the Desktop
-fn get_desktop_selection() -> Option<PathBuf> {
-    // Disabled for safety: direct ListView text retrieval across processes can
-    // crash Explorer when done incorrectly. We'll implement a safe UIA-based
-    // method later. For now return None so Desktop preview won't attempt unsafe reads.
-    None
+/// Get the selected files on the Desktop
+///
+/// The desktop's icon list isn't in `IShellWindows`, so its selection is read
+/// through UI Automation's selection pattern rather than by poking the ListView
+/// in Explorer's process. UIA only gives the item's display name, which is
+/// matched against the user and public Desktop folders.
+fn get_desktop_selection() -> Option<Vec<PathBuf>> {
+    unsafe {
+        use windows::Win32::System::Com::*;
+        use windows::Win32::UI::Accessibility::*;
+
+        let list_view = find_desktop_list_view()?;
+
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let element = automation.ElementFromHandle(list_view).ok()?;
+        let selection: IUIAutomationSelectionPattern =
+            element.GetCurrentPatternAs(UIA_SelectionPatternId).ok()?;
+        let selected = selection.GetCurrentSelection().ok()?;
+        let files = (0..selected.Length().ok()?)
+            .filter_map(|i| {
+                let name = selected.GetElement(i).ok()?.CurrentName().ok()?.to_string();
+                resolve_desktop_item(&name)
+            })
+            .collect();
+        Some(files)
+    }
+}
+
+/// The desktop's icon ListView, when the desktop has focus
+unsafe fn find_desktop_list_view() -> Option<HWND> {
+    let foreground = GetForegroundWindow();
+    let mut class_name = [0u16; 64];
+    let len = GetClassNameW(foreground, &mut class_name) as usize;
+    let class = String::from_utf16_lossy(&class_name[..len]);
+    if class != "Progman" && class != "WorkerW" {
+        return None;
+    }
+
+    let def_view = to_wide("SHELLDLL_DefView");
+    let list_view = to_wide("SysListView32");
+    // The icons usually live under Progman, but under a WorkerW once the wallpaper has animated
+    let mut hosts = vec![foreground];
+    if let Ok(progman) = FindWindowW(PCWSTR(to_wide("Progman").as_ptr()), PCWSTR::null()) {
+        hosts.push(progman);
+    }
+    hosts.into_iter().find_map(|host| {
+        let view = FindWindowExW(host, None, PCWSTR(def_view.as_ptr()), PCWSTR::null()).ok()?;
+        FindWindowExW(view, None, PCWSTR(list_view.as_ptr()), PCWSTR::null()).ok()
+    })
+}
+
+/// Find a desktop icon's file from its display name
+///
+/// Explorer may hide extensions (and always hides `.lnk`), so a name also
+/// matches a file with that stem. Virtual items like This PC have no file.
+fn resolve_desktop_item(name: &str) -> Option<PathBuf> {
+    let public = std::env::var_os("PUBLIC").map(|p| PathBuf::from(p).join("Desktop"));
+    let folders: Vec<PathBuf> = dirs::desktop_dir().into_iter().chain(public).collect();
+
+    let exact = folders.iter().map(|f| f.join(name)).find(|p| p.exists());
+    exact.or_else(|| {
+        folders.iter().find_map(|folder| {
+            std::fs::read_dir(folder).ok()?.flatten().map(|e| e.path()).find(|path| {
+                path.file_stem().map(|stem| stem.to_string_lossy() == name).unwrap_or(false)
+            })
+        })
+    })
 }
 
 /// Check if hwnd1 is an ancestor of hwnd2
@@ -312,8 +387,42 @@ fn is_ancestor(hwnd1: HWND, hwnd2: HWND) -> bool {
     }
 }
 
-/// Show the preview window for a file
-fn show_preview(file_path: &Path) -> Result<()> {
+/// Preview a file next to `anchor` without taking focus from it, or switch an
+/// open preview to the file
+pub fn preview_beside(file_path: &Path, anchor: HWND) -> Result<()> {
+    let current = HWND(PREVIEW_HWND_RAW.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if !current.0.is_null() {
+        if get_preview_state(current).map(|s| s.attached).unwrap_or(false) {
+            set_gallery(current, vec![file_path.to_path_buf()]);
+            if let Some(state) = get_preview_state(current) {
+                unsafe { place_beside(current, anchor, calculate_window_size(&state.content)) };
+            }
+            return Ok(());
+        }
+        close_preview_window();
+    }
+    open_preview(vec![file_path.to_path_buf()], Some(anchor))
+}
+
+/// Close the preview window if one is open
+pub fn close_preview() {
+    close_preview_window();
+}
+
+/// Whether a preview window is open
+pub fn is_preview_open() -> bool {
+    PREVIEW_HWND_RAW.load(Ordering::SeqCst) != 0
+}
+
+/// Show the preview window for the selected files, starting with the first
+fn show_preview(files: Vec<PathBuf>) -> Result<()> {
+    open_preview(files, None)
+}
+
+/// Create the preview window, centered and focused, or beside `anchor` and inactive
+fn open_preview(gallery: Vec<PathBuf>, anchor: Option<HWND>) -> Result<()> {
+    let file_path = gallery.first().ok_or_else(|| anyhow::anyhow!("Nothing to preview"))?.as_path();
+
     // Determine preview type
     let content = determine_preview_content(file_path)?;
     
@@ -330,11 +439,17 @@ fn show_preview(file_path: &Path) -> Result<()> {
         let class = to_wide(QUICKLOOK_CLASS);
         let hinstance = GetModuleHandleW(None)?;
 
+        let ex_style = if anchor.is_some() {
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE
+        } else {
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW
+        };
+
         CreateWindowExW(
-            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            ex_style,
             PCWSTR(class.as_ptr()),
             PCWSTR::null(),
-            WS_POPUP | WS_VISIBLE,
+            if anchor.is_some() { WS_POPUP } else { WS_POPUP | WS_VISIBLE },
             0,
             0,
             win_width,
@@ -349,15 +464,22 @@ fn show_preview(file_path: &Path) -> Result<()> {
     // Store the window handle
     PREVIEW_HWND_RAW.store(hwnd.0 as isize, Ordering::SeqCst);
 
-    // Center on screen
-    unsafe {
-        let screen_w = GetSystemMetrics(SM_CXSCREEN);
-        let screen_h = GetSystemMetrics(SM_CYSCREEN);
-        let x = (screen_w - win_width) / 2;
-        let y = (screen_h - win_height) / 2;
-        SetWindowPos(hwnd, HWND_TOPMOST, x, y, win_width, win_height, SWP_SHOWWINDOW).ok();
-        let _ = SetForegroundWindow(hwnd);
-        let _ = SetFocus(hwnd);
+    if let Some(anchor) = anchor {
+        unsafe {
+            place_beside(hwnd, anchor, (win_width, win_height));
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        }
+    } else {
+        // Center on screen
+        unsafe {
+            let screen_w = GetSystemMetrics(SM_CXSCREEN);
+            let screen_h = GetSystemMetrics(SM_CYSCREEN);
+            let x = (screen_w - win_width) / 2;
+            let y = (screen_h - win_height) / 2;
+            SetWindowPos(hwnd, HWND_TOPMOST, x, y, win_width, win_height, SWP_SHOWWINDOW).ok();
+            let _ = SetForegroundWindow(hwnd);
+            let _ = SetFocus(hwnd);
+        }
     }
 
     // Get file icon
@@ -373,18 +495,21 @@ fn show_preview(file_path: &Path) -> Result<()> {
     // Store state
     let state = Box::new(QuickLookState {
         file_path: file_path.to_path_buf(),
+        gallery: gallery.clone(),
+        gallery_index: 0,
         content,
         file_icon,
         scroll_offset: 0,
         image_data,
+        attached: anchor.is_some(),
     });
     
     unsafe {
         SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize);
     }
 
-    // Start a background poller to update preview when selection changes
-    {
+    // Start a background poller to update preview when Explorer's selection changes
+    if anchor.is_none() {
         let hwnd_poll = hwnd;
         std::thread::spawn(move || {
             // Poll for selection changes while preview window is open
@@ -392,16 +517,17 @@ fn show_preview(file_path: &Path) -> Result<()> {
                 std::thread::sleep(std::time::Duration::from_millis(250));
                 if PREVIEW_HWND_RAW.load(Ordering::SeqCst) == 0 { break; }
 
-                if let Some(current) = get_selected_file() {
+                let current = get_selected_files();
+                if !current.is_empty() {
                     // Compare to current preview state
                     if PREVIEW_HWND_RAW.load(Ordering::SeqCst) != 0 {
                         let h = HWND(PREVIEW_HWND_RAW.load(Ordering::SeqCst) as *mut std::ffi::c_void);
                         if h.0.is_null() { break; }
 
                         if let Some(st) = get_preview_state(h) {
-                            if st.file_path != current {
+                            if st.gallery != current {
                                 // Replace preview content for new selection
-                                reload_preview_for_hwnd(h, &current);
+                                set_gallery(h, current);
                             }
                         }
                     }
@@ -413,8 +539,39 @@ fn show_preview(file_path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Replace the preview contents in an existing preview window
-fn reload_preview_for_hwnd(hwnd: HWND, file_path: &Path) {
+/// Browse a new set of files in an existing preview window, starting with the first
+fn set_gallery(hwnd: HWND, gallery: Vec<PathBuf>) {
+    let Some(first) = gallery.first() else {
+        return;
+    };
+    if reload_preview_for_hwnd(hwnd, first) {
+        if let Some(s) = get_preview_state_mut(hwnd) {
+            s.gallery = gallery;
+            s.gallery_index = 0;
+        }
+    }
+}
+
+/// Move through the gallery by `step` files, wrapping around at either end
+fn step_gallery(hwnd: HWND, step: isize) {
+    let Some(state) = get_preview_state(hwnd) else {
+        return;
+    };
+    let count = state.gallery.len();
+    if count < 2 {
+        return;
+    }
+    let index = (state.gallery_index as isize + step).rem_euclid(count as isize) as usize;
+    let path = state.gallery[index].clone();
+    if reload_preview_for_hwnd(hwnd, &path) {
+        if let Some(s) = get_preview_state_mut(hwnd) {
+            s.gallery_index = index;
+        }
+    }
+}
+
+/// Replace the preview contents in an existing preview window; false if the file can't be read
+fn reload_preview_for_hwnd(hwnd: HWND, file_path: &Path) -> bool {
     // Load new content and update the window state in-place
     if let Ok(content) = determine_preview_content(file_path) {
         // Load resources before mutating state so we don't lose previous ones on error
@@ -441,8 +598,31 @@ fn reload_preview_for_hwnd(hwnd: HWND, file_path: &Path) {
 
             // Request redraw
             unsafe { let _ = InvalidateRect(hwnd, None, false); }
+            return true;
         }
     }
+    false
+}
+
+/// Put the preview below `anchor`, shrunk to fit the rest of its monitor
+unsafe fn place_beside(hwnd: HWND, anchor: HWND, (width, height): (i32, i32)) {
+    let mut anchor_rect = RECT::default();
+    let _ = GetWindowRect(anchor, &mut anchor_rect);
+    let monitor = MonitorFromWindow(anchor, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcWork;
+
+    let gap = 8;
+    let top = anchor_rect.bottom + gap;
+    let width = width.min(area.right - area.left - gap * 2);
+    let height = height.min(area.bottom - top - gap).max(120);
+    let center = (anchor_rect.left + anchor_rect.right) / 2;
+    let x = (center - width / 2).clamp(area.left + gap, area.right - gap - width);
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, top, width, height, SWP_NOACTIVATE);
 }
 
 /// Determine what kind of preview to show
@@ -459,9 +639,27 @@ fn determine_preview_content(file_path: &Path) -> Result<PreviewContent> {
         return Ok(PreviewContent::Image(file_path.to_path_buf()));
     }
 
+    // Documents rendered with formatting
+    let document = match extension.as_str() {
+        "md" | "markdown" => Some(
+            std::fs::read_to_string(file_path)
+                .map(|text| documents::parse_markdown(&text))
+                .map_err(Into::into),
+        ),
+        "docx" => Some(documents::read_docx(file_path)),
+        "xlsx" => Some(documents::read_xlsx(file_path)),
+        _ => None,
+    };
+    if let Some(document) = document {
+        return Ok(match document {
+            Ok(blocks) => PreviewContent::Document(blocks),
+            Err(e) => PreviewContent::Text(format!("[Unable to read document: {}]", e)),
+        });
+    }
+
     // Text extensions
     let text_exts = [
-        "txt", "md", "rs", "py", "js", "ts", "jsx", "tsx", "json", "xml", "html", "htm",
+        "txt", "rs", "py", "js", "ts", "jsx", "tsx", "json", "xml", "html", "htm",
         "css", "scss", "less", "yaml", "yml", "toml", "ini", "cfg", "conf", "log",
         "sh", "bash", "zsh", "ps1", "bat", "cmd", "c", "cpp", "h", "hpp", "java",
         "go", "rb", "php", "sql", "gitignore", "dockerignore", "env", "vue", "svelte",
@@ -495,6 +693,7 @@ fn calculate_window_size(content: &PreviewContent) -> (i32, i32) {
     match content {
         PreviewContent::Image(_) => (800, 600),
         PreviewContent::Text(_) => (700, 500),
+        PreviewContent::Document(_) => (760, 560),
         PreviewContent::Unsupported(_) => (400, 200),
     }
 }
@@ -632,6 +831,12 @@ unsafe extern "system" fn preview_wnd_proc(
                 0x20 => { // Space - close preview
                     close_preview_window();
                 }
+                0x25 => { // Left arrow - previous selected file
+                    step_gallery(hwnd, -1);
+                }
+                0x27 => { // Right arrow - next selected file
+                    step_gallery(hwnd, 1);
+                }
                 0x26 => { // Up arrow - scroll up
                     if let Some(state) = get_preview_state_mut(hwnd) {
                         state.scroll_offset = (state.scroll_offset - 30).max(0);
@@ -679,6 +884,15 @@ unsafe extern "system" fn preview_wnd_proc(
             LRESULT(0)
         }
 
+        WM_MOUSEACTIVATE => {
+            // A preview beside quick search must leave the focus there, or the search closes
+            if get_preview_state(hwnd).map(|s| s.attached).unwrap_or(false) {
+                LRESULT(MA_NOACTIVATE as isize)
+            } else {
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+        }
+
         WM_KILLFOCUS => {
             // Close when losing focus
             close_preview_window();
@@ -771,6 +985,17 @@ unsafe fn paint_preview(hdc: HDC, hwnd: HWND, state: &QuickLookState) {
     let path_wide: Vec<u16> = path_str.encode_utf16().chain(std::iter::once(0)).collect();
     let _ = TextOutW(hdc, 52, 28, &path_wide[..path_wide.len() - 1]);
 
+    // Position in the gallery, right-aligned in the header
+    if state.gallery.len() > 1 {
+        let _ = SelectObject(hdc, title_font);
+        SetTextColor(hdc, Color::rgb(160, 160, 165).colorref());
+        let counter = format!("{} / {}", state.gallery_index + 1, state.gallery.len());
+        let counter_wide: Vec<u16> = counter.encode_utf16().collect();
+        let mut size = windows::Win32::Foundation::SIZE::default();
+        let _ = GetTextExtentPoint32W(hdc, &counter_wide, &mut size);
+        let _ = TextOutW(hdc, width - 16 - size.cx, 16, &counter_wide);
+    }
+
     let _ = SelectObject(hdc, old_font);
     let _ = DeleteObject(title_font);
     let _ = DeleteObject(path_font);
@@ -791,6 +1016,9 @@ unsafe fn paint_preview(hdc: HDC, hwnd: HWND, state: &QuickLookState) {
         PreviewContent::Text(text) => {
             paint_text_preview(hdc, &content_rect, text, state.scroll_offset, text_color);
         }
+        PreviewContent::Document(blocks) => {
+            paint_document_preview(hdc, &content_rect, blocks, state.scroll_offset, text_color, bg_color, accent_color);
+        }
         PreviewContent::Unsupported(ext) => {
             paint_unsupported(hdc, &content_rect, ext, text_color);
         }
@@ -805,7 +1033,11 @@ unsafe fn paint_preview(hdc: HDC, hwnd: HWND, state: &QuickLookState) {
     let _ = SelectObject(hdc, footer_font);
     SetTextColor(hdc, Color::rgb(120, 120, 125).colorref());
     
-    let hint = "Press Space/Esc to close • Enter to open • Scroll to navigate";
+    let hint = if state.gallery.len() > 1 {
+        "Press Space/Esc to close • Enter to open • ←/→ for other files • Scroll to navigate"
+    } else {
+        "Press Space/Esc to close • Enter to open • Scroll to navigate"
+    };
     let hint_wide: Vec<u16> = hint.encode_utf16().chain(std::iter::once(0)).collect();
     let _ = TextOutW(hdc, 16, height - 24, &hint_wide[..hint_wide.len() - 1]);
     
@@ -905,6 +1137,279 @@ unsafe fn paint_text_preview(hdc: HDC, rect: &RECT, text: &str, scroll_offset: i
     let _ = DeleteObject(font);
 }
 
+/// Colors for document previews, derived from the preview's theme
+struct DocumentColors {
+    text: Color,
+    muted: Color,
+    accent: Color,
+    /// Code block and table header background
+    surface: Color,
+    /// Rules and table grid lines
+    line: Color,
+}
+
+/// Fonts for document previews, created on first use and deleted on drop
+struct DocumentFonts(Vec<((i32, bool, bool, bool), HFONT)>);
+
+impl DocumentFonts {
+    unsafe fn get(&mut self, height: i32, bold: bool, italic: bool, mono: bool) -> HFONT {
+        let key = (height, bold, italic, mono);
+        if let Some((_, font)) = self.0.iter().find(|(k, _)| *k == key) {
+            return *font;
+        }
+        let weight = if bold { FW_SEMIBOLD.0 } else { FW_NORMAL.0 };
+        let face = if mono { "Consolas" } else { "Segoe UI" };
+        let font = CreateFontW(
+            height, 0, 0, 0, weight as i32, italic as u32, 0, 0,
+            DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
+            PCWSTR(to_wide(face).as_ptr()),
+        );
+        self.0.push((key, font));
+        font
+    }
+}
+
+impl Drop for DocumentFonts {
+    fn drop(&mut self) {
+        for (_, font) in &self.0 {
+            unsafe {
+                let _ = DeleteObject(*font);
+            }
+        }
+    }
+}
+
+/// Paint a formatted document (Markdown, Word or Excel)
+unsafe fn paint_document_preview(
+    hdc: HDC,
+    rect: &RECT,
+    blocks: &[Block],
+    scroll_offset: i32,
+    text_color: Color,
+    bg_color: Color,
+    accent_color: Color,
+) {
+    let colors = DocumentColors {
+        text: text_color,
+        muted: text_color.blend(&bg_color, 0.4),
+        accent: accent_color,
+        surface: bg_color.blend(&text_color, 0.07),
+        line: bg_color.blend(&text_color, 0.2),
+    };
+    let mut fonts = DocumentFonts(Vec::new());
+    let old_font = SelectObject(hdc, fonts.get(14, false, false, false));
+
+    let clip_rgn = CreateRectRgn(rect.left, rect.top, rect.right, rect.bottom);
+    SelectClipRgn(hdc, clip_rgn);
+
+    let (left, right) = (rect.left, rect.right);
+    let mut y = rect.top - scroll_offset;
+
+    for block in blocks {
+        // Stop if we're way past the visible area
+        if y > rect.bottom + 500 {
+            break;
+        }
+        match block {
+            Block::Heading(level, spans) => {
+                let size = match level {
+                    1 => 26,
+                    2 => 21,
+                    3 => 18,
+                    _ => 15,
+                };
+                y += if *level <= 2 { 10 } else { 6 };
+                y = draw_spans(hdc, &mut fonts, spans, size, true, colors.text, &colors, left, right, y, size + 6, rect);
+                if *level <= 2 {
+                    fill_solid(hdc, RECT { left, top: y + 2, right, bottom: y + 3 }, colors.line);
+                    y += 8;
+                } else {
+                    y += 4;
+                }
+            }
+            Block::Paragraph(spans) => {
+                y = draw_spans(hdc, &mut fonts, spans, 14, false, colors.text, &colors, left, right, y, 20, rect) + 8;
+            }
+            Block::ListItem { marker, depth, spans } => {
+                let indent = left + 8 + *depth as i32 * 18;
+                SelectObject(hdc, fonts.get(14, false, false, false));
+                SetTextColor(hdc, colors.muted.colorref());
+                let marker_wide: Vec<u16> = marker.encode_utf16().collect();
+                let _ = TextOutW(hdc, indent, y, &marker_wide);
+                let text_left = indent + if marker.len() > 2 { 26 } else { 16 };
+                y = draw_spans(hdc, &mut fonts, spans, 14, false, colors.text, &colors, text_left, right, y, 20, rect) + 2;
+            }
+            Block::Quote(spans) => {
+                let top = y;
+                y = draw_spans(hdc, &mut fonts, spans, 14, false, colors.muted, &colors, left + 14, right, y, 20, rect);
+                fill_solid(hdc, RECT { left, top, right: left + 3, bottom: y }, colors.accent);
+                y += 8;
+            }
+            Block::Code(code) => {
+                let line_height = 16;
+                let lines: Vec<&str> = code.lines().collect();
+                let height = lines.len().max(1) as i32 * line_height + 16;
+                fill_solid(hdc, RECT { left, top: y, right, bottom: y + height }, colors.surface);
+                SelectObject(hdc, fonts.get(13, false, false, true));
+                SetTextColor(hdc, colors.text.colorref());
+                for (i, line) in lines.iter().enumerate() {
+                    let line_y = y + 8 + i as i32 * line_height;
+                    if line_y + line_height > rect.top && line_y < rect.bottom {
+                        let line_wide: Vec<u16> = line.replace('\t', "    ").encode_utf16().collect();
+                        let _ = TextOutW(hdc, left + 10, line_y, &line_wide);
+                    }
+                }
+                y += height + 10;
+            }
+            Block::Rule => {
+                y += 6;
+                fill_solid(hdc, RECT { left, top: y, right, bottom: y + 1 }, colors.line);
+                y += 12;
+            }
+            Block::Table { rows, header } => {
+                y = paint_table(hdc, &mut fonts, rows, *header, left, right, y, rect, &colors) + 10;
+            }
+        }
+    }
+
+    // Reset clipping
+    SelectClipRgn(hdc, None);
+    let _ = DeleteObject(clip_rgn);
+
+    let _ = SelectObject(hdc, old_font);
+}
+
+/// Draw spans word-wrapped between `left` and `right`, returning the y below the last line
+#[allow(clippy::too_many_arguments)]
+unsafe fn draw_spans(
+    hdc: HDC,
+    fonts: &mut DocumentFonts,
+    spans: &[documents::Span],
+    size: i32,
+    bold: bool,
+    color: Color,
+    colors: &DocumentColors,
+    left: i32,
+    right: i32,
+    mut y: i32,
+    line_height: i32,
+    clip: &RECT,
+) -> i32 {
+    let mut x = left;
+    for span in spans {
+        let font = fonts.get(if span.code { size - 1 } else { size }, bold || span.bold, span.italic, span.code);
+        SelectObject(hdc, font);
+        SetTextColor(hdc, if span.code { colors.accent } else { color }.colorref());
+
+        for piece in span.text.split_inclusive(char::is_whitespace) {
+            let piece_wide: Vec<u16> = piece.replace('\t', "    ").encode_utf16().collect();
+            let mut size = windows::Win32::Foundation::SIZE::default();
+            let _ = GetTextExtentPoint32W(hdc, &piece_wide, &mut size);
+            if x + size.cx > right && x > left {
+                x = left;
+                y += line_height;
+                if piece.trim().is_empty() {
+                    continue;
+                }
+            }
+            if y + line_height > clip.top && y < clip.bottom {
+                let _ = TextOutW(hdc, x, y, &piece_wide);
+            }
+            x += size.cx;
+        }
+    }
+    y + line_height
+}
+
+/// Draw a table with a grid, sizing columns to their widest cell
+#[allow(clippy::too_many_arguments)]
+unsafe fn paint_table(
+    hdc: HDC,
+    fonts: &mut DocumentFonts,
+    rows: &[Vec<String>],
+    header: bool,
+    left: i32,
+    right: i32,
+    y: i32,
+    clip: &RECT,
+    colors: &DocumentColors,
+) -> i32 {
+    const ROW_HEIGHT: i32 = 22;
+    const CELL_PADDING: i32 = 6;
+    const MAX_COLUMN_WIDTH: i32 = 260;
+    /// Rows measured when sizing columns
+    const MEASURE_ROWS: usize = 200;
+
+    let regular = fonts.get(13, false, false, false);
+    let bold = fonts.get(13, true, false, false);
+    let row_font = |r: usize| if header && r == 0 { bold } else { regular };
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![CELL_PADDING * 4; columns];
+    for (r, row) in rows.iter().enumerate().take(MEASURE_ROWS) {
+        SelectObject(hdc, row_font(r));
+        for (c, cell) in row.iter().enumerate() {
+            let cell_wide: Vec<u16> = cell.encode_utf16().collect();
+            let mut size = windows::Win32::Foundation::SIZE::default();
+            let _ = GetTextExtentPoint32W(hdc, &cell_wide, &mut size);
+            widths[c] = widths[c].max((size.cx + CELL_PADDING * 2).min(MAX_COLUMN_WIDTH));
+        }
+    }
+    let table_right = (left + widths.iter().sum::<i32>()).min(right);
+    let bottom = y + rows.len() as i32 * ROW_HEIGHT;
+
+    for (r, row) in rows.iter().enumerate() {
+        let top = y + r as i32 * ROW_HEIGHT;
+        if top > clip.bottom {
+            break;
+        }
+        if top + ROW_HEIGHT < clip.top {
+            continue;
+        }
+        let is_header = header && r == 0;
+        if is_header {
+            fill_solid(hdc, RECT { left, top, right: table_right, bottom: top + ROW_HEIGHT }, colors.surface);
+        }
+        SelectObject(hdc, row_font(r));
+        SetTextColor(hdc, if is_header { colors.muted } else { colors.text }.colorref());
+
+        let mut x = left;
+        for (c, cell) in row.iter().enumerate() {
+            if x >= right {
+                break;
+            }
+            // Clip each cell so long values don't run into the next column
+            let saved = SaveDC(hdc);
+            let _ = IntersectClipRect(hdc, x + CELL_PADDING, top, (x + widths[c] - CELL_PADDING).min(right), top + ROW_HEIGHT);
+            let cell_wide: Vec<u16> = cell.encode_utf16().collect();
+            let _ = TextOutW(hdc, x + CELL_PADDING, top + 4, &cell_wide);
+            let _ = RestoreDC(hdc, saved);
+            x += widths[c];
+        }
+        fill_solid(hdc, RECT { left, top: top + ROW_HEIGHT - 1, right: table_right, bottom: top + ROW_HEIGHT }, colors.line);
+    }
+
+    // Column lines
+    let mut x = left;
+    fill_solid(hdc, RECT { left, top: y, right: table_right, bottom: y + 1 }, colors.line);
+    for width in std::iter::once(0).chain(widths) {
+        x += width;
+        if x > table_right {
+            break;
+        }
+        fill_solid(hdc, RECT { left: x.min(table_right - 1), top: y, right: x.min(table_right - 1) + 1, bottom }, colors.line);
+    }
+
+    bottom
+}
+
+/// Fill a rectangle with a solid color
+unsafe fn fill_solid(hdc: HDC, rect: RECT, color: Color) {
+    let brush = CreateSolidBrush(color.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+}
+
 /// Paint unsupported file type message
 unsafe fn paint_unsupported(hdc: HDC, rect: &RECT, ext: &str, text_color: Color) {
     let font = CreateFontW(
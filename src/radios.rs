@@ -0,0 +1,40 @@
+//! Wi-Fi and Bluetooth radios
+//!
+//! Read and switched through `Windows.Devices.Radios`, the same switches as the
+//! quick settings in Windows. Each call waits on a WinRT operation, so they're
+//! made off the UI thread.
+
+use windows::core::{Error, Result, HRESULT};
+use windows::Devices::Radios::{Radio, RadioAccessStatus, RadioState};
+
+pub use windows::Devices::Radios::RadioKind;
+
+/// E_ACCESSDENIED, for when Windows won't let radios be switched
+const ACCESS_DENIED: HRESULT = HRESULT(0x80070005_u32 as i32);
+
+/// Whether the radios of `kind` are on; None when there are none
+pub fn is_on(kind: RadioKind) -> Option<bool> {
+    let radios = Radio::GetRadiosAsync().and_then(|op| op.get()).ok()?;
+    let mut found = None;
+    for radio in &radios {
+        if radio.Kind().ok() == Some(kind) {
+            let on = radio.State().ok() == Some(RadioState::On);
+            found = Some(found.unwrap_or(false) || on);
+        }
+    }
+    found
+}
+
+/// Switch every radio of `kind` on or off
+pub fn set(kind: RadioKind, on: bool) -> Result<()> {
+    if Radio::RequestAccessAsync()?.get()? != RadioAccessStatus::Allowed {
+        return Err(Error::new(ACCESS_DENIED, "Not allowed to switch radios"));
+    }
+    let state = if on { RadioState::On } else { RadioState::Off };
+    for radio in &Radio::GetRadiosAsync()?.get()? {
+        if radio.Kind()? == kind && radio.SetStateAsync(state)?.get()? != RadioAccessStatus::Allowed {
+            return Err(Error::new(ACCESS_DENIED, "Not allowed to switch radios"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Recent files and folders for the app menu
+//!
+//! Windows keeps a shortcut to each file and folder opened through the shell in
+//! the Recent Items folder (`%APPDATA%\Microsoft\Windows\Recent`). The newest
+//! are listed by the shortcut's time and resolved to their targets, so ones
+//! pointing at deleted or moved files are left out. Clearing them goes through
+//! the shell, the same as File Explorer's "Clear" for recent files.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use windows::core::{Interface, PCWSTR};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, IPersistFile, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, STGM_READ,
+};
+use windows::Win32::UI::Shell::{IShellLinkW, SHAddToRecentDocs, ShellLink, SHARD_PIDL};
+
+/// A recently used file or folder
+#[derive(Debug, Clone)]
+pub struct RecentItem {
+    /// The shortcut's name, without `.lnk`
+    pub name: String,
+    /// The shortcut in the Recent Items folder
+    pub link: PathBuf,
+    /// What it points to, when the shortcut could be read
+    pub target: Option<PathBuf>,
+}
+
+impl RecentItem {
+    /// The path to open and take the icon from: the target, else the shortcut
+    pub fn path(&self) -> &Path {
+        self.target.as_deref().unwrap_or(&self.link)
+    }
+}
+
+/// The Recent Items folder
+pub fn folder() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("Microsoft").join("Windows").join("Recent"))
+}
+
+/// Up to `limit` recent items, newest first
+pub fn list(limit: usize) -> Vec<RecentItem> {
+    let Some(entries) = folder().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut links: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lnk")))
+        .filter_map(|path| Some((path.metadata().ok()?.modified().ok()?, path)))
+        .collect();
+    links.sort_by_key(|(modified, _)| Reverse(*modified));
+
+    links
+        .into_iter()
+        .filter_map(|(_, link)| {
+            let target = resolve(&link);
+            // Network paths aren't checked; an offline share would stall the menu
+            let missing = target
+                .as_ref()
+                .is_some_and(|t| !t.to_string_lossy().starts_with("\\\\") && !t.exists());
+            if missing {
+                return None;
+            }
+            Some(RecentItem {
+                name: link.file_stem()?.to_string_lossy().into_owned(),
+                link,
+                target,
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Open a recent item with its default program (or Explorer, for a folder)
+pub fn open(item: &RecentItem) {
+    crate::utils::open_url(&item.path().to_string_lossy());
+}
+
+/// Forget every recent item
+pub fn clear() {
+    unsafe {
+        SHAddToRecentDocs(SHARD_PIDL.0 as u32, None);
+    }
+}
+
+/// Where a shortcut points, for shortcuts to files and folders
+fn resolve(link: &Path) -> Option<PathBuf> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let shell_link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER).ok()?;
+        let file: IPersistFile = shell_link.cast().ok()?;
+        let wide: Vec<u16> = link.to_string_lossy().encode_utf16().chain(std::iter::once(0)).collect();
+        file.Load(PCWSTR(wide.as_ptr()), STGM_READ).ok()?;
+
+        let mut buffer = [0u16; 260];
+        shell_link.GetPath(&mut buffer, std::ptr::null_mut(), 0).ok()?;
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        (len > 0).then(|| PathBuf::from(String::from_utf16_lossy(&buffer[..len])))
+    }
+}
@@ -0,0 +1,237 @@
+//! Banner for a ringing alarm
+//!
+//! A rounded panel at the top of the active monitor with the alarm's label and
+//! time and Snooze / Dismiss buttons. It's its own top-level window, so it shows
+//! even when the bar is hidden, and it never takes focus, so a full-screen game
+//! or presentation underneath keeps running.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::window::state::get_window_state;
+
+const BANNER_CLASS: &str = "TopBarAlarmBannerClass";
+const WIDTH: i32 = 380;
+const HEIGHT: i32 = 132;
+const BUTTON_WIDTH: i32 = 150;
+const BUTTON_HEIGHT: i32 = 34;
+const PADDING: i32 = 16;
+
+/// The banner window, if shown
+static BANNER: AtomicIsize = AtomicIsize::new(0);
+/// Label and time currently shown
+static CONTENT: Mutex<(String, String)> = Mutex::new((String::new(), String::new()));
+
+/// Show (or update) the banner
+pub fn show_alarm_banner(label: &str, time: &str) {
+    *CONTENT.lock() = (label.to_string(), time.to_string());
+    if let Err(e) = unsafe { show() } {
+        log::warn!("Failed to show alarm banner: {}", e);
+    }
+}
+
+/// Close the banner if it's open
+pub fn hide_alarm_banner() {
+    let hwnd = HWND(BANNER.swap(0, Ordering::SeqCst) as *mut std::ffi::c_void);
+    if !hwnd.0.is_null() {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+    }
+}
+
+unsafe fn show() -> Result<()> {
+    let mut hwnd = HWND(BANNER.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(BANNER_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        BANNER.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    position(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    Ok(())
+}
+
+/// Center the banner near the top of the foreground window's monitor
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let width = scale(WIDTH, dpi);
+    let height = scale(HEIGHT, dpi);
+
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    // The full monitor, not the work area: a full-screen app covers the bar anyway
+    let area = info.rcMonitor;
+    let x = area.left + (area.right - area.left - width) / 2;
+    let y = area.top + scale(48, dpi);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(16, dpi), scale(16, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+/// Snooze and Dismiss button rectangles
+fn button_rects(client: &RECT, dpi: u32) -> (RECT, RECT) {
+    let width = scale(BUTTON_WIDTH, dpi);
+    let height = scale(BUTTON_HEIGHT, dpi);
+    let padding = scale(PADDING, dpi);
+    let bottom = client.bottom - padding;
+    let snooze = RECT {
+        left: padding,
+        top: bottom - height,
+        right: padding + width,
+        bottom,
+    };
+    let dismiss = RECT {
+        left: client.right - padding - width,
+        top: bottom - height,
+        right: client.right - padding,
+        bottom,
+    };
+    (snooze, dismiss)
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let (label, time) = CONTENT.lock().clone();
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.accent.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    SetBkMode(hdc, TRANSPARENT);
+    let padding = scale(PADDING, dpi);
+    let family = font_family();
+
+    let title_font = create_font(&family, scale(22, dpi), true);
+    let old_font = SelectObject(hdc, title_font);
+    SetTextColor(hdc, theme.text_primary.colorref());
+    let (_, title_height) = measure_text(hdc, &format!("⏰ {}", label));
+    draw_text(hdc, padding, padding, &format!("⏰ {}", label));
+
+    let time_font = create_font(&family, scale(14, dpi), false);
+    SelectObject(hdc, time_font);
+    SetTextColor(hdc, theme.text_secondary.colorref());
+    draw_text(hdc, padding, padding + title_height + scale(2, dpi), &time);
+
+    let (snooze, dismiss) = button_rects(&rect, dpi);
+    for (button, text, primary) in [(snooze, "Snooze", false), (dismiss, "Dismiss", true)] {
+        let fill = if primary { theme.accent } else { theme.background_secondary };
+        let brush = CreateSolidBrush(fill.colorref());
+        let region = CreateRoundRectRgn(button.left, button.top, button.right, button.bottom, scale(8, dpi), scale(8, dpi));
+        let _ = FillRgn(hdc, region, brush);
+        let _ = DeleteObject(region);
+        let _ = DeleteObject(brush);
+
+        SetTextColor(hdc, if primary { COLORREF(0x00FFFFFF) } else { theme.text_primary.colorref() });
+        let (w, h) = measure_text(hdc, text);
+        draw_text(
+            hdc,
+            button.left + (button.right - button.left - w) / 2,
+            button.top + (button.bottom - button.top - h) / 2,
+            text,
+        );
+    }
+
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(title_font);
+    let _ = DeleteObject(time_font);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(BANNER_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_HAND)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+
+        WM_LBUTTONUP => {
+            let point = POINT {
+                x: (lparam.0 & 0xFFFF) as i16 as i32,
+                y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+            };
+            let mut rect = RECT::default();
+            let _ = GetClientRect(hwnd, &mut rect);
+            let (snooze, dismiss) = button_rects(&rect, GetDpiForWindow(hwnd).max(96));
+            if PtInRect(&snooze, point).as_bool() {
+                crate::alarms::snooze();
+            } else if PtInRect(&dismiss, point).as_bool() {
+                crate::alarms::dismiss();
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = BANNER.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,240 @@
+//! Banner for the morning briefing
+//!
+//! A rounded panel near the top of the active monitor with a greeting and the
+//! briefing's sections. Like the alarm banner it's a top-level window that never
+//! takes focus. A click anywhere closes it; otherwise it closes on its own after
+//! the configured time.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::briefing::Briefing;
+use crate::window::state::get_window_state;
+
+const BANNER_CLASS: &str = "TopBarBriefingClass";
+const HIDE_TIMER: usize = 1;
+const MIN_WIDTH: i32 = 360;
+const PADDING: i32 = 16;
+const SECTION_GAP: i32 = 10;
+
+/// The banner window, if shown
+static BANNER: AtomicIsize = AtomicIsize::new(0);
+/// Briefing currently shown
+static CONTENT: Mutex<Option<Briefing>> = Mutex::new(None);
+
+/// Show (or replace) the briefing banner; `duration_secs` 0 keeps it until clicked
+pub fn show_briefing(briefing: &Briefing, duration_secs: u32) {
+    *CONTENT.lock() = Some(briefing.clone());
+    if let Err(e) = unsafe { show(duration_secs) } {
+        log::warn!("Failed to show briefing: {}", e);
+    }
+}
+
+unsafe fn show(duration_secs: u32) -> Result<()> {
+    let mut hwnd = HWND(BANNER.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(BANNER_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        BANNER.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    position(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    if duration_secs > 0 {
+        SetTimer(hwnd, HIDE_TIMER, duration_secs.saturating_mul(1000), None);
+    } else {
+        let _ = KillTimer(hwnd, HIDE_TIMER);
+    }
+    Ok(())
+}
+
+/// Fonts for the title, section headings and lines
+struct Fonts {
+    title: HFONT,
+    heading: HFONT,
+    line: HFONT,
+}
+
+impl Fonts {
+    fn new(dpi: u32) -> Self {
+        let family = font_family();
+        Self {
+            title: create_font(&family, scale(20, dpi), true),
+            heading: create_font(&family, scale(12, dpi), true),
+            line: create_font(&family, scale(14, dpi), false),
+        }
+    }
+}
+
+impl Drop for Fonts {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeleteObject(self.title);
+            let _ = DeleteObject(self.heading);
+            let _ = DeleteObject(self.line);
+        }
+    }
+}
+
+/// Lay the content out from the top left of the panel, drawing it when `draw` is
+/// set; returns the size it needs
+unsafe fn layout(hdc: HDC, dpi: u32, draw: Option<&crate::theme::Theme>) -> (i32, i32) {
+    let Some(Briefing { title, sections }) = CONTENT.lock().clone() else {
+        return (0, 0);
+    };
+    let fonts = Fonts::new(dpi);
+    let padding = scale(PADDING, dpi);
+    let old_font = SelectObject(hdc, fonts.title);
+
+    let mut width = 0;
+    let mut y = padding;
+    let mut text = |font: HFONT, color: Option<crate::theme::Color>, line: &str, y: &mut i32| {
+        SelectObject(hdc, font);
+        let (w, h) = measure_text(hdc, line);
+        if let Some(color) = color {
+            SetTextColor(hdc, color.colorref());
+            draw_text(hdc, padding, *y, line);
+        }
+        width = width.max(w);
+        *y += h;
+    };
+
+    text(fonts.title, draw.map(|t| t.text_primary), &title, &mut y);
+    for (heading, lines) in &sections {
+        y += scale(SECTION_GAP, dpi);
+        text(fonts.heading, draw.map(|t| t.text_accent), &heading.to_uppercase(), &mut y);
+        y += scale(2, dpi);
+        for line in lines {
+            text(fonts.line, draw.map(|t| t.text_primary), line, &mut y);
+        }
+    }
+    if sections.is_empty() {
+        y += scale(SECTION_GAP, dpi);
+        text(fonts.line, draw.map(|t| t.text_secondary), "Nothing planned today.", &mut y);
+    }
+
+    SelectObject(hdc, old_font);
+    ((width + padding * 2).max(scale(MIN_WIDTH, dpi)), y + padding)
+}
+
+/// Size the banner to its content and center it near the top of the foreground window's monitor
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let hdc = GetDC(hwnd);
+    let (width, height) = layout(hdc, dpi, None);
+    ReleaseDC(hwnd, hdc);
+
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcWork;
+    let width = width.min(area.right - area.left);
+    let x = area.left + (area.right - area.left - width) / 2;
+    let y = area.top + scale(48, dpi);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(16, dpi), scale(16, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    SetBkMode(hdc, TRANSPARENT);
+    layout(hdc, dpi, Some(&theme));
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(BANNER_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_HAND)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+
+        WM_LBUTTONUP => {
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+
+        WM_TIMER if wparam.0 == HIDE_TIMER => {
+            let _ = KillTimer(hwnd, HIDE_TIMER);
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = BANNER.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,197 @@
+//! Charts for resource histories
+//!
+//! A chart draws one or more series as lines, each over a filled area that
+//! fades toward the baseline, with optional dotted guides at the lowest,
+//! highest and average value. Stacked series are drawn one on top of the
+//! other. The fill is blended with `AlphaBlend`, so it shows whatever is
+//! behind it: the bar, a pill or a popup's graph background.
+//! `[appearance.graphs]` sets the fill, smoothing and guides everywhere.
+
+use windows::Win32::Foundation::POINT;
+use windows::Win32::Graphics::Gdi::*;
+
+use crate::config::GraphConfig;
+use crate::theme::Color;
+use crate::utils::Rect;
+
+/// How much of the fill's opacity is gone at the baseline
+const FILL_FADE: f32 = 0.8;
+
+/// One line on a chart, oldest value first
+pub struct Series {
+    pub values: Vec<f32>,
+    pub color: Color,
+}
+
+/// A set of series sharing one scale
+pub struct Chart<'a> {
+    pub series: Vec<Series>,
+    /// Value at the top of the chart; stacked series are scaled by their total
+    pub max: f32,
+    /// Draw each series on top of the ones before it
+    pub stacked: bool,
+    pub style: &'a GraphConfig,
+}
+
+impl<'a> Chart<'a> {
+    /// A chart of values from 0 to 100
+    pub fn percent(series: Vec<Series>, style: &'a GraphConfig) -> Self {
+        Self {
+            series,
+            max: 100.0,
+            stacked: false,
+            style,
+        }
+    }
+
+    /// Draw into `rect`, leaving `padding` on the left and right and 2 pixels
+    /// above and below, like the bar's other graphs
+    pub fn draw(&self, hdc: HDC, rect: &Rect, padding: i32) {
+        let area = Rect::new(rect.x + padding, rect.y + 2, rect.width - padding * 2, rect.height - 4);
+        let len = self.series.iter().map(|s| s.values.len()).max().unwrap_or(0);
+        if area.width <= 0 || area.height <= 0 || len == 0 || self.max <= 0.0 {
+            return;
+        }
+
+        // Series of different lengths line up at the newest value
+        let smoothing = self.style.smoothing as usize;
+        let mut base = vec![0.0; len];
+        let mut lines = Vec::with_capacity(self.series.len());
+        for series in &self.series {
+            let mut values = vec![0.0; len - series.values.len()];
+            values.extend(smooth(&series.values, smoothing));
+            if self.stacked {
+                for (value, below) in values.iter_mut().zip(&base) {
+                    *value += below;
+                }
+            }
+            let bottom = if self.stacked { base.clone() } else { vec![0.0; len] };
+            if self.stacked {
+                base.clone_from(&values);
+            }
+            lines.push((self.points(&values, &area), self.points(&bottom, &area), series.color, values));
+        }
+
+        unsafe {
+            if self.style.fill {
+                let opacity = self.style.fill_opacity.clamp(0.0, 1.0);
+                for (top, bottom, color, _) in &lines {
+                    fill_area(hdc, top, bottom, &area, *color, opacity);
+                }
+            }
+            if self.style.guides {
+                // Guides follow the total when stacked, otherwise the first series
+                let guided = if self.stacked { lines.last() } else { lines.first() };
+                if let Some((_, _, color, values)) = guided {
+                    self.draw_guides(hdc, values, &area, *color);
+                }
+            }
+            for (top, _, color, _) in &lines {
+                let pen = CreatePen(PS_SOLID, 1, color.colorref());
+                let old_pen = SelectObject(hdc, pen);
+                let _ = Polyline(hdc, top);
+                let _ = SelectObject(hdc, old_pen);
+                let _ = DeleteObject(pen);
+            }
+        }
+    }
+
+    /// Chart coordinates of each value, spread evenly across `area`
+    fn points(&self, values: &[f32], area: &Rect) -> Vec<POINT> {
+        let step = if values.len() > 1 {
+            area.width as f32 / (values.len() - 1) as f32
+        } else {
+            0.0
+        };
+        let mut points: Vec<POINT> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| POINT {
+                x: area.x + (i as f32 * step) as i32,
+                y: self.y(*value, area),
+            })
+            .collect();
+        // A single value still draws as a (flat) line
+        if points.len() == 1 {
+            points.push(POINT {
+                x: area.right(),
+                y: points[0].y,
+            });
+        }
+        points
+    }
+
+    fn y(&self, value: f32, area: &Rect) -> i32 {
+        let fraction = (value / self.max).clamp(0.0, 1.0);
+        area.y + ((1.0 - fraction) * area.height as f32) as i32
+    }
+
+    /// Dotted lines at the minimum, maximum and average of `values`
+    unsafe fn draw_guides(&self, hdc: HDC, values: &[f32], area: &Rect, color: Color) {
+        let min = values.iter().copied().fold(f32::MAX, f32::min);
+        let max = values.iter().copied().fold(0.0, f32::max);
+        let avg = values.iter().sum::<f32>() / values.len() as f32;
+
+        let pen = CreatePen(PS_DOT, 1, color.colorref());
+        let old_pen = SelectObject(hdc, pen);
+        let old_mode = SetBkMode(hdc, TRANSPARENT);
+        for value in [min, max, avg] {
+            let y = self.y(value, area);
+            let _ = MoveToEx(hdc, area.x, y, None);
+            let _ = LineTo(hdc, area.right(), y);
+        }
+        SetBkMode(hdc, BACKGROUND_MODE(old_mode as u32));
+        let _ = SelectObject(hdc, old_pen);
+        let _ = DeleteObject(pen);
+    }
+}
+
+/// Average each value with up to `radius` neighbours on either side
+fn smooth(values: &[f32], radius: usize) -> Vec<f32> {
+    if radius == 0 {
+        return values.to_vec();
+    }
+    (0..values.len())
+        .map(|i| {
+            let window = &values[i.saturating_sub(radius)..(i + radius + 1).min(values.len())];
+            window.iter().sum::<f32>() / window.len() as f32
+        })
+        .collect()
+}
+
+/// Fill between the `top` and `bottom` lines, strongest at the top of `area`
+/// and fading toward its baseline
+unsafe fn fill_area(hdc: HDC, top: &[POINT], bottom: &[POINT], area: &Rect, color: Color, opacity: f32) {
+    let outline: Vec<POINT> = top.iter().chain(bottom.iter().rev()).copied().collect();
+    let highest = top.iter().map(|p| p.y).min().unwrap_or(area.bottom());
+    if highest >= area.bottom() || opacity <= 0.0 {
+        return;
+    }
+
+    let region = CreatePolygonRgn(&outline, WINDING);
+    let saved = SaveDC(hdc);
+    ExtSelectClipRgn(hdc, region, RGN_AND);
+
+    // One pixel of the color, stretched across each row at that row's opacity
+    let source = CreateCompatibleDC(hdc);
+    let pixel = CreateCompatibleBitmap(hdc, 1, 1);
+    let old = SelectObject(source, pixel);
+    SetPixel(source, 0, 0, color.colorref());
+    for y in highest..area.bottom() {
+        let depth = (y - area.y) as f32 / area.height as f32;
+        let alpha = opacity * (1.0 - FILL_FADE * depth);
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: (alpha * 255.0) as u8,
+            AlphaFormat: 0,
+        };
+        let _ = AlphaBlend(hdc, area.x, y, area.width, 1, source, 0, 0, 1, 1, blend);
+    }
+    SelectObject(source, old);
+    let _ = DeleteObject(pixel);
+    let _ = DeleteDC(source);
+
+    let _ = RestoreDC(hdc, saved);
+    let _ = DeleteObject(region);
+}
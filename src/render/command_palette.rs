@@ -0,0 +1,492 @@
+//! Command palette - every bar action, reachable from the keyboard
+//!
+//! A quick-search-style popup listing the actions from `actions`. Actions that
+//! take an argument are expanded into one entry per choice: show/hide and refresh
+//! for each module, each snap and tile layout, each media key and each saved
+//! profile. Typing fuzzy-matches the entries; the chord bound to an entry, if
+//! any, is shown on its right. Enter (or a click) runs the highlighted entry.
+
+use anyhow::Result;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::sounds::{self, SoundEvent};
+use crate::theme::Color;
+use crate::window::renderer::with_renderer;
+use crate::window::state::get_window_state;
+
+const PALETTE_CLASS: &str = "TopBarCommandPaletteClass";
+const WIN_WIDTH: i32 = 560;
+const PADDING: i32 = 12;
+const INPUT_HEIGHT: i32 = 44;
+const LIST_TOP: i32 = PADDING + INPUT_HEIGHT + 10;
+const ROW_HEIGHT: i32 = 36;
+const MAX_ROWS: usize = 9;
+const FOOTER_HEIGHT: i32 = 30;
+const WIN_HEIGHT: i32 = LIST_TOP + MAX_ROWS as i32 * ROW_HEIGHT + FOOTER_HEIGHT;
+
+/// Actions that need an argument; their choices are listed instead
+const EXPANDED: &[&str] = &["refresh", "toggle_module", "snap", "tile", "media", "profile", "run", "command_palette"];
+
+/// An entry in the palette
+struct Command {
+    title: String,
+    /// Action spec to run
+    spec: String,
+    /// Hotkey bound to the action, if any
+    chord: Option<String>,
+}
+
+struct PaletteState {
+    /// The bar, which actions run against
+    parent: HWND,
+    input: String,
+    commands: Vec<Command>,
+    /// Indexes into `commands` that match the input, best first
+    matches: Vec<usize>,
+    selected: usize,
+    /// First match shown
+    scroll: usize,
+}
+
+impl PaletteState {
+    /// Re-match the commands against the input and go back to the top
+    fn filter(&mut self) {
+        let query = self.input.trim().to_lowercase();
+        if query.is_empty() {
+            self.matches = (0..self.commands.len()).collect();
+        } else {
+            let mut scored: Vec<(i32, usize)> = self
+                .commands
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    let title = crate::search::fuzzy_score(&c.title, &query);
+                    let spec = crate::search::fuzzy_score(&c.spec, &query);
+                    Some((title.max(spec)?, i))
+                })
+                .collect();
+            // Stable, so equal scores keep the list order
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        }
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// Move the selection, wrapping around, and keep it in view
+    fn step(&mut self, delta: i32) {
+        let count = self.matches.len() as i32;
+        if count == 0 {
+            return;
+        }
+        self.selected = (self.selected as i32 + delta).rem_euclid(count) as usize;
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + MAX_ROWS {
+            self.scroll = self.selected + 1 - MAX_ROWS;
+        }
+    }
+}
+
+/// Everything the palette offers, in display order
+fn commands() -> Vec<Command> {
+    use crate::modules::media::MediaKey;
+    use crate::window::snap::SnapLayout;
+    use crate::window::tile::TileLayout;
+
+    let mut entries: Vec<(String, String)> = crate::actions::registry()
+        .lock()
+        .list()
+        .into_iter()
+        .filter(|(name, _)| !EXPANDED.contains(name))
+        // Descriptions explain arguments in parentheses, which don't apply here
+        .map(|(name, description)| {
+            let title = description.split(" (").next().unwrap_or(description);
+            (title.to_string(), name.to_string())
+        })
+        .collect();
+
+    let modules: Vec<(String, String)> = with_renderer(|renderer| {
+        renderer
+            .module_registry
+            .all()
+            .iter()
+            .map(|m| (m.id().to_string(), m.name().to_string()))
+            .collect()
+    })
+    .unwrap_or_default();
+    for (id, name) in &modules {
+        entries.push((format!("Show or Hide {}", name), format!("toggle_module:{}", id)));
+    }
+    for (id, name) in &modules {
+        entries.push((format!("Refresh {}", name), format!("refresh:{}", id)));
+    }
+    for layout in SnapLayout::ALL {
+        entries.push((format!("Snap Window: {}", layout.label()), format!("snap:{}", layout.key())));
+    }
+    for layout in TileLayout::ALL {
+        entries.push((layout.label().to_string(), format!("tile:{}", layout.key())));
+    }
+    for key in MediaKey::ALL {
+        entries.push((format!("Media: {}", key.label()), format!("media:{}", key.name())));
+    }
+    let profiles = crate::config::Config::profiles();
+    if !profiles.is_empty() {
+        entries.push(("Switch to Profile: Default".to_string(), "profile:default".to_string()));
+    }
+    for name in profiles {
+        entries.push((format!("Switch to Profile: {}", name), format!("profile:{}", name)));
+    }
+
+    let chords = get_window_state()
+        .map(|s| crate::hotkey::bound_chords(&s.read().config))
+        .unwrap_or_default();
+    entries
+        .into_iter()
+        .map(|(title, spec)| Command {
+            chord: chords.get(&spec).cloned(),
+            title,
+            spec,
+        })
+        .collect()
+}
+
+pub fn show_command_palette(parent: HWND) -> Result<()> {
+    unsafe { register_class()?; }
+
+    let hwnd = unsafe {
+        let class = to_wide(PALETTE_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, WIN_WIDTH, WIN_HEIGHT,
+            parent,
+            None,
+            hinstance,
+            None,
+        )?
+    };
+
+    let mut state = Box::new(PaletteState {
+        parent,
+        input: String::new(),
+        commands: commands(),
+        matches: Vec::new(),
+        selected: 0,
+        scroll: 0,
+    });
+    state.filter();
+    unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize); }
+
+    // Centered near the top of the screen, where quick search opens
+    unsafe {
+        let screen_w = GetSystemMetrics(SM_CXSCREEN);
+        let x = (screen_w - WIN_WIDTH) / 2;
+        SetWindowPos(hwnd, HWND_TOPMOST, x, 80, WIN_WIDTH, WIN_HEIGHT, SWP_SHOWWINDOW).ok();
+        let _ = SetForegroundWindow(hwnd);
+        let _ = SetFocus(hwnd);
+    }
+    sounds::play_event(SoundEvent::PopupOpen);
+
+    Ok(())
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(PALETTE_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_HREDRAW | CS_VREDRAW | CS_DROPSHADOW,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe fn create_font(height: i32, weight: i32) -> HFONT {
+    CreateFontW(
+        height, 0, 0, 0, weight, 0, 0, 0,
+        DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
+        PCWSTR(to_wide("Segoe UI").as_ptr())
+    )
+}
+
+unsafe fn fill_rounded(hdc: HDC, rect: &RECT, radius: i32, color: Color) {
+    let brush = CreateSolidBrush(color.colorref());
+    let rgn = CreateRoundRectRgn(rect.left, rect.top, rect.right, rect.bottom, radius, radius);
+    let _ = FillRgn(hdc, rgn, brush);
+    let _ = DeleteObject(rgn);
+    let _ = DeleteObject(brush);
+}
+
+unsafe fn draw_text(hdc: HDC, text: &str, rect: &mut RECT, format: DRAW_TEXT_FORMAT) {
+    let mut wide: Vec<u16> = text.encode_utf16().collect();
+    DrawTextW(hdc, &mut wide, rect, format | DT_SINGLELINE | DT_VCENTER | DT_NOPREFIX);
+}
+
+unsafe fn paint(hdc: HDC, state: &PaletteState, accent: Color) {
+    let mut client = RECT { left: 0, top: 0, right: WIN_WIDTH, bottom: WIN_HEIGHT };
+    let bg = CreateSolidBrush(Color::rgb(22, 22, 24).colorref());
+    FillRect(hdc, &client, bg);
+    let _ = DeleteObject(bg);
+    SetBkMode(hdc, TRANSPARENT);
+
+    // Input
+    let input_rect = RECT { left: PADDING, top: PADDING, right: WIN_WIDTH - PADDING, bottom: PADDING + INPUT_HEIGHT };
+    fill_rounded(hdc, &input_rect, 10, Color::rgb(38, 38, 42));
+    let input_font = create_font(18, FW_NORMAL.0 as i32);
+    let old_font = SelectObject(hdc, input_font);
+    let mut text_rect = RECT { left: input_rect.left + 16, right: input_rect.right - 16, ..input_rect };
+    if state.input.is_empty() {
+        SetTextColor(hdc, Color::rgb(100, 100, 105).colorref());
+        draw_text(hdc, &crate::i18n::tr("Type a command..."), &mut text_rect, DT_LEFT);
+    } else {
+        SetTextColor(hdc, Color::rgb(245, 245, 245).colorref());
+        draw_text(hdc, &state.input, &mut text_rect, DT_LEFT);
+        let wide: Vec<u16> = state.input.encode_utf16().collect();
+        let mut size = windows::Win32::Foundation::SIZE::default();
+        let _ = GetTextExtentPoint32W(hdc, &wide, &mut size);
+        let cursor_x = text_rect.left + size.cx + 2;
+        let cursor = RECT { left: cursor_x, top: input_rect.top + 11, right: cursor_x + 2, bottom: input_rect.bottom - 11 };
+        let brush = CreateSolidBrush(accent.colorref());
+        FillRect(hdc, &cursor, brush);
+        let _ = DeleteObject(brush);
+    }
+
+    // Commands
+    let title_font = create_font(15, FW_NORMAL.0 as i32);
+    let chord_font = create_font(12, FW_SEMIBOLD.0 as i32);
+    if state.matches.is_empty() {
+        let _ = SelectObject(hdc, title_font);
+        SetTextColor(hdc, Color::rgb(120, 120, 125).colorref());
+        let mut rect = RECT { left: PADDING + 12, top: LIST_TOP, right: WIN_WIDTH - PADDING, bottom: LIST_TOP + ROW_HEIGHT };
+        draw_text(hdc, &crate::i18n::tr("No commands match \"{}\"").replacen("{}", &state.input, 1), &mut rect, DT_LEFT);
+    }
+    for (row, &index) in state.matches.iter().skip(state.scroll).take(MAX_ROWS).enumerate() {
+        let command = &state.commands[index];
+        let top = LIST_TOP + row as i32 * ROW_HEIGHT;
+        let row_rect = RECT { left: PADDING - 4, top, right: WIN_WIDTH - PADDING + 4, bottom: top + ROW_HEIGHT - 2 };
+        let selected = state.scroll + row == state.selected;
+        if selected {
+            fill_rounded(hdc, &row_rect, 8, accent);
+        }
+
+        let mut chord_left = row_rect.right - 12;
+        if let Some(chord) = &command.chord {
+            // The chord in a key cap on the right
+            let _ = SelectObject(hdc, chord_font);
+            let wide: Vec<u16> = chord.encode_utf16().collect();
+            let mut size = windows::Win32::Foundation::SIZE::default();
+            let _ = GetTextExtentPoint32W(hdc, &wide, &mut size);
+            let cap = RECT {
+                left: row_rect.right - 12 - size.cx - 16,
+                top: top + 7,
+                right: row_rect.right - 12,
+                bottom: top + ROW_HEIGHT - 9,
+            };
+            let cap_color = if selected { Color::rgb(255, 255, 255) } else { Color::rgb(44, 44, 48) };
+            fill_rounded(hdc, &cap, 6, cap_color);
+            SetTextColor(hdc, if selected { accent.colorref() } else { Color::rgb(170, 170, 175).colorref() });
+            let mut chord_rect = cap;
+            draw_text(hdc, chord, &mut chord_rect, DT_CENTER);
+            chord_left = cap.left - 8;
+        }
+
+        let _ = SelectObject(hdc, title_font);
+        SetTextColor(hdc, if selected {
+            Color::rgb(255, 255, 255).colorref()
+        } else {
+            Color::rgb(235, 235, 238).colorref()
+        });
+        let mut title_rect = RECT { left: row_rect.left + 12, top, right: chord_left, bottom: top + ROW_HEIGHT - 2 };
+        draw_text(hdc, &command.title, &mut title_rect, DT_LEFT | DT_END_ELLIPSIS);
+    }
+
+    // Footer
+    let _ = SelectObject(hdc, chord_font);
+    SetTextColor(hdc, Color::rgb(80, 80, 85).colorref());
+    let count = state.matches.len();
+    let footer = format!(
+        "{} command{}  •  ↑↓ to move  •  Enter to run  •  Esc to close",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    client.left = PADDING + 8;
+    client.top = WIN_HEIGHT - FOOTER_HEIGHT;
+    draw_text(hdc, &footer, &mut client, DT_LEFT);
+
+    let _ = SelectObject(hdc, old_font);
+    let _ = DeleteObject(input_font);
+    let _ = DeleteObject(title_font);
+    let _ = DeleteObject(chord_font);
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            let mut ps = PAINTSTRUCT::default();
+            let hdc = BeginPaint(hwnd, &mut ps);
+            if let Some(state) = get_state(hwnd) {
+                let accent = get_window_state()
+                    .map(|s| s.read().theme_manager.theme().accent)
+                    .unwrap_or(Color::rgb(0, 120, 215));
+                // Paint off-screen so typing doesn't flicker
+                let mem = CreateCompatibleDC(hdc);
+                let bitmap = CreateCompatibleBitmap(hdc, WIN_WIDTH, WIN_HEIGHT);
+                let old_bitmap = SelectObject(mem, bitmap);
+                paint(mem, state, accent);
+                let _ = BitBlt(hdc, 0, 0, WIN_WIDTH, WIN_HEIGHT, mem, 0, 0, SRCCOPY);
+                let _ = SelectObject(mem, old_bitmap);
+                let _ = DeleteObject(bitmap);
+                let _ = DeleteDC(mem);
+            }
+            let _ = EndPaint(hwnd, &ps);
+            LRESULT(0)
+        }
+
+        WM_CHAR => {
+            if let Some(state) = get_state_mut(hwnd) {
+                let ch = char::from_u32(wparam.0 as u32).unwrap_or('\0');
+                match ch {
+                    '\u{8}' => { state.input.pop(); }
+                    _ if !ch.is_control() => state.input.push(ch),
+                    _ => return LRESULT(0),
+                }
+                state.filter();
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            LRESULT(0)
+        }
+
+        WM_KEYDOWN => {
+            match wparam.0 as u32 {
+                0x1B => close_window(hwnd), // ESC
+                0x26 | 0x28 => { // UP, DOWN
+                    if let Some(state) = get_state_mut(hwnd) {
+                        if !state.matches.is_empty() {
+                            state.step(if wparam.0 == 0x26 { -1 } else { 1 });
+                            sounds::play_event(SoundEvent::Focus);
+                            let _ = InvalidateRect(hwnd, None, false);
+                        }
+                    }
+                }
+                0x0D => { // ENTER
+                    if let Some(state) = get_state(hwnd) {
+                        run_command(hwnd, state.selected);
+                    }
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+
+        WM_MOUSEWHEEL => {
+            let delta = (wparam.0 >> 16) as i16;
+            if let Some(state) = get_state_mut(hwnd) {
+                let max_scroll = state.matches.len().saturating_sub(MAX_ROWS);
+                state.scroll = if delta > 0 {
+                    state.scroll.saturating_sub(1)
+                } else {
+                    (state.scroll + 1).min(max_scroll)
+                };
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            let y = (lparam.0 >> 16) as i16 as i32;
+            if y >= LIST_TOP {
+                let row = ((y - LIST_TOP) / ROW_HEIGHT) as usize;
+                if let Some(state) = get_state(hwnd) {
+                    if row < MAX_ROWS {
+                        run_command(hwnd, state.scroll + row);
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_KILLFOCUS => {
+            close_window(hwnd);
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = free_state(hwnd);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// Close the palette, then run a matched command against the bar
+fn run_command(hwnd: HWND, match_index: usize) {
+    let Some(state) = get_state(hwnd) else {
+        return;
+    };
+    let Some(command) = state.matches.get(match_index).map(|&i| &state.commands[i]) else {
+        return;
+    };
+    let (parent, spec) = (state.parent, command.spec.clone());
+    // Closed first: actions may open popups of their own that need the focus
+    close_window(hwnd);
+    if let Err(e) = crate::actions::execute(parent, &spec) {
+        log::warn!("Command palette action {} failed: {}", spec, e);
+    }
+}
+
+fn get_state(hwnd: HWND) -> Option<&'static PaletteState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut PaletteState;
+        if ptr.is_null() { None } else { Some(&*ptr) }
+    }
+}
+
+fn get_state_mut(hwnd: HWND) -> Option<&'static mut PaletteState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut PaletteState;
+        if ptr.is_null() { None } else { Some(&mut *ptr) }
+    }
+}
+
+/// Free the palette state; returns false if it was already freed
+fn free_state(hwnd: HWND) -> bool {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut PaletteState;
+        if ptr.is_null() {
+            return false;
+        }
+        drop(Box::from_raw(ptr));
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        true
+    }
+}
+
+fn close_window(hwnd: HWND) {
+    // Destroying the window re-enters here through WM_KILLFOCUS; only the first call counts
+    if !free_state(hwnd) {
+        return;
+    }
+    unsafe {
+        let _ = DestroyWindow(hwnd);
+    }
+    sounds::play_event(SoundEvent::PopupClose);
+}
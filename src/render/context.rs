@@ -1,6 +1,7 @@
 use windows::Win32::Graphics::Gdi::HDC;
 
-use crate::modules::ModuleRenderContext;
+use crate::config::ModuleColors;
+use crate::modules::{Module, ModuleRenderContext};
 use crate::theme::Theme;
 
 /// Render context passed to modules
@@ -12,4 +13,24 @@ impl ModuleRenderContext {
             dpi,
         }
     }
-}
\ No newline at end of file
+
+    /// Context for drawing one module, with its colors from
+    /// `[appearance.module_colors]` and the rules matching its current values
+    pub fn for_module(
+        hdc: HDC,
+        theme: &Theme,
+        dpi: u32,
+        module: Option<&dyn Module>,
+        colors: &ModuleColors,
+    ) -> Self {
+        let mut theme = theme.clone();
+        theme.apply_colors(&colors.colors);
+        if !colors.rules.is_empty() {
+            let metrics = module.map(|m| m.metrics()).unwrap_or_default();
+            for rule in colors.rules.iter().filter(|r| r.matches(&metrics)) {
+                theme.apply_colors(&rule.colors);
+            }
+        }
+        Self { hdc, theme, dpi }
+    }
+}
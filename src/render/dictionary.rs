@@ -0,0 +1,360 @@
+//! Dictionary popup
+//!
+//! Shows a word's pronunciation, its definitions grouped by part of speech and
+//! their synonyms, Spotlight-style. The lookup runs in the background while the
+//! popup says so. Long entries scroll with the wheel or the arrow keys; Esc or
+//! clicking elsewhere closes it.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, AtomicU64, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, scale};
+use crate::dictionary::Entry;
+use crate::theme::Theme;
+use crate::window::state::get_window_state;
+
+const POPUP_CLASS: &str = "TopBarDictionaryClass";
+const WIDTH: i32 = 420;
+const HEIGHT: i32 = 360;
+const PADDING: i32 = 18;
+/// Definitions shown per part of speech
+const MAX_DEFINITIONS: usize = 4;
+/// Synonyms shown per part of speech
+const MAX_SYNONYMS: usize = 8;
+/// Pixels scrolled per wheel notch or arrow key, before DPI scaling
+const SCROLL_STEP: i32 = 40;
+/// Posted by the lookup thread when it's done
+const WM_LOOKED_UP: u32 = WM_APP + 22;
+
+/// What the popup shows
+struct Content {
+    word: String,
+    /// None while the lookup is out
+    result: Option<Result<Entry, String>>,
+    /// How far the text is scrolled, in pixels
+    scroll: i32,
+    /// Height of everything drawn at the last paint, for limiting the scroll
+    height: i32,
+}
+
+/// The popup window, if shown
+static POPUP: AtomicIsize = AtomicIsize::new(0);
+static CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+/// Bumped per lookup, so a slow answer doesn't replace a newer one
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// How a paragraph is drawn
+#[derive(Clone, Copy)]
+enum Style {
+    Word,
+    Phonetic,
+    PartOfSpeech,
+    Definition,
+    Synonyms,
+}
+
+/// Look up `word` in the background and show the popup
+pub fn show_definition(word: &str) -> Result<()> {
+    let config = get_window_state()
+        .map(|s| s.read().config.dictionary.clone())
+        .unwrap_or_default();
+    let word = word.trim().to_string();
+    *CONTENT.lock() = Some(Content {
+        word: word.clone(),
+        result: None,
+        scroll: 0,
+        height: 0,
+    });
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    std::thread::spawn(move || {
+        let result = crate::dictionary::lookup(&config, &word);
+        if let Err(e) = &result {
+            log::info!("Dictionary lookup for {}: {}", word, e);
+        }
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Some(content) = CONTENT.lock().as_mut() {
+            content.result = Some(result);
+        }
+        let hwnd = HWND(POPUP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+        if !hwnd.0.is_null() {
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_LOOKED_UP, WPARAM(0), LPARAM(0));
+            }
+        }
+    });
+
+    unsafe { show() }
+}
+
+unsafe fn show() -> Result<()> {
+    let mut hwnd = HWND(POPUP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(POPUP_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        POPUP.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    position(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOW);
+    let _ = SetForegroundWindow(hwnd);
+    let _ = SetFocus(hwnd);
+    let _ = InvalidateRect(hwnd, None, false);
+    crate::sounds::play_event(crate::sounds::SoundEvent::PopupOpen);
+    Ok(())
+}
+
+/// Center the popup near the top of the foreground window's monitor
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let width = scale(WIDTH, dpi);
+    let height = scale(HEIGHT, dpi);
+
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcWork;
+    let x = area.left + (area.right - area.left - width) / 2;
+    let y = area.top + scale(48, dpi);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(16, dpi), scale(16, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+/// The popup's text as styled paragraphs, top to bottom
+fn paragraphs(word: &str, result: &Option<Result<Entry, String>>) -> Vec<(Style, String)> {
+    let mut out = Vec::new();
+    match result {
+        None => {
+            out.push((Style::Word, word.to_string()));
+            out.push((Style::Phonetic, "Looking up...".to_string()));
+        }
+        Some(Err(e)) => {
+            out.push((Style::Word, word.to_string()));
+            out.push((Style::Phonetic, e.clone()));
+        }
+        Some(Ok(entry)) => {
+            out.push((Style::Word, entry.word.clone()));
+            if let Some(phonetic) = &entry.phonetic {
+                out.push((Style::Phonetic, phonetic.clone()));
+            }
+            for meaning in &entry.meanings {
+                out.push((Style::PartOfSpeech, meaning.part_of_speech.clone()));
+                for (i, definition) in meaning.definitions.iter().take(MAX_DEFINITIONS).enumerate() {
+                    out.push((Style::Definition, format!("{}. {}", i + 1, definition)));
+                }
+                if !meaning.synonyms.is_empty() {
+                    let synonyms: Vec<&str> = meaning.synonyms.iter().take(MAX_SYNONYMS).map(String::as_str).collect();
+                    out.push((Style::Synonyms, format!("Synonyms: {}", synonyms.join(", "))));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Draw the paragraphs from `top`; returns the height they take
+unsafe fn draw_paragraphs(hdc: HDC, paragraphs: &[(Style, String)], theme: &Theme, client: &RECT, top: i32, dpi: u32) -> i32 {
+    let family = font_family();
+    let fonts = [
+        create_font(&family, scale(24, dpi), true),
+        create_font(&family, scale(13, dpi), false),
+        create_font(&family, scale(13, dpi), true),
+        create_font(&family, scale(14, dpi), false),
+    ];
+    let old_font = SelectObject(hdc, fonts[0]);
+    let padding = scale(PADDING, dpi);
+
+    let mut y = top;
+    for (i, (style, text)) in paragraphs.iter().enumerate() {
+        let (font, color, gap_before) = match style {
+            Style::Word => (fonts[0], theme.text_primary, 0),
+            Style::Phonetic => (fonts[1], theme.text_secondary, 2),
+            Style::PartOfSpeech => (fonts[2], theme.accent, 14),
+            Style::Definition => (fonts[3], theme.text_primary, 4),
+            Style::Synonyms => (fonts[1], theme.text_secondary, 6),
+        };
+        if i > 0 {
+            y += scale(gap_before, dpi);
+        }
+        SelectObject(hdc, font);
+        SetTextColor(hdc, color.colorref());
+
+        let mut wide: Vec<u16> = text.encode_utf16().collect();
+        let format = DT_WORDBREAK | DT_EDITCONTROL | DT_NOPREFIX;
+        let mut rect = RECT { left: padding, top: y, right: client.right - padding, bottom: y };
+        DrawTextW(hdc, &mut wide, &mut rect, format | DT_CALCRECT);
+        if rect.bottom > client.top && rect.top < client.bottom {
+            DrawTextW(hdc, &mut wide, &mut rect, format);
+        }
+        y = rect.bottom;
+    }
+
+    SelectObject(hdc, old_font);
+    for font in fonts {
+        let _ = DeleteObject(font);
+    }
+    y - top
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(Theme::dark);
+    let (word, result, scroll) = CONTENT
+        .lock()
+        .as_ref()
+        .map(|c| (c.word.clone(), c.result.clone(), c.scroll))
+        .unwrap_or_default();
+
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+
+    // Paint off-screen; scrolling redraws everything
+    let mem = CreateCompatibleDC(hdc);
+    let bitmap = CreateCompatibleBitmap(hdc, rect.right, rect.bottom);
+    let old_bitmap = SelectObject(mem, bitmap);
+
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(mem, &rect, brush);
+    let _ = DeleteObject(brush);
+    SetBkMode(mem, TRANSPARENT);
+
+    let padding = scale(PADDING, dpi);
+    let height = draw_paragraphs(mem, &paragraphs(&word, &result), &theme, &rect, padding - scroll, dpi);
+    if let Some(content) = CONTENT.lock().as_mut() {
+        content.height = height + padding * 2;
+    }
+
+    let border = CreateSolidBrush(theme.accent.colorref());
+    FrameRect(mem, &rect, border);
+    let _ = DeleteObject(border);
+
+    let _ = BitBlt(hdc, 0, 0, rect.right, rect.bottom, mem, 0, 0, SRCCOPY);
+    SelectObject(mem, old_bitmap);
+    let _ = DeleteObject(bitmap);
+    let _ = DeleteDC(mem);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+/// Scroll by `delta` pixels, keeping the text in view
+unsafe fn scroll_by(hwnd: HWND, delta: i32) {
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    if let Some(content) = CONTENT.lock().as_mut() {
+        let max = (content.height - rect.bottom).max(0);
+        content.scroll = (content.scroll + delta).clamp(0, max);
+    }
+    let _ = InvalidateRect(hwnd, None, false);
+}
+
+fn close(hwnd: HWND) {
+    // Destroying re-enters through WM_KILLFOCUS; only the first call counts
+    if POPUP.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return;
+    }
+    *CONTENT.lock() = None;
+    unsafe {
+        let _ = DestroyWindow(hwnd);
+    }
+    crate::sounds::play_event(crate::sounds::SoundEvent::PopupClose);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(POPUP_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_DROPSHADOW,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_LOOKED_UP => {
+            let _ = InvalidateRect(hwnd, None, false);
+            LRESULT(0)
+        }
+
+        WM_MOUSEWHEEL => {
+            let notches = (wparam.0 >> 16) as i16 as i32 / WHEEL_DELTA as i32;
+            scroll_by(hwnd, -notches * scale(SCROLL_STEP, GetDpiForWindow(hwnd).max(96)));
+            LRESULT(0)
+        }
+
+        WM_KEYDOWN => {
+            let step = scale(SCROLL_STEP, GetDpiForWindow(hwnd).max(96));
+            match wparam.0 as u32 {
+                0x1B | 0x0D => close(hwnd), // ESC, ENTER
+                0x26 => scroll_by(hwnd, -step), // UP
+                0x28 => scroll_by(hwnd, step), // DOWN
+                _ => {}
+            }
+            LRESULT(0)
+        }
+
+        WM_KILLFOCUS => {
+            close(hwnd);
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = POPUP.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
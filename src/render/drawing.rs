@@ -1,11 +1,16 @@
 use windows::Win32::Graphics::Gdi::*;
-use windows::Win32::Foundation::COLORREF;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use windows::Win32::Foundation::LPARAM;
 
-use crate::theme::Theme;
+use crate::config::BarPosition;
+use crate::theme::{Color, Theme};
 use crate::utils::Rect;
 
 /// Draw the background
-pub fn draw_background(hdc: HDC, rect: &Rect, theme: &Theme) {
+pub fn draw_background(hdc: HDC, rect: &Rect, theme: &Theme, corner_radius: Option<i32>) {
     unsafe {
         let brush = CreateSolidBrush(theme.background.colorref());
         let win_rect = windows::Win32::Foundation::RECT {
@@ -16,22 +21,425 @@ pub fn draw_background(hdc: HDC, rect: &Rect, theme: &Theme) {
         };
         FillRect(hdc, &win_rect, brush);
         let _ = DeleteObject(brush);
+    }
+    draw_bar_border(hdc, rect, theme, corner_radius);
+}
 
-        // Draw subtle bottom border
+/// Draw the subtle border of the bar: along the edge facing the desktop, or all
+/// the way around a floating bar with rounded corners
+pub fn draw_bar_border(hdc: HDC, rect: &Rect, theme: &Theme, corner_radius: Option<i32>) {
+    unsafe {
         let border_brush = CreateSolidBrush(theme.border.colorref());
-        let border_rect = windows::Win32::Foundation::RECT {
-            left: 0,
-            top: rect.height - 1,
-            right: rect.width,
-            bottom: rect.height,
-        };
-        FillRect(hdc, &border_rect, border_brush);
+        match corner_radius {
+            Some(radius) => {
+                // Same shape as the window region, so the border follows its edge
+                let region = CreateRoundRectRgn(0, 0, rect.width + 1, rect.height + 1, radius * 2, radius * 2);
+                let _ = FrameRgn(hdc, region, border_brush, 1, 1);
+                let _ = DeleteObject(region);
+            }
+            None => {
+                // The layout's top faces the desktop on a bottom bar and, once
+                // turned, on a left one
+                let top_edge = crate::window::state::get_window_state()
+                    .map(|s| matches!(s.read().config.appearance.position, BarPosition::Bottom | BarPosition::Left))
+                    .unwrap_or(false);
+                let y = if top_edge { 0 } else { rect.height - 1 };
+                let border_rect = windows::Win32::Foundation::RECT {
+                    left: 0,
+                    top: y,
+                    right: rect.width,
+                    bottom: y + 1,
+                };
+                FillRect(hdc, &border_rect, border_brush);
+            }
+        }
         let _ = DeleteObject(border_brush);
     }
 }
 
-/// Create a font with optimized rendering for modern UI (macOS-inspired)
+/// Whether a hit-test id gets a pill; single launcher and task icons sit
+/// side by side without spacing, so they go without
+fn has_pill(id: &str) -> bool {
+    crate::modules::launcher::item_index(id).is_none() && crate::modules::tasks::item_hwnd(id).is_none()
+}
+
+/// A module's pill: `padding` wider than the module on each side, and as tall as
+/// the bar less a small inset
+fn pill_rect(bounds: &Rect, bar_rect: &Rect, padding: i32, dpi: u32) -> Rect {
+    let inset = scale(4, dpi);
+    Rect::new(
+        bounds.x - padding,
+        inset,
+        bounds.width + padding * 2,
+        bar_rect.height - inset * 2,
+    )
+}
+
+/// Draw a rounded background behind every module (`[appearance.pills]`), and
+/// one shared background behind each module group whether pills are on or not.
+/// Drawn before the modules, from the bounds of the last frame.
+pub fn draw_module_pills(
+    hdc: HDC,
+    bar_rect: &Rect,
+    theme: &Theme,
+    module_bounds: &std::collections::HashMap<String, Rect>,
+    dpi: u32,
+) {
+    let Some(config) = crate::window::state::get_window_state().map(|s| s.read().config.clone()) else {
+        return;
+    };
+    let pills = &config.appearance.pills;
+
+    let color = theme.background.blend(&theme.text_primary, pills.opacity);
+    let padding = scale(pills.padding as i32, dpi);
+    let diameter = scale(pills.radius as i32 * 2, dpi);
+    unsafe {
+        let brush = CreateSolidBrush(color.colorref());
+        for group in &config.modules.groups {
+            let id = group.id();
+            let Some(header) = module_bounds.get(&id) else {
+                continue;
+            };
+            let mut span = *header;
+            for bounds in group.modules.iter().filter_map(|m| module_bounds.get(m)) {
+                let right = span.right().max(bounds.right());
+                span.x = span.x.min(bounds.x);
+                span.width = right - span.x;
+            }
+            let pill = pill_rect(&span, bar_rect, padding, dpi);
+            let region = CreateRoundRectRgn(pill.x, pill.y, pill.right() + 1, pill.bottom() + 1, diameter, diameter);
+            let _ = FillRgn(hdc, region, brush);
+            let _ = DeleteObject(region);
+        }
+        for (id, bounds) in module_bounds {
+            if !pills.enabled || !has_pill(id) || config.modules.group(id).is_some() || config.modules.group_of(id).is_some() {
+                continue;
+            }
+            let pill = pill_rect(bounds, bar_rect, padding, dpi);
+            let region = CreateRoundRectRgn(pill.x, pill.y, pill.right() + 1, pill.bottom() + 1, diameter, diameter);
+            let _ = FillRgn(hdc, region, brush);
+            let _ = DeleteObject(region);
+        }
+        let _ = DeleteObject(brush);
+    }
+}
+
+/// Draw a rounded background behind the module under the mouse, stronger while
+/// it's pressed, in the shape of its pill when pills are on. Drawn before the
+/// modules, from the bounds of the last frame.
+pub fn draw_module_highlight(
+    hdc: HDC,
+    bar_rect: &Rect,
+    theme: &Theme,
+    module_bounds: &std::collections::HashMap<String, Rect>,
+    dpi: u32,
+) {
+    let Some((hovered, pressed, pills)) = crate::window::state::get_window_state().and_then(|s| {
+        let state = s.read();
+        if !state.config.appearance.hover_highlight || state.dragging_module.is_some() {
+            return None;
+        }
+        let hovered = state.hover_module.clone()?;
+        let pressed = state.clicked_module.as_deref() == Some(hovered.as_str());
+        let pills = state.config.appearance.pills.clone();
+        Some((hovered, pressed, pills))
+    }) else {
+        return;
+    };
+    let Some(bounds) = module_bounds.get(&hovered) else {
+        return;
+    };
+
+    let color = if pressed { theme.background_active } else { theme.background_hover };
+    // Inset like the app menu button; region edges are exclusive, hence the +1
+    let (shape, diameter) = if pills.enabled && has_pill(&hovered) {
+        let pill = pill_rect(bounds, bar_rect, scale(pills.padding as i32, dpi), dpi);
+        (pill, scale(pills.radius as i32 * 2, dpi))
+    } else {
+        let inset = Rect::new(bounds.x + 2, bounds.y + 1, bounds.width - 4, bounds.height - 2);
+        (inset, scale(6, dpi))
+    };
+    unsafe {
+        let brush = CreateSolidBrush(color.colorref());
+        let region = CreateRoundRectRgn(
+            shape.x,
+            shape.y,
+            shape.right() + 1,
+            shape.bottom() + 1,
+            diameter,
+            diameter,
+        );
+        let _ = FillRgn(hdc, region, brush);
+        let _ = DeleteObject(region);
+        let _ = DeleteObject(brush);
+    }
+}
+
+/// Draw attention effects: a glow around modules that asked for attention and a
+/// pulsing bottom edge while an interruptive request is active
+pub fn draw_attention(
+    hdc: HDC,
+    rect: &Rect,
+    theme: &Theme,
+    module_bounds: &std::collections::HashMap<String, Rect>,
+    dpi: u32,
+) {
+    let (config, reduce_motion) = crate::window::state::get_window_state()
+        .map(|s| {
+            let config = &s.read().config;
+            (
+                config.attention.clone(),
+                crate::accessibility::reduce_motion(&config.appearance),
+            )
+        })
+        .unwrap_or_default();
+    if !config.enabled {
+        return;
+    }
+    // With reduced motion the effects are drawn at full strength instead of pulsing
+    let phase = |since| {
+        if reduce_motion {
+            1.0
+        } else {
+            crate::attention::pulse_phase(since)
+        }
+    };
+
+    unsafe {
+        if config.module_glow {
+            for attention in crate::attention::active() {
+                let Some(bounds) = module_bounds.get(&attention.module_id) else {
+                    continue;
+                };
+                let strength = 0.5 + 0.5 * phase(attention.since);
+                let color = theme.background.blend(&attention.level.color(theme), strength);
+                let pen = CreatePen(PS_SOLID, scale(2, dpi).max(1), color.colorref());
+                let old_pen = SelectObject(hdc, pen);
+                let old_brush = SelectObject(hdc, GetStockObject(NULL_BRUSH));
+                let radius = scale(6, dpi);
+                let _ = RoundRect(
+                    hdc,
+                    bounds.x,
+                    bounds.y + 1,
+                    bounds.x + bounds.width,
+                    bounds.y + bounds.height - 1,
+                    radius,
+                    radius,
+                );
+                SelectObject(hdc, old_brush);
+                SelectObject(hdc, old_pen);
+                let _ = DeleteObject(pen);
+            }
+        }
+
+        if config.edge_pulse {
+            if let Some(attention) = crate::attention::pulsing() {
+                let strength = phase(attention.since);
+                let color = theme.border.blend(&attention.level.color(theme), strength);
+                let brush = CreateSolidBrush(color.colorref());
+                let edge = windows::Win32::Foundation::RECT {
+                    left: 0,
+                    top: rect.height - scale(2, dpi).max(1),
+                    right: rect.width,
+                    bottom: rect.height,
+                };
+                FillRect(hdc, &edge, brush);
+                let _ = DeleteObject(brush);
+            }
+        }
+    }
+}
+
+/// How long "Copied" covers a module after Ctrl+click
+const COPIED_FLASH_MS: u128 = 900;
+
+/// Whether attention effects or the "Copied" flash are on screen, or the flash
+/// has only just ended, so the whole bar has to keep repainting
+pub fn effects_animating() -> bool {
+    let attention = crate::window::state::get_window_state()
+        .map(|s| s.read().config.attention.enabled)
+        .unwrap_or(false)
+        && !crate::attention::active().is_empty();
+    let flash = crate::window::state::get_window_state()
+        .and_then(|s| s.read().copied_flash.as_ref().map(|(_, since)| since.elapsed().as_millis()))
+        .is_some_and(|elapsed| elapsed < COPIED_FLASH_MS + 200);
+    attention || flash
+}
+
+/// Grey out modules that offline mode has stopped by washing them over with
+/// the bar's background
+pub fn draw_offline(hdc: HDC, theme: &Theme, bounds: &[Rect]) {
+    if bounds.is_empty() {
+        return;
+    }
+    unsafe {
+        let source = CreateCompatibleDC(hdc);
+        let pixel = CreateCompatibleBitmap(hdc, 1, 1);
+        let old = SelectObject(source, pixel);
+        SetPixel(source, 0, 0, theme.background.colorref());
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: 150,
+            AlphaFormat: 0,
+        };
+        for rect in bounds {
+            let _ = AlphaBlend(hdc, rect.x, rect.y, rect.width, rect.height, source, 0, 0, 1, 1, blend);
+        }
+        SelectObject(source, old);
+        let _ = DeleteObject(pixel);
+        let _ = DeleteDC(source);
+    }
+}
+
+/// Outline the module the keyboard is on (see `window::focus`)
+pub fn draw_focus_ring(
+    hdc: HDC,
+    theme: &Theme,
+    module_bounds: &std::collections::HashMap<String, Rect>,
+    dpi: u32,
+) {
+    let focused = crate::window::state::get_window_state().and_then(|s| s.read().focused_module.clone());
+    let Some(bounds) = focused.and_then(|id| module_bounds.get(&id).copied()) else {
+        return;
+    };
+
+    unsafe {
+        let pen = CreatePen(PS_SOLID, scale(2, dpi), theme.accent.colorref());
+        let old_pen = SelectObject(hdc, pen);
+        let old_brush = SelectObject(hdc, GetStockObject(NULL_BRUSH));
+        let radius = scale(6, dpi);
+        let _ = RoundRect(
+            hdc,
+            bounds.x,
+            bounds.y + 1,
+            bounds.x + bounds.width,
+            bounds.y + bounds.height - 1,
+            radius,
+            radius,
+        );
+        SelectObject(hdc, old_brush);
+        SelectObject(hdc, old_pen);
+        let _ = DeleteObject(pen);
+    }
+}
+
+/// Cover a module with a "Copied" pill right after its value was copied
+pub fn draw_copied_flash(
+    hdc: HDC,
+    theme: &Theme,
+    module_bounds: &std::collections::HashMap<String, Rect>,
+    dpi: u32,
+) {
+    let flash = crate::window::state::get_window_state().and_then(|s| s.read().copied_flash.clone());
+    let Some((module_id, since)) = flash else {
+        return;
+    };
+    let Some(bounds) = module_bounds.get(&module_id) else {
+        return;
+    };
+    if since.elapsed().as_millis() >= COPIED_FLASH_MS {
+        return;
+    }
+
+    unsafe {
+        let brush = CreateSolidBrush(theme.accent.colorref());
+        let pen = CreatePen(PS_SOLID, 1, theme.accent.colorref());
+        let old_brush = SelectObject(hdc, brush);
+        let old_pen = SelectObject(hdc, pen);
+        let radius = scale(6, dpi);
+        let _ = RoundRect(
+            hdc,
+            bounds.x,
+            bounds.y + 1,
+            bounds.x + bounds.width,
+            bounds.y + bounds.height - 1,
+            radius,
+            radius,
+        );
+        SelectObject(hdc, old_pen);
+        SelectObject(hdc, old_brush);
+        let _ = DeleteObject(pen);
+        let _ = DeleteObject(brush);
+
+        let font = create_font("Segoe UI Variable Text", scale(12, dpi), true);
+        let old_font = SelectObject(hdc, font);
+        let text = "Copied";
+        let (w, h) = measure_text(hdc, text);
+        let text_color = if theme.accent.is_dark() { Color::rgb(255, 255, 255) } else { Color::rgb(0, 0, 0) };
+        SetTextColor(hdc, text_color.colorref());
+        SetBkMode(hdc, TRANSPARENT);
+        draw_text(
+            hdc,
+            bounds.x + (bounds.width - w) / 2,
+            bounds.y + (bounds.height - h) / 2,
+            text,
+        );
+        SelectObject(hdc, old_font);
+        let _ = DeleteObject(font);
+    }
+}
+
+/// Installed fonts tried in order when a text font isn't installed
+const TEXT_FALLBACKS: &[&str] = &["Segoe UI Variable Text", "Segoe UI"];
+/// Icon fonts tried in order; Segoe Fluent Icons only ships with Windows 11
+const ICON_FALLBACKS: &[&str] = &["Segoe Fluent Icons", "Segoe MDL2 Assets"];
+
+/// Whether each font family asked for is installed, checked once per family
+static INSTALLED_FONTS: Lazy<Mutex<HashMap<String, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Missing families already logged
+static MISSING_FONTS_REPORTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Whether a font family is installed
+fn is_font_installed(family: &str) -> bool {
+    unsafe extern "system" fn found(_: *const LOGFONTW, _: *const TEXTMETRICW, _: u32, lparam: LPARAM) -> i32 {
+        *(lparam.0 as *mut bool) = true;
+        0
+    }
+
+    if let Some(installed) = INSTALLED_FONTS.lock().get(family) {
+        return *installed;
+    }
+    let mut installed = false;
+    unsafe {
+        let mut lf = LOGFONTW {
+            lfCharSet: DEFAULT_CHARSET,
+            ..Default::default()
+        };
+        let family_wide: Vec<u16> = family.encode_utf16().collect();
+        let face_len = family_wide.len().min(31);
+        lf.lfFaceName[..face_len].copy_from_slice(&family_wide[..face_len]);
+        let hdc = GetDC(None);
+        EnumFontFamiliesExW(hdc, &lf, Some(found), LPARAM(&mut installed as *mut bool as isize), 0);
+        ReleaseDC(None, hdc);
+    }
+    INSTALLED_FONTS.lock().insert(family.to_string(), installed);
+    installed
+}
+
+/// The family to create a font with: `family` when installed, otherwise the
+/// first installed fallback of its kind
+pub fn resolve_font_family(family: &str) -> &str {
+    if is_font_installed(family) {
+        return family;
+    }
+    let is_icon_font = ICON_FALLBACKS.contains(&family) || family.contains("Icons");
+    let fallbacks = if is_icon_font { ICON_FALLBACKS } else { TEXT_FALLBACKS };
+    let fallback = fallbacks
+        .iter()
+        .copied()
+        .find(|f| *f != family && is_font_installed(f))
+        .unwrap_or(fallbacks[fallbacks.len() - 1]);
+    if MISSING_FONTS_REPORTED.lock().insert(family.to_string()) {
+        log::warn!("Font \"{}\" is not installed, using \"{}\"", family, fallback);
+    }
+    fallback
+}
+
+/// Create a font with optimized rendering for modern UI (macOS-inspired). A
+/// family that isn't installed falls back to a similar one that is.
 pub fn create_font(family: &str, size: i32, bold: bool) -> HFONT {
+    let family = resolve_font_family(family);
     unsafe {
         let family_wide: Vec<u16> = family.encode_utf16().chain(std::iter::once(0)).collect();
         let mut lf = LOGFONTW {
@@ -52,8 +460,110 @@ pub fn create_font(family: &str, size: i32, bold: bool) -> HFONT {
     }
 }
 
-/// Measure text dimensions
+/// Text extents kept before the least recently used are dropped
+const TEXT_CACHE_CAPACITY: usize = 1024;
+
+/// Face name, height, weight and italic of a font
+type FontKey = ([u16; 32], i32, i32, u8);
+
+/// Measured text extents by font and string, with when each was last used
+#[derive(Default)]
+struct TextCache {
+    entries: HashMap<(FontKey, String), ((i32, i32), u64)>,
+    clock: u64,
+}
+
+/// Fonts are created per frame, so extents are keyed by what the font is
+/// rather than its handle
+static TEXT_EXTENTS: Lazy<Mutex<TextCache>> = Lazy::new(|| Mutex::new(TextCache::default()));
+
+/// Forget every measured extent, e.g. after the DPI or configured fonts changed
+pub fn clear_text_cache() {
+    let mut cache = TEXT_EXTENTS.lock();
+    cache.entries.clear();
+    cache.clock = 0;
+}
+
+/// Describe the font selected into `hdc`
+unsafe fn selected_font(hdc: HDC) -> Option<FontKey> {
+    let font = GetCurrentObject(hdc, OBJ_FONT);
+    let mut lf = LOGFONTW::default();
+    let size = std::mem::size_of::<LOGFONTW>() as i32;
+    if GetObjectW(font, size, Some(&mut lf as *mut LOGFONTW as *mut std::ffi::c_void)) == 0 {
+        return None;
+    }
+    Some((lf.lfFaceName, lf.lfHeight, lf.lfWeight, lf.lfItalic))
+}
+
+/// Draw digits in equal-width cells (`appearance.tabular_numbers`)
+static TABULAR_NUMBERS: AtomicBool = AtomicBool::new(true);
+
+pub fn set_tabular_numbers(enabled: bool) {
+    TABULAR_NUMBERS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether `text` gets tabular digits. Text with right-to-left script in it is
+/// drawn in one piece so Windows can order it.
+fn tabular(text: &str) -> bool {
+    TABULAR_NUMBERS.load(Ordering::Relaxed)
+        && text.bytes().any(|b| b.is_ascii_digit())
+        && !crate::utils::has_rtl(text)
+}
+
+/// Width of a digit cell in the selected font: its widest digit
+fn digit_cell(hdc: HDC) -> i32 {
+    const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+    DIGITS.iter().map(|d| measure_plain(hdc, d).0).max().unwrap_or(0)
+}
+
+/// Measure text dimensions. With tabular numbers on, every digit counts as
+/// wide as the widest one, matching `draw_text`.
 pub fn measure_text(hdc: HDC, text: &str) -> (i32, i32) {
+    let (width, height) = measure_plain(hdc, text);
+    if !tabular(text) {
+        return (width, height);
+    }
+    let cell = digit_cell(hdc);
+    let mut buf = [0u8; 4];
+    let padding: i32 = text
+        .chars()
+        .filter(char::is_ascii_digit)
+        .map(|d| cell - measure_plain(hdc, d.encode_utf8(&mut buf)).0)
+        .sum();
+    (width + padding, height)
+}
+
+/// Measure text as the font lays it out, from the cache when the same string
+/// was measured in the same font before
+fn measure_plain(hdc: HDC, text: &str) -> (i32, i32) {
+    let Some(font) = (unsafe { selected_font(hdc) }) else {
+        return measure_text_uncached(hdc, text);
+    };
+    let key = (font, text.to_string());
+    {
+        let mut cache = TEXT_EXTENTS.lock();
+        cache.clock += 1;
+        let now = cache.clock;
+        if let Some((extent, used)) = cache.entries.get_mut(&key) {
+            *used = now;
+            return *extent;
+        }
+    }
+
+    let extent = measure_text_uncached(hdc, text);
+    let mut cache = TEXT_EXTENTS.lock();
+    if cache.entries.len() >= TEXT_CACHE_CAPACITY {
+        let oldest = cache.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            cache.entries.remove(&oldest);
+        }
+    }
+    let now = cache.clock;
+    cache.entries.insert(key, (extent, now));
+    extent
+}
+
+fn measure_text_uncached(hdc: HDC, text: &str) -> (i32, i32) {
     unsafe {
         let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
         let mut size = windows::Win32::Foundation::SIZE::default();
@@ -62,17 +572,44 @@ pub fn measure_text(hdc: HDC, text: &str) -> (i32, i32) {
     }
 }
 
-/// Draw text at position
+/// Draw text at position; with tabular numbers on, each digit is centered in
+/// a cell as wide as the widest digit
 pub fn draw_text(hdc: HDC, x: i32, y: i32, text: &str) {
+    if !tabular(text) {
+        draw_plain(hdc, x, y, text);
+        return;
+    }
+    let cell = digit_cell(hdc);
+    let mut x = x;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let run = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        if run > 0 {
+            draw_plain(hdc, x, y, &rest[..run]);
+            x += measure_plain(hdc, &rest[..run]).0;
+            rest = &rest[run..];
+            continue;
+        }
+        let digit = &rest[..1];
+        draw_plain(hdc, x + (cell - measure_plain(hdc, digit).0) / 2, y, digit);
+        x += cell;
+        rest = &rest[1..];
+    }
+}
+
+/// Draw text in one piece; Arabic or Hebrew text is laid out right to left so
+/// punctuation and embedded numbers land where a reader expects them
+fn draw_plain(hdc: HDC, x: i32, y: i32, text: &str) {
     unsafe {
-        let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
-        let _ = TextOutW(hdc, x, y, &wide[..wide.len() - 1]);
+        let wide: Vec<u16> = text.encode_utf16().collect();
+        let options = if crate::utils::is_rtl(text) { ETO_RTLREADING } else { ETO_OPTIONS(0) };
+        let _ = ExtTextOutW(hdc, x, y, options, None, windows::core::PCWSTR(wide.as_ptr()), wide.len() as u32, None);
     }
 }
 
 /// Scale a value by DPI
 pub fn scale(value: i32, dpi: u32) -> i32 {
-    (value as f32 * dpi as f32 / 96.0) as i32
+    crate::utils::scale_by_dpi(value, dpi)
 }
 
 /// Downsample a series of values to fit within max_points by averaging chunks
@@ -103,47 +640,3 @@ pub fn downsample_values(values: Vec<f32>, max_points: usize) -> Vec<f32> {
     
     out
 }
-
-/// Draw a line graph from values (0-100) within a rectangle
-pub fn draw_line_graph(hdc: HDC, values: &[f32], rect: &Rect, padding: i32, color: COLORREF) {
-    if values.is_empty() {
-        return;
-    }
-    
-    let inner_w = rect.width - padding * 2;
-    let inner_h = rect.height - 4;
-    
-    let mut points: Vec<windows::Win32::Foundation::POINT> = Vec::with_capacity(values.len());
-    let step = if values.len() > 1 { inner_w as f32 / (values.len() - 1) as f32 } else { 0.0 };
-    
-    for (i, v) in values.iter().enumerate() {
-        let clamped = v.clamp(0.0, 100.0) / 100.0;
-        let px = rect.x + padding + (i as f32 * step) as i32;
-        let py = rect.y + 2 + ((1.0 - clamped) * inner_h as f32) as i32;
-        points.push(windows::Win32::Foundation::POINT { x: px, y: py });
-    }
-    
-    // Ensure at least 2 points for drawing
-    if points.len() == 1 {
-        points.push(points[0]);
-    }
-    
-    unsafe {
-        use windows::Win32::Graphics::Gdi::{CreatePen, PS_SOLID, SelectObject, MoveToEx, LineTo};
-        let pen = CreatePen(PS_SOLID, 1, color);
-        let old_pen = SelectObject(hdc, pen);
-        
-        let mut first = true;
-        for p in &points {
-            if first {
-                let _ = MoveToEx(hdc, p.x, p.y, Some(std::ptr::null_mut()));
-                first = false;
-            } else {
-                let _ = LineTo(hdc, p.x, p.y);
-            }
-        }
-        
-        let _ = SelectObject(hdc, old_pen);
-        let _ = DeleteObject(pen);
-    }
-}
\ No newline at end of file
@@ -1,30 +1,69 @@
-//! Dropdown menu system for TopBar
-//! 
-//! Provides macOS-style dropdown menus for various modules.
+//! Themed dropdown menus for modules
+//!
+//! Module dropdowns drawn like the rest of the bar instead of as classic Win32
+//! menus: a rounded panel in the theme's colors with rows that can carry a
+//! check mark, an icon, a shortcut hint or a submenu, plus separators and
+//! sliders that report their value while dragged. [`track_dropdown`] runs
+//! modally and returns the chosen command, like `TrackPopupMenu` with
+//! `TPM_RETURNCMD`, and [`items_from_menu`] reads a menu built with
+//! `AppendMenuW`, so existing module menus show up themed unchanged.
+//!
+//! The first panel captures the mouse while open; a submenu opens beside its
+//! row on hover. The arrow keys, Enter and Escape work while the bar has the
+//! keyboard.
 
-#![allow(dead_code)]
-
-use windows::Win32::Foundation::{HWND, RECT, LPARAM, WPARAM, LRESULT, COLORREF};
-use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::Win32::UI::Input::KeyboardAndMouse::{SetCapture, ReleaseCapture};
-use windows::Win32::Graphics::Gdi::*;
-use windows::core::PCWSTR;
 use anyhow::Result;
+use std::cell::RefCell;
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    ReleaseCapture, SetCapture, VIRTUAL_KEY, VK_DOWN, VK_ESCAPE, VK_LEFT, VK_RETURN, VK_RIGHT, VK_SPACE, VK_UP,
+};
+use windows::Win32::UI::WindowsAndMessaging::*;
 
-use crate::theme::{Theme, Color};
-use crate::utils::{to_wide_string, to_pcwstr};
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::theme::{Color, Theme};
+use crate::utils::{to_pcwstr, to_wide_string};
+use crate::window::state::get_window_state;
 
-/// Menu item data
+/// Window class name for dropdown panels
+const DROPDOWN_CLASS: &str = "TopBarDropdownClass";
+
+/// Sizes before DPI scaling
+const ITEM_HEIGHT: i32 = 28;
+const SEPARATOR_HEIGHT: i32 = 9;
+const SLIDER_HEIGHT: i32 = 34;
+const PADDING: i32 = 6;
+const MIN_WIDTH: i32 = 200;
+/// Width of the check mark and icon columns
+const COLUMN: i32 = 24;
+const SLIDER_WIDTH: i32 = 140;
+/// Room for a slider's value after its track
+const SLIDER_VALUE_WIDTH: i32 = 36;
+const ICON_SIZE: i32 = 16;
+
+/// How far the arrow keys move a slider
+const SLIDER_STEP: u32 = 5;
+
+/// A row of a dropdown
 #[derive(Debug, Clone)]
 pub struct DropdownItem {
     pub id: u32,
     pub label: String,
+    /// Glyph drawn in the icon column
     pub icon: Option<String>,
+    /// 32-bit premultiplied bitmap drawn in the icon column; owned by the caller
+    pub bitmap: Option<HBITMAP>,
     pub shortcut: Option<String>,
     pub is_separator: bool,
     pub is_disabled: bool,
     pub is_checked: bool,
     pub submenu: Option<Vec<DropdownItem>>,
+    /// Value (0-100) of a slider row
+    pub slider: Option<u32>,
+    /// Label color instead of the theme's, e.g. for a warning
+    pub color: Option<Color>,
 }
 
 impl DropdownItem {
@@ -34,25 +73,30 @@ impl DropdownItem {
             id,
             label: label.to_string(),
             icon: None,
+            bitmap: None,
             shortcut: None,
             is_separator: false,
             is_disabled: false,
             is_checked: false,
             submenu: None,
+            slider: None,
+            color: None,
         }
     }
 
     /// Create a separator
     pub fn separator() -> Self {
         Self {
-            id: 0,
-            label: String::new(),
-            icon: None,
-            shortcut: None,
             is_separator: true,
-            is_disabled: false,
-            is_checked: false,
-            submenu: None,
+            ..Self::new(0, "")
+        }
+    }
+
+    /// Create a slider; its command id comes with every value while dragged
+    pub fn slider(id: u32, label: &str, value: u32) -> Self {
+        Self {
+            slider: Some(value.min(100)),
+            ..Self::new(id, label)
         }
     }
 
@@ -74,6 +118,12 @@ impl DropdownItem {
         self
     }
 
+    /// Draw the label in `color`
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
     /// Mark as checked
     pub fn checked(mut self, checked: bool) -> Self {
         self.is_checked = checked;
@@ -85,365 +135,794 @@ impl DropdownItem {
         self.submenu = Some(items);
         self
     }
-}
-
-/// Dropdown menu window
-pub struct DropdownMenu {
-    hwnd: HWND,
-    items: Vec<DropdownItem>,
-    item_height: i32,
-    padding: i32,
-    hover_index: Option<usize>,
-    theme: Theme,
-    on_select: Option<Box<dyn Fn(u32) + Send + Sync>>,
-}
-
-/// Window class name for dropdown menus
-const DROPDOWN_CLASS: &str = "TopBarDropdownClass";
 
-impl DropdownMenu {
-    /// Create a new dropdown menu
-    pub fn new(parent: HWND, items: Vec<DropdownItem>, theme: &Theme) -> Result<Self> {
-        // Register window class if needed
-        Self::ensure_class_registered()?;
-
-        let item_height = 36;  // Taller items for better touch targets
-        let padding = 10;  // More breathing room
-        
-        // Calculate menu size
-        let width = 280;  // Wider for modern look
-        let height = Self::calculate_height(&items, item_height, padding);
-
-        // Create popup window
-        let hwnd = unsafe {
-            let class = to_wide_string(DROPDOWN_CLASS);
-            let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
-
-            CreateWindowExW(
-                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
-                to_pcwstr(&class),
-                PCWSTR::null(),
-                WS_POPUP,
-                0, 0, width, height,
-                parent,
-                None,
-                hinstance,
-                None,
-            )?
-        };
+    /// Whether the row can be hovered and chosen
+    fn is_selectable(&self) -> bool {
+        !self.is_separator && !self.is_disabled
+    }
 
-        Ok(Self {
-            hwnd,
-            items,
-            item_height,
-            padding,
-            hover_index: None,
-            theme: theme.clone(),
-            on_select: None,
-        })
+    fn height(&self, dpi: u32) -> i32 {
+        if self.is_separator {
+            scale(SEPARATOR_HEIGHT, dpi)
+        } else if self.slider.is_some() {
+            scale(SLIDER_HEIGHT, dpi)
+        } else {
+            scale(ITEM_HEIGHT, dpi)
+        }
     }
+}
 
-    /// Register window class
-    fn ensure_class_registered() -> Result<()> {
-        unsafe {
-            let class_name = to_wide_string(DROPDOWN_CLASS);
-            let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
-
-            let wc = WNDCLASSEXW {
-                cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
-                style: CS_HREDRAW | CS_VREDRAW | CS_DROPSHADOW,
-                lpfnWndProc: Some(dropdown_proc),
-                hInstance: hinstance.into(),
-                hCursor: LoadCursorW(None, IDC_ARROW)?,
-                lpszClassName: to_pcwstr(&class_name),
-                hbrBackground: HBRUSH::default(),
+/// Read a popup menu built with `AppendMenuW` into dropdown rows, with its
+/// submenus, check marks, disabled items and bitmaps
+pub fn items_from_menu(menu: HMENU) -> Vec<DropdownItem> {
+    let mut items = Vec::new();
+    unsafe {
+        let count = GetMenuItemCount(menu).max(0) as u32;
+        for position in 0..count {
+            let mut info = MENUITEMINFOW {
+                cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+                fMask: MIIM_FTYPE | MIIM_STATE | MIIM_ID | MIIM_SUBMENU | MIIM_STRING | MIIM_BITMAP,
                 ..Default::default()
             };
+            if GetMenuItemInfoW(menu, position, true, &mut info).is_err() {
+                continue;
+            }
+            if info.fType.0 & MFT_SEPARATOR.0 != 0 {
+                items.push(DropdownItem::separator());
+                continue;
+            }
+            // Second call with a buffer for the text
+            let mut text = vec![0u16; info.cch as usize + 1];
+            info.fMask = MIIM_STRING;
+            info.dwTypeData = PWSTR(text.as_mut_ptr());
+            info.cch += 1;
+            let _ = GetMenuItemInfoW(menu, position, true, &mut info);
+            let text = String::from_utf16_lossy(&text[..text.iter().position(|&c| c == 0).unwrap_or(text.len())]);
+            let (label, shortcut) = split_menu_text(&text);
+
+            let mut item = DropdownItem::new(info.wID, &label).checked(info.fState.0 & MFS_CHECKED.0 != 0);
+            item.shortcut = shortcut;
+            item.is_disabled = info.fState.0 & MFS_DISABLED.0 != 0;
+            // Values up to 11 are the HBMMENU_* system glyphs, not bitmaps
+            if info.hbmpItem.0 as isize > 11 {
+                item.bitmap = Some(info.hbmpItem);
+            }
+            if !info.hSubMenu.is_invalid() {
+                item.submenu = Some(items_from_menu(info.hSubMenu));
+            }
+            items.push(item);
+        }
+    }
+    items
+}
 
-            // Ignore error if class already exists
-            let _ = RegisterClassExW(&wc);
+/// Split a menu item's text into its label, without `&` mnemonics, and the
+/// shortcut hint after a tab
+fn split_menu_text(text: &str) -> (String, Option<String>) {
+    let (label, shortcut) = match text.split_once('\t') {
+        Some((label, shortcut)) => (label, Some(shortcut.to_string())),
+        None => (text, None),
+    };
+    let mut clean = String::with_capacity(label.len());
+    let mut chars = label.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            // "&&" is a literal ampersand
+            if chars.peek() == Some(&'&') {
+                chars.next();
+                clean.push('&');
+            }
+            continue;
         }
-        Ok(())
+        clean.push(c);
     }
+    (clean, shortcut)
+}
 
-    /// Calculate menu height
-    fn calculate_height(items: &[DropdownItem], item_height: i32, padding: i32) -> i32 {
-        let mut height = padding * 2;
-        for item in items {
+/// Build a native popup menu from dropdown rows, for when themed menus are off.
+/// Sliders have no native counterpart and are left out.
+pub fn to_native_menu(items: &[DropdownItem]) -> HMENU {
+    unsafe {
+        let Ok(menu) = CreatePopupMenu() else {
+            return HMENU::default();
+        };
+        for item in items.iter().filter(|i| i.slider.is_none()) {
             if item.is_separator {
-                height += 13;  // Taller separator with margins
-            } else {
-                height += item_height;
+                let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+                continue;
+            }
+            let text = match &item.shortcut {
+                Some(shortcut) => format!("{}\t{}", item.label.replace('&', "&&"), shortcut),
+                None => item.label.replace('&', "&&"),
+            };
+            let wide = to_wide_string(&text);
+            let mut flags = MF_STRING;
+            if item.is_checked {
+                flags |= MF_CHECKED;
+            }
+            if item.is_disabled {
+                flags |= MF_GRAYED;
+            }
+            match &item.submenu {
+                Some(sub) => {
+                    let sub = to_native_menu(sub);
+                    let _ = AppendMenuW(menu, flags | MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr()));
+                }
+                None => {
+                    let _ = AppendMenuW(menu, flags, item.id as usize, PCWSTR(wide.as_ptr()));
+                }
+            }
+            if let Some(bitmap) = item.bitmap {
+                let info = MENUITEMINFOW {
+                    cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+                    fMask: MIIM_BITMAP,
+                    hbmpItem: bitmap,
+                    ..Default::default()
+                };
+                let count = GetMenuItemCount(menu);
+                let _ = SetMenuItemInfoW(menu, (count - 1) as u32, true, &info);
             }
         }
-        height
+        menu
     }
+}
 
-    /// Show the menu at position
-    pub fn show_at(&mut self, x: i32, y: i32) {
-        unsafe {
-            // Get menu size
-            let width = 280;  // Match new width
-            let height = Self::calculate_height(&self.items, self.item_height, self.padding);
+/// An open panel: the top-level dropdown or one of its submenus
+struct Level {
+    hwnd: HWND,
+    items: Vec<DropdownItem>,
+    /// Screen rectangle
+    rect: RECT,
+    hover: Option<usize>,
+    /// Row of the level above that opened this one
+    parent_row: Option<usize>,
+}
 
-            // Adjust position to keep on screen with margin
-            let screen_width = GetSystemMetrics(SM_CXSCREEN);
-            let screen_height = GetSystemMetrics(SM_CYSCREEN);
-            let margin = 8;  // Screen edge margin
+/// State of the dropdown being tracked
+struct Tracking {
+    levels: Vec<Level>,
+    dpi: u32,
+    theme: Theme,
+    font_family: String,
+    /// Corner radius of the panels, scaled
+    radius: i32,
+    /// Chosen command once closed (0 when dismissed)
+    result: Option<u32>,
+    /// Slider being dragged, as (level, row)
+    dragging: Option<(usize, usize)>,
+    /// Slider values that changed since the caller last heard, as (command, value)
+    slid: Vec<(u32, u32)>,
+}
 
-            let mut final_x = x;
-            let mut final_y = y;
+thread_local! {
+    static TRACKING: RefCell<Option<Tracking>> = const { RefCell::new(None) };
+}
 
-            if x + width > screen_width - margin {
-                final_x = screen_width - width - margin;
+/// Show a dropdown with its top left at `x`, `y` (screen coordinates) and wait
+/// until a command is chosen or it's dismissed; returns the command, or 0.
+/// `on_slide` gets each new value of a slider (command id, 0-100) as it moves.
+pub fn track_dropdown(items: Vec<DropdownItem>, x: i32, y: i32, mut on_slide: impl FnMut(u32, u32)) -> u32 {
+    // One dropdown at a time, like native menus
+    if TRACKING.with(|t| t.borrow().is_some()) {
+        return 0;
+    }
+    let (dpi, theme, font_family, radius) = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            let appearance = &s.config.appearance;
+            (s.dpi, s.theme_manager.theme().clone(), appearance.font_family.clone(), appearance.corner_radius)
+        })
+        .unwrap_or_else(|| (96, Theme::dark(), "Segoe UI".to_string(), 12));
+    let radius = scale(radius as i32, dpi);
+
+    let hwnd = match unsafe { create_panel() } {
+        Ok(hwnd) => hwnd,
+        Err(e) => {
+            log::warn!("Failed to create dropdown: {}", e);
+            return 0;
+        }
+    };
+    let (width, height) = measure(&items, dpi, &font_family);
+    let rect = unsafe { place(hwnd, x, y, width, height, radius, None) };
+    TRACKING.with(|t| {
+        *t.borrow_mut() = Some(Tracking {
+            levels: vec![Level {
+                hwnd,
+                items,
+                rect,
+                hover: None,
+                parent_row: None,
+            }],
+            dpi,
+            theme,
+            font_family,
+            radius,
+            result: None,
+            dragging: None,
+            slid: Vec::new(),
+        })
+    });
+
+    unsafe {
+        SetCapture(hwnd);
+        let mut msg = MSG::default();
+        loop {
+            let (slid, done) = TRACKING.with(|t| {
+                let mut t = t.borrow_mut();
+                let tracking = t.as_mut().expect("dropdown tracking state");
+                (std::mem::take(&mut tracking.slid), tracking.result.is_some())
+            });
+            for (id, value) in slid {
+                on_slide(id, value);
+            }
+            if done {
+                break;
             }
-            if y + height > screen_height - margin {
-                final_y = y - height;
+            if !GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                // Leave WM_QUIT for the main loop
+                PostQuitMessage(msg.wParam.0 as i32);
+                break;
             }
+            match msg.message {
+                WM_KEYDOWN | WM_SYSKEYDOWN => {
+                    TRACKING.with(|t| {
+                        if let Some(tracking) = t.borrow_mut().as_mut() {
+                            tracking.key(VIRTUAL_KEY(msg.wParam.0 as u16));
+                        }
+                    });
+                    continue;
+                }
+                WM_KEYUP | WM_SYSKEYUP | WM_CHAR | WM_SYSCHAR => continue,
+                _ => {}
+            }
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
 
-            SetWindowPos(
-                self.hwnd,
-                HWND_TOPMOST,
-                final_x, final_y, width, height,
-                SWP_SHOWWINDOW | SWP_NOACTIVATE,
-            ).ok();
+        let tracking = TRACKING.with(|t| t.borrow_mut().take());
+        let _ = ReleaseCapture();
+        let Some(tracking) = tracking else {
+            return 0;
+        };
+        for level in &tracking.levels {
+            let _ = DestroyWindow(level.hwnd);
+        }
+        tracking.result.unwrap_or(0)
+    }
+}
 
-            // Capture mouse
-            SetCapture(self.hwnd);
+/// Size a panel needs for its rows
+fn measure(items: &[DropdownItem], dpi: u32, font_family: &str) -> (i32, i32) {
+    unsafe {
+        let hdc = GetDC(None);
+        let font = create_font(font_family, scale(13, dpi), false);
+        let old_font = SelectObject(hdc, font);
+        let columns = text_left(items, dpi);
+        let mut width = 0;
+        for item in items.iter().filter(|i| !i.is_separator) {
+            let mut row = columns + measure_text(hdc, &item.label).0;
+            if item.slider.is_some() {
+                row += scale(12 + SLIDER_WIDTH + SLIDER_VALUE_WIDTH, dpi);
+            }
+            if let Some(shortcut) = &item.shortcut {
+                row += scale(24, dpi) + measure_text(hdc, shortcut).0;
+            }
+            if item.submenu.is_some() {
+                row += scale(COLUMN, dpi);
+            }
+            width = width.max(row);
         }
+        SelectObject(hdc, old_font);
+        let _ = DeleteObject(font);
+        ReleaseDC(None, hdc);
+
+        let padding = scale(PADDING, dpi);
+        let height = items.iter().map(|i| i.height(dpi)).sum::<i32>() + padding * 2;
+        ((width + padding * 2 + scale(12, dpi)).max(scale(MIN_WIDTH, dpi)), height)
+    }
+}
+
+/// Where labels start: after the check mark column and, when any row has one,
+/// the icon column
+fn text_left(items: &[DropdownItem], dpi: u32) -> i32 {
+    let has_icons = items.iter().any(|i| i.icon.is_some() || i.bitmap.is_some());
+    scale(PADDING + COLUMN, dpi) + if has_icons { scale(COLUMN, dpi) } else { 0 }
+}
+
+/// Top of each row in a panel, in client coordinates
+fn row_tops(items: &[DropdownItem], dpi: u32) -> Vec<i32> {
+    let mut y = scale(PADDING, dpi);
+    items
+        .iter()
+        .map(|item| {
+            let top = y;
+            y += item.height(dpi);
+            top
+        })
+        .collect()
+}
+
+/// Row at `y` in client coordinates
+fn row_at(items: &[DropdownItem], dpi: u32, y: i32) -> Option<usize> {
+    let tops = row_tops(items, dpi);
+    tops.iter()
+        .zip(items)
+        .position(|(top, item)| y >= *top && y < top + item.height(dpi))
+}
+
+/// Left and right end of a slider's track in a panel `width` wide
+fn slider_track(width: i32, dpi: u32) -> (i32, i32) {
+    let right = width - scale(PADDING + 6 + SLIDER_VALUE_WIDTH, dpi);
+    (right - scale(SLIDER_WIDTH, dpi), right)
+}
+
+impl Tracking {
+    /// Level and row under a screen point, deepest panel first
+    fn hit(&self, point: POINT) -> Option<(usize, Option<usize>)> {
+        self.levels.iter().enumerate().rev().find_map(|(index, level)| {
+            unsafe { PtInRect(&level.rect, point).as_bool() }
+                .then(|| (index, row_at(&level.items, self.dpi, point.y - level.rect.top)))
+        })
     }
 
-    /// Hide the menu
-    pub fn hide(&self) {
-        unsafe {
-            ReleaseCapture().ok();
-            let _ = ShowWindow(self.hwnd, SW_HIDE);
+    fn invalidate(&self, level: usize) {
+        if let Some(level) = self.levels.get(level) {
+            unsafe {
+                let _ = InvalidateRect(level.hwnd, None, false);
+            }
         }
     }
 
-    /// Set selection callback
-    pub fn on_select<F: Fn(u32) + Send + Sync + 'static>(&mut self, callback: F) {
-        self.on_select = Some(Box::new(callback));
-    }
-
-    /// Draw the menu
-    fn draw(&self, hdc: HDC) {
-        unsafe {
-            let mut rect = RECT::default();
-            GetClientRect(self.hwnd, &mut rect).ok();
-
-            // Draw background with slightly elevated color
-            let bg_brush = CreateSolidBrush(self.theme.background.colorref());
-            FillRect(hdc, &rect, bg_brush);
-            let _ = DeleteObject(bg_brush);
-
-            // Draw subtle border with slight alpha
-            let border_brush = CreateSolidBrush(self.theme.border_hover.colorref());
-            FrameRect(hdc, &rect, border_brush);
-            let _ = DeleteObject(border_brush);
-
-            // Draw items
-            let mut y = self.padding;
-            
-            // Create font - macOS uses SF Pro, we use Segoe UI Variable
-            let font = self.create_font(13, false);
-            let old_font = SelectObject(hdc, font);
-            SetBkMode(hdc, TRANSPARENT);
-
-            for (index, item) in self.items.iter().enumerate() {
-                if item.is_separator {
-                    // Draw separator with macOS-style subtle divider
-                    let sep_rect = RECT {
-                        left: self.padding + 12,
-                        top: y + 7,
-                        right: rect.right - self.padding - 12,
-                        bottom: y + 8,
-                    };
-                    let sep_brush = CreateSolidBrush(self.theme.border.colorref());
-                    FillRect(hdc, &sep_rect, sep_brush);
-                    let _ = DeleteObject(sep_brush);
-                    y += 15;  // More breathing room
-                } else {
-                    // Draw item with padding for rounded effect
-                    let item_rect = RECT {
-                        left: self.padding + 4,
-                        top: y,
-                        right: rect.right - self.padding - 4,
-                        bottom: y + self.item_height,
-                    };
-
-                    // macOS-style hover with rounded corners (simulate with inner rect)
-                    if Some(index) == self.hover_index && !item.is_disabled {
-                        let hover_color = if self.theme.is_dark {
-                            Color::new(255, 255, 255, 20)  // Subtle white glow
-                        } else {
-                            self.theme.accent.with_alpha(230)  // Soft accent
-                        };
-                        let hover_brush = CreateSolidBrush(hover_color.colorref());
-                        let rounded_rect = item_rect;
-                        FillRect(hdc, &rounded_rect, hover_brush);
-                        let _ = DeleteObject(hover_brush);
-
-                        if self.theme.is_dark {
-                            SetTextColor(hdc, Color::rgb(255, 255, 255).colorref());
-                        } else {
-                            SetTextColor(hdc, Color::rgb(255, 255, 255).colorref());
-                        }
-                    } else {
-                        let color = if item.is_disabled {
-                            self.theme.text_disabled
-                        } else {
-                            self.theme.text_primary
-                        };
-                        SetTextColor(hdc, color.colorref());
-                    }
-
-                    // Draw icon if present (better spacing)
-                    let text_x = self.padding + 10;
-                    if let Some(ref icon) = item.icon {
-                        let icon_wide: Vec<u16> = icon.encode_utf16().chain(std::iter::once(0)).collect();
-                        let _ = TextOutW(hdc, text_x, y + 6, &icon_wide[..icon_wide.len()-1]);
-                    }
-
-                    // Draw label with improved vertical centering
-                    let label_x = text_x + 28;
-                    let label_wide: Vec<u16> = item.label.encode_utf16().chain(std::iter::once(0)).collect();
-                    let _ = TextOutW(hdc, label_x, y + 6, &label_wide[..label_wide.len()-1]);
-
-                    // Draw shortcut with secondary color
-                    if let Some(ref shortcut) = item.shortcut {
-                        SetTextColor(hdc, self.theme.text_secondary.colorref());
-                        let shortcut_wide: Vec<u16> = shortcut.encode_utf16().chain(std::iter::once(0)).collect();
-                        
-                        let mut size = windows::Win32::Foundation::SIZE::default();
-                        let _ = GetTextExtentPoint32W(hdc, &shortcut_wide[..shortcut_wide.len()-1], &mut size);
-                        
-                        let _ = TextOutW(hdc, item_rect.right - size.cx - 12, y + 6, &shortcut_wide[..shortcut_wide.len()-1]);
-                    }
-
-                    // Draw submenu arrow
-                    if item.submenu.is_some() {
-                        SetTextColor(hdc, self.theme.text_secondary.colorref());
-                        let arrow: Vec<u16> = "▶".encode_utf16().chain(std::iter::once(0)).collect();
-                        let _ = TextOutW(hdc, item_rect.right - 20, y + 6, &arrow[..arrow.len()-1]);
-                    }
-
-                    // Draw checkmark
-                    if item.is_checked {
-                        let check: Vec<u16> = "✓".encode_utf16().chain(std::iter::once(0)).collect();
-                        let _ = TextOutW(hdc, self.padding + 4, y + 6, &check[..check.len()-1]);
-                    }
-
-                    y += self.item_height;
+    /// Close the panels below `level`
+    fn close_below(&mut self, level: usize) {
+        while self.levels.len() > level + 1 {
+            if let Some(closed) = self.levels.pop() {
+                unsafe {
+                    let _ = DestroyWindow(closed.hwnd);
                 }
             }
+        }
+    }
+
+    fn set_hover(&mut self, level: usize, row: Option<usize>) {
+        let Some(current) = self.levels.get_mut(level) else {
+            return;
+        };
+        let row = row.filter(|&r| current.items[r].is_selectable());
+        if current.hover != row {
+            current.hover = row;
+            self.invalidate(level);
+        }
+    }
+
+    /// Open the submenu of a row beside it, unless it's already open
+    fn open_submenu(&mut self, level: usize, row: usize) {
+        if self.levels.get(level + 1).and_then(|l| l.parent_row) == Some(row) {
+            return;
+        }
+        self.close_below(level);
+        let parent = &self.levels[level];
+        let Some(items) = parent.items[row].submenu.clone() else {
+            return;
+        };
+        let top = parent.rect.top + row_tops(&parent.items, self.dpi)[row] - scale(PADDING, self.dpi);
+        let parent_rect = parent.rect;
+        let hwnd = match unsafe { create_panel() } {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                log::warn!("Failed to open submenu: {}", e);
+                return;
+            }
+        };
+        let (width, height) = measure(&items, self.dpi, &self.font_family);
+        let rect = unsafe { place(hwnd, parent_rect.right - scale(2, self.dpi), top, width, height, self.radius, Some(parent_rect)) };
+        self.levels.push(Level {
+            hwnd,
+            items,
+            rect,
+            hover: None,
+            parent_row: Some(row),
+        });
+    }
+
+    /// Set a slider from a screen x position
+    fn slide_to(&mut self, level: usize, row: usize, x: i32) {
+        let dpi = self.dpi;
+        let Some(panel) = self.levels.get_mut(level) else {
+            return;
+        };
+        let (left, right) = slider_track(panel.rect.right - panel.rect.left, dpi);
+        let offset = (x - panel.rect.left - left) as f32 / (right - left).max(1) as f32;
+        self.set_slider(level, row, (offset.clamp(0.0, 1.0) * 100.0).round() as u32);
+    }
 
-            SelectObject(hdc, old_font);
-            let _ = DeleteObject(font);
+    fn set_slider(&mut self, level: usize, row: usize, value: u32) {
+        let item = &mut self.levels[level].items[row];
+        let value = value.min(100);
+        if item.slider != Some(value) {
+            item.slider = Some(value);
+            self.slid.push((item.id, value));
+            self.invalidate(level);
         }
     }
 
-    /// Create a font with macOS-inspired styling
-    fn create_font(&self, size: i32, bold: bool) -> HFONT {
-        unsafe {
-            let family: Vec<u16> = "Segoe UI Variable Text".encode_utf16().chain(std::iter::once(0)).collect();
-            let mut lf = LOGFONTW::default();
-            lf.lfHeight = -size;
-            lf.lfWeight = if bold { 600 } else { 400 };  // SF Pro-inspired weights
-            lf.lfCharSet = DEFAULT_CHARSET;
-            lf.lfQuality = CLEARTYPE_QUALITY;  // Smooth text rendering
-            lf.lfOutPrecision = OUT_TT_PRECIS;
-            lf.lfPitchAndFamily = VARIABLE_PITCH.0 | FF_SWISS.0;
-            
-            let face_len = family.len().min(32);
-            lf.lfFaceName[..face_len].copy_from_slice(&family[..face_len]);
-            
-            CreateFontIndirectW(&lf)
+    fn mouse_move(&mut self, point: POINT) {
+        if let Some((level, row)) = self.dragging {
+            self.slide_to(level, row, point.x);
+            return;
+        }
+        let Some((level, row)) = self.hit(point) else {
+            return;
+        };
+        self.set_hover(level, row);
+        match self.levels[level].hover {
+            Some(row) if self.levels[level].items[row].submenu.is_some() => self.open_submenu(level, row),
+            _ => self.close_below(level),
         }
     }
 
-    /// Hit test to find item at position
-    fn hit_test(&self, y: i32) -> Option<usize> {
-        let mut current_y = self.padding;
-        
-        for (index, item) in self.items.iter().enumerate() {
-            let item_h = if item.is_separator { 15 } else { self.item_height };
-            
-            if y >= current_y && y < current_y + item_h {
-                if !item.is_separator && !item.is_disabled {
-                    return Some(index);
+    fn button_down(&mut self, point: POINT) {
+        match self.hit(point) {
+            None => self.result = Some(0),
+            Some((level, Some(row))) => {
+                let item = &self.levels[level].items[row];
+                if item.slider.is_some() && !item.is_disabled {
+                    self.dragging = Some((level, row));
+                    self.slide_to(level, row, point.x);
                 }
-                return None;
             }
-            
-            current_y += item_h;
+            Some(_) => {}
         }
-        
-        None
     }
-}
 
-impl Drop for DropdownMenu {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = DestroyWindow(self.hwnd);
+    fn button_up(&mut self, point: POINT) {
+        if self.dragging.take().is_some() {
+            return;
+        }
+        if let Some((level, Some(row))) = self.hit(point) {
+            self.activate(level, row);
         }
     }
-}
 
-/// Window procedure for dropdown menus
-unsafe extern "system" fn dropdown_proc(
-    hwnd: HWND,
-    msg: u32,
-    wparam: WPARAM,
-    lparam: LPARAM,
-) -> LRESULT {
-    match msg {
-        WM_PAINT => {
-            let mut ps = PAINTSTRUCT::default();
-            let hdc = BeginPaint(hwnd, &mut ps);
-            
-            // Would draw menu here (need to store reference)
-            // For now, just fill with a color
-            let brush = CreateSolidBrush(COLORREF(0x2D2D2D));
-            FillRect(hdc, &ps.rcPaint, brush);
-            let _ = DeleteObject(brush);
-            
-            let _ = EndPaint(hwnd, &ps);
-            LRESULT(0)
+    /// Choose a row: run its command, or open its submenu
+    fn activate(&mut self, level: usize, row: usize) {
+        let item = &self.levels[level].items[row];
+        if !item.is_selectable() || item.slider.is_some() {
+            return;
+        }
+        if item.submenu.is_some() {
+            self.open_submenu(level, row);
+        } else {
+            self.result = Some(item.id);
         }
+    }
 
-        WM_MOUSEMOVE => {
-            // Update hover state
-            let _ = InvalidateRect(hwnd, None, false);
-            LRESULT(0)
+    /// Move the hover of a panel to the next selectable row up or down, wrapping around
+    fn step_hover(&mut self, level: usize, down: bool) {
+        let items = &self.levels[level].items;
+        let count = items.len();
+        if count == 0 {
+            return;
+        }
+        let start = self.levels[level].hover;
+        let mut index = start.unwrap_or(if down { count - 1 } else { 0 });
+        for _ in 0..count {
+            index = if down { (index + 1) % count } else { (index + count - 1) % count };
+            if items[index].is_selectable() {
+                self.set_hover(level, Some(index));
+                return;
+            }
         }
+    }
 
-        WM_LBUTTONUP => {
-            // Handle selection
-            ReleaseCapture().ok();
-            let _ = ShowWindow(hwnd, SW_HIDE);
-            LRESULT(0)
+    fn key(&mut self, vk: VIRTUAL_KEY) {
+        let level = self.levels.len() - 1;
+        let hovered = self.levels[level].hover;
+        let slider = hovered.and_then(|row| self.levels[level].items[row].slider.map(|value| (row, value)));
+        match vk {
+            VK_ESCAPE if level > 0 => self.close_below(level - 1),
+            VK_ESCAPE => self.result = Some(0),
+            VK_UP => self.step_hover(level, false),
+            VK_DOWN => self.step_hover(level, true),
+            VK_LEFT | VK_RIGHT if slider.is_some() => {
+                let (row, value) = slider.unwrap_or_default();
+                let value = if vk == VK_RIGHT {
+                    value + SLIDER_STEP
+                } else {
+                    value.saturating_sub(SLIDER_STEP)
+                };
+                self.set_slider(level, row, value);
+            }
+            VK_LEFT if level > 0 => self.close_below(level - 1),
+            VK_RIGHT | VK_RETURN | VK_SPACE => {
+                let Some(row) = hovered else {
+                    return;
+                };
+                if vk == VK_RIGHT && self.levels[level].items[row].submenu.is_none() {
+                    return;
+                }
+                self.activate(level, row);
+                if self.levels.len() > level + 1 {
+                    self.step_hover(level + 1, true);
+                }
+            }
+            _ => {}
         }
+    }
+
+    unsafe fn paint(&self, hwnd: HWND, hdc: HDC) {
+        let Some(depth) = self.levels.iter().position(|l| l.hwnd == hwnd) else {
+            return;
+        };
+        let level = &self.levels[depth];
+        let dpi = self.dpi;
+        let theme = &self.theme;
+        let mut rect = RECT::default();
+        let _ = GetClientRect(hwnd, &mut rect);
+        let width = rect.right;
+        let padding = scale(PADDING, dpi);
+        let radius = self.radius;
+
+        let brush = CreateSolidBrush(theme.background.colorref());
+        FillRect(hdc, &rect, brush);
+        let _ = DeleteObject(brush);
+        let outline = CreateRoundRectRgn(0, 0, rect.right + 1, rect.bottom + 1, radius, radius);
+        let border = CreateSolidBrush(theme.border.colorref());
+        let _ = FrameRgn(hdc, outline, border, 1, 1);
+        let _ = DeleteObject(border);
+        let _ = DeleteObject(outline);
+
+        let font = create_font(&self.font_family, scale(13, dpi), false);
+        let old_font = SelectObject(hdc, font);
+        SetBkMode(hdc, TRANSPARENT);
+        let label_x = text_left(&level.items, dpi);
+        let icon_x = padding + scale(COLUMN, dpi);
+
+        for ((index, item), top) in level.items.iter().enumerate().zip(row_tops(&level.items, dpi)) {
+            let height = item.height(dpi);
+            if item.is_separator {
+                let line = RECT {
+                    left: padding + scale(8, dpi),
+                    top: top + height / 2,
+                    right: width - padding - scale(8, dpi),
+                    bottom: top + height / 2 + 1,
+                };
+                let brush = CreateSolidBrush(theme.border.colorref());
+                FillRect(hdc, &line, brush);
+                let _ = DeleteObject(brush);
+                continue;
+            }
 
-        WM_CAPTURECHANGED => {
-            // Lost capture, close menu
-            let _ = ShowWindow(hwnd, SW_HIDE);
-            LRESULT(0)
+            // A row stays highlighted while its submenu is open
+            let open = self.levels.get(depth + 1).and_then(|l| l.parent_row) == Some(index);
+            if level.hover == Some(index) || open {
+                let brush = CreateSolidBrush(theme.background_hover.colorref());
+                let region = CreateRoundRectRgn(padding, top, width - padding + 1, top + height + 1, radius / 2, radius / 2);
+                let _ = FillRgn(hdc, region, brush);
+                let _ = DeleteObject(region);
+                let _ = DeleteObject(brush);
+            }
+
+            let (_, text_height) = measure_text(hdc, "Ag");
+            let text_y = top + (height - text_height) / 2;
+            let text_color = match item.color {
+                Some(color) => color,
+                None if item.is_disabled => theme.text_disabled,
+                None => theme.text_primary,
+            };
+
+            if item.is_checked {
+                SetTextColor(hdc, theme.accent.colorref());
+                let (check_width, _) = measure_text(hdc, "✓");
+                draw_text(hdc, padding + (scale(COLUMN, dpi) - check_width) / 2, text_y, "✓");
+            }
+            if let Some(bitmap) = item.bitmap {
+                let size = scale(ICON_SIZE, dpi);
+                draw_bitmap(hdc, bitmap, icon_x + (scale(COLUMN, dpi) - size) / 2, top + (height - size) / 2, size);
+            } else if let Some(icon) = &item.icon {
+                SetTextColor(hdc, text_color.colorref());
+                let (icon_width, _) = measure_text(hdc, icon);
+                draw_text(hdc, icon_x + (scale(COLUMN, dpi) - icon_width) / 2, text_y, icon);
+            }
+
+            SetTextColor(hdc, text_color.colorref());
+            draw_text(hdc, label_x, text_y, &item.label);
+
+            let right = width - padding - scale(8, dpi);
+            if let Some(value) = item.slider {
+                draw_slider(hdc, theme, width, top, height, value, dpi);
+            } else if item.submenu.is_some() {
+                SetTextColor(hdc, theme.text_secondary.colorref());
+                let (arrow_width, _) = measure_text(hdc, "›");
+                draw_text(hdc, right - arrow_width, text_y, "›");
+            } else if let Some(shortcut) = &item.shortcut {
+                SetTextColor(hdc, theme.text_secondary.colorref());
+                let (shortcut_width, _) = measure_text(hdc, shortcut);
+                draw_text(hdc, right - shortcut_width, text_y, shortcut);
+            }
         }
 
-        WM_KEYDOWN => {
-            let vk = wparam.0 as u16;
-            match vk {
-                0x1B => {  // VK_ESCAPE
-                    ReleaseCapture().ok();
-                    let _ = ShowWindow(hwnd, SW_HIDE);
+        SelectObject(hdc, old_font);
+        let _ = DeleteObject(font);
+    }
+}
+
+/// Draw a slider's track, filled up to `value`, with its knob and value
+unsafe fn draw_slider(hdc: HDC, theme: &Theme, width: i32, top: i32, height: i32, value: u32, dpi: u32) {
+    let (left, right) = slider_track(width, dpi);
+    let center = top + height / 2;
+    let thickness = scale(4, dpi);
+    let filled = left + (right - left) * value as i32 / 100;
+
+    let track = CreateSolidBrush(theme.background_secondary.colorref());
+    let region = CreateRoundRectRgn(left, center - thickness / 2, right + 1, center + thickness / 2 + 1, thickness, thickness);
+    let _ = FillRgn(hdc, region, track);
+    let _ = DeleteObject(region);
+    let _ = DeleteObject(track);
+
+    let accent = CreateSolidBrush(theme.accent.colorref());
+    let region = CreateRoundRectRgn(left, center - thickness / 2, filled + 1, center + thickness / 2 + 1, thickness, thickness);
+    let _ = FillRgn(hdc, region, accent);
+    let _ = DeleteObject(region);
+    let knob = scale(6, dpi);
+    let region = CreateEllipticRgn(filled - knob, center - knob, filled + knob + 1, center + knob + 1);
+    let _ = FillRgn(hdc, region, accent);
+    let _ = DeleteObject(region);
+    let _ = DeleteObject(accent);
+
+    SetTextColor(hdc, theme.text_secondary.colorref());
+    let text = value.to_string();
+    let (text_width, text_height) = measure_text(hdc, &text);
+    let value_right = width - scale(PADDING + 6, dpi);
+    draw_text(hdc, value_right - text_width, center - text_height / 2, &text);
+}
+
+/// Draw a 32-bit premultiplied bitmap scaled to `size`
+unsafe fn draw_bitmap(hdc: HDC, bitmap: HBITMAP, x: i32, y: i32, size: i32) {
+    let mut info = BITMAP::default();
+    let got = GetObjectW(
+        HGDIOBJ(bitmap.0),
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut info as *mut BITMAP as *mut std::ffi::c_void),
+    );
+    if got == 0 {
+        return;
+    }
+    let source = CreateCompatibleDC(hdc);
+    let old = SelectObject(source, bitmap);
+    let blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: 255,
+        AlphaFormat: AC_SRC_ALPHA as u8,
+    };
+    let _ = AlphaBlend(hdc, x, y, size, size, source, 0, 0, info.bmWidth, info.bmHeight.abs(), blend);
+    SelectObject(source, old);
+    let _ = DeleteDC(source);
+}
+
+unsafe fn create_panel() -> Result<HWND> {
+    let class_name = to_wide_string(DROPDOWN_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_DROPSHADOW,
+        lpfnWndProc: Some(dropdown_proc),
+        hInstance: hinstance.into(),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        lpszClassName: to_pcwstr(&class_name),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    // Fails harmlessly once the class exists
+    let _ = RegisterClassExW(&wc);
+
+    let hwnd = CreateWindowExW(
+        WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+        to_pcwstr(&class_name),
+        PCWSTR::null(),
+        WS_POPUP,
+        0, 0, 0, 0,
+        None,
+        None,
+        hinstance,
+        None,
+    )?;
+    Ok(hwnd)
+}
+
+/// Show a panel at `x`, `y`, kept inside the work area of its monitor. A
+/// submenu that doesn't fit beside its `parent` opens on the other side; a
+/// dropdown that doesn't fit below its point opens above it.
+unsafe fn place(hwnd: HWND, x: i32, y: i32, width: i32, height: i32, radius: i32, parent: Option<RECT>) -> RECT {
+    let monitor = MonitorFromPoint(POINT { x, y }, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcWork;
+
+    let mut x = x;
+    let mut y = y;
+    if x + width > area.right {
+        // Open leftward from the point instead, like native menus
+        x = match parent {
+            Some(parent) => parent.left - width,
+            None if x - width >= area.left => x - width,
+            None => area.right - width,
+        };
+    }
+    if y + height > area.bottom {
+        y = match parent {
+            Some(_) => area.bottom - height,
+            None => y - height,
+        };
+    }
+    let x = x.max(area.left);
+    let y = y.max(area.top);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_SHOWWINDOW | SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, radius, radius);
+    SetWindowRgn(hwnd, region, true);
+    RECT {
+        left: x,
+        top: y,
+        right: x + width,
+        bottom: y + height,
+    }
+}
+
+/// Screen position of a mouse message sent to `hwnd`
+fn message_point(hwnd: HWND, lparam: LPARAM) -> POINT {
+    let mut point = POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    };
+    unsafe {
+        let _ = ClientToScreen(hwnd, &mut point);
+    }
+    point
+}
+
+/// Window procedure for dropdown panels. Mouse input arrives at the first
+/// panel, which has the capture; the others only paint.
+unsafe extern "system" fn dropdown_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    // Panels are created and destroyed while the state is borrowed; their
+    // messages then go to the default handling
+    let handled = TRACKING.with(|t| {
+        let Ok(mut tracking) = t.try_borrow_mut() else {
+            return false;
+        };
+        let Some(tracking) = tracking.as_mut() else {
+            return false;
+        };
+        match msg {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+                tracking.paint(hwnd, hdc);
+                let _ = EndPaint(hwnd, &ps);
+            }
+            WM_MOUSEMOVE => tracking.mouse_move(message_point(hwnd, lparam)),
+            WM_LBUTTONDOWN | WM_RBUTTONDOWN => tracking.button_down(message_point(hwnd, lparam)),
+            WM_LBUTTONUP | WM_RBUTTONUP => tracking.button_up(message_point(hwnd, lparam)),
+            WM_CAPTURECHANGED => {
+                // Another window took the mouse: dismiss
+                if tracking.result.is_none() {
+                    tracking.result = Some(0);
                 }
-                _ => {}
             }
-            LRESULT(0)
+            _ => return false,
         }
-
+        true
+    });
+    if handled {
+        return LRESULT(0);
+    }
+    match msg {
+        WM_ERASEBKGND => LRESULT(1),
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
         _ => DefWindowProcW(hwnd, msg, wparam, lparam),
     }
 }
@@ -0,0 +1,193 @@
+//! App icons for the launcher, running apps strip and active window
+//!
+//! Icons are extracted on a worker thread so a slow disk or a network path
+//! never stalls a paint; until an icon arrives the caller draws its
+//! placeholder glyph and the bar repaints once it's ready. Each icon is loaded
+//! at the size variant closest to what's drawn (16, 20, 24 or 32 pixels), so
+//! it stays sharp at higher DPI. The least recently drawn icons are dropped
+//! once the cache is full, and an icon is reloaded when its file changes.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant, SystemTime};
+
+use log::{debug, warn};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, HICON};
+
+/// Icons kept before the least recently drawn are destroyed
+const CAPACITY: usize = 96;
+/// Sizes icons are extracted at
+const VARIANTS: [i32; 4] = [16, 20, 24, 32];
+/// How often a cached icon's file is checked for changes
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An icon to extract: (path, size, window to repaint when it's ready)
+type Request = (String, i32, isize);
+/// An extracted icon: (path, size, icon handle or 0 when there is none, file time)
+type Loaded = (String, i32, isize, Option<SystemTime>);
+
+static REQUESTS: OnceCell<Mutex<Sender<Request>>> = OnceCell::new();
+static LOADED: Lazy<Mutex<Vec<Loaded>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+struct Entry {
+    /// None when the file has no icon
+    icon: Option<HICON>,
+    modified: Option<SystemTime>,
+    checked: Instant,
+    used: u64,
+}
+
+/// Icons by path and size variant
+#[derive(Default)]
+pub struct IconCache {
+    entries: HashMap<(String, i32), Entry>,
+    /// Requested and not back yet
+    loading: std::collections::HashSet<(String, i32)>,
+    clock: u64,
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The icon for `path` to draw at `size` pixels, or None while it's loading
+    /// or if the file has none; `hwnd` is repainted when a requested icon arrives
+    pub fn get(&mut self, path: &str, size: i32, hwnd: HWND) -> Option<HICON> {
+        if path.is_empty() {
+            return None;
+        }
+        self.take_loaded();
+        self.clock += 1;
+        let key = (path.to_string(), variant(size));
+
+        let stale = match self.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.used = self.clock;
+                let due = entry.checked.elapsed() >= RECHECK_INTERVAL;
+                if due {
+                    entry.checked = Instant::now();
+                }
+                due && modified(path) != entry.modified
+            }
+            None => true,
+        };
+        // A changed file keeps showing its old icon until the new one is in
+        if stale && self.loading.insert(key.clone()) {
+            request(key.0.clone(), key.1, hwnd);
+        }
+        self.entries.get(&key).and_then(|entry| entry.icon)
+    }
+
+    /// Store icons the worker has finished
+    fn take_loaded(&mut self) {
+        let loaded = std::mem::take(&mut *LOADED.lock());
+        for (path, size, icon, modified) in loaded {
+            let key = (path, size);
+            self.loading.remove(&key);
+            let entry = Entry {
+                icon: (icon != 0).then_some(HICON(icon as *mut std::ffi::c_void)),
+                modified,
+                checked: Instant::now(),
+                used: self.clock,
+            };
+            if let Some(old) = self.entries.insert(key, entry) {
+                destroy(old.icon);
+            }
+        }
+        while self.entries.len() > CAPACITY {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                destroy(entry.icon);
+            }
+        }
+    }
+}
+
+impl Drop for IconCache {
+    fn drop(&mut self) {
+        for (_, entry) in self.entries.drain() {
+            destroy(entry.icon);
+        }
+    }
+}
+
+/// The smallest variant at least `size` pixels across
+fn variant(size: i32) -> i32 {
+    VARIANTS.iter().copied().find(|v| *v >= size).unwrap_or(VARIANTS[VARIANTS.len() - 1])
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn destroy(icon: Option<HICON>) {
+    if let Some(icon) = icon {
+        unsafe {
+            let _ = DestroyIcon(icon);
+        }
+    }
+}
+
+/// Hand a request to the worker, starting it on first use
+fn request(path: String, size: i32, hwnd: HWND) {
+    let sender = REQUESTS.get_or_init(|| {
+        let (sender, receiver) = channel::<Request>();
+        let spawned = std::thread::Builder::new().name("icon-loader".into()).spawn(move || {
+            unsafe {
+                let _ = windows::Win32::System::Com::CoInitializeEx(None, windows::Win32::System::Com::COINIT_APARTMENTTHREADED);
+            }
+            for (path, size, hwnd) in receiver {
+                let modified = modified(&path);
+                let icon = extract(&path, size).map(|icon| icon.0 as isize).unwrap_or(0);
+                LOADED.lock().push((path, size, icon, modified));
+                unsafe {
+                    let _ = InvalidateRect(HWND(hwnd as *mut std::ffi::c_void), None, false);
+                }
+            }
+        });
+        if let Err(e) = spawned {
+            warn!("Failed to start the icon loader: {}", e);
+        }
+        Mutex::new(sender)
+    });
+    let _ = sender.lock().send((path, size, hwnd.0 as isize));
+}
+
+/// Extract the file's icon at `size` pixels: straight from the file's icon
+/// resources when it has them (programs), else the small shell icon for it
+fn extract(path: &str, size: i32) -> Option<HICON> {
+    use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
+    use windows::Win32::UI::Shell::{SHDefExtractIconW, SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_SMALLICON};
+
+    let wide = crate::utils::to_wide_string(path);
+    unsafe {
+        let mut icon = HICON::default();
+        let size_arg = size as u32 | ((size as u32) << 16);
+        let extracted = SHDefExtractIconW(PCWSTR(wide.as_ptr()), 0, 0, Some(&mut icon), None, size_arg);
+        if extracted.0 == 0 && !icon.is_invalid() {
+            return Some(icon);
+        }
+
+        let mut info = SHFILEINFOW::default();
+        let found = SHGetFileInfoW(
+            PCWSTR(wide.as_ptr()),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_ICON | SHGFI_SMALLICON,
+        );
+        if found != 0 && !info.hIcon.is_invalid() {
+            return Some(info.hIcon);
+        }
+    }
+    debug!("No icon for {}", path);
+    None
+}
@@ -184,6 +184,8 @@ pub mod fluent {
 
     pub const BRIGHTNESS: char = '\u{E706}';
     pub const BLUETOOTH: char = '\u{E702}';
+    pub const NIGHT_LIGHT: char = '\u{E708}';
+    pub const DO_NOT_DISTURB: char = '\u{E7ED}';
 
     pub const SETTINGS: char = '\u{E713}';
     pub const SEARCH: char = '\u{E721}';
@@ -0,0 +1,186 @@
+//! On-screen indicator for lock key changes
+//!
+//! A small rounded panel in the lower part of the active monitor that shows e.g.
+//! "Caps Lock On" and disappears after a moment. It never takes focus and lets
+//! clicks through. A new change while it's visible replaces the text and restarts
+//! the timer instead of stacking panels.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::window::state::get_window_state;
+
+const INDICATOR_CLASS: &str = "TopBarLockIndicatorClass";
+const HIDE_TIMER: usize = 1;
+
+/// The indicator window, if shown
+static INDICATOR: AtomicIsize = AtomicIsize::new(0);
+/// Text and on/off state currently shown
+static CONTENT: Mutex<(String, bool)> = Mutex::new((String::new(), false));
+
+/// Show (or update) the indicator for `duration_ms`
+pub fn show_lock_indicator(text: &str, on: bool, duration_ms: u32) {
+    *CONTENT.lock() = (text.to_string(), on);
+    if let Err(e) = unsafe { show(duration_ms) } {
+        log::warn!("Failed to show lock indicator: {}", e);
+    }
+}
+
+unsafe fn show(duration_ms: u32) -> Result<()> {
+    let mut hwnd = HWND(INDICATOR.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(INDICATOR_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_LAYERED | WS_EX_TRANSPARENT,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        INDICATOR.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    let opaque = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            s.theme_manager.theme().forced_colors
+                || crate::accessibility::reduce_transparency(&s.config.appearance)
+        })
+        .unwrap_or(false);
+    let alpha = if opaque { 255 } else { 235 };
+    let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA);
+
+    position(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    SetTimer(hwnd, HIDE_TIMER, duration_ms.max(200), None);
+    Ok(())
+}
+
+/// Size the panel to its text and center it near the bottom of the foreground monitor
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let text = CONTENT.lock().0.clone();
+
+    let hdc = GetDC(hwnd);
+    let font = create_font(&font_family(), scale(20, dpi), true);
+    let old_font = SelectObject(hdc, font);
+    let (text_width, text_height) = measure_text(hdc, &text);
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
+    ReleaseDC(hwnd, hdc);
+
+    let width = text_width + scale(48, dpi);
+    let height = text_height + scale(28, dpi);
+
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let work = info.rcWork;
+    let x = work.left + (work.right - work.left - width) / 2;
+    let y = work.bottom - height - (work.bottom - work.top) / 8;
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(16, dpi), scale(16, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let (text, on) = CONTENT.lock().clone();
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut rect = Default::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    let font = create_font(&font_family(), scale(20, dpi), true);
+    let old_font = SelectObject(hdc, font);
+    SetBkMode(hdc, TRANSPARENT);
+    let color = if on { theme.accent } else { theme.text_primary };
+    SetTextColor(hdc, color.colorref());
+    let (text_width, text_height) = measure_text(hdc, &text);
+    draw_text(
+        hdc,
+        (rect.right - text_width) / 2,
+        (rect.bottom - text_height) / 2,
+        &text,
+    );
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(INDICATOR_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_TIMER if wparam.0 == HIDE_TIMER => {
+            let _ = KillTimer(hwnd, HIDE_TIMER);
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            INDICATOR.store(0, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
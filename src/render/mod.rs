@@ -5,12 +5,43 @@
 
 #![allow(dead_code, unused_unsafe)]
 
+mod alarm_banner;
+mod briefing;
+mod chart;
+mod command_palette;
 mod context;
+mod dictionary;
 mod drawing;
+mod dropdown;
+mod gpu_usage;
+mod icon_cache;
 mod icons;
+mod lock_indicator;
 mod modules;
+mod network_usage;
+mod osd;
 mod quick_search;
+mod quick_toggles;
+mod region_select;
 mod renderer;
+mod shelf;
+mod tooltip;
+mod translation;
 
+pub use alarm_banner::{hide_alarm_banner, show_alarm_banner};
+pub use briefing::show_briefing;
+pub use command_palette::show_command_palette;
+pub use dictionary::show_definition;
+pub use drawing::{clear_text_cache, set_tabular_numbers};
+pub use dropdown::{items_from_menu, to_native_menu, track_dropdown, DropdownItem};
+pub use gpu_usage::toggle_gpu_usage;
+pub use lock_indicator::show_lock_indicator;
+pub use network_usage::toggle_network_usage;
+pub use osd::{show_osd, show_osd_level};
 pub use quick_search::show_quick_search;
-pub use renderer::Renderer;
+pub use quick_toggles::toggle_quick_toggles;
+pub use region_select::show_region_select;
+pub use renderer::{Renderer, Repaint};
+pub use shelf::{accept_drops, drop_on_shelf, show_shelf};
+pub use tooltip::{hide_tooltip, show_tooltip, tooltip_module};
+pub use translation::show_translation;
@@ -2,10 +2,12 @@ use chrono::Local;
 use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::UI::WindowsAndMessaging::{DrawIconEx, DI_NORMAL, HICON};
 
+use crate::modules::{sandbox, ModuleRenderContext};
 use crate::theme::Theme;
 use crate::utils::Rect;
 use crate::window::state::get_window_state;
-use super::drawing::{create_font, measure_text, draw_text, scale, draw_line_graph, downsample_values};
+use super::chart::{Chart, Series};
+use super::drawing::{create_font, measure_text, draw_text, scale, downsample_values};
 
 /// Draw all modules
 pub fn draw_modules(
@@ -36,17 +38,42 @@ pub fn draw_modules(
         });
     let dragging = dragging_module.clone();
 
-    // First update all modules to get fresh data
-    renderer.module_registry.update_all(&config);
+    // The active window follows this bar's monitor when asked to
+    let monitor = config
+        .modules
+        .active_window
+        .per_monitor
+        .then(|| unsafe { MonitorFromWindow(renderer.hwnd, MONITOR_DEFAULTTONEAREST) });
+    if let Some(awm) = renderer
+        .module_registry
+        .get_mut("active_window")
+        .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::active_window::ActiveWindowModule>())
+    {
+        awm.set_monitor(monitor);
+    }
+
+    // First update all modules to get fresh data, unless the change check
+    // that asked for this paint just did
+    if !std::mem::take(&mut renderer.modules_updated) {
+        renderer.module_registry.update_all(&config);
+    }
 
-    let padding = scale(8, renderer.dpi); // Edge padding
-    let item_spacing = scale(4, renderer.dpi); // Minimal spacing between items
+    // Pills reach past their module, so modules move apart to make room
+    let pill_padding = if config.appearance.pills.enabled {
+        scale(config.appearance.pills.padding as i32, renderer.dpi)
+    } else {
+        0
+    };
+    let padding = scale(8, renderer.dpi) + pill_padding; // Edge padding
+    let item_spacing = scale(4, renderer.dpi) + pill_padding * 2; // Minimal spacing between items
     let item_padding = scale(8, renderer.dpi); // Internal item padding
 
-    // Create font - use optimized modern fonts for macOS-like aesthetics
-    // Segoe UI Variable offers better clarity, while Inter is a great fallback
-    let font = create_font("Segoe UI Variable Text", scale(13, renderer.dpi), false);
-    let bold_font = create_font("Segoe UI Variable Display", scale(13, renderer.dpi), true);
+    // The configured font; the default Segoe UI Variable has a Display cut for bold text
+    let family = config.appearance.font_family.as_str();
+    let bold_family = if family == "Segoe UI Variable Text" { "Segoe UI Variable Display" } else { family };
+    let font_size = scale(config.appearance.font_size as i32, renderer.dpi);
+    let font = create_font(family, font_size, false);
+    let bold_font = create_font(bold_family, font_size, true);
 
     unsafe {
         let _old_font = SelectObject(hdc, font);
@@ -91,6 +118,48 @@ pub fn draw_modules(
             x += search_rect.width + item_spacing;
         }
 
+        // Pinned app launcher icons
+        if left_modules.contains(&"launcher".to_string())
+            && dragging.as_deref() != Some("launcher")
+        {
+            let apps: Vec<String> = renderer
+                .module_registry
+                .get("launcher")
+                .and_then(|m| m.as_any().downcast_ref::<crate::modules::launcher::LauncherModule>())
+                .map(|lm| lm.apps().iter().map(|a| a.path.clone()).collect())
+                .unwrap_or_default();
+
+            if !apps.is_empty() {
+                let icon_size = scale(16, renderer.dpi);
+                let slot_width = icon_size + scale(10, renderer.dpi);
+                let slot_height = icon_size + scale(8, renderer.dpi);
+                let y = (bar_rect.height - slot_height) / 2;
+                for (i, path) in apps.iter().enumerate() {
+                    let icon_x = x + (slot_width - icon_size) / 2;
+                    let icon_y = (bar_rect.height - icon_size) / 2;
+                    match get_small_icon_for_path(renderer, path, icon_size) {
+                        Some(hicon) => {
+                            let _ = DrawIconEx(hdc, icon_x, icon_y, hicon, icon_size, icon_size, 0, HBRUSH::default(), DI_NORMAL);
+                        }
+                        None => {
+                            // Loading, missing file or no icon: fall back to a generic app glyph
+                            let glyph = renderer.icons.get("app");
+                            let (gw, gh) = measure_text(hdc, &glyph);
+                            SetTextColor(hdc, theme.text_secondary.colorref());
+                            draw_text(hdc, x + (slot_width - gw) / 2, (bar_rect.height - gh) / 2, &glyph);
+                        }
+                    }
+                    // One hit-test id per icon so clicks map to a specific app
+                    renderer.module_bounds.insert(
+                        format!("{}{}", crate::modules::launcher::ITEM_PREFIX, i),
+                        Rect::new(x, y, slot_width, slot_height),
+                    );
+                    x += slot_width;
+                }
+                x += item_spacing;
+            }
+        }
+
         // Active application name
         if left_modules.contains(&"active_app".to_string())
             && dragging.as_deref() != Some("active_app")
@@ -99,7 +168,7 @@ pub fn draw_modules(
             let app_name = renderer
                 .module_registry
                 .get("active_window")
-                .map(|m| m.display_text(config.as_ref()))
+                .map(|m| sandbox::display_text(m, config.as_ref()))
                 .unwrap_or_else(|| "TopBar".to_string());
             // Try load a small app icon for the active application
             let mut app_icon: Option<HICON> = None;
@@ -117,7 +186,7 @@ pub fn draw_modules(
             }
 
             if let Some(path) = path_opt {
-                app_icon = get_small_icon_for_path(renderer, &path);
+                app_icon = get_small_icon_for_path(renderer, &path, scale(16, renderer.dpi));
             }
 
             let app_rect = draw_module_text(
@@ -135,6 +204,90 @@ pub fn draw_modules(
             SelectObject(hdc, font);
             renderer.module_bounds
                 .insert("active_app".to_string(), app_rect);
+            x += app_rect.width + item_spacing;
+        }
+
+        // Running apps strip
+        if left_modules.contains(&"tasks".to_string())
+            && dragging.as_deref() != Some("tasks")
+        {
+            use crate::modules::tasks::{item_id, TasksModule};
+
+            let windows: Vec<(isize, String, bool)> = renderer
+                .module_registry
+                .get("tasks")
+                .and_then(|m| m.as_any().downcast_ref::<TasksModule>())
+                .map(|tm| {
+                    tm.windows()
+                        .iter()
+                        .map(|w| (w.hwnd, w.path.clone(), tm.is_active(w.hwnd)))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let icon_size = scale(16, renderer.dpi);
+            let slot_width = icon_size + scale(12, renderer.dpi);
+            let slot_height = icon_size + scale(8, renderer.dpi);
+            let y = (bar_rect.height - slot_height) / 2;
+            // Leave the middle of the bar to the center section
+            let max_x = bar_rect.width * 2 / 5;
+            let mut hidden = 0;
+            for (hwnd, path, is_active) in windows.iter() {
+                if x + slot_width > max_x {
+                    hidden += 1;
+                    continue;
+                }
+                let id = item_id(*hwnd);
+                let slot = Rect::new(x, y, slot_width, slot_height);
+                if dragging.as_deref() == Some(id.as_str()) {
+                    // Leave a gap where the dragged window was
+                    x += slot_width;
+                    continue;
+                }
+                if *is_active {
+                    let brush = CreateSolidBrush(theme.background_active.colorref());
+                    let r = windows::Win32::Foundation::RECT {
+                        left: slot.x + 1,
+                        top: slot.y,
+                        right: slot.right() - 1,
+                        bottom: slot.bottom(),
+                    };
+                    FillRect(hdc, &r, brush);
+                    let _ = DeleteObject(brush);
+
+                    // Underline like the Windows taskbar's running indicator
+                    let accent = CreateSolidBrush(theme.accent.colorref());
+                    let line = windows::Win32::Foundation::RECT {
+                        left: slot.x + slot_width / 4,
+                        top: slot.bottom() - scale(2, renderer.dpi),
+                        right: slot.right() - slot_width / 4,
+                        bottom: slot.bottom(),
+                    };
+                    FillRect(hdc, &line, accent);
+                    let _ = DeleteObject(accent);
+                }
+                let icon_x = x + (slot_width - icon_size) / 2;
+                let icon_y = (bar_rect.height - icon_size) / 2;
+                match get_small_icon_for_path(renderer, path, icon_size) {
+                    Some(hicon) => {
+                        let _ = DrawIconEx(hdc, icon_x, icon_y, hicon, icon_size, icon_size, 0, HBRUSH::default(), DI_NORMAL);
+                    }
+                    None => {
+                        let glyph = renderer.icons.get("app");
+                        let (gw, gh) = measure_text(hdc, &glyph);
+                        SetTextColor(hdc, theme.text_secondary.colorref());
+                        draw_text(hdc, x + (slot_width - gw) / 2, (bar_rect.height - gh) / 2, &glyph);
+                    }
+                }
+                renderer.module_bounds.insert(id, slot);
+                x += slot_width;
+            }
+            if hidden > 0 {
+                let more = format!("+{}", hidden);
+                let (_, mh) = measure_text(hdc, &more);
+                SetTextColor(hdc, theme.text_secondary.colorref());
+                draw_text(hdc, x + item_padding / 2, (bar_rect.height - mh) / 2, &more);
+            }
         }
 
         // === CENTER SECTION ===
@@ -152,19 +305,18 @@ pub fn draw_modules(
                 if dragging.as_deref() == Some(id.as_str()) {
                     continue;
                 }
+                let _font = ModuleFontGuard::select(hdc, &config, id, renderer.dpi);
                 let w = match id.as_str() {
                     "clock" => {
                         // Use sample text to get fixed width and prevent layout shifting
-                        let sample = clock_sample_text(&config, renderer.dpi);
-                        let (tw, _) = measure_text(hdc, &sample);
-                        tw + item_padding * 2
+                        clock_sample_width(hdc, &config, renderer.dpi) + item_padding * 2
                     }
                     _ => {
                         // Default measurement for text modules
                         let text = renderer
                             .module_registry
                             .get(id.as_str())
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_default();
                         let (tw, _) = measure_text(hdc, &text);
                         tw + item_padding * 2
@@ -178,12 +330,16 @@ pub fn draw_modules(
                 total_width = total_width.saturating_sub(item_spacing); // remove trailing spacing
                 let mut cx = (bar_rect.width - total_width) / 2;
                 for (id, w) in center_widths.iter() {
+                    let _font = ModuleFontGuard::select(hdc, &config, id, renderer.dpi);
+                    let module_ctx = module_context(renderer, hdc, theme, &config, id);
+                    let theme = module_ctx.as_ref().map(|c| &c.theme).unwrap_or(theme);
+                    let dx = begin_slide(renderer, hdc, id, cx);
                     // Draw each center item
                     if id == "clock" {
                         let clock_text = renderer
                             .module_registry
                             .get("clock")
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_else(|| Local::now().format("%I:%M %p").to_string());
                         let rect = draw_module_text_fixed(
                             hdc,
@@ -195,12 +351,13 @@ pub fn draw_modules(
                             theme,
                             renderer.dpi,
                         );
+                        draw_clock_alarm_badge(renderer, hdc, &rect, theme);
                         renderer.module_bounds.insert("clock".to_string(), rect);
                     } else {
                         let text = renderer
                             .module_registry
                             .get(id.as_str())
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_default();
                         let rect = draw_module_text(
                             hdc,
@@ -215,6 +372,7 @@ pub fn draw_modules(
                         );
                         renderer.module_bounds.insert(id.clone(), rect);
                     }
+                    finish_module(renderer, hdc, &config, id, dx);
                     cx += w + item_spacing;
                 }
             }
@@ -223,22 +381,52 @@ pub fn draw_modules(
         // === RIGHT SECTION (draw right-to-left based on config order) ===
         x = bar_rect.width - padding;
 
-        for id in right_modules.iter().rev() {
+        // Group pills need room of their own when modules don't already have pills
+        let group_padding = if config.appearance.pills.enabled {
+            0
+        } else {
+            scale(config.appearance.pills.padding as i32, renderer.dpi)
+        };
+        let mut previous_group: Option<&str> = None;
+        let right_sequence = right_sequence(&config, &right_modules, dragging.as_deref());
+
+        for (id, group) in right_sequence.iter().rev() {
             if dragging.as_deref() == Some(id.as_str()) {
                 continue;
             }
+            // Space around a group, thin lines between the modules inside it
+            let group = group.as_deref();
+            if group != previous_group {
+                if previous_group.is_some() {
+                    x -= group_padding;
+                }
+                if group.is_some() {
+                    x -= group_padding;
+                }
+            } else if group.is_some() {
+                draw_group_separator(hdc, x + item_spacing / 2, bar_rect, theme, renderer.dpi);
+            }
+            previous_group = group;
+            let _font = ModuleFontGuard::select(hdc, &config, id, renderer.dpi);
+            let module_ctx = module_context(renderer, hdc, theme, &config, id);
+            let theme = module_ctx.as_ref().map(|c| &c.theme).unwrap_or(theme);
+            // Modules are placed right to left, so their right edge is what slides
+            let start = x;
+            let dx = begin_slide(renderer, hdc, id, x);
+            if let Some(end) = renderer.reuse_slot(id, start, dx) {
+                x = end;
+                continue;
+            }
 
             match id.as_str() {
                 "clock" => {
                     let clock_text = renderer
                         .module_registry
                         .get("clock")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| Local::now().format("%I:%M %p").to_string());
                     // Use sample text to get fixed width and prevent layout shifting
-                    let sample = clock_sample_text(&config, renderer.dpi);
-                    let (sample_width, _) = measure_text(hdc, &sample);
-                    let min_width = sample_width + item_padding * 2;
+                    let min_width = clock_sample_width(hdc, &config, renderer.dpi) + item_padding * 2;
                     x -= min_width;
                     let clock_rect = draw_module_text_fixed(
                         hdc,
@@ -250,6 +438,7 @@ pub fn draw_modules(
                         theme,
                         renderer.dpi,
                     );
+                    draw_clock_alarm_badge(renderer, hdc, &clock_rect, theme);
                     renderer.module_bounds.insert("clock".to_string(), clock_rect);
                     x -= item_spacing;
                 }
@@ -258,7 +447,7 @@ pub fn draw_modules(
                     let battery_text = renderer
                         .module_registry
                         .get("battery")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| {
                             let icon = renderer.icons.get("battery");
                             format!("{} --", icon)
@@ -288,7 +477,7 @@ pub fn draw_modules(
                     let volume_text = renderer
                         .module_registry
                         .get("volume")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| renderer.icons.get("volume_high"));
                     // Dynamically calculate width based on actual display text
                     let (text_width, _) = measure_text(hdc, &volume_text);
@@ -318,7 +507,7 @@ pub fn draw_modules(
                             .module_registry
                             .get("network")
                             .map(|m| {
-                                let t = m.display_text(config.as_ref());
+                                let t = sandbox::display_text(m, config.as_ref());
                                 if t.trim().is_empty() {
                                     renderer.icons.get("wifi")
                                 } else {
@@ -377,14 +566,24 @@ pub fn draw_modules(
 
                             if let Some(si) = module.as_any().downcast_ref::<SystemInfoModule>() {
                                 let cpu_bars = downsample_values(si.cpu_history(), max_points);
+                                let cpu_bars = renderer.animations.graph("system_info", "cpu", cpu_bars);
                                 let mem_bars = downsample_values(si.memory_history(), max_points);
-
-                                draw_line_graph(hdc, &cpu_bars, &rect, item_padding, theme.text_primary.colorref());
-                                draw_line_graph(hdc, &mem_bars, &rect, item_padding, theme.text_secondary.colorref());
+                                let mem_bars = renderer.animations.graph("system_info", "memory", mem_bars);
+
+                                let series = vec![
+                                    Series { values: cpu_bars, color: theme.text_primary },
+                                    Series { values: mem_bars, color: theme.text_secondary },
+                                ];
+                                let mut chart = Chart::percent(series, &config.appearance.graphs);
+                                if config.modules.system_info.stack_graphs {
+                                    chart.stacked = true;
+                                    chart.max = 200.0;
+                                }
+                                chart.draw(hdc, &rect, item_padding);
 
                                 // Labels
                                 unsafe {
-                                    let small_font = create_font("Segoe UI Variable Text", scale(9, renderer.dpi), false);
+                                    let small_font = create_font(family, scale(9, renderer.dpi), false);
                                     let prev_font = SelectObject(hdc, small_font);
                                     let label_x = rect.x + item_padding + 2;
                                     let label_y = rect.y + 2;
@@ -397,10 +596,12 @@ pub fn draw_modules(
                                 }
                             } else if let Some(values) = module.graph_values() {
                                 let bars = downsample_values(values, max_points);
-                                draw_line_graph(hdc, &bars, &rect, item_padding, theme.text_secondary.colorref());
+                                let bars = renderer.animations.graph("system_info", "cpu", bars);
+                                let series = vec![Series { values: bars, color: theme.text_secondary }];
+                                Chart::percent(series, &config.appearance.graphs).draw(hdc, &rect, item_padding);
                                 
                                 unsafe {
-                                    let small_font = create_font("Segoe UI Variable Text", scale(9, renderer.dpi), false);
+                                    let small_font = create_font(family, scale(9, renderer.dpi), false);
                                     let prev_font = SelectObject(hdc, small_font);
                                     let _ = SetTextColor(hdc, theme.text_secondary.colorref());
                                     draw_text(hdc, rect.x + item_padding + 2, rect.y + 2, "CPU");
@@ -416,7 +617,7 @@ pub fn draw_modules(
                         let sysinfo_text = renderer
                             .module_registry
                             .get("system_info")
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_else(|| "CPU --  RAM --".to_string());
 
                         // Compute a sensible minimum width based on which parts are configured
@@ -457,7 +658,7 @@ pub fn draw_modules(
                     let media_text = renderer
                         .module_registry
                         .get("media")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_default();
                     if !media_text.is_empty() {
                         let (text_width, _) = measure_text(hdc, &media_text);
@@ -483,7 +684,7 @@ pub fn draw_modules(
                     let clipboard_text = renderer
                         .module_registry
                         .get("clipboard")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| "📋".to_string());
                     let (text_width, _) = measure_text(hdc, &clipboard_text);
                     x -= text_width + item_padding * 2;
@@ -522,10 +723,12 @@ pub fn draw_modules(
                             if let Some(values) = module.graph_values() {
                                 let max_points = (rect.width - item_padding * 2).max(1) as usize;
                                 let bars = downsample_values(values, max_points);
-                                draw_line_graph(hdc, &bars, &rect, item_padding, theme.text_primary.colorref());
+                                let bars = renderer.animations.graph("gpu", "usage", bars);
+                                let series = vec![Series { values: bars, color: theme.text_primary }];
+                                Chart::percent(series, &config.appearance.graphs).draw(hdc, &rect, item_padding);
 
                                 unsafe {
-                                    let small_font = create_font("Segoe UI Variable Text", scale(9, renderer.dpi), false);
+                                    let small_font = create_font(family, scale(9, renderer.dpi), false);
                                     let prev_font = SelectObject(hdc, small_font);
                                     let _ = SetTextColor(hdc, theme.text_primary.colorref());
                                     draw_text(hdc, rect.x + item_padding + 2, rect.y + 2, "GPU");
@@ -541,7 +744,7 @@ pub fn draw_modules(
                         let gpu_text = renderer
                             .module_registry
                             .get("gpu")
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_else(|| renderer.icons.get("gpu"));
                         // Fixed width for "GPU 100%" format
                         let min_width = scale(92, renderer.dpi);
@@ -563,11 +766,367 @@ pub fn draw_modules(
                     }
                 }
 
+                "watchdog" => {
+                    let statuses: Vec<bool> = renderer
+                        .module_registry
+                        .get("watchdog")
+                        .and_then(|m| {
+                            m.as_any()
+                                .downcast_ref::<crate::modules::watchdog::WatchdogModule>()
+                                .map(|wm| wm.statuses().iter().map(|s| s.running).collect())
+                        })
+                        .unwrap_or_default();
+
+                    if !statuses.is_empty() {
+                        let (dot_width, text_height) = measure_text(hdc, "●");
+                        let dot_gap = scale(3, renderer.dpi);
+                        let dots_width = dot_width * statuses.len() as i32
+                            + dot_gap * (statuses.len() as i32 - 1);
+                        let width = dots_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            let text_y = (bar_rect.height - text_height) / 2;
+                            let mut dot_x = x + item_padding;
+                            for running in &statuses {
+                                let color = if *running { theme.success } else { theme.error };
+                                SetTextColor(hdc, color.colorref());
+                                draw_text(hdc, dot_x, text_y, "●");
+                                dot_x += dot_width + dot_gap;
+                            }
+                        }
+
+                        renderer.module_bounds
+                            .insert("watchdog".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
+                "thermal" => {
+                    let (thermal_text, is_warning) = renderer
+                        .module_registry
+                        .get("thermal")
+                        .map(|m| {
+                            let warning = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::thermal::ThermalModule>()
+                                .map(|tm| tm.is_warning())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), warning)
+                        })
+                        .unwrap_or_else(|| ("🌡 --".to_string(), false));
+
+                    let (text_width, text_height) = measure_text(hdc, &thermal_text);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        // Tint the module while above the warning threshold
+                        if is_warning {
+                            let brush = CreateSolidBrush(theme.error.colorref());
+                            let r = windows::Win32::Foundation::RECT {
+                                left: x + 2,
+                                top: y + 1,
+                                right: x + width - 2,
+                                bottom: y + height - 1,
+                            };
+                            FillRect(hdc, &r, brush);
+                            let _ = DeleteObject(brush);
+                            SetTextColor(hdc, theme.background.colorref());
+                        } else {
+                            SetTextColor(hdc, theme.text_primary.colorref());
+                        }
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &thermal_text);
+                    }
+
+                    renderer.module_bounds
+                        .insert("thermal".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
+                "printing" => {
+                    let (printing_text, has_problem) = renderer
+                        .module_registry
+                        .get("printing")
+                        .map(|m| {
+                            let problem = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::printing::PrintingModule>()
+                                .map(|pm| pm.has_problem())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), problem)
+                        })
+                        .unwrap_or_default();
+
+                    // Nothing queued, nothing shown
+                    if !printing_text.is_empty() {
+                        let (text_width, text_height) = measure_text(hdc, &printing_text);
+                        let width = text_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            let color = if has_problem { theme.error } else { theme.text_primary };
+                            SetTextColor(hdc, color.colorref());
+                            draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &printing_text);
+                        }
+
+                        renderer.module_bounds
+                            .insert("printing".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
+                "removable_media" => {
+                    let (media_text, busy) = renderer
+                        .module_registry
+                        .get("removable_media")
+                        .map(|m| {
+                            let busy = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::removable_media::RemovableMediaModule>()
+                                .map(|rm| rm.is_busy())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), busy)
+                        })
+                        .unwrap_or_default();
+
+                    // Only while a drive is attached
+                    if !media_text.is_empty() {
+                        let (text_width, text_height) = measure_text(hdc, &media_text);
+                        let width = text_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            // Accent while copying, so it's clear not to pull the drive
+                            let color = if busy { theme.accent } else { theme.text_primary };
+                            SetTextColor(hdc, color.colorref());
+                            draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &media_text);
+                        }
+
+                        renderer.module_bounds
+                            .insert("removable_media".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
+                "services" => {
+                    let (services_text, has_problem) = renderer
+                        .module_registry
+                        .get("services")
+                        .map(|m| {
+                            let problem = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::services::ServicesModule>()
+                                .map(|sm| sm.has_problem())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), problem)
+                        })
+                        .unwrap_or_else(|| ("⚙ 0/0".to_string(), false));
+
+                    let (text_width, text_height) = measure_text(hdc, &services_text);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        // Highlight when any monitored service is down
+                        if has_problem {
+                            SetTextColor(hdc, theme.error.colorref());
+                        } else {
+                            SetTextColor(hdc, theme.text_primary.colorref());
+                        }
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &services_text);
+                    }
+
+                    renderer.module_bounds
+                        .insert("services".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
+                "connectivity" => {
+                    let (text, latency) = renderer
+                        .module_registry
+                        .get("connectivity")
+                        .map(|m| {
+                            let latency = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::connectivity::ConnectivityModule>()
+                                .and_then(|cm| cm.latency());
+                            (sandbox::display_text(m, config.as_ref()), latency)
+                        })
+                        .unwrap_or_else(|| ("🌐 --".to_string(), None));
+
+                    let (text_width, text_height) = measure_text(hdc, &text);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        use crate::modules::connectivity::Latency;
+                        let color = match latency {
+                            Some(Latency::Good) => theme.success,
+                            Some(Latency::Slow) => theme.warning,
+                            Some(Latency::Bad) => theme.error,
+                            None => theme.text_secondary,
+                        };
+                        SetTextColor(hdc, color.colorref());
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &text);
+                    }
+
+                    renderer.module_bounds
+                        .insert("connectivity".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
+                "security" => {
+                    let (unprotected, warning) = renderer
+                        .module_registry
+                        .get("security")
+                        .and_then(|m| m.as_any().downcast_ref::<crate::modules::security::SecurityModule>())
+                        .map(|sm| (sm.is_unprotected(), sm.has_warning()))
+                        .unwrap_or((false, false));
+
+                    let shield = "🛡";
+                    let (text_width, text_height) = measure_text(hdc, shield);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        let color = if warning && !unprotected { theme.warning } else { theme.text_primary };
+                        SetTextColor(hdc, color.colorref());
+                        let text_y = (bar_rect.height - text_height) / 2;
+                        draw_text(hdc, x + item_padding, text_y, shield);
+
+                        // Red badge on the shield's top-right corner while unprotected
+                        if unprotected {
+                            let size = scale(7, renderer.dpi);
+                            let right = x + item_padding + text_width + size / 3;
+                            let top = text_y - size / 3;
+                            let brush = CreateSolidBrush(theme.error.colorref());
+                            let old_brush = SelectObject(hdc, brush);
+                            let old_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+                            let _ = Ellipse(hdc, right - size, top, right, top + size);
+                            SelectObject(hdc, old_pen);
+                            SelectObject(hdc, old_brush);
+                            let _ = DeleteObject(brush);
+                        }
+                    }
+
+                    renderer.module_bounds
+                        .insert("security".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
+                "monitor_input" => {
+                    let input_text = renderer
+                        .module_registry
+                        .get("monitor_input")
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
+                        .unwrap_or_else(|| "🖥".to_string());
+                    let (text_width, _) = measure_text(hdc, &input_text);
+                    x -= text_width + item_padding * 2;
+                    let input_rect = draw_module_text(
+                        hdc,
+                        x,
+                        bar_rect.height,
+                        &input_text,
+                        item_padding,
+                        theme,
+                        false,
+                        None,
+                        renderer.dpi,
+                    );
+                    renderer.module_bounds.insert("monitor_input".to_string(), input_rect);
+                    x -= item_spacing;
+                }
+
+                "capture" => {
+                    let (capture_text, is_recording) = renderer
+                        .module_registry
+                        .get("capture")
+                        .map(|m| {
+                            let recording = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::capture::CaptureModule>()
+                                .map(|cm| cm.is_recording())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), recording)
+                        })
+                        .unwrap_or_else(|| ("📷".to_string(), false));
+
+                    let (text_width, text_height) = measure_text(hdc, &capture_text);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        // Red recording indicator while a recording is running
+                        if is_recording {
+                            SetTextColor(hdc, theme.error.colorref());
+                        } else {
+                            SetTextColor(hdc, theme.text_primary.colorref());
+                        }
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &capture_text);
+                    }
+
+                    renderer.module_bounds
+                        .insert("capture".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
+                "voice_memo" => {
+                    let (memo_text, is_recording) = renderer
+                        .module_registry
+                        .get("voice_memo")
+                        .map(|m| {
+                            let recording = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::voice_memo::VoiceMemoModule>()
+                                .map(|vm| vm.is_recording())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), recording)
+                        })
+                        .unwrap_or_else(|| ("🎙".to_string(), false));
+
+                    let (text_width, text_height) = measure_text(hdc, &memo_text);
+                    let width = text_width + item_padding * 2;
+                    let height = text_height + item_padding + 2;
+                    let y = (bar_rect.height - height) / 2;
+                    x -= width;
+
+                    unsafe {
+                        // Red with the elapsed time while a memo is being recorded
+                        if is_recording {
+                            SetTextColor(hdc, theme.error.colorref());
+                        } else {
+                            SetTextColor(hdc, theme.text_primary.colorref());
+                        }
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &memo_text);
+                    }
+
+                    renderer.module_bounds
+                        .insert("voice_memo".to_string(), Rect::new(x, y, width, height));
+                    x -= item_spacing;
+                }
+
                 "keyboard_layout" => {
                     let keyboard_text = renderer
                         .module_registry
                         .get("keyboard_layout")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| "EN".to_string());
                     let (text_width, _) = measure_text(hdc, &keyboard_text);
                     x -= text_width + item_padding * 2;
@@ -587,11 +1146,73 @@ pub fn draw_modules(
                     x -= item_spacing;
                 }
 
+                "lock_keys" => {
+                    let keys: Vec<(&str, bool)> = renderer
+                        .module_registry
+                        .get("lock_keys")
+                        .and_then(|m| {
+                            m.as_any()
+                                .downcast_ref::<crate::modules::lock_keys::LockKeysModule>()
+                                .map(|lk| lk.keys().iter().map(|(k, on)| (k.label(), *on)).collect())
+                        })
+                        .unwrap_or_default();
+
+                    if !keys.is_empty() {
+                        let key_gap = scale(4, renderer.dpi);
+                        let (_, text_height) = measure_text(hdc, "A");
+                        let key_widths: Vec<i32> = keys
+                            .iter()
+                            .map(|(label, _)| measure_text(hdc, label).0 + key_gap * 2)
+                            .collect();
+                        let width = key_widths.iter().sum::<i32>()
+                            + key_gap * (keys.len() as i32 - 1)
+                            + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            let text_y = (bar_rect.height - text_height) / 2;
+                            let mut key_x = x + item_padding;
+                            for ((label, on), key_width) in keys.iter().zip(&key_widths) {
+                                // Active locks get an accent pill, inactive ones are dimmed
+                                if *on {
+                                    let pill = windows::Win32::Foundation::RECT {
+                                        left: key_x,
+                                        top: text_y - 1,
+                                        right: key_x + key_width,
+                                        bottom: text_y + text_height + 1,
+                                    };
+                                    let brush = CreateSolidBrush(theme.accent.colorref());
+                                    let pen = CreatePen(PS_SOLID, 1, theme.accent.colorref());
+                                    let old_brush = SelectObject(hdc, brush);
+                                    let old_pen = SelectObject(hdc, pen);
+                                    let radius = scale(6, renderer.dpi);
+                                    let _ = RoundRect(hdc, pill.left, pill.top, pill.right, pill.bottom, radius, radius);
+                                    SelectObject(hdc, old_brush);
+                                    SelectObject(hdc, old_pen);
+                                    let _ = DeleteObject(brush);
+                                    let _ = DeleteObject(pen);
+                                    SetTextColor(hdc, theme.background.colorref());
+                                } else {
+                                    SetTextColor(hdc, theme.text_disabled.colorref());
+                                }
+                                draw_text(hdc, key_x + key_gap, text_y, label);
+                                key_x += key_width + key_gap;
+                            }
+                        }
+
+                        renderer.module_bounds
+                            .insert("lock_keys".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
                 "uptime" => {
                     let uptime_text = renderer
                         .module_registry
                         .get("uptime")
-                        .map(|m| m.display_text(config.as_ref()))
+                        .map(|m| sandbox::display_text(m, config.as_ref()))
                         .unwrap_or_else(|| "0d 0h".to_string());
                     let min_width = scale(72, renderer.dpi);
                     x -= min_width;
@@ -619,7 +1240,7 @@ pub fn draw_modules(
                             .module_registry
                             .get("bluetooth")
                             .map(|m| {
-                                let t = m.display_text(config.as_ref());
+                                let t = sandbox::display_text(m, config.as_ref());
                                 if t.trim().is_empty() {
                                     renderer.icons.get("bluetooth")
                                 } else {
@@ -659,7 +1280,7 @@ pub fn draw_modules(
                         let night_light_text = renderer
                             .module_registry
                             .get("night_light")
-                            .map(|m| m.display_text(config.as_ref()))
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
                             .unwrap_or_else(|| "NL".to_string());
                         let (text_width, _) = measure_text(hdc, &night_light_text);
                         x -= text_width + item_padding * 2;
@@ -683,6 +1304,82 @@ pub fn draw_modules(
                     }
                 }
 
+                "power" => {
+                    // Segoe UI Symbol has the power glyph
+                    let power_font = create_font("Segoe UI Symbol", scale(14, renderer.dpi), false);
+                    unsafe {
+                        let old_font = SelectObject(hdc, power_font);
+                        let power_text = renderer
+                            .module_registry
+                            .get("power")
+                            .map(|m| sandbox::display_text(m, config.as_ref()))
+                            .unwrap_or_else(|| "⏻".to_string());
+                        let (text_width, _) = measure_text(hdc, &power_text);
+                        x -= text_width + item_padding * 2;
+                        let power_rect = draw_module_text(
+                            hdc,
+                            x,
+                            bar_rect.height,
+                            &power_text,
+                            item_padding,
+                            theme,
+                            false,
+                            None,
+                            renderer.dpi,
+                        );
+                        renderer.module_bounds.insert("power".to_string(), power_rect);
+                        x -= item_spacing;
+
+                        let _ = SelectObject(hdc, old_font);
+                        let _ = DeleteObject(power_font);
+                    }
+                }
+
+                "user" => {
+                    let user_config = &config.modules.user;
+                    let picture_size = scale(20, renderer.dpi);
+                    let (name, picture) = renderer
+                        .module_registry
+                        .get("user")
+                        .map(|m| {
+                            let picture = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::user::UserModule>()
+                                .and_then(|um| um.picture(picture_size as u32))
+                                .map(|p| p.to_string_lossy().to_string());
+                            (sandbox::display_text(m, config.as_ref()), picture)
+                        })
+                        .unwrap_or_default();
+                    let picture = picture
+                        .filter(|_| user_config.show_picture)
+                        .and_then(|path| get_picture(renderer, &path, picture_size));
+                    // Without a picture the glyph stands in, so the button never disappears
+                    let text = match (&picture, name.is_empty()) {
+                        (None, true) => "👤".to_string(),
+                        (None, false) => format!("👤 {}", name),
+                        (Some(_), _) => name,
+                    };
+                    let (text_width, _) = if text.is_empty() { (0, 0) } else { measure_text(hdc, &text) };
+                    let gap = if picture.is_some() && text_width > 0 { scale(6, renderer.dpi) } else { 0 };
+                    let picture_width = if picture.is_some() { picture_size } else { 0 };
+                    let content_width = picture_width + gap + text_width;
+                    x -= content_width + item_padding * 2;
+
+                    let user_rect = Rect::new(x, 0, content_width + item_padding * 2, bar_rect.height);
+                    if let Some(bitmap) = picture {
+                        let px = x + item_padding;
+                        let py = (bar_rect.height - picture_size) / 2;
+                        unsafe { draw_round_picture(hdc, bitmap, px, py, picture_size) };
+                    }
+                    if text_width > 0 {
+                        let (_, text_height) = measure_text(hdc, &text);
+                        unsafe { SetTextColor(hdc, theme.text_primary.colorref()) };
+                        draw_text(hdc, x + item_padding + picture_width + gap, (bar_rect.height - text_height) / 2, &text);
+                    }
+                    renderer.module_bounds.insert("user".to_string(), user_rect);
+                    x -= item_spacing;
+                }
+
                 "disk" => {
                     let disk_width = scale(24, renderer.dpi);
                     let disk_height = bar_rect.height - scale(8, renderer.dpi);
@@ -747,12 +1444,44 @@ pub fn draw_modules(
                 }
 
                 "weather" => {
-                    let weather_text = renderer
+                    let (weather_text, has_alerts) = renderer
                         .module_registry
                         .get("weather")
-                        .map(|m| m.display_text(config.as_ref()))
-                        .unwrap_or_else(|| "🌡️ ...".to_string());
-                    if !weather_text.is_empty() {
+                        .map(|m| {
+                            let alerts = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::weather::WeatherModule>()
+                                .map(|wm| wm.has_alerts())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), alerts)
+                        })
+                        .unwrap_or_else(|| ("🌡️ ...".to_string(), false));
+                    if !weather_text.is_empty() && has_alerts {
+                        let (text_width, text_height) = measure_text(hdc, &weather_text);
+                        let width = text_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            // Tint the module while a severe-weather alert is in effect
+                            let brush = CreateSolidBrush(theme.warning.colorref());
+                            let r = windows::Win32::Foundation::RECT {
+                                left: x + 2,
+                                top: y + 1,
+                                right: x + width - 2,
+                                bottom: y + height - 1,
+                            };
+                            FillRect(hdc, &r, brush);
+                            let _ = DeleteObject(brush);
+                            SetTextColor(hdc, theme.background.colorref());
+                            draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &weather_text);
+                        }
+
+                        renderer.module_bounds
+                            .insert("weather".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    } else if !weather_text.is_empty() {
                         let (text_width, _) = measure_text(hdc, &weather_text);
                         x -= text_width + item_padding * 2;
                         let weather_rect = draw_module_text(
@@ -772,8 +1501,93 @@ pub fn draw_modules(
                     }
                 }
 
+                "sports" => {
+                    let (sports_text, is_live) = renderer
+                        .module_registry
+                        .get("sports")
+                        .map(|m| {
+                            let live = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::sports::SportsModule>()
+                                .map(|sm| sm.is_live())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), live)
+                        })
+                        .unwrap_or_default();
+
+                    // No followed teams: take no space
+                    if !sports_text.is_empty() {
+                        let (text_width, text_height) = measure_text(hdc, &sports_text);
+                        let width = text_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            // Live matches stand out in the accent color
+                            let color = if is_live { theme.accent } else { theme.text_primary };
+                            SetTextColor(hdc, color.colorref());
+                            draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &sports_text);
+                        }
+
+                        renderer.module_bounds
+                            .insert("sports".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
+                "stocks" => {
+                    let (stocks_text, is_down) = renderer
+                        .module_registry
+                        .get("stocks")
+                        .map(|m| {
+                            let down = m
+                                .as_any()
+                                .downcast_ref::<crate::modules::stocks::StocksModule>()
+                                .map(|sm| sm.is_down())
+                                .unwrap_or(false);
+                            (sandbox::display_text(m, config.as_ref()), down)
+                        })
+                        .unwrap_or_default();
+
+                    // Nothing configured: take no space
+                    if !stocks_text.is_empty() {
+                        let (text_width, text_height) = measure_text(hdc, &stocks_text);
+                        let width = text_width + item_padding * 2;
+                        let height = text_height + item_padding + 2;
+                        let y = (bar_rect.height - height) / 2;
+                        x -= width;
+
+                        unsafe {
+                            let color = if is_down { theme.error } else { theme.success };
+                            SetTextColor(hdc, color.colorref());
+                            draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &stocks_text);
+                        }
+
+                        renderer.module_bounds
+                            .insert("stocks".to_string(), Rect::new(x, y, width, height));
+                        x -= item_spacing;
+                    }
+                }
+
+                header if header.starts_with(crate::config::GROUP_PREFIX) => {
+                    if let Some(group) = config.modules.group(header) {
+                        let arrow = if group.collapsed { "►" } else { "▼" };
+                        let text = format!("{} {}", group.label(), arrow);
+                        let (text_width, text_height) = measure_text(hdc, &text);
+                        x -= text_width + item_padding * 2;
+                        SetTextColor(hdc, theme.text_secondary.colorref());
+                        draw_text(hdc, x + item_padding, (bar_rect.height - text_height) / 2, &text);
+                        let rect = Rect::new(x, 0, text_width + item_padding * 2, bar_rect.height);
+                        renderer.module_bounds.insert(header.to_string(), rect);
+                        x -= item_spacing;
+                    }
+                }
+
                 _ => {}
             }
+            renderer.record_slot(id, start, x);
+            finish_module(renderer, hdc, &config, id, dx);
         }
 
         // If a drag is active, draw the dragged item as an overlay and a drop marker
@@ -784,7 +1598,19 @@ pub fn draw_modules(
                 let display = renderer
                     .module_registry
                     .get(drag_id)
-                    .map(|m| m.display_text(config.as_ref()))
+                    .map(|m| sandbox::display_text(m, config.as_ref()))
+                    .or_else(|| {
+                        // Running app icons show their window title while dragged
+                        let hwnd = crate::modules::tasks::item_hwnd(drag_id)?;
+                        renderer
+                            .module_registry
+                            .get("tasks")?
+                            .as_any()
+                            .downcast_ref::<crate::modules::tasks::TasksModule>()?
+                            .title(hwnd.0 as isize)
+                            .map(|t| crate::utils::truncate_string(t, 40))
+                    })
+                    .or_else(|| config.modules.group(drag_id).map(|g| g.label().to_string()))
                     .unwrap_or_else(|| drag_id.clone());
 
                 let (text_w, text_h) = measure_text(hdc, &display);
@@ -829,6 +1655,127 @@ pub fn draw_modules(
     }
 }
 
+/// The right section's ids in order, each group expanded to its header and
+/// (unless collapsed) its modules, paired with the group they're drawn in
+fn right_sequence(
+    config: &crate::config::Config,
+    right_modules: &[String],
+    dragging: Option<&str>,
+) -> Vec<(String, Option<String>)> {
+    let modules = &config.modules;
+    let mut sequence = Vec::new();
+    for id in right_modules {
+        match modules.group(id) {
+            Some(group) => {
+                // A dragged group is drawn as one overlay
+                if dragging == Some(id.as_str()) {
+                    continue;
+                }
+                sequence.push((id.clone(), Some(id.clone())));
+                if !group.collapsed {
+                    sequence.extend(group.modules.iter().map(|m| (m.clone(), Some(id.clone()))));
+                }
+            }
+            // Drawn by its group
+            None if modules.group_of(id).is_some() => {}
+            None => sequence.push((id.clone(), None)),
+        }
+    }
+    sequence
+}
+
+/// Thin vertical line between two modules of a group
+fn draw_group_separator(hdc: HDC, x: i32, bar_rect: &Rect, theme: &Theme, dpi: u32) {
+    let inset = scale(9, dpi);
+    unsafe {
+        let pen = CreatePen(PS_SOLID, 1, theme.border.colorref());
+        let old_pen = SelectObject(hdc, pen);
+        let _ = MoveToEx(hdc, x, inset, None);
+        let _ = LineTo(hdc, x, bar_rect.height - inset);
+        SelectObject(hdc, old_pen);
+        let _ = DeleteObject(pen);
+    }
+}
+
+/// Start drawing a module whose layout puts it at `target`: shift drawing to
+/// where its slide animation has it this frame, returning the offset
+fn begin_slide(renderer: &mut super::renderer::Renderer, hdc: HDC, id: &str, target: i32) -> i32 {
+    let dx = renderer.animations.slide(id, target) - target;
+    unsafe {
+        let _ = SetViewportOrgEx(hdc, dx, 0, None);
+    }
+    dx
+}
+
+/// Finish drawing a module: undo its slide offset, move its bounds along so
+/// clicks and effects follow it, and hand it to the animation engine for fades
+fn finish_module(
+    renderer: &mut super::renderer::Renderer,
+    hdc: HDC,
+    config: &crate::config::Config,
+    id: &str,
+    dx: i32,
+) {
+    unsafe {
+        let _ = SetViewportOrgEx(hdc, 0, 0, None);
+    }
+    let Some(rect) = renderer.module_bounds.get_mut(id).map(|rect| {
+        rect.x += dx;
+        *rect
+    }) else {
+        return;
+    };
+    let text = renderer
+        .module_registry
+        .get(id)
+        .map(|m| sandbox::display_text(m, config))
+        .unwrap_or_default();
+    let mut pixels = unsafe { renderer.back_pixels() };
+    renderer.animations.finish_module(id, &text, rect, pixels.as_mut());
+}
+
+/// A module's own font from `[appearance.module_fonts]`, selected into the DC
+/// until dropped
+struct ModuleFontGuard {
+    hdc: HDC,
+    font: HFONT,
+    previous: HGDIOBJ,
+}
+
+impl ModuleFontGuard {
+    fn select(hdc: HDC, config: &crate::config::Config, id: &str, dpi: u32) -> Option<Self> {
+        let appearance = &config.appearance;
+        let custom = appearance.module_fonts.get(id)?;
+        let family = custom.family.as_deref().unwrap_or(&appearance.font_family);
+        let size = custom.size.unwrap_or(appearance.font_size);
+        let font = create_font(family, scale(size as i32, dpi), false);
+        let previous = unsafe { SelectObject(hdc, font) };
+        Some(Self { hdc, font, previous })
+    }
+}
+
+impl Drop for ModuleFontGuard {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.hdc, self.previous);
+            let _ = DeleteObject(self.font);
+        }
+    }
+}
+
+/// Render context with a module's own colors, when the config gives it any
+fn module_context(
+    renderer: &super::renderer::Renderer,
+    hdc: HDC,
+    theme: &Theme,
+    config: &crate::config::Config,
+    id: &str,
+) -> Option<ModuleRenderContext> {
+    let colors = config.appearance.module_colors.get(id)?;
+    let module = renderer.module_registry.get(id);
+    Some(ModuleRenderContext::for_module(hdc, theme, renderer.dpi, module, colors))
+}
+
 /// Draw a module button with modern hover effect
 pub fn draw_module_button(
     hdc: HDC,
@@ -981,6 +1928,70 @@ pub fn clock_sample_text(config: &crate::config::Config, dpi: u32) -> String {
     result
 }
 
+/// Sample strings for each custom clock format, or the built-in one when none
+/// are set. Each format is rendered for a Wednesday in September in the evening
+/// (typically the widest names) with every digit turned into a 0.
+pub fn clock_sample_texts(config: &crate::config::Config, dpi: u32) -> Vec<String> {
+    use chrono::TimeZone;
+
+    let formats: Vec<&String> = config
+        .modules
+        .clock
+        .formats
+        .iter()
+        .filter(|f| crate::modules::clock::is_valid_format(f))
+        .collect();
+    let sample_time = Local.with_ymd_and_hms(2000, 9, 27, 20, 0, 0).earliest();
+    match sample_time {
+        Some(time) if !formats.is_empty() => formats
+            .into_iter()
+            .map(|format| {
+                time.format(format)
+                    .to_string()
+                    .chars()
+                    .map(|c| if c.is_ascii_digit() { '0' } else { c })
+                    .collect()
+            })
+            .collect(),
+        _ => vec![clock_sample_text(config, dpi)],
+    }
+}
+
+/// Width of the widest clock text the configuration can produce, so the clock
+/// keeps its width as it ticks and as formats are cycled
+pub fn clock_sample_width(hdc: HDC, config: &crate::config::Config, dpi: u32) -> i32 {
+    clock_sample_texts(config, dpi)
+        .iter()
+        .map(|sample| measure_text(hdc, sample).0)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Accent dot on the clock's top-right corner while an alarm is armed
+fn draw_clock_alarm_badge(renderer: &super::renderer::Renderer, hdc: HDC, rect: &Rect, theme: &Theme) {
+    let armed = renderer
+        .module_registry
+        .get("clock")
+        .and_then(|m| m.as_any().downcast_ref::<crate::modules::clock::ClockModule>())
+        .map(|cm| cm.has_alarm())
+        .unwrap_or(false);
+    if !armed {
+        return;
+    }
+    let size = scale(5, renderer.dpi);
+    let right = rect.x + rect.width - scale(2, renderer.dpi);
+    let top = rect.y + scale(2, renderer.dpi);
+    unsafe {
+        let brush = CreateSolidBrush(theme.accent.colorref());
+        let old_brush = SelectObject(hdc, brush);
+        let old_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+        let _ = Ellipse(hdc, right - size, top, right, top + size);
+        SelectObject(hdc, old_pen);
+        SelectObject(hdc, old_brush);
+        let _ = DeleteObject(brush);
+    }
+}
+
 /// Draw module text with a minimum width to prevent layout shifting
 pub fn draw_module_text_fixed(
     hdc: HDC,
@@ -1008,34 +2019,71 @@ pub fn draw_module_text_fixed(
     Rect::new(x, y, width, height)
 }
 
-/// Try to load a small icon handle (HICON) for an executable path and cache it
-pub fn get_small_icon_for_path(renderer: &mut super::renderer::Renderer, path: &str) -> Option<HICON> {
-    if path.is_empty() {
-        return None;
+/// Draw a picture clipped to a circle, the way Windows shows account pictures
+unsafe fn draw_round_picture(hdc: HDC, bitmap: HBITMAP, x: i32, y: i32, size: i32) {
+    let mut info = BITMAP::default();
+    let got = GetObjectW(
+        HGDIOBJ(bitmap.0),
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut info as *mut BITMAP as *mut std::ffi::c_void),
+    );
+    if got == 0 {
+        return;
     }
+    // The region is in device units, which the bar's DC doesn't offset
+    let region = CreateEllipticRgn(x, y, x + size + 1, y + size + 1);
+    SelectClipRgn(hdc, region);
+    let source = CreateCompatibleDC(hdc);
+    let old = SelectObject(source, bitmap);
+    let blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: 255,
+        AlphaFormat: AC_SRC_ALPHA as u8,
+    };
+    let _ = AlphaBlend(hdc, x, y, size, size, source, 0, 0, info.bmWidth, info.bmHeight.abs(), blend);
+    SelectObject(source, old);
+    let _ = DeleteDC(source);
+    SelectClipRgn(hdc, None);
+    let _ = DeleteObject(region);
+}
 
-    if let Some(icon) = renderer.icon_cache.get(path) {
-        return Some(*icon);
-    }
+/// Load a picture as a square 32-bit bitmap through the shell and cache it.
+/// Photos come back without alpha, so they're made opaque for `AlphaBlend`.
+pub fn get_picture(renderer: &mut super::renderer::Renderer, path: &str, size: i32) -> Option<HBITMAP> {
+    use windows::Win32::Foundation::SIZE;
+    use windows::Win32::UI::Shell::{IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF_RESIZETOFIT};
 
-    unsafe {
-        let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-        let mut sfi = windows::Win32::UI::Shell::SHFILEINFOW::default();
-        let flags = windows::Win32::UI::Shell::SHGFI_ICON | windows::Win32::UI::Shell::SHGFI_SMALLICON;
-        let res = windows::Win32::UI::Shell::SHGetFileInfoW(
-            windows::core::PCWSTR(wide.as_ptr()),
-            windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(0),
-            Some(&mut sfi as *mut windows::Win32::UI::Shell::SHFILEINFOW),
-            std::mem::size_of::<windows::Win32::UI::Shell::SHFILEINFOW>() as u32,
-            flags,
-        );
-
-        if res != 0 && !sfi.hIcon.is_invalid() {
-            let icon = sfi.hIcon;
-            renderer.icon_cache.insert(path.to_string(), icon);
-            return Some(icon);
-        }
+    let key = (path.to_string(), size);
+    if let Some(bitmap) = renderer.picture_cache.get(&key) {
+        return *bitmap;
     }
+    let bitmap = unsafe {
+        let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+        SHCreateItemFromParsingName::<_, _, IShellItemImageFactory>(windows::core::PCWSTR(wide.as_ptr()), None)
+            .and_then(|factory| factory.GetImage(SIZE { cx: size, cy: size }, SIIGBF_RESIZETOFIT))
+            .ok()
+            .inspect(|bitmap| {
+                let mut info = BITMAP::default();
+                GetObjectW(HGDIOBJ(bitmap.0), std::mem::size_of::<BITMAP>() as i32, Some(&mut info as *mut BITMAP as *mut std::ffi::c_void));
+                if info.bmBitsPixel == 32 && !info.bmBits.is_null() {
+                    let pixels = std::slice::from_raw_parts_mut(
+                        info.bmBits as *mut u8,
+                        (info.bmWidthBytes * info.bmHeight.abs()) as usize,
+                    );
+                    if pixels.chunks_exact(4).all(|px| px[3] == 0) {
+                        pixels.chunks_exact_mut(4).for_each(|px| px[3] = 255);
+                    }
+                }
+            })
+    };
+    renderer.picture_cache.insert(key, bitmap);
+    bitmap
+}
 
-    None
+/// The icon for an executable path at `size` pixels, or None while it's still
+/// loading or if there isn't one
+pub fn get_small_icon_for_path(renderer: &mut super::renderer::Renderer, path: &str, size: i32) -> Option<HICON> {
+    let hwnd = renderer.hwnd;
+    renderer.icon_cache.get(path, size, hwnd)
 }
\ No newline at end of file
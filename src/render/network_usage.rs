@@ -0,0 +1,452 @@
+//! Network usage popup
+//!
+//! A panel next to the bar with the network module's throughput for the last
+//! few minutes, download and upload drawn as two lines that scroll left as
+//! samples come in, and a table of the processes moving the most data. It
+//! redraws every second from the module's samples and never takes focus; the
+//! close button or another click on the network module closes it.
+//!
+//! Per-process rates come from TCP connection statistics when Windows allows
+//! collecting them (elevated). Otherwise each process with an open connection is
+//! estimated from its I/O counters, which can't split download from upload.
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::chart::{Chart, Series};
+use super::drawing::{create_font, downsample_values, draw_text, measure_text, scale};
+use crate::config::BarPosition;
+use crate::modules::network::{self, NetworkModule, ProcessTraffic, SPEED_HISTORY_LEN};
+use crate::sounds::{self, SoundEvent};
+use crate::utils::{format_bytes, Rect};
+use crate::window::renderer::with_renderer;
+use crate::window::state::get_window_state;
+
+const USAGE_CLASS: &str = "TopBarNetworkUsageClass";
+const REFRESH_TIMER: usize = 1;
+const REFRESH_MS: u32 = 1000;
+
+/// Sizes before DPI scaling
+const WIDTH: i32 = 380;
+const PADDING: i32 = 12;
+const HEADER_HEIGHT: i32 = 36;
+const GRAPH_HEIGHT: i32 = 96;
+/// Line under the graph with the time axis
+const AXIS_HEIGHT: i32 = 18;
+const ROW_HEIGHT: i32 = 22;
+/// Width of each rate column in the table
+const RATE_COLUMN: i32 = 80;
+/// Rows in the process table
+const TABLE_ROWS: i32 = 10;
+
+/// The popup window, if shown
+static USAGE_HWND: AtomicIsize = AtomicIsize::new(0);
+/// Whether the mouse is over the close button
+static CLOSE_HOVER: AtomicBool = AtomicBool::new(false);
+
+/// WM_MOUSELEAVE message constant
+const WM_MOUSELEAVE: u32 = 0x02A3;
+
+/// What the popup shows, read from the network module on each paint
+#[derive(Default)]
+struct Snapshot {
+    download: Vec<f32>,
+    upload: Vec<f32>,
+    speeds: (u64, u64),
+    processes: Vec<ProcessTraffic>,
+}
+
+/// Show the popup beside the bar, centered on `x`, `y` (screen coordinates)
+/// along the bar, or close it if it's already open
+pub fn toggle_network_usage(bar: HWND, x: i32, y: i32) {
+    if !hide_network_usage() {
+        if let Err(e) = unsafe { show(bar, x, y) } {
+            log::warn!("Failed to show network usage: {}", e);
+        }
+    }
+}
+
+/// Close the popup; returns whether it was open
+pub fn hide_network_usage() -> bool {
+    let hwnd = HWND(USAGE_HWND.swap(0, Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() {
+        return false;
+    }
+    unsafe {
+        let _ = DestroyWindow(hwnd);
+    }
+    sounds::play_event(SoundEvent::PopupClose);
+    true
+}
+
+unsafe fn show(bar: HWND, x: i32, y: i32) -> Result<()> {
+    register_class()?;
+    let class = to_wide(USAGE_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let hwnd = CreateWindowExW(
+        WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+        PCWSTR(class.as_ptr()),
+        PCWSTR::null(),
+        WS_POPUP,
+        0, 0, 0, 0,
+        None,
+        None,
+        hinstance,
+        None,
+    )?;
+    USAGE_HWND.store(hwnd.0 as isize, Ordering::SeqCst);
+    network::set_breakdown_wanted(true);
+
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let width = scale(WIDTH, dpi);
+    let height = scale(HEADER_HEIGHT + GRAPH_HEIGHT + AXIS_HEIGHT + ROW_HEIGHT * (TABLE_ROWS + 2) + PADDING, dpi);
+
+    let mut bar_rect = RECT::default();
+    let _ = GetWindowRect(bar, &mut bar_rect);
+    let monitor = MonitorFromWindow(bar, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcMonitor;
+    let margin = scale(8, dpi);
+    let gap = scale(6, dpi);
+    let center_x = (x - width / 2).clamp(area.left + margin, area.right - margin - width);
+    let center_y = (y - height / 2).clamp(area.top + margin, area.bottom - margin - height);
+    // Open away from the screen edge the bar is on
+    let edge = get_window_state()
+        .map(|s| s.read().config.appearance.position)
+        .unwrap_or(BarPosition::Top);
+    let (left, top) = match edge {
+        BarPosition::Top => (center_x, bar_rect.bottom + gap),
+        BarPosition::Bottom => (center_x, bar_rect.top - gap - height),
+        BarPosition::Left => (bar_rect.right + gap, center_y),
+        BarPosition::Right => (bar_rect.left - gap - width, center_y),
+    };
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, left, top, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(12, dpi), scale(12, dpi));
+    SetWindowRgn(hwnd, region, true);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    SetTimer(hwnd, REFRESH_TIMER, REFRESH_MS, None);
+    sounds::play_event(SoundEvent::PopupOpen);
+    Ok(())
+}
+
+fn snapshot() -> Snapshot {
+    with_renderer(|renderer| {
+        let module = renderer
+            .module_registry
+            .get("network")?
+            .as_any()
+            .downcast_ref::<NetworkModule>()?;
+        let (download, upload) = module.speed_history();
+        Some(Snapshot {
+            download,
+            upload,
+            speeds: module.speeds(),
+            processes: module.process_table().to_vec(),
+        })
+    })
+    .flatten()
+    .unwrap_or_default()
+}
+
+fn close_rect(client: &RECT, dpi: u32) -> RECT {
+    RECT {
+        left: client.right - scale(PADDING + 24, dpi),
+        top: scale(8, dpi),
+        right: client.right - scale(PADDING, dpi),
+        bottom: scale(HEADER_HEIGHT - 8, dpi),
+    }
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+/// Draw the download and upload series into `graph`, scaled to `max` and
+/// right-aligned so the newest sample is at the right edge whatever the
+/// history's length
+unsafe fn draw_rates(hdc: HDC, data: &Snapshot, max: f32, graph: &Rect, theme: &crate::theme::Theme) {
+    let len = data.download.len().max(data.upload.len());
+    if len < 2 {
+        return;
+    }
+    let width = graph.width * len as i32 / SPEED_HISTORY_LEN as i32;
+    let area = Rect::new(graph.right() - width, graph.y, width, graph.height);
+    let points = (area.width / 2).max(2) as usize;
+    let series = vec![
+        Series { values: downsample_values(data.download.clone(), points), color: theme.accent },
+        Series { values: downsample_values(data.upload.clone(), points), color: theme.success },
+    ];
+    let style = get_window_state()
+        .map(|s| s.read().config.appearance.graphs.clone())
+        .unwrap_or_default();
+    let chart = Chart {
+        series,
+        max,
+        stacked: false,
+        style: &style,
+    };
+    chart.draw(hdc, &area, 0);
+}
+
+/// Right-align `text` so it ends at `right`
+unsafe fn draw_right(hdc: HDC, right: i32, y: i32, text: &str) {
+    let (w, _) = measure_text(hdc, text);
+    draw_text(hdc, right - w, y, text);
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+    let data = snapshot();
+
+    let mut client = RECT::default();
+    let _ = GetClientRect(hwnd, &mut client);
+
+    // Draw off-screen so the once-a-second redraw doesn't flicker
+    let mem_dc = CreateCompatibleDC(hdc);
+    let buffer = CreateCompatibleBitmap(hdc, client.right, client.bottom);
+    let old_buffer = SelectObject(mem_dc, buffer);
+
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(mem_dc, &client, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(mem_dc, &client, border);
+    let _ = DeleteObject(border);
+    SetBkMode(mem_dc, TRANSPARENT);
+
+    let family = font_family();
+    let title_font = create_font(&family, scale(14, dpi), true);
+    let text_font = create_font(&family, scale(12, dpi), false);
+    let old_font = SelectObject(mem_dc, title_font);
+    let padding = scale(PADDING, dpi);
+    let right = client.right - padding;
+    let download_color = theme.accent.colorref();
+    let upload_color = theme.success.colorref();
+
+    // Header: title, current rates and the close button
+    SetTextColor(mem_dc, theme.text_primary.colorref());
+    let (_, title_height) = measure_text(mem_dc, "Network Usage");
+    let header_y = (scale(HEADER_HEIGHT, dpi) - title_height) / 2;
+    draw_text(mem_dc, padding, header_y, "Network Usage");
+
+    SelectObject(mem_dc, text_font);
+    let close = close_rect(&client, dpi);
+    if CLOSE_HOVER.load(Ordering::Relaxed) {
+        let brush = CreateSolidBrush(theme.background_hover.colorref());
+        let region = CreateRoundRectRgn(close.left, close.top, close.right, close.bottom, scale(6, dpi), scale(6, dpi));
+        let _ = FillRgn(mem_dc, region, brush);
+        let _ = DeleteObject(region);
+        let _ = DeleteObject(brush);
+    }
+    SetTextColor(mem_dc, theme.text_secondary.colorref());
+    let (w, h) = measure_text(mem_dc, "✕");
+    draw_text(mem_dc, close.left + (close.right - close.left - w) / 2, close.top + (close.bottom - close.top - h) / 2, "✕");
+
+    let (_, text_height) = measure_text(mem_dc, "0");
+    let rates_y = (scale(HEADER_HEIGHT, dpi) - text_height) / 2;
+    SetTextColor(mem_dc, upload_color);
+    let upload = format!("↑ {}/s", format_bytes(data.speeds.1));
+    let upload_right = close.left - scale(8, dpi);
+    draw_right(mem_dc, upload_right, rates_y, &upload);
+    let (upload_width, _) = measure_text(mem_dc, &upload);
+    SetTextColor(mem_dc, download_color);
+    draw_right(mem_dc, upload_right - upload_width - scale(10, dpi), rates_y, &format!("↓ {}/s", format_bytes(data.speeds.0)));
+
+    // Graph, scaled to the busiest second in view
+    let graph_top = scale(HEADER_HEIGHT, dpi);
+    let graph = Rect::new(padding, graph_top, client.right - padding * 2, scale(GRAPH_HEIGHT, dpi));
+    let graph_bg = CreateSolidBrush(theme.background_secondary.colorref());
+    let graph_rect = RECT {
+        left: graph.x,
+        top: graph.y,
+        right: graph.right(),
+        bottom: graph.y + graph.height,
+    };
+    FillRect(mem_dc, &graph_rect, graph_bg);
+    let _ = DeleteObject(graph_bg);
+    let peak = data.download.iter().chain(data.upload.iter()).copied().fold(0.0, f32::max);
+    let max = peak.max(1.0);
+    draw_rates(mem_dc, &data, max, &graph, &theme);
+
+    let axis_y = graph.y + graph.height + scale(2, dpi);
+    SetTextColor(mem_dc, theme.text_secondary.colorref());
+    draw_text(mem_dc, padding, axis_y, &format!("{} min ago", SPEED_HISTORY_LEN / 60));
+    draw_right(mem_dc, right, axis_y, "now");
+    let peak_label = format!("peak {}/s", format_bytes(peak as u64));
+    let (peak_width, _) = measure_text(mem_dc, &peak_label);
+    draw_text(mem_dc, (client.right - peak_width) / 2, axis_y, &peak_label);
+
+    // Process table
+    let row_height = scale(ROW_HEIGHT, dpi);
+    let row_offset = (row_height - text_height) / 2;
+    let table_top = axis_y - scale(2, dpi) + scale(AXIS_HEIGHT, dpi);
+    let download_right = right - scale(RATE_COLUMN, dpi);
+    let name_right = download_right - scale(RATE_COLUMN, dpi);
+    draw_text(mem_dc, padding, table_top + row_offset, "Process");
+    draw_right(mem_dc, download_right, table_top + row_offset, "Download");
+    draw_right(mem_dc, right, table_top + row_offset, "Upload");
+    let line = CreateSolidBrush(theme.border.colorref());
+    let divider = RECT {
+        left: padding,
+        top: table_top + row_height - 1,
+        right,
+        bottom: table_top + row_height,
+    };
+    FillRect(mem_dc, &divider, line);
+    let _ = DeleteObject(line);
+
+    let estimated = data.processes.iter().any(|p| p.estimated);
+    for (i, process) in data.processes.iter().take(TABLE_ROWS as usize).enumerate() {
+        let y = table_top + row_height * (i as i32 + 1) + row_offset;
+        SetTextColor(mem_dc, theme.text_primary.colorref());
+        let mut name: Vec<u16> = process.name.encode_utf16().collect();
+        let mut name_rect = RECT {
+            left: padding,
+            top: y,
+            right: name_right - scale(8, dpi),
+            bottom: y + text_height,
+        };
+        DrawTextW(mem_dc, &mut name, &mut name_rect, DT_LEFT | DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX);
+        if process.estimated {
+            // Only the combined rate is known
+            SetTextColor(mem_dc, theme.text_secondary.colorref());
+            draw_right(mem_dc, right, y, &format!("⇅ {}/s", format_bytes(process.total_speed())));
+        } else {
+            SetTextColor(mem_dc, download_color);
+            draw_right(mem_dc, download_right, y, &format!("{}/s", format_bytes(process.download_speed)));
+            SetTextColor(mem_dc, upload_color);
+            draw_right(mem_dc, right, y, &format!("{}/s", format_bytes(process.upload_speed)));
+        }
+    }
+
+    let footer_y = table_top + row_height * (TABLE_ROWS + 1) + row_offset;
+    SetTextColor(mem_dc, theme.text_secondary.colorref());
+    if data.processes.is_empty() {
+        // Processes are compared between samples a couple of seconds apart
+        let hint = if data.download.len() < 3 { "Sampling..." } else { "No network activity" };
+        draw_text(mem_dc, padding, table_top + row_height + row_offset, hint);
+    }
+    if estimated {
+        draw_text(mem_dc, padding, footer_y, "Estimated from process I/O; run as administrator for exact rates");
+    }
+
+    let _ = BitBlt(hdc, 0, 0, client.right, client.bottom, mem_dc, 0, 0, SRCCOPY);
+
+    SelectObject(mem_dc, old_font);
+    let _ = DeleteObject(title_font);
+    let _ = DeleteObject(text_font);
+    SelectObject(mem_dc, old_buffer);
+    let _ = DeleteObject(buffer);
+    let _ = DeleteDC(mem_dc);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(USAGE_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn point_from_lparam(lparam: LPARAM) -> POINT {
+    POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    }
+}
+
+fn over_close(hwnd: HWND, point: POINT) -> bool {
+    let mut client = RECT::default();
+    unsafe {
+        let _ = GetClientRect(hwnd, &mut client);
+        let dpi = GetDpiForWindow(hwnd).max(96);
+        PtInRect(&close_rect(&client, dpi), point).as_bool()
+    }
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+
+        WM_TIMER if wparam.0 == REFRESH_TIMER => {
+            let _ = InvalidateRect(hwnd, None, false);
+            LRESULT(0)
+        }
+
+        WM_MOUSEMOVE => {
+            let hover = over_close(hwnd, point_from_lparam(lparam));
+            if CLOSE_HOVER.swap(hover, Ordering::Relaxed) != hover {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            let mut tme = windows::Win32::UI::Input::KeyboardAndMouse::TRACKMOUSEEVENT {
+                cbSize: std::mem::size_of::<windows::Win32::UI::Input::KeyboardAndMouse::TRACKMOUSEEVENT>() as u32,
+                dwFlags: windows::Win32::UI::Input::KeyboardAndMouse::TME_LEAVE,
+                hwndTrack: hwnd,
+                dwHoverTime: 0,
+            };
+            let _ = windows::Win32::UI::Input::KeyboardAndMouse::TrackMouseEvent(&mut tme);
+            LRESULT(0)
+        }
+
+        WM_MOUSELEAVE => {
+            if CLOSE_HOVER.swap(false, Ordering::Relaxed) {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            if over_close(hwnd, point_from_lparam(lparam)) {
+                hide_network_usage();
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = KillTimer(hwnd, REFRESH_TIMER);
+            CLOSE_HOVER.store(false, Ordering::Relaxed);
+            network::set_breakdown_wanted(false);
+            let _ = USAGE_HWND.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,314 @@
+//! On-screen display for transient feedback
+//!
+//! A small rounded panel next to the bar with an icon, a line of text and
+//! optionally a level bar, e.g. "Volume 40%" after scrolling on the volume
+//! module or the new input language after switching layouts. It fades in, stays
+//! for `[osd] duration_ms` and fades out; a new message while it's up replaces
+//! the content and restarts the timer instead of stacking panels. It never
+//! takes focus and lets clicks through, and it follows the bar's theme.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::time::Instant;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::config::BarPosition;
+use crate::window::state::get_window_state;
+
+const OSD_CLASS: &str = "TopBarOsdClass";
+const FADE_TIMER: usize = 1;
+/// Fade step interval
+const FRAME_MS: u32 = 16;
+const FADE_IN_MS: u128 = 120;
+const FADE_OUT_MS: u128 = 250;
+/// Gap between the bar and the panel
+const GAP: i32 = 8;
+const LEVEL_WIDTH: i32 = 120;
+const LEVEL_HEIGHT: i32 = 4;
+
+/// The OSD window, if shown
+static OSD: AtomicIsize = AtomicIsize::new(0);
+/// What the panel shows
+static CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+
+struct Content {
+    text: String,
+    icon: String,
+    /// Level bar in percent, if any
+    level: Option<u32>,
+    shown: Instant,
+    duration_ms: u128,
+    /// Skip the fades (reduce motion)
+    instant: bool,
+    /// Full opacity (forced colors or reduce transparency)
+    opaque: bool,
+}
+
+/// Show `text` with an `icon` glyph (may be empty) near the bar
+pub fn show_osd(text: &str, icon: &str) {
+    show_content(text, icon, None);
+}
+
+/// Show `text` and `icon` above a level bar filled to `percent`
+pub fn show_osd_level(text: &str, icon: &str, percent: u32) {
+    show_content(text, icon, Some(percent.min(100)));
+}
+
+fn show_content(text: &str, icon: &str, level: Option<u32>) {
+    let Some((duration_ms, instant, opaque)) = get_window_state().and_then(|s| {
+        let s = s.read();
+        let cfg = &s.config.osd;
+        cfg.enabled.then(|| {
+            (
+                cfg.duration_ms.max(300) as u128,
+                crate::accessibility::reduce_motion(&s.config.appearance),
+                s.theme_manager.theme().forced_colors
+                    || crate::accessibility::reduce_transparency(&s.config.appearance),
+            )
+        })
+    }) else {
+        return;
+    };
+
+    // A panel that's already up keeps its opacity instead of fading in again
+    let visible = CONTENT.lock().as_ref().is_some_and(|c| alpha(c) > 0);
+    *CONTENT.lock() = Some(Content {
+        text: text.to_string(),
+        icon: icon.to_string(),
+        level,
+        shown: if visible && !instant {
+            Instant::now() - std::time::Duration::from_millis(FADE_IN_MS as u64)
+        } else {
+            Instant::now()
+        },
+        duration_ms,
+        instant,
+        opaque,
+    });
+    if let Err(e) = unsafe { show() } {
+        log::warn!("Failed to show OSD: {}", e);
+    }
+}
+
+unsafe fn show() -> Result<()> {
+    let mut hwnd = HWND(OSD.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(OSD_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_LAYERED | WS_EX_TRANSPARENT,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        OSD.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    position(hwnd);
+    apply_alpha(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    SetTimer(hwnd, FADE_TIMER, FRAME_MS, None);
+    Ok(())
+}
+
+/// Opacity for the current point in the panel's life; 0 once it's over
+fn alpha(content: &Content) -> u8 {
+    let full: u128 = if content.opaque { 255 } else { 235 };
+    let elapsed = content.shown.elapsed().as_millis();
+    if content.instant {
+        return if elapsed < content.duration_ms { full as u8 } else { 0 };
+    }
+    let fade_out_at = FADE_IN_MS + content.duration_ms;
+    let a = if elapsed < FADE_IN_MS {
+        full * elapsed / FADE_IN_MS
+    } else if elapsed < fade_out_at {
+        full
+    } else if elapsed < fade_out_at + FADE_OUT_MS {
+        full * (fade_out_at + FADE_OUT_MS - elapsed) / FADE_OUT_MS
+    } else {
+        0
+    };
+    a as u8
+}
+
+/// Apply the current opacity; false when the panel has faded out
+unsafe fn apply_alpha(hwnd: HWND) -> bool {
+    let a = CONTENT.lock().as_ref().map(alpha).unwrap_or(0);
+    let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), a.max(1), LWA_ALPHA);
+    a > 0
+}
+
+/// Size the panel to its content and put it next to the bar, centered along it
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let (text, icon, level) = match CONTENT.lock().as_ref() {
+        Some(c) => (c.text.clone(), c.icon.clone(), c.level.is_some()),
+        None => return,
+    };
+
+    let hdc = GetDC(hwnd);
+    let font = create_font(&font_family(), scale(16, dpi), true);
+    let old_font = SelectObject(hdc, font);
+    let (text_width, text_height) = measure_text(hdc, &text);
+    let (icon_width, _) = if icon.is_empty() { (0, 0) } else { measure_text(hdc, &icon) };
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
+    ReleaseDC(hwnd, hdc);
+
+    let icon_space = if icon_width > 0 { icon_width + scale(10, dpi) } else { 0 };
+    let mut width = icon_space + text_width;
+    let mut height = text_height;
+    if level {
+        width = width.max(scale(LEVEL_WIDTH, dpi));
+        height += scale(10 + LEVEL_HEIGHT, dpi);
+    }
+    width += scale(40, dpi);
+    height += scale(24, dpi);
+
+    let (bar, bar_position) = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            (s.bar_rect, s.config.appearance.position)
+        })
+        .unwrap_or((Default::default(), BarPosition::Top));
+    let gap = scale(GAP, dpi);
+    let center_x = bar.x + (bar.width - width) / 2;
+    let center_y = bar.y + (bar.height - height) / 2;
+    let (x, y) = match bar_position {
+        BarPosition::Top => (center_x, bar.y + bar.height + gap),
+        BarPosition::Bottom => (center_x, bar.y - height - gap),
+        BarPosition::Left => (bar.x + bar.width + gap, center_y),
+        BarPosition::Right => (bar.x - width - gap, center_y),
+    };
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(14, dpi), scale(14, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let (text, icon, level) = match CONTENT.lock().as_ref() {
+        Some(c) => (c.text.clone(), c.icon.clone(), c.level),
+        None => (String::new(), String::new(), None),
+    };
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    let font = create_font(&font_family(), scale(16, dpi), true);
+    let old_font = SelectObject(hdc, font);
+    SetBkMode(hdc, TRANSPARENT);
+    let (text_width, text_height) = measure_text(hdc, &text);
+    let (icon_width, _) = if icon.is_empty() { (0, 0) } else { measure_text(hdc, &icon) };
+    let icon_space = if icon_width > 0 { icon_width + scale(10, dpi) } else { 0 };
+
+    let bar_space = if level.is_some() { scale(10 + LEVEL_HEIGHT, dpi) } else { 0 };
+    let top = (rect.bottom - text_height - bar_space) / 2;
+    let left = (rect.right - icon_space - text_width) / 2;
+    if icon_width > 0 {
+        SetTextColor(hdc, theme.accent.colorref());
+        draw_text(hdc, left, top, &icon);
+    }
+    SetTextColor(hdc, theme.text_primary.colorref());
+    draw_text(hdc, left + icon_space, top, &text);
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
+
+    if let Some(percent) = level {
+        let width = rect.right - scale(40, dpi);
+        let track = RECT {
+            left: (rect.right - width) / 2,
+            top: top + text_height + scale(10, dpi),
+            right: (rect.right - width) / 2 + width,
+            bottom: top + text_height + scale(10 + LEVEL_HEIGHT, dpi),
+        };
+        let brush = CreateSolidBrush(theme.border.colorref());
+        FillRect(hdc, &track, brush);
+        let _ = DeleteObject(brush);
+        let fill = RECT {
+            right: track.left + width * percent as i32 / 100,
+            ..track
+        };
+        let brush = CreateSolidBrush(theme.accent.colorref());
+        FillRect(hdc, &fill, brush);
+        let _ = DeleteObject(brush);
+    }
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(OSD_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_TIMER if wparam.0 == FADE_TIMER => {
+            if !apply_alpha(hwnd) {
+                let _ = KillTimer(hwnd, FADE_TIMER);
+                let _ = DestroyWindow(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            OSD.store(0, Ordering::SeqCst);
+            *CONTENT.lock() = None;
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -1,20 +1,43 @@
 //! Quick search UI - Spotlight-style popup for file search
+//!
+//! Installed apps (Start Menu and store apps) are listed before indexed files. The
+//! list can also start with an inline answer (math, unit or currency conversion;
+//! Enter copies it), offer a dictionary definition when the query is a single
+//! word, and end with a web search row when nothing matches. Queries starting
+//! with "content:" search inside indexed text files instead.
+//!
+//! Right arrow (or right-click) on a file or app opens its action list: open, open
+//! containing folder, copy path, run as administrator, copy the file itself.
+//!
+//! After moving through the results with the arrow keys, Space previews the
+//! highlighted file in a QuickLook window below the popup; the preview follows the
+//! selection until Space or Esc closes it, or typing resumes.
 
 use anyhow::Result;
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM, RECT};
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::Win32::UI::Shell::{ShellExecuteW, SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_SMALLICON};
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Shell::{ILFree, SHGetFileInfoW, SHParseDisplayName, ShellExecuteW, SHFILEINFOW, SHGFI_ICON, SHGFI_PIDL, SHGFI_SMALLICON};
 use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
 use windows::Win32::Graphics::Gdi::*;
 
+use super::drawing::scale;
 use crate::window::state::get_window_state;
 use crate::theme::Color;
+use crate::app_index::{self, App, AppKind};
+use crate::calculator::{self, Answer};
+use crate::content_index::{self, ContentMatch};
+use crate::dictionary;
+use crate::quicklook;
+use crate::runtime::Task;
 use crate::search; 
+use crate::sounds::{self, SoundEvent};
 use std::path::Path;
 use std::collections::HashMap;
 
 const SEARCH_CLASS: &str = "TopBarQuickSearchClass";
+// Layout in pixels at 96 DPI and an interface size of 1; scaled when drawn
 const WIN_WIDTH: i32 = 620;
 const WIN_HEIGHT: i32 = 420;
 const ROW_HEIGHT: i32 = 56;
@@ -22,13 +45,84 @@ const RESULTS_START_Y: i32 = 72;
 const MAX_RESULTS: usize = 6;
 const INPUT_HEIGHT: i32 = 52;
 const PADDING: i32 = 16;
+const ACTION_WIDTH: i32 = 240;
+const ACTION_HEIGHT: i32 = 30;
+
+/// A row in the results list
+enum ResultRow {
+    /// Inline calculator or conversion answer
+    Answer(Answer),
+    /// Start Menu or store app
+    App(App),
+    /// Indexed file
+    File(String),
+    /// Text file whose contents matched a "content:" query
+    Content(ContentMatch),
+    /// Dictionary definition of the query
+    Define(String),
+    /// "Search the web for ..." with the URL to open
+    Web { query: String, url: String },
+}
+
+/// Something to do with a file or app result besides opening it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowAction {
+    Open,
+    Reveal,
+    CopyPath,
+    RunAsAdmin,
+    CopyFile,
+}
+
+impl RowAction {
+    const ALL: [RowAction; 5] = [Self::Open, Self::Reveal, Self::CopyPath, Self::RunAsAdmin, Self::CopyFile];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Open => "Open",
+            Self::Reveal => "Open Containing Folder",
+            Self::CopyPath => "Copy Full Path",
+            Self::RunAsAdmin => "Run as Administrator",
+            Self::CopyFile => "Copy File",
+        }
+    }
+}
+
+impl ResultRow {
+    /// The file behind the row, if there is one
+    fn path(&self) -> Option<&str> {
+        match self {
+            ResultRow::File(path) => Some(path),
+            ResultRow::Content(found) => Some(&found.path),
+            ResultRow::App(app) if app.kind == AppKind::Shortcut => Some(&app.target),
+            _ => None,
+        }
+    }
+
+    /// Actions offered in the row's action list (empty: no list)
+    fn actions(&self) -> &'static [RowAction] {
+        if self.path().is_some() {
+            &RowAction::ALL
+        } else {
+            &[]
+        }
+    }
+}
 
 struct SearchState {
     input: String,
-    results: Vec<String>,
+    results: Vec<ResultRow>,
     selected: usize,
     focused: bool,
     icon_cache: HashMap<String, HICON>,
+    /// Highlighted entry while the selected row's action list is open
+    action: Option<usize>,
+    /// The selection was last moved with the keyboard, so Space previews instead of typing
+    navigated: bool,
+    /// Content search running in the background; it reads files, too slow to wait for
+    content: Option<Task<Vec<ContentMatch>>>,
+    /// DPI of the monitor the popup is on
+    dpi: u32,
 }
 
 pub fn show_quick_search(parent: HWND) -> Result<()> {
@@ -51,10 +145,12 @@ pub fn show_quick_search(parent: HWND) -> Result<()> {
     };
 
     // Center near top of screen
+    let dpi = unsafe { GetDpiForWindow(hwnd).max(96) };
     unsafe {
         let screen_w = GetSystemMetrics(SM_CXSCREEN);
-        let x = (screen_w - WIN_WIDTH) / 2;
-        SetWindowPos(hwnd, HWND_TOPMOST, x, 80, WIN_WIDTH, WIN_HEIGHT, SWP_SHOWWINDOW).ok();
+        let (width, height) = (scale(WIN_WIDTH, dpi), scale(WIN_HEIGHT, dpi));
+        let x = (screen_w - width) / 2;
+        SetWindowPos(hwnd, HWND_TOPMOST, x, scale(80, dpi), width, height, SWP_SHOWWINDOW).ok();
         let _ = SetForegroundWindow(hwnd);
         let _ = SetFocus(hwnd);
 
@@ -69,11 +165,24 @@ pub fn show_quick_search(parent: HWND) -> Result<()> {
         selected: 0,
         focused: true,
         icon_cache: HashMap::new(),
+        action: None,
+        navigated: false,
+        content: None,
+        dpi,
     });
     unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize); }
 
+    let search_config = get_window_state()
+        .map(|s| s.read().config.search.clone())
+        .unwrap_or_default();
+    if search_config.index_apps {
+        app_index::refresh_if_stale();
+    }
+    content_index::refresh_if_stale(&search_config);
+
     // Timer for progress updates
     unsafe { SetTimer(hwnd, 1, 200, None); }
+    sounds::play_event(SoundEvent::PopupOpen);
 
     Ok(())
 }
@@ -153,6 +262,37 @@ unsafe fn get_file_icon(path: &str, cache: &mut HashMap<String, HICON>) -> Optio
     }
 }
 
+/// Get the icon for an app; store apps have no file, so it comes from their `shell:AppsFolder` item
+unsafe fn get_app_icon(app: &App, cache: &mut HashMap<String, HICON>) -> Option<HICON> {
+    if app.kind == AppKind::Shortcut {
+        return get_file_icon(&app.target, cache);
+    }
+    let key = app.launch_path();
+    if let Some(&icon) = cache.get(&key) {
+        return Some(icon);
+    }
+
+    let wide = to_wide(&key);
+    let mut pidl = std::ptr::null_mut();
+    SHParseDisplayName(PCWSTR(wide.as_ptr()), None, &mut pidl, 0, None).ok()?;
+    let mut shfi = SHFILEINFOW::default();
+    let result = SHGetFileInfoW(
+        PCWSTR(pidl as *const u16),
+        windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(0),
+        Some(&mut shfi),
+        std::mem::size_of::<SHFILEINFOW>() as u32,
+        SHGFI_PIDL | SHGFI_ICON | SHGFI_SMALLICON,
+    );
+    ILFree(Some(pidl));
+
+    if result != 0 && !shfi.hIcon.is_invalid() {
+        cache.insert(key, shfi.hIcon);
+        Some(shfi.hIcon)
+    } else {
+        None
+    }
+}
+
 /// Draw a rounded rectangle
 unsafe fn draw_rounded_rect(hdc: HDC, rect: &RECT, radius: i32, brush: HBRUSH) {
     let rgn = CreateRoundRectRgn(rect.left, rect.top, rect.right, rect.bottom, radius, radius);
@@ -169,6 +309,7 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
             if let Some(state) = get_state(hwnd) {
                 if let Some(gs) = get_window_state() {
                     let theme: crate::theme::Theme = gs.read().theme_manager.theme().clone();
+                    let dpi = state.dpi;
 
                     // Main background - dark glass effect
                     let bg = CreateSolidBrush(Color::rgb(22, 22, 24).colorref());
@@ -180,18 +321,13 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                     // ===== SEARCH INPUT AREA =====
                     // Input background (slightly lighter)
                     let input_bg = CreateSolidBrush(Color::rgb(38, 38, 42).colorref());
-                    let input_rect = RECT {
-                        left: PADDING,
-                        top: PADDING,
-                        right: WIN_WIDTH - PADDING,
-                        bottom: PADDING + INPUT_HEIGHT,
-                    };
-                    draw_rounded_rect(hdc, &input_rect, 10, input_bg);
+                    let input_rect = scaled(PADDING, PADDING, WIN_WIDTH - PADDING * 2, INPUT_HEIGHT, dpi);
+                    draw_rounded_rect(hdc, &input_rect, scale(10, dpi), input_bg);
                     let _ = DeleteObject(input_bg);
 
                     // Search icon (magnifying glass)
                     let icon_font = CreateFontW(
-                        20, 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
+                        scale(20, dpi), 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
                         DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
                         PCWSTR(to_wide("Segoe UI Symbol").as_ptr())
                     );
@@ -199,13 +335,13 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                     SetTextColor(hdc, Color::rgb(120, 120, 125).colorref());
                     let search_icon = "🔍";
                     let icon_wide: Vec<u16> = search_icon.encode_utf16().chain(std::iter::once(0)).collect();
-                    let _ = TextOutW(hdc, PADDING + 14, PADDING + 14, &icon_wide[..icon_wide.len() - 1]);
+                    let _ = TextOutW(hdc, scale(PADDING + 14, dpi), scale(PADDING + 14, dpi), &icon_wide[..icon_wide.len() - 1]);
                     let _ = SelectObject(hdc, old_font);
                     let _ = DeleteObject(icon_font);
 
                     // Input text
                     let input_font = CreateFontW(
-                        18, 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
+                        scale(18, dpi), 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
                         DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
                         PCWSTR(to_wide("Segoe UI").as_ptr())
                     );
@@ -229,17 +365,17 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                         state.input.clone()
                     };
                     let wide: Vec<u16> = display.encode_utf16().chain(std::iter::once(0)).collect();
-                    let text_x = PADDING + 48;
-                    let _ = TextOutW(hdc, text_x, PADDING + 16, &wide[..wide.len() - 1]);
+                    let text_x = scale(PADDING + 48, dpi);
+                    let _ = TextOutW(hdc, text_x, scale(PADDING + 16, dpi), &wide[..wide.len() - 1]);
 
                     // Cursor
                     if state.focused && !state.input.is_empty() {
                         let mut size = windows::Win32::Foundation::SIZE { cx: 0, cy: 0 };
                         let _ = GetTextExtentPoint32W(hdc, &wide[..wide.len() - 1], &mut size);
-                        let cursor_x = text_x + size.cx + 2;
+                        let cursor_x = text_x + size.cx + scale(2, dpi);
                         let cursor_brush = CreateSolidBrush(theme.accent.colorref());
                         let cursor_rect = RECT {
-                            left: cursor_x, top: PADDING + 14, right: cursor_x + 2, bottom: PADDING + 38
+                            left: cursor_x, top: scale(PADDING + 14, dpi), right: cursor_x + scale(2, dpi), bottom: scale(PADDING + 38, dpi)
                         };
                         FillRect(hdc, &cursor_rect, cursor_brush);
                         let _ = DeleteObject(cursor_brush);
@@ -250,12 +386,7 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
 
                     // ===== SEPARATOR LINE =====
                     let sep_brush = CreateSolidBrush(Color::rgb(50, 50, 55).colorref());
-                    let sep_rect = RECT {
-                        left: PADDING,
-                        top: PADDING + INPUT_HEIGHT + 8,
-                        right: WIN_WIDTH - PADDING,
-                        bottom: PADDING + INPUT_HEIGHT + 9,
-                    };
+                    let sep_rect = scaled(PADDING, PADDING + INPUT_HEIGHT + 8, WIN_WIDTH - PADDING * 2, 1, dpi);
                     FillRect(hdc, &sep_rect, sep_brush);
                     let _ = DeleteObject(sep_brush);
 
@@ -264,12 +395,12 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
 
                     // Fonts for results
                     let name_font = CreateFontW(
-                        16, 0, 0, 0, FW_SEMIBOLD.0 as i32, 0, 0, 0,
+                        scale(16, dpi), 0, 0, 0, FW_SEMIBOLD.0 as i32, 0, 0, 0,
                         DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
                         PCWSTR(to_wide("Segoe UI").as_ptr())
                     );
                     let path_font = CreateFontW(
-                        12, 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
+                        scale(12, dpi), 0, 0, 0, FW_NORMAL.0 as i32, 0, 0, 0,
                         DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
                         PCWSTR(to_wide("Segoe UI").as_ptr())
                     );
@@ -279,63 +410,101 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                         if search::is_index_ready() && state.input.is_empty() {
                             // Empty state with hint
                             SetTextColor(hdc, Color::rgb(100, 100, 105).colorref());
-                            let msg = "Type to search for apps, files, and more";
+                            let msg = crate::i18n::tr("Type to search for apps, files, and more");
                             let wide: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
-                            let _ = TextOutW(hdc, PADDING + 8, y + 16, &wide[..wide.len() - 1]);
+                            let _ = TextOutW(hdc, scale(PADDING + 8, dpi), scale(y + 16, dpi), &wide[..wide.len() - 1]);
                             
                             // Keyboard shortcut hint
                             let _ = SelectObject(hdc, path_font);
                             SetTextColor(hdc, Color::rgb(80, 80, 85).colorref());
-                            let hint = "Press Enter to open • Esc to close";
+                            let hint = crate::i18n::tr("Press Enter to open • Esc to close");
                             let hint_wide: Vec<u16> = hint.encode_utf16().chain(std::iter::once(0)).collect();
-                            let _ = TextOutW(hdc, PADDING + 8, y + 40, &hint_wide[..hint_wide.len() - 1]);
+                            let _ = TextOutW(hdc, scale(PADDING + 8, dpi), scale(y + 40, dpi), &hint_wide[..hint_wide.len() - 1]);
                         } else if !state.input.is_empty() {
                             // No results found
                             SetTextColor(hdc, Color::rgb(120, 120, 125).colorref());
-                            let msg = format!("No results for \"{}\"", state.input);
+                            let content_query = state.input.to_lowercase().starts_with(content_index::PREFIX);
+                            let content_enabled = get_window_state()
+                                .map(|s| !s.read().config.search.content_paths.is_empty())
+                                .unwrap_or(false);
+                            let msg = if content_query && !content_enabled {
+                                crate::i18n::tr("Add folders to search.content_paths to search file contents")
+                            } else if content_query && state.content.is_some() {
+                                crate::i18n::tr("Searching file contents...")
+                            } else if content_query && content_index::is_building() {
+                                crate::i18n::tr("Indexing file contents...")
+                            } else {
+                                crate::i18n::tr("No results for \"{}\"").replacen("{}", &state.input, 1)
+                            };
                             let wide: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
-                            let _ = TextOutW(hdc, PADDING + 8, y + 16, &wide[..wide.len() - 1]);
+                            let _ = TextOutW(hdc, scale(PADDING + 8, dpi), scale(y + 16, dpi), &wide[..wide.len() - 1]);
                         }
                     } else {
-                        for (i, path) in state.results.iter().enumerate().take(MAX_RESULTS) {
+                        for (i, row) in state.results.iter().enumerate().take(MAX_RESULTS) {
                             let is_selected = i == state.selected;
-                            let row_rect = RECT {
-                                left: PADDING - 4,
-                                top: y,
-                                right: WIN_WIDTH - PADDING + 4,
-                                bottom: y + ROW_HEIGHT - 4,
-                            };
+                            let row_rect = scaled(PADDING - 4, y, WIN_WIDTH - PADDING * 2 + 8, ROW_HEIGHT - 4, dpi);
+                            let icon_size = scale(24, dpi);
+                            let (icon_x, icon_y) = (scale(PADDING + 8, dpi), scale(y + 12, dpi));
 
                             // Selection background with rounded corners
                             if is_selected {
                                 let sel = CreateSolidBrush(theme.accent.colorref());
-                                draw_rounded_rect(hdc, &row_rect, 8, sel);
+                                draw_rounded_rect(hdc, &row_rect, scale(8, dpi), sel);
                                 let _ = DeleteObject(sel);
                             } else {
                                 // Subtle hover hint on alternate rows
                                 if i % 2 == 1 {
                                     let alt_bg = CreateSolidBrush(Color::rgb(26, 26, 28).colorref());
-                                    draw_rounded_rect(hdc, &row_rect, 8, alt_bg);
+                                    draw_rounded_rect(hdc, &row_rect, scale(8, dpi), alt_bg);
                                     let _ = DeleteObject(alt_bg);
                                 }
                             }
 
-                            // File icon - get actual system icon
-                            if let Some(state_mut) = get_state_mut(hwnd) {
-                                if let Some(icon) = get_file_icon(path, &mut state_mut.icon_cache) {
-                                    let _ = DrawIconEx(
-                                        hdc,
-                                        PADDING + 8,
-                                        y + 12,
-                                        icon,
-                                        24,  // width
-                                        24,  // height
-                                        0,
-                                        None,
-                                        DI_NORMAL,
-                                    );
+                            let (title, subtitle) = match row {
+                                ResultRow::App(app) => {
+                                    if let Some(state_mut) = get_state_mut(hwnd) {
+                                        if let Some(icon) = get_app_icon(app, &mut state_mut.icon_cache) {
+                                            let _ = DrawIconEx(hdc, icon_x, icon_y, icon, icon_size, icon_size, 0, None, DI_NORMAL);
+                                        }
+                                    }
+                                    let kind = match app.kind {
+                                        AppKind::Shortcut => "Application",
+                                        AppKind::Packaged => "Store app",
+                                    };
+                                    (app.name.clone(), kind.to_string())
                                 }
-                            }
+                                ResultRow::File(path) => {
+                                    // File icon - get actual system icon
+                                    if let Some(state_mut) = get_state_mut(hwnd) {
+                                        if let Some(icon) = get_file_icon(path, &mut state_mut.icon_cache) {
+                                            let _ = DrawIconEx(hdc, icon_x, icon_y, icon, icon_size, icon_size, 0, None, DI_NORMAL);
+                                        }
+                                    }
+                                    (get_filename(path).to_string(), get_parent_path(path))
+                                }
+                                ResultRow::Content(found) => {
+                                    if let Some(state_mut) = get_state_mut(hwnd) {
+                                        if let Some(icon) = get_file_icon(&found.path, &mut state_mut.icon_cache) {
+                                            let _ = DrawIconEx(hdc, icon_x, icon_y, icon, icon_size, icon_size, 0, None, DI_NORMAL);
+                                        }
+                                    }
+                                    (get_filename(&found.path).to_string(), format!("Line {}: {}", found.line, found.snippet))
+                                }
+                                ResultRow::Answer(answer) => {
+                                    draw_row_glyph(hdc, "=", y, dpi, if is_selected { Color::rgb(255, 255, 255) } else { theme.accent });
+                                    let hint = if answer.pending { "" } else { " • Enter to copy" };
+                                    (answer.text.clone(), format!("{}{}", answer.expression, hint))
+                                }
+                                ResultRow::Define(word) => {
+                                    draw_row_glyph(hdc, "📖", y, dpi, Color::rgb(160, 160, 165));
+                                    (format!("Define \"{}\"", word), "Dictionary".to_string())
+                                }
+                                ResultRow::Web { query, url } => {
+                                    draw_row_glyph(hdc, "🌐", y, dpi, Color::rgb(160, 160, 165));
+                                    let host = url.split("://").nth(1).and_then(|rest| rest.split('/').next()).unwrap_or(url);
+                                    (format!("Search the web for \"{}\"", query), host.to_string())
+                                }
+                            };
 
                             // Filename (bold)
                             let _ = SelectObject(hdc, name_font);
@@ -344,9 +513,8 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                             } else {
                                 Color::rgb(240, 240, 242).colorref()
                             });
-                            let filename = get_filename(path);
-                            let name_wide: Vec<u16> = filename.encode_utf16().chain(std::iter::once(0)).collect();
-                            let _ = TextOutW(hdc, PADDING + 48, y + 10, &name_wide[..name_wide.len() - 1]);
+                            let name_wide: Vec<u16> = title.encode_utf16().chain(std::iter::once(0)).collect();
+                            let _ = TextOutW(hdc, scale(PADDING + 48, dpi), scale(y + 10, dpi), &name_wide[..name_wide.len() - 1]);
 
                             // Path (smaller, muted)
                             let _ = SelectObject(hdc, path_font);
@@ -355,9 +523,8 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                             } else {
                                 Color::rgb(110, 110, 115).colorref()
                             });
-                            let parent = get_parent_path(path);
-                            let path_wide: Vec<u16> = parent.encode_utf16().chain(std::iter::once(0)).collect();
-                            let _ = TextOutW(hdc, PADDING + 48, y + 30, &path_wide[..path_wide.len() - 1]);
+                            let path_wide: Vec<u16> = subtitle.encode_utf16().chain(std::iter::once(0)).collect();
+                            let _ = TextOutW(hdc, scale(PADDING + 48, dpi), scale(y + 30, dpi), &path_wide[..path_wide.len() - 1]);
 
                             y += ROW_HEIGHT;
                         }
@@ -365,13 +532,22 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                         // Result count indicator
                         let _ = SelectObject(hdc, path_font);
                         SetTextColor(hdc, Color::rgb(80, 80, 85).colorref());
-                        let count_str = if state.results.len() > MAX_RESULTS {
+                        let mut count_str = if state.results.len() > MAX_RESULTS {
                             format!("Showing {} of {} results", MAX_RESULTS, state.results.len())
                         } else {
                             format!("{} result{}", state.results.len(), if state.results.len() == 1 { "" } else { "s" })
                         };
+                        let has_actions = state.results.get(state.selected).map(|r| !r.actions().is_empty()).unwrap_or(false);
+                        if has_actions {
+                            count_str.push_str("  •  → for actions");
+                            if state.navigated {
+                                count_str.push_str("  •  Space to preview");
+                            }
+                        }
                         let count_wide: Vec<u16> = count_str.encode_utf16().chain(std::iter::once(0)).collect();
-                        let _ = TextOutW(hdc, PADDING + 8, WIN_HEIGHT - 28, &count_wide[..count_wide.len() - 1]);
+                        let _ = TextOutW(hdc, scale(PADDING + 8, dpi), scale(WIN_HEIGHT - 28, dpi), &count_wide[..count_wide.len() - 1]);
+
+                        draw_action_list(hdc, state, &theme, path_font);
                     }
 
                     let _ = DeleteObject(name_font);
@@ -386,6 +562,13 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
         WM_CHAR => {
             if let Some(state) = get_state_mut(hwnd) {
                 let ch = (wparam.0 & 0xFF) as u8 as char;
+                let has_path = state.results.get(state.selected).and_then(|r| r.path()).is_some();
+                if ch == ' ' && has_path && (state.navigated || quicklook::is_preview_open()) {
+                    toggle_preview(hwnd, state);
+                    return LRESULT(0);
+                }
+                state.navigated = false;
+                quicklook::close_preview();
                 match ch {
                     '\u{8}' => { state.input.pop(); }
                     '\r' | '\n' => {}
@@ -403,14 +586,46 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
 
         WM_KEYDOWN => {
             let vk = wparam.0 as u32;
+            // The action list takes the arrows, Enter and Esc while it's open
+            if let Some(state) = get_state_mut(hwnd) {
+                if let Some(action) = state.action {
+                    let count = state.results.get(state.selected).map(|r| r.actions().len()).unwrap_or(0);
+                    match vk {
+                        0x1B | 0x25 => set_action_list(hwnd, state, None), // ESC, LEFT
+                        0x26 if count > 0 => { // UP
+                            set_action_list(hwnd, state, Some(if action == 0 { count - 1 } else { action - 1 }));
+                            sounds::play_event(SoundEvent::Focus);
+                        }
+                        0x28 if count > 0 => { // DOWN
+                            set_action_list(hwnd, state, Some((action + 1) % count));
+                            sounds::play_event(SoundEvent::Focus);
+                        }
+                        0x0D => run_action(hwnd, state.selected, action),
+                        _ => {}
+                    }
+                    return LRESULT(0);
+                }
+            }
             match vk {
+                0x1B if quicklook::is_preview_open() => quicklook::close_preview(),
                 0x1B => close_window(hwnd),
+                0x27 | 0x5D => { // RIGHT, context menu key
+                    if let Some(state) = get_state_mut(hwnd) {
+                        let has_actions = state.results.get(state.selected).map(|r| !r.actions().is_empty()).unwrap_or(false);
+                        if has_actions {
+                            set_action_list(hwnd, state, Some(0));
+                        }
+                    }
+                }
                 0x26 => { // UP
                     if let Some(state) = get_state_mut(hwnd) {
                         if !state.results.is_empty() {
                             let max = state.results.len().min(MAX_RESULTS);
                             let old = state.selected;
                             state.selected = if state.selected == 0 { max - 1 } else { state.selected - 1 };
+                            state.navigated = true;
+                            follow_selection(hwnd, state);
+                            sounds::play_event(SoundEvent::Focus);
                             // Only redraw the previously selected and newly selected rows to avoid flashing
                             invalidate_result_row(hwnd, old);
                             invalidate_result_row(hwnd, state.selected);
@@ -423,6 +638,9 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                             let max = state.results.len().min(MAX_RESULTS);
                             let old = state.selected;
                             state.selected = (state.selected + 1) % max;
+                            state.navigated = true;
+                            follow_selection(hwnd, state);
+                            sounds::play_event(SoundEvent::Focus);
                             invalidate_result_row(hwnd, old);
                             invalidate_result_row(hwnd, state.selected);
                         }
@@ -430,10 +648,8 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                 }
                 0x0D => { // ENTER
                     if let Some(state) = get_state(hwnd) {
-                        if let Some(path) = state.results.get(state.selected) {
-                            let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-                            ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
-                            close_window(hwnd);
+                        if let Some(row) = state.results.get(state.selected) {
+                            open_result(hwnd, row);
                         }
                     }
                 }
@@ -443,14 +659,38 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
         }
 
         WM_LBUTTONUP => {
+            let x = (lparam.0 & 0xFFFF) as i16 as i32;
             let y = (lparam.0 >> 16) as i16 as i32;
-            if y >= RESULTS_START_Y {
-                let idx = ((y - RESULTS_START_Y) / ROW_HEIGHT) as usize;
-                if let Some(state) = get_state(hwnd) {
-                    if let Some(path) = state.results.get(idx) {
-                        let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-                        ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
-                        close_window(hwnd);
+            if let Some(state) = get_state_mut(hwnd) {
+                if state.action.is_some() {
+                    // A click on an action runs it; anywhere else just closes the list
+                    let hit = action_list_rect(state).and_then(|rect| {
+                        let inside = x >= rect.left && x < rect.right && y >= rect.top && y < rect.bottom;
+                        inside.then(|| ((y - rect.top) / scale(ACTION_HEIGHT, state.dpi)) as usize)
+                    });
+                    match hit {
+                        Some(action) => run_action(hwnd, state.selected, action),
+                        None => set_action_list(hwnd, state, None),
+                    }
+                    return LRESULT(0);
+                }
+            }
+            if let Some(state) = get_state(hwnd) {
+                if let Some(row) = row_at(y, state.dpi).and_then(|idx| state.results.get(idx)) {
+                    open_result(hwnd, row);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_RBUTTONUP => {
+            let y = (lparam.0 >> 16) as i16 as i32;
+            if let Some(state) = get_state_mut(hwnd) {
+                if let Some(idx) = row_at(y, state.dpi) {
+                    let has_actions = state.results.get(idx).map(|r| !r.actions().is_empty()).unwrap_or(false);
+                    if has_actions {
+                        state.selected = idx;
+                        set_action_list(hwnd, state, Some(0));
                     }
                 }
             }
@@ -476,7 +716,9 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
         WM_TIMER => {
             let id = wparam.0 as u32;
             if id == 1 {
-                if !search::is_index_ready() {
+                let answer_changed = get_state_mut(hwnd).map(refresh_pending_answer).unwrap_or(false);
+                let content_found = get_state_mut(hwnd).map(collect_content).unwrap_or(false);
+                if !search::is_index_ready() || content_index::is_building() || answer_changed || content_found {
                     // Update without erasing background to avoid flicker
                     let _ = InvalidateRect(hwnd, None, false);
                 }
@@ -493,7 +735,7 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
 
         WM_DESTROY => {
             let _ = KillTimer(hwnd, 1);
-            free_state(hwnd);
+            let _ = free_state(hwnd);
             LRESULT(0)
         }
 
@@ -504,31 +746,308 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
 fn do_search(state: &mut SearchState) {
     state.results.clear();
     state.selected = 0;
+    state.action = None;
+    // A search for older input is dropped along with its task
+    state.content = None;
 
     if state.input.is_empty() {
         return;
     }
 
+    let config = get_window_state()
+        .map(|s| s.read().config.search.clone())
+        .unwrap_or_default();
+
+    // "content:words" searches inside text files and nothing else
+    let lower = state.input.to_lowercase();
+    if let Some(query) = lower.strip_prefix(content_index::PREFIX) {
+        let query = query.to_string();
+        state.content = Some(crate::runtime::fetch(move || content_index::search(&query, 200)));
+        return;
+    }
+
+    if config.calculator {
+        if let Some(answer) = calculator::evaluate(&state.input) {
+            state.results.push(ResultRow::Answer(answer));
+        }
+    }
+
+    // Apps first, so "code" opens VS Code before any file named code
+    if config.index_apps && !state.input.starts_with('.') {
+        let apps = app_index::search(&state.input, 20);
+        state.results.extend(apps.into_iter().map(ResultRow::App));
+    }
+
+    // A single word might be looked up rather than opened; offered before the
+    // files so it isn't pushed out of view
+    let define = get_window_state()
+        .map(|s| s.read().config.dictionary.in_search)
+        .unwrap_or(false);
+    if define && dictionary::is_word(&state.input) {
+        state.results.push(ResultRow::Define(state.input.trim().to_string()));
+    }
+
     if let Some(index) = search::global_index() {
         if let Some(ref idx) = *index.read() {
             // If input starts with '.', treat as extension search
-            if state.input.starts_with('.') {
-                state.results = idx.search_by_extension(&state.input, 200);
+            let files = if state.input.starts_with('.') {
+                idx.search_by_extension(&state.input, 200)
             } else {
-                // Use simpler contains-based search to find installed apps better
-                state.results = idx.search_query(&state.input, 200);
+                // Fuzzy search over names, ranked with app and recent-use boosts
+                idx.search_query(&state.input, 200)
+            };
+            // Start Menu shortcuts are already listed as apps
+            let files = files
+                .into_iter()
+                .filter(|path| !config.index_apps || !app_index::contains_shortcut(path));
+            state.results.extend(files.map(ResultRow::File));
+        }
+    }
+
+    // Nothing on disk matched: offer a web search instead
+    let has_files = state.results.iter().any(|r| matches!(r, ResultRow::File(_) | ResultRow::App(_)));
+    if !has_files && !config.web_search_url.is_empty() {
+        let query = state.input.trim().to_string();
+        let url = config.web_search_url.replace("{query}", &crate::utils::url_encode(&query));
+        state.results.push(ResultRow::Web { query, url });
+    }
+}
+
+/// Show the content search results once they're in; true if it finished
+fn collect_content(state: &mut SearchState) -> bool {
+    let Some(result) = state.content.as_ref().and_then(|task| task.poll()) else {
+        return false;
+    };
+    state.content = None;
+    match result {
+        Ok(found) => state.results.extend(found.into_iter().map(ResultRow::Content)),
+        Err(e) => log::warn!("Content search failed: {}", e),
+    }
+    true
+}
+
+/// Re-evaluate an answer that was waiting for data (exchange rates); true if it changed
+fn refresh_pending_answer(state: &mut SearchState) -> bool {
+    let Some(ResultRow::Answer(answer)) = state.results.first_mut() else {
+        return false;
+    };
+    if !answer.pending {
+        return false;
+    }
+    match calculator::evaluate(&state.input) {
+        Some(updated) if updated != *answer => {
+            *answer = updated;
+            true
+        }
+        Some(_) => false,
+        // Not a known currency after all
+        None => {
+            state.results.remove(0);
+            state.selected = 0;
+            true
+        }
+    }
+}
+
+/// Act on a result (start an app, open a file, copy an answer, search the web) and close the popup
+unsafe fn open_result(hwnd: HWND, row: &ResultRow) {
+    match row {
+        ResultRow::App(app) => {
+            let target = app.launch_path();
+            let wide = to_wide(&target);
+            ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
+            search::record_use(&target);
+        }
+        ResultRow::Content(found) => {
+            let wide = to_wide(&found.path);
+            ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
+            search::record_use(&found.path);
+        }
+        ResultRow::File(path) => {
+            let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+            ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
+            search::record_use(path);
+        }
+        ResultRow::Answer(answer) => {
+            if answer.pending {
+                return;
+            }
+            if let Err(e) = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(answer.value.clone())) {
+                log::warn!("Failed to copy answer: {}", e);
+            }
+        }
+        ResultRow::Define(word) => {
+            // The popup takes the focus, which closes this window and frees the row
+            let word = word.clone();
+            close_window(hwnd);
+            if let Err(e) = crate::render::show_definition(&word) {
+                log::warn!("Failed to show definition: {}", e);
+            }
+            return;
+        }
+        ResultRow::Web { url, .. } => crate::utils::open_url(url),
+    }
+    close_window(hwnd);
+}
+
+/// Where the selected row's action list is drawn, if it's open
+fn action_list_rect(state: &SearchState) -> Option<RECT> {
+    state.action?;
+    let count = state.results.get(state.selected)?.actions().len() as i32;
+    let height = count * ACTION_HEIGHT;
+    let row_top = RESULTS_START_Y + state.selected as i32 * ROW_HEIGHT;
+    // Below the row, or above it when that would run off the window
+    let mut top = row_top + ROW_HEIGHT - 8;
+    if top + height > WIN_HEIGHT - 8 {
+        top = row_top - height + 4;
+    }
+    let right = WIN_WIDTH - PADDING - 4;
+    Some(scaled(right - ACTION_WIDTH, top, ACTION_WIDTH, height, state.dpi))
+}
+
+/// A rectangle in layout pixels, scaled for the DPI and interface size
+fn scaled(left: i32, top: i32, width: i32, height: i32, dpi: u32) -> RECT {
+    RECT {
+        left: scale(left, dpi),
+        top: scale(top, dpi),
+        right: scale(left + width, dpi),
+        bottom: scale(top + height, dpi),
+    }
+}
+
+/// The result row under a window y coordinate
+fn row_at(y: i32, dpi: u32) -> Option<usize> {
+    (0..MAX_RESULTS).find(|&idx| {
+        let top = RESULTS_START_Y + idx as i32 * ROW_HEIGHT;
+        y >= scale(top, dpi) && y < scale(top + ROW_HEIGHT, dpi)
+    })
+}
+
+/// Open, move within or close the action list, redrawing what changed
+fn set_action_list(hwnd: HWND, state: &mut SearchState, action: Option<usize>) {
+    let opening = state.action.is_none() && action.is_some();
+    if state.action.is_some() && action.is_none() {
+        // The list overlaps other rows; redraw everything under it
+        unsafe { let _ = InvalidateRect(hwnd, None, false); }
+    }
+    state.action = action;
+    if opening {
+        sounds::play_event(SoundEvent::PopupOpen);
+    }
+    if let Some(rect) = action_list_rect(state) {
+        unsafe { let _ = InvalidateRect(hwnd, Some(&rect), false); }
+    }
+}
+
+/// Draw the action list over the results
+unsafe fn draw_action_list(hdc: HDC, state: &SearchState, theme: &crate::theme::Theme, font: HFONT) {
+    let (Some(rect), Some(selected)) = (action_list_rect(state), state.action) else {
+        return;
+    };
+    let Some(row) = state.results.get(state.selected) else {
+        return;
+    };
+
+    let dpi = state.dpi;
+    let bg = CreateSolidBrush(Color::rgb(36, 36, 40).colorref());
+    draw_rounded_rect(hdc, &rect, scale(8, dpi), bg);
+    let _ = DeleteObject(bg);
+    let border = CreateSolidBrush(Color::rgb(60, 60, 66).colorref());
+    let _ = FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    let old_font = SelectObject(hdc, font);
+    SetBkMode(hdc, TRANSPARENT);
+    for (i, action) in row.actions().iter().enumerate() {
+        let top = rect.top + i as i32 * scale(ACTION_HEIGHT, dpi);
+        if i == selected {
+            let item = RECT {
+                left: rect.left + scale(3, dpi),
+                top: top + scale(2, dpi),
+                right: rect.right - scale(3, dpi),
+                bottom: top + scale(ACTION_HEIGHT - 2, dpi),
+            };
+            let sel = CreateSolidBrush(theme.accent.colorref());
+            draw_rounded_rect(hdc, &item, scale(6, dpi), sel);
+            let _ = DeleteObject(sel);
+        }
+        SetTextColor(hdc, if i == selected {
+            Color::rgb(255, 255, 255).colorref()
+        } else {
+            Color::rgb(220, 220, 225).colorref()
+        });
+        let wide: Vec<u16> = action.label().encode_utf16().collect();
+        let _ = TextOutW(hdc, rect.left + scale(14, dpi), top + scale(7, dpi), &wide);
+    }
+    let _ = SelectObject(hdc, old_font);
+}
+
+/// Run an entry of a row's action list and close the popup
+unsafe fn run_action(hwnd: HWND, row_index: usize, action_index: usize) {
+    let Some(state) = get_state(hwnd) else {
+        return;
+    };
+    let Some(row) = state.results.get(row_index) else {
+        return;
+    };
+    let Some(&action) = row.actions().get(action_index) else {
+        return;
+    };
+    if action == RowAction::Open {
+        open_result(hwnd, row);
+        return;
+    }
+    let Some(path) = row.path().map(str::to_string) else {
+        return;
+    };
+
+    match action {
+        RowAction::Reveal => {
+            let _ = std::process::Command::new("explorer.exe")
+                .arg(format!("/select,{}", path))
+                .spawn();
+        }
+        RowAction::CopyPath => {
+            if let Err(e) = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(path.clone())) {
+                log::warn!("Failed to copy path: {}", e);
             }
         }
+        RowAction::RunAsAdmin => {
+            let wide = to_wide(&path);
+            ShellExecuteW(None, windows::core::w!("runas"), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
+            search::record_use(&path);
+        }
+        RowAction::CopyFile => {
+            if let Err(e) = crate::utils::copy_files_to_clipboard(std::slice::from_ref(&path)) {
+                log::warn!("Failed to copy file: {}", e);
+            }
+        }
+        RowAction::Open => {}
     }
+    close_window(hwnd);
+}
+
+/// Draw a text glyph in the icon slot of a row
+unsafe fn draw_row_glyph(hdc: HDC, glyph: &str, y: i32, dpi: u32, color: Color) {
+    let font = CreateFontW(
+        scale(22, dpi), 0, 0, 0, FW_SEMIBOLD.0 as i32, 0, 0, 0,
+        DEFAULT_CHARSET.0 as u32, 0, 0, CLEARTYPE_QUALITY.0 as u32, 0,
+        PCWSTR(to_wide("Segoe UI Symbol").as_ptr())
+    );
+    let old_font = SelectObject(hdc, font);
+    SetTextColor(hdc, color.colorref());
+    let wide: Vec<u16> = glyph.encode_utf16().collect();
+    let _ = TextOutW(hdc, scale(PADDING + 12, dpi), scale(y + 12, dpi), &wide);
+    let _ = SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
 }
 
 fn invalidate_result_row(hwnd: HWND, idx: usize) {
     unsafe {
         if idx >= MAX_RESULTS { return; }
+        let Some(state) = get_state(hwnd) else { return };
         let top = RESULTS_START_Y + (idx as i32) * ROW_HEIGHT;
-        let rect = RECT {
-            left: PADDING - 4, top, right: WIN_WIDTH - PADDING + 4, bottom: top + ROW_HEIGHT
-        };
+        let rect = scaled(PADDING - 4, top, WIN_WIDTH - PADDING * 2 + 8, ROW_HEIGHT, state.dpi);
         let _ = InvalidateRect(hwnd, Some(&rect), false);
     }
 }
@@ -547,23 +1066,57 @@ fn get_state_mut(hwnd: HWND) -> Option<&'static mut SearchState> {
     }
 }
 
-fn free_state(hwnd: HWND) {
+/// Free the popup state; returns false if it was already freed
+fn free_state(hwnd: HWND) -> bool {
     unsafe {
         let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut SearchState;
-        if !ptr.is_null() {
-            let state = Box::from_raw(ptr);
-            // Clean up cached icons
-            for (_, icon) in state.icon_cache.iter() {
-                let _ = DestroyIcon(*icon);
-            }
-            SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        if ptr.is_null() {
+            return false;
         }
+        let state = Box::from_raw(ptr);
+        // Clean up cached icons
+        for (_, icon) in state.icon_cache.iter() {
+            let _ = DestroyIcon(*icon);
+        }
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        true
+    }
+}
+
+/// Open the QuickLook preview for the selected row, or close it if it's open
+fn toggle_preview(hwnd: HWND, state: &SearchState) {
+    if quicklook::is_preview_open() {
+        quicklook::close_preview();
+        return;
+    }
+    if let Some(path) = state.results.get(state.selected).and_then(|r| r.path()) {
+        if let Err(e) = quicklook::preview_beside(Path::new(path), hwnd) {
+            log::warn!("Failed to preview {}: {}", path, e);
+        }
+    }
+}
+
+/// Keep an open preview on the selected row
+fn follow_selection(hwnd: HWND, state: &SearchState) {
+    if !quicklook::is_preview_open() {
+        return;
+    }
+    match state.results.get(state.selected).and_then(|r| r.path()) {
+        Some(path) => {
+            let _ = quicklook::preview_beside(Path::new(path), hwnd);
+        }
+        None => quicklook::close_preview(),
     }
 }
 
 fn close_window(hwnd: HWND) {
+    // Destroying the window re-enters here through WM_KILLFOCUS; only the first call counts
+    if !free_state(hwnd) {
+        return;
+    }
+    quicklook::close_preview();
     unsafe {
-        free_state(hwnd);
         let _ = DestroyWindow(hwnd);
     }
+    sounds::play_event(SoundEvent::PopupClose);
 }
@@ -0,0 +1,908 @@
+//! Quick toggles panel
+//!
+//! What the app menu opens: a grid of tiles for Wi-Fi, Bluetooth, Night Light
+//! and Do Not Disturb with brightness and volume sliders under them, like the
+//! quick settings in Windows. A click switches a tile, a right-click opens its
+//! page in Settings and **More...** swaps the panel for the classic app menu.
+//!
+//! Radios and brightness are read in the background when the panel opens, and
+//! switching a radio shows the new state straight away while Windows catches
+//! up, since both wait on WinRT or DDC/CI. The panel never takes focus; a click
+//! anywhere else closes it.
+
+use anyhow::Result;
+use log::warn;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, ReleaseCapture, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT, VK_LBUTTON, VK_MBUTTON,
+    VK_RBUTTON,
+};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use super::icons::fluent;
+use crate::config::BarPosition;
+use crate::modules::network::NetworkModule;
+use crate::modules::night_light::{NightLightModule, NightLightState};
+use crate::modules::volume::VolumeModule;
+use crate::radios::{self, RadioKind};
+use crate::sounds::{self, SoundEvent};
+use crate::window::renderer::with_renderer;
+use crate::window::state::get_window_state;
+
+const PANEL_CLASS: &str = "TopBarQuickTogglesClass";
+const REFRESH_TIMER: usize = 1;
+const REFRESH_MS: u32 = 500;
+/// Watches for a click outside the panel
+const DISMISS_TIMER: usize = 2;
+const DISMISS_MS: u32 = 50;
+
+/// Sizes before DPI scaling
+const WIDTH: i32 = 300;
+const PADDING: i32 = 12;
+const GAP: i32 = 8;
+const TILE_HEIGHT: i32 = 60;
+const SLIDER_HEIGHT: i32 = 36;
+/// Room for the glyph left of a slider and the percentage right of it
+const SLIDER_ICON: i32 = 28;
+const SLIDER_VALUE: i32 = 44;
+const FOOTER_HEIGHT: i32 = 36;
+const SLIDERS_TOP: i32 = PADDING + TILE_HEIGHT * 2 + GAP * 2;
+const FOOTER_TOP: i32 = SLIDERS_TOP + SLIDER_HEIGHT * 2 + GAP;
+const HEIGHT: i32 = FOOTER_TOP + FOOTER_HEIGHT;
+
+/// The panel window, if shown
+static PANEL_HWND: AtomicIsize = AtomicIsize::new(0);
+/// The bar and the point the panel was opened at, for **More...**
+static ANCHOR: Mutex<(isize, i32, i32)> = Mutex::new((0, 0, 0));
+/// Radio and brightness state, filled in by a background read
+static READINGS: Mutex<Readings> = Mutex::new(Readings {
+    wifi: None,
+    bluetooth: None,
+    brightness: None,
+    loaded: false,
+});
+/// The part under the mouse
+static HOVER: Mutex<Option<Part>> = Mutex::new(None);
+/// The slider being dragged
+static DRAG: Mutex<Option<Level>> = Mutex::new(None);
+
+/// WM_MOUSELEAVE message constant
+const WM_MOUSELEAVE: u32 = 0x02A3;
+
+#[derive(Clone, Copy)]
+struct Readings {
+    wifi: Option<bool>,
+    bluetooth: Option<bool>,
+    brightness: Option<u32>,
+    /// Whether the read has finished, so None means there's nothing to switch
+    loaded: bool,
+}
+
+impl Readings {
+    fn radio_mut(&mut self, kind: RadioKind) -> &mut Option<bool> {
+        if kind == RadioKind::WiFi {
+            &mut self.wifi
+        } else {
+            &mut self.bluetooth
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Toggle {
+    WiFi,
+    Bluetooth,
+    NightLight,
+    DoNotDisturb,
+}
+
+impl Toggle {
+    const ALL: [Toggle; 4] = [
+        Toggle::WiFi,
+        Toggle::Bluetooth,
+        Toggle::NightLight,
+        Toggle::DoNotDisturb,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Toggle::WiFi => "Wi-Fi",
+            Toggle::Bluetooth => "Bluetooth",
+            Toggle::NightLight => "Night Light",
+            Toggle::DoNotDisturb => "Do Not Disturb",
+        }
+    }
+
+    fn glyph(self) -> char {
+        match self {
+            Toggle::WiFi => fluent::WIFI,
+            Toggle::Bluetooth => fluent::BLUETOOTH,
+            Toggle::NightLight => fluent::NIGHT_LIGHT,
+            Toggle::DoNotDisturb => fluent::DO_NOT_DISTURB,
+        }
+    }
+
+    fn settings_page(self) -> &'static str {
+        match self {
+            Toggle::WiFi => "ms-settings:network-wifi",
+            Toggle::Bluetooth => "ms-settings:bluetooth",
+            Toggle::NightLight => "ms-settings:nightlight",
+            Toggle::DoNotDisturb => "ms-settings:quiethours",
+        }
+    }
+
+    fn radio(self) -> Option<RadioKind> {
+        match self {
+            Toggle::WiFi => Some(RadioKind::WiFi),
+            Toggle::Bluetooth => Some(RadioKind::Bluetooth),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Level {
+    Brightness,
+    Volume,
+}
+
+impl Level {
+    const ALL: [Level; 2] = [Level::Brightness, Level::Volume];
+
+    fn settings_page(self) -> &'static str {
+        match self {
+            Level::Brightness => "ms-settings:display",
+            Level::Volume => "ms-settings:sound",
+        }
+    }
+}
+
+/// A clickable part of the panel
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Part {
+    Tile(Toggle),
+    /// The glyph left of a slider; mutes for volume
+    LevelIcon(Level),
+    Slider(Level),
+    More,
+}
+
+/// Show the panel beside the bar at `x`, `y` (screen coordinates), or close it
+/// if it's already open
+pub fn toggle_quick_toggles(bar: HWND, x: i32, y: i32) {
+    if !hide_quick_toggles() {
+        if let Err(e) = unsafe { show(bar, x, y) } {
+            warn!("Failed to show quick toggles: {}", e);
+        }
+    }
+}
+
+/// Close the panel; returns whether it was open
+pub fn hide_quick_toggles() -> bool {
+    let hwnd = HWND(PANEL_HWND.swap(0, Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() {
+        return false;
+    }
+    unsafe {
+        let _ = DestroyWindow(hwnd);
+    }
+    sounds::play_event(SoundEvent::PopupClose);
+    true
+}
+
+unsafe fn show(bar: HWND, x: i32, y: i32) -> Result<()> {
+    register_class()?;
+    let class = to_wide(PANEL_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let hwnd = CreateWindowExW(
+        WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+        PCWSTR(class.as_ptr()),
+        PCWSTR::null(),
+        WS_POPUP,
+        0, 0, 0, 0,
+        None,
+        None,
+        hinstance,
+        None,
+    )?;
+    PANEL_HWND.store(hwnd.0 as isize, Ordering::SeqCst);
+    *ANCHOR.lock() = (bar.0 as isize, x, y);
+    load_readings();
+
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let width = scale(WIDTH, dpi);
+    let height = scale(HEIGHT, dpi);
+
+    let mut bar_rect = RECT::default();
+    let _ = GetWindowRect(bar, &mut bar_rect);
+    let monitor = MonitorFromWindow(bar, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcMonitor;
+    let margin = scale(8, dpi);
+    let gap = scale(6, dpi);
+    let center_x = (x - width / 2).clamp(area.left + margin, area.right - margin - width);
+    let center_y = (y - height / 2).clamp(area.top + margin, area.bottom - margin - height);
+    // Open away from the screen edge the bar is on
+    let edge = get_window_state()
+        .map(|s| s.read().config.appearance.position)
+        .unwrap_or(BarPosition::Top);
+    let (left, top) = match edge {
+        BarPosition::Top => (center_x, bar_rect.bottom + gap),
+        BarPosition::Bottom => (center_x, bar_rect.top - gap - height),
+        BarPosition::Left => (bar_rect.right + gap, center_y),
+        BarPosition::Right => (bar_rect.left - gap - width, center_y),
+    };
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, left, top, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(12, dpi), scale(12, dpi));
+    SetWindowRgn(hwnd, region, true);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    SetTimer(hwnd, REFRESH_TIMER, REFRESH_MS, None);
+    SetTimer(hwnd, DISMISS_TIMER, DISMISS_MS, None);
+    sounds::play_event(SoundEvent::PopupOpen);
+    Ok(())
+}
+
+/// Read the radios and brightness in the background; the refresh timer draws them
+fn load_readings() {
+    READINGS.lock().loaded = false;
+    crate::runtime::spawn(|| {
+        let readings = Readings {
+            wifi: radios::is_on(RadioKind::WiFi),
+            bluetooth: radios::is_on(RadioKind::Bluetooth),
+            brightness: crate::brightness::get(),
+            loaded: true,
+        };
+        *READINGS.lock() = readings;
+    });
+}
+
+fn bar() -> HWND {
+    HWND(ANCHOR.lock().0 as *mut std::ffi::c_void)
+}
+
+/// Whether a toggle is on (None when it can't be switched) and the line under its label
+fn tile_state(toggle: Toggle, readings: &Readings) -> (Option<bool>, String) {
+    let on = match toggle {
+        Toggle::WiFi => readings.wifi,
+        Toggle::Bluetooth => readings.bluetooth,
+        Toggle::NightLight => with_renderer(|renderer| {
+            let module = renderer
+                .module_registry
+                .get("night_light")?
+                .as_any()
+                .downcast_ref::<NightLightModule>()?;
+            match module.state() {
+                NightLightState::On => Some(true),
+                NightLightState::Off => Some(false),
+                NightLightState::Unknown => None,
+            }
+        })
+        .flatten(),
+        Toggle::DoNotDisturb => {
+            get_window_state().map(|s| crate::attention::is_do_not_disturb(&s.read().config.attention))
+        }
+    };
+    let status = match on {
+        Some(true) if toggle == Toggle::WiFi => with_renderer(|renderer| {
+            let module = renderer
+                .module_registry
+                .get("network")?
+                .as_any()
+                .downcast_ref::<NetworkModule>()?;
+            module.network_name().map(str::to_string)
+        })
+        .flatten()
+        .unwrap_or_else(|| crate::i18n::tr("On")),
+        Some(true) => crate::i18n::tr("On"),
+        Some(false) => crate::i18n::tr("Off"),
+        None if toggle.radio().is_some() && !readings.loaded => "...".to_string(),
+        None => crate::i18n::tr("Unavailable"),
+    };
+    (on, status)
+}
+
+/// A slider's value in percent, and whether volume is muted
+fn level_value(level: Level, readings: &Readings) -> Option<(u32, bool)> {
+    match level {
+        Level::Brightness => readings.brightness.map(|value| (value, false)),
+        Level::Volume => with_renderer(|renderer| {
+            let module = renderer
+                .module_registry
+                .get("volume")?
+                .as_any()
+                .downcast_ref::<VolumeModule>()?;
+            Some((module.volume_level(), module.is_muted()))
+        })
+        .flatten(),
+    }
+}
+
+fn scaled(left: i32, top: i32, width: i32, height: i32, dpi: u32) -> RECT {
+    RECT {
+        left: scale(left, dpi),
+        top: scale(top, dpi),
+        right: scale(left + width, dpi),
+        bottom: scale(top + height, dpi),
+    }
+}
+
+fn tile_rect(index: usize, dpi: u32) -> RECT {
+    let width = (WIDTH - PADDING * 2 - GAP) / 2;
+    let left = PADDING + (index % 2) as i32 * (width + GAP);
+    let top = PADDING + (index / 2) as i32 * (TILE_HEIGHT + GAP);
+    scaled(left, top, width, TILE_HEIGHT, dpi)
+}
+
+fn level_top(level: Level) -> i32 {
+    SLIDERS_TOP + Level::ALL.iter().position(|l| *l == level).unwrap_or(0) as i32 * SLIDER_HEIGHT
+}
+
+fn level_icon_rect(level: Level, dpi: u32) -> RECT {
+    scaled(PADDING, level_top(level), SLIDER_ICON, SLIDER_HEIGHT, dpi)
+}
+
+fn track_rect(level: Level, dpi: u32) -> RECT {
+    let left = PADDING + SLIDER_ICON + 4;
+    scaled(
+        left,
+        level_top(level),
+        WIDTH - PADDING - SLIDER_VALUE - left,
+        SLIDER_HEIGHT,
+        dpi,
+    )
+}
+
+fn more_rect(dpi: u32) -> RECT {
+    scaled(PADDING, FOOTER_TOP + 4, WIDTH - PADDING * 2, FOOTER_HEIGHT - 8, dpi)
+}
+
+fn hit_test(point: POINT, dpi: u32) -> Option<Part> {
+    let inside = |rect: RECT| unsafe { PtInRect(&rect, point).as_bool() };
+    if let Some(index) = (0..Toggle::ALL.len()).find(|&i| inside(tile_rect(i, dpi))) {
+        return Some(Part::Tile(Toggle::ALL[index]));
+    }
+    for level in Level::ALL {
+        if inside(level_icon_rect(level, dpi)) {
+            return Some(Part::LevelIcon(level));
+        }
+        if inside(track_rect(level, dpi)) {
+            return Some(Part::Slider(level));
+        }
+    }
+    inside(more_rect(dpi)).then_some(Part::More)
+}
+
+/// The percentage at `x` along a slider's track
+fn value_at(level: Level, x: i32, dpi: u32) -> u32 {
+    let track = track_rect(level, dpi);
+    let width = (track.right - track.left).max(1);
+    ((x - track.left) * 100 / width).clamp(0, 100) as u32
+}
+
+/// Move a slider to `value`; brightness is only sent to the displays once the
+/// drag ends, since each change is a slow round trip
+fn apply_level(level: Level, value: u32, done: bool) {
+    match level {
+        Level::Brightness => {
+            READINGS.lock().brightness = Some(value);
+            if done {
+                crate::runtime::spawn(move || crate::brightness::set(value));
+            }
+        }
+        Level::Volume => {
+            with_renderer(|renderer| {
+                if let Some(module) = renderer
+                    .module_registry
+                    .get_mut("volume")
+                    .and_then(|m| m.as_any_mut().downcast_mut::<VolumeModule>())
+                {
+                    let delta = value as i32 - module.volume_level() as i32;
+                    if delta != 0 {
+                        module.change_volume(delta);
+                    }
+                }
+            });
+        }
+    }
+}
+
+fn switch(toggle: Toggle) {
+    let bar = bar();
+    match toggle {
+        Toggle::WiFi | Toggle::Bluetooth => {
+            let Some(kind) = toggle.radio() else { return };
+            let Some(on) = *READINGS.lock().radio_mut(kind) else {
+                open_settings(toggle.settings_page());
+                return;
+            };
+            // Shown switched right away; put back if Windows refuses
+            *READINGS.lock().radio_mut(kind) = Some(!on);
+            crate::runtime::spawn(move || {
+                if let Err(e) = radios::set(kind, !on) {
+                    warn!("Failed to switch {:?} radio: {}", kind, e);
+                    *READINGS.lock().radio_mut(kind) = radios::is_on(kind);
+                    crate::utils::open_url(toggle.settings_page());
+                }
+            });
+        }
+        Toggle::NightLight => {
+            let toggled = with_renderer(|renderer| {
+                renderer
+                    .module_registry
+                    .get_mut("night_light")
+                    .map(|module| module.on_click())
+                    .is_some()
+            })
+            .unwrap_or(false);
+            if !toggled {
+                open_settings(toggle.settings_page());
+                return;
+            }
+            if let Some(state) = get_window_state() {
+                state.write().needs_redraw = true;
+            }
+            unsafe {
+                let _ = InvalidateRect(bar, None, false);
+            }
+        }
+        Toggle::DoNotDisturb => {
+            crate::window::config_handlers::toggle_config_bool(bar, |c| &mut c.attention.do_not_disturb);
+        }
+    }
+}
+
+fn open_settings(page: &str) {
+    hide_quick_toggles();
+    crate::utils::open_url(page);
+}
+
+fn click(part: Part) {
+    match part {
+        Part::Tile(toggle) => switch(toggle),
+        Part::LevelIcon(Level::Volume) => {
+            with_renderer(|renderer| {
+                if let Some(module) = renderer
+                    .module_registry
+                    .get_mut("volume")
+                    .and_then(|m| m.as_any_mut().downcast_mut::<VolumeModule>())
+                {
+                    module.toggle_mute();
+                }
+            });
+        }
+        Part::LevelIcon(level) => open_settings(level.settings_page()),
+        Part::Slider(_) => {}
+        Part::More => {
+            let (bar, x, y) = *ANCHOR.lock();
+            hide_quick_toggles();
+            crate::window::module_handlers::show_app_menu(HWND(bar as *mut std::ffi::c_void), x, y);
+        }
+    }
+}
+
+fn right_click(part: Part) {
+    match part {
+        Part::Tile(toggle) => open_settings(toggle.settings_page()),
+        Part::LevelIcon(level) | Part::Slider(level) => open_settings(level.settings_page()),
+        Part::More => {}
+    }
+}
+
+/// Whether a mouse button just went down somewhere other than the panel or the
+/// app menu module (which closes the panel itself)
+fn clicked_outside(hwnd: HWND) -> bool {
+    unsafe {
+        let pressed = [VK_LBUTTON, VK_RBUTTON, VK_MBUTTON]
+            .iter()
+            .any(|key| GetAsyncKeyState(key.0 as i32) as u16 & 0x8000 != 0);
+        if !pressed || DRAG.lock().is_some() {
+            return false;
+        }
+        let mut cursor = POINT::default();
+        let _ = GetCursorPos(&mut cursor);
+        let mut rect = RECT::default();
+        let _ = GetWindowRect(hwnd, &mut rect);
+        if PtInRect(&rect, cursor).as_bool() {
+            return false;
+        }
+        let mut bar_rect = RECT::default();
+        let _ = GetWindowRect(bar(), &mut bar_rect);
+        let over_module = with_renderer(|renderer| {
+            renderer
+                .module_bounds()
+                .get("app_menu")
+                .is_some_and(|bounds| bounds.contains(cursor.x - bar_rect.left, cursor.y - bar_rect.top))
+        })
+        .unwrap_or(false);
+        !over_module
+    }
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn fill_round(hdc: HDC, rect: &RECT, radius: i32, color: COLORREF) {
+    let brush = CreateSolidBrush(color);
+    let region = CreateRoundRectRgn(rect.left, rect.top, rect.right + 1, rect.bottom + 1, radius, radius);
+    let _ = FillRgn(hdc, region, brush);
+    let _ = DeleteObject(region);
+    let _ = DeleteObject(brush);
+}
+
+/// Draw `text` centered vertically in `rect`, starting at its left edge
+unsafe fn draw_middle(hdc: HDC, rect: &RECT, text: &str) {
+    let (_, h) = measure_text(hdc, text);
+    draw_text(hdc, rect.left, rect.top + (rect.bottom - rect.top - h) / 2, text);
+}
+
+unsafe fn draw_centered(hdc: HDC, rect: &RECT, text: &str) {
+    let (w, h) = measure_text(hdc, text);
+    draw_text(
+        hdc,
+        rect.left + (rect.right - rect.left - w) / 2,
+        rect.top + (rect.bottom - rect.top - h) / 2,
+        text,
+    );
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+    let readings = *READINGS.lock();
+    let hover = *HOVER.lock();
+
+    let mut client = RECT::default();
+    let _ = GetClientRect(hwnd, &mut client);
+
+    // Draw off-screen so the periodic redraw doesn't flicker
+    let mem_dc = CreateCompatibleDC(hdc);
+    let buffer = CreateCompatibleBitmap(hdc, client.right, client.bottom);
+    let old_buffer = SelectObject(mem_dc, buffer);
+
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(mem_dc, &client, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(mem_dc, &client, border);
+    let _ = DeleteObject(border);
+    SetBkMode(mem_dc, TRANSPARENT);
+
+    let family = font_family();
+    let label_font = create_font(&family, scale(12, dpi), true);
+    let text_font = create_font(&family, scale(11, dpi), false);
+    let icon_font = create_font("Segoe Fluent Icons", scale(16, dpi), false);
+    let old_font = SelectObject(mem_dc, text_font);
+    let radius = scale(8, dpi);
+    let inset = scale(10, dpi);
+    // Text on an accent-filled tile
+    let on_accent = COLORREF(0x00FFFFFF);
+
+    // Tiles: glyph on top, label and status under it
+    for (index, toggle) in Toggle::ALL.iter().enumerate() {
+        let rect = tile_rect(index, dpi);
+        let (on, status) = tile_state(*toggle, &readings);
+        let hovered = hover == Some(Part::Tile(*toggle));
+        let fill = match (on, hovered) {
+            (Some(true), true) => theme.accent_hover,
+            (Some(true), false) => theme.accent,
+            (_, true) => theme.background_hover,
+            (_, false) => theme.background_secondary,
+        };
+        fill_round(mem_dc, &rect, radius, fill.colorref());
+        let (primary, secondary) = match on {
+            Some(true) => (on_accent, on_accent),
+            Some(false) => (theme.text_primary.colorref(), theme.text_secondary.colorref()),
+            None => (theme.text_disabled.colorref(), theme.text_disabled.colorref()),
+        };
+
+        let third = (rect.bottom - rect.top) / 3;
+        SelectObject(mem_dc, icon_font);
+        SetTextColor(mem_dc, primary);
+        let icon_row = RECT {
+            left: rect.left + inset,
+            top: rect.top + scale(4, dpi),
+            right: rect.right,
+            bottom: rect.top + third + scale(4, dpi),
+        };
+        draw_middle(mem_dc, &icon_row, &toggle.glyph().to_string());
+
+        SelectObject(mem_dc, label_font);
+        let mut label: Vec<u16> = crate::i18n::tr(toggle.label()).encode_utf16().collect();
+        let mut label_rect = RECT {
+            left: rect.left + inset,
+            top: rect.top + third + scale(2, dpi),
+            right: rect.right - inset,
+            bottom: rect.top + third * 2 + scale(2, dpi),
+        };
+        DrawTextW(
+            mem_dc,
+            &mut label,
+            &mut label_rect,
+            DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX,
+        );
+
+        SelectObject(mem_dc, text_font);
+        SetTextColor(mem_dc, secondary);
+        let mut status: Vec<u16> = status.encode_utf16().collect();
+        let mut status_rect = RECT {
+            left: rect.left + inset,
+            top: rect.top + third * 2,
+            right: rect.right - inset,
+            bottom: rect.bottom - scale(2, dpi),
+        };
+        DrawTextW(
+            mem_dc,
+            &mut status,
+            &mut status_rect,
+            DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX,
+        );
+    }
+
+    // Sliders: glyph, track with a thumb, percentage
+    let track_height = scale(4, dpi);
+    let thumb = scale(14, dpi);
+    for level in Level::ALL {
+        let value = level_value(level, &readings);
+        let icon = level_icon_rect(level, dpi);
+        if hover == Some(Part::LevelIcon(level)) {
+            fill_round(mem_dc, &icon, scale(6, dpi), theme.background_hover.colorref());
+        }
+        let glyph = match (level, value) {
+            (Level::Brightness, _) => fluent::BRIGHTNESS,
+            (Level::Volume, Some((_, true))) => fluent::VOLUME_MUTE,
+            (Level::Volume, Some((v, false))) if v >= 66 => fluent::VOLUME_3,
+            (Level::Volume, Some((v, false))) if v >= 33 => fluent::VOLUME_2,
+            (Level::Volume, Some((v, false))) if v > 0 => fluent::VOLUME_1,
+            (Level::Volume, _) => fluent::VOLUME_0,
+        };
+        SelectObject(mem_dc, icon_font);
+        SetTextColor(
+            mem_dc,
+            if value.is_some() {
+                theme.text_primary.colorref()
+            } else {
+                theme.text_disabled.colorref()
+            },
+        );
+        draw_centered(mem_dc, &icon, &glyph.to_string());
+
+        let track = track_rect(level, dpi);
+        let mid = (track.top + track.bottom) / 2;
+        let rail = RECT {
+            left: track.left + thumb / 2,
+            top: mid - track_height / 2,
+            right: track.right - thumb / 2,
+            bottom: mid + track_height / 2,
+        };
+        fill_round(mem_dc, &rail, track_height, theme.background_active.colorref());
+
+        SelectObject(mem_dc, text_font);
+        let value_rect = RECT {
+            left: track.right + scale(6, dpi),
+            top: track.top,
+            right: client.right - scale(PADDING, dpi),
+            bottom: track.bottom,
+        };
+        match value {
+            Some((percent, muted)) => {
+                let x = rail.left + (rail.right - rail.left) * percent as i32 / 100;
+                let filled = RECT { right: x, ..rail };
+                let color = if muted { theme.text_disabled } else { theme.accent };
+                fill_round(mem_dc, &filled, track_height, color.colorref());
+                let knob = RECT {
+                    left: x - thumb / 2,
+                    top: mid - thumb / 2,
+                    right: x + thumb / 2,
+                    bottom: mid + thumb / 2,
+                };
+                let knob_color = if hover == Some(Part::Slider(level)) || *DRAG.lock() == Some(level) {
+                    theme.accent_hover
+                } else {
+                    color
+                };
+                fill_round(mem_dc, &knob, thumb, knob_color.colorref());
+                SetTextColor(mem_dc, theme.text_secondary.colorref());
+                draw_middle(mem_dc, &value_rect, &format!("{}%", percent));
+            }
+            None => {
+                SetTextColor(mem_dc, theme.text_disabled.colorref());
+                let text = if level == Level::Brightness && !readings.loaded {
+                    "..."
+                } else {
+                    "—"
+                };
+                draw_middle(mem_dc, &value_rect, text);
+            }
+        }
+    }
+
+    // Footer: divider and the way to the classic menu
+    let divider_y = scale(FOOTER_TOP, dpi);
+    let line = CreateSolidBrush(theme.border.colorref());
+    let divider = RECT {
+        left: scale(PADDING, dpi),
+        top: divider_y,
+        right: client.right - scale(PADDING, dpi),
+        bottom: divider_y + 1,
+    };
+    FillRect(mem_dc, &divider, line);
+    let _ = DeleteObject(line);
+    let more = more_rect(dpi);
+    if hover == Some(Part::More) {
+        fill_round(mem_dc, &more, scale(6, dpi), theme.background_hover.colorref());
+    }
+    SetTextColor(mem_dc, theme.text_primary.colorref());
+    let more_text = RECT {
+        left: more.left + scale(8, dpi),
+        ..more
+    };
+    draw_middle(mem_dc, &more_text, &crate::i18n::tr("More..."));
+    SelectObject(mem_dc, icon_font);
+    SetTextColor(mem_dc, theme.text_secondary.colorref());
+    let chevron = fluent::CHEVRON_RIGHT.to_string();
+    let (chevron_width, _) = measure_text(mem_dc, &chevron);
+    let chevron_rect = RECT {
+        left: more.right - scale(8, dpi) - chevron_width,
+        ..more
+    };
+    draw_middle(mem_dc, &chevron_rect, &chevron);
+
+    let _ = BitBlt(hdc, 0, 0, client.right, client.bottom, mem_dc, 0, 0, SRCCOPY);
+
+    SelectObject(mem_dc, old_font);
+    let _ = DeleteObject(label_font);
+    let _ = DeleteObject(text_font);
+    let _ = DeleteObject(icon_font);
+    SelectObject(mem_dc, old_buffer);
+    let _ = DeleteObject(buffer);
+    let _ = DeleteDC(mem_dc);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(PANEL_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn point_from_lparam(lparam: LPARAM) -> POINT {
+    POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    }
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+
+        WM_TIMER if wparam.0 == REFRESH_TIMER => {
+            let _ = InvalidateRect(hwnd, None, false);
+            LRESULT(0)
+        }
+
+        WM_TIMER if wparam.0 == DISMISS_TIMER => {
+            if clicked_outside(hwnd) {
+                hide_quick_toggles();
+            }
+            LRESULT(0)
+        }
+
+        WM_MOUSEMOVE => {
+            let point = point_from_lparam(lparam);
+            let dragging = *DRAG.lock();
+            if let Some(level) = dragging {
+                apply_level(level, value_at(level, point.x, dpi), false);
+                let _ = InvalidateRect(hwnd, None, false);
+                return LRESULT(0);
+            }
+            let part = hit_test(point, dpi);
+            if std::mem::replace(&mut *HOVER.lock(), part) != part {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            let mut tme = TRACKMOUSEEVENT {
+                cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                dwFlags: TME_LEAVE,
+                hwndTrack: hwnd,
+                dwHoverTime: 0,
+            };
+            let _ = TrackMouseEvent(&mut tme);
+            LRESULT(0)
+        }
+
+        WM_MOUSELEAVE => {
+            if HOVER.lock().take().is_some() {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONDOWN => {
+            let point = point_from_lparam(lparam);
+            if let Some(Part::Slider(level)) = hit_test(point, dpi) {
+                if level_value(level, &READINGS.lock()).is_some() {
+                    *DRAG.lock() = Some(level);
+                    SetCapture(hwnd);
+                    apply_level(level, value_at(level, point.x, dpi), false);
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            let point = point_from_lparam(lparam);
+            let dragged = DRAG.lock().take();
+            if let Some(level) = dragged {
+                let _ = ReleaseCapture();
+                apply_level(level, value_at(level, point.x, dpi), true);
+            } else if let Some(part) = hit_test(point, dpi) {
+                click(part);
+            }
+            if PANEL_HWND.load(Ordering::SeqCst) == hwnd.0 as isize {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            LRESULT(0)
+        }
+
+        WM_RBUTTONUP => {
+            if let Some(part) = hit_test(point_from_lparam(lparam), dpi) {
+                right_click(part);
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = KillTimer(hwnd, REFRESH_TIMER);
+            let _ = KillTimer(hwnd, DISMISS_TIMER);
+            *HOVER.lock() = None;
+            if DRAG.lock().take().is_some() {
+                let _ = ReleaseCapture();
+            }
+            let _ = PANEL_HWND.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
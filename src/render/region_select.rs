@@ -0,0 +1,324 @@
+//! Region selection overlay for screenshots
+//!
+//! Covers the virtual screen with a frozen, dimmed copy of the screenshot. Dragging
+//! with the left button reveals the selection at full brightness; releasing it
+//! hands the chosen area back to the caller. Escape or a right click cancels.
+
+use anyhow::Result;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture, SetFocus};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::modules::capture::ScreenImage;
+use crate::utils::Rect;
+use crate::window::state::get_window_state;
+
+const SELECT_CLASS: &str = "TopBarRegionSelectClass";
+
+/// Selections smaller than this (in either direction) are treated as stray clicks
+const MIN_SELECTION: i32 = 4;
+
+type SelectCallback = Box<dyn FnOnce(&ScreenImage, Rect)>;
+
+struct SelectState {
+    image: ScreenImage,
+    bright: HBITMAP,
+    dimmed: HBITMAP,
+    anchor: Option<(i32, i32)>,
+    current: (i32, i32),
+    on_select: Option<SelectCallback>,
+}
+
+impl SelectState {
+    /// Current selection in client coordinates
+    fn selection(&self) -> Option<RECT> {
+        let (ax, ay) = self.anchor?;
+        let (cx, cy) = self.current;
+        Some(RECT {
+            left: ax.min(cx),
+            top: ay.min(cy),
+            right: ax.max(cx),
+            bottom: ay.max(cy),
+        })
+    }
+}
+
+/// Show the overlay for `image`; `on_select` runs with the chosen area in screen coordinates
+pub fn show_region_select(
+    parent: HWND,
+    image: ScreenImage,
+    on_select: impl FnOnce(&ScreenImage, Rect) + 'static,
+) -> Result<()> {
+    unsafe { register_class()?; }
+
+    let (bright, dimmed) = unsafe { create_bitmaps(&image)? };
+
+    let hwnd = unsafe {
+        let class = to_wide(SELECT_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            image.x, image.y, image.width, image.height,
+            parent,
+            None,
+            hinstance,
+            None,
+        )?
+    };
+
+    let state = Box::new(SelectState {
+        image,
+        bright,
+        dimmed,
+        anchor: None,
+        current: (0, 0),
+        on_select: Some(Box::new(on_select)),
+    });
+    unsafe {
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize);
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+        let _ = SetFocus(hwnd);
+    }
+
+    Ok(())
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(SELECT_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        hCursor: LoadCursorW(None, IDC_CROSS)?,
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Create the full-brightness and dimmed bitmaps for the frozen screen
+unsafe fn create_bitmaps(image: &ScreenImage) -> Result<(HBITMAP, HBITMAP)> {
+    let dimmed_pixels: Vec<u8> = image
+        .pixels
+        .iter()
+        .map(|&c| (c as u16 * 55 / 100) as u8)
+        .collect();
+    Ok((
+        create_bitmap(image, &image.pixels)?,
+        create_bitmap(image, &dimmed_pixels)?,
+    ))
+}
+
+unsafe fn create_bitmap(image: &ScreenImage, pixels: &[u8]) -> Result<HBITMAP> {
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: image.width,
+            biHeight: -image.height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    let bitmap = CreateDIBSection(None, &info, DIB_RGB_COLORS, &mut bits, None, 0)?;
+    std::ptr::copy_nonoverlapping(pixels.as_ptr(), bits as *mut u8, pixels.len());
+    Ok(bitmap)
+}
+
+unsafe fn paint(hwnd: HWND, state: &SelectState) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let (width, height) = (state.image.width, state.image.height);
+
+    // Compose off-screen to avoid flicker while dragging
+    let back_dc = CreateCompatibleDC(hdc);
+    let back = CreateCompatibleBitmap(hdc, width, height);
+    let old_back = SelectObject(back_dc, back);
+    let src_dc = CreateCompatibleDC(hdc);
+
+    let old_src = SelectObject(src_dc, state.dimmed);
+    let _ = BitBlt(back_dc, 0, 0, width, height, src_dc, 0, 0, SRCCOPY);
+
+    if let Some(sel) = state.selection() {
+        let _ = SelectObject(src_dc, state.bright);
+        let _ = BitBlt(
+            back_dc,
+            sel.left,
+            sel.top,
+            sel.right - sel.left,
+            sel.bottom - sel.top,
+            src_dc,
+            sel.left,
+            sel.top,
+            SRCCOPY,
+        );
+
+        let accent = get_window_state()
+            .map(|s| s.read().theme_manager.theme().accent)
+            .unwrap_or(crate::theme::Color::rgb(0, 122, 255));
+        let brush = CreateSolidBrush(accent.colorref());
+        for inset in 0..2 {
+            let frame = RECT {
+                left: sel.left - inset,
+                top: sel.top - inset,
+                right: sel.right + inset,
+                bottom: sel.bottom + inset,
+            };
+            FrameRect(back_dc, &frame, brush);
+        }
+        let _ = DeleteObject(brush);
+
+        // Size label just above the selection (inside it when at the top edge)
+        let label = to_wide(&format!("{} × {}", sel.right - sel.left, sel.bottom - sel.top));
+        SetBkMode(back_dc, TRANSPARENT);
+        SetTextColor(back_dc, crate::theme::Color::rgb(255, 255, 255).colorref());
+        let label_y = if sel.top >= 22 { sel.top - 20 } else { sel.top + 4 };
+        let _ = TextOutW(back_dc, sel.left + 2, label_y, &label[..label.len() - 1]);
+    }
+
+    let _ = BitBlt(hdc, 0, 0, width, height, back_dc, 0, 0, SRCCOPY);
+
+    let _ = SelectObject(src_dc, old_src);
+    let _ = DeleteDC(src_dc);
+    let _ = SelectObject(back_dc, old_back);
+    let _ = DeleteObject(back);
+    let _ = DeleteDC(back_dc);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+fn point_from_lparam(lparam: LPARAM) -> (i32, i32) {
+    let x = (lparam.0 & 0xFFFF) as i16 as i32;
+    let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+    (x, y)
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            if let Some(state) = get_state(hwnd) {
+                paint(hwnd, state);
+            }
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_LBUTTONDOWN => {
+            if let Some(state) = get_state_mut(hwnd) {
+                let pt = point_from_lparam(lparam);
+                state.anchor = Some(pt);
+                state.current = pt;
+                SetCapture(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_MOUSEMOVE => {
+            if let Some(state) = get_state_mut(hwnd) {
+                if state.anchor.is_some() {
+                    state.current = point_from_lparam(lparam);
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            let _ = ReleaseCapture();
+            let Some(state) = get_state_mut(hwnd) else {
+                return LRESULT(0);
+            };
+            state.current = point_from_lparam(lparam);
+            let Some(sel) = state.selection() else {
+                return LRESULT(0);
+            };
+            if sel.right - sel.left < MIN_SELECTION || sel.bottom - sel.top < MIN_SELECTION {
+                state.anchor = None;
+                let _ = InvalidateRect(hwnd, None, false);
+                return LRESULT(0);
+            }
+
+            // Hide first so the overlay is gone before the (synchronous) save runs
+            let _ = ShowWindow(hwnd, SW_HIDE);
+            let rect = Rect::new(
+                state.image.x + sel.left,
+                state.image.y + sel.top,
+                sel.right - sel.left,
+                sel.bottom - sel.top,
+            );
+            if let Some(on_select) = state.on_select.take() {
+                on_select(&state.image, rect);
+            }
+            close_window(hwnd);
+            LRESULT(0)
+        }
+
+        WM_RBUTTONDOWN => {
+            close_window(hwnd);
+            LRESULT(0)
+        }
+
+        WM_KEYDOWN => {
+            if wparam.0 as u32 == 0x1B {
+                close_window(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            free_state(hwnd);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+fn get_state(hwnd: HWND) -> Option<&'static SelectState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut SelectState;
+        if ptr.is_null() { None } else { Some(&*ptr) }
+    }
+}
+
+fn get_state_mut(hwnd: HWND) -> Option<&'static mut SelectState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut SelectState;
+        if ptr.is_null() { None } else { Some(&mut *ptr) }
+    }
+}
+
+fn free_state(hwnd: HWND) {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut SelectState;
+        if !ptr.is_null() {
+            let state = Box::from_raw(ptr);
+            let _ = DeleteObject(state.bright);
+            let _ = DeleteObject(state.dimmed);
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        }
+    }
+}
+
+fn close_window(hwnd: HWND) {
+    unsafe {
+        free_state(hwnd);
+        let _ = DestroyWindow(hwnd);
+    }
+}
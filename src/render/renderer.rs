@@ -1,12 +1,21 @@
 use std::collections::HashMap;
-use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::{HWND, POINT};
 use windows::Win32::Graphics::Gdi::*;
-use windows::Win32::UI::WindowsAndMessaging::DestroyIcon;
 
+use crate::config::Config;
+use crate::effects::animation::{Animations, Pixels};
 use crate::modules::ModuleRegistry;
-use crate::theme::Theme;
+use crate::theme::{Color, Theme};
 use crate::utils::Rect;
 
+/// What needs painting after the modules were checked for changes
+pub enum Repaint {
+    Nothing,
+    /// Only these areas of the bar, in window coordinates
+    Areas(Vec<Rect>),
+    Full,
+}
+
 /// Main renderer for the topbar
 pub struct Renderer {
     pub(crate) hwnd: HWND,
@@ -14,12 +23,34 @@ pub struct Renderer {
     pub(crate) module_registry: ModuleRegistry,
     pub(crate) module_bounds: HashMap<String, Rect>,
     pub(crate) icons: crate::render::icons::Icons,
-    // Cache of small HICONs for executables (keyed by path)
-    pub(crate) icon_cache: std::collections::HashMap<String, windows::Win32::UI::WindowsAndMessaging::HICON>,
+    // App icons by path and size, loaded in the background
+    pub(crate) icon_cache: super::icon_cache::IconCache,
+    // Cache of picture bitmaps (keyed by path and size); None if it couldn't be loaded
+    pub(crate) picture_cache: HashMap<(String, i32), Option<HBITMAP>>,
+    pub(crate) animations: Animations,
+    /// Fingerprint of each module as of the last change check
+    fingerprints: HashMap<String, u64>,
+    /// Whether module bounds moved in the last paint
+    layout_changed: bool,
+    /// Whether `pending_repaint` already updated the modules for the next paint
+    pub(crate) modules_updated: bool,
+    /// The part of the bar being repainted, when it isn't all of it
+    paint_area: Option<Rect>,
+    /// Module bounds as of the last frame, while the next one is drawn
+    previous_bounds: HashMap<String, Rect>,
+    /// Where each right-side module's slot started and ended when last drawn
+    slots: HashMap<String, (i32, i32)>,
     // Double buffering
     back_buffer: HDC,
     back_bitmap: HBITMAP,
+    /// Pixels of the back buffer (top-down, premultiplied BGRA)
+    back_bits: *mut u32,
     buffer_size: (i32, i32),
+    /// A vertical bar's frame, turned from the back buffer onto the window
+    turned_buffer: HDC,
+    turned_bitmap: HBITMAP,
+    turned_bits: *mut u32,
+    turned_size: (i32, i32),
 }
 
 impl Renderer {
@@ -34,15 +65,31 @@ impl Renderer {
             module_registry,
             module_bounds: HashMap::new(),
             icons,
-            icon_cache: std::collections::HashMap::new(),
+            icon_cache: super::icon_cache::IconCache::new(),
+            picture_cache: HashMap::new(),
+            animations: Animations::new(),
+            fingerprints: HashMap::new(),
+            layout_changed: false,
+            modules_updated: false,
+            paint_area: None,
+            previous_bounds: HashMap::new(),
+            slots: HashMap::new(),
             back_buffer: HDC::default(),
             back_bitmap: HBITMAP::default(),
+            back_bits: std::ptr::null_mut(),
             buffer_size: (0, 0),
+            turned_buffer: HDC::default(),
+            turned_bitmap: HBITMAP::default(),
+            turned_bits: std::ptr::null_mut(),
+            turned_size: (0, 0),
         })
     }
 
-    /// Update DPI
+    /// Update DPI; text measured at the old DPI no longer applies
     pub fn set_dpi(&mut self, dpi: u32) {
+        if dpi != self.dpi {
+            super::drawing::clear_text_cache();
+        }
         self.dpi = dpi;
     }
 
@@ -50,50 +97,293 @@ impl Renderer {
     fn ensure_back_buffer(&mut self, hdc: HDC, width: i32, height: i32) {
         if self.buffer_size.0 != width || self.buffer_size.1 != height {
             unsafe {
-                // Clean up old buffer
-                if !self.back_buffer.is_invalid() {
-                    let _ = DeleteDC(self.back_buffer);
-                }
-                if !self.back_bitmap.is_invalid() {
-                    let _ = DeleteObject(self.back_bitmap);
-                }
-
-                // Create new buffer
-                self.back_buffer = CreateCompatibleDC(hdc);
-                self.back_bitmap = CreateCompatibleBitmap(hdc, width, height);
-                SelectObject(self.back_buffer, self.back_bitmap);
+                free_buffer(self.back_buffer, self.back_bitmap);
+                (self.back_buffer, self.back_bitmap, self.back_bits) = create_buffer(hdc, width, height);
                 self.buffer_size = (width, height);
             }
         }
     }
 
-    /// Main paint function
-    pub fn paint(&mut self, hdc: HDC, bar_rect: &Rect, theme: &Theme) {
-        self.ensure_back_buffer(hdc, bar_rect.width, bar_rect.height);
+    /// Copy the back buffer to a vertical bar's window, turned a quarter clockwise
+    unsafe fn present_turned(&mut self, hdc: HDC, bar_rect: &Rect) {
+        let (width, height) = (bar_rect.width, bar_rect.height);
+        if self.turned_size != (width, height) {
+            free_buffer(self.turned_buffer, self.turned_bitmap);
+            (self.turned_buffer, self.turned_bitmap, self.turned_bits) = create_buffer(hdc, width, height);
+            self.turned_size = (width, height);
+        }
 
-        // Clear module bounds
-        self.module_bounds.clear();
+        if self.back_bits.is_null() || self.turned_bits.is_null() {
+            // No pixel access: let GDI turn it (drops per-pixel alpha)
+            let corners = [
+                POINT { x: width, y: 0 },
+                POINT { x: width, y: height },
+                POINT { x: 0, y: 0 },
+            ];
+            let _ = PlgBlt(hdc, &corners, self.back_buffer, 0, 0, height, width, None, 0, 0);
+            return;
+        }
 
-        // Draw to back buffer
-        super::drawing::draw_background(self.back_buffer, bar_rect, theme);
-        super::modules::draw_modules(self, self.back_buffer, bar_rect, theme);
+        let _ = GdiFlush();
+        let source = std::slice::from_raw_parts(self.back_bits, (width * height) as usize);
+        let turned = std::slice::from_raw_parts_mut(self.turned_bits, (width * height) as usize);
+        // Window pixel (x, y) shows layout pixel (y, width - 1 - x); layout rows are `height` long
+        for y in 0..height {
+            let row = &mut turned[(y * width) as usize..((y + 1) * width) as usize];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = source[((width - 1 - x as i32) * height + y) as usize];
+            }
+        }
+        let _ = BitBlt(hdc, 0, 0, width, height, self.turned_buffer, 0, 0, SRCCOPY);
+    }
+
+    /// Main paint function; `window_rect` is the bar's rectangle on screen and
+    /// `area` the part of the window being repainted
+    pub fn paint(&mut self, hdc: HDC, window_rect: &Rect, area: &Rect, theme: &Theme) {
+        // Everything is drawn horizontally; a vertical bar is turned when copied out
+        let layout = crate::window::manager::WindowManager::layout_rect(window_rect);
+        let bar_rect = &layout;
+        let whole = area.x <= 0 && area.y <= 0 && area.right() >= bar_rect.width && area.bottom() >= bar_rect.height;
+        self.paint_area = (!whole && bar_rect.width == window_rect.width).then_some(*area);
+        self.ensure_back_buffer(hdc, bar_rect.width, bar_rect.height);
+        self.draw_frame(bar_rect, theme);
 
         // Copy to screen
         unsafe {
+            if bar_rect.width != window_rect.width {
+                self.present_turned(hdc, window_rect);
+                return;
+            }
+            let copy = self.paint_area.take().unwrap_or(*bar_rect);
             let _ = BitBlt(
                 hdc,
-                0,
-                0,
-                bar_rect.width,
-                bar_rect.height,
+                copy.x,
+                copy.y,
+                copy.width,
+                copy.height,
                 self.back_buffer,
-                0,
-                0,
+                copy.x,
+                copy.y,
                 SRCCOPY,
             );
         }
     }
 
+    /// Draw the bar as it looks now without putting it on screen, as straight
+    /// (not premultiplied) RGBA rows; a vertical bar comes out lying down, the
+    /// way it's laid out
+    pub fn render_offscreen(&mut self, window_rect: &Rect, theme: &Theme) -> Option<(i32, i32, Vec<u8>)> {
+        let layout = crate::window::manager::WindowManager::layout_rect(window_rect);
+        unsafe {
+            let screen = GetDC(None);
+            self.ensure_back_buffer(screen, layout.width, layout.height);
+            ReleaseDC(None, screen);
+        }
+        self.paint_area = None;
+        let translucent = self.draw_frame(&layout, theme);
+        let pixels = unsafe { self.back_pixels()? };
+        let rgba = pixels
+            .data
+            .iter()
+            .flat_map(|&pixel| {
+                // Only translucent frames have real alpha; GDI leaves it at 0 otherwise
+                let alpha = if translucent { (pixel >> 24).max(1) } else { 255 };
+                let channel = |shift: u32| (((pixel >> shift) & 0xFF) * 255 / alpha).min(255) as u8;
+                [channel(16), channel(8), channel(0), alpha as u8]
+            })
+            .collect();
+        Some((layout.width, layout.height, rgba))
+    }
+
+    /// Draw a frame into the back buffer; returns whether it was drawn
+    /// translucent (with per-pixel alpha)
+    fn draw_frame(&mut self, bar_rect: &Rect, theme: &Theme) -> bool {
+        // Bounds are rebuilt as modules draw; last frame's place the hover highlight
+        self.previous_bounds = std::mem::take(&mut self.module_bounds);
+        // Only the area being repainted is drawn; the rest keeps its pixels
+        if let Some(area) = self.paint_area {
+            unsafe {
+                IntersectClipRect(self.back_buffer, area.x, area.y, area.right(), area.bottom());
+            }
+        }
+
+        // Draw to back buffer
+        let tint = if crate::window::manager::WindowManager::is_translucent() && !self.back_bits.is_null() {
+            tint_color(theme)
+        } else {
+            None
+        };
+        let corner_radius = floating_corner_radius(self.dpi);
+        match tint {
+            Some(tint) => {
+                self.fill(tint);
+                super::drawing::draw_bar_border(self.back_buffer, bar_rect, theme, corner_radius);
+            }
+            None => super::drawing::draw_background(self.back_buffer, bar_rect, theme, corner_radius),
+        }
+        // Nothing but the background fits while shrunk to a strip
+        if !crate::window::manager::WindowManager::is_slim() {
+            super::drawing::draw_module_pills(self.back_buffer, bar_rect, theme, &self.previous_bounds, self.dpi);
+            super::drawing::draw_module_highlight(self.back_buffer, bar_rect, theme, &self.previous_bounds, self.dpi);
+            let background = unsafe { self.back_pixels() };
+            self.animations.begin_frame(animation_duration(), background.as_ref());
+            super::modules::draw_modules(self, self.back_buffer, bar_rect, theme);
+            self.animations.end_frame();
+            super::drawing::draw_offline(self.back_buffer, theme, &self.offline_bounds());
+            super::drawing::draw_attention(self.back_buffer, bar_rect, theme, &self.module_bounds, self.dpi);
+            super::drawing::draw_copied_flash(self.back_buffer, theme, &self.module_bounds, self.dpi);
+            super::drawing::draw_focus_ring(self.back_buffer, theme, &self.module_bounds, self.dpi);
+        }
+        if tint.is_some() {
+            self.make_drawn_pixels_opaque();
+        }
+        if self.paint_area.is_some() {
+            unsafe {
+                SelectClipRgn(self.back_buffer, None);
+            }
+        }
+        self.layout_changed = self.module_bounds != self.previous_bounds;
+        tint.is_some()
+    }
+
+    /// Bounds of the modules that offline mode has stopped
+    fn offline_bounds(&self) -> Vec<Rect> {
+        let Some(config) = crate::window::state::get_window_state().map(|s| s.read().config.clone()) else {
+            return Vec::new();
+        };
+        if !config.privacy.offline_mode {
+            return Vec::new();
+        }
+        self.module_bounds
+            .iter()
+            .filter(|(id, _)| {
+                self.module_registry
+                    .get(id)
+                    .is_some_and(|m| crate::modules::is_offline(m, &config))
+            })
+            .map(|(_, bounds)| *bounds)
+            .collect()
+    }
+
+    /// Fill the back buffer with a premultiplied color
+    fn fill(&mut self, color: Color) {
+        let alpha = color.a as u32;
+        let premultiply = |c: u8| c as u32 * alpha / 255;
+        let pixel = (alpha << 24) | (premultiply(color.r) << 16) | (premultiply(color.g) << 8) | premultiply(color.b);
+        unsafe {
+            let _ = GdiFlush();
+            self.pixels().fill(pixel);
+        }
+    }
+
+    /// GDI leaves alpha at 0 on everything it draws (text, icons, highlights); make
+    /// those pixels opaque so only the untouched tint lets the backdrop through
+    fn make_drawn_pixels_opaque(&mut self) {
+        unsafe {
+            let _ = GdiFlush();
+            for pixel in self.pixels() {
+                if *pixel >> 24 == 0 {
+                    *pixel |= 0xFF00_0000;
+                }
+            }
+        }
+    }
+
+    /// The back buffer's pixels; the buffer must be a DIB
+    unsafe fn pixels(&mut self) -> &mut [u32] {
+        let len = (self.buffer_size.0.max(0) * self.buffer_size.1.max(0)) as usize;
+        std::slice::from_raw_parts_mut(self.back_bits, len)
+    }
+
+    /// The back buffer's pixels for the animation engine, once pending drawing
+    /// has landed; `None` when the buffer isn't a DIB
+    pub(crate) unsafe fn back_pixels<'a>(&self) -> Option<Pixels<'a>> {
+        if self.back_bits.is_null() {
+            return None;
+        }
+        let _ = GdiFlush();
+        let len = (self.buffer_size.0.max(0) * self.buffer_size.1.max(0)) as usize;
+        Some(Pixels {
+            data: std::slice::from_raw_parts_mut(self.back_bits, len),
+            width: self.buffer_size.0,
+            height: self.buffer_size.1,
+        })
+    }
+
+    /// Whether modules moved or resized in the last paint, so a paint limited to
+    /// the modules that changed left the rest of the bar out of date
+    pub fn layout_changed(&self) -> bool {
+        self.layout_changed
+    }
+
+    /// Update the modules and work out what has to be repainted: the areas of
+    /// the modules whose text, graph or values changed since the last check, or
+    /// the whole bar when a change can't be placed (a module without bounds, a
+    /// vertical bar) or attention effects are animating
+    pub fn pending_repaint(&mut self, config: &Config, window_rect: &Rect) -> Repaint {
+        self.module_registry.update_all(config);
+        self.modules_updated = true;
+
+        let modules = &config.modules;
+        let mut ids: Vec<String> = modules.left_modules.clone();
+        ids.extend(modules.center_modules.iter().cloned());
+        ids.extend(modules.right_modules_flat());
+        let mut changed = Vec::new();
+        for id in ids {
+            if modules.group_of(&id).is_some_and(|g| g.collapsed) {
+                continue;
+            }
+            let Some(fingerprint) = self.module_registry.fingerprint(&id, config) else {
+                continue;
+            };
+            if self.fingerprints.insert(id.clone(), fingerprint) != Some(fingerprint) {
+                changed.push(id);
+            }
+        }
+
+        if super::drawing::effects_animating() {
+            return Repaint::Full;
+        }
+        if changed.is_empty() {
+            return Repaint::Nothing;
+        }
+        let layout = crate::window::manager::WindowManager::layout_rect(window_rect);
+        if layout.width != window_rect.width {
+            return Repaint::Full;
+        }
+
+        // Pills and highlights reach past a module's bounds
+        let reach = crate::utils::scale_by_dpi(config.appearance.pills.padding as i32 + 4, self.dpi);
+        let mut areas = Vec::with_capacity(changed.len());
+        for id in &changed {
+            let Some(bounds) = self.module_bounds.get(id) else {
+                return Repaint::Full;
+            };
+            areas.push(Rect::new(bounds.x - reach, 0, bounds.width + reach * 2, window_rect.height));
+        }
+        Repaint::Areas(areas)
+    }
+
+    /// Where the slot of a right-side module laid out from `start` ends, when
+    /// the module sits where it did last frame, outside the area being
+    /// repainted; it keeps its bounds and pixels instead of being drawn again
+    pub(crate) fn reuse_slot(&mut self, id: &str, start: i32, dx: i32) -> Option<i32> {
+        let area = self.paint_area?;
+        let &(previous_start, end) = self.slots.get(id)?;
+        let bounds = *self.previous_bounds.get(id)?;
+        if dx != 0 || previous_start != start || bounds.intersects(&area) {
+            return None;
+        }
+        self.module_bounds.insert(id.to_string(), bounds);
+        self.animations.keep_module(id);
+        Some(end)
+    }
+
+    /// Remember where a right-side module's slot started and ended
+    pub(crate) fn record_slot(&mut self, id: &str, start: i32, end: i32) {
+        self.slots.insert(id.to_string(), (start, end));
+    }
+
     /// Hit test to find which module was clicked
     pub fn hit_test(&self, x: i32, y: i32) -> Option<String> {
         for (id, rect) in &self.module_bounds {
@@ -110,20 +400,89 @@ impl Renderer {
     }
 }
 
+/// Create an offscreen buffer: a 32-bit DIB so translucent mode can set alpha per
+/// pixel, or a plain bitmap (and no pixels) if that fails
+unsafe fn create_buffer(hdc: HDC, width: i32, height: i32) -> (HDC, HBITMAP, *mut u32) {
+    let buffer = CreateCompatibleDC(hdc);
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    let (bitmap, bits) = match CreateDIBSection(hdc, &info, DIB_RGB_COLORS, &mut bits, None, 0) {
+        Ok(bitmap) => (bitmap, bits as *mut u32),
+        Err(e) => {
+            log::warn!("Failed to create back buffer DIB: {}", e);
+            (CreateCompatibleBitmap(hdc, width, height), std::ptr::null_mut())
+        }
+    };
+    SelectObject(buffer, bitmap);
+    (buffer, bitmap, bits)
+}
+
+unsafe fn free_buffer(buffer: HDC, bitmap: HBITMAP) {
+    if !buffer.is_invalid() {
+        let _ = DeleteDC(buffer);
+    }
+    if !bitmap.is_invalid() {
+        let _ = DeleteObject(bitmap);
+    }
+}
+
+/// Tint painted over the blurred backdrop: the configured color or the theme's
+/// background, with `blur_intensity` deciding how much of the backdrop shows
+fn tint_color(theme: &Theme) -> Option<Color> {
+    let state = crate::window::state::get_window_state()?;
+    let state = state.read();
+    let appearance = &state.config.appearance;
+    let color = appearance
+        .tint
+        .as_deref()
+        .and_then(Color::from_hex)
+        .unwrap_or(theme.background);
+    // Alpha 0 marks pixels GDI drew on, so the tint never goes fully clear
+    let alpha = 255 - appearance.blur_intensity.min(100) * 255 / 100;
+    Some(color.with_alpha(alpha.max(1) as u8))
+}
+
+/// Corner radius of a floating bar, or `None` while it spans the screen
+fn floating_corner_radius(dpi: u32) -> Option<i32> {
+    let state = crate::window::state::get_window_state()?;
+    let floating = &state.read().config.appearance.floating;
+    if !floating.enabled || crate::window::manager::WindowManager::is_slim() {
+        return None;
+    }
+    Some(crate::utils::scale_by_dpi(floating.radius as i32, dpi))
+}
+
+/// Length of module animations, or `None` when motion is reduced
+fn animation_duration() -> Option<u32> {
+    let state = crate::window::state::get_window_state()?;
+    let state = state.read();
+    let appearance = &state.config.appearance;
+    if crate::accessibility::reduce_motion(appearance) {
+        return None;
+    }
+    Some(appearance.animation_speed.max(1))
+}
+
 impl Drop for Renderer {
     fn drop(&mut self) {
         unsafe {
-            if !self.back_buffer.is_invalid() {
-                let _ = DeleteDC(self.back_buffer);
-            }
-            if !self.back_bitmap.is_invalid() {
-                let _ = DeleteObject(self.back_bitmap);
-            }
+            free_buffer(self.back_buffer, self.back_bitmap);
+            free_buffer(self.turned_buffer, self.turned_bitmap);
 
-            // Destroy any cached icon handles
-            for (_path, icon) in self.icon_cache.drain() {
-                if !icon.is_invalid() {
-                    let _ = DestroyIcon(icon);
+            for (_key, bitmap) in self.picture_cache.drain() {
+                if let Some(bitmap) = bitmap {
+                    let _ = DeleteObject(bitmap);
                 }
             }
         }
@@ -0,0 +1,683 @@
+//! Drop shelf popup
+//!
+//! A panel under the bar with a tile (thumbnail and name) for each file on the
+//! shelf. Files can be dropped on it as well as on the bar. Dragging a tile, or
+//! the "Drag All" button, drags the files into another app or folder; double-click
+//! opens a file and right-click takes it off the shelf. The panel never takes
+//! focus and stays open while you switch windows, until it's closed or emptied.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, SIZE, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture};
+use windows::Win32::UI::Shell::{
+    DragAcceptFiles, IShellItemImageFactory, SHCreateItemFromParsingName, ShellExecuteW, HDROP,
+    SIIGBF_RESIZETOFIT,
+};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::sounds::{self, SoundEvent};
+use crate::window::state::get_window_state;
+
+const SHELF_CLASS: &str = "TopBarShelfClass";
+const COLUMNS: i32 = 3;
+const TILE_WIDTH: i32 = 104;
+const TILE_HEIGHT: i32 = 104;
+const THUMBNAIL: i32 = 64;
+const HEADER_HEIGHT: i32 = 40;
+const PADDING: i32 = 12;
+/// WM_COPYGLOBALDATA, which Explorer sends along with a drop
+const WM_COPYGLOBALDATA: u32 = 0x0049;
+/// Height of the drop hint shown while the shelf is empty
+const EMPTY_HEIGHT: i32 = 96;
+/// Tiles shown; Drag All still takes every file
+const MAX_TILES: usize = 12;
+
+/// Posted when the shelf changed on another thread (a drag out finished)
+const WM_SHELF_CHANGED: u32 = WM_APP + 40;
+
+/// WM_MOUSELEAVE message constant
+const WM_MOUSELEAVE: u32 = 0x02A3;
+
+/// The shelf window, if shown
+static SHELF_HWND: AtomicIsize = AtomicIsize::new(0);
+/// The bar the shelf hangs under
+static BAR_HWND: AtomicIsize = AtomicIsize::new(0);
+
+/// Part of the panel under the mouse
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hit {
+    Tile(usize),
+    DragAll,
+    Clear,
+    Close,
+}
+
+/// A cached thumbnail
+struct Thumbnail {
+    bitmap: HBITMAP,
+    width: i32,
+    height: i32,
+    /// Has an alpha channel to blend with; otherwise it's copied as is
+    alpha: bool,
+}
+
+struct ShelfState {
+    items: Vec<PathBuf>,
+    thumbnails: HashMap<PathBuf, Option<Thumbnail>>,
+    hover: Option<Hit>,
+    /// Where the left button went down, until it's released or a drag starts
+    pressed: Option<(Hit, POINT)>,
+}
+
+/// Take or stop taking dropped files on `hwnd`. The drop messages are let
+/// through the message filter too, or nothing can be dropped from Explorer
+/// while the bar runs elevated.
+pub fn accept_drops(hwnd: HWND, enabled: bool) {
+    let action = if enabled { MSGFLT_ALLOW } else { MSGFLT_RESET };
+    unsafe {
+        DragAcceptFiles(hwnd, enabled);
+        for message in [WM_DROPFILES, WM_COPYDATA, WM_COPYGLOBALDATA] {
+            if let Err(e) = ChangeWindowMessageFilterEx(hwnd, message, action, None) {
+                log::debug!("Failed to change the message filter for {:#x}: {}", message, e);
+            }
+        }
+    }
+}
+
+/// Show the shelf under the bar, centered on `x` (screen coordinates)
+pub fn show_shelf(bar: HWND, x: i32) {
+    if let Err(e) = unsafe { show(bar, x) } {
+        log::warn!("Failed to show shelf: {}", e);
+    }
+}
+
+/// Add dropped files to the shelf and show it
+pub fn drop_on_shelf(bar: HWND, hdrop: HDROP) {
+    let paths = crate::shelf::dropped_files(hdrop);
+    if paths.is_empty() {
+        return;
+    }
+    crate::shelf::add(paths);
+    let mut cursor = POINT::default();
+    unsafe {
+        let _ = GetCursorPos(&mut cursor);
+    }
+    show_shelf(bar, cursor.x);
+}
+
+unsafe fn show(bar: HWND, x: i32) -> Result<()> {
+    BAR_HWND.store(bar.0 as isize, Ordering::SeqCst);
+    let mut hwnd = HWND(SHELF_HWND.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    let opening = hwnd.0.is_null() || !IsWindow(hwnd).as_bool();
+    if opening {
+        register_class()?;
+        let class = to_wide(SHELF_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        let state = Box::new(ShelfState {
+            items: Vec::new(),
+            thumbnails: HashMap::new(),
+            hover: None,
+            pressed: None,
+        });
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize);
+        accept_drops(hwnd, true);
+        SHELF_HWND.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    if let Some(state) = get_state_mut(hwnd) {
+        state.items = crate::shelf::items();
+    }
+
+    let mut bar_rect = RECT::default();
+    let _ = GetWindowRect(bar, &mut bar_rect);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let (width, height) = panel_size(hwnd, dpi);
+    let monitor = MonitorFromWindow(bar, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let margin = scale(8, dpi);
+    let left = (x - width / 2).clamp(info.rcMonitor.left + margin, info.rcMonitor.right - margin - width);
+    let top = bar_rect.bottom + scale(6, dpi);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, left, top, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(12, dpi), scale(12, dpi));
+    SetWindowRgn(hwnd, region, true);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    if opening {
+        sounds::play_event(SoundEvent::PopupOpen);
+    }
+    Ok(())
+}
+
+/// Close the shelf if it's open
+pub fn hide_shelf() {
+    let hwnd = HWND(SHELF_HWND.swap(0, Ordering::SeqCst) as *mut std::ffi::c_void);
+    if !hwnd.0.is_null() {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+        sounds::play_event(SoundEvent::PopupClose);
+    }
+}
+
+/// Reload the items after a change, resizing the panel or closing it when empty
+unsafe fn refresh(hwnd: HWND) {
+    let items = crate::shelf::items();
+    if items.is_empty() {
+        hide_shelf();
+        return;
+    }
+    let mut rect = RECT::default();
+    let _ = GetWindowRect(hwnd, &mut rect);
+    if let Some(state) = get_state_mut(hwnd) {
+        state.thumbnails.retain(|path, thumbnail| {
+            let keep = items.contains(path);
+            if !keep {
+                if let Some(thumbnail) = thumbnail {
+                    let _ = DeleteObject(thumbnail.bitmap);
+                }
+            }
+            keep
+        });
+        state.hover = None;
+    }
+    let bar = HWND(BAR_HWND.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    let _ = show(bar, (rect.left + rect.right) / 2);
+}
+
+/// Panel size for the current number of items
+unsafe fn panel_size(hwnd: HWND, dpi: u32) -> (i32, i32) {
+    let count = get_state(hwnd).map(|s| s.items.len().min(MAX_TILES) as i32).unwrap_or(0);
+    let width = scale(PADDING * 2 + TILE_WIDTH * COLUMNS, dpi);
+    let body = if count == 0 {
+        EMPTY_HEIGHT
+    } else {
+        ((count + COLUMNS - 1) / COLUMNS) * TILE_HEIGHT
+    };
+    (width, scale(HEADER_HEIGHT + body + PADDING, dpi))
+}
+
+fn tile_rect(index: usize, dpi: u32) -> RECT {
+    let column = index as i32 % COLUMNS;
+    let row = index as i32 / COLUMNS;
+    let left = scale(PADDING + column * TILE_WIDTH, dpi);
+    let top = scale(HEADER_HEIGHT + row * TILE_HEIGHT, dpi);
+    RECT {
+        left,
+        top,
+        right: left + scale(TILE_WIDTH, dpi),
+        bottom: top + scale(TILE_HEIGHT, dpi),
+    }
+}
+
+/// Header buttons: Drag All, Clear and close
+fn header_rects(client: &RECT, dpi: u32) -> [(Hit, RECT); 3] {
+    let top = scale(8, dpi);
+    let bottom = scale(HEADER_HEIGHT - 8, dpi);
+    let close = RECT {
+        left: client.right - scale(PADDING + 24, dpi),
+        top,
+        right: client.right - scale(PADDING, dpi),
+        bottom,
+    };
+    let clear = RECT {
+        left: close.left - scale(58, dpi),
+        top,
+        right: close.left - scale(6, dpi),
+        bottom,
+    };
+    let drag_all = RECT {
+        left: clear.left - scale(78, dpi),
+        top,
+        right: clear.left - scale(6, dpi),
+        bottom,
+    };
+    [(Hit::DragAll, drag_all), (Hit::Clear, clear), (Hit::Close, close)]
+}
+
+fn hit_test(hwnd: HWND, point: POINT) -> Option<Hit> {
+    let state = get_state(hwnd)?;
+    let dpi = unsafe { GetDpiForWindow(hwnd).max(96) };
+    let mut client = RECT::default();
+    unsafe {
+        let _ = GetClientRect(hwnd, &mut client);
+    }
+    let header = header_rects(&client, dpi)
+        .into_iter()
+        .filter(|(hit, _)| !state.items.is_empty() || *hit == Hit::Close);
+    for (hit, rect) in header {
+        if unsafe { PtInRect(&rect, point).as_bool() } {
+            return Some(hit);
+        }
+    }
+    (0..state.items.len().min(MAX_TILES))
+        .find(|&i| unsafe { PtInRect(&tile_rect(i, dpi), point).as_bool() })
+        .map(Hit::Tile)
+}
+
+/// Thumbnail from the shell (a preview for images and documents, the icon otherwise)
+unsafe fn load_thumbnail(path: &Path, size: i32) -> Option<Thumbnail> {
+    let wide = to_wide(&path.to_string_lossy());
+    let factory: IShellItemImageFactory = SHCreateItemFromParsingName(PCWSTR(wide.as_ptr()), None).ok()?;
+    let bitmap = factory.GetImage(SIZE { cx: size, cy: size }, SIIGBF_RESIZETOFIT).ok()?;
+
+    let mut info = BITMAP::default();
+    GetObjectW(bitmap, std::mem::size_of::<BITMAP>() as i32, Some(&mut info as *mut _ as *mut _));
+    // Photos come back with an empty alpha channel; only icons need blending
+    let alpha = info.bmBitsPixel == 32 && !info.bmBits.is_null() && {
+        let pixels = std::slice::from_raw_parts(
+            info.bmBits as *const u8,
+            (info.bmWidthBytes * info.bmHeight.abs()) as usize,
+        );
+        pixels.chunks_exact(4).any(|px| px[3] != 0)
+    };
+    Some(Thumbnail {
+        bitmap,
+        width: info.bmWidth,
+        height: info.bmHeight.abs(),
+        alpha,
+    })
+}
+
+unsafe fn draw_thumbnail(hdc: HDC, thumbnail: &Thumbnail, center_x: i32, top: i32, size: i32) {
+    let x = center_x - thumbnail.width / 2;
+    let y = top + (size - thumbnail.height) / 2;
+    let mem_dc = CreateCompatibleDC(hdc);
+    let old = SelectObject(mem_dc, thumbnail.bitmap);
+    if thumbnail.alpha {
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: 255,
+            AlphaFormat: AC_SRC_ALPHA as u8,
+        };
+        let _ = AlphaBlend(hdc, x, y, thumbnail.width, thumbnail.height, mem_dc, 0, 0, thumbnail.width, thumbnail.height, blend);
+    } else {
+        let _ = BitBlt(hdc, x, y, thumbnail.width, thumbnail.height, mem_dc, 0, 0, SRCCOPY);
+    }
+    SelectObject(mem_dc, old);
+    let _ = DeleteDC(mem_dc);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut client = RECT::default();
+    let _ = GetClientRect(hwnd, &mut client);
+
+    // Draw off-screen so thumbnails don't flicker
+    let mem_dc = CreateCompatibleDC(hdc);
+    let buffer = CreateCompatibleBitmap(hdc, client.right, client.bottom);
+    let old_buffer = SelectObject(mem_dc, buffer);
+
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(mem_dc, &client, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(mem_dc, &client, border);
+    let _ = DeleteObject(border);
+    SetBkMode(mem_dc, TRANSPARENT);
+
+    let family = font_family();
+    let title_font = create_font(&family, scale(14, dpi), true);
+    let text_font = create_font(&family, scale(12, dpi), false);
+    let old_font = SelectObject(mem_dc, title_font);
+
+    let Some(state) = get_state_mut(hwnd) else {
+        SelectObject(mem_dc, old_font);
+        let _ = DeleteObject(title_font);
+        let _ = DeleteObject(text_font);
+        SelectObject(mem_dc, old_buffer);
+        let _ = DeleteObject(buffer);
+        let _ = DeleteDC(mem_dc);
+        let _ = EndPaint(hwnd, &ps);
+        return;
+    };
+
+    // Header
+    let padding = scale(PADDING, dpi);
+    SetTextColor(mem_dc, theme.text_primary.colorref());
+    let title = match state.items.len() {
+        0 => "Shelf".to_string(),
+        1 => "Shelf · 1 file".to_string(),
+        n => format!("Shelf · {} files", n),
+    };
+    let (_, title_height) = measure_text(mem_dc, &title);
+    draw_text(mem_dc, padding, (scale(HEADER_HEIGHT, dpi) - title_height) / 2, &title);
+
+    SelectObject(mem_dc, text_font);
+    for (hit, rect) in header_rects(&client, dpi) {
+        if hit != Hit::Close && state.items.is_empty() {
+            continue;
+        }
+        let label = match hit {
+            Hit::DragAll => "Drag All",
+            Hit::Clear => "Clear",
+            _ => "✕",
+        };
+        if state.hover == Some(hit) {
+            let brush = CreateSolidBrush(theme.background_hover.colorref());
+            let region = CreateRoundRectRgn(rect.left, rect.top, rect.right, rect.bottom, scale(6, dpi), scale(6, dpi));
+            let _ = FillRgn(mem_dc, region, brush);
+            let _ = DeleteObject(region);
+            let _ = DeleteObject(brush);
+        }
+        SetTextColor(mem_dc, theme.text_secondary.colorref());
+        let (w, h) = measure_text(mem_dc, label);
+        draw_text(
+            mem_dc,
+            rect.left + (rect.right - rect.left - w) / 2,
+            rect.top + (rect.bottom - rect.top - h) / 2,
+            label,
+        );
+    }
+
+    if state.items.is_empty() {
+        SetTextColor(mem_dc, theme.text_secondary.colorref());
+        let hint = crate::i18n::tr("Drop files here to park them");
+        let (w, h) = measure_text(mem_dc, &hint);
+        let body_top = scale(HEADER_HEIGHT, dpi);
+        draw_text(
+            mem_dc,
+            (client.right - w) / 2,
+            body_top + (scale(EMPTY_HEIGHT, dpi) - h) / 2,
+            &hint,
+        );
+    }
+
+    // Tiles
+    let thumb_size = scale(THUMBNAIL, dpi);
+    let items = state.items.clone();
+    for (i, path) in items.iter().enumerate().take(MAX_TILES) {
+        let tile = tile_rect(i, dpi);
+        if state.hover == Some(Hit::Tile(i)) {
+            let brush = CreateSolidBrush(theme.background_hover.colorref());
+            let region = CreateRoundRectRgn(tile.left + 2, tile.top + 2, tile.right - 2, tile.bottom - 2, scale(8, dpi), scale(8, dpi));
+            let _ = FillRgn(mem_dc, region, brush);
+            let _ = DeleteObject(region);
+            let _ = DeleteObject(brush);
+        }
+
+        let thumb_top = tile.top + scale(6, dpi);
+        let thumbnail = state
+            .thumbnails
+            .entry(path.clone())
+            .or_insert_with(|| load_thumbnail(path, thumb_size));
+        if let Some(thumbnail) = thumbnail {
+            draw_thumbnail(mem_dc, thumbnail, (tile.left + tile.right) / 2, thumb_top, thumb_size);
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+        let mut name_wide: Vec<u16> = name.encode_utf16().collect();
+        let mut name_rect = RECT {
+            left: tile.left + scale(4, dpi),
+            top: thumb_top + thumb_size + scale(4, dpi),
+            right: tile.right - scale(4, dpi),
+            bottom: tile.bottom - scale(2, dpi),
+        };
+        SetTextColor(mem_dc, theme.text_primary.colorref());
+        DrawTextW(
+            mem_dc,
+            &mut name_wide,
+            &mut name_rect,
+            DT_CENTER | DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX,
+        );
+    }
+
+    let _ = BitBlt(hdc, 0, 0, client.right, client.bottom, mem_dc, 0, 0, SRCCOPY);
+
+    SelectObject(mem_dc, old_font);
+    let _ = DeleteObject(title_font);
+    let _ = DeleteObject(text_font);
+    SelectObject(mem_dc, old_buffer);
+    let _ = DeleteObject(buffer);
+    let _ = DeleteDC(mem_dc);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+/// Drag files out on a thread of its own; the shelf refreshes when the drop is done
+fn start_drag(hwnd: HWND, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    let remove_after_drag = get_window_state()
+        .map(|s| s.read().config.shelf.remove_after_drag)
+        .unwrap_or(true);
+    let hwnd_raw = hwnd.0 as isize;
+    std::thread::spawn(move || {
+        match crate::shelf::drag_out(&paths) {
+            Ok(effect) => {
+                if remove_after_drag && effect != windows::Win32::System::Ole::DROPEFFECT_NONE {
+                    crate::shelf::remove(&paths);
+                }
+            }
+            Err(e) => log::warn!("Shelf drag failed: {}", e),
+        }
+        unsafe {
+            let _ = PostMessageW(HWND(hwnd_raw as *mut std::ffi::c_void), WM_SHELF_CHANGED, WPARAM(0), LPARAM(0));
+        }
+    });
+}
+
+fn open_path(path: &Path) {
+    let wide = to_wide(&path.to_string_lossy());
+    unsafe {
+        ShellExecuteW(None, PCWSTR::null(), PCWSTR(wide.as_ptr()), None, None, SW_SHOWNORMAL);
+    }
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(SHELF_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_DBLCLKS | CS_DROPSHADOW,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn get_state(hwnd: HWND) -> Option<&'static ShelfState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut ShelfState;
+        if ptr.is_null() { None } else { Some(&*ptr) }
+    }
+}
+
+fn get_state_mut(hwnd: HWND) -> Option<&'static mut ShelfState> {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut ShelfState;
+        if ptr.is_null() { None } else { Some(&mut *ptr) }
+    }
+}
+
+fn free_state(hwnd: HWND) {
+    unsafe {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut ShelfState;
+        if ptr.is_null() {
+            return;
+        }
+        let state = Box::from_raw(ptr);
+        for thumbnail in state.thumbnails.values().flatten() {
+            let _ = DeleteObject(thumbnail.bitmap);
+        }
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+    }
+}
+
+fn point_from_lparam(lparam: LPARAM) -> POINT {
+    POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    }
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+
+        WM_DROPFILES => {
+            crate::shelf::add(crate::shelf::dropped_files(HDROP(wparam.0 as *mut std::ffi::c_void)));
+            refresh(hwnd);
+            LRESULT(0)
+        }
+
+        WM_SHELF_CHANGED => {
+            refresh(hwnd);
+            LRESULT(0)
+        }
+
+        WM_MOUSEMOVE => {
+            let point = point_from_lparam(lparam);
+            let hit = hit_test(hwnd, point);
+            let Some(state) = get_state_mut(hwnd) else {
+                return LRESULT(0);
+            };
+
+            if let Some((pressed, origin)) = state.pressed {
+                let dx = (point.x - origin.x).abs();
+                let dy = (point.y - origin.y).abs();
+                if dx > GetSystemMetrics(SM_CXDRAG) || dy > GetSystemMetrics(SM_CYDRAG) {
+                    state.pressed = None;
+                    let _ = ReleaseCapture();
+                    let paths = match pressed {
+                        Hit::Tile(i) => state.items.get(i).cloned().into_iter().collect(),
+                        Hit::DragAll => state.items.clone(),
+                        _ => Vec::new(),
+                    };
+                    start_drag(hwnd, paths);
+                }
+                return LRESULT(0);
+            }
+
+            if state.hover != hit {
+                state.hover = hit;
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+            let mut tme = windows::Win32::UI::Input::KeyboardAndMouse::TRACKMOUSEEVENT {
+                cbSize: std::mem::size_of::<windows::Win32::UI::Input::KeyboardAndMouse::TRACKMOUSEEVENT>() as u32,
+                dwFlags: windows::Win32::UI::Input::KeyboardAndMouse::TME_LEAVE,
+                hwndTrack: hwnd,
+                dwHoverTime: 0,
+            };
+            let _ = windows::Win32::UI::Input::KeyboardAndMouse::TrackMouseEvent(&mut tme);
+            LRESULT(0)
+        }
+
+        WM_MOUSELEAVE => {
+            if let Some(state) = get_state_mut(hwnd) {
+                if state.hover.take().is_some() {
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONDOWN => {
+            let point = point_from_lparam(lparam);
+            if let (Some(hit @ (Hit::Tile(_) | Hit::DragAll)), Some(state)) = (hit_test(hwnd, point), get_state_mut(hwnd)) {
+                state.pressed = Some((hit, point));
+                SetCapture(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            let point = point_from_lparam(lparam);
+            if let Some(state) = get_state_mut(hwnd) {
+                if state.pressed.take().is_some() {
+                    let _ = ReleaseCapture();
+                }
+            }
+            match hit_test(hwnd, point) {
+                Some(Hit::Clear) => {
+                    crate::shelf::clear();
+                    refresh(hwnd);
+                }
+                Some(Hit::Close) => hide_shelf(),
+                _ => {}
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONDBLCLK => {
+            if let Some(Hit::Tile(i)) = hit_test(hwnd, point_from_lparam(lparam)) {
+                if let Some(path) = get_state(hwnd).and_then(|s| s.items.get(i)) {
+                    open_path(path);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_RBUTTONUP => {
+            if let Some(Hit::Tile(i)) = hit_test(hwnd, point_from_lparam(lparam)) {
+                if let Some(path) = get_state(hwnd).and_then(|s| s.items.get(i).cloned()) {
+                    crate::shelf::remove(&[path]);
+                    refresh(hwnd);
+                }
+            }
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            free_state(hwnd);
+            let _ = SHELF_HWND.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
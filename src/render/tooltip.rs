@@ -0,0 +1,328 @@
+//! Hover tooltip for modules
+//!
+//! A small panel next to the hovered module with its tooltip text and, for
+//! modules that keep a history (CPU, GPU, network throughput), a sparkline of
+//! the last few minutes drawn with the same line graph helper as the bar. Lines
+//! written as "Key: value" line up in two columns, keys dimmed. It never takes
+//! focus and lets clicks through; hovering another module replaces it rather
+//! than stacking panels.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::chart::{Chart, Series};
+use super::drawing::{create_font, downsample_values, draw_text, measure_text, scale};
+use crate::config::BarPosition;
+use crate::modules::History;
+use crate::utils::Rect;
+use crate::window::state::get_window_state;
+
+const TOOLTIP_CLASS: &str = "TopBarTooltipClass";
+
+/// Sparkline size, before DPI scaling
+const SPARKLINE_HEIGHT: i32 = 28;
+const SPARKLINE_MIN_WIDTH: i32 = 160;
+
+/// The tooltip window, if shown
+static TOOLTIP: AtomicIsize = AtomicIsize::new(0);
+/// What the tooltip currently shows
+static CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+
+/// Longest text before ": " that still counts as a key
+const MAX_KEY_CHARS: usize = 24;
+
+struct Content {
+    module_id: String,
+    rows: Vec<Row>,
+    /// History scaled to 0-100
+    sparkline: Option<Vec<f32>>,
+}
+
+/// Show (or update) the tooltip for a module. `anchor` is the module's bounds in
+/// screen coordinates; the panel opens on the side of it away from the bar's
+/// screen `edge`.
+pub fn show_tooltip(module_id: &str, text: &str, history: Option<&History>, anchor: Rect, edge: BarPosition) {
+    *CONTENT.lock() = Some(Content {
+        module_id: module_id.to_string(),
+        rows: text.lines().map(Row::parse).collect(),
+        sparkline: history.and_then(sparkline_values),
+    });
+    if let Err(e) = unsafe { show(anchor, edge) } {
+        log::warn!("Failed to show tooltip: {}", e);
+    }
+}
+
+/// One line of tooltip text
+#[derive(Clone)]
+enum Row {
+    Text(String),
+    /// "Key: value", drawn in two columns
+    Pair(String, String),
+}
+
+impl Row {
+    fn parse(line: &str) -> Self {
+        match line.split_once(": ") {
+            Some((key, value)) if !key.trim().is_empty() && key.chars().count() <= MAX_KEY_CHARS => {
+                Row::Pair(key.trim().to_string(), value.trim().to_string())
+            }
+            _ => Row::Text(line.to_string()),
+        }
+    }
+}
+
+/// Hide the tooltip if shown
+pub fn hide_tooltip() {
+    let hwnd = HWND(TOOLTIP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if !hwnd.0.is_null() {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+    }
+    *CONTENT.lock() = None;
+}
+
+/// Module the tooltip is shown for
+pub fn tooltip_module() -> Option<String> {
+    if TOOLTIP.load(Ordering::SeqCst) == 0 {
+        return None;
+    }
+    CONTENT.lock().as_ref().map(|c| c.module_id.clone())
+}
+
+/// Scale a history to 0-100; needs at least two samples to draw a line
+fn sparkline_values(history: &History) -> Option<Vec<f32>> {
+    if history.values.len() < 2 {
+        return None;
+    }
+    let max = history
+        .max
+        .unwrap_or_else(|| history.values.iter().copied().fold(0.0, f32::max))
+        .max(1.0);
+    Some(history.values.iter().map(|v| v * 100.0 / max).collect())
+}
+
+unsafe fn show(anchor: Rect, edge: BarPosition) -> Result<()> {
+    let mut hwnd = HWND(TOOLTIP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(TOOLTIP_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_LAYERED | WS_EX_TRANSPARENT,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        TOOLTIP.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    let opaque = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            s.theme_manager.theme().forced_colors
+                || crate::accessibility::reduce_transparency(&s.config.appearance)
+        })
+        .unwrap_or(false);
+    let alpha = if opaque { 255 } else { 235 };
+    let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA);
+
+    position(hwnd, anchor, edge);
+    let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    let _ = InvalidateRect(hwnd, None, false);
+    Ok(())
+}
+
+/// Lay the text out from the top left of the panel, drawing it when `draw` is
+/// set; returns the size it needs and whether a sparkline follows
+unsafe fn layout(hdc: HDC, dpi: u32, draw: Option<&crate::theme::Theme>) -> (i32, i32, bool) {
+    let (rows, sparkline) = match CONTENT.lock().as_ref() {
+        Some(content) => (content.rows.clone(), content.sparkline.is_some()),
+        None => return (0, 0, false),
+    };
+    let font = create_font(&font_family(), scale(13, dpi), false);
+    let old_font = SelectObject(hdc, font);
+    let padding = scale(10, dpi);
+    let (_, line_height) = measure_text(hdc, "Ag");
+
+    // Values line up after the widest key
+    let key_width = rows
+        .iter()
+        .filter_map(|row| match row {
+            Row::Pair(key, _) => Some(measure_text(hdc, key).0),
+            Row::Text(_) => None,
+        })
+        .max()
+        .map(|w| w + scale(12, dpi))
+        .unwrap_or(0);
+
+    let mut width = 0;
+    let mut y = padding;
+    for row in &rows {
+        match row {
+            Row::Text(text) => {
+                if let Some(theme) = draw {
+                    SetTextColor(hdc, theme.text_primary.colorref());
+                    draw_text(hdc, padding, y, text);
+                }
+                width = width.max(measure_text(hdc, text).0);
+            }
+            Row::Pair(key, value) => {
+                if let Some(theme) = draw {
+                    SetTextColor(hdc, theme.text_secondary.colorref());
+                    draw_text(hdc, padding, y, key);
+                    SetTextColor(hdc, theme.text_primary.colorref());
+                    draw_text(hdc, padding + key_width, y, value);
+                }
+                width = width.max(key_width + measure_text(hdc, value).0);
+            }
+        }
+        y += line_height;
+    }
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(font);
+    (width + padding * 2, y + padding, sparkline)
+}
+
+/// Size the panel to its contents and place it next to the module, inside its monitor
+unsafe fn position(hwnd: HWND, anchor: Rect, edge: BarPosition) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let hdc = GetDC(hwnd);
+    let (mut width, mut height, sparkline) = layout(hdc, dpi, None);
+    ReleaseDC(hwnd, hdc);
+
+    if sparkline {
+        let padding = scale(10, dpi);
+        width = width.max(scale(SPARKLINE_MIN_WIDTH, dpi) + padding * 2);
+        height += scale(6, dpi) + scale(SPARKLINE_HEIGHT, dpi);
+    }
+
+    let center = POINT {
+        x: anchor.x + anchor.width / 2,
+        y: anchor.y + anchor.height / 2,
+    };
+    let monitor = MonitorFromPoint(center, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let screen = info.rcMonitor;
+
+    let gap = scale(6, dpi);
+    let x = (center.x - width / 2).clamp(screen.left, (screen.right - width).max(screen.left));
+    let y = (center.y - height / 2).clamp(screen.top, (screen.bottom - height).max(screen.top));
+    let (x, y) = match edge {
+        BarPosition::Top => (x, anchor.y + anchor.height + gap),
+        BarPosition::Bottom => (x, anchor.y - height - gap),
+        BarPosition::Left => (anchor.x + anchor.width + gap, y),
+        BarPosition::Right => (anchor.x - width - gap, y),
+    };
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(8, dpi), scale(8, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+
+    let mut rect = Default::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.border.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    SetBkMode(hdc, TRANSPARENT);
+    let (_, text_bottom, _) = layout(hdc, dpi, Some(&theme));
+    let sparkline = CONTENT.lock().as_ref().and_then(|c| c.sparkline.clone());
+
+    let padding = scale(10, dpi);
+    if let Some(values) = sparkline {
+        let graph = Rect::new(
+            padding,
+            text_bottom - padding + scale(6, dpi),
+            rect.right - padding * 2,
+            scale(SPARKLINE_HEIGHT, dpi),
+        );
+        let base = CreateSolidBrush(theme.background_secondary.colorref());
+        let base_rect = windows::Win32::Foundation::RECT {
+            left: graph.x,
+            top: graph.y,
+            right: graph.right(),
+            bottom: graph.y + graph.height,
+        };
+        FillRect(hdc, &base_rect, base);
+        let _ = DeleteObject(base);
+        // One point per two pixels keeps long histories readable
+        let values = downsample_values(values, (graph.width / 2).max(2) as usize);
+        let style = get_window_state()
+            .map(|s| s.read().config.appearance.graphs.clone())
+            .unwrap_or_default();
+        Chart::percent(vec![Series { values, color: theme.accent }], &style).draw(hdc, &graph, 0);
+    }
+
+    let _ = EndPaint(hwnd, &ps);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(TOOLTIP_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_DESTROY => {
+            TOOLTIP.store(0, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,387 @@
+//! Translation popup
+//!
+//! Shows the text being translated and, once the server answers, its translation
+//! with Copy and Replace buttons. Replace pastes the translation over the
+//! selection in the window the text came from. The popup takes focus so Esc
+//! (close), Enter (replace) and Ctrl+C (copy) work, and closes when it loses it.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicIsize, AtomicU64, Ordering};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, SetFocus, VK_CONTROL};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::drawing::{create_font, draw_text, measure_text, scale};
+use crate::translate::Translation;
+use crate::window::state::get_window_state;
+
+const POPUP_CLASS: &str = "TopBarTranslationClass";
+const WIDTH: i32 = 440;
+const HEIGHT: i32 = 260;
+const BUTTON_WIDTH: i32 = 100;
+const BUTTON_HEIGHT: i32 = 32;
+const PADDING: i32 = 16;
+/// Posted by the translating thread when it's done
+const WM_TRANSLATED: u32 = WM_APP + 21;
+
+/// What the popup shows
+struct Content {
+    source: String,
+    /// None while the request is out
+    result: Option<Result<Translation, String>>,
+    /// Window the text came from, for Replace
+    origin: isize,
+}
+
+/// The popup window, if shown
+static POPUP: AtomicIsize = AtomicIsize::new(0);
+static CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+/// Bumped per request, so a slow answer doesn't replace a newer one
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Translate `text` in the background and show the popup; `origin` is the window
+/// the text was taken from and `target` the language (the configured one when empty)
+pub fn show_translation(text: String, origin: HWND, target: &str) -> Result<()> {
+    let config = get_window_state()
+        .map(|s| s.read().config.translate.clone())
+        .unwrap_or_default();
+    *CONTENT.lock() = Some(Content {
+        source: text.clone(),
+        result: None,
+        origin: origin.0 as isize,
+    });
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let target = target.to_string();
+    std::thread::spawn(move || {
+        let result = crate::translate::translate(&config, &text, &target);
+        if let Err(e) = &result {
+            log::warn!("Translation failed: {}", e);
+        }
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Some(content) = CONTENT.lock().as_mut() {
+            content.result = Some(result);
+        }
+        let hwnd = HWND(POPUP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+        if !hwnd.0.is_null() {
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_TRANSLATED, WPARAM(0), LPARAM(0));
+            }
+        }
+    });
+
+    unsafe { show() }
+}
+
+unsafe fn show() -> Result<()> {
+    let mut hwnd = HWND(POPUP.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if hwnd.0.is_null() || !IsWindow(hwnd).as_bool() {
+        register_class()?;
+        let class = to_wide(POPUP_CLASS);
+        let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+        hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            PCWSTR(class.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0, 0, 0, 0,
+            None,
+            None,
+            hinstance,
+            None,
+        )?;
+        POPUP.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    position(hwnd);
+    let _ = ShowWindow(hwnd, SW_SHOW);
+    let _ = SetForegroundWindow(hwnd);
+    let _ = SetFocus(hwnd);
+    let _ = InvalidateRect(hwnd, None, false);
+    crate::sounds::play_event(crate::sounds::SoundEvent::PopupOpen);
+    Ok(())
+}
+
+/// Center the popup near the top of the foreground window's monitor
+unsafe fn position(hwnd: HWND) {
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let width = scale(WIDTH, dpi);
+    let height = scale(HEIGHT, dpi);
+
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(monitor, &mut info);
+    let area = info.rcWork;
+    let x = area.left + (area.right - area.left - width) / 2;
+    let y = area.top + scale(48, dpi);
+
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_NOACTIVATE);
+    let region = CreateRoundRectRgn(0, 0, width + 1, height + 1, scale(16, dpi), scale(16, dpi));
+    SetWindowRgn(hwnd, region, true);
+}
+
+/// Copy and Replace button rectangles
+fn button_rects(client: &RECT, dpi: u32) -> (RECT, RECT) {
+    let width = scale(BUTTON_WIDTH, dpi);
+    let height = scale(BUTTON_HEIGHT, dpi);
+    let padding = scale(PADDING, dpi);
+    let bottom = client.bottom - padding;
+    let replace = RECT {
+        left: client.right - padding - width,
+        top: bottom - height,
+        right: client.right - padding,
+        bottom,
+    };
+    let copy = RECT {
+        left: replace.left - scale(8, dpi) - width,
+        top: bottom - height,
+        right: replace.left - scale(8, dpi),
+        bottom,
+    };
+    (copy, replace)
+}
+
+fn font_family() -> String {
+    get_window_state()
+        .map(|s| s.read().config.appearance.font_family.clone())
+        .unwrap_or_else(|| "Segoe UI".to_string())
+}
+
+/// Draw wrapped text in a box, with an ellipsis when it doesn't fit
+unsafe fn draw_wrapped(hdc: HDC, text: &str, rect: &RECT) {
+    let mut wide: Vec<u16> = text.encode_utf16().collect();
+    let mut rect = *rect;
+    DrawTextW(hdc, &mut wide, &mut rect, DT_WORDBREAK | DT_EDITCONTROL | DT_END_ELLIPSIS | DT_NOPREFIX);
+}
+
+unsafe fn paint(hwnd: HWND) {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    let dpi = GetDpiForWindow(hwnd).max(96);
+    let theme = get_window_state()
+        .map(|s| s.read().theme_manager.theme().clone())
+        .unwrap_or_else(crate::theme::Theme::dark);
+    let (source, result) = CONTENT
+        .lock()
+        .as_ref()
+        .map(|c| (c.source.clone(), c.result.clone()))
+        .unwrap_or_default();
+
+    let mut rect = RECT::default();
+    let _ = GetClientRect(hwnd, &mut rect);
+    let brush = CreateSolidBrush(theme.background.colorref());
+    FillRect(hdc, &rect, brush);
+    let _ = DeleteObject(brush);
+    let border = CreateSolidBrush(theme.accent.colorref());
+    FrameRect(hdc, &rect, border);
+    let _ = DeleteObject(border);
+
+    SetBkMode(hdc, TRANSPARENT);
+    let padding = scale(PADDING, dpi);
+    let family = font_family();
+    let (copy, replace) = button_rects(&rect, dpi);
+
+    // Heading: languages, or progress
+    let small_font = create_font(&family, scale(12, dpi), false);
+    let old_font = SelectObject(hdc, small_font);
+    SetTextColor(hdc, theme.text_secondary.colorref());
+    let heading = match &result {
+        None => "Translating...".to_string(),
+        Some(Ok(t)) => match &t.detected {
+            Some(from) => format!("Translated {} → {}", from, t.target),
+            None => format!("Translated into {}", t.target),
+        },
+        Some(Err(_)) => "Translation failed".to_string(),
+    };
+    let (_, line_height) = measure_text(hdc, &heading);
+    draw_text(hdc, padding, padding, &heading);
+
+    // The original, a couple of lines at most
+    let source_top = padding + line_height + scale(6, dpi);
+    let source_rect = RECT {
+        left: padding,
+        top: source_top,
+        right: rect.right - padding,
+        bottom: source_top + line_height * 2,
+    };
+    draw_wrapped(hdc, &source, &source_rect);
+
+    // The translation (or the error)
+    let text_font = create_font(&family, scale(16, dpi), false);
+    SelectObject(hdc, text_font);
+    let body = match &result {
+        None => String::new(),
+        Some(Ok(t)) => t.text.clone(),
+        Some(Err(e)) => e.clone(),
+    };
+    SetTextColor(hdc, theme.text_primary.colorref());
+    let body_rect = RECT {
+        left: padding,
+        top: source_rect.bottom + scale(10, dpi),
+        right: rect.right - padding,
+        bottom: copy.top - scale(8, dpi),
+    };
+    draw_wrapped(hdc, &body, &body_rect);
+
+    // Buttons, once there's something to act on
+    if matches!(result, Some(Ok(_))) {
+        SelectObject(hdc, small_font);
+        for (button, text, primary) in [(copy, "Copy", false), (replace, "Replace", true)] {
+            let fill = if primary { theme.accent } else { theme.background_secondary };
+            let brush = CreateSolidBrush(fill.colorref());
+            let region = CreateRoundRectRgn(button.left, button.top, button.right, button.bottom, scale(8, dpi), scale(8, dpi));
+            let _ = FillRgn(hdc, region, brush);
+            let _ = DeleteObject(region);
+            let _ = DeleteObject(brush);
+
+            SetTextColor(hdc, if primary { COLORREF(0x00FFFFFF) } else { theme.text_primary.colorref() });
+            let (w, h) = measure_text(hdc, text);
+            draw_text(
+                hdc,
+                button.left + (button.right - button.left - w) / 2,
+                button.top + (button.bottom - button.top - h) / 2,
+                text,
+            );
+        }
+    }
+
+    SelectObject(hdc, old_font);
+    let _ = DeleteObject(small_font);
+    let _ = DeleteObject(text_font);
+    let _ = EndPaint(hwnd, &ps);
+}
+
+/// The finished translation, if there is one
+fn translated_text() -> Option<String> {
+    match CONTENT.lock().as_ref()?.result.as_ref()? {
+        Ok(t) => Some(t.text.clone()),
+        Err(_) => None,
+    }
+}
+
+/// Put the translation on the clipboard
+fn copy() -> bool {
+    let Some(text) = translated_text() else {
+        return false;
+    };
+    match arboard::Clipboard::new().and_then(|mut cb| cb.set_text(text)) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Failed to copy translation: {}", e);
+            false
+        }
+    }
+}
+
+/// Paste the translation over the selection it came from
+unsafe fn replace(hwnd: HWND) {
+    let origin = CONTENT.lock().as_ref().map(|c| c.origin).unwrap_or(0);
+    if !copy() {
+        return;
+    }
+    close(hwnd);
+    let origin = HWND(origin as *mut std::ffi::c_void);
+    if !origin.0.is_null() {
+        let _ = SetForegroundWindow(origin);
+        // Let the focus settle before pasting
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        crate::utils::send_ctrl_key(b'V');
+    }
+}
+
+fn close(hwnd: HWND) {
+    // Destroying re-enters through WM_KILLFOCUS; only the first call counts
+    if POPUP.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return;
+    }
+    *CONTENT.lock() = None;
+    unsafe {
+        let _ = DestroyWindow(hwnd);
+    }
+    crate::sounds::play_event(crate::sounds::SoundEvent::PopupClose);
+}
+
+unsafe fn register_class() -> Result<()> {
+    let class_name = to_wide(POPUP_CLASS);
+    let hinstance = windows::Win32::System::LibraryLoader::GetModuleHandleW(None)?;
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_DROPSHADOW,
+        lpfnWndProc: Some(wnd_proc),
+        hInstance: hinstance.into(),
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        hCursor: LoadCursorW(None, IDC_ARROW)?,
+        hbrBackground: HBRUSH::default(),
+        ..Default::default()
+    };
+    let _ = RegisterClassExW(&wc);
+    Ok(())
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+
+        WM_ERASEBKGND => LRESULT(1),
+
+        WM_TRANSLATED => {
+            let _ = InvalidateRect(hwnd, None, false);
+            LRESULT(0)
+        }
+
+        WM_KEYDOWN => {
+            match wparam.0 as u32 {
+                0x1B => close(hwnd), // ESC
+                0x0D => replace(hwnd), // ENTER
+                0x43 if GetKeyState(VK_CONTROL.0 as i32) < 0 && copy() => close(hwnd), // Ctrl+C
+                _ => {}
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONUP => {
+            let point = POINT {
+                x: (lparam.0 & 0xFFFF) as i16 as i32,
+                y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+            };
+            let mut rect = RECT::default();
+            let _ = GetClientRect(hwnd, &mut rect);
+            let (copy_button, replace_button) = button_rects(&rect, GetDpiForWindow(hwnd).max(96));
+            if PtInRect(&copy_button, point).as_bool() {
+                if copy() {
+                    close(hwnd);
+                }
+            } else if PtInRect(&replace_button, point).as_bool() {
+                replace(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_KILLFOCUS => {
+            close(hwnd);
+            LRESULT(0)
+        }
+
+        WM_DESTROY => {
+            let _ = POPUP.compare_exchange(hwnd.0 as isize, 0, Ordering::SeqCst, Ordering::SeqCst);
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,120 @@
+//! Shared runtime for background fetches
+//!
+//! Network modules used to start a thread for every fetch. They hand their
+//! work to one tokio runtime instead, owned by `Application`: blocking calls
+//! (ureq) run on its blocking pool, which reuses a handful of threads. A job
+//! that finishes posts `WM_TOPBAR_UPDATE` to the bar, so its result is drawn
+//! right away rather than on the next update tick.
+//!
+//! `spawn` runs a job that stores its own result; `fetch` returns a `Task`
+//! whose result comes back over a channel for the module to `poll` in its
+//! update. Before the runtime starts (or if it couldn't be built) jobs get a
+//! thread of their own, as before.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+use anyhow::Result;
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::PostMessageW;
+
+/// Threads kept for blocking jobs; fetches beyond this wait their turn
+const BLOCKING_THREADS: usize = 8;
+
+/// The running runtime's handle, for spawning from anywhere
+static HANDLE: OnceCell<tokio::runtime::Handle> = OnceCell::new();
+/// The bar window told about finished jobs
+static BAR: AtomicIsize = AtomicIsize::new(0);
+
+/// The runtime itself, owned by `Application`; shut down when dropped
+pub struct Runtime {
+    inner: Option<tokio::runtime::Runtime>,
+}
+
+impl Runtime {
+    /// Build the runtime and make it the one `spawn` and `fetch` use
+    pub fn start() -> Result<Self> {
+        let inner = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(BLOCKING_THREADS)
+            .thread_name("topbar-fetch")
+            .build()?;
+        let _ = HANDLE.set(inner.handle().clone());
+        info!("Background runtime started");
+        Ok(Self { inner: Some(inner) })
+    }
+
+    /// Post `WM_TOPBAR_UPDATE` to `hwnd` whenever a job finishes
+    pub fn notify(&self, hwnd: HWND) {
+        BAR.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        BAR.store(0, Ordering::SeqCst);
+        // Fetches still waiting on the network are abandoned rather than awaited
+        if let Some(inner) = self.inner.take() {
+            inner.shutdown_timeout(Duration::from_secs(1));
+        }
+    }
+}
+
+/// Result of a `fetch` on its way back
+pub struct Task<T> {
+    receiver: Mutex<Receiver<T>>,
+}
+
+impl<T> Task<T> {
+    /// `None` while the job runs; then its result once, or an error if the job
+    /// died without one
+    pub fn poll(&self) -> Option<Result<T, String>> {
+        match self.receiver.lock().try_recv() {
+            Ok(value) => Some(Ok(value)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("Background job failed".to_string())),
+        }
+    }
+}
+
+/// Run a blocking job in the background and get its result back through a `Task`
+pub fn fetch<T: Send + 'static>(job: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+    let (sender, receiver) = mpsc::channel();
+    spawn(move || {
+        let _ = sender.send(job());
+    });
+    Task {
+        receiver: Mutex::new(receiver),
+    }
+}
+
+/// Run a blocking job in the background; the bar repaints when it's done
+pub fn spawn(job: impl FnOnce() + Send + 'static) {
+    let job = move || {
+        job();
+        notify_bar();
+    };
+    match HANDLE.get() {
+        Some(handle) => {
+            handle.spawn_blocking(job);
+        }
+        None => {
+            if let Err(e) = std::thread::Builder::new().spawn(job) {
+                warn!("Failed to start background job: {}", e);
+            }
+        }
+    }
+}
+
+fn notify_bar() {
+    let hwnd = HWND(BAR.load(Ordering::SeqCst) as *mut std::ffi::c_void);
+    if !hwnd.0.is_null() {
+        unsafe {
+            let _ = PostMessageW(hwnd, crate::window::WM_TOPBAR_UPDATE, WPARAM(0), LPARAM(0));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Built-in task scheduler
+//!
+//! Runs the `[[scheduler.tasks]]` actions at a daily time or on an interval. The
+//! main window's one-second timer calls [`tick`], so actions run on the UI thread
+//! like any other menu command. Missed runs (bar not running) are not caught up.
+
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use log::warn;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use windows::Win32::Foundation::HWND;
+
+use crate::config::{Config, ScheduledTask};
+
+/// A daily task still runs if the bar was busy and the tick came late by up to this much
+const DAILY_GRACE_SECONDS: i64 = 90;
+
+struct Scheduler {
+    started: DateTime<Local>,
+    last_run: HashMap<String, DateTime<Local>>,
+}
+
+static SCHEDULER: OnceCell<Mutex<Scheduler>> = OnceCell::new();
+
+fn scheduler() -> &'static Mutex<Scheduler> {
+    SCHEDULER.get_or_init(|| {
+        Mutex::new(Scheduler {
+            started: Local::now(),
+            last_run: HashMap::new(),
+        })
+    })
+}
+
+/// Key identifying a task across config reloads
+fn task_key(task: &ScheduledTask) -> String {
+    format!("{}\u{1f}{}", task.name, task.action)
+}
+
+fn parse_time(at: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(at.trim(), "%H:%M").ok()
+}
+
+/// When the task should next run, or None if it has no valid schedule
+pub fn next_run(task: &ScheduledTask) -> Option<DateTime<Local>> {
+    let state = scheduler().lock();
+    let last = state.last_run.get(&task_key(task)).copied();
+    next_run_after(task, last, state.started, Local::now())
+}
+
+fn next_run_after(
+    task: &ScheduledTask,
+    last: Option<DateTime<Local>>,
+    started: DateTime<Local>,
+    now: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    if let Some(minutes) = task.every_minutes.filter(|m| *m > 0) {
+        return Some(last.unwrap_or(started) + Duration::minutes(minutes as i64));
+    }
+
+    let time = task.at.as_deref().and_then(parse_time)?;
+    let today = now.date_naive().and_time(time).and_local_timezone(Local).single()?;
+    let ran_today = last.map(|l| l.date_naive() == now.date_naive()).unwrap_or(false);
+    let missed = (now - today).num_seconds() > DAILY_GRACE_SECONDS;
+    if ran_today || missed {
+        Some(today + Duration::days(1))
+    } else {
+        Some(today)
+    }
+}
+
+/// When the task last ran during this session
+pub fn last_run(task: &ScheduledTask) -> Option<DateTime<Local>> {
+    scheduler().lock().last_run.get(&task_key(task)).copied()
+}
+
+/// Run every enabled task that is due
+pub fn tick(hwnd: HWND, config: &Config) {
+    let now = Local::now();
+    let due: Vec<&ScheduledTask> = {
+        let state = scheduler().lock();
+        config
+            .scheduler
+            .tasks
+            .iter()
+            .filter(|t| t.enabled)
+            .filter(|t| {
+                let last = state.last_run.get(&task_key(t)).copied();
+                next_run_after(t, last, state.started, now)
+                    .map(|next| next <= now)
+                    .unwrap_or(false)
+            })
+            .collect()
+    };
+
+    for task in due {
+        run_now(hwnd, task);
+    }
+}
+
+/// Run a task immediately and record the run
+pub fn run_now(hwnd: HWND, task: &ScheduledTask) {
+    // Record first so a failing task isn't retried every tick
+    scheduler().lock().last_run.insert(task_key(task), Local::now());
+    if let Err(e) = crate::actions::execute(hwnd, &task.action) {
+        warn!("Scheduled task '{}' failed: {}", task.name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn task(at: Option<&str>, every_minutes: Option<u64>) -> ScheduledTask {
+        ScheduledTask {
+            name: "Test".to_string(),
+            action: "refresh:weather".to_string(),
+            at: at.map(str::to_string),
+            every_minutes,
+            enabled: true,
+        }
+    }
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 6, 10, hour, minute, second).single().unwrap()
+    }
+
+    #[test]
+    fn interval_counts_from_last_run_or_start() {
+        let every = task(None, Some(30));
+        let started = at(8, 0, 0);
+        assert_eq!(next_run_after(&every, None, started, at(8, 5, 0)), Some(at(8, 30, 0)));
+        assert_eq!(next_run_after(&every, Some(at(9, 10, 0)), started, at(9, 15, 0)), Some(at(9, 40, 0)));
+    }
+
+    #[test]
+    fn daily_runs_today_until_missed() {
+        let daily = task(Some("09:30"), None);
+        let started = at(8, 0, 0);
+        assert_eq!(next_run_after(&daily, None, started, at(9, 0, 0)), Some(at(9, 30, 0)));
+        // A late tick within the grace period still runs today
+        assert_eq!(next_run_after(&daily, None, started, at(9, 31, 0)), Some(at(9, 30, 0)));
+        let tomorrow = at(9, 30, 0) + Duration::days(1);
+        assert_eq!(next_run_after(&daily, None, started, at(9, 35, 0)), Some(tomorrow));
+        assert_eq!(next_run_after(&daily, Some(at(9, 30, 1)), started, at(9, 30, 5)), Some(tomorrow));
+    }
+
+    #[test]
+    fn no_schedule_without_a_valid_time() {
+        let started = at(8, 0, 0);
+        assert_eq!(next_run_after(&task(None, None), None, started, started), None);
+        assert_eq!(next_run_after(&task(Some("25:00"), None), None, started, started), None);
+        // A zero interval falls back to the daily time
+        assert_eq!(next_run_after(&task(Some("12:00"), Some(0)), None, started, started), Some(at(12, 0, 0)));
+    }
+}
@@ -2,19 +2,63 @@
 //!
 //! Uses `walkdir` to collect file paths and `fst` to build a compact, fast
 //! prefix-searchable set.
+//!
+//! The index is saved to disk so search works immediately on the next start, and
+//! kept current with `ReadDirectoryChangesW` (through `notify`) instead of full
+//! rescans. A full rescan only runs when the saved index is missing, was built for
+//! different paths, or is older than `search.rescan_hours`.
+//!
+//! Queries are matched fuzzily against file names (fzf-style scoring, so "vsc" finds
+//! "Visual Studio Code"), with boosts for apps and for results opened recently.
+//!
+//! Installed apps (Start Menu shortcuts and store packages) are listed separately in
+//! `app_index` and searched ahead of files; text file contents are in `content_index`.
 
 use anyhow::Result;
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use walkdir::WalkDir;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use once_cell::sync::OnceCell;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
 
+use crate::config::SearchConfig;
+
 static GLOBAL_INDEX: OnceCell<Arc<RwLock<Option<SearchIndex>>>> = OnceCell::new();
 static SCANNED_COUNT: AtomicUsize = AtomicUsize::new(0);
 static IS_BUILDING: AtomicBool = AtomicBool::new(false);
 static ESTIMATED_TOTAL: AtomicUsize = AtomicUsize::new(0);
+/// File system watcher for the indexed roots (dropping it stops watching)
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+/// Set when the watcher changed the index since it was last saved
+static DIRTY: AtomicBool = AtomicBool::new(false);
+/// Opened results: full path -> (times opened, last opened as Unix seconds)
+static HISTORY: OnceCell<Mutex<HashMap<String, (u32, u64)>>> = OnceCell::new();
+
+/// Only common application files and shortcuts are indexed
+const MAX_ENTRIES: usize = 10000;
+const MAX_DEPTH: usize = 6;
+const ALLOWED_EXTS: [&str; 12] = ["exe", "lnk", "bat", "cmd", "msi", "com", "ps1", "txt", "pdf", "json", "xml", "zip"];
+
+/// First line of the saved index; bump the version when the format changes
+const CACHE_HEADER: &str = "topbar-search-index 1";
+/// How often watcher changes are written back to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Opened results remembered for ranking
+const MAX_HISTORY: usize = 500;
+
+// Fuzzy scoring weights, after fzf
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP_START: i32 = -3;
+const PENALTY_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
 
 /// Set the global index
 pub fn set_global_index(idx: Arc<RwLock<Option<SearchIndex>>>) {
@@ -45,8 +89,79 @@ pub fn estimated_total() -> usize {
     ESTIMATED_TOTAL.load(Ordering::Relaxed)
 }
 
-fn meta_path() -> PathBuf {
-    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("search_index_count.txt")
+fn cache_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("search_index.txt")
+}
+
+fn history_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("search_history.json")
+}
+
+fn history() -> &'static Mutex<HashMap<String, (u32, u64)>> {
+    HISTORY.get_or_init(|| {
+        let saved = std::fs::read_to_string(history_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Mutex::new(saved)
+    })
+}
+
+/// Remember that a result was opened, so it ranks higher next time
+pub fn record_use(path: &str) {
+    let mut history = history().lock();
+    let entry = history.entry(path.to_string()).or_insert((0, 0));
+    entry.0 = entry.0.saturating_add(1);
+    entry.1 = now_secs();
+
+    // Forget the least recently used results once the list gets long
+    if history.len() > MAX_HISTORY {
+        let mut by_age: Vec<(String, u64)> = history.iter().map(|(p, (_, t))| (p.clone(), *t)).collect();
+        by_age.sort_by_key(|(_, t)| *t);
+        for (p, _) in by_age.into_iter().take(history.len() - MAX_HISTORY) {
+            history.remove(&p);
+        }
+    }
+
+    let result = serde_json::to_string(&*history)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            let path = history_path();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, json)?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        log::warn!("Failed to save search history: {}", e);
+    }
+}
+
+/// Ranking boost for results opened before; frequent and recent uses count most
+pub(crate) fn history_boost(path: &str) -> f32 {
+    let history = history().lock();
+    let Some((count, last)) = history.get(path) else {
+        return 0.0;
+    };
+    let age = now_secs().saturating_sub(*last);
+    let recency = if age < 86_400 {
+        1.0
+    } else if age < 7 * 86_400 {
+        0.6
+    } else {
+        0.3
+    };
+    (*count).min(10) as f32 * 8.0 * recency
+}
+
+fn is_app_directory(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.contains("\\program files\\") || lower.contains("\\program files (x86)\\") || lower.contains("\\start menu\\")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
 }
 
 /// A simple in-memory search index built from filenames -> full paths.
@@ -56,10 +171,32 @@ pub struct SearchIndex {
     entries: Vec<(String, String, String)>,
     /// Map-like set of paths that are from app/program directories (Start Menu, Program Files, etc.)
     app_paths: std::collections::HashSet<String>,
+    /// Roots and exclusions the index was built for (saved with it, and used for watcher updates)
+    roots: Vec<PathBuf>,
+    exclude_patterns: Vec<String>,
+    exclude_globs: Vec<glob::Pattern>,
+    /// When the last full scan ran (seconds since the Unix epoch)
+    built_at: u64,
 }  
 
 impl SearchIndex {
+    fn empty(roots: &[PathBuf], exclude_patterns: &[String]) -> Self {
+        Self {
+            entries: Vec::new(),
+            app_paths: std::collections::HashSet::new(),
+            roots: roots.to_vec(),
+            exclude_patterns: exclude_patterns.to_vec(),
+            // Compile glob patterns for exclusion
+            exclude_globs: exclude_patterns
+                .iter()
+                .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+                .collect(),
+            built_at: now_secs(),
+        }
+    }
+
     /// Build an index from the provided roots (walks recursively).
+    #[cfg(test)]
     pub fn build(roots: &[PathBuf]) -> Result<Self> {
         Self::build_with_excludes(roots, &[])
     }
@@ -67,23 +204,7 @@ impl SearchIndex {
     /// Build an index with exclusion patterns
     pub fn build_with_excludes(roots: &[PathBuf], exclude_patterns: &[String]) -> Result<Self> {
         // Minimal, fast index: only include common application files and shortcuts
-        const MAX_ENTRIES: usize = 10000;
-        const MAX_DEPTH: usize = 6;
-        let allowed_exts = ["exe", "lnk", "bat", "cmd", "msi", "com", "ps1", "txt", "pdf", "json", "xml", "zip"];
-
-        // Compile glob patterns for exclusion
-        let exclude_globs: Vec<glob::Pattern> = exclude_patterns
-            .iter()
-            .filter_map(|pattern| glob::Pattern::new(pattern).ok())
-            .collect();
-
-        let mut entries: Vec<(String, String, String)> = Vec::new();
-        let mut app_paths = std::collections::HashSet::new();
-
-        let is_app_directory = |path: &str| -> bool {
-            let lower = path.to_lowercase();
-            lower.contains("\\program files\\") || lower.contains("\\program files (x86)\\") || lower.contains("\\start menu\\")
-        };
+        let mut index = Self::empty(roots, exclude_patterns);
 
         SCANNED_COUNT.store(0, Ordering::Relaxed);
         IS_BUILDING.store(true, Ordering::Relaxed);
@@ -96,41 +217,142 @@ impl SearchIndex {
                 let path_str = entry.path().to_string_lossy();
 
                 // Check exclusions
-                if exclude_globs.iter().any(|p| p.matches(&path_str)) {
+                if index.is_excluded(&path_str) {
                     continue;
                 }
 
                 if entry.file_type().is_file() {
                     SCANNED_COUNT.fetch_add(1, Ordering::Relaxed);
 
-                    let full = entry.path().to_string_lossy().to_string();
-                    let filename = entry.file_name().to_string_lossy().to_lowercase();
-                    if let Some(ext_os) = entry.path().extension() {
-                        if let Some(ext) = ext_os.to_str() {
-                            let e = ext.to_lowercase();
-                            if allowed_exts.contains(&e.as_str()) {
-                                let full_lower = full.to_lowercase();
-                                entries.push((filename.clone(), full_lower, full.clone()));
-                                if is_app_directory(&full) {
-                                    app_paths.insert(full.clone());
-                                }
-                                if entries.len() >= MAX_ENTRIES {
-                                    log::info!("Reached max entries ({}), stopping early", MAX_ENTRIES);
-                                    break;
-                                }
-                            }
-                        }
+                    index.push_path(entry.path().to_string_lossy().to_string());
+                    if index.entries.len() >= MAX_ENTRIES {
+                        log::info!("Reached max entries ({}), stopping early", MAX_ENTRIES);
+                        break;
                     }
                 }
             }
 
-            if entries.len() >= MAX_ENTRIES { break; }
+            if index.entries.len() >= MAX_ENTRIES { break; }
         }
 
         IS_BUILDING.store(false, Ordering::Relaxed);
-        log::info!("Minimal search index built with {} entries", entries.len());
+        log::info!("Minimal search index built with {} entries", index.entries.len());
+
+        Ok(index)
+    }
 
-        Ok(Self { entries, app_paths })
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude_globs.iter().any(|p| p.matches(path))
+    }
+
+    /// Add a file if it has one of the indexed extensions; returns true if added
+    fn push_path(&mut self, full: String) -> bool {
+        let Some(ext) = Path::new(&full).extension().and_then(|e| e.to_str()) else {
+            return false;
+        };
+        if !ALLOWED_EXTS.contains(&ext.to_lowercase().as_str()) {
+            return false;
+        }
+        let filename = Path::new(&full)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if is_app_directory(&full) {
+            self.app_paths.insert(full.clone());
+        }
+        self.entries.push((filename, full.to_lowercase(), full));
+        true
+    }
+
+    /// Add a file reported by the watcher, applying the same rules as a full scan
+    fn insert_path(&mut self, path: &Path) -> bool {
+        if self.entries.len() >= MAX_ENTRIES || !path.is_file() {
+            return false;
+        }
+        let full = path.to_string_lossy().to_string();
+        let within_depth = self.roots.iter().any(|root| {
+            path.strip_prefix(root)
+                .map(|rel| rel.components().count() <= MAX_DEPTH)
+                .unwrap_or(false)
+        });
+        if !within_depth || self.is_excluded(&full) {
+            return false;
+        }
+        let lower = full.to_lowercase();
+        if self.entries.iter().any(|(_, path_lower, _)| *path_lower == lower) {
+            return false;
+        }
+        self.push_path(full)
+    }
+
+    /// Remove a file, or everything under a directory; returns true if anything was removed
+    fn remove_path(&mut self, path: &Path) -> bool {
+        let lower = path.to_string_lossy().to_lowercase();
+        let dir_prefix = format!("{}\\", lower.trim_end_matches('\\'));
+        let before = self.entries.len();
+        self.entries
+            .retain(|(_, path_lower, _)| *path_lower != lower && !path_lower.starts_with(&dir_prefix));
+        if self.entries.len() == before {
+            return false;
+        }
+        let entries = &self.entries;
+        self.app_paths
+            .retain(|p| entries.iter().any(|(_, _, full)| full == p));
+        true
+    }
+
+    /// Whether the index was built for these roots and exclusions
+    fn matches_config(&self, roots: &[PathBuf], exclude_patterns: &[String]) -> bool {
+        self.roots == roots && self.exclude_patterns == exclude_patterns
+    }
+
+    /// Time since the last full scan
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now_secs().saturating_sub(self.built_at))
+    }
+
+    /// Write the index to disk (one path per line after a small header)
+    pub fn save(&self) -> Result<()> {
+        use std::io::Write;
+
+        let path = cache_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        writeln!(out, "{}", CACHE_HEADER)?;
+        writeln!(out, "{}", self.built_at)?;
+        writeln!(out, "{}", serde_json::to_string(&(&self.roots, &self.exclude_patterns))?)?;
+        for (_, _, full) in &self.entries {
+            writeln!(out, "{}", full)?;
+        }
+        out.flush()?;
+        log::info!("Saved search index ({} entries) to {}", self.entries.len(), path.display());
+        Ok(())
+    }
+
+    /// Load the saved index if it was built for the same roots and exclusions
+    pub fn load_cached(roots: &[PathBuf], exclude_patterns: &[String]) -> Option<Self> {
+        let content = std::fs::read_to_string(cache_path()).ok()?;
+        let mut lines = content.lines();
+        if lines.next()? != CACHE_HEADER {
+            return None;
+        }
+        let built_at: u64 = lines.next()?.parse().ok()?;
+        let (saved_roots, saved_excludes): (Vec<PathBuf>, Vec<String>) =
+            serde_json::from_str(lines.next()?).ok()?;
+
+        let mut index = Self::empty(&saved_roots, &saved_excludes);
+        if !index.matches_config(roots, exclude_patterns) {
+            log::info!("Saved search index was built for different paths, ignoring it");
+            return None;
+        }
+        index.built_at = built_at;
+        for line in lines.filter(|l| !l.is_empty()) {
+            index.push_path(line.to_string());
+        }
+        ESTIMATED_TOTAL.store(index.entries.len(), Ordering::Relaxed);
+        Some(index)
     }
 
     /// Return the number of indexed entries
@@ -143,9 +365,9 @@ impl SearchIndex {
         let q = prefix.to_lowercase();
         let mut best: Vec<(f32, String)> = Vec::new();
 
-        for (filename, _path_lower, full) in &self.entries {
+        for (filename, path_lower, full) in &self.entries {
             if filename.starts_with(&q) {
-                let score = calculate_relevance_score(filename, full, &q, &self.app_paths);
+                let score = self.rank(filename, path_lower, full, &q).unwrap_or(0.0);
                 if best.len() < limit {
                     best.push((score, full.clone()));
                 } else {
@@ -173,33 +395,38 @@ impl SearchIndex {
         best.into_iter().map(|(_, path)| path).collect()
     }
 
-    /// Simple contains-based search (case-insensitive) that matches query anywhere in filename or path
+    /// Fuzzy search (case-insensitive) over file names, falling back to substring matches in the path
+    ///
+    /// Results are ranked by match quality, then boosted for apps and for results opened recently.
     pub fn search_query(&self, query: &str, limit: usize) -> Vec<String> {
-        let q = query.to_lowercase();
+        let q: String = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+        if q.is_empty() {
+            return Vec::new();
+        }
 
         // Maintain a small bounded collection of best candidates to avoid allocating and sorting huge result sets
         let mut best: Vec<(f32, String)> = Vec::new();
 
         for (filename, path_lower, full) in &self.entries {
-            if filename.contains(&q) || filename.split('.').next().unwrap_or("").contains(&q) || path_lower.contains(&q) {
-                let score = calculate_relevance_score(filename, full, &q, &self.app_paths);
-
-                if best.len() < limit {
-                    best.push((score, full.clone()));
-                } else {
-                    // find smallest score in current best and replace if this is better
-                    let mut min_idx = 0usize;
-                    let mut min_score = best[0].0;
-                    for i in 1..best.len() {
-                        if best[i].0 < min_score {
-                            min_score = best[i].0;
-                            min_idx = i;
-                        }
-                    }
-                    if score > min_score {
-                        best[min_idx] = (score, full.clone());
+            let Some(score) = self.rank(filename, path_lower, full, &q) else {
+                continue;
+            };
+
+            if best.len() < limit {
+                best.push((score, full.clone()));
+            } else {
+                // find smallest score in current best and replace if this is better
+                let mut min_idx = 0usize;
+                let mut min_score = best[0].0;
+                for i in 1..best.len() {
+                    if best[i].0 < min_score {
+                        min_score = best[i].0;
+                        min_idx = i;
                     }
                 }
+                if score > min_score {
+                    best[min_idx] = (score, full.clone());
+                }
             }
         }
 
@@ -212,6 +439,32 @@ impl SearchIndex {
         best.into_iter().map(|(_, path)| path).collect()
     }
 
+    /// Score one entry for a (lowercase, whitespace-free) query; None if it doesn't match
+    fn rank(&self, filename: &str, path_lower: &str, full: &str, query: &str) -> Option<f32> {
+        // Match against the display name: original case (for camelCase bonuses) without the extension
+        let name = Path::new(full).file_stem().map(|n| n.to_string_lossy()).unwrap_or_default();
+        let mut score = match fuzzy_score(&name, query) {
+            Some(s) => s as f32,
+            // Path matches rank well below any name match
+            None if path_lower.contains(query) => SCORE_MATCH as f32,
+            None => return None,
+        };
+
+        if name.to_lowercase() == query {
+            score += 50.0;
+        }
+        if self.app_paths.contains(full) || filename.ends_with(".lnk") {
+            score += 40.0;
+        } else if filename.ends_with(".exe") {
+            score += 20.0;
+        }
+        score += history_boost(full);
+
+        // Prefer files closer to the root
+        score -= path_lower.matches('\\').count() as f32;
+        Some(score)
+    }
+
     /// Search by extension (.ext or ext). Case-insensitive. Up to `limit` results
     pub fn search_by_extension(&self, ext: &str, limit: usize) -> Vec<String> {
         let e = ext.trim_start_matches('.').to_lowercase();
@@ -230,54 +483,200 @@ impl SearchIndex {
     }
 }
 
-/// Calculate relevance score for a search result
-/// Higher scores = more relevant
-fn calculate_relevance_score(filename: &str, path: &str, query: &str, app_paths: &std::collections::HashSet<String>) -> f32 {
-    let mut score: f32 = 0.0;
+/// Load or build the index for `config` into the global slot, then keep it current.
+///
+/// Blocks while scanning, so call it from a background thread.
+pub fn start(config: &SearchConfig) {
+    if config.index_apps {
+        crate::app_index::refresh();
+    }
+
+    let Some(global) = global_index() else {
+        return;
+    };
+    let roots = &config.index_paths;
+    let excludes = &config.exclude_patterns;
 
-    // 1. Boost for applications/programs (highest priority)
-    if app_paths.contains(path) {
-        score += 1000.0;
+    let cached = if config.persist_index {
+        SearchIndex::load_cached(roots, excludes)
+    } else {
+        None
+    };
+    let stale = cached
+        .as_ref()
+        .map(|idx| idx.age() >= Duration::from_secs(config.rescan_hours.max(1) * 3600))
+        .unwrap_or(true);
+    if let Some(idx) = cached {
+        log::info!("Loaded saved search index with {} entries", idx.count());
+        *global.write() = Some(idx);
+    }
+
+    if stale {
+        log::info!("Starting background search indexing...");
+        log::info!("Indexing {} root directories:", roots.len());
+        for root in roots {
+            log::info!("  - {}", root.display());
+        }
+        match SearchIndex::build_with_excludes(roots, excludes) {
+            Ok(idx) => {
+                let len = idx.count();
+                if config.persist_index {
+                    if let Err(e) = idx.save() {
+                        log::warn!("Failed to save search index: {}", e);
+                    }
+                }
+                *global.write() = Some(idx);
+                log::info!("Search index built with {} entries", len);
+            }
+            Err(e) => {
+                log::error!("Failed to build search index: {}", e);
+            }
+        }
     }
 
-    // 2. Exact filename match (without extension)
-    let filename_no_ext = filename.split('.').next().unwrap_or(filename);
-    if filename_no_ext.to_lowercase() == query {
-        score += 500.0;
+    crate::content_index::rebuild(config);
+
+    if config.watch_changes {
+        watch(roots, config.persist_index);
     }
+}
+
+/// Apply file system changes under the indexed roots to the global index
+fn watch(roots: &[PathBuf], persist: bool) {
+    let watcher = notify::recommended_watcher(|result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        let Some(global) = global_index() else {
+            return;
+        };
+        let mut guard = global.write();
+        let Some(index) = guard.as_mut() else {
+            return;
+        };
+        let changed = match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                event.paths.iter().fold(false, |acc, p| index.insert_path(p) | acc)
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                event.paths.iter().fold(false, |acc, p| index.remove_path(p) | acc)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                index.remove_path(&event.paths[0]) | index.insert_path(&event.paths[1])
+            }
+            _ => false,
+        };
+        if changed {
+            DIRTY.store(true, Ordering::Relaxed);
+        }
+    });
 
-    // 3. Filename starts with query (already guaranteed by prefix search)
-    // But boost if it's a closer match
-    if filename.to_lowercase().starts_with(query) {
-        let match_ratio = query.len() as f32 / filename.len() as f32;
-        score += 100.0 * match_ratio;
+    let mut watcher = match watcher {
+        Ok(w) => w,
+        Err(e) => {
+            log::warn!("Failed to create search index watcher: {}", e);
+            return;
+        }
+    };
+    for root in roots {
+        if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+            log::warn!("Failed to watch {} for search: {}", root.display(), e);
+        }
     }
+    *WATCHER.lock() = Some(watcher);
+    log::info!("Watching {} search roots for changes", roots.len());
+
+    if persist {
+        std::thread::spawn(|| {
+            // Runs until watching stops; a final save happens on the way out
+            loop {
+                std::thread::sleep(SAVE_INTERVAL);
+                let watching = WATCHER.lock().is_some();
+                if DIRTY.swap(false, Ordering::Relaxed) {
+                    if let Some(Err(e)) = global_index().and_then(|g| g.read().as_ref().map(|i| i.save())) {
+                        log::warn!("Failed to save search index: {}", e);
+                    }
+                }
+                if !watching {
+                    break;
+                }
+            }
+        });
+    }
+}
 
-    // 4. Penalty for very long paths (prefer files closer to root)
-    let depth = path.matches('\\').count() as f32;
-    score -= depth * 2.0;
+/// Stop watching the indexed roots (when search is disabled)
+pub fn stop() {
+    *WATCHER.lock() = None;
+}
 
-    // 5. Boost for executable and script files
-    if filename.ends_with(".exe") || filename.ends_with(".lnk") || filename.ends_with(".bat") || filename.ends_with(".cmd") || filename.ends_with(".ps1") {
-        score += 50.0;
+/// fzf-style fuzzy match of `query` (lowercase) in `text`: the best-scoring alignment where
+/// every query character appears in order. Matches at word starts, camelCase humps and
+/// consecutive runs score higher; gaps between matched characters cost a little.
+pub(crate) fn fuzzy_score(text: &str, query: &str) -> Option<i32> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let q: Vec<char> = query.chars().collect();
+    if q.is_empty() || q.len() > lower.len() {
+        return None;
     }
-    
-    // 6. Boost for document and archive files
-    if filename.ends_with(".txt") || filename.ends_with(".pdf") || filename.ends_with(".json") || filename.ends_with(".xml") || filename.ends_with(".zip") {
-        score += 20.0;
+
+    // Cheap rejection before the DP: the query must be a subsequence
+    let mut qi = 0;
+    for c in &lower {
+        if qi < q.len() && *c == q[qi] {
+            qi += 1;
+        }
+    }
+    if qi < q.len() {
+        return None;
     }
 
-    // 7. Boost if filename appears at the very start of path (not in a subdirectory as much)
-    if path.to_lowercase().contains(&format!("\\{}", filename.to_lowercase())) {
-        let pos = path.to_lowercase().rfind(&format!("\\{}", filename.to_lowercase())).unwrap_or(0);
-        let prefix_depth = path[..pos].matches('\\').count();
-        score += 50.0 / (prefix_depth as f32 + 1.0);
+    let bonus: Vec<i32> = (0..chars.len())
+        .map(|i| {
+            let cur = chars[i];
+            match i.checked_sub(1).map(|p| chars[p]) {
+                None => BONUS_BOUNDARY,
+                Some(prev) if !prev.is_alphanumeric() => BONUS_BOUNDARY,
+                Some(prev) if prev.is_lowercase() && cur.is_uppercase() => BONUS_CAMEL,
+                Some(prev) if !prev.is_ascii_digit() && cur.is_ascii_digit() => BONUS_CAMEL,
+                _ => 0,
+            }
+        })
+        .collect();
+
+    // prev[i]: best score with the previous query characters matched and the last one at i
+    let mut prev: Vec<Option<i32>> = vec![None; lower.len()];
+    for (j, qc) in q.iter().enumerate() {
+        let mut cur: Vec<Option<i32>> = vec![None; lower.len()];
+        // Best score ending before i with a gap since, including gap penalties
+        let mut gapped: Option<i32> = None;
+        for i in 0..lower.len() {
+            if j > 0 && i >= 2 {
+                let opened = prev[i - 2].map(|s| s + PENALTY_GAP_START);
+                let extended = gapped.map(|s| s + PENALTY_GAP_EXTENSION);
+                gapped = opened.max(extended);
+            }
+            if lower[i] != *qc {
+                continue;
+            }
+            cur[i] = if j == 0 {
+                Some(SCORE_MATCH + bonus[i] * BONUS_FIRST_CHAR_MULTIPLIER)
+            } else {
+                let consecutive = i
+                    .checked_sub(1)
+                    .and_then(|p| prev[p])
+                    .map(|s| s + SCORE_MATCH + bonus[i].max(BONUS_CONSECUTIVE));
+                let after_gap = gapped.map(|s| s + SCORE_MATCH + bonus[i]);
+                consecutive.max(after_gap)
+            };
+        }
+        prev = cur;
     }
 
-    score
+    prev.into_iter().flatten().max()
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,10 +701,40 @@ mod tests {
         let results_contains = idx.search_query("llo", 10);
         assert!(results_contains.iter().any(|p| p.ends_with("Hello.exe")));
 
+        // fuzzy search matches word starts in order
+        File::create(dir.path().join("Visual Studio Code.lnk")).unwrap();
+        File::create(dir.path().join("services.exe")).unwrap();
+        let idx_fuzzy = SearchIndex::build(&[dir.path().to_path_buf()]).unwrap();
+        let fuzzy = idx_fuzzy.search_query("vsc", 10);
+        assert!(fuzzy.first().is_some_and(|p| p.ends_with("Visual Studio Code.lnk")));
+
         // Test extension search
         File::create(dir.path().join("image.EXE")).unwrap();
         let idx2 = SearchIndex::build(&[dir.path().to_path_buf()]).unwrap();
         let ext_results = idx2.search_by_extension(".exe", 10);
         assert!(ext_results.iter().any(|p| p.ends_with("image.EXE")));
     }
+
+    #[test]
+    fn fuzzy_needs_every_character_in_order() {
+        assert_eq!(fuzzy_score("abc", "acb"), None);
+        assert_eq!(fuzzy_score("abc", ""), None);
+        assert_eq!(fuzzy_score("ab", "abc"), None);
+        assert!(fuzzy_score("HELLO", "hel").is_some());
+    }
+
+    #[test]
+    fn fuzzy_scores_exact_match() {
+        // First character on a boundary, then two consecutive matches
+        let first = SCORE_MATCH + BONUS_BOUNDARY * BONUS_FIRST_CHAR_MULTIPLIER;
+        assert_eq!(fuzzy_score("abc", "abc"), Some(first + 2 * (SCORE_MATCH + BONUS_CONSECUTIVE)));
+    }
+
+    #[test]
+    fn fuzzy_prefers_word_starts_and_runs() {
+        assert!(fuzzy_score("Visual Studio Code", "vsc") > fuzzy_score("avscx", "vsc"));
+        assert!(fuzzy_score("fooBar", "b") > fuzzy_score("foobar", "b"));
+        assert!(fuzzy_score("abcd", "ab") > fuzzy_score("axxb", "ab"));
+        assert!(fuzzy_score("axb", "ab") > fuzzy_score("axxxxb", "ab"));
+    }
 }
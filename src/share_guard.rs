@@ -0,0 +1,128 @@
+//! Screen-share guard
+//!
+//! While a video call shares the screen, the bar shrinks to a thin click-through
+//! strip so it never covers the shared content, and comes back when the share
+//! ends. Sharing is recognized by the toolbars conferencing apps open for it
+//! (Teams' sharing control bar, Zoom's floating controls) and the "is sharing your
+//! screen" bar browsers show for web meetings (`[share_guard]` in the config).
+//!
+//! The bar only shrinks after sharing has been seen for `delay_secs`, and only
+//! returns after it has been gone as long, so a quick start-stop doesn't make it
+//! jump around.
+
+use log::info;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::config::{Config, ShareGuardConfig};
+
+/// Height of the bar while shrunk, before DPI scaling
+pub const SLIM_HEIGHT: i32 = 3;
+
+/// Whether the bar is currently shrunk
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Since when sharing has been seen (while expanded) or gone (while shrunk)
+static PENDING_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Whether the bar is shrunk for a screen share
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Check for a screen share and shrink or restore the bar (called once a second)
+pub fn tick(hwnd: HWND, config: &Config) {
+    let guard = &config.share_guard;
+    let sharing = guard.enabled && is_sharing(guard);
+    let active = is_active();
+
+    let mut pending = PENDING_SINCE.lock();
+    if sharing == active {
+        *pending = None;
+        return;
+    }
+    // Turning off the guard restores the bar right away
+    let delay = if guard.enabled { Duration::from_secs(guard.delay_secs) } else { Duration::ZERO };
+    let since = *pending.get_or_insert_with(Instant::now);
+    if since.elapsed() < delay {
+        return;
+    }
+    *pending = None;
+    drop(pending);
+
+    if sharing {
+        info!("Screen share detected, shrinking the bar");
+    } else {
+        info!("Screen share ended, restoring the bar");
+    }
+    set_active(hwnd, sharing);
+}
+
+fn set_active(hwnd: HWND, active: bool) {
+    ACTIVE.store(active, Ordering::Relaxed);
+    unsafe {
+        // Clicks go through to the shared window underneath while shrunk
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let ex_style = if active {
+            ex_style | WS_EX_TRANSPARENT.0 as isize
+        } else {
+            ex_style & !(WS_EX_TRANSPARENT.0 as isize)
+        };
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style);
+    }
+    crate::window::manager::WindowManager::relayout(hwnd);
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, true);
+    }
+}
+
+/// Whether a sharing toolbar or browser sharing bar is open
+fn is_sharing(config: &ShareGuardConfig) -> bool {
+    struct Search<'a> {
+        config: &'a ShareGuardConfig,
+        found: bool,
+    }
+
+    unsafe extern "system" fn check(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        if IsWindowVisible(hwnd).as_bool() && is_share_window(hwnd, search.config) {
+            search.found = true;
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    if config.window_classes.is_empty() && config.window_titles.is_empty() {
+        return false;
+    }
+    let mut search = Search { config, found: false };
+    unsafe {
+        let _ = EnumWindows(Some(check), LPARAM(&mut search as *mut Search as isize));
+    }
+    search.found
+}
+
+fn is_share_window(hwnd: HWND, config: &ShareGuardConfig) -> bool {
+    unsafe {
+        let mut class = [0u16; 128];
+        let len = GetClassNameW(hwnd, &mut class) as usize;
+        let class = String::from_utf16_lossy(&class[..len]);
+        if config.window_classes.contains(&class) {
+            return true;
+        }
+
+        let mut title = [0u16; 256];
+        let len = GetWindowTextW(hwnd, &mut title) as usize;
+        if len == 0 {
+            return false;
+        }
+        let title = String::from_utf16_lossy(&title[..len]).to_lowercase();
+        config
+            .window_titles
+            .iter()
+            .any(|t| !t.is_empty() && title.contains(&t.to_lowercase()))
+    }
+}
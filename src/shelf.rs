@@ -0,0 +1,153 @@
+//! Drop shelf
+//!
+//! Files dropped on the bar are parked on the shelf until they're dragged out
+//! into another app or folder, Dropover/Yoink-style. The shelf only holds paths -
+//! nothing is copied or moved until the drag out - and the list is saved, so
+//! parked files survive switching windows and restarting TopBar.
+//!
+//! Dropping in uses the shell's `WM_DROPFILES`. Dragging out needs OLE, which
+//! needs a single-threaded apartment; the UI thread is multithreaded for the
+//! audio APIs, so each drag runs on its own short-lived thread.
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::path::PathBuf;
+use windows::core::PCWSTR;
+use windows::Win32::System::Com::IDataObject;
+use windows::Win32::System::Ole::{
+    OleInitialize, OleUninitialize, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_LINK, DROPEFFECT_MOVE,
+};
+use windows::Win32::UI::Shell::Common::ITEMIDLIST;
+use windows::Win32::UI::Shell::{
+    DragFinish, DragQueryFileW, ILFree, SHCreateShellItemArrayFromIDLists, SHDoDragDrop, SHParseDisplayName,
+    BHID_DataObject, HDROP,
+};
+
+static SHELF: OnceCell<Mutex<Vec<PathBuf>>> = OnceCell::new();
+
+fn shelf() -> &'static Mutex<Vec<PathBuf>> {
+    SHELF.get_or_init(|| {
+        let items: Vec<PathBuf> = std::fs::read_to_string(state_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Mutex::new(items)
+    })
+}
+
+fn state_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("topbar").join("shelf.json")
+}
+
+fn save(items: &[PathBuf]) {
+    let result = serde_json::to_string(items)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            let path = state_path();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, json)?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        log::warn!("Failed to save shelf: {}", e);
+    }
+}
+
+/// Files on the shelf, oldest first; files that were moved or deleted are dropped
+pub fn items() -> Vec<PathBuf> {
+    let mut items = shelf().lock();
+    let before = items.len();
+    items.retain(|path| path.exists());
+    if items.len() != before {
+        save(&items);
+    }
+    items.clone()
+}
+
+/// Park files on the shelf, returning how many weren't already there
+pub fn add(paths: impl IntoIterator<Item = PathBuf>) -> usize {
+    let mut items = shelf().lock();
+    let before = items.len();
+    for path in paths {
+        if !items.contains(&path) {
+            items.push(path);
+        }
+    }
+    let added = items.len() - before;
+    if added > 0 {
+        save(&items);
+    }
+    added
+}
+
+/// Take files off the shelf
+pub fn remove(paths: &[PathBuf]) {
+    let mut items = shelf().lock();
+    items.retain(|path| !paths.contains(path));
+    save(&items);
+}
+
+/// Empty the shelf
+pub fn clear() {
+    let mut items = shelf().lock();
+    items.clear();
+    save(&items);
+}
+
+/// Paths of a `WM_DROPFILES` drop; releases the drop handle
+pub fn dropped_files(hdrop: HDROP) -> Vec<PathBuf> {
+    unsafe {
+        let count = DragQueryFileW(hdrop, u32::MAX, None);
+        let paths = (0..count)
+            .filter_map(|i| {
+                let len = DragQueryFileW(hdrop, i, None) as usize;
+                let mut buffer = vec![0u16; len + 1];
+                (DragQueryFileW(hdrop, i, Some(&mut buffer)) > 0)
+                    .then(|| PathBuf::from(String::from_utf16_lossy(&buffer[..len])))
+            })
+            .collect();
+        DragFinish(hdrop);
+        paths
+    }
+}
+
+/// Drag files out of the shelf; blocks until they're dropped or the drag is cancelled
+///
+/// Call from a thread of its own while the mouse button is still down. Returns
+/// the effect the drop target chose (none when cancelled).
+pub fn drag_out(paths: &[PathBuf]) -> windows::core::Result<DROPEFFECT> {
+    unsafe {
+        OleInitialize(None)?;
+        let result = data_object(paths).and_then(|data| {
+            SHDoDragDrop(
+                None,
+                &data,
+                None,
+                DROPEFFECT_COPY | DROPEFFECT_MOVE | DROPEFFECT_LINK,
+            )
+        });
+        OleUninitialize();
+        result
+    }
+}
+
+/// Shell data object for a set of files, as Explorer would offer when dragging them
+unsafe fn data_object(paths: &[PathBuf]) -> windows::core::Result<IDataObject> {
+    let mut pidls: Vec<*mut ITEMIDLIST> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let wide: Vec<u16> = path.to_string_lossy().encode_utf16().chain(std::iter::once(0)).collect();
+        let mut pidl = std::ptr::null_mut();
+        if SHParseDisplayName(PCWSTR(wide.as_ptr()), None, &mut pidl, 0, None).is_ok() {
+            pidls.push(pidl);
+        }
+    }
+    let list: Vec<*const ITEMIDLIST> = pidls.iter().map(|p| *p as *const ITEMIDLIST).collect();
+    let result = SHCreateShellItemArrayFromIDLists(&list)
+        .and_then(|items| items.BindToHandler(None, &BHID_DataObject));
+    for pidl in pidls {
+        ILFree(Some(pidl));
+    }
+    result
+}
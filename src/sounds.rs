@@ -0,0 +1,257 @@
+//! Audio cues for accessibility
+//!
+//! Short sounds for focus movement, menus and popups opening or closing, and values
+//! changing under the mouse wheel. The built-in themes are synthesized tones; the
+//! custom theme plays files from a folder.
+//!
+//! Cues are played by one worker thread. When cues arrive faster than they can be
+//! heard (holding an arrow key, spinning the wheel) only the latest one plays, and
+//! while a screen reader is running cues are spaced further apart so they don't
+//! talk over speech.
+//!
+//! The clock's hourly chime and spoken time and the alarm sound also go through
+//! here; they play even when the cue theme is off.
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rodio::Source;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use crate::config::{SoundTheme, SoundsConfig};
+
+/// Minimum time between cues without a screen reader
+const MIN_GAP: Duration = Duration::from_millis(40);
+
+static SENDER: OnceCell<Mutex<Sender<Cue>>> = OnceCell::new();
+
+/// Something the user can hear
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundEvent {
+    /// Keyboard focus moved to another item
+    Focus,
+    /// A menu or popup opened
+    PopupOpen,
+    /// A menu or popup closed
+    PopupClose,
+    /// A value changed; `Some(0.0..=1.0)` sets the pitch
+    ValueChange(Option<f32>),
+    /// The clock's periodic chime
+    Chime,
+    /// A ringing alarm (repeated until snoozed or dismissed)
+    Alarm,
+}
+
+impl SoundEvent {
+    /// File name (without extension) used by the custom theme
+    fn file_stem(&self) -> &'static str {
+        match self {
+            Self::Focus => "focus",
+            Self::PopupOpen => "popup_open",
+            Self::PopupClose => "popup_close",
+            Self::ValueChange(_) => "value_change",
+            Self::Chime => "chime",
+            Self::Alarm => "alarm",
+        }
+    }
+
+    fn enabled(&self, config: &SoundsConfig) -> bool {
+        match self {
+            Self::Focus => config.focus_cues,
+            Self::PopupOpen | Self::PopupClose => config.popup_cues,
+            Self::ValueChange(_) => config.value_cues,
+            Self::Chime | Self::Alarm => true,
+        }
+    }
+
+    /// Notes for a synthesized theme: (frequency Hz, start ms, length ms)
+    fn tones(&self, theme: SoundTheme) -> Vec<(f32, u64, u64)> {
+        let value = |v: &Option<f32>| v.unwrap_or(0.5).clamp(0.0, 1.0);
+        match (theme, self) {
+            (_, Self::Chime) => vec![(1318.5, 0, 400), (1046.5, 450, 700)],
+            (_, Self::Alarm) => (0..4).map(|i| (1760.0, i * 200, 120)).collect(),
+            (SoundTheme::Chimes, Self::Focus) => vec![(784.0, 0, 60)],
+            (SoundTheme::Chimes, Self::PopupOpen) => vec![(523.0, 0, 70), (784.0, 70, 110)],
+            (SoundTheme::Chimes, Self::PopupClose) => vec![(784.0, 0, 70), (523.0, 70, 110)],
+            (SoundTheme::Chimes, Self::ValueChange(v)) => vec![(400.0 + 500.0 * value(v), 0, 60)],
+            (_, Self::Focus) => vec![(1320.0, 0, 30)],
+            (_, Self::PopupOpen) => vec![(660.0, 0, 35), (990.0, 35, 45)],
+            (_, Self::PopupClose) => vec![(990.0, 0, 35), (660.0, 35, 45)],
+            (_, Self::ValueChange(v)) => vec![(500.0 + 700.0 * value(v), 0, 30)],
+        }
+    }
+}
+
+/// A cue with the settings it was requested with
+struct Cue {
+    event: SoundEvent,
+    theme: SoundTheme,
+    volume: f32,
+    custom_dir: Option<PathBuf>,
+    gap: Duration,
+}
+
+/// Play the cue for `event` if the sound theme and settings allow it
+pub fn play(config: &SoundsConfig, event: SoundEvent) {
+    if config.theme == SoundTheme::Off || !event.enabled(config) {
+        return;
+    }
+    let gap = if crate::accessibility::screen_reader_active() {
+        Duration::from_millis(config.screen_reader_gap_ms).max(MIN_GAP)
+    } else {
+        MIN_GAP
+    };
+    let cue = Cue {
+        event,
+        theme: config.theme,
+        volume: config.volume.clamp(0.0, 1.0),
+        custom_dir: config.custom_dir.clone(),
+        gap,
+    };
+    let _ = sender().lock().send(cue);
+}
+
+/// Play a cue using the current configuration
+pub fn play_event(event: SoundEvent) {
+    let config = crate::window::state::get_window_state().map(|s| s.read().config.sounds.clone());
+    if let Some(config) = config {
+        play(&config, event);
+    }
+}
+
+/// Play a chime or alarm at `volume`, whatever the cue theme
+///
+/// The custom theme's file for the event is used when there is one.
+pub fn play_at(config: &SoundsConfig, event: SoundEvent, volume: f32) {
+    let theme = if config.theme == SoundTheme::Custom {
+        SoundTheme::Custom
+    } else {
+        SoundTheme::Chimes
+    };
+    let cue = Cue {
+        event,
+        theme,
+        volume: volume.clamp(0.0, 1.0),
+        custom_dir: config.custom_dir.clone(),
+        gap: MIN_GAP,
+    };
+    let _ = sender().lock().send(cue);
+}
+
+/// Speak `text` with the default SAPI voice at `volume`
+///
+/// Runs on its own thread so a slow voice never blocks the bar. `delay` lets a
+/// chime finish first.
+pub fn speak(text: &str, volume: f32, delay: Duration) {
+    let text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let volume = (volume.clamp(0.0, 1.0) * 100.0).round() as u16;
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        if let Err(e) = unsafe { speak_blocking(&text, volume) } {
+            log::warn!("Failed to speak: {}", e);
+        }
+    });
+}
+
+unsafe fn speak_blocking(text: &[u16], volume: u16) -> windows::core::Result<()> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Media::Speech::{ISpVoice, SpVoice, SPF_IS_NOT_XML};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_ALL, COINIT_MULTITHREADED,
+    };
+
+    let initialized = CoInitializeEx(None, COINIT_MULTITHREADED).is_ok();
+    let result = (|| {
+        let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL)?;
+        voice.SetVolume(volume)?;
+        // Synchronous, so the voice lives until it has finished speaking
+        voice.Speak(PCWSTR(text.as_ptr()), SPF_IS_NOT_XML.0 as u32, None)
+    })();
+    if initialized {
+        CoUninitialize();
+    }
+    result
+}
+
+fn sender() -> &'static Mutex<Sender<Cue>> {
+    SENDER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || worker(rx));
+        Mutex::new(tx)
+    })
+}
+
+fn worker(rx: Receiver<Cue>) {
+    // Opened on first use and kept, so cues start without the device setup delay
+    let mut output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)> = None;
+    let mut last_played: Option<Instant> = None;
+
+    while let Ok(mut cue) = rx.recv() {
+        // Wait out the gap, then skip to the newest cue that arrived meanwhile
+        if let Some(last) = last_played {
+            let since = last.elapsed();
+            if since < cue.gap {
+                std::thread::sleep(cue.gap - since);
+            }
+        }
+        while let Ok(newer) = rx.try_recv() {
+            cue = newer;
+        }
+
+        if output.is_none() {
+            match rodio::OutputStream::try_default() {
+                Ok(stream) => output = Some(stream),
+                Err(e) => {
+                    log::warn!("Failed to open audio output for cues: {}", e);
+                    continue;
+                }
+            }
+        }
+        if let Some((_, handle)) = &output {
+            if let Err(e) = play_cue(handle, &cue) {
+                log::debug!("Failed to play {:?} cue: {}", cue.event, e);
+                // The device may have gone away; reopen it next time
+                output = None;
+            }
+        }
+        last_played = Some(Instant::now());
+    }
+}
+
+fn play_cue(handle: &rodio::OutputStreamHandle, cue: &Cue) -> anyhow::Result<()> {
+    if cue.theme == SoundTheme::Custom {
+        let Some(path) = cue.custom_dir.as_deref().and_then(|dir| custom_file(dir, cue.event)) else {
+            // Chimes and alarms fall back to the built-in sounds
+            if matches!(cue.event, SoundEvent::Chime | SoundEvent::Alarm) {
+                return play_tones(handle, cue);
+            }
+            return Ok(());
+        };
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let source = rodio::Decoder::new(file)?;
+        handle.play_raw(source.amplify(cue.volume).convert_samples())?;
+        return Ok(());
+    }
+    play_tones(handle, cue)
+}
+
+fn play_tones(handle: &rodio::OutputStreamHandle, cue: &Cue) -> anyhow::Result<()> {
+    for (freq, start, length) in cue.event.tones(cue.theme) {
+        let tone = rodio::source::SineWave::new(freq)
+            .take_duration(Duration::from_millis(length))
+            .fade_in(Duration::from_millis(4))
+            .amplify(cue.volume * 0.5)
+            .delay(Duration::from_millis(start));
+        handle.play_raw(tone)?;
+    }
+    Ok(())
+}
+
+/// First existing `<event>.wav`, `.mp3` or `.ogg` in the custom folder
+fn custom_file(dir: &Path, event: SoundEvent) -> Option<PathBuf> {
+    ["wav", "mp3", "ogg"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", event.file_stem(), ext)))
+        .find(|p| p.exists())
+}
@@ -0,0 +1,303 @@
+//! Start with Windows
+//!
+//! Registers the bar to launch at sign-in, either as a value under the current
+//! user's `Run` key or as a Task Scheduler task. The task runs with highest
+//! privileges, so a bar that needs elevation (for services, or to manage
+//! elevated windows) starts without a UAC prompt; creating or removing the task
+//! asks for elevation once.
+//!
+//! `general.start_with_windows` and `general.startup_method` say what's wanted.
+//! [`set`] (the menu) registers or unregisters to match. [`apply`] runs at
+//! launch and after the config changes: it keeps the `Run` value in line, but
+//! only notes a task that needs creating or removing, since that would mean a
+//! UAC prompt nobody asked for; [`take_problem`] hands the note over once.
+//! [`registered`] reports what's actually there for the menus. Registering and
+//! checking the task runs helper processes, so both happen on a background
+//! thread.
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Registry::*;
+use windows::Win32::System::Threading::{GetExitCodeProcess, WaitForSingleObject};
+use windows::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+
+use crate::config::{GeneralConfig, StartupMethod};
+use crate::utils::to_wide_string;
+
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+/// `Run` value and task name
+const ENTRY_NAME: &str = "TopBar";
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+/// How long to wait for the elevated PowerShell (including the UAC prompt)
+const ELEVATED_TIMEOUT_MS: u32 = 120_000;
+
+/// What was registered when last checked; `None` inside until the first check
+static REGISTERED: Mutex<Option<Option<StartupMethod>>> = Mutex::new(None);
+/// Set while the menu's change is being registered, which may wait on a UAC prompt
+static CHANGING: AtomicBool = AtomicBool::new(false);
+/// A task that `apply` found needs creating or removing, waiting to be shown
+static PROBLEM: Mutex<Option<String>> = Mutex::new(None);
+/// The last problem shown, so the same one isn't raised on every reload
+static REPORTED: Mutex<Option<String>> = Mutex::new(None);
+
+/// How the bar is currently registered to start, as of the last check
+pub fn registered() -> Option<StartupMethod> {
+    let mut cached = REGISTERED.lock();
+    // Only the Run key is cheap enough to read here; the task is checked by `apply`
+    *cached.get_or_insert_with(|| run_key_command().map(|_| StartupMethod::RunKey))
+}
+
+/// Check the registration against `general` in the background, fixing the
+/// `Run` value and noting a task that needs elevation to create or remove
+pub fn apply(general: &GeneralConfig) {
+    let wanted = general.start_with_windows.then_some(general.startup_method);
+    std::thread::spawn(move || {
+        if let Err(e) = sync_run_key(wanted) {
+            warn!("Failed to update start with Windows: {}", e);
+        }
+        let now = check();
+        *REGISTERED.lock() = Some(now);
+        info!("Start with Windows: {:?}", now);
+        // The menu's own change is still on its way
+        if CHANGING.load(Ordering::SeqCst) {
+            return;
+        }
+        match mismatch(wanted, now) {
+            None => {
+                *PROBLEM.lock() = None;
+                *REPORTED.lock() = None;
+            }
+            Some(problem) if REPORTED.lock().as_ref() != Some(&problem) => {
+                warn!("{}", problem);
+                *PROBLEM.lock() = Some(problem);
+            }
+            Some(_) => {}
+        }
+    });
+}
+
+/// Take the mismatch the last `apply` found, so it's shown once
+pub fn take_problem() -> Option<String> {
+    let problem = PROBLEM.lock().take()?;
+    *REPORTED.lock() = Some(problem.clone());
+    Some(problem)
+}
+
+/// Why the registration found (`now`) doesn't do what's `wanted`, when only the
+/// menu can fix it
+fn mismatch(wanted: Option<StartupMethod>, now: Option<StartupMethod>) -> Option<String> {
+    let wants_task = wanted == Some(StartupMethod::TaskScheduler);
+    let has_task = now == Some(StartupMethod::TaskScheduler);
+    if wants_task && !has_task {
+        Some(
+            "Start with Windows is set to use Task Scheduler, but the task isn't registered; \
+             choose it from the Start with Windows menu to register it"
+                .to_string(),
+        )
+    } else if has_task && !wants_task {
+        Some(
+            "The Task Scheduler task still starts TopBar with Windows, which the config doesn't ask for; \
+             choose a setting from the Start with Windows menu to remove it"
+                .to_string(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Turn starting with Windows off, or on with `method`, and save it to the config
+pub fn set(method: Option<StartupMethod>) {
+    let Some(state) = crate::window::state::get_window_state() else {
+        return;
+    };
+    let mut new_config = (*state.read().config).clone();
+    new_config.general.start_with_windows = method.is_some();
+    if let Some(method) = method {
+        new_config.general.startup_method = method;
+    }
+    if let Err(e) = new_config.save() {
+        warn!("Failed to save config: {}", e);
+    }
+    // Chosen from the menu, so creating or removing the task may ask for elevation
+    CHANGING.store(true, Ordering::SeqCst);
+    std::thread::spawn(move || {
+        if let Err(e) = sync(method) {
+            warn!("Failed to update start with Windows: {}", e);
+        }
+        let now = check();
+        *REGISTERED.lock() = Some(now);
+        *PROBLEM.lock() = None;
+        *REPORTED.lock() = None;
+        CHANGING.store(false, Ordering::SeqCst);
+        info!("Start with Windows: {:?}", now);
+    });
+    state.write().config = std::sync::Arc::new(new_config);
+}
+
+/// What's registered now
+fn check() -> Option<StartupMethod> {
+    if task_exists() {
+        Some(StartupMethod::TaskScheduler)
+    } else if run_key_command().is_some() {
+        Some(StartupMethod::RunKey)
+    } else {
+        None
+    }
+}
+
+/// Register or unregister to match `wanted`; the task asks for elevation
+fn sync(wanted: Option<StartupMethod>) -> anyhow::Result<()> {
+    sync_run_key(wanted)?;
+    let exe = std::env::current_exe()?.to_string_lossy().to_string();
+    let has_task = task_exists();
+    if wanted == Some(StartupMethod::TaskScheduler) && !has_task {
+        register_task(&exe)?;
+    } else if wanted != Some(StartupMethod::TaskScheduler) && has_task {
+        run_elevated(&format!("Unregister-ScheduledTask -TaskName '{}' -Confirm:$false", ENTRY_NAME))?;
+    }
+    Ok(())
+}
+
+/// The Run value is cheap to rewrite and needs no elevation, which also
+/// follows the exe if it moved
+fn sync_run_key(wanted: Option<StartupMethod>) -> anyhow::Result<()> {
+    let exe = std::env::current_exe()?.to_string_lossy().to_string();
+    let command = format!("\"{}\"", exe);
+    if wanted == Some(StartupMethod::RunKey) {
+        if run_key_command().as_deref() != Some(command.as_str()) {
+            set_run_key(&command)?;
+        }
+    } else if run_key_command().is_some() {
+        delete_run_key()?;
+    }
+    Ok(())
+}
+
+fn open_run_key(access: REG_SAM_FLAGS) -> Option<HKEY> {
+    let path = to_wide_string(RUN_KEY);
+    let mut key = HKEY::default();
+    unsafe {
+        RegOpenKeyExW(HKEY_CURRENT_USER, PCWSTR(path.as_ptr()), 0, access, &mut key)
+            .ok()
+            .ok()?;
+    }
+    Some(key)
+}
+
+/// The command under our `Run` value, if there is one
+fn run_key_command() -> Option<String> {
+    let key = open_run_key(KEY_READ)?;
+    let name = to_wide_string(ENTRY_NAME);
+    let mut data = [0u16; 1024];
+    let mut size = std::mem::size_of_val(&data) as u32;
+    let result = unsafe {
+        RegGetValueW(
+            key,
+            PCWSTR::null(),
+            PCWSTR(name.as_ptr()),
+            RRF_RT_REG_SZ,
+            None,
+            Some(data.as_mut_ptr() as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    result.ok().ok()?;
+    let len = (size as usize / 2).min(data.len());
+    Some(String::from_utf16_lossy(&data[..len]).trim_end_matches('\0').to_string())
+}
+
+fn set_run_key(command: &str) -> windows::core::Result<()> {
+    let key = open_run_key(KEY_SET_VALUE).ok_or_else(windows::core::Error::from_win32)?;
+    let name = to_wide_string(ENTRY_NAME);
+    let data: Vec<u8> = to_wide_string(command).iter().flat_map(|c| c.to_le_bytes()).collect();
+    let result = unsafe { RegSetValueExW(key, PCWSTR(name.as_ptr()), 0, REG_SZ, Some(&data)) };
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    result.ok()
+}
+
+fn delete_run_key() -> windows::core::Result<()> {
+    let key = open_run_key(KEY_SET_VALUE).ok_or_else(windows::core::Error::from_win32)?;
+    let name = to_wide_string(ENTRY_NAME);
+    let result = unsafe { RegDeleteValueW(key, PCWSTR(name.as_ptr())) };
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    result.ok()
+}
+
+fn task_exists() -> bool {
+    Command::new("schtasks")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["/Query", "/TN", ENTRY_NAME])
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+/// Create the sign-in task for this user. Registered through PowerShell rather
+/// than `schtasks /Create`, which can't lift the default three-day run limit or
+/// the battery conditions that would stop the bar.
+fn register_task(exe: &str) -> anyhow::Result<()> {
+    let user = match std::env::var("USERDOMAIN") {
+        Ok(domain) => format!("{}\\{}", domain, std::env::var("USERNAME")?),
+        Err(_) => std::env::var("USERNAME")?,
+    };
+    // Single quotes are doubled inside a PowerShell single-quoted string
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let script = format!(
+        "$a = New-ScheduledTaskAction -Execute {exe}; \
+         $t = New-ScheduledTaskTrigger -AtLogOn -User {user}; \
+         $s = New-ScheduledTaskSettingsSet -AllowStartIfOnBatteries -DontStopIfGoingOnBatteries -ExecutionTimeLimit ([TimeSpan]::Zero); \
+         $p = New-ScheduledTaskPrincipal -UserId {user} -LogonType Interactive -RunLevel Highest; \
+         Register-ScheduledTask -TaskName {name} -Action $a -Trigger $t -Settings $s -Principal $p -Force",
+        exe = quote(exe),
+        user = quote(&user),
+        name = quote(ENTRY_NAME),
+    );
+    run_elevated(&script)
+}
+
+/// Run a PowerShell command elevated (shows a UAC prompt) and wait for it
+fn run_elevated(script: &str) -> anyhow::Result<()> {
+    let verb = to_wide_string("runas");
+    let file = to_wide_string("powershell.exe");
+    let params = to_wide_string(&format!(
+        "-NoProfile -NonInteractive -WindowStyle Hidden -Command \"{}\"",
+        script.replace('"', "\\\"")
+    ));
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS,
+        lpVerb: PCWSTR(verb.as_ptr()),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(params.as_ptr()),
+        nShow: SW_HIDE.0,
+        ..Default::default()
+    };
+    unsafe {
+        // Fails when the UAC prompt is declined
+        ShellExecuteExW(&mut info)?;
+        if info.hProcess.is_invalid() {
+            return Ok(());
+        }
+        WaitForSingleObject(info.hProcess, ELEVATED_TIMEOUT_MS);
+        let mut code = 0u32;
+        let _ = GetExitCodeProcess(info.hProcess, &mut code);
+        let _ = CloseHandle(info.hProcess);
+        if code != 0 {
+            anyhow::bail!("PowerShell exited with {}", code);
+        }
+    }
+    Ok(())
+}
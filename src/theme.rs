@@ -1,10 +1,12 @@
 //! Theming system for TopBar
 //!
-//! Handles light/dark themes, colors, and visual styling.
+//! Handles light/dark themes, user-defined themes from the config, colors, and
+//! visual styling.
 
 #![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::COLORREF;
@@ -12,12 +14,16 @@ use windows::Win32::System::Registry::{
     RegOpenKeyExW, RegQueryValueExW, HKEY_CURRENT_USER, KEY_READ,
 };
 
+use crate::config::{AppearanceConfig, CustomTheme};
+
 /// Theme mode setting
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
 pub enum ThemeMode {
     Light,
     Dark,
     Transparent,
+    /// Windows' contrast colors, whether or not a contrast theme is on
+    HighContrast,
     #[default]
     Auto,
 }
@@ -124,6 +130,8 @@ pub struct Theme {
     pub name: String,
     /// Whether this is a dark theme
     pub is_dark: bool,
+    /// Windows high contrast is on: system colors only, fully opaque, no effects
+    pub forced_colors: bool,
 
     // Background colors
     pub background: Color,
@@ -181,6 +189,7 @@ impl Theme {
         Self {
             name: "Light".to_string(),
             is_dark: false,
+            forced_colors: false,
 
             // macOS Big Sur-inspired translucent white
             background: Color::new(252, 252, 254, 230), // Brighter, more translucent
@@ -238,6 +247,7 @@ impl Theme {
         Self {
             name: "Dark".to_string(),
             is_dark: true,
+            forced_colors: false,
 
             // macOS Monterey-inspired dark glass
             background: Color::new(30, 30, 32, 245), // Rich dark with high opacity
@@ -290,11 +300,84 @@ impl Theme {
         }
     }
 
+    /// Create the high contrast theme from the current Windows system colors.
+    ///
+    /// Every color is opaque and comes from the active contrast theme, so text
+    /// stays readable whatever palette the user picked. Status colors map to the
+    /// highlight color rather than red/green, which high contrast themes don't define.
+    pub fn high_contrast() -> Self {
+        use windows::Win32::Graphics::Gdi::{
+            GetSysColor, COLOR_GRAYTEXT, COLOR_HIGHLIGHT, COLOR_HIGHLIGHTTEXT, COLOR_HOTLIGHT,
+            COLOR_WINDOW, COLOR_WINDOWTEXT, SYS_COLOR_INDEX,
+        };
+
+        let sys = |index: SYS_COLOR_INDEX| {
+            let c = unsafe { GetSysColor(index) };
+            Color::rgb((c & 0xFF) as u8, ((c >> 8) & 0xFF) as u8, ((c >> 16) & 0xFF) as u8)
+        };
+        let window = sys(COLOR_WINDOW);
+        let text = sys(COLOR_WINDOWTEXT);
+        let highlight = sys(COLOR_HIGHLIGHT);
+        let highlight_text = sys(COLOR_HIGHLIGHTTEXT);
+        let disabled = sys(COLOR_GRAYTEXT);
+        let link = sys(COLOR_HOTLIGHT);
+
+        Self {
+            name: "High Contrast".to_string(),
+            is_dark: window.is_dark(),
+            forced_colors: true,
+
+            // No hover tints: a subtle fill would be invisible or hurt contrast
+            background: window,
+            background_secondary: window,
+            background_hover: window,
+            background_active: window,
+
+            text_primary: text,
+            text_secondary: text,
+            text_disabled: disabled,
+            text_accent: link,
+
+            accent: highlight,
+            accent_hover: highlight,
+            accent_active: highlight,
+
+            border: text,
+            border_hover: highlight,
+
+            success: text,
+            warning: highlight,
+            error: highlight,
+            info: highlight,
+
+            shadow: text,
+            overlay: highlight_text,
+
+            battery_full: text,
+            battery_medium: text,
+            battery_low: highlight,
+            battery_critical: highlight,
+            battery_charging: text,
+
+            network_connected: text,
+            network_disconnected: disabled,
+
+            cpu_normal: text,
+            cpu_high: highlight,
+            cpu_critical: highlight,
+
+            memory_normal: text,
+            memory_high: highlight,
+            memory_critical: highlight,
+        }
+    }
+
     /// Create the transparent theme
     pub fn transparent() -> Self {
         Self {
             name: "Transparent".to_string(),
             is_dark: true,
+            forced_colors: false,
 
             // Fully transparent background
             background: Color::new(0, 0, 0, 0),
@@ -347,6 +430,94 @@ impl Theme {
         }
     }
 
+    /// Layer a custom theme's colors over this one. Unknown names and colors that
+    /// aren't valid hex are logged and leave the built-in color in place.
+    pub fn with_custom(mut self, name: &str, custom: &CustomTheme) -> Self {
+        Self::validate_colors(&format!("Theme \"{}\"", name), &custom.colors);
+        self.apply_colors(&custom.colors);
+        if custom.colors.contains_key("background") {
+            self.is_dark = self.background.is_dark();
+        }
+        self.name = name.to_string();
+        self
+    }
+
+    /// Overwrite colors by their config names, skipping unknown names and invalid hex
+    pub fn apply_colors(&mut self, colors: &BTreeMap<String, String>) {
+        for (key, value) in colors {
+            if let (Some(color), Some(slot)) = (Color::from_hex(value), self.color_mut(key)) {
+                *slot = color;
+            }
+        }
+    }
+
+    /// Log unknown names and invalid hex in a table of colors from the config
+    pub fn validate_colors(owner: &str, colors: &BTreeMap<String, String>) {
+        let mut probe = Theme::light();
+        for (key, value) in colors {
+            if probe.color_mut(key).is_none() {
+                log::warn!("{}: unknown color \"{}\"", owner, key);
+            } else if Color::from_hex(value).is_none() {
+                log::warn!("{}: invalid color {} = \"{}\"", owner, key, value);
+            }
+        }
+    }
+
+    /// Replace the accent, deriving the hover and pressed tints from it
+    pub fn with_accent(mut self, accent: Color) -> Self {
+        let accent = accent.with_alpha(255);
+        self.accent = accent;
+        if self.is_dark {
+            self.accent_hover = accent.lighten(0.15);
+            self.accent_active = accent.darken(0.15);
+            self.text_accent = accent.lighten(0.2);
+        } else {
+            self.accent_hover = accent.darken(0.1);
+            self.accent_active = accent.darken(0.2);
+            self.text_accent = accent.darken(0.1);
+        }
+        self
+    }
+
+    /// The color with the given config name
+    fn color_mut(&mut self, key: &str) -> Option<&mut Color> {
+        Some(match key {
+            "background" => &mut self.background,
+            "background_secondary" => &mut self.background_secondary,
+            "background_hover" => &mut self.background_hover,
+            "background_active" => &mut self.background_active,
+            "text_primary" => &mut self.text_primary,
+            "text_secondary" => &mut self.text_secondary,
+            "text_disabled" => &mut self.text_disabled,
+            "text_accent" => &mut self.text_accent,
+            "accent" => &mut self.accent,
+            "accent_hover" => &mut self.accent_hover,
+            "accent_active" => &mut self.accent_active,
+            "border" => &mut self.border,
+            "border_hover" => &mut self.border_hover,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "info" => &mut self.info,
+            "shadow" => &mut self.shadow,
+            "overlay" => &mut self.overlay,
+            "battery_full" => &mut self.battery_full,
+            "battery_medium" => &mut self.battery_medium,
+            "battery_low" => &mut self.battery_low,
+            "battery_critical" => &mut self.battery_critical,
+            "battery_charging" => &mut self.battery_charging,
+            "network_connected" => &mut self.network_connected,
+            "network_disconnected" => &mut self.network_disconnected,
+            "cpu_normal" => &mut self.cpu_normal,
+            "cpu_high" => &mut self.cpu_high,
+            "cpu_critical" => &mut self.cpu_critical,
+            "memory_normal" => &mut self.memory_normal,
+            "memory_high" => &mut self.memory_high,
+            "memory_critical" => &mut self.memory_critical,
+            _ => return None,
+        })
+    }
+
     /// Get color for CPU usage percentage
     pub fn cpu_color(&self, usage: f32) -> Color {
         if usage >= 90.0 {
@@ -389,31 +560,33 @@ impl Theme {
 pub struct ThemeManager {
     current_theme: Theme,
     mode: ThemeMode,
+    /// Selected custom theme from the config, with its name
+    custom: Option<(String, CustomTheme)>,
+    /// Accent from `appearance.accent_color`
+    accent_color: Option<Color>,
+    /// Follow the Windows accent color
+    system_accent: bool,
+    /// Last Windows accent color seen, while following it
+    system_accent_color: Option<Color>,
     system_is_dark: AtomicBool,
+    high_contrast: AtomicBool,
 }
 
 impl ThemeManager {
     /// Create a new theme manager
     pub fn new(mode: ThemeMode) -> Self {
-        let system_is_dark = detect_system_dark_mode();
-        let current_theme = match mode {
-            ThemeMode::Light => Theme::light(),
-            ThemeMode::Dark => Theme::dark(),
-            ThemeMode::Transparent => Theme::transparent(),
-            ThemeMode::Auto => {
-                if system_is_dark {
-                    Theme::dark()
-                } else {
-                    Theme::light()
-                }
-            }
-        };
-
-        Self {
-            current_theme,
+        let mut manager = Self {
+            current_theme: Theme::light(),
             mode,
-            system_is_dark: AtomicBool::new(system_is_dark),
-        }
+            custom: None,
+            accent_color: None,
+            system_accent: false,
+            system_accent_color: None,
+            system_is_dark: AtomicBool::new(detect_system_dark_mode()),
+            high_contrast: AtomicBool::new(detect_high_contrast()),
+        };
+        manager.update_theme();
+        manager
     }
 
     /// Get the current theme
@@ -432,12 +605,42 @@ impl ThemeManager {
         self.update_theme();
     }
 
+    /// Pick up the custom theme and accent settings from the config. An unknown
+    /// theme name is logged and falls back to the built-in themes; per-module colors
+    /// are checked here so mistakes are logged once rather than on every paint.
+    pub fn set_appearance(&mut self, appearance: &AppearanceConfig) {
+        self.custom = appearance.theme.as_deref().and_then(|name| match appearance.themes.get(name) {
+            Some(custom) => Some((name.to_string(), custom.clone())),
+            None => {
+                log::warn!("Theme \"{}\" not found in [appearance.themes], using built-in theme", name);
+                None
+            }
+        });
+        self.accent_color = appearance.accent_color.as_deref().and_then(|hex| {
+            let color = Color::from_hex(hex);
+            if color.is_none() {
+                log::warn!("Invalid accent_color \"{}\"", hex);
+            }
+            color
+        });
+        for (module, colors) in &appearance.module_colors {
+            let owner = format!("Colors for \"{}\"", module);
+            Theme::validate_colors(&owner, &colors.colors);
+            for rule in &colors.rules {
+                Theme::validate_colors(&owner, &rule.colors);
+            }
+        }
+        self.system_accent = appearance.system_accent;
+        self.system_accent_color = if self.system_accent { get_windows_accent_color() } else { None };
+        self.update_theme();
+    }
+
     /// Cycle through light, dark, and transparent themes
     pub fn toggle(&mut self) {
         self.mode = match self.mode {
             ThemeMode::Light => ThemeMode::Dark,
             ThemeMode::Dark => ThemeMode::Transparent,
-            ThemeMode::Transparent => ThemeMode::Light,
+            ThemeMode::Transparent | ThemeMode::HighContrast => ThemeMode::Light,
             ThemeMode::Auto => {
                 if self.system_is_dark.load(Ordering::Relaxed) {
                     ThemeMode::Dark
@@ -449,24 +652,50 @@ impl ThemeManager {
         self.update_theme();
     }
 
-    /// Check if system theme changed and update if in auto mode
+    /// Check if system theme or high contrast changed and update the theme if needed
     pub fn check_system_theme(&mut self) -> bool {
         let system_is_dark = detect_system_dark_mode();
         let prev = self.system_is_dark.swap(system_is_dark, Ordering::Relaxed);
+        let high_contrast = detect_high_contrast();
+        let prev_high_contrast = self.high_contrast.swap(high_contrast, Ordering::Relaxed);
 
+        // System colors can change while high contrast stays on (switching contrast themes)
+        if high_contrast || prev_high_contrast != high_contrast {
+            self.update_theme();
+            return true;
+        }
         if prev != system_is_dark && self.mode == ThemeMode::Auto {
             self.update_theme();
             return true;
         }
+        if self.system_accent {
+            let accent = get_windows_accent_color();
+            if accent != self.system_accent_color {
+                self.system_accent_color = accent;
+                self.update_theme();
+                return true;
+            }
+        }
         false
     }
 
-    /// Update the current theme based on mode
+    /// Update the current theme based on mode and custom theme (high contrast overrides both)
     fn update_theme(&mut self) {
-        self.current_theme = match self.mode {
+        if self.high_contrast.load(Ordering::Relaxed) || self.mode == ThemeMode::HighContrast {
+            self.current_theme = Theme::high_contrast();
+            return;
+        }
+        // A custom theme with an explicit base keeps it; "Auto" layers over the mode's theme
+        let mode = match &self.custom {
+            Some((_, custom)) if custom.base != ThemeMode::Auto => custom.base,
+            _ => self.mode,
+        };
+        let theme = match mode {
             ThemeMode::Light => Theme::light(),
             ThemeMode::Dark => Theme::dark(),
             ThemeMode::Transparent => Theme::transparent(),
+            // A custom theme based on the contrast colors
+            ThemeMode::HighContrast => Theme::high_contrast(),
             ThemeMode::Auto => {
                 if self.system_is_dark.load(Ordering::Relaxed) {
                     Theme::dark()
@@ -475,6 +704,15 @@ impl ThemeManager {
                 }
             }
         };
+        let theme = match &self.custom {
+            Some((name, custom)) => theme.with_custom(name, custom),
+            None => theme,
+        };
+        // The Windows accent wins over a configured one, which wins over the theme's
+        self.current_theme = match self.system_accent_color.or(self.accent_color) {
+            Some(accent) => theme.with_accent(accent),
+            None => theme,
+        };
     }
 
     /// Check if currently using dark theme
@@ -527,7 +765,30 @@ fn detect_system_dark_mode() -> bool {
     }
 }
 
-/// Get Windows accent color
+/// Detect if Windows high contrast mode is on
+pub fn detect_high_contrast() -> bool {
+    use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut hc = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            hc.cbSize,
+            Some(&mut hc as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+        .is_ok()
+            && (hc.dwFlags & HCF_HIGHCONTRASTON).0 != 0
+    }
+}
+
+/// Get the Windows accent color (DWM colorization color), as ARGB
 pub fn get_windows_accent_color() -> Option<Color> {
     unsafe {
         let mut key = windows::Win32::System::Registry::HKEY::default();
@@ -0,0 +1,164 @@
+//! Text translation
+//!
+//! Sends text to a LibreTranslate-compatible server (`[translate]` in the config)
+//! and keeps the most recent results, so translating the same text again doesn't
+//! go back to the network and past translations can be copied from the clipboard
+//! menu. The `translate` action grabs the selection in the focused app (or takes
+//! the clipboard when nothing is selected) and shows the result in a popup.
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::config::TranslateConfig;
+
+/// Translations kept for reuse
+const CACHE_SIZE: usize = 50;
+
+/// A finished translation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub source_text: String,
+    pub text: String,
+    /// Language the text was in, when the server detected it
+    pub detected: Option<String>,
+    /// Language it was translated into
+    pub target: String,
+}
+
+static CACHE: OnceCell<Mutex<VecDeque<Translation>>> = OnceCell::new();
+
+fn cache() -> &'static Mutex<VecDeque<Translation>> {
+    CACHE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Recent translations, newest first
+pub fn recent() -> Vec<Translation> {
+    cache().lock().iter().cloned().collect()
+}
+
+/// Translate `text` into `target` (the configured language when empty). Blocks on
+/// the network unless the same text was translated recently.
+pub fn translate(config: &TranslateConfig, text: &str, target: &str) -> Result<Translation, String> {
+    let target = if target.is_empty() { config.target.as_str() } else { target };
+
+    {
+        let mut cache = cache().lock();
+        if let Some(pos) = cache.iter().position(|t| t.source_text == text && t.target == target) {
+            let hit = cache.remove(pos).expect("position is in range");
+            cache.push_front(hit.clone());
+            return Ok(hit);
+        }
+    }
+
+    let translation = request(config, text, target)?;
+    let mut cache = cache().lock();
+    cache.push_front(translation.clone());
+    cache.truncate(CACHE_SIZE);
+    Ok(translation)
+}
+
+fn request(config: &TranslateConfig, text: &str, target: &str) -> Result<Translation, String> {
+    if config.url.is_empty() {
+        return Err("No translation server set in [translate]".to_string());
+    }
+    if crate::modules::offline_mode() {
+        return Err("Offline mode is on".to_string());
+    }
+    let mut body = serde_json::json!({
+        "q": text,
+        "source": config.source,
+        "target": target,
+        "format": "text",
+    });
+    if !config.api_key.is_empty() {
+        body["api_key"] = serde_json::Value::String(config.api_key.clone());
+    }
+
+    let response = ureq::post(&config.url)
+        .set("User-Agent", "TopBar/1.0")
+        .timeout(Duration::from_secs(15))
+        .send_json(body);
+    let json: serde_json::Value = match response {
+        Ok(response) => response
+            .into_json()
+            .map_err(|e| format!("Failed to read response: {}", e))?,
+        // The server explains rejected requests (bad key, unknown language) in the body
+        Err(ureq::Error::Status(code, response)) => {
+            let message = response
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|json| json["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| format!("HTTP {}", code));
+            return Err(message);
+        }
+        Err(e) => return Err(format!("HTTP error: {}", e)),
+    };
+    parse(text, target, &json)
+}
+
+/// The translation in a LibreTranslate response
+fn parse(text: &str, target: &str, json: &serde_json::Value) -> Result<Translation, String> {
+    let translated = json["translatedText"]
+        .as_str()
+        .ok_or("Missing translatedText in response")?;
+    Ok(Translation {
+        source_text: text.to_string(),
+        text: translated.to_string(),
+        detected: json["detectedLanguage"]["language"].as_str().map(str::to_string),
+        target: target.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(source_text: &str, target: &str) -> Translation {
+        Translation {
+            source_text: source_text.to_string(),
+            text: format!("{} ({})", source_text, target),
+            detected: None,
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_response() {
+        let json = serde_json::json!({
+            "translatedText": "Hallo",
+            "detectedLanguage": { "confidence": 90, "language": "en" },
+        });
+        let result = parse("Hello", "de", &json).expect("translation");
+        assert_eq!(result.text, "Hallo");
+        assert_eq!(result.detected.as_deref(), Some("en"));
+        assert_eq!(result.target, "de");
+
+        let plain = parse("Hello", "de", &serde_json::json!({ "translatedText": "Hallo" })).unwrap();
+        assert_eq!(plain.detected, None);
+        assert!(parse("Hello", "de", &serde_json::json!({ "error": "Invalid API key" })).is_err());
+    }
+
+    #[test]
+    fn reuses_recent_translations() {
+        // No server, so anything not in the cache fails
+        let config = TranslateConfig {
+            url: String::new(),
+            target: "fr".to_string(),
+            ..Default::default()
+        };
+        {
+            let mut cache = cache().lock();
+            cache.push_front(translation("cache test one", "fr"));
+            cache.push_front(translation("cache test two", "de"));
+        }
+
+        // An empty target means the configured one
+        let hit = translate(&config, "cache test one", "").expect("cached");
+        assert_eq!(hit, translation("cache test one", "fr"));
+        assert_eq!(recent().first(), Some(&hit));
+        assert!(translate(&config, "cache test two", "fr").is_err());
+        assert!(translate(&config, "cache test two", "de").is_ok());
+    }
+}
@@ -7,14 +7,16 @@ use log::{debug, info};
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::{HWND, LPARAM};
 use windows::Win32::UI::Shell::{
-    Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
-    NOTIFYICONDATAW,
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_ERROR, NIIF_INFO,
+    NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
     DestroyIcon, LoadImageW, HICON, IMAGE_ICON, LR_DEFAULTSIZE, LR_SHARED,
 };
 
-use crate::utils::to_wide_string;
+use crate::attention::AttentionLevel;
+use crate::sounds::SoundEvent;
+use crate::utils::{to_wide_string, to_wide_truncated};
 use crate::window::WM_TOPBAR_TRAY;
 
 /// Tray icon identifier
@@ -76,8 +78,7 @@ impl TrayIcon {
         };
 
         // Copy tooltip
-        let tooltip_len = tooltip.len().min(128);
-        nid.szTip[..tooltip_len].copy_from_slice(&tooltip[..tooltip_len]);
+        nid.szTip[..tooltip.len()].copy_from_slice(&tooltip);
 
         unsafe {
             if !Shell_NotifyIconW(NIM_ADD, &nid).as_bool() {
@@ -122,7 +123,7 @@ impl TrayIcon {
             return Ok(());
         }
 
-        let tooltip = to_wide_string(text);
+        let tooltip = to_wide_truncated(text, 128);
 
         let mut nid = NOTIFYICONDATAW {
             cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
@@ -132,8 +133,7 @@ impl TrayIcon {
             ..Default::default()
         };
 
-        let tooltip_len = tooltip.len().min(128);
-        nid.szTip[..tooltip_len].copy_from_slice(&tooltip[..tooltip_len]);
+        nid.szTip[..tooltip.len()].copy_from_slice(&tooltip);
 
         unsafe {
             if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
@@ -196,6 +196,71 @@ impl TrayIcon {
     }
 }
 
+/// Show a notification balloon from the tray icon owned by `hwnd`.
+///
+/// Fails if the tray icon hasn't been added (it's optional at startup).
+pub fn show_balloon(hwnd: HWND, title: &str, text: &str, level: AttentionLevel) -> Result<()> {
+    // Cut to fit the balloon's buffers, leaving room for the terminating null
+    let title = to_wide_truncated(title, 64);
+    let text = to_wide_truncated(text, 256);
+
+    let mut nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: TRAY_ICON_ID,
+        uFlags: NIF_INFO,
+        dwInfoFlags: match level {
+            AttentionLevel::Info => NIIF_INFO,
+            AttentionLevel::Warning => NIIF_WARNING,
+            AttentionLevel::Error => NIIF_ERROR,
+        },
+        ..Default::default()
+    };
+
+    nid.szInfoTitle[..title.len()].copy_from_slice(&title);
+    nid.szInfo[..text.len()].copy_from_slice(&text);
+
+    unsafe {
+        if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
+            return Err(anyhow::anyhow!("Failed to show tray notification"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Swap the tray icon owned by `hwnd` between the warning icon and the default icon
+pub fn set_attention_icon(hwnd: HWND, attention: bool) -> Result<()> {
+    use windows::Win32::UI::WindowsAndMessaging::{IDI_APPLICATION, IDI_WARNING};
+
+    let icon = unsafe {
+        LoadImageW(
+            None,
+            if attention { IDI_WARNING } else { IDI_APPLICATION },
+            IMAGE_ICON,
+            0,
+            0,
+            LR_DEFAULTSIZE | LR_SHARED,
+        )?
+    };
+    let nid = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: TRAY_ICON_ID,
+        uFlags: NIF_ICON,
+        hIcon: HICON(icon.0),
+        ..Default::default()
+    };
+
+    unsafe {
+        if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
+            return Err(anyhow::anyhow!("Failed to update tray icon"));
+        }
+    }
+
+    Ok(())
+}
+
 impl Drop for TrayIcon {
     fn drop(&mut self) {
         let _ = self.remove();
@@ -301,7 +366,7 @@ impl TrayMenu {
                 if item.is_separator {
                     InsertMenuW(menu, u32::MAX, flags, 0, PCWSTR::null()).ok()?;
                 } else {
-                    let label = to_wide_string(&item.label);
+                    let label = to_wide_string(&crate::i18n::tr(&item.label));
                     InsertMenuW(
                         menu,
                         u32::MAX,
@@ -318,6 +383,7 @@ impl TrayMenu {
 
             let _ = SetForegroundWindow(hwnd);
 
+            crate::sounds::play_event(SoundEvent::PopupOpen);
             let cmd = TrackPopupMenu(
                 menu,
                 TPM_RIGHTBUTTON | TPM_RETURNCMD,
@@ -327,6 +393,7 @@ impl TrayMenu {
                 hwnd,
                 None,
             );
+            crate::sounds::play_event(SoundEvent::PopupClose);
 
             DestroyMenu(menu).ok()?;
 
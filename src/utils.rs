@@ -3,6 +3,7 @@
 #![allow(dead_code)]
 
 use std::ffi::OsStr;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::os::windows::ffi::OsStrExt;
 use windows::core::{w, PCWSTR};
 
@@ -62,9 +63,26 @@ pub fn format_percentage(value: f32) -> String {
     }
 }
 
-/// Calculate DPI scaling factor
+/// Interface size multiplier in percent (large-UI mode), on top of DPI scaling
+static UI_SCALE_PERCENT: AtomicU32 = AtomicU32::new(100);
+
+/// Interface size presets offered in the menu
+pub const UI_SCALE_PRESETS: [f32; 3] = [1.0, 1.25, 1.5];
+
+/// Set the interface size multiplier (clamped to 1.0-2.0)
+pub fn set_ui_scale(scale: f32) {
+    let percent = (scale.clamp(1.0, 2.0) * 100.0).round() as u32;
+    UI_SCALE_PERCENT.store(percent, Ordering::Relaxed);
+}
+
+/// Current interface size multiplier
+pub fn ui_scale() -> f32 {
+    UI_SCALE_PERCENT.load(Ordering::Relaxed) as f32 / 100.0
+}
+
+/// Calculate the scaling factor for a DPI, including the interface size multiplier
 pub fn get_dpi_scale(dpi: u32) -> f32 {
-    dpi as f32 / 96.0
+    dpi as f32 / 96.0 * ui_scale()
 }
 
 /// Scale a value by DPI
@@ -73,7 +91,7 @@ pub fn scale_by_dpi(value: i32, dpi: u32) -> i32 {
 }
 
 /// Rectangle structure for layout calculations
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -284,14 +302,63 @@ impl Animator {
     }
 }
 
-/// Truncate string to fit width with ellipsis
+/// Truncate string to `max_chars` user-visible characters with an ellipsis.
+/// Cuts between graphemes, so accents, emoji sequences and flags stay whole.
 pub fn truncate_string(s: &str, max_chars: usize) -> String {
-    if s.chars().count() <= max_chars {
-        s.to_string()
-    } else {
-        let truncated: String = s.chars().take(max_chars - 1).collect();
-        format!("{}…", truncated)
+    use unicode_segmentation::UnicodeSegmentation;
+
+    if s.graphemes(true).nth(max_chars).is_none() {
+        return s.to_string();
+    }
+    let end = s
+        .grapheme_indices(true)
+        .nth(max_chars.saturating_sub(1))
+        .map(|(i, _)| i)
+        .unwrap_or(s.len());
+    format!("{}…", s[..end].trim_end())
+}
+
+/// Null-terminated UTF-16 for a fixed-size Win32 buffer of `capacity` units,
+/// truncated between graphemes with an ellipsis when it doesn't fit
+pub fn to_wide_truncated(s: &str, capacity: usize) -> Vec<u16> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    // No room for an ellipsis and the null; a buffer of one just gets the null
+    if capacity < 2 {
+        return vec![0; capacity];
+    }
+    let mut wide: Vec<u16> = s.encode_utf16().collect();
+    if wide.len() >= capacity {
+        // Room for the ellipsis and the null
+        let limit = capacity.saturating_sub(2);
+        wide.clear();
+        for grapheme in s.graphemes(true) {
+            if wide.len() + grapheme.encode_utf16().count() > limit {
+                break;
+            }
+            wide.extend(grapheme.encode_utf16());
+        }
+        wide.push('…' as u16);
     }
+    wide.push(0);
+    wide
+}
+
+/// Whether a character belongs to a right-to-left script (Hebrew, Arabic,
+/// Syriac, Thaana, N'Ko and their presentation forms)
+fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF)
+}
+
+/// Whether `s` reads right to left: its first letter is from an RTL script
+pub fn is_rtl(s: &str) -> bool {
+    s.chars().find(|c| c.is_alphabetic()).is_some_and(is_rtl_char)
+}
+
+/// Whether `s` has any right-to-left text in it
+pub fn has_rtl(s: &str) -> bool {
+    s.chars().any(is_rtl_char)
 }
 
 /// Get the primary monitor work area
@@ -347,6 +414,149 @@ pub fn open_url(url: &str) {
     }
 }
 
+/// Put files on the clipboard (CF_HDROP), so they can be pasted in Explorer
+pub fn copy_files_to_clipboard(paths: &[String]) -> windows::core::Result<()> {
+    use windows::Win32::Foundation::{GlobalFree, HANDLE, HWND};
+    use windows::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData};
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GHND};
+    use windows::Win32::UI::Shell::DROPFILES;
+
+    /// Standard clipboard format for a file list
+    const CF_HDROP: u32 = 15;
+
+    // DROPFILES header, then the paths as NUL-separated UTF-16 ending in a double NUL
+    let mut list: Vec<u16> = Vec::new();
+    for path in paths {
+        list.extend(OsStr::new(path).encode_wide());
+        list.push(0);
+    }
+    list.push(0);
+    let header = std::mem::size_of::<DROPFILES>();
+    let size = header + list.len() * 2;
+
+    unsafe {
+        let memory = GlobalAlloc(GHND, size)?;
+        let data = GlobalLock(memory);
+        if data.is_null() {
+            let _ = GlobalFree(memory);
+            return Err(windows::core::Error::from_win32());
+        }
+        let drop_files = DROPFILES {
+            pFiles: header as u32,
+            fWide: true.into(),
+            ..Default::default()
+        };
+        std::ptr::write_unaligned(data as *mut DROPFILES, drop_files);
+        std::ptr::copy_nonoverlapping(list.as_ptr(), (data as *mut u8).add(header) as *mut u16, list.len());
+        let _ = GlobalUnlock(memory);
+
+        OpenClipboard(HWND::default())?;
+        let result = EmptyClipboard().and_then(|_| SetClipboardData(CF_HDROP, HANDLE(memory.0)));
+        let _ = CloseClipboard();
+        if result.is_err() {
+            // The clipboard only takes ownership on success
+            let _ = GlobalFree(memory);
+        }
+        result.map(|_| ())
+    }
+}
+
+/// Press Ctrl+`key` in the foreground window, e.g. `b'C'` to copy its selection.
+///
+/// Shift, Alt and Win still held from the hotkey that got us here are lifted
+/// while Ctrl is down (so Alt doesn't open the app's menu), letting the app see
+/// exactly Ctrl+key.
+pub fn send_ctrl_key(key: u8) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    let event = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+    let mut inputs = vec![event(VK_CONTROL, KEYBD_EVENT_FLAGS(0))];
+    for held in [VK_SHIFT, VK_MENU, VK_LWIN, VK_RWIN] {
+        if unsafe { GetAsyncKeyState(held.0 as i32) } < 0 {
+            inputs.push(event(held, KEYEVENTF_KEYUP));
+        }
+    }
+    inputs.push(event(VIRTUAL_KEY(key as u16), KEYBD_EVENT_FLAGS(0)));
+    inputs.push(event(VIRTUAL_KEY(key as u16), KEYEVENTF_KEYUP));
+    inputs.push(event(VK_CONTROL, KEYEVENTF_KEYUP));
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+/// Press Win+`key`, e.g. `b'D'` to show the desktop
+pub fn send_win_key(key: u8) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+        VK_LWIN,
+    };
+
+    let event = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+    let inputs = [
+        event(VK_LWIN, KEYBD_EVENT_FLAGS(0)),
+        event(VIRTUAL_KEY(key as u16), KEYBD_EVENT_FLAGS(0)),
+        event(VIRTUAL_KEY(key as u16), KEYEVENTF_KEYUP),
+        event(VK_LWIN, KEYEVENTF_KEYUP),
+    ];
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+/// Text selected in the focused app, copied with Ctrl+C; when nothing gets
+/// copied, whatever text is already on the clipboard
+pub fn copy_selection() -> Option<String> {
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    // How long to wait for the app to answer Ctrl+C
+    const COPY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(400);
+
+    let before = unsafe { GetClipboardSequenceNumber() };
+    send_ctrl_key(b'C');
+    let started = std::time::Instant::now();
+    while unsafe { GetClipboardSequenceNumber() } == before && started.elapsed() < COPY_TIMEOUT {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    let text = arboard::Clipboard::new().and_then(|mut cb| cb.get_text()).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Percent-encode a string for use in a URL query
+pub fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
 /// Check if running with administrator privileges
 pub fn is_elevated() -> bool {
     use windows::Win32::Security::{
@@ -394,6 +604,34 @@ pub fn is_on_battery() -> bool {
     false
 }
 
+/// Check if battery saver is on
+pub fn is_battery_saver_on() -> bool {
+    unsafe {
+        use windows::Win32::System::Power::GetSystemPowerStatus;
+        let mut status = windows::Win32::System::Power::SYSTEM_POWER_STATUS::default();
+        if GetSystemPowerStatus(&mut status).is_ok() {
+            // SystemStatusFlag: 1 = battery saver on
+            return status.SystemStatusFlag == 1;
+        }
+    }
+    false
+}
+
+/// Get currently available physical memory in bytes
+pub fn available_memory_bytes() -> u64 {
+    unsafe {
+        use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+        let mut status = MEMORYSTATUSEX {
+            dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+            ..Default::default()
+        };
+        if GlobalMemoryStatusEx(&mut status).is_ok() {
+            return status.ullAvailPhys;
+        }
+    }
+    0
+}
+
 /// Play a system beep for volume feedback
 pub fn play_volume_feedback_sound() {
     use std::io::BufReader;
@@ -605,3 +843,56 @@ exit 2
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    const FLAGS: &str = "\u{1F1E9}\u{1F1EA}\u{1F1EB}\u{1F1F7}\u{1F1EA}\u{1F1F8}";
+
+    #[test]
+    fn truncate_keeps_graphemes_whole() {
+        // "e" plus a combining acute accent is one character on screen
+        let accents = "e\u{301}e\u{301}e\u{301}";
+        assert_eq!(truncate_string(accents, 2), "e\u{301}…");
+        assert_eq!(truncate_string(&format!("a{}bc", FAMILY), 3), format!("a{}…", FAMILY));
+        assert_eq!(truncate_string(FLAGS, 2), "\u{1F1E9}\u{1F1EA}…");
+    }
+
+    #[test]
+    fn truncate_limits() {
+        assert_eq!(truncate_string("abc", 0), "…");
+        assert_eq!(truncate_string("", 0), "");
+        // Exactly at the limit stays as it is
+        assert_eq!(truncate_string("hello", 5), "hello");
+        assert_eq!(truncate_string(FLAGS, 3), FLAGS);
+        assert_eq!(truncate_string("hello world", 7), "hello…");
+    }
+
+    #[test]
+    fn wide_truncated_fits_the_buffer() {
+        assert_eq!(to_wide_truncated("abc", 10), vec!['a' as u16, 'b' as u16, 'c' as u16, 0]);
+        assert_eq!(to_wide_truncated("abc", 0), Vec::<u16>::new());
+        assert_eq!(to_wide_truncated("abc", 1), vec![0]);
+        assert_eq!(to_wide_truncated("abc", 2), vec!['…' as u16, 0]);
+        for capacity in 0..12 {
+            for text in ["abcdef", FAMILY, FLAGS] {
+                assert!(to_wide_truncated(text, capacity).len() <= capacity, "{:?} in {}", text, capacity);
+            }
+        }
+    }
+
+    #[test]
+    fn wide_truncated_keeps_graphemes_whole() {
+        // Each flag is four UTF-16 units; the second doesn't fit beside the ellipsis
+        let mut expected: Vec<u16> = "\u{1F1E9}\u{1F1EA}".encode_utf16().collect();
+        expected.extend(['…' as u16, 0]);
+        assert_eq!(to_wide_truncated(FLAGS, 7), expected);
+
+        let accents = "e\u{301}e\u{301}e\u{301}";
+        let mut expected: Vec<u16> = "e\u{301}e\u{301}".encode_utf16().collect();
+        expected.extend(['…' as u16, 0]);
+        assert_eq!(to_wide_truncated(accents, 6), expected);
+    }
+}
@@ -0,0 +1,170 @@
+//! Bar visibility rules
+//!
+//! Gets the bar out of the way of games, video players and presentations: it
+//! hides (or dims) while an app is full screen on the bar's monitor, and while an
+//! app listed in `[[behavior.app_rules]]` is focused. It comes back as soon as
+//! focus moves on. A dimmed bar is back at full strength while the pointer is
+//! over it.
+//!
+//! The main window's 100ms timer calls [`tick`], which is quick enough to follow
+//! focus changes without hooking them.
+
+use log::debug;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use windows::Win32::Foundation::{COLORREF, HWND, POINT, RECT};
+use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, PtInRect, MONITORINFO, MONITOR_DEFAULTTONEAREST};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::config::{BarVisibility, BehaviorConfig, Config};
+use crate::window::state::get_window_state;
+
+/// Opacity of a dimmed bar
+const DIM_ALPHA: u8 = 64;
+
+/// Visibility currently applied to the bar (a `BarVisibility` as u8)
+static APPLIED: AtomicU8 = AtomicU8::new(0);
+/// The focused window and its executable name, so the name is only looked up
+/// when focus changes
+static FOREGROUND: Mutex<Option<(isize, String)>> = Mutex::new(None);
+
+fn applied() -> BarVisibility {
+    match APPLIED.load(Ordering::Relaxed) {
+        1 => BarVisibility::Dim,
+        2 => BarVisibility::Hide,
+        _ => BarVisibility::Show,
+    }
+}
+
+/// Hide, dim or restore the bar for the focused app (called every 100ms)
+pub fn tick(hwnd: HWND, config: &Config) {
+    let mut wanted = wanted(hwnd, &config.behavior);
+    if wanted == BarVisibility::Dim && pointer_over(hwnd) {
+        wanted = BarVisibility::Show;
+    }
+    let from = applied();
+    if wanted == from {
+        return;
+    }
+    debug!("Bar visibility: {:?} -> {:?}", from, wanted);
+    APPLIED.store(wanted as u8, Ordering::Relaxed);
+    apply(hwnd, from, wanted);
+}
+
+/// What the rules ask for with the current foreground window
+fn wanted(bar: HWND, behavior: &BehaviorConfig) -> BarVisibility {
+    let foreground = unsafe { GetForegroundWindow() };
+    // Clicking the bar focuses it, which lifts whatever applied before
+    if foreground.0.is_null() || foreground == bar {
+        return BarVisibility::Show;
+    }
+
+    if !behavior.app_rules.is_empty() {
+        let name = process_name(foreground);
+        let stem = name.strip_suffix(".exe").unwrap_or(&name);
+        let rule = behavior.app_rules.iter().find(|rule| {
+            let process = rule.process.trim().to_lowercase();
+            !process.is_empty() && (process == name || process == stem)
+        });
+        if let Some(rule) = rule {
+            return rule.action;
+        }
+    }
+
+    if behavior.fullscreen != BarVisibility::Show && is_fullscreen(foreground, bar) {
+        return behavior.fullscreen;
+    }
+    BarVisibility::Show
+}
+
+/// Lowercase executable name of the process owning a window
+fn process_name(hwnd: HWND) -> String {
+    let mut cached = FOREGROUND.lock();
+    if let Some((cached_hwnd, name)) = cached.as_ref() {
+        if *cached_hwnd == hwnd.0 as isize {
+            return name.clone();
+        }
+    }
+    let path = crate::modules::active_window::window_process_path(hwnd);
+    let name = std::path::Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    *cached = Some((hwnd.0 as isize, name.clone()));
+    name
+}
+
+/// Whether a window covers the whole monitor the bar is on, or an exclusive
+/// full-screen Direct3D app is running
+fn is_fullscreen(window: HWND, bar: HWND) -> bool {
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_RUNNING_D3D_FULL_SCREEN};
+
+    unsafe {
+        if SHQueryUserNotificationState().is_ok_and(|s| s == QUNS_RUNNING_D3D_FULL_SCREEN) {
+            return true;
+        }
+
+        // The desktop covers the screen too
+        let mut class = [0u16; 32];
+        let len = GetClassNameW(window, &mut class) as usize;
+        let class = String::from_utf16_lossy(&class[..len]);
+        if class == "Progman" || class == "WorkerW" {
+            return false;
+        }
+
+        let monitor = MonitorFromWindow(bar, MONITOR_DEFAULTTONEAREST);
+        if MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST) != monitor {
+            return false;
+        }
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let mut rect = RECT::default();
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() || GetWindowRect(window, &mut rect).is_err() {
+            return false;
+        }
+        let screen = info.rcMonitor;
+        rect.left <= screen.left && rect.top <= screen.top && rect.right >= screen.right && rect.bottom >= screen.bottom
+    }
+}
+
+fn pointer_over(hwnd: HWND) -> bool {
+    unsafe {
+        let mut point = POINT::default();
+        let mut rect = RECT::default();
+        GetCursorPos(&mut point).is_ok()
+            && GetWindowRect(hwnd, &mut rect).is_ok()
+            && PtInRect(&rect, point).as_bool()
+    }
+}
+
+fn apply(hwnd: HWND, from: BarVisibility, to: BarVisibility) {
+    // A bar the user hid stays hidden; one they bring back while a rule applies stays up
+    let visible = get_window_state().map(|s| s.read().is_visible).unwrap_or(true);
+    unsafe {
+        if from == BarVisibility::Dim {
+            // Back to the configured style (layered or translucent)
+            let theme = get_window_state().map(|s| s.read().theme_manager.theme().clone());
+            if let Some(theme) = theme {
+                let _ = crate::window::manager::WindowManager::apply_window_style(hwnd, &theme);
+            }
+        }
+        match to {
+            BarVisibility::Hide => {
+                if visible {
+                    let _ = ShowWindow(hwnd, SW_HIDE);
+                }
+            }
+            BarVisibility::Dim => {
+                let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_LAYERED.0 as isize);
+                let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), DIM_ALPHA, LWA_ALPHA);
+            }
+            BarVisibility::Show => {}
+        }
+        if from == BarVisibility::Hide && to != BarVisibility::Hide && visible {
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        }
+    }
+}
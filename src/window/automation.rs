@@ -0,0 +1,337 @@
+//! UI Automation for the bar, so screen readers can read and use it
+//!
+//! The bar is a toolbar whose children are its visible modules, each a button
+//! named after the module ("Battery") with what it shows as its value ("73%")
+//! and its tooltip as help text. Invoking one does what clicking it does.
+//!
+//! UI Automation calls providers from its own threads, while modules and their
+//! bounds live with the renderer on the window's thread. The window keeps a
+//! snapshot of the modules here instead (refreshed when a client asks for the
+//! bar and after each paint while one is listening), and an invoke is posted
+//! back to the window as `WM_TOPBAR_MODULE_CLICK`.
+
+// UI Automation's ids are matched by their Windows names
+#![allow(non_upper_case_globals)]
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use log::debug;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use windows::core::{implement, Error, Result, BSTR, PCWSTR, VARIANT};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Com::SAFEARRAY;
+use windows::Win32::System::Ole::{SafeArrayCreateVector, SafeArrayPutElement};
+use windows::Win32::System::Variant::VT_I4;
+use windows::Win32::UI::Accessibility::*;
+use windows::Win32::UI::WindowsAndMessaging::PostMessageW;
+
+use crate::config::Config;
+use crate::render::Renderer;
+use crate::utils::Rect;
+
+use super::proc::WM_TOPBAR_MODULE_CLICK;
+
+/// A module as UI Automation sees it
+#[derive(Clone, PartialEq)]
+struct Element {
+    id: String,
+    name: String,
+    value: String,
+    help: String,
+    /// On screen
+    bounds: Rect,
+    /// Where a click on it lands, in the bar's layout
+    click_x: i32,
+}
+
+/// The bar's modules in reading order
+static ELEMENTS: Lazy<Mutex<Vec<Element>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Modules invoked through UI Automation, waiting for the window to click them
+static INVOKED: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Whether a WM_GETOBJECT is UI Automation asking for the bar
+pub fn is_root_request(lparam: LPARAM) -> bool {
+    lparam.0 as i32 == UiaRootObjectId
+}
+
+/// Answer UI Automation's WM_GETOBJECT with the bar's provider; `update` the
+/// snapshot first so a new client sees the current modules
+pub fn provide(hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let provider: IRawElementProviderSimple = BarProvider { hwnd: hwnd.0 as isize }.into();
+    unsafe { UiaReturnRawElementProvider(hwnd, wparam, lparam, &provider) }
+}
+
+/// Refresh the snapshot after a paint, if anything is reading it
+pub fn refresh(renderer: &Renderer, config: &Config, bar_rect: &Rect) {
+    if unsafe { UiaClientsAreListening() }.as_bool() {
+        update(renderer, config, bar_rect);
+    }
+}
+
+/// Modules invoked since the last call, with where to click them
+pub fn take_invoked() -> Vec<(String, i32)> {
+    let invoked = std::mem::take(&mut *INVOKED.lock());
+    let elements = ELEMENTS.lock();
+    invoked
+        .into_iter()
+        .filter_map(|id| elements.iter().find(|e| e.id == id).map(|e| (id, e.click_x)))
+        .collect()
+}
+
+/// Take a new snapshot of the modules on the bar
+pub fn update(renderer: &Renderer, config: &Config, bar_rect: &Rect) {
+    let mut elements: Vec<Element> = renderer
+        .module_bounds()
+        .iter()
+        .filter_map(|(id, layout)| {
+            let module = renderer.module_registry.get(id)?;
+            let window = super::manager::WindowManager::from_layout(bar_rect, layout);
+            Some(Element {
+                id: id.clone(),
+                name: crate::i18n::tr(module.name()),
+                value: spoken(&module.display_text(config)),
+                help: crate::modules::tooltip_text(module, config).unwrap_or_default(),
+                bounds: Rect::new(bar_rect.x + window.x, bar_rect.y + window.y, window.width, window.height),
+                click_x: layout.x + layout.width / 2,
+            })
+        })
+        .collect();
+    // Left to right, or top to bottom on a vertical bar
+    elements.sort_by_key(|e| (e.bounds.y, e.bounds.x));
+
+    let mut current = ELEMENTS.lock();
+    if *current != elements {
+        debug!("Automation tree now has {} modules", elements.len());
+        *current = elements;
+    }
+}
+
+/// Display text without the icon font's private-use glyphs, which a screen
+/// reader would read out as gibberish
+fn spoken(text: &str) -> String {
+    let text: String = text.chars().filter(|c| !('\u{E000}'..='\u{F8FF}').contains(c)).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn element(id: &str) -> Option<Element> {
+    ELEMENTS.lock().iter().find(|e| e.id == id).cloned()
+}
+
+fn hwnd(raw: isize) -> HWND {
+    HWND(raw as *mut std::ffi::c_void)
+}
+
+/// A null interface pointer, returned with S_OK
+fn none<T>() -> Result<T> {
+    Err(Error::empty())
+}
+
+fn ui_rect(rect: &Rect) -> UiaRect {
+    UiaRect {
+        left: rect.x as f64,
+        top: rect.y as f64,
+        width: rect.width as f64,
+        height: rect.height as f64,
+    }
+}
+
+fn module_provider(hwnd: isize, id: &str) -> IRawElementProviderFragment {
+    ModuleProvider { hwnd, id: id.to_string() }.into()
+}
+
+/// The bar itself: the root of the tree, hosted by its window
+#[implement(IRawElementProviderSimple, IRawElementProviderFragment, IRawElementProviderFragmentRoot)]
+struct BarProvider {
+    hwnd: isize,
+}
+
+impl IRawElementProviderSimple_Impl for BarProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider)
+    }
+
+    fn GetPatternProvider(&self, _pattern: UIA_PATTERN_ID) -> Result<windows::core::IUnknown> {
+        none()
+    }
+
+    fn GetPropertyValue(&self, property: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        Ok(match property {
+            UIA_NamePropertyId => VARIANT::from("TopBar"),
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_ToolBarControlTypeId.0),
+            UIA_AutomationIdPropertyId => VARIANT::from("TopBar"),
+            _ => VARIANT::default(),
+        })
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        unsafe { UiaHostProviderFromHwnd(hwnd(self.hwnd)) }
+    }
+}
+
+impl IRawElementProviderFragment_Impl for BarProvider_Impl {
+    fn Navigate(&self, direction: NavigateDirection) -> Result<IRawElementProviderFragment> {
+        let elements = ELEMENTS.lock();
+        let child = match direction {
+            NavigateDirection_FirstChild => elements.first(),
+            NavigateDirection_LastChild => elements.last(),
+            _ => None,
+        };
+        match child {
+            Some(e) => Ok(module_provider(self.hwnd, &e.id)),
+            None => none(),
+        }
+    }
+
+    fn GetRuntimeId(&self) -> Result<*mut SAFEARRAY> {
+        // The window's provider supplies the root's id
+        Ok(std::ptr::null_mut())
+    }
+
+    fn BoundingRectangle(&self) -> Result<UiaRect> {
+        // Also the window's
+        Ok(UiaRect::default())
+    }
+
+    fn GetEmbeddedFragmentRoots(&self) -> Result<*mut SAFEARRAY> {
+        Ok(std::ptr::null_mut())
+    }
+
+    fn SetFocus(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn FragmentRoot(&self) -> Result<IRawElementProviderFragmentRoot> {
+        Ok(BarProvider { hwnd: self.hwnd }.into())
+    }
+}
+
+impl IRawElementProviderFragmentRoot_Impl for BarProvider_Impl {
+    fn ElementProviderFromPoint(&self, x: f64, y: f64) -> Result<IRawElementProviderFragment> {
+        let elements = ELEMENTS.lock();
+        match elements.iter().find(|e| e.bounds.contains(x as i32, y as i32)) {
+            Some(e) => Ok(module_provider(self.hwnd, &e.id)),
+            None => none(),
+        }
+    }
+
+    fn GetFocus(&self) -> Result<IRawElementProviderFragment> {
+        none()
+    }
+}
+
+/// One module on the bar
+#[implement(IRawElementProviderSimple, IRawElementProviderFragment, IInvokeProvider, IValueProvider)]
+struct ModuleProvider {
+    hwnd: isize,
+    id: String,
+}
+
+impl IRawElementProviderSimple_Impl for ModuleProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider)
+    }
+
+    fn GetPatternProvider(&self, pattern: UIA_PATTERN_ID) -> Result<windows::core::IUnknown> {
+        match pattern {
+            UIA_InvokePatternId | UIA_ValuePatternId => unsafe { self.cast() },
+            _ => none(),
+        }
+    }
+
+    fn GetPropertyValue(&self, property: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        let Some(element) = element(&self.id) else {
+            return Ok(VARIANT::default());
+        };
+        Ok(match property {
+            UIA_NamePropertyId => VARIANT::from(element.name.as_str()),
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_ButtonControlTypeId.0),
+            UIA_AutomationIdPropertyId => VARIANT::from(element.id.as_str()),
+            UIA_HelpTextPropertyId => VARIANT::from(element.help.as_str()),
+            UIA_ValueValuePropertyId => VARIANT::from(element.value.as_str()),
+            UIA_ValueIsReadOnlyPropertyId => VARIANT::from(true),
+            _ => VARIANT::default(),
+        })
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        none()
+    }
+}
+
+impl IRawElementProviderFragment_Impl for ModuleProvider_Impl {
+    fn Navigate(&self, direction: NavigateDirection) -> Result<IRawElementProviderFragment> {
+        if direction == NavigateDirection_Parent {
+            return Ok(BarProvider { hwnd: self.hwnd }.into());
+        }
+        let elements = ELEMENTS.lock();
+        let Some(index) = elements.iter().position(|e| e.id == self.id) else {
+            return none();
+        };
+        let sibling = match direction {
+            NavigateDirection_NextSibling => elements.get(index + 1),
+            NavigateDirection_PreviousSibling => index.checked_sub(1).and_then(|i| elements.get(i)),
+            _ => None,
+        };
+        match sibling {
+            Some(e) => Ok(module_provider(self.hwnd, &e.id)),
+            None => none(),
+        }
+    }
+
+    fn GetRuntimeId(&self) -> Result<*mut SAFEARRAY> {
+        // Stable across repaints and reordering: derived from the module id
+        let mut hasher = DefaultHasher::new();
+        self.id.hash(&mut hasher);
+        let ids = [UiaAppendRuntimeId as i32, hasher.finish() as i32];
+        unsafe {
+            let array = SafeArrayCreateVector(VT_I4, 0, ids.len() as u32);
+            if array.is_null() {
+                return Err(Error::from_win32());
+            }
+            for (i, id) in ids.iter().enumerate() {
+                SafeArrayPutElement(array, &(i as i32), id as *const i32 as *const std::ffi::c_void)?;
+            }
+            Ok(array)
+        }
+    }
+
+    fn BoundingRectangle(&self) -> Result<UiaRect> {
+        Ok(element(&self.id).map(|e| ui_rect(&e.bounds)).unwrap_or_default())
+    }
+
+    fn GetEmbeddedFragmentRoots(&self) -> Result<*mut SAFEARRAY> {
+        Ok(std::ptr::null_mut())
+    }
+
+    fn SetFocus(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn FragmentRoot(&self) -> Result<IRawElementProviderFragmentRoot> {
+        Ok(BarProvider { hwnd: self.hwnd }.into())
+    }
+}
+
+impl IInvokeProvider_Impl for ModuleProvider_Impl {
+    fn Invoke(&self) -> Result<()> {
+        INVOKED.lock().push(self.id.clone());
+        unsafe { PostMessageW(hwnd(self.hwnd), WM_TOPBAR_MODULE_CLICK, WPARAM(0), LPARAM(0)) }
+    }
+}
+
+impl IValueProvider_Impl for ModuleProvider_Impl {
+    fn SetValue(&self, _value: &PCWSTR) -> Result<()> {
+        Err(Error::from(windows::Win32::Foundation::E_ACCESSDENIED))
+    }
+
+    fn Value(&self) -> Result<BSTR> {
+        Ok(BSTR::from(element(&self.id).map(|e| e.value).unwrap_or_default()))
+    }
+
+    fn IsReadOnly(&self) -> Result<BOOL> {
+        Ok(true.into())
+    }
+}
@@ -36,6 +36,121 @@ where
     }
 }
 
+/// Change the interface size multiplier and resize the bar
+pub fn set_ui_scale(hwnd: HWND, scale: f32) {
+    if let Some(state) = get_window_state() {
+        let config = state.read().config.clone();
+        let mut new_config = (*config).clone();
+        new_config.appearance.ui_scale = scale;
+
+        if let Err(e) = new_config.save() {
+            warn!("Failed to save config: {}", e);
+        }
+
+        state.write().config = std::sync::Arc::new(new_config);
+        crate::utils::set_ui_scale(scale);
+        super::manager::WindowManager::relayout(hwnd);
+    }
+}
+
+/// Opacity change per scroll notch, and the least the bar can be turned down to
+const OPACITY_STEP: f32 = 0.05;
+const MIN_OPACITY: f32 = 0.3;
+/// Bar height change per scroll notch and its range, before DPI scaling
+const HEIGHT_STEP: u32 = 2;
+const MIN_BAR_HEIGHT: u32 = 20;
+const MAX_BAR_HEIGHT: u32 = 80;
+
+/// Make the bar more (positive `steps`) or less opaque and save it
+pub fn adjust_opacity(hwnd: HWND, steps: i32) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let mut new_config = (*state.read().config).clone();
+    let current = new_config.appearance.opacity;
+    // Kept on whole percents so repeated steps don't drift
+    let opacity = ((current + steps as f32 * OPACITY_STEP).clamp(MIN_OPACITY, 1.0) * 100.0).round() / 100.0;
+    if (opacity - current).abs() < f32::EPSILON {
+        return;
+    }
+    new_config.appearance.opacity = opacity;
+
+    if let Err(e) = new_config.save() {
+        warn!("Failed to save config: {}", e);
+    }
+
+    state.write().config = std::sync::Arc::new(new_config);
+    super::manager::WindowManager::set_opacity(hwnd, opacity);
+    let percent = (opacity * 100.0).round() as u32;
+    let text = crate::i18n::tr("Opacity {}%").replacen("{}", &percent.to_string(), 1);
+    crate::render::show_osd_level(&text, "◐", percent);
+}
+
+/// Make the bar taller (positive `steps`) or thinner and save it
+pub fn adjust_bar_height(hwnd: HWND, steps: i32) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let mut new_config = (*state.read().config).clone();
+    let current = new_config.appearance.bar_height;
+    let height = current
+        .saturating_add_signed(steps * HEIGHT_STEP as i32)
+        .clamp(MIN_BAR_HEIGHT, MAX_BAR_HEIGHT);
+    if height == current {
+        return;
+    }
+    new_config.appearance.bar_height = height;
+
+    if let Err(e) = new_config.save() {
+        warn!("Failed to save config: {}", e);
+    }
+
+    state.write().config = std::sync::Arc::new(new_config);
+    super::manager::WindowManager::relayout(hwnd);
+    let text = crate::i18n::tr("Bar height {} px").replacen("{}", &height.to_string(), 1);
+    crate::render::show_osd(&text, "↕");
+}
+
+/// Choose how the clock announces the time
+pub fn set_chime_mode(hwnd: HWND, mode: crate::config::ChimeMode) {
+    if let Some(state) = get_window_state() {
+        let config = state.read().config.clone();
+        let mut new_config = (*config).clone();
+        new_config.modules.clock.chime.mode = mode;
+
+        if let Err(e) = new_config.save() {
+            warn!("Failed to save config: {}", e);
+        }
+
+        state.write().config = std::sync::Arc::new(new_config);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, true);
+        }
+    }
+}
+
+/// Collapse or expand a module group
+pub fn toggle_group(hwnd: HWND, group_id: &str) {
+    if let Some(state) = get_window_state() {
+        let config = state.read().config.clone();
+        let mut new_config = (*config).clone();
+        let Some(group) = new_config.modules.groups.iter_mut().find(|g| g.id() == group_id) else {
+            return;
+        };
+        group.collapsed = !group.collapsed;
+        info!("Group {} {}", group.name, if group.collapsed { "collapsed" } else { "expanded" });
+
+        if let Err(e) = new_config.save() {
+            warn!("Failed to save config: {}", e);
+        }
+
+        state.write().config = std::sync::Arc::new(new_config);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, true);
+        }
+    }
+}
+
 /// Toggle a module on/off
 pub fn toggle_module(hwnd: HWND, module_id: &str) {
     if let Some(state) = get_window_state() {
@@ -55,6 +170,33 @@ pub fn toggle_module(hwnd: HWND, module_id: &str) {
             new_config.modules.clock.center = false;
             info!("Disabled centered clock: {}", module_id);
         }
+        // The launcher lives on the left, between the app menu and the active app;
+        // the running apps strip follows the active app
+        else if module_id == "launcher" || module_id == "tasks" {
+            let left = &mut new_config.modules.left_modules;
+            if let Some(pos) = left.iter().position(|m| m == module_id) {
+                left.remove(pos);
+                info!("Disabled module: {}", module_id);
+            } else {
+                let insert_pos = match module_id {
+                    "launcher" => left.iter().position(|m| m == "active_app"),
+                    _ => None,
+                }
+                .unwrap_or(left.len());
+                left.insert(insert_pos, module_id.to_string());
+                info!("Enabled module: {}", module_id);
+            }
+        }
+        // A module in a group is turned off by taking it out of the group
+        else if let Some(group) = new_config
+            .modules
+            .groups
+            .iter_mut()
+            .find(|g| g.modules.iter().any(|m| m == module_id) && config.modules.right_modules.contains(&g.id()))
+        {
+            group.modules.retain(|m| m != module_id);
+            info!("Disabled module: {} (removed from group {})", module_id, group.name);
+        }
         // Check if module exists in right_modules
         else if let Some(pos) = new_config
             .modules
@@ -118,6 +260,49 @@ pub fn open_config_file() {
     info!("Opening config file: {:?}", path);
 }
 
+/// Switch between the light and dark theme
+pub fn toggle_theme(hwnd: HWND) {
+    if let Some(state) = get_window_state() {
+        let mut s = state.write();
+        s.theme_manager.toggle();
+        let theme = s.theme_manager.theme().clone();
+        drop(s);
+        let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, true);
+        }
+    }
+}
+
+/// Switch to a theme mode for this session, like `toggle_theme`
+pub fn set_theme_mode(hwnd: HWND, mode: crate::theme::ThemeMode) {
+    if let Some(state) = get_window_state() {
+        let mut s = state.write();
+        s.theme_manager.set_mode(mode);
+        let theme = s.theme_manager.theme().clone();
+        drop(s);
+        let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, true);
+        }
+    }
+}
+
+/// Turn auto-hide on or off and save it
+pub fn toggle_auto_hide(hwnd: HWND) {
+    if let Some(state) = get_window_state() {
+        let mut new_config = (*state.read().config).clone();
+        new_config.behavior.auto_hide = !new_config.behavior.auto_hide;
+        info!("Auto-hide {}", if new_config.behavior.auto_hide { "on" } else { "off" });
+        if let Err(e) = new_config.save() {
+            warn!("Failed to save config: {}", e);
+        }
+        state.write().config = std::sync::Arc::new(new_config);
+        // Gives back or takes the reserved screen space
+        super::manager::WindowManager::relayout(hwnd);
+    }
+}
+
 /// Reload configuration
 pub fn reload_config(hwnd: HWND) {
     use crate::config::Config;
@@ -125,8 +310,38 @@ pub fn reload_config(hwnd: HWND) {
     match Config::load_or_default() {
         Ok(config) => {
             if let Some(state) = get_window_state() {
-                state.write().config = std::sync::Arc::new(config);
+                {
+                    let mut s = state.write();
+                    s.theme_manager.set_appearance(&config.appearance);
+                    s.config = std::sync::Arc::new(config);
+                }
                 info!("Configuration reloaded");
+                // Transparency preferences, the theme and the interface size may have changed
+                let (theme, ui_scale, tabular_numbers, language, shelf) = {
+                    let s = state.read();
+                    let appearance = &s.config.appearance;
+                    (
+                        s.theme_manager.theme().clone(),
+                        appearance.ui_scale,
+                        appearance.tabular_numbers,
+                        s.config.general.language.clone(),
+                        s.config.shelf.enabled,
+                    )
+                };
+                let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
+                crate::utils::set_ui_scale(ui_scale);
+                // Fonts may have changed
+                crate::render::clear_text_cache();
+                crate::render::set_tabular_numbers(tabular_numbers);
+                crate::i18n::set_language(&language);
+                // The shelf may have been turned on or off
+                crate::render::accept_drops(hwnd, shelf);
+                super::manager::WindowManager::relayout(hwnd);
+                // Pick up edited keybindings
+                let config = state.read().config.clone();
+                crate::hotkey::register_and_report(hwnd, &config);
+                report_config_problems(&config);
+                crate::startup::apply(&config.general);
                 unsafe {
                     let _ = InvalidateRect(hwnd, None, true);
                 }
@@ -138,6 +353,28 @@ pub fn reload_config(hwnd: HWND) {
     }
 }
 
+/// Raise an alert on the app menu if the last config load found problems, or
+/// start with Windows doesn't match the config
+pub fn report_config_problems(config: &Config) {
+    let mut problems = crate::config::take_load_problems();
+    problems.extend(crate::startup::take_problem());
+    if let Some(first) = problems.first() {
+        let text = match problems.len() {
+            1 => first.clone(),
+            n => format!("{} (and {} more; see the log)", first, n - 1),
+        };
+        crate::attention::request(
+            config,
+            crate::attention::AttentionRequest::new(
+                "app_menu",
+                crate::attention::AttentionLevel::Warning,
+                "Config problem",
+                &text,
+            ),
+        );
+    }
+}
+
 /// Reset configuration to defaults (with confirmation)
 pub fn reset_config(hwnd: HWND) {
     use crate::config::Config;
@@ -150,9 +387,10 @@ pub fn reset_config(hwnd: HWND) {
         let resp = MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), MB_YESNO | MB_ICONWARNING);
         if resp.0 == IDYES.0 {
             let cfg = Config::default();
-            match cfg.save() {
+            match cfg.replace_file() {
                 Ok(_) => {
                     if let Some(state) = get_window_state() {
+                        crate::render::accept_drops(hwnd, cfg.shelf.enabled);
                         state.write().config = std::sync::Arc::new(cfg);
                         info!("Configuration reset to defaults");
                         let _ = InvalidateRect(hwnd, None, true);
@@ -168,6 +406,37 @@ pub fn reset_config(hwnd: HWND) {
     }
 }
 
+/// Save a picture of the bar with a copy of the config that's safe to post
+/// (`Config::to_shareable_toml`) next to the screenshots, and show them in
+/// Explorer; returns the picture's path
+pub fn share_setup(hwnd: HWND) -> anyhow::Result<std::path::PathBuf> {
+    let (config, theme, bar_rect) = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            (s.config.clone(), s.theme_manager.theme().clone(), s.bar_rect)
+        })
+        .ok_or_else(|| anyhow::anyhow!("The bar isn't running"))?;
+    let (width, height, rgba) = super::renderer::with_renderer(|r| r.render_offscreen(&bar_rect, &theme))
+        .flatten()
+        .ok_or_else(|| anyhow::anyhow!("Couldn't draw the bar"))?;
+    // The back buffer was drawn over, so the next paint has to start from scratch
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+
+    let picture = crate::modules::capture::output_path(&config.modules.capture, "TopBar Setup", "png")?;
+    image::RgbaImage::from_raw(width as u32, height as u32, rgba)
+        .ok_or_else(|| anyhow::anyhow!("Invalid bar dimensions"))?
+        .save(&picture)?;
+    std::fs::write(picture.with_extension("toml"), config.to_shareable_toml()?)?;
+    info!("Saved bar setup to {:?}", picture);
+
+    let _ = std::process::Command::new("explorer.exe")
+        .arg(format!("/select,{}", picture.display()))
+        .spawn();
+    Ok(picture)
+}
+
 /// Install bundled macOS-style cursors by running the INF 'Install.inf' in the resources folder.
 /// This will invoke the system installer (may prompt for UAC) and display a confirmation on error/success.
 pub fn install_mac_cursors(hwnd: HWND) {
@@ -237,19 +506,34 @@ pub fn install_mac_cursors(hwnd: HWND) {
 /// Default order of right-side modules for insertion position calculation
 const DEFAULT_RIGHT_MODULE_ORDER: &[&str] = &[ 
     "weather",
+    "stocks",
+    "sports",
     "media",
     "clipboard",
+    "capture",
+    "voice_memo",
+    "printing",
+    "removable_media",
+    "watchdog",
+    "services",
+    "security",
+    "monitor_input",
     "keyboard_layout",
+    "lock_keys",
     "gpu",
     "system_info",
+    "thermal",
     "disk",
     "network",
+    "connectivity",
     "bluetooth",
     "night_light",
     "volume",
     "battery",
     "uptime",
+    "user",
     "clock",
+    "power",
 ];
 
 /// Find the appropriate insert position for a module based on default order
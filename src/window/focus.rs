@@ -0,0 +1,146 @@
+//! Keyboard navigation of the bar
+//!
+//! The `focus_bar` hotkey activates the bar and puts a focus ring on its first
+//! module. The arrow keys move the ring, Home and End jump to either end,
+//! Enter or Space click the module, and Shift+F10 or the menu key opens its
+//! context menu. Escape (or Tab) hands the keyboard back to the window that
+//! had it.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+use log::debug;
+use windows::Win32::Foundation::{HWND, POINT};
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId, SetForegroundWindow};
+
+use super::renderer::with_renderer;
+use super::state::get_window_state;
+
+/// The window that was in front when the bar took focus
+static PREVIOUS: AtomicIsize = AtomicIsize::new(0);
+
+/// Bring the bar forward with the first module focused
+pub fn focus_bar(hwnd: HWND) {
+    let Some(first) = modules().into_iter().next() else {
+        return;
+    };
+    unsafe {
+        let previous = GetForegroundWindow();
+        if previous != hwnd {
+            PREVIOUS.store(previous.0 as isize, Ordering::Relaxed);
+        }
+        let _ = SetForegroundWindow(hwnd);
+        let _ = SetFocus(hwnd);
+    }
+    set_focused(hwnd, Some(first));
+}
+
+/// Whether a module has the focus ring
+pub fn is_active() -> bool {
+    get_window_state().is_some_and(|s| s.read().focused_module.is_some())
+}
+
+/// Handle a key pressed while the bar has focus; returns false for keys it
+/// doesn't use
+pub fn handle_key(hwnd: HWND, key: VIRTUAL_KEY) -> bool {
+    let Some(current) = get_window_state().and_then(|s| s.read().focused_module.clone()) else {
+        return false;
+    };
+    let order = modules();
+    let index = order.iter().position(|id| *id == current).unwrap_or(0);
+    let shift = unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0;
+
+    match key {
+        VK_LEFT | VK_UP => move_to(hwnd, &order, index.checked_sub(1).unwrap_or(order.len().saturating_sub(1))),
+        VK_RIGHT | VK_DOWN => move_to(hwnd, &order, if index + 1 < order.len() { index + 1 } else { 0 }),
+        VK_HOME => move_to(hwnd, &order, 0),
+        VK_END => move_to(hwnd, &order, order.len().saturating_sub(1)),
+        VK_RETURN | VK_SPACE => {
+            if let Some(x) = layout_center(&current) {
+                super::module_handlers::handle_module_click(hwnd, &current, x);
+            }
+        }
+        VK_APPS => open_menu(hwnd, &current),
+        VK_F10 if shift => open_menu(hwnd, &current),
+        VK_ESCAPE | VK_TAB => release(hwnd),
+        _ => return false,
+    }
+    true
+}
+
+/// The bar lost the keyboard: drop the ring unless focus went to one of the
+/// bar's own menus or popups
+pub fn focus_lost(hwnd: HWND, new_focus: HWND) {
+    let ours = !new_focus.is_invalid() && unsafe {
+        GetWindowThreadProcessId(new_focus, None) == GetWindowThreadProcessId(hwnd, None)
+    };
+    if !ours && is_active() {
+        set_focused(hwnd, None);
+    }
+}
+
+/// Give the keyboard back to the window that had it
+fn release(hwnd: HWND) {
+    set_focused(hwnd, None);
+    let previous = HWND(PREVIOUS.swap(0, Ordering::Relaxed) as *mut std::ffi::c_void);
+    if !previous.is_invalid() {
+        unsafe {
+            let _ = SetForegroundWindow(previous);
+        }
+    }
+}
+
+fn move_to(hwnd: HWND, order: &[String], index: usize) {
+    if let Some(id) = order.get(index) {
+        set_focused(hwnd, Some(id.clone()));
+    }
+}
+
+fn set_focused(hwnd: HWND, module: Option<String>) {
+    debug!("Keyboard focus on {:?}", module);
+    if let Some(state) = get_window_state() {
+        state.write().focused_module = module;
+    }
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+}
+
+/// Open a module's own context menu, or the bar's, below the module
+fn open_menu(hwnd: HWND, module_id: &str) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let bar_rect = state.read().bar_rect;
+    let Some(bounds) = with_renderer(|r| r.module_bounds().get(module_id).copied()).flatten() else {
+        return;
+    };
+    let window = super::manager::WindowManager::from_layout(&bar_rect, &bounds);
+    let pt = POINT {
+        x: bar_rect.x + window.x,
+        y: bar_rect.y + window.bottom(),
+    };
+    if !super::module_handlers::handle_module_right_click(hwnd, module_id, pt.x, pt.y) {
+        super::menus::show_context_menu(hwnd, pt.x, pt.y);
+    }
+}
+
+fn layout_center(module_id: &str) -> Option<i32> {
+    with_renderer(|r| r.module_bounds().get(module_id).map(|b| b.x + b.width / 2)).flatten()
+}
+
+/// Modules on the bar in order along it
+fn modules() -> Vec<String> {
+    with_renderer(|r| {
+        let mut modules: Vec<(i32, String)> = r
+            .module_bounds()
+            .iter()
+            .filter(|(id, _)| r.module_registry.get(id).is_some())
+            .map(|(id, bounds)| (bounds.x, id.clone()))
+            .collect();
+        modules.sort();
+        modules.into_iter().map(|(_, id)| id).collect()
+    })
+    .unwrap_or_default()
+}
@@ -5,30 +5,32 @@
 use anyhow::Result;
 use log::info;
 use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 use windows::Win32::Foundation::{HWND, LPARAM, RECT};
 use windows::Win32::Graphics::Dwm::{
-    DwmSetWindowAttribute, DWMWA_SYSTEMBACKDROP_TYPE, DWMWA_USE_IMMERSIVE_DARK_MODE,
-    DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_ROUND, DWM_SYSTEMBACKDROP_TYPE,
-    DWM_WINDOW_CORNER_PREFERENCE,
+    DwmSetWindowAttribute, DWMWA_USE_IMMERSIVE_DARK_MODE, DWMWA_WINDOW_CORNER_PREFERENCE,
+    DWMWCP_ROUND, DWM_WINDOW_CORNER_PREFERENCE,
 };
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::HiDpi::{
     GetDpiForWindow, SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
 };
-use windows::Win32::UI::Input::KeyboardAndMouse::RegisterHotKey;
-use windows::Win32::Foundation::GetLastError;
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::Graphics::Gdi::{CreateRoundRectRgn, InvalidateRect, SetWindowRgn, HRGN};
 
 use crate::config::{BarPosition, Config};
-use crate::hotkey::HotkeyAction;
 use crate::render::Renderer;
 use crate::theme::Theme;
 use crate::utils::{get_screen_size, scale_by_dpi, to_pcwstr, to_wide_string, Rect};
 
 use super::state::{set_window_state, WindowState};
 
+/// Opacity from the config, restored when high contrast is turned off
+static CONFIGURED_OPACITY: AtomicU8 = AtomicU8::new(255);
+/// Whether the backdrop shows through the bar (see `apply_window_style`)
+static TRANSLUCENT: AtomicBool = AtomicBool::new(false);
+
 /// Window class name
 const WINDOW_CLASS: &str = "TopBarWindowClass";
 const WINDOW_TITLE: &str = "TopBar";
@@ -71,9 +73,14 @@ impl WindowManager {
         }
 
         // Apply window styling
-        Self::apply_window_style(hwnd, state.read().theme_manager.theme())?;
+        crate::accessibility::refresh();
+        let theme = state.read().theme_manager.theme().clone();
+        Self::apply_window_style(hwnd, &theme)?;
 
         // Calculate and set position
+        crate::utils::set_ui_scale(config.appearance.ui_scale);
+        crate::render::set_tabular_numbers(config.appearance.tabular_numbers);
+        crate::i18n::set_language(&config.general.language);
         let bar_rect = Self::calculate_bar_rect(&config, dpi);
         {
             let mut state_guard = state.write();
@@ -105,50 +112,10 @@ impl WindowManager {
             }
         });
 
-        // Register configured hotkeys and store a simple map for dispatch
-        let mut global_map: std::collections::HashMap<i32, HotkeyAction> = std::collections::HashMap::new();
-
-        // Helper to register a single hotkey id for a configured string
-        let mut register_k = |id: i32, key_str: Option<String>, action: HotkeyAction| {
-            if let Some(s) = key_str {
-                if let Some(hk) = crate::hotkey::Hotkey::parse(&s, action) {
-                    unsafe {
-                        let res = RegisterHotKey(hwnd, id, windows::Win32::UI::Input::KeyboardAndMouse::HOT_KEY_MODIFIERS(hk.modifiers), hk.key);
-                        if res.is_ok() {
-                            // Record mapping and log success for diagnostics
-                            global_map.insert(id, action);
-                            info!("Registered hotkey '{}' -> id={} modifiers={} key=0x{:X}", s, id, hk.modifiers, hk.key);
-                        } else {
-                            let err = unsafe { GetLastError() };
-                            log::warn!("Failed to register hotkey {} -> {} (err={})", s, id, err.0);
-                        }
-                    }
-                }
-            }
-        };
-
-
-        // Fixed ids for core hotkeys (keeps behavior deterministic)
-        const HK_TOGGLE_BAR: i32 = 6000;
-        const HK_OPEN_MENU: i32 = 6001;
-        const HK_QUICK_SEARCH: i32 = 6002;
-        const HK_TOGGLE_THEME: i32 = 6003;
-
-        register_k(HK_TOGGLE_BAR, config.hotkeys.toggle_bar.clone(), HotkeyAction::ToggleBar);
-        register_k(HK_OPEN_MENU, config.hotkeys.open_menu.clone(), HotkeyAction::OpenMenu);
-        // Only register quick-search hotkey if search is enabled
-        if config.search.enabled {
-            register_k(HK_QUICK_SEARCH, config.hotkeys.quick_search.clone(), HotkeyAction::QuickSearch);
-        }
-        register_k(HK_TOGGLE_THEME, config.hotkeys.toggle_theme.clone(), HotkeyAction::ToggleTheme);
-
-        crate::hotkey::set_global_hotkey_map(global_map);
-
-        // Log the final global hotkey map for diagnostics (helpful when registrations fail)
-        if let Some(m) = crate::hotkey::global_hotkey_map() {
-            let g = m.lock();
-            info!("Global hotkey map configured: {:?}", g);
-        }
+        // Register configured hotkeys; problems are logged and raised on the app menu
+        crate::hotkey::register_and_report(hwnd, &config);
+        // And so are problems found while loading the config
+        super::config_handlers::report_config_problems(&config);
 
         info!("Window created successfully at {:?}", bar_rect);
 
@@ -213,6 +180,7 @@ impl WindowManager {
 
             // Set layered window attributes for transparency
             let opacity = (config.appearance.opacity * 255.0) as u8;
+            CONFIGURED_OPACITY.store(opacity, Ordering::Relaxed);
             SetLayeredWindowAttributes(
                 hwnd,
                 windows::Win32::Foundation::COLORREF(0),
@@ -220,6 +188,9 @@ impl WindowManager {
                 LWA_ALPHA,
             )?;
 
+            // Files dropped on the bar go to the shelf
+            crate::render::accept_drops(hwnd, config.shelf.enabled);
+
             Ok(hwnd)
         }
     }
@@ -245,34 +216,139 @@ impl WindowManager {
                 std::mem::size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
             );
 
-            // Try to enable Mica/Acrylic backdrop (Windows 11 22H2+)
-            // 2 = Mica, 3 = Acrylic, 4 = Mica Alt
-            let backdrop_type: i32 = 3; // Acrylic
-            let _ = DwmSetWindowAttribute(
-                hwnd,
-                DWMWA_SYSTEMBACKDROP_TYPE,
-                &backdrop_type as *const _ as *const _,
-                std::mem::size_of::<DWM_SYSTEMBACKDROP_TYPE>() as u32,
-            );
+            // High contrast and reduced transparency get a solid, fully opaque bar
+            let (opaque, appearance) = match super::state::get_window_state() {
+                Some(s) => {
+                    let appearance = s.read().config.appearance.clone();
+                    (theme.forced_colors || crate::accessibility::reduce_transparency(&appearance), appearance)
+                }
+                None => (theme.forced_colors, Default::default()),
+            };
+
+            // With blur on, the renderer paints per-pixel alpha and the backdrop shows
+            // through the tint. A layered window would ignore that alpha, so the bar
+            // stops being layered and the opacity setting is left to the tint.
+            let translucent = !opaque && appearance.blur_enabled;
+            TRANSLUCENT.store(translucent, Ordering::Relaxed);
+            let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+            let layered = WS_EX_LAYERED.0 as isize;
+            if translucent {
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style & !layered);
+            } else {
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | layered);
+                let opacity = if opaque {
+                    255
+                } else {
+                    CONFIGURED_OPACITY.load(Ordering::Relaxed)
+                };
+                let _ = SetLayeredWindowAttributes(
+                    hwnd,
+                    windows::Win32::Foundation::COLORREF(0),
+                    opacity,
+                    LWA_ALPHA,
+                );
+            }
+            if let Err(e) = crate::effects::EffectsManager::set_translucent(hwnd, translucent, appearance.backdrop) {
+                log::debug!("Failed to set up the backdrop: {}", e);
+            }
         }
         Ok(())
     }
 
+    /// Whether the bar is painted with per-pixel alpha over a blurred backdrop
+    pub fn is_translucent() -> bool {
+        TRANSLUCENT.load(Ordering::Relaxed)
+    }
+
+    /// Whether the bar is shrunk to a thin strip, for a screen share or by auto-hide
+    pub fn is_slim() -> bool {
+        crate::share_guard::is_active() || crate::auto_hide::is_collapsed()
+    }
+
+    /// Whether the bar pushes other windows aside; an auto-hiding bar opens over them
+    fn reserves_space(config: &Config) -> bool {
+        config.behavior.reserve_space && !config.behavior.auto_hide
+    }
+
     /// Calculate the bar rectangle based on config and screen
     pub fn calculate_bar_rect(config: &Config, dpi: u32) -> Rect {
         let screen = get_screen_size();
-        let height = scale_by_dpi(config.appearance.bar_height as i32, dpi);
-
-        let y = match config.appearance.position {
-            BarPosition::Top => 0,
-            BarPosition::Bottom => screen.height - height,
+        let slim = Self::is_slim();
+        // Bar height is the thickness across the bar, also for a vertical one
+        let height = if slim {
+            scale_by_dpi(crate::share_guard::SLIM_HEIGHT, dpi)
+        } else {
+            scale_by_dpi(config.appearance.bar_height as i32, dpi)
+        };
+        // A floating bar keeps clear of the screen edges; the strip stays on the edge
+        let margin = if config.appearance.floating.enabled && !slim {
+            scale_by_dpi(config.appearance.floating.margin as i32, dpi)
+        } else {
+            0
         };
 
-        Rect {
-            x: 0,
-            y,
-            width: screen.width,
-            height,
+        match config.appearance.position {
+            BarPosition::Top => Rect::new(margin, margin, screen.width - margin * 2, height),
+            BarPosition::Bottom => Rect::new(margin, screen.height - height - margin, screen.width - margin * 2, height),
+            BarPosition::Left => Rect::new(margin, margin, height, screen.height - margin * 2),
+            BarPosition::Right => Rect::new(screen.width - height - margin, margin, height, screen.height - margin * 2),
+        }
+    }
+
+    /// The horizontal rectangle the bar's contents are laid out and drawn in,
+    /// from the origin. A vertical bar (taller than wide) is drawn lengthwise and
+    /// turned a quarter clockwise onto the window, so its first modules are at
+    /// the top.
+    pub fn layout_rect(bar_rect: &Rect) -> Rect {
+        if bar_rect.height > bar_rect.width {
+            Rect::new(0, 0, bar_rect.height, bar_rect.width)
+        } else {
+            Rect::new(0, 0, bar_rect.width, bar_rect.height)
+        }
+    }
+
+    /// Map a point in the window to the layout (see `layout_rect`)
+    pub fn to_layout(bar_rect: &Rect, x: i32, y: i32) -> (i32, i32) {
+        if bar_rect.height > bar_rect.width {
+            (y, bar_rect.width - 1 - x)
+        } else {
+            (x, y)
+        }
+    }
+
+    /// Map a rectangle in the layout, such as a module's bounds, to the window
+    pub fn from_layout(bar_rect: &Rect, rect: &Rect) -> Rect {
+        if bar_rect.height > bar_rect.width {
+            Rect::new(bar_rect.width - rect.bottom(), rect.x, rect.height, rect.width)
+        } else {
+            *rect
+        }
+    }
+
+    /// Use `opacity` (0.0 - 1.0) for the bar from now on and apply it
+    pub fn set_opacity(hwnd: HWND, opacity: f32) {
+        CONFIGURED_OPACITY.store((opacity.clamp(0.0, 1.0) * 255.0) as u8, Ordering::Relaxed);
+        let theme = super::state::get_window_state().map(|s| s.read().theme_manager.theme().clone());
+        if let Some(theme) = theme {
+            let _ = Self::apply_window_style(hwnd, &theme);
+        }
+    }
+
+    /// Recalculate the bar size from the current config (e.g. after an interface size
+    /// change) and move the window and its reserved screen space
+    pub fn relayout(hwnd: HWND) {
+        let Some(state) = super::state::get_window_state() else {
+            return;
+        };
+        let (config, dpi) = {
+            let s = state.read();
+            (s.config.clone(), s.dpi)
+        };
+        let bar_rect = Self::calculate_bar_rect(&config, dpi);
+        state.write().bar_rect = bar_rect;
+        let _ = Self::position_window(hwnd, &bar_rect, &config);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, true);
         }
     }
 
@@ -288,21 +364,42 @@ impl WindowManager {
                 rect.height,
                 SWP_NOACTIVATE | SWP_SHOWWINDOW,
             )?;
+            Self::apply_shape(hwnd, rect, config);
 
-            // Reserve screen space if configured
-            if config.behavior.reserve_space {
+            // Reserve screen space if configured; turning on auto-hide gives it back
+            if Self::reserves_space(config) {
                 Self::reserve_screen_space(hwnd, rect, config)?;
+            } else {
+                Self::remove_screen_space(hwnd);
             }
         }
         Ok(())
     }
 
+    /// Round the corners of a floating bar; a full-width bar stays square
+    fn apply_shape(hwnd: HWND, rect: &Rect, config: &Config) {
+        let floating = &config.appearance.floating;
+        unsafe {
+            if floating.enabled && !Self::is_slim() {
+                let dpi = GetDpiForWindow(hwnd).max(96);
+                let diameter = scale_by_dpi(floating.radius as i32 * 2, dpi);
+                let region = CreateRoundRectRgn(0, 0, rect.width + 1, rect.height + 1, diameter, diameter);
+                SetWindowRgn(hwnd, region, true);
+            } else {
+                SetWindowRgn(hwnd, HRGN::default(), true);
+            }
+        }
+    }
+
     /// Reserve screen space (like a taskbar)
     fn reserve_screen_space(hwnd: HWND, rect: &Rect, config: &Config) -> Result<()> {
         use windows::Win32::UI::Shell::{
-            SHAppBarMessage, ABE_BOTTOM, ABE_TOP, ABM_NEW, ABM_QUERYPOS, ABM_SETPOS, APPBARDATA,
+            SHAppBarMessage, ABE_BOTTOM, ABE_LEFT, ABE_RIGHT, ABE_TOP, ABM_NEW, ABM_QUERYPOS, ABM_SETPOS, APPBARDATA,
         };
 
+        // A floating bar's gap to the screen edge is kept clear on both sides of it
+        let gap = if config.appearance.position.is_vertical() { rect.y } else { rect.x };
+
         unsafe {
             let mut abd = APPBARDATA {
                 cbSize: std::mem::size_of::<APPBARDATA>() as u32,
@@ -311,12 +408,14 @@ impl WindowManager {
                 uEdge: match config.appearance.position {
                     BarPosition::Top => ABE_TOP,
                     BarPosition::Bottom => ABE_BOTTOM,
+                    BarPosition::Left => ABE_LEFT,
+                    BarPosition::Right => ABE_RIGHT,
                 },
                 rc: RECT {
-                    left: rect.x,
-                    top: rect.y,
-                    right: rect.right(),
-                    bottom: rect.bottom(),
+                    left: rect.x - gap,
+                    top: rect.y - gap,
+                    right: rect.right() + gap,
+                    bottom: rect.bottom() + gap,
                 },
                 lParam: LPARAM(0),
             };
@@ -363,7 +462,7 @@ impl WindowManager {
 
         // If configured, register/reserve the screen space when showing
         let state_guard = self.state.read();
-        if state_guard.config.behavior.reserve_space {
+        if Self::reserves_space(&state_guard.config) {
             let rect = state_guard.bar_rect;
             let cfg = state_guard.config.clone();
             drop(state_guard);
@@ -380,7 +479,7 @@ impl WindowManager {
 
         // If configured, remove the reserved space so other apps can use full screen
         let state_guard = self.state.read();
-        if state_guard.config.behavior.reserve_space {
+        if Self::reserves_space(&state_guard.config) {
             drop(state_guard);
             Self::remove_screen_space(self.hwnd);
         }
@@ -396,6 +495,37 @@ impl WindowManager {
         }
     }
 
+    /// Show or hide the bar by its handle, for hotkeys and actions that have no manager
+    pub fn toggle_bar(hwnd: HWND) {
+        let visible = super::state::get_window_state().map(|s| s.read().is_visible).unwrap_or(false);
+        Self::set_bar_visible(hwnd, !visible);
+    }
+
+    /// Show or hide the bar by its handle; does nothing if it's already that way
+    pub fn set_bar_visible(hwnd: HWND, show: bool) {
+        let Some(state) = super::state::get_window_state() else {
+            return;
+        };
+        let (visible, rect, config) = {
+            let s = state.read();
+            (s.is_visible, s.bar_rect, s.config.clone())
+        };
+        if visible == show {
+            return;
+        }
+        unsafe {
+            let _ = ShowWindow(hwnd, if show { SW_SHOWNOACTIVATE } else { SW_HIDE });
+        }
+        state.write().is_visible = show;
+        if Self::reserves_space(&config) {
+            if show {
+                let _ = Self::reserve_screen_space(hwnd, &rect, &config);
+            } else {
+                Self::remove_screen_space(hwnd);
+            }
+        }
+    }
+
     /// Request a redraw
     pub fn request_redraw(&self) {
         self.state.write().needs_redraw = true;
@@ -408,10 +538,11 @@ impl WindowManager {
     pub fn update_theme(&self) {
         let mut state = self.state.write();
         state.theme_manager.check_system_theme();
-        let theme = state.theme_manager.theme();
-        let _ = Self::apply_window_style(self.hwnd, theme);
+        let theme = state.theme_manager.theme().clone();
         state.needs_redraw = true;
         drop(state);
+        // apply_window_style reads the config, so the lock must be released first
+        let _ = Self::apply_window_style(self.hwnd, &theme);
 
         unsafe {
             let _ = InvalidateRect(self.hwnd, None, true);
@@ -10,10 +10,12 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{RegisterHotKey, UnregisterHotK
 use windows::Win32::Graphics::Gdi::InvalidateRect;
 
 use crate::config::Config;
+use crate::sounds::SoundEvent;
+use crate::render::{items_from_menu, to_native_menu, track_dropdown, DropdownItem};
 
 use super::state::get_window_state;
 use super::renderer::with_renderer;
-use super::config_handlers::{open_config_file, reload_config, reset_config, install_mac_cursors, toggle_config_bool, toggle_module};
+use super::config_handlers::{open_config_file, reload_config, reset_config, install_mac_cursors, set_chime_mode, set_ui_scale, toggle_config_bool, toggle_module};
 
 // Menu item IDs
 const MENU_SHOW_CLOCK: u32 = 1001;
@@ -30,6 +32,23 @@ const MENU_SHOW_DISK: u32 = 1011;
 const MENU_SHOW_CLIPBOARD: u32 = 1012;
 const MENU_SHOW_WEATHER: u32 = 1013;
 const MENU_SHOW_NIGHT_LIGHT: u32 = 1014;
+const MENU_SHOW_THERMAL: u32 = 1015;
+const MENU_SHOW_WATCHDOG: u32 = 1016;
+const MENU_SHOW_CAPTURE: u32 = 1017;
+const MENU_SHOW_SERVICES: u32 = 1018;
+const MENU_SHOW_LAUNCHER: u32 = 1019;
+const MENU_SHOW_TASKS: u32 = 1020;
+const MENU_SHOW_LOCK_KEYS: u32 = 1021;
+const MENU_SHOW_STOCKS: u32 = 1022;
+const MENU_SHOW_SPORTS: u32 = 1023;
+const MENU_SHOW_SECURITY: u32 = 1024;
+const MENU_SHOW_MONITOR_INPUT: u32 = 1025;
+const MENU_SHOW_VOICE_MEMO: u32 = 1026;
+const MENU_SHOW_POWER: u32 = 1027;
+const MENU_SHOW_USER: u32 = 1028;
+const MENU_SHOW_CONNECTIVITY: u32 = 1029;
+const MENU_SHOW_PRINTING: u32 = 1030;
+const MENU_SHOW_REMOVABLE_MEDIA: u32 = 1031;
 
 // GPU menu items
 const GPU_SHOW_USAGE: u32 = 2601;
@@ -37,7 +56,16 @@ const GPU_SHOW_GRAPH: u32 = 2604;
 const MENU_SETTINGS: u32 = 1200;
 const MENU_RELOAD: u32 = 1201;
 const MENU_RESET: u32 = 1202;
+const MENU_SHARE_SETUP: u32 = 1203;
 const MENU_TOGGLE_SEARCH: u32 = 1210;
+const MENU_SCHEDULER: u32 = 1220;
+const MENU_DO_NOT_DISTURB: u32 = 1221;
+const MENU_OFFLINE_MODE: u32 = 1222;
+// Interface size presets (one ID per entry in utils::UI_SCALE_PRESETS)
+const MENU_UI_SCALE_BASE: u32 = 1230;
+const MENU_STARTUP_OFF: u32 = 1240;
+const MENU_STARTUP_RUN_KEY: u32 = 1241;
+const MENU_STARTUP_TASK: u32 = 1242;
 const MENU_EXIT: u32 = 1999;
 
 /// Helper to display a popup menu and return the selected command ID (or 0 if none)
@@ -50,7 +78,35 @@ pub fn show_popup_menu(hwnd: HWND, x: i32, y: i32, build_menu: impl FnOnce(HMENU
 
         build_menu(menu);
 
+        let cmd = if native_menus() {
+            track_native_menu(hwnd, menu, x, y)
+        } else {
+            track_themed_dropdown(hwnd, items_from_menu(menu), x, y, |_, _| {})
+        };
+        DestroyMenu(menu).ok();
+        cmd
+    }
+}
+
+/// Show a dropdown built from `items` and return the selected command ID (or 0
+/// if none). `on_slide` gets each new value of a slider row while it moves; with
+/// `native_menus` set, sliders are left out of the classic menu.
+pub fn show_dropdown(hwnd: HWND, x: i32, y: i32, items: Vec<DropdownItem>, on_slide: impl FnMut(u32, u32)) -> u32 {
+    if native_menus() {
+        let menu = to_native_menu(&items);
+        let cmd = track_native_menu(hwnd, menu, x, y);
+        unsafe {
+            DestroyMenu(menu).ok();
+        }
+        return cmd;
+    }
+    track_themed_dropdown(hwnd, items, x, y, on_slide)
+}
+
+fn track_native_menu(hwnd: HWND, menu: HMENU, x: i32, y: i32) -> u32 {
+    unsafe {
         let _ = SetForegroundWindow(hwnd);
+        crate::sounds::play_event(SoundEvent::PopupOpen);
         let cmd = TrackPopupMenu(
             menu,
             TPM_RIGHTBUTTON | TPM_LEFTALIGN | TPM_TOPALIGN | TPM_RETURNCMD,
@@ -60,11 +116,28 @@ pub fn show_popup_menu(hwnd: HWND, x: i32, y: i32, build_menu: impl FnOnce(HMENU
             hwnd,
             None,
         );
-        DestroyMenu(menu).ok();
+        crate::sounds::play_event(SoundEvent::PopupClose);
         cmd.0 as u32
     }
 }
 
+fn track_themed_dropdown(hwnd: HWND, items: Vec<DropdownItem>, x: i32, y: i32, on_slide: impl FnMut(u32, u32)) -> u32 {
+    unsafe {
+        let _ = SetForegroundWindow(hwnd);
+    }
+    crate::sounds::play_event(SoundEvent::PopupOpen);
+    let cmd = track_dropdown(items, x, y, on_slide);
+    crate::sounds::play_event(SoundEvent::PopupClose);
+    cmd
+}
+
+/// Whether module menus are classic Windows menus rather than themed dropdowns
+fn native_menus() -> bool {
+    get_window_state()
+        .map(|s| s.read().config.appearance.native_menus)
+        .unwrap_or(false)
+}
+
 /// Show the context menu
 pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
     unsafe {
@@ -78,7 +151,9 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
             .map(|s| s.read().config.clone())
             .unwrap_or_default();
 
-        let right_modules = &config.modules.right_modules;
+        let left_modules = &config.modules.left_modules;
+        // Modules inside a group count as shown
+        let right_modules = &config.modules.right_modules_flat();
         let center_modules = &config.modules.center_modules;
 
         // Module toggles with checkmarks
@@ -125,18 +200,90 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
             "Clipboard",
             right_modules.contains(&"clipboard".to_string()),
         );
+        append_menu_item(
+            menu,
+            MENU_SHOW_CAPTURE,
+            "Screen Capture",
+            right_modules.contains(&"capture".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_VOICE_MEMO,
+            "Voice Memo",
+            right_modules.contains(&"voice_memo".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_WATCHDOG,
+            "Process Watchdog",
+            right_modules.contains(&"watchdog".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_LAUNCHER,
+            "App Launcher",
+            left_modules.contains(&"launcher".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_TASKS,
+            "Running Apps",
+            left_modules.contains(&"tasks".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_PRINTING,
+            "Print Queue",
+            right_modules.contains(&"printing".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_REMOVABLE_MEDIA,
+            "USB Drives",
+            right_modules.contains(&"removable_media".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_SERVICES,
+            "Services",
+            right_modules.contains(&"services".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_SECURITY,
+            "Windows Security",
+            right_modules.contains(&"security".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_MONITOR_INPUT,
+            "Monitor Input",
+            right_modules.contains(&"monitor_input".to_string()),
+        );
         append_menu_item(
             menu,
             MENU_SHOW_GPU,
             "GPU Usage",
             right_modules.contains(&"gpu".to_string()),
         );
+        append_menu_item(
+            menu,
+            MENU_SHOW_THERMAL,
+            "Thermals",
+            right_modules.contains(&"thermal".to_string()),
+        );
         append_menu_item(
             menu,
             MENU_SHOW_KEYBOARD,
             "Keyboard Layout",
             right_modules.contains(&"keyboard_layout".to_string()),
         );
+        append_menu_item(
+            menu,
+            MENU_SHOW_LOCK_KEYS,
+            "Lock Keys",
+            right_modules.contains(&"lock_keys".to_string()),
+        );
         append_menu_item(
             menu,
             MENU_SHOW_UPTIME,
@@ -167,15 +314,64 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
             "Weather",
             right_modules.contains(&"weather".to_string()),
         );
+        append_menu_item(
+            menu,
+            MENU_SHOW_STOCKS,
+            "Stocks",
+            right_modules.contains(&"stocks".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_SPORTS,
+            "Sports Scores",
+            right_modules.contains(&"sports".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_CONNECTIVITY,
+            "Ping & Public IP",
+            right_modules.contains(&"connectivity".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_USER,
+            "User",
+            right_modules.contains(&"user".to_string()),
+        );
+        append_menu_item(
+            menu,
+            MENU_SHOW_POWER,
+            "Power Menu",
+            right_modules.contains(&"power".to_string()),
+        );
 
         // Separator
         AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
 
         // Settings and exit
         append_menu_item(menu, MENU_TOGGLE_SEARCH, "Enable Quick Search", config.search.enabled);
+        append_menu_item(
+            menu,
+            MENU_DO_NOT_DISTURB,
+            "Do Not Disturb",
+            config.attention.do_not_disturb,
+        );
+        append_menu_item(menu, MENU_OFFLINE_MODE, "Offline Mode", config.privacy.offline_mode);
+        if let Ok(sub) = CreatePopupMenu() {
+            for (i, preset) in crate::utils::UI_SCALE_PRESETS.iter().enumerate() {
+                let label = format!("{:.0}%", preset * 100.0);
+                let current = (config.appearance.ui_scale - preset).abs() < 0.01;
+                append_menu_item(sub, MENU_UI_SCALE_BASE + i as u32, &label, current);
+            }
+            let wide: Vec<u16> = crate::i18n::tr("Interface Size").encode_utf16().chain(std::iter::once(0)).collect();
+            AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+        }
+        append_startup_menu(menu);
+        append_menu_item(menu, MENU_SCHEDULER, "Scheduled Tasks && Alarms...", false);
         append_menu_item(menu, MENU_SETTINGS, "Open Config File", false);
         append_menu_item(menu, MENU_RELOAD, "Reload Config", false);
         append_menu_item(menu, MENU_RESET, "Reset to Defaults", false);
+        append_menu_item(menu, MENU_SHARE_SETUP, "Share My Setup...", false);
 
         AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
         append_menu_item(menu, MENU_EXIT, "Exit TopBar", false);
@@ -183,6 +379,7 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
         // Need to set foreground for menu to work properly
         let _ = SetForegroundWindow(hwnd);
 
+        crate::sounds::play_event(SoundEvent::PopupOpen);
         let cmd = TrackPopupMenu(
             menu,
             TPM_RIGHTBUTTON | TPM_LEFTALIGN | TPM_TOPALIGN | TPM_RETURNCMD,
@@ -192,6 +389,7 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
             hwnd,
             None,
         );
+        crate::sounds::play_event(SoundEvent::PopupClose);
 
         DestroyMenu(menu).ok();
 
@@ -202,16 +400,47 @@ pub fn show_context_menu(hwnd: HWND, x: i32, y: i32) {
     }
 }
 
+/// Append the "Start with Windows" submenu, checked by what's actually registered
+pub(super) fn append_startup_menu(menu: HMENU) {
+    use crate::config::StartupMethod;
+
+    let registered = crate::startup::registered();
+    unsafe {
+        if let Ok(sub) = CreatePopupMenu() {
+            append_menu_item(sub, MENU_STARTUP_OFF, "Off", registered.is_none());
+            append_menu_item(sub, MENU_STARTUP_RUN_KEY, "At Sign-in", registered == Some(StartupMethod::RunKey));
+            append_menu_item(
+                sub,
+                MENU_STARTUP_TASK,
+                "At Sign-in, Elevated (Task Scheduler)",
+                registered == Some(StartupMethod::TaskScheduler),
+            );
+            let wide: Vec<u16> = crate::i18n::tr("Start with Windows").encode_utf16().chain(std::iter::once(0)).collect();
+            AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+        }
+    }
+}
+
+/// Right-to-left reading order for items whose text is Arabic or Hebrew
+pub(super) fn reading_order(text: &str) -> MENU_ITEM_FLAGS {
+    if crate::utils::is_rtl(text) {
+        MENU_ITEM_FLAGS(MFT_RIGHTORDER.0)
+    } else {
+        MENU_ITEM_FLAGS(0)
+    }
+}
+
 /// Helper to append a menu item
 fn append_menu_item(menu: HMENU, id: u32, text: &str, checked: bool) {
     unsafe {
+        let text = crate::i18n::tr(text);
         let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
         let flags = if checked {
             MF_STRING | MF_CHECKED
         } else {
             MF_STRING
         };
-        AppendMenuW(menu, flags, id as usize, PCWSTR(wide.as_ptr())).ok();
+        AppendMenuW(menu, flags | reading_order(&text), id as usize, PCWSTR(wide.as_ptr())).ok();
     }
 }
 
@@ -227,15 +456,52 @@ pub fn handle_menu_command(hwnd: HWND, cmd_id: u32) {
         MENU_SHOW_MEDIA => toggle_module(hwnd, "media"),
         MENU_SHOW_CLIPBOARD => toggle_module(hwnd, "clipboard"),
         MENU_SHOW_GPU => toggle_module(hwnd, "gpu"),
+        MENU_SHOW_THERMAL => toggle_module(hwnd, "thermal"),
+        MENU_SHOW_WATCHDOG => toggle_module(hwnd, "watchdog"),
+        MENU_SHOW_CAPTURE => toggle_module(hwnd, "capture"),
+        MENU_SHOW_SERVICES => toggle_module(hwnd, "services"),
+        MENU_SHOW_LAUNCHER => toggle_module(hwnd, "launcher"),
+        MENU_SHOW_TASKS => toggle_module(hwnd, "tasks"),
         MENU_SHOW_KEYBOARD => toggle_module(hwnd, "keyboard_layout"),
+        MENU_SHOW_LOCK_KEYS => toggle_module(hwnd, "lock_keys"),
+        MENU_SHOW_STOCKS => toggle_module(hwnd, "stocks"),
+        MENU_SHOW_SPORTS => toggle_module(hwnd, "sports"),
+        MENU_SHOW_SECURITY => toggle_module(hwnd, "security"),
+        MENU_SHOW_MONITOR_INPUT => toggle_module(hwnd, "monitor_input"),
+        MENU_SHOW_VOICE_MEMO => toggle_module(hwnd, "voice_memo"),
+        MENU_SHOW_POWER => toggle_module(hwnd, "power"),
+        MENU_SHOW_USER => toggle_module(hwnd, "user"),
+        MENU_SHOW_CONNECTIVITY => toggle_module(hwnd, "connectivity"),
+        MENU_SHOW_PRINTING => toggle_module(hwnd, "printing"),
+        MENU_SHOW_REMOVABLE_MEDIA => toggle_module(hwnd, "removable_media"),
         MENU_SHOW_UPTIME => toggle_module(hwnd, "uptime"),
         MENU_SHOW_BLUETOOTH => toggle_module(hwnd, "bluetooth"),
         MENU_SHOW_NIGHT_LIGHT => toggle_module(hwnd, "night_light"),
         MENU_SHOW_DISK => toggle_module(hwnd, "disk"),
         MENU_SHOW_WEATHER => toggle_module(hwnd, "weather"),
+        MENU_SCHEDULER => {
+            let mut pt = windows::Win32::Foundation::POINT::default();
+            unsafe {
+                let _ = GetCursorPos(&mut pt);
+            }
+            super::module_handlers::show_scheduler_menu(hwnd, pt.x, pt.y);
+        }
+        MENU_DO_NOT_DISTURB => toggle_config_bool(hwnd, |c| &mut c.attention.do_not_disturb),
+        MENU_OFFLINE_MODE => toggle_config_bool(hwnd, |c| &mut c.privacy.offline_mode),
+        id if (MENU_UI_SCALE_BASE..MENU_UI_SCALE_BASE + crate::utils::UI_SCALE_PRESETS.len() as u32).contains(&id) => {
+            set_ui_scale(hwnd, crate::utils::UI_SCALE_PRESETS[(id - MENU_UI_SCALE_BASE) as usize]);
+        }
+        MENU_STARTUP_OFF => crate::startup::set(None),
+        MENU_STARTUP_RUN_KEY => crate::startup::set(Some(crate::config::StartupMethod::RunKey)),
+        MENU_STARTUP_TASK => crate::startup::set(Some(crate::config::StartupMethod::TaskScheduler)),
         MENU_SETTINGS => open_config_file(),
         MENU_RELOAD => reload_config(hwnd),
         MENU_RESET => reset_config(hwnd),
+        MENU_SHARE_SETUP => {
+            if let Err(e) = super::config_handlers::share_setup(hwnd) {
+                warn!("Failed to save bar setup: {}", e);
+            }
+        }
         MENU_EXIT => unsafe {
             let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
         },
@@ -245,6 +511,10 @@ pub fn handle_menu_command(hwnd: HWND, cmd_id: u32) {
         2002 => toggle_config_bool(hwnd, |c| &mut c.modules.clock.show_seconds),
         2003 => toggle_config_bool(hwnd, |c| &mut c.modules.clock.show_date),
         2004 => toggle_config_bool(hwnd, |c| &mut c.modules.clock.show_day),
+        2006 => set_chime_mode(hwnd, crate::config::ChimeMode::Off),
+        2007 => set_chime_mode(hwnd, crate::config::ChimeMode::Chime),
+        2008 => set_chime_mode(hwnd, crate::config::ChimeMode::Speak),
+        2009 => set_chime_mode(hwnd, crate::config::ChimeMode::Both),
 
         // Battery settings
         2401 => {
@@ -268,6 +538,21 @@ pub fn handle_menu_command(hwnd: HWND, cmd_id: u32) {
             }
         },
         2402 => toggle_config_bool(hwnd, |c| &mut c.modules.battery.show_time_remaining),
+        2403 => {
+            toggle_config_bool(hwnd, |c| &mut c.modules.battery.show_devices);
+            if let Some(state) = get_window_state() {
+                let config = state.read().config.clone();
+                with_renderer(|renderer| {
+                    if let Some(b) = renderer
+                        .module_registry
+                        .get_mut("battery")
+                        .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::battery::BatteryModule>())
+                    {
+                        b.rebuild_cached_text(&config);
+                    }
+                });
+            }
+        }
 
         // Volume settings
         2201 => {
@@ -305,17 +590,34 @@ pub fn handle_menu_command(hwnd: HWND, cmd_id: u32) {
         // Network settings
         2301 => toggle_config_bool(hwnd, |c| &mut c.modules.network.show_name),
         2302 => toggle_config_bool(hwnd, |c| &mut c.modules.network.show_speed),
+        2303 => toggle_config_bool(hwnd, |c| &mut c.modules.network.show_top_talkers),
 
         // System info settings
         2103 => toggle_config_bool(hwnd, |c| &mut c.modules.system_info.show_graph),
 
         // GPU settings
         2604 => toggle_config_bool(hwnd, |c| &mut c.modules.gpu.show_graph),
+        2602 => toggle_config_bool(hwnd, |c| &mut c.modules.gpu.show_temperature),
+
+        // Thermal settings
+        2801 => toggle_config_bool(hwnd, |c| &mut c.modules.thermal.show_fans),
+        2802 => toggle_config_bool(hwnd, |c| &mut c.modules.thermal.notify),
+
+        // Capture settings
+        3504 => toggle_config_bool(hwnd, |c| &mut c.modules.capture.copy_to_clipboard),
 
         // Keyboard layout settings
         2701 => {
             toggle_config_bool(hwnd, |c| &mut c.modules.keyboard_layout.show_full_name)
         }
+        2702 => toggle_config_bool(hwnd, |c| &mut c.modules.keyboard_layout.show_flag),
+
+        // Lock keys settings
+        3800 => toggle_config_bool(hwnd, |c| &mut c.modules.lock_keys.show_caps_lock),
+        3801 => toggle_config_bool(hwnd, |c| &mut c.modules.lock_keys.show_num_lock),
+        3802 => toggle_config_bool(hwnd, |c| &mut c.modules.lock_keys.show_scroll_lock),
+        3803 => toggle_config_bool(hwnd, |c| &mut c.modules.lock_keys.only_active),
+        3804 => toggle_config_bool(hwnd, |c| &mut c.modules.lock_keys.on_screen_indicator),
 
         // Uptime settings
         // (ShowDays and Compact removed - fixed behavior)
@@ -393,22 +695,12 @@ pub fn handle_menu_command(hwnd: HWND, cmd_id: u32) {
                         }
                     }
 
-                    // Build in background and set global index
-                    let paths = new_config.search.index_paths.clone();
-                    std::thread::spawn(move || {
-                        match crate::search::SearchIndex::build(&paths) {
-                            Ok(idx) => {
-                                if let Some(g) = crate::search::global_index() {
-                                    *g.write() = Some(idx);
-                                }
-                            }
-                            Err(e) => {
-                                log::warn!("Failed to build search index after enabling: {}", e);
-                            }
-                        }
-                    });
+                    // Load or build in background and set global index
+                    let search_config = new_config.search.clone();
+                    std::thread::spawn(move || crate::search::start(&search_config));
                 } else {
-                    // Disable: clear the in-memory index and unregister the hotkey
+                    // Disable: stop watching, clear the in-memory index and unregister the hotkey
+                    crate::search::stop();
                     if let Some(g) = crate::search::global_index() {
                         *g.write() = None;
                     }
@@ -587,19 +879,34 @@ pub fn show_quickstart_dialog() {
 /// Default order of right-side modules for insertion position calculation
 const DEFAULT_RIGHT_MODULE_ORDER: &[&str] = &[
     "weather",
+    "stocks",
+    "sports",
     "media",
     "clipboard",
+    "capture",
+    "voice_memo",
+    "printing",
+    "removable_media",
+    "watchdog",
+    "services",
+    "security",
+    "monitor_input",
     "keyboard_layout",
+    "lock_keys",
     "gpu",
     "system_info",
+    "thermal",
     "disk",
     "network",
+    "connectivity",
     "bluetooth",
     "night_light",
     "volume",
     "battery",
     "uptime",
+    "user",
     "clock",
+    "power",
 ];
 
 /// Find the appropriate insert position for a module based on default order
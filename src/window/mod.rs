@@ -9,8 +9,12 @@ pub mod proc;
 pub mod menus;
 pub mod module_handlers;
 pub mod config_handlers;
+pub mod snap;
+pub mod tile;
+pub mod automation;
+pub mod focus;
 
 // Re-export main types for convenience
 pub use manager::WindowManager;
-pub use proc::{window_proc, WM_TOPBAR_UPDATE, WM_TOPBAR_THEME_CHANGED, WM_TOPBAR_TRAY, WM_TOPBAR_MODULE_CLICK, WM_TOPBAR_NIGHTLIGHT_TOGGLED};
+pub use proc::{window_proc, WM_TOPBAR_UPDATE, WM_TOPBAR_THEME_CHANGED, WM_TOPBAR_TRAY, WM_TOPBAR_MODULE_CLICK, WM_TOPBAR_NIGHTLIGHT_TOGGLED, WM_TOPBAR_IPC};
 pub use state::get_main_hwnd;
\ No newline at end of file
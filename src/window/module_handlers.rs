@@ -2,41 +2,157 @@
 //!
 //! Contains functions for handling module clicks and showing module-specific menus.
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::Win32::Graphics::Gdi::{ClientToScreen, InvalidateRect};
 
+use crate::config::BarPosition;
+use crate::render::DropdownItem;
+use crate::sounds::SoundEvent;
 use crate::utils::open_url;
 
 use super::state::get_window_state;
 use super::renderer::with_renderer;
-use super::menus::show_popup_menu;
+use super::menus::{reading_order, show_dropdown, show_popup_menu};
 
 // Menu IDs for clock settings
 const CLOCK_24H: u32 = 2001;
 const CLOCK_SECONDS: u32 = 2002;
 const CLOCK_DATE: u32 = 2003;
 const CLOCK_DAY: u32 = 2004;
+const CLOCK_CHIME_OFF: u32 = 2006;
+const CLOCK_CHIME: u32 = 2007;
+const CLOCK_CHIME_SPEAK: u32 = 2008;
+const CLOCK_CHIME_BOTH: u32 = 2009;
 
 // Menu IDs for system info
 const SYSINFO_SHOW_GRAPH: u32 = 2103; // show as moving graph
+const SYSINFO_FREE_STANDBY: u32 = 2104;
+// Top memory process actions (two IDs per process: location, end)
+const SYSINFO_PROC_BASE: u32 = 2110;
+const SYSINFO_TOP_PROCESSES: usize = 5;
+
+// Menu IDs for GPU (settings toggles are handled in menus.rs)
+const GPU_USAGE: u32 = 2605;
 
 // Menu IDs for volume
 const VOL_SHOW_PCT: u32 = 2201;
 const VOL_MUTE: u32 = 2202;
+const VOL_SLIDER: u32 = 2203;
 
 // Menu IDs for network
 const NET_SHOW_NAME: u32 = 2301;
 const NET_SHOW_SPEED: u32 = 2302;
+const NET_SHOW_TOP_TALKERS: u32 = 2303;
+const NET_USAGE: u32 = 2304;
+// Top-talker actions: base + index * 2 opens the file location, + 1 ends the process
+const NET_TALKER_BASE: u32 = 2310;
 
 // Menu IDs for battery
 const BAT_SHOW_PCT: u32 = 2401;
 const BAT_SHOW_TIME: u32 = 2402;
+const BAT_SHOW_DEVICES: u32 = 2403;
 
 // Menu IDs for keyboard layout
 const KEYBOARD_SHOW_FULL: u32 = 2701;
+const KEYBOARD_SHOW_FLAG: u32 = 2702;
+const KEYBOARD_LAYOUT_BASE: u32 = 2710;
+
+// Menu IDs for thermal
+const THERMAL_SHOW_FANS: u32 = 2801;
+const THERMAL_NOTIFY: u32 = 2802;
+
+// Watchdog process actions (two IDs per process: start, view log)
+const WATCHDOG_BASE: u32 = 3400;
+
+// Service actions (three IDs per service: start, stop, restart)
+const SERVICES_OPEN_MMC: u32 = 3590;
+const SERVICES_BASE: u32 = 3600;
+/// Services listed in the menu; each takes 3 IDs, so they stay below LAUNCHER_OPEN
+const SERVICES_MAX: usize = 33;
+
+// Stocks menu items
+const STOCKS_REFRESH: u32 = 3900;
+
+// Sports menu items
+const SPORTS_REFRESH: u32 = 3950;
+
+// Windows Security menu items
+const SECURITY_OPEN: u32 = 3960;
+const SECURITY_FIREWALL: u32 = 3961;
+const SECURITY_REFRESH: u32 = 3962;
+
+// Power menu items (POWER_BASE + index into PowerAction::ALL)
+const POWER_BASE: u32 = 3970;
+// User menu items
+const USER_LOCK: u32 = 3980;
+const USER_SWITCH: u32 = 3981;
+const USER_SIGN_OUT: u32 = 3982;
+const USER_SETTINGS: u32 = 3983;
+
+// Connectivity menu items
+const CONNECTIVITY_REFRESH: u32 = 3990;
+const CONNECTIVITY_COPY_IP: u32 = 3991;
+const CONNECTIVITY_SETTINGS: u32 = 3992;
+
+// Print queue menu items (PRINTING_QUEUE_BASE + printer index, PRINTING_CANCEL_BASE + job index)
+const PRINTING_SETTINGS: u32 = 6200;
+const PRINTING_QUEUE_BASE: u32 = 6210;
+const PRINTING_CANCEL_BASE: u32 = 6250;
+const PRINTING_MAX_JOBS: usize = 40;
+
+// Removable media menu items (REMOVABLE_OPEN_BASE / REMOVABLE_EJECT_BASE + volume index)
+const REMOVABLE_OPEN_BASE: u32 = 6300;
+const REMOVABLE_EJECT_BASE: u32 = 6330;
+const REMOVABLE_SETTINGS: u32 = 6360;
+
+// Voice memo menu items (recent memos are VOICE_MEMO_BASE + index)
+const VOICE_MEMO_RECORD: u32 = 4150;
+const VOICE_MEMO_OPEN_FOLDER: u32 = 4151;
+const VOICE_MEMO_BASE: u32 = 4160;
+const VOICE_MEMO_MAX_LISTED: usize = 20;
+
+// Monitor input menu items (inputs are MONITOR_INPUT_BASE + monitor * 20 + input)
+const MONITOR_INPUT_REFRESH: u32 = 4190;
+const MONITOR_INPUT_BASE: u32 = 4200;
+const MONITOR_INPUT_STRIDE: u32 = 20;
+
+// Launcher icon context menu
+const LAUNCHER_OPEN: u32 = 3700;
+const LAUNCHER_RUN_ADMIN: u32 = 3701;
+const LAUNCHER_OPEN_LOCATION: u32 = 3702;
+
+/// Quick alarms in the clock menu: (minutes from now, label)
+const ALARM_PRESETS: [(u32, &str); 5] = [
+    (5, "In 5 Minutes"),
+    (10, "In 10 Minutes"),
+    (15, "In 15 Minutes"),
+    (30, "In 30 Minutes"),
+    (60, "In 1 Hour"),
+];
+
+// Scheduled task actions (two IDs per task: run now, enable/disable)
+const SCHEDULER_BASE: u32 = 5000;
+const ALARM_IN_BASE: u32 = 5180;
+const ALARM_SNOOZE: u32 = 5190;
+const ALARM_DISMISS: u32 = 5191;
+const ALARM_BASE: u32 = 5200;
+
+// Menu IDs for capture
+const CAPTURE_FULL: u32 = 3500;
+const CAPTURE_REGION: u32 = 3501;
+const CAPTURE_RECORD: u32 = 3502;
+const CAPTURE_OPEN_FOLDER: u32 = 3503;
+const CAPTURE_CLIPBOARD: u32 = 3504;
+
+// Menu IDs for lock keys
+const LOCK_KEYS_CAPS: u32 = 3800;
+const LOCK_KEYS_NUM: u32 = 3801;
+const LOCK_KEYS_SCROLL: u32 = 3802;
+const LOCK_KEYS_ONLY_ACTIVE: u32 = 3803;
+const LOCK_KEYS_INDICATOR: u32 = 3804;
 
 // Menu IDs for uptime
 // (compact/ShowDays removed - behavior now fixed)
@@ -51,10 +167,22 @@ const DISK_SELECT_BASE: u32 = 3100;
 
 // Clipboard history base (dynamic entries)
 const CLIPBOARD_BASE: u32 = 4000;
+const CLIPBOARD_TRANSLATE: u32 = 4100;
+const CLIPBOARD_TRANSLATION_BASE: u32 = 4110;
+const CLIPBOARD_TRANSLATIONS_LISTED: usize = 10;
+
+// Active window menu IDs (the window list is ACTIVE_WINDOW_BASE + index)
+const ACTIVE_MINIMIZE: u32 = 6090;
+const ACTIVE_MAXIMIZE: u32 = 6091;
+const ACTIVE_CLOSE: u32 = 6092;
+const ACTIVE_WINDOW_BASE: u32 = 6100;
+const ACTIVE_WINDOW_MAX: usize = 50;
 
 // Weather menu IDs
 const WEATHER_OPEN: u32 = 6001;
 const WEATHER_REFRESH: u32 = 6002;
+const WEATHER_ALERT_BASE: u32 = 6020;
+const WEATHER_ALERTS_LISTED: usize = 10;
 
 // Clock center toggle
 const CLOCK_CENTER: u32 = 2005;
@@ -65,31 +193,90 @@ const APP_SETTINGS: u32 = 2502;
 const APP_RELOAD: u32 = 2503;
 const APP_RESET: u32 = 2505;
 const APP_INSTALL_CURSORS: u32 = 2506;
+const APP_SHELF: u32 = 2507;
+const APP_COMMAND_PALETTE: u32 = 2508;
 const APP_EXIT: u32 = 2504;
+const APP_SNAP_BASE: u32 = 2510;
+const APP_LOGIN_SETTINGS: u32 = 2520;
+const APP_TILE_BASE: u32 = 2521;
+const APP_RECENT_CLEAR: u32 = 2509;
+const APP_RECENT_BASE: u32 = 2580;
+const APP_LOGIN_BASE: u32 = 6400;
+/// Login items listed in the app menu (IDs up to 6499)
+const APP_LOGIN_MAX: usize = 100;
+/// Recent files and folders listed in the app menu
+const RECENT_LIMIT: usize = 12;
 
 /// Handle module click actions - show in-app configuration dropdowns
 pub fn handle_module_click(hwnd: HWND, module_id: &str, click_x: i32) {
     info!("Module clicked: {}", module_id);
+    crate::attention::dismiss(module_id);
+
+    // A configured on_click replaces whatever the module does itself
+    let binding = get_window_state().and_then(|s| s.read().config.modules.on_click(module_id).map(str::to_string));
+    if let Some(binding) = binding {
+        if let Err(e) = crate::actions::execute_binding(hwnd, &binding) {
+            warn!("on_click for {} failed: {}", module_id, e);
+        }
+        return;
+    }
+
+    // Group headers collapse and expand their group
+    if module_id.starts_with(crate::config::GROUP_PREFIX) {
+        super::config_handlers::toggle_group(hwnd, module_id);
+        return;
+    }
+
+    // Pinned launcher icons start their app directly
+    if let Some(index) = crate::modules::launcher::item_index(module_id) {
+        if let Some(app) = pinned_app(index) {
+            crate::modules::launcher::launch(&app, false);
+        }
+        return;
+    }
 
-    // Special case: keyboard_layout should switch languages on click, not show menu
-    if module_id == "keyboard_layout" {
+    // Running app icons focus or minimize their window
+    if let Some(target) = crate::modules::tasks::item_hwnd(module_id) {
         with_renderer(|renderer| {
-            if let Some(module) = renderer.module_registry.get_mut(module_id) {
-                module.on_click();
+            if let Some(tm) = renderer
+                .module_registry
+                .get_mut("tasks")
+                .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::tasks::TasksModule>())
+            {
+                tm.activate_or_minimize(target);
             }
         });
-        // Request redraw to update the display
-        if let Some(state) = get_window_state() {
-            state.write().needs_redraw = true;
-        }
         unsafe {
             let _ = InvalidateRect(hwnd, None, false);
         }
         return;
     }
 
-    // Get screen position for dropdown
-    let mut pt = windows::Win32::Foundation::POINT { x: click_x, y: 28 };
+    // While recording, a click on the indicator stops the recording
+    if module_id == "capture" && crate::modules::screen_recorder::is_recording() {
+        toggle_recording(hwnd);
+        return;
+    }
+
+    // Voice memos start and stop with a single click; the memo list is on right-click
+    if module_id == "voice_memo" {
+        toggle_voice_memo(hwnd);
+        return;
+    }
+
+    // Get screen position for dropdown: under the click, or beside it on a vertical bar
+    let (bar_rect, position) = get_window_state()
+        .map(|s| {
+            let s = s.read();
+            (s.bar_rect, s.config.appearance.position)
+        })
+        .unwrap_or((crate::utils::Rect::default(), BarPosition::Top));
+    let (x, y) = match position {
+        BarPosition::Top | BarPosition::Bottom => (click_x, bar_rect.height),
+        BarPosition::Left => (bar_rect.width, click_x),
+        BarPosition::Right => (0, click_x),
+    };
+    let mut pt = windows::Win32::Foundation::POINT { x, y };
     unsafe {
         let _ = ClientToScreen(hwnd, &mut pt);
     }
@@ -97,16 +284,189 @@ pub fn handle_module_click(hwnd: HWND, module_id: &str, click_x: i32) {
     show_module_menu(hwnd, module_id, pt.x, pt.y);
 }
 
+/// Handle a middle click on a module
+pub fn handle_module_middle_click(hwnd: HWND, module_id: &str) {
+    // Middle-click closes a running app's window, as on the Windows taskbar
+    if let Some(target) = crate::modules::tasks::item_hwnd(module_id) {
+        crate::modules::tasks::TasksModule::close(target);
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, false);
+        }
+    }
+}
+
+/// Handle a Ctrl+click on a module: copy its value and flash "Copied" over it
+pub fn copy_module_value(hwnd: HWND, module_id: &str) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let config = state.read().config.clone();
+    let value = with_renderer(|renderer| {
+        renderer
+            .module_registry
+            .get(module_id)
+            .map(|m| m.copy_value(&config))
+    })
+    .flatten()
+    .map(|v| v.trim().to_string())
+    .filter(|v| !v.is_empty());
+    let Some(value) = value else {
+        debug!("Nothing to copy from module: {}", module_id);
+        return;
+    };
+
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(value.clone())) {
+        log::warn!("Failed to copy value of {}: {}", module_id, e);
+        return;
+    }
+    info!("Copied value of {}: {}", module_id, value);
+    state.write().copied_flash = Some((module_id.to_string(), std::time::Instant::now()));
+    crate::sounds::play_event(SoundEvent::ValueChange(None));
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+}
+
+/// Show the tooltip for the hovered module, or hide it when there is nothing to show
+pub fn show_module_tooltip(hwnd: HWND) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let (hover, edge, bar_rect, config) = {
+        let s = state.read();
+        let hover = s.hover_module.clone().filter(|_| s.dragging_module.is_none());
+        (hover, s.config.appearance.position, s.bar_rect, s.config.clone())
+    };
+    let content = hover.filter(|_| !super::manager::WindowManager::is_slim()).and_then(|id| {
+        with_renderer(|renderer| {
+            let bounds = *renderer.module_bounds().get(&id)?;
+            let module = renderer.module_registry.get(&id)?;
+            Some((id, bounds, crate::modules::tooltip_text(module, &config)?, module.history()))
+        })
+        .flatten()
+    });
+    let Some((id, bounds, text, history)) = content else {
+        crate::render::hide_tooltip();
+        return;
+    };
+
+    let bounds = super::manager::WindowManager::from_layout(&bar_rect, &bounds);
+    let mut origin = windows::Win32::Foundation::POINT { x: bounds.x, y: bounds.y };
+    unsafe {
+        let _ = ClientToScreen(hwnd, &mut origin);
+    }
+    let anchor = crate::utils::Rect::new(origin.x, origin.y, bounds.width, bounds.height);
+    crate::render::show_tooltip(&id, &text, history.as_ref(), anchor, edge);
+}
+
+/// Handle a right click on a module; returns false to fall back to the bar context menu
+pub fn handle_module_right_click(hwnd: HWND, module_id: &str, x: i32, y: i32) -> bool {
+    if let Some(index) = crate::modules::launcher::item_index(module_id) {
+        show_launcher_item_menu(hwnd, index, x, y);
+        return true;
+    }
+    if module_id == "voice_memo" {
+        show_voice_memo_menu(hwnd, x, y);
+        return true;
+    }
+    if module_id == "active_app" {
+        show_active_window_actions(hwnd, x, y);
+        return true;
+    }
+    if module_id == "network" {
+        show_network_menu(hwnd, x, y);
+        return true;
+    }
+    if module_id == "gpu" {
+        show_gpu_menu(hwnd, x, y);
+        return true;
+    }
+    if module_id == "printing" {
+        show_printing_menu(hwnd, x, y);
+        return true;
+    }
+    false
+}
+
+/// Look up a pinned launcher app by index
+fn pinned_app(index: usize) -> Option<crate::config::PinnedApp> {
+    get_window_state()?
+        .read()
+        .config
+        .modules
+        .launcher
+        .apps
+        .get(index)
+        .cloned()
+}
+
+/// Context menu for a single launcher icon
+fn show_launcher_item_menu(hwnd: HWND, index: usize, x: i32, y: i32) {
+    let Some(app) = pinned_app(index) else {
+        return;
+    };
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_disabled_item(menu, &app.display_name());
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, LAUNCHER_OPEN, "Open", false);
+        append_menu_item(menu, LAUNCHER_RUN_ADMIN, "Run as Administrator", false);
+        append_menu_item(menu, LAUNCHER_OPEN_LOCATION, "Open File Location", false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, APP_SETTINGS, "Edit Pinned Apps...", false);
+    });
+
+    if cmd != 0 {
+        info!("Launcher menu returned cmd: {}", cmd);
+        match cmd {
+            LAUNCHER_OPEN => crate::modules::launcher::launch(&app, false),
+            LAUNCHER_RUN_ADMIN => crate::modules::launcher::launch(&app, true),
+            LAUNCHER_OPEN_LOCATION => open_process_location(&app.path),
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
 /// Show module-specific menu
 pub fn show_module_menu(hwnd: HWND, module_id: &str, x: i32, y: i32) {
     match module_id {
         "clock" => show_clock_menu(hwnd, x, y),
         "battery" => show_battery_menu(hwnd, x, y),
         "volume" => show_volume_menu(hwnd, x, y),
-        "network" => show_network_menu(hwnd, x, y),
+        "network" => crate::render::toggle_network_usage(hwnd, x, y),
         "system_info" => show_sysinfo_menu(hwnd, x, y),
-        "gpu" => show_gpu_menu(hwnd, x, y),
-        "keyboard_layout" => show_keyboard_menu(hwnd, x, y), // This won't be reached due to early return above
+        "gpu" => crate::render::toggle_gpu_usage(hwnd, x, y),
+        "thermal" => show_thermal_menu(hwnd, x, y),
+        "watchdog" => show_watchdog_menu(hwnd, x, y),
+        "capture" => show_capture_menu(hwnd, x, y),
+        "voice_memo" => show_voice_memo_menu(hwnd, x, y),
+        "services" => show_services_menu(hwnd, x, y),
+        "removable_media" => show_removable_media_menu(hwnd, x, y),
+        "printing" => {
+            // One printer busy: straight to its queue, like the macOS printer icon
+            let mut printers = Vec::new();
+            with_renderer(|renderer| {
+                if let Some(module) = renderer.module_registry.get("printing") {
+                    if let Some(pm) = module.as_any().downcast_ref::<crate::modules::printing::PrintingModule>() {
+                        printers = pm.printers();
+                    }
+                }
+            });
+            match printers.as_slice() {
+                [printer] => crate::modules::printing::open_queue(printer),
+                _ => show_printing_menu(hwnd, x, y),
+            }
+        }
+        "stocks" => show_stocks_menu(hwnd, x, y),
+        "sports" => show_sports_menu(hwnd, x, y),
+        "security" => show_security_menu(hwnd, x, y),
+        "power" => show_power_menu(hwnd, x, y),
+        "active_app" => show_active_window_list(hwnd, x, y),
+        "user" => show_user_menu(hwnd, x, y),
+        "connectivity" => show_connectivity_menu(hwnd, x, y),
+        "monitor_input" => show_monitor_input_menu(hwnd, x, y),
+        "keyboard_layout" => show_keyboard_menu(hwnd, x, y),
+        "lock_keys" => show_lock_keys_menu(hwnd, x, y),
         "uptime" => show_uptime_menu(hwnd, x, y),
         "bluetooth" => show_bluetooth_menu(hwnd, x, y),
         "night_light" => {
@@ -126,7 +486,16 @@ pub fn show_module_menu(hwnd: HWND, module_id: &str, x: i32, y: i32) {
         }
         "disk" => show_disk_menu(hwnd, x, y),
         "clipboard" => show_clipboard_menu(hwnd, x, y),
-        "app_menu" => show_app_menu(hwnd, x, y),
+        "app_menu" => {
+            let quick_toggles = get_window_state()
+                .map(|s| s.read().config.modules.app_menu.quick_toggles)
+                .unwrap_or(true);
+            if quick_toggles {
+                crate::render::toggle_quick_toggles(hwnd, x, y);
+            } else {
+                show_app_menu(hwnd, x, y);
+            }
+        }
         "weather" => show_weather_menu(hwnd, x, y),
         "search" => {
             // Open quick search popup
@@ -149,12 +518,90 @@ fn show_clock_menu(hwnd: HWND, x: i32, y: i32) {
         append_menu_item(menu, CLOCK_DATE, "Show Date", config.modules.clock.show_date);
         append_menu_item(menu, CLOCK_DAY, "Show Day of Week", config.modules.clock.show_day);
         append_menu_item(menu, CLOCK_CENTER, "Center Clock", config.modules.clock.center);
+
+        let mode = config.modules.clock.chime.mode;
+        unsafe {
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+            if let Ok(sub) = CreatePopupMenu() {
+                use crate::config::ChimeMode;
+                append_menu_item(sub, CLOCK_CHIME_OFF, "Off", mode == ChimeMode::Off);
+                append_menu_item(sub, CLOCK_CHIME, "Chime", mode == ChimeMode::Chime);
+                append_menu_item(sub, CLOCK_CHIME_SPEAK, "Speak Time", mode == ChimeMode::Speak);
+                append_menu_item(sub, CLOCK_CHIME_BOTH, "Chime and Speak", mode == ChimeMode::Both);
+                let label = if config.modules.clock.chime.interval_min == 60 {
+                    "Hourly Announcement".to_string()
+                } else {
+                    format!("Announce Every {} Min", config.modules.clock.chime.interval_min)
+                };
+                let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+
+            if let Ok(sub) = CreatePopupMenu() {
+                append_alarm_items(sub, &config.scheduler.alarms);
+                if let Ok(add) = CreatePopupMenu() {
+                    for (i, (_, label)) in ALARM_PRESETS.iter().enumerate() {
+                        append_menu_item(add, ALARM_IN_BASE + i as u32, label, false);
+                    }
+                    let wide: Vec<u16> = crate::i18n::tr("New Alarm").encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(sub, MF_POPUP, add.0 as usize, PCWSTR(wide.as_ptr())).ok();
+                }
+                append_menu_item(sub, APP_SETTINGS, "Edit Alarms...", false);
+                let wide: Vec<u16> = crate::i18n::tr("Alarms").encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
     });
 
     if cmd != 0 {
         info!("Clock menu returned cmd: {}", cmd);
-        super::menus::handle_menu_command(hwnd, cmd);
+        if (ALARM_IN_BASE..ALARM_IN_BASE + ALARM_PRESETS.len() as u32).contains(&cmd) {
+            let (minutes, _) = ALARM_PRESETS[(cmd - ALARM_IN_BASE) as usize];
+            let at = crate::alarms::parse_time(&format!("+{}", minutes)).unwrap_or_default();
+            if let Err(e) = crate::alarms::add(crate::config::Alarm::new("", &at, "")) {
+                warn!("Failed to add alarm: {}", e);
+            }
+        } else if !handle_alarm_command(hwnd, cmd, config.scheduler.alarms.len()) {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Snooze/dismiss while ringing, then each alarm with its next ring (click to turn it on or off)
+fn append_alarm_items(menu: HMENU, alarms: &[crate::config::Alarm]) {
+    if crate::alarms::is_ringing() {
+        append_menu_item(menu, ALARM_SNOOZE, "Snooze Alarm", false);
+        append_menu_item(menu, ALARM_DISMISS, "Dismiss Alarm", false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+    }
+    for (i, alarm) in alarms.iter().enumerate() {
+        let repeat = if alarm.repeat.trim().is_empty() { "once" } else { alarm.repeat.trim() };
+        let mut label = format!("⏰ {} {} — {}", alarm.at, alarm.label, repeat);
+        if alarm.enabled {
+            if let Some(next) = crate::alarms::next_ring(alarm) {
+                label.push_str(&format!(" (next {})", next.format("%a %H:%M")));
+            }
+        }
+        append_menu_item(menu, ALARM_BASE + i as u32, &label, alarm.enabled);
+    }
+    if !alarms.is_empty() {
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+    }
+}
+
+/// Run a command from `append_alarm_items`; false if it wasn't one
+fn handle_alarm_command(hwnd: HWND, cmd: u32, alarm_count: usize) -> bool {
+    if cmd == ALARM_SNOOZE {
+        crate::alarms::snooze();
+    } else if cmd == ALARM_DISMISS {
+        crate::alarms::dismiss();
+    } else if (ALARM_BASE..ALARM_BASE + alarm_count as u32).contains(&cmd) {
+        let index = (cmd - ALARM_BASE) as usize;
+        super::config_handlers::toggle_config_bool(hwnd, |c| &mut c.scheduler.alarms[index].enabled);
+    } else {
+        return false;
     }
+    true
 }
 
 fn show_battery_menu(hwnd: HWND, x: i32, y: i32) {
@@ -162,9 +609,49 @@ fn show_battery_menu(hwnd: HWND, x: i32, y: i32) {
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
+    let mut batteries = Vec::new();
+    let mut devices = Vec::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("battery") {
+            if let Some(bm) = module.as_any().downcast_ref::<crate::modules::battery::BatteryModule>() {
+                batteries = bm.batteries().to_vec();
+                devices = bm.devices().to_vec();
+            }
+        }
+    });
+
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        // Every battery on its own, then the connected devices
+        let numbered = batteries.len() > 1;
+        for (i, battery) in batteries.iter().enumerate() {
+            let state = if battery.charging {
+                "Charging"
+            } else if battery.on_ac {
+                "Plugged in"
+            } else {
+                "On battery"
+            };
+            let label = match (numbered, battery.name.is_empty()) {
+                (true, false) => format!("Battery {} ({}): {}% · {}", i + 1, battery.name, battery.percent, state),
+                (true, true) => format!("Battery {}: {}% · {}", i + 1, battery.percent, state),
+                (false, _) => format!("Battery: {}% · {}", battery.percent, state),
+            };
+            append_disabled_item(menu, &label);
+        }
+        if config.modules.battery.show_devices && !devices.is_empty() {
+            if !batteries.is_empty() {
+                unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            }
+            for device in &devices {
+                append_disabled_item(menu, &format!("{} {}: {}%", device.kind.icon(), device.name, device.percent));
+            }
+        }
+        if !batteries.is_empty() || (config.modules.battery.show_devices && !devices.is_empty()) {
+            unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        }
         append_menu_item(menu, BAT_SHOW_PCT, "Show Percentage", config.modules.battery.show_percentage);
         append_menu_item(menu, BAT_SHOW_TIME, "Show Time Remaining", config.modules.battery.show_time_remaining);
+        append_menu_item(menu, BAT_SHOW_DEVICES, "Show Device Batteries", config.modules.battery.show_devices);
     });
 
     if cmd != 0 {
@@ -178,20 +665,36 @@ fn show_volume_menu(hwnd: HWND, x: i32, y: i32) {
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
-    // Get actual mute state from volume module
+    // Get actual volume and mute state from volume module
     let mut is_muted = false;
+    let mut level = 0;
     with_renderer(|renderer| {
         if let Some(module) = renderer.module_registry.get("volume") {
             if let Some(vm) = module.as_any().downcast_ref::<crate::modules::volume::VolumeModule>() {
                 is_muted = vm.is_muted();
+                level = vm.volume_level();
             }
         }
     });
 
-    let cmd = show_popup_menu(hwnd, x, y, |menu| {
-        append_menu_item(menu, VOL_SHOW_PCT, "Show Percentage", config.modules.volume.show_percentage);
-        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
-        append_menu_item(menu, VOL_MUTE, "Mute", is_muted);
+    let items = vec![
+        DropdownItem::slider(VOL_SLIDER, "Volume", level),
+        DropdownItem::separator(),
+        DropdownItem::new(VOL_SHOW_PCT, "Show Percentage").checked(config.modules.volume.show_percentage),
+        DropdownItem::new(VOL_MUTE, "Mute").checked(is_muted),
+    ];
+    let cmd = show_dropdown(hwnd, x, y, items, |_, value| {
+        // Follow the slider live, so the bar shows each new level
+        with_renderer(|renderer| {
+            if let Some(module) = renderer.module_registry.get_mut("volume") {
+                if let Some(vm) = module.as_any_mut().downcast_mut::<crate::modules::volume::VolumeModule>() {
+                    vm.change_volume(value as i32 - vm.volume_level() as i32);
+                }
+            }
+        });
+        unsafe {
+            let _ = InvalidateRect(hwnd, None, false);
+        }
     });
 
     if cmd != 0 {
@@ -205,20 +708,194 @@ fn show_network_menu(hwnd: HWND, x: i32, y: i32) {
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
+    // Snapshot the current top talkers from the network module
+    let mut talkers: Vec<crate::modules::network::ProcessTraffic> = Vec::new();
+    let mut stats_available = true;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("network") {
+            if let Some(nm) = module.as_any().downcast_ref::<crate::modules::network::NetworkModule>() {
+                talkers = nm.top_talkers().to_vec();
+                stats_available = nm.top_talkers_available();
+            }
+        }
+    });
+
+    // Menu bitmaps must outlive the menu, free them once it closes
+    let mut bitmaps: Vec<windows::Win32::Graphics::Gdi::HBITMAP> = Vec::new();
+
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_menu_item(menu, NET_USAGE, "Usage History...", false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
         append_menu_item(menu, NET_SHOW_NAME, "Show Network Name", config.modules.network.show_name);
         append_menu_item(menu, NET_SHOW_SPEED, "Show Speed (MB/s)", config.modules.network.show_speed);
+        append_menu_item(menu, NET_SHOW_TOP_TALKERS, "Track Top Processes", config.modules.network.show_top_talkers);
+
+        if config.modules.network.show_top_talkers {
+            unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            if talkers.is_empty() {
+                let label = if stats_available {
+                    "No network activity"
+                } else {
+                    "Per-process stats require administrator"
+                };
+                append_disabled_item(menu, label);
+            }
+            for (i, t) in talkers.iter().enumerate() {
+                let rates = if t.estimated {
+                    format!("⇅{}/s", crate::utils::format_bytes(t.total_speed()))
+                } else {
+                    format!(
+                        "↓{}/s ↑{}/s",
+                        crate::utils::format_bytes(t.download_speed),
+                        crate::utils::format_bytes(t.upload_speed)
+                    )
+                };
+                let label = format!("{}  {}", crate::utils::truncate_string(&t.name, 24), rates);
+                unsafe {
+                    let Ok(sub) = CreatePopupMenu() else { continue };
+                    let base = NET_TALKER_BASE + i as u32 * 2;
+                    append_menu_item(sub, base, "Open File Location", false);
+                    append_menu_item(sub, base + 1, "End Process", false);
+                    let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+
+                    if let Some(bmp) = process_icon_bitmap(&t.path) {
+                        let count = GetMenuItemCount(menu);
+                        let info = MENUITEMINFOW {
+                            cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+                            fMask: MIIM_BITMAP,
+                            hbmpItem: bmp,
+                            ..Default::default()
+                        };
+                        let _ = SetMenuItemInfoW(menu, (count - 1) as u32, true, &info);
+                        bitmaps.push(bmp);
+                    }
+                }
+            }
+        }
     });
 
+    for bmp in bitmaps {
+        unsafe {
+            let _ = windows::Win32::Graphics::Gdi::DeleteObject(bmp);
+        }
+    }
+
     if cmd != 0 {
         info!("Network menu returned cmd: {}", cmd);
-        super::menus::handle_menu_command(hwnd, cmd);
+        if cmd == NET_USAGE {
+            crate::render::toggle_network_usage(hwnd, x, y);
+        } else if (NET_TALKER_BASE..NET_TALKER_BASE + talkers.len() as u32 * 2).contains(&cmd) {
+            let offset = cmd - NET_TALKER_BASE;
+            let talker = &talkers[(offset / 2) as usize];
+            match offset % 2 {
+                0 => open_process_location(&talker.path),
+                _ => end_process(talker.pid, &talker.name),
+            }
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Build a 16x16 menu bitmap from an executable's small shell icon
+fn process_icon_bitmap(path: &str) -> Option<windows::Win32::Graphics::Gdi::HBITMAP> {
+    use windows::Win32::Graphics::Gdi::*;
+
+    if path.is_empty() {
+        return None;
+    }
+
+    unsafe {
+        let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut sfi = windows::Win32::UI::Shell::SHFILEINFOW::default();
+        let res = windows::Win32::UI::Shell::SHGetFileInfoW(
+            PCWSTR(wide.as_ptr()),
+            windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut sfi),
+            std::mem::size_of::<windows::Win32::UI::Shell::SHFILEINFOW>() as u32,
+            windows::Win32::UI::Shell::SHGFI_ICON | windows::Win32::UI::Shell::SHGFI_SMALLICON,
+        );
+        if res == 0 || sfi.hIcon.is_invalid() {
+            return None;
+        }
+
+        // 32bpp top-down DIB so the icon's alpha channel is preserved in the menu
+        let bmi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: 16,
+                biHeight: -16,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let screen_dc = GetDC(None);
+        let mem_dc = CreateCompatibleDC(screen_dc);
+        let mut bits: *mut std::ffi::c_void = std::ptr::null_mut();
+        let bitmap = CreateDIBSection(screen_dc, &bmi, DIB_RGB_COLORS, &mut bits, None, 0).ok();
+        if let Some(bmp) = bitmap {
+            let old = SelectObject(mem_dc, bmp);
+            let _ = DrawIconEx(mem_dc, 0, 0, sfi.hIcon, 16, 16, 0, None, DI_NORMAL);
+            SelectObject(mem_dc, old);
+        }
+        let _ = DeleteDC(mem_dc);
+        ReleaseDC(None, screen_dc);
+        let _ = DestroyIcon(sfi.hIcon);
+
+        bitmap
+    }
+}
+
+/// Reveal a process executable in Explorer
+fn open_process_location(path: &str) {
+    if path.is_empty() {
+        return;
+    }
+    let _ = std::process::Command::new("explorer.exe")
+        .arg(format!("/select,{}", path))
+        .spawn();
+}
+
+/// Terminate a process after asking the user to confirm
+fn end_process(pid: u32, name: &str) {
+    use windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    let title: Vec<u16> = "End Process".encode_utf16().chain(std::iter::once(0)).collect();
+    let msg: Vec<u16> = format!("End {} (PID {})? Unsaved data will be lost.", name, pid)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        let resp = MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), MB_YESNO | MB_ICONWARNING);
+        if resp != IDYES {
+            return;
+        }
+
+        match OpenProcess(PROCESS_TERMINATE, false, pid) {
+            Ok(handle) => {
+                if let Err(e) = TerminateProcess(handle, 1) {
+                    log::warn!("Failed to terminate {} ({}): {}", name, pid, e);
+                }
+                let _ = windows::Win32::Foundation::CloseHandle(handle);
+            }
+            Err(e) => log::warn!("Failed to open {} ({}) for termination: {}", name, pid, e),
+        }
     }
 }
 
 fn show_disk_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::i18n::tr;
+    use crate::modules::smart::{DiskHealth, Health};
+
     // Get dynamic list of disks
     let mut disks: Vec<(String, String)> = Vec::new();
+    let mut health: Vec<(DiskHealth, Health)> = Vec::new();
     with_renderer(|renderer| {
         if let Some(module) = renderer.module_registry.get("disk") {
             if let Some(dm) = module.as_any().downcast_ref::<crate::modules::disk::DiskModule>() {
@@ -226,6 +903,7 @@ fn show_disk_menu(hwnd: HWND, x: i32, y: i32) {
                     let label = if d.mount_point.is_empty() { d.name.clone() } else { d.mount_point.clone() };
                     disks.push((label, d.mount_point.clone()));
                 }
+                health = dm.health().iter().map(|disk| (disk.clone(), dm.verdict(disk))).collect();
             }
         }
     });
@@ -233,13 +911,55 @@ fn show_disk_menu(hwnd: HWND, x: i32, y: i32) {
     let config = get_window_state()
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
+    let theme = get_window_state().map(|s| s.read().theme_manager.theme().clone());
+
+    let mut items: Vec<DropdownItem> = disks
+        .iter()
+        .enumerate()
+        .map(|(i, (label, mount))| {
+            DropdownItem::new(DISK_SELECT_BASE + i as u32, label)
+                .checked(mount == &config.modules.disk.primary_disk)
+        })
+        .collect();
+
+    // One row per physical drive, its details in a submenu
+    if !health.is_empty() {
+        items.push(DropdownItem::separator());
+    }
+    for (disk, verdict) in &health {
+        let mut details = Vec::new();
+        if let Some(temperature) = disk.temperature {
+            details.push(format!("{}: {:.0}°C", tr("Temperature"), temperature));
+        }
+        if let Some(sectors) = disk.reallocated_sectors {
+            details.push(format!("{}: {}", tr("Reallocated sectors"), sectors));
+        }
+        if let Some(errors) = disk.media_errors {
+            details.push(format!("{}: {}", tr("Media errors"), errors));
+        }
+        if let Some(hours) = disk.power_on_hours {
+            details.push(format!("{}: {}", tr("Power-on hours"), hours));
+        }
+        if let Some(wear) = disk.wear {
+            details.push(format!("{}: {}%", tr("Wear"), wear));
+        }
+        if details.is_empty() {
+            details.push(tr("No SMART data"));
+        }
 
-    let cmd = show_popup_menu(hwnd, x, y, |menu| {
-        for (i, (label, mount)) in disks.iter().enumerate() {
-            let id = DISK_SELECT_BASE + i as u32;
-            append_menu_item(menu, id, label, mount == &config.modules.disk.primary_disk);
+        let mut row = DropdownItem::new(0, &format!("{}: {}", disk.model, tr(verdict.label())))
+            .with_submenu(details.iter().map(|line| DropdownItem::new(0, line).disabled()).collect());
+        if let Some(theme) = &theme {
+            match verdict {
+                Health::Good => {}
+                Health::Caution => row = row.with_color(theme.warning),
+                Health::Bad => row = row.with_color(theme.error),
+            }
         }
-    });
+        items.push(row);
+    }
+
+    let cmd = show_dropdown(hwnd, x, y, items, |_, _| {});
 
     if cmd != 0 {
         info!("Disk menu returned cmd: {}", cmd);
@@ -258,6 +978,8 @@ fn show_clipboard_menu(hwnd: HWND, x: i32, y: i32) {
         }
     });
 
+    let translations = crate::translate::recent();
+
     // Capture the currently focused window so we can restore it when pasting
     let prev_hwnd = unsafe { windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow() };
 
@@ -271,9 +993,49 @@ fn show_clipboard_menu(hwnd: HWND, x: i32, y: i32) {
                 append_menu_item(menu, CLIPBOARD_BASE + i as u32, &label, false);
             }
         }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, CLIPBOARD_TRANSLATE, "Translate Clipboard", false);
+        if !translations.is_empty() {
+            unsafe {
+                if let Ok(sub) = CreatePopupMenu() {
+                    for (i, t) in translations.iter().take(CLIPBOARD_TRANSLATIONS_LISTED).enumerate() {
+                        let label = format!(
+                            "{} → {}",
+                            crate::utils::truncate_string(&t.source_text, 24),
+                            crate::utils::truncate_string(&t.text, 24)
+                        );
+                        append_menu_item(sub, CLIPBOARD_TRANSLATION_BASE + i as u32, &label, false);
+                    }
+                    let wide: Vec<u16> = crate::i18n::tr("Recent Translations").encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+                }
+            }
+        }
     });
 
-    if cmd != 0 {
+    if cmd == CLIPBOARD_TRANSLATE {
+        match arboard::Clipboard::new().and_then(|mut cb| cb.get_text()) {
+            Ok(text) if !text.trim().is_empty() => {
+                if let Err(e) = crate::render::show_translation(text.trim().to_string(), prev_hwnd, "") {
+                    log::warn!("Failed to show translation: {}", e);
+                }
+            }
+            _ => info!("Nothing on the clipboard to translate"),
+        }
+    } else if let Some(t) = cmd
+        .checked_sub(CLIPBOARD_TRANSLATION_BASE)
+        .and_then(|i| translations.get(i as usize))
+        .filter(|_| cmd < CLIPBOARD_TRANSLATION_BASE + CLIPBOARD_TRANSLATIONS_LISTED as u32)
+    {
+        // Copy a past translation, through the module so its history sees it
+        with_renderer(|renderer| {
+            if let Some(module) = renderer.module_registry.get_mut("clipboard") {
+                if let Some(cm) = module.as_any_mut().downcast_mut::<crate::modules::clipboard::ClipboardModule>() {
+                    cm.set_clipboard_text(&t.text);
+                }
+            }
+        });
+    } else if cmd != 0 {
         let cmd_id = cmd as u32;
         // If a clipboard entry was selected, set clipboard & try to paste into the previous window
         if (CLIPBOARD_BASE..CLIPBOARD_BASE + 100).contains(&cmd_id) {
@@ -366,14 +1128,93 @@ fn show_sysinfo_menu(hwnd: HWND, x: i32, y: i32) {
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
+    use crate::utils::format_bytes;
+
+    let details = crate::modules::system_info::memory_details();
+    let top = crate::modules::metrics::top_memory_processes(SYSINFO_TOP_PROCESSES);
+
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
         // CPU and Memory are always shown; do not expose toggles to the user.
         append_menu_item(menu, SYSINFO_SHOW_GRAPH, "Show Graph", config.modules.system_info.show_graph);
+
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        match &details {
+            Some(d) => {
+                append_disabled_item(
+                    menu,
+                    &format!("Committed: {} / {}", format_bytes(d.committed), format_bytes(d.commit_limit)),
+                );
+                append_disabled_item(menu, &format!("Cached: {}", format_bytes(d.cached)));
+                append_disabled_item(menu, &format!("Paged pool: {}", format_bytes(d.paged_pool)));
+                append_disabled_item(menu, &format!("Non-paged pool: {}", format_bytes(d.nonpaged_pool)));
+            }
+            None => append_disabled_item(menu, "Memory details unavailable"),
+        }
+
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        for (i, p) in top.iter().enumerate() {
+            let label = format!(
+                "{}  {}",
+                crate::utils::truncate_string(&p.name, 24),
+                format_bytes(p.memory)
+            );
+            unsafe {
+                let Ok(sub) = CreatePopupMenu() else { continue };
+                let base = SYSINFO_PROC_BASE + i as u32 * 2;
+                append_menu_item(sub, base, "Open File Location", false);
+                append_menu_item(sub, base + 1, "End Process", false);
+                let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
+
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, SYSINFO_FREE_STANDBY, "Free Standby Memory", false);
     });
 
     if cmd != 0 {
         info!("Sysinfo menu returned cmd: {}", cmd);
-        super::menus::handle_menu_command(hwnd, cmd);
+        if cmd == SYSINFO_FREE_STANDBY {
+            free_standby_memory();
+        } else if (SYSINFO_PROC_BASE..SYSINFO_PROC_BASE + top.len() as u32 * 2).contains(&cmd) {
+            let offset = cmd - SYSINFO_PROC_BASE;
+            let process = &top[(offset / 2) as usize];
+            match offset % 2 {
+                0 => {
+                    if let Some(exe) = &process.exe {
+                        open_process_location(&exe.to_string_lossy());
+                    }
+                }
+                _ => end_process(process.pid, &process.name),
+            }
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Purge the standby list and report how much memory became free
+fn free_standby_memory() {
+    let available_before = crate::utils::available_memory_bytes();
+    let (msg, flags) = match crate::modules::system_info::purge_standby_memory() {
+        Ok(()) => {
+            let freed = crate::utils::available_memory_bytes().saturating_sub(available_before);
+            info!("Purged standby list, {} freed", crate::utils::format_bytes(freed));
+            (
+                format!("Standby memory freed ({}).", crate::utils::format_bytes(freed)),
+                MB_OK | MB_ICONINFORMATION,
+            )
+        }
+        Err(e) => {
+            log::warn!("Failed to purge standby list: {}", e);
+            (e, MB_OK | MB_ICONWARNING)
+        }
+    };
+
+    let title: Vec<u16> = "Free Standby Memory".encode_utf16().chain(std::iter::once(0)).collect();
+    let msg: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), flags);
     }
 }
 
@@ -385,14 +1226,18 @@ fn show_weather_menu(hwnd: HWND, x: i32, y: i32) {
             return;
         }
 
-        // Gather forecast from module
+        // Gather forecast (and local station readings) from module
         let mut lines: Vec<String> = Vec::new();
+        let mut station_lines: Vec<String> = Vec::new();
+        let mut alerts: Vec<crate::modules::weather::WeatherAlert> = Vec::new();
         with_renderer(|renderer| {
             if let Some(module) = renderer.module_registry.get("weather") {
                 if let Some(wm) = module
                     .as_any()
                     .downcast_ref::<crate::modules::weather::WeatherModule>()
                 {
+                    station_lines = wm.station_lines();
+                    alerts = wm.alerts();
                     if let Some(data) = wm.weather_data() {
                         if data.forecast.is_empty() {
                             lines.push("No forecast available".to_string());
@@ -414,6 +1259,21 @@ fn show_weather_menu(hwnd: HWND, x: i32, y: i32) {
             }
         });
 
+        // Alerts first; clicking one shows its full text
+        if !alerts.is_empty() {
+            for (i, alert) in alerts.iter().take(WEATHER_ALERTS_LISTED).enumerate() {
+                append_menu_item(menu, WEATHER_ALERT_BASE + i as u32, &alert.summary(), false);
+            }
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+        }
+
+        if !station_lines.is_empty() {
+            for line in &station_lines {
+                append_disabled_item(menu, line);
+            }
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+        }
+
         if lines.is_empty() {
             append_menu_item(menu, WEATHER_REFRESH, "Fetching weather...", false);
         } else {
@@ -427,6 +1287,7 @@ fn show_weather_menu(hwnd: HWND, x: i32, y: i32) {
         }
 
         let _ = SetForegroundWindow(hwnd);
+        crate::sounds::play_event(SoundEvent::PopupOpen);
         let cmd = TrackPopupMenu(
             menu,
             TPM_RIGHTBUTTON | TPM_LEFTALIGN | TPM_TOPALIGN | TPM_RETURNCMD,
@@ -436,6 +1297,7 @@ fn show_weather_menu(hwnd: HWND, x: i32, y: i32) {
             hwnd,
             None,
         );
+        crate::sounds::play_event(SoundEvent::PopupClose);
         DestroyMenu(menu).ok();
 
         info!("Weather menu returned cmd: {}", cmd.0);
@@ -446,17 +1308,110 @@ fn show_weather_menu(hwnd: HWND, x: i32, y: i32) {
                     // Clicking a forecast day - open forecast in browser
                     open_url("https://wttr.in/");
                 }
+                id if (WEATHER_ALERT_BASE..WEATHER_ALERT_BASE + WEATHER_ALERTS_LISTED as u32).contains(&id) => {
+                    if let Some(alert) = alerts.get((id - WEATHER_ALERT_BASE) as usize) {
+                        show_weather_alert(alert);
+                    }
+                }
                 _ => {}
             }
         }
     }
 }
 
-fn show_app_menu(hwnd: HWND, x: i32, y: i32) {
+/// The classic app menu, also reached from the quick toggles panel
+pub fn show_app_menu(hwnd: HWND, x: i32, y: i32) {
+    use super::snap::SnapLayout;
+    use super::tile::TileLayout;
+
+    // The bar doesn't take focus, so this is the window the user was working in
+    let target = unsafe { GetForegroundWindow() };
+
+    // Shown as last read; the fresh read is ready for the next time the menu opens
+    let login_items = crate::login_items::cached();
+    crate::login_items::refresh();
+    let login_items_loaded = login_items.is_some();
+    let login_items: Vec<_> = login_items.unwrap_or_default().into_iter().take(APP_LOGIN_MAX).collect();
+    let recent = crate::recent_items::list(RECENT_LIMIT);
+
+    // Menu bitmaps must outlive the menu, free them once it closes
+    let mut bitmaps: Vec<windows::Win32::Graphics::Gdi::HBITMAP> = Vec::new();
+
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
         append_menu_item(menu, APP_ABOUT, "Quickstart / Intro Guide", false);
+        append_menu_item(menu, APP_COMMAND_PALETTE, "Command Palette...", false);
         append_menu_item(menu, APP_INSTALL_CURSORS, "Install macOS Cursors", false);
-        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, APP_SHELF, "Show Shelf", false);
+        unsafe {
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+            if let Ok(sub) = CreatePopupMenu() {
+                if recent.is_empty() {
+                    append_disabled_item(sub, "No recent items");
+                }
+                for (i, item) in recent.iter().enumerate() {
+                    let label = crate::utils::truncate_string(&item.name, 48);
+                    let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(sub, MF_STRING, (APP_RECENT_BASE + i as u32) as usize, PCWSTR(wide.as_ptr())).ok();
+
+                    if let Some(bmp) = process_icon_bitmap(&item.path().to_string_lossy()) {
+                        let info = MENUITEMINFOW {
+                            cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+                            fMask: MIIM_BITMAP,
+                            hbmpItem: bmp,
+                            ..Default::default()
+                        };
+                        let _ = SetMenuItemInfoW(sub, i as u32, true, &info);
+                        bitmaps.push(bmp);
+                    }
+                }
+                if !recent.is_empty() {
+                    AppendMenuW(sub, MF_SEPARATOR, 0, None).ok();
+                    append_menu_item(sub, APP_RECENT_CLEAR, "Clear Recent", false);
+                }
+                let wide: Vec<u16> = crate::i18n::tr("Recent").encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+            if let Ok(sub) = CreatePopupMenu() {
+                for (i, layout) in SnapLayout::ALL.iter().enumerate() {
+                    append_menu_item(sub, APP_SNAP_BASE + i as u32, layout.label(), false);
+                }
+                AppendMenuW(sub, MF_SEPARATOR, 0, None).ok();
+                for (i, layout) in TileLayout::ALL.iter().enumerate() {
+                    append_menu_item(sub, APP_TILE_BASE + i as u32, layout.label(), false);
+                }
+                let wide: Vec<u16> = crate::i18n::tr("Window").encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+            if let Ok(sub) = CreatePopupMenu() {
+                if !login_items_loaded {
+                    append_disabled_item(sub, "Loading...");
+                } else if login_items.is_empty() {
+                    append_disabled_item(sub, "No login items");
+                }
+                for (i, item) in login_items.iter().enumerate() {
+                    let id = APP_LOGIN_BASE + i as u32;
+                    let label = if item.needs_admin() {
+                        format!("{} (all users)", item.name)
+                    } else {
+                        item.name.clone()
+                    };
+                    if item.locked {
+                        // Set by group policy
+                        let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                        let check = if item.enabled { MF_CHECKED } else { MF_UNCHECKED };
+                        AppendMenuW(sub, MF_STRING | MF_GRAYED | check, id as usize, PCWSTR(wide.as_ptr())).ok();
+                    } else {
+                        append_menu_item(sub, id, &label, item.enabled);
+                    }
+                }
+                AppendMenuW(sub, MF_SEPARATOR, 0, None).ok();
+                append_menu_item(sub, APP_LOGIN_SETTINGS, "Startup Apps Settings...", false);
+                let wide: Vec<u16> = crate::i18n::tr("Login Items").encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+            super::menus::append_startup_menu(menu);
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+        }
         append_menu_item(menu, APP_SETTINGS, "Open Config File", false);
         append_menu_item(menu, APP_RELOAD, "Reload Config", false);
         append_menu_item(menu, APP_RESET, "Reset to Defaults", false);
@@ -464,39 +1419,1349 @@ fn show_app_menu(hwnd: HWND, x: i32, y: i32) {
         append_menu_item(menu, APP_EXIT, "Exit TopBar", false);
     });
 
-    if cmd != 0 {
-        info!("App menu returned cmd: {}", cmd);
-        super::menus::handle_menu_command(hwnd, cmd);
+    for bmp in bitmaps {
+        unsafe {
+            let _ = windows::Win32::Graphics::Gdi::DeleteObject(bmp);
+        }
     }
-}
 
-fn show_gpu_menu(hwnd: HWND, x: i32, y: i32) {
+    if let Some(item) = cmd
+        .checked_sub(APP_RECENT_BASE)
+        .and_then(|i| recent.get(i as usize))
+    {
+        crate::recent_items::open(item);
+    } else if cmd == APP_RECENT_CLEAR {
+        crate::recent_items::clear();
+    } else if let Some(layout) = cmd
+        .checked_sub(APP_SNAP_BASE)
+        .and_then(|i| SnapLayout::ALL.get(i as usize))
+    {
+        super::snap::snap_window(target, *layout);
+    } else if let Some(layout) = cmd
+        .checked_sub(APP_TILE_BASE)
+        .and_then(|i| TileLayout::ALL.get(i as usize))
+    {
+        // Put the user's window back in front so it gets the main cell
+        unsafe {
+            let _ = SetForegroundWindow(target);
+        }
+        super::tile::tile_windows(*layout);
+    } else if let Some(item) = cmd
+        .checked_sub(APP_LOGIN_BASE)
+        .and_then(|i| login_items.get(i as usize))
+    {
+        if let Err(e) = crate::login_items::set_enabled(item, !item.enabled) {
+            log::warn!("Failed to change login item {}: {}", item.name, e);
+            let msg = if item.needs_admin() {
+                format!("Changing \"{}\" needs administrator rights.", item.name)
+            } else {
+                format!("Couldn't change \"{}\": {}", item.name, e)
+            };
+            let title: Vec<u16> = "Login Items".encode_utf16().chain(std::iter::once(0)).collect();
+            let msg: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
+            unsafe {
+                MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), MB_OK | MB_ICONWARNING);
+            }
+        }
+        crate::login_items::refresh();
+    } else if cmd == APP_LOGIN_SETTINGS {
+        open_url("ms-settings:startupapps");
+    } else if cmd == APP_SHELF {
+        crate::render::show_shelf(hwnd, x);
+    } else if cmd == APP_COMMAND_PALETTE {
+        let _ = crate::render::show_command_palette(hwnd);
+    } else if cmd != 0 {
+        info!("App menu returned cmd: {}", cmd);
+        super::menus::handle_menu_command(hwnd, cmd);
+    }
+}
+
+fn show_gpu_menu(hwnd: HWND, x: i32, y: i32) {
     let config = get_window_state()
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_menu_item(menu, GPU_USAGE, "Usage by Process...", false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
         // GPU usage is always shown; do not expose a toggle in the menu.
         append_menu_item(menu, 2604, "Show Graph", config.modules.gpu.show_graph);
+        append_menu_item(menu, 2602, "Show Temperature", config.modules.gpu.show_temperature);
     });
 
     if cmd != 0 {
         info!("GPU menu returned cmd: {}", cmd);
+        if cmd == GPU_USAGE {
+            crate::render::toggle_gpu_usage(hwnd, x, y);
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show current sensor readings and thermal settings
+fn show_thermal_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+
+    let mut readings: Vec<String> = Vec::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("thermal") {
+            if let Some(tm) = module
+                .as_any()
+                .downcast_ref::<crate::modules::thermal::ThermalModule>()
+            {
+                let snapshot = tm.snapshot();
+                if let Some(t) = snapshot.cpu_temp {
+                    readings.push(format!("CPU: {:.0}°C", t));
+                }
+                if let Some(t) = snapshot.gpu_temp {
+                    readings.push(format!("GPU: {:.0}°C", t));
+                }
+                for fan in &snapshot.fans {
+                    readings.push(format!("{}: {:.0} RPM", fan.name, fan.rpm));
+                }
+            }
+        }
+    });
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if readings.is_empty() {
+            append_disabled_item(menu, "No sensors available");
+        }
+        for line in &readings {
+            append_disabled_item(menu, line);
+        }
+        append_disabled_item(
+            menu,
+            &format!("Warning at {:.0}°C", config.modules.thermal.warning_threshold),
+        );
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, THERMAL_SHOW_FANS, "Show Fan Speed", config.modules.thermal.show_fans);
+        append_menu_item(menu, THERMAL_NOTIFY, "Warn When Hot", config.modules.thermal.notify);
+    });
+
+    if cmd != 0 {
+        info!("Thermal menu returned cmd: {}", cmd);
+        super::menus::handle_menu_command(hwnd, cmd);
+    }
+}
+
+/// Show watched processes with start / view log actions
+fn show_watchdog_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let watched = &config.modules.watchdog.processes;
+
+    let mut running: Vec<bool> = vec![false; watched.len()];
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("watchdog") {
+            if let Some(wm) = module
+                .as_any()
+                .downcast_ref::<crate::modules::watchdog::WatchdogModule>()
+            {
+                for (slot, status) in running.iter_mut().zip(wm.statuses()) {
+                    *slot = status.running;
+                }
+            }
+        }
+    });
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if watched.is_empty() {
+            append_disabled_item(menu, "No processes configured");
+        }
+        for (i, w) in watched.iter().enumerate() {
+            let label = format!(
+                "{} {} — {}",
+                if running[i] { "●" } else { "○" },
+                w.name,
+                if running[i] { "running" } else { "not running" }
+            );
+            unsafe {
+                let Ok(sub) = CreatePopupMenu() else { continue };
+                let base = WATCHDOG_BASE + i as u32 * 2;
+                if running[i] || w.start_command.is_none() {
+                    let wide: Vec<u16> = crate::i18n::tr("Start").encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(sub, MF_STRING | MF_GRAYED, base as usize, PCWSTR(wide.as_ptr())).ok();
+                } else {
+                    append_menu_item(sub, base, "Start", false);
+                }
+                if w.log_path.is_some() {
+                    append_menu_item(sub, base + 1, "View Log", false);
+                }
+                let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, APP_SETTINGS, "Edit Watched Processes...", false);
+    });
+
+    if cmd != 0 {
+        info!("Watchdog menu returned cmd: {}", cmd);
+        if (WATCHDOG_BASE..WATCHDOG_BASE + watched.len() as u32 * 2).contains(&cmd) {
+            let offset = cmd - WATCHDOG_BASE;
+            let w = &watched[(offset / 2) as usize];
+            match offset % 2 {
+                0 => {
+                    crate::modules::watchdog::WatchdogModule::start_process(w);
+                }
+                _ => crate::modules::watchdog::WatchdogModule::open_log(w),
+            }
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show queued print jobs by printer, with cancel and open-queue actions
+fn show_printing_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::i18n::tr;
+
+    let mut jobs: Vec<crate::modules::printing::PrintJob> = Vec::new();
+    let mut printers: Vec<String> = Vec::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("printing") {
+            if let Some(pm) = module.as_any().downcast_ref::<crate::modules::printing::PrintingModule>() {
+                jobs = pm.jobs().iter().take(PRINTING_MAX_JOBS).cloned().collect();
+                printers = pm.printers();
+            }
+        }
+    });
+    let error = get_window_state().map(|s| s.read().theme_manager.theme().error);
+
+    let mut items = Vec::new();
+    if jobs.is_empty() {
+        items.push(DropdownItem::new(0, &tr("No print jobs")).disabled());
+    }
+    for (p, printer) in printers.iter().enumerate() {
+        if p > 0 {
+            items.push(DropdownItem::separator());
+        }
+        items.push(DropdownItem::new(0, printer).disabled());
+        for (i, job) in jobs.iter().enumerate().filter(|(_, j)| &j.printer == printer) {
+            let mut row = DropdownItem::new(0, &format!("{} — {}", job.document, job.progress()))
+                .with_submenu(vec![DropdownItem::new(PRINTING_CANCEL_BASE + i as u32, &tr("Cancel Document"))]);
+            if let (true, Some(error)) = (job.problem, error) {
+                row = row.with_color(error);
+            }
+            items.push(row);
+        }
+        items.push(DropdownItem::new(PRINTING_QUEUE_BASE + p as u32, &tr("Open Print Queue...")));
+    }
+    items.push(DropdownItem::separator());
+    items.push(DropdownItem::new(PRINTING_SETTINGS, &tr("Printers & Scanners...")));
+
+    let cmd = show_dropdown(hwnd, x, y, items, |_, _| {});
+    if cmd != 0 {
+        info!("Printing menu returned cmd: {}", cmd);
+        let queue_end = PRINTING_QUEUE_BASE + printers.len() as u32;
+        let cancel_end = PRINTING_CANCEL_BASE + jobs.len() as u32;
+        match cmd {
+            PRINTING_SETTINGS => open_url("ms-settings:printers"),
+            _ if (PRINTING_QUEUE_BASE..queue_end).contains(&cmd) => {
+                crate::modules::printing::open_queue(&printers[(cmd - PRINTING_QUEUE_BASE) as usize]);
+            }
+            _ if (PRINTING_CANCEL_BASE..cancel_end).contains(&cmd) => {
+                let job = &jobs[(cmd - PRINTING_CANCEL_BASE) as usize];
+                crate::modules::printing::cancel(&job.printer, job.id);
+                with_renderer(|renderer| {
+                    if let Some(module) = renderer.module_registry.get_mut("printing") {
+                        let config = get_window_state()
+                            .map(|s| s.read().config.clone())
+                            .unwrap_or_default();
+                        module.force_refresh(&config);
+                    }
+                });
+            }
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show attached USB drives and memory cards with open and eject actions
+fn show_removable_media_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::i18n::tr;
+    use crate::utils::format_bytes;
+
+    let mut volumes: Vec<crate::modules::removable_media::RemovableVolume> = Vec::new();
+    let mut ejecting = None;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("removable_media") {
+            if let Some(rm) = module.as_any().downcast_ref::<crate::modules::removable_media::RemovableMediaModule>() {
+                volumes = rm.volumes().to_vec();
+                ejecting = rm.ejecting().map(str::to_string);
+            }
+        }
+    });
+
+    let mut items = Vec::new();
+    if volumes.is_empty() {
+        items.push(DropdownItem::new(0, &tr("No removable drives")).disabled());
+    }
+    for (i, volume) in volumes.iter().enumerate() {
+        if i > 0 {
+            items.push(DropdownItem::separator());
+        }
+        items.push(DropdownItem::new(0, &volume.name()).disabled());
+        items.push(
+            DropdownItem::new(
+                0,
+                &format!(
+                    "{} {} {}",
+                    format_bytes(volume.free_bytes),
+                    tr("free of"),
+                    format_bytes(volume.total_bytes)
+                ),
+            )
+            .disabled(),
+        );
+        if volume.is_busy() {
+            let rate = format_bytes(volume.read_rate + volume.write_rate);
+            items.push(DropdownItem::new(0, &tr("Copying... {}/s").replacen("{}", &rate, 1)).disabled());
+        }
+        items.push(DropdownItem::new(REMOVABLE_OPEN_BASE + i as u32, &tr("Open")));
+        let eject = DropdownItem::new(REMOVABLE_EJECT_BASE + i as u32, &tr("Eject"));
+        // One eject at a time; Plug and Play handles them in turn anyway
+        items.push(if ejecting.is_some() { eject.disabled() } else { eject });
+    }
+    items.push(DropdownItem::separator());
+    items.push(DropdownItem::new(REMOVABLE_SETTINGS, &tr("USB Settings...")));
+
+    let cmd = show_dropdown(hwnd, x, y, items, |_, _| {});
+    if cmd != 0 {
+        info!("Removable media menu returned cmd: {}", cmd);
+        let open_end = REMOVABLE_OPEN_BASE + volumes.len() as u32;
+        let eject_end = REMOVABLE_EJECT_BASE + volumes.len() as u32;
+        match cmd {
+            REMOVABLE_SETTINGS => open_url("ms-settings:usb"),
+            _ if (REMOVABLE_OPEN_BASE..open_end).contains(&cmd) => {
+                let root = &volumes[(cmd - REMOVABLE_OPEN_BASE) as usize].root;
+                let _ = std::process::Command::new("explorer.exe").arg(format!("{}\\", root)).spawn();
+            }
+            _ if (REMOVABLE_EJECT_BASE..eject_end).contains(&cmd) => {
+                let root = volumes[(cmd - REMOVABLE_EJECT_BASE) as usize].root.clone();
+                with_renderer(|renderer| {
+                    if let Some(module) = renderer.module_registry.get_mut("removable_media") {
+                        if let Some(rm) = module
+                            .as_any_mut()
+                            .downcast_mut::<crate::modules::removable_media::RemovableMediaModule>()
+                        {
+                            rm.eject(&root);
+                        }
+                    }
+                });
+                unsafe {
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+            }
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show monitored services with start / stop / restart actions
+fn show_services_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::modules::services::{ServiceAction, ServiceState, ServiceStatus, ServicesModule};
+
+    let mut statuses: Vec<ServiceStatus> = Vec::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("services") {
+            if let Some(sm) = module.as_any().downcast_ref::<ServicesModule>() {
+                statuses = sm.statuses().iter().take(SERVICES_MAX).cloned().collect();
+            }
+        }
+    });
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if statuses.is_empty() {
+            append_disabled_item(menu, "No services configured");
+        }
+        for (i, s) in statuses.iter().enumerate() {
+            let running = s.state == ServiceState::Running;
+            let label = format!(
+                "{} {} — {}",
+                if running { "●" } else { "○" },
+                s.display_name,
+                s.state.label()
+            );
+            unsafe {
+                let Ok(sub) = CreatePopupMenu() else { continue };
+                let base = SERVICES_BASE + i as u32 * 3;
+                let actions = [
+                    (base, "Start", s.state == ServiceState::Stopped),
+                    (base + 1, "Stop", running || s.state == ServiceState::Paused),
+                    (base + 2, "Restart", running),
+                ];
+                for (id, text, enabled) in actions {
+                    if enabled {
+                        append_menu_item(sub, id, text, false);
+                    } else {
+                        let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+                        AppendMenuW(sub, MF_STRING | MF_GRAYED, id as usize, PCWSTR(wide.as_ptr())).ok();
+                    }
+                }
+                let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, SERVICES_OPEN_MMC, "Open Services...", false);
+        append_menu_item(menu, APP_SETTINGS, "Edit Monitored Services...", false);
+    });
+
+    if cmd != 0 {
+        info!("Services menu returned cmd: {}", cmd);
+        if (SERVICES_BASE..SERVICES_BASE + statuses.len() as u32 * 3).contains(&cmd) {
+            let offset = cmd - SERVICES_BASE;
+            let action = match offset % 3 {
+                0 => ServiceAction::Start,
+                1 => ServiceAction::Stop,
+                _ => ServiceAction::Restart,
+            };
+            ServicesModule::run_action(&statuses[(offset / 3) as usize].name, action);
+        } else if cmd == SERVICES_OPEN_MMC {
+            open_url("services.msc");
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show the portfolio broken down per position, or the watchlist quotes
+fn show_stocks_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::modules::stocks::{format_amount, format_signed_money, format_signed_pct, StocksModule};
+
+    let mut data = None;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("stocks") {
+            if let Some(sm) = module.as_any().downcast_ref::<StocksModule>() {
+                data = Some((sm.portfolio(), sm.quotes(), sm.currency().to_string(), sm.is_fetching()));
+            }
+        }
+    });
+    let Some((portfolio, quotes, currency, fetching)) = data else {
+        return;
+    };
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let stocks = &config.modules.stocks;
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if stocks.holdings.is_empty() && stocks.symbols.is_empty() {
+            append_disabled_item(menu, "No symbols or holdings configured");
+        }
+        if !stocks.holdings.is_empty() {
+            append_disabled_item(menu, &format!("Portfolio: {}{}", currency, format_amount(portfolio.value)));
+            append_disabled_item(
+                menu,
+                &format!(
+                    "Today: {} ({})",
+                    format_signed_money(portfolio.day_change, &currency),
+                    format_signed_pct(portfolio.day_change_pct)
+                ),
+            );
+            if let (Some(total), Some(pct)) = (portfolio.total_change, portfolio.total_change_pct) {
+                append_disabled_item(
+                    menu,
+                    &format!("Total: {} ({})", format_signed_money(total, &currency), format_signed_pct(pct)),
+                );
+            }
+            unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+
+            for p in &portfolio.positions {
+                let label = format!(
+                    "{} — {} × {:.2} = {}{}",
+                    p.symbol,
+                    p.quantity,
+                    p.price,
+                    currency,
+                    format_amount(p.value)
+                );
+                unsafe {
+                    let Ok(sub) = CreatePopupMenu() else { continue };
+                    append_disabled_item(
+                        sub,
+                        &format!(
+                            "Today: {} ({})",
+                            format_signed_money(p.day_change, &currency),
+                            format_signed_pct(p.day_change_pct)
+                        ),
+                    );
+                    match (p.total_change, p.total_change_pct) {
+                        (Some(total), Some(pct)) => append_disabled_item(
+                            sub,
+                            &format!("Total: {} ({})", format_signed_money(total, &currency), format_signed_pct(pct)),
+                        ),
+                        _ => append_disabled_item(sub, "Total: no cost basis"),
+                    }
+                    if portfolio.value > 0.0 {
+                        append_disabled_item(sub, &format!("Weight: {:.1}%", p.value / portfolio.value * 100.0));
+                    }
+                    let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                    AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+                }
+            }
+            for symbol in &portfolio.missing {
+                append_disabled_item(menu, &format!("{} — waiting for quote", symbol));
+            }
+        }
+
+        if !stocks.symbols.is_empty() {
+            if !stocks.holdings.is_empty() {
+                unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            }
+            for symbol in &stocks.symbols {
+                let symbol = symbol.trim().to_uppercase();
+                let label = match quotes.get(&symbol) {
+                    Some(q) => format!(
+                        "{} — {:.2} ({}, {})",
+                        symbol,
+                        q.price,
+                        format_signed_money(q.change(), ""),
+                        format_signed_pct(q.change_pct())
+                    ),
+                    None => format!("{} — waiting for quote", symbol),
+                };
+                append_disabled_item(menu, &label);
+            }
+        }
+
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        if fetching {
+            append_disabled_item(menu, "Refreshing...");
+        } else {
+            append_menu_item(menu, STOCKS_REFRESH, "Refresh Now", false);
+        }
+        append_menu_item(menu, APP_SETTINGS, "Edit Holdings...", false);
+    });
+
+    if cmd != 0 {
+        info!("Stocks menu returned cmd: {}", cmd);
+        if cmd == STOCKS_REFRESH {
+            with_renderer(|renderer| {
+                if let Some(module) = renderer.module_registry.get_mut("stocks") {
+                    module.force_refresh(&config);
+                }
+            });
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show this week's fixtures and results for followed teams, by day
+fn show_sports_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::modules::sports::SportsModule;
+
+    let mut data = None;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("sports") {
+            if let Some(sm) = module.as_any().downcast_ref::<SportsModule>() {
+                data = Some((sm.matches(), sm.error()));
+            }
+        }
+    });
+    let Some((matches, error)) = data else {
+        return;
+    };
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if config.modules.sports.teams.is_empty() {
+            append_disabled_item(menu, "No teams followed");
+        } else if matches.is_empty() {
+            append_disabled_item(menu, "No matches this week");
+        }
+        let mut day = None;
+        for m in &matches {
+            let date = m.start.date_naive();
+            if day != Some(date) {
+                if day.is_some() {
+                    unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+                }
+                append_disabled_item(menu, &m.start.format("%A %e %B").to_string());
+                day = Some(date);
+            }
+            let live = matches!(m.state, crate::modules::sports::MatchState::Live(_));
+            append_disabled_item(
+                menu,
+                &format!("{}{}   {}   ({})", if live { "● " } else { "" }, m.summary(), m.status(), m.league),
+            );
+        }
+        if let Some(e) = &error {
+            append_disabled_item(menu, e);
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, SPORTS_REFRESH, "Refresh Now", false);
+        append_menu_item(menu, APP_SETTINGS, "Edit Followed Teams...", false);
+    });
+
+    if cmd != 0 {
+        info!("Sports menu returned cmd: {}", cmd);
+        if cmd == SPORTS_REFRESH {
+            with_renderer(|renderer| {
+                if let Some(module) = renderer.module_registry.get_mut("sports") {
+                    module.force_refresh(&config);
+                }
+            });
+        } else {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show protection, scan and firewall state with links into Windows Security
+fn show_security_menu(hwnd: HWND, x: i32, y: i32) {
+    let mut status = None;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("security") {
+            if let Some(sm) = module.as_any().downcast_ref::<crate::modules::security::SecurityModule>() {
+                status = sm.status();
+            }
+        }
+    });
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+
+    let on_off = |on: Option<bool>| match on {
+        Some(true) => "On",
+        Some(false) => "Off",
+        None => "Unknown",
+    };
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        match &status {
+            None => append_disabled_item(menu, "Reading security status..."),
+            Some(s) => {
+                let problems = s.problems(config.modules.security.scan_warning_days);
+                for problem in &problems {
+                    append_disabled_item(menu, &format!("⚠ {}", problem));
+                }
+                if !problems.is_empty() {
+                    unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+                }
+                for av in &s.antivirus {
+                    append_disabled_item(menu, &format!("{}: {}", av.name, on_off(Some(av.enabled))));
+                }
+                if s.antivirus.is_empty() {
+                    append_disabled_item(menu, &format!("Real-time protection: {}", on_off(s.real_time)));
+                }
+                let scan = match s.last_scan_days {
+                    Some(0) => "today".to_string(),
+                    Some(1) => "yesterday".to_string(),
+                    Some(days) => format!("{} days ago", days),
+                    None => "never".to_string(),
+                };
+                append_disabled_item(menu, &format!("Last scan: {}", scan));
+                for fw in &s.firewalls {
+                    append_disabled_item(menu, &format!("{}: {}", fw.name, on_off(Some(fw.enabled))));
+                }
+                for (profile, on) in &s.firewall_profiles {
+                    append_disabled_item(menu, &format!("Firewall ({}): {}", profile, on_off(Some(*on))));
+                }
+            }
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, SECURITY_OPEN, "Open Windows Security...", false);
+        append_menu_item(menu, SECURITY_FIREWALL, "Firewall && Network Protection...", false);
+        append_menu_item(menu, SECURITY_REFRESH, "Refresh Now", false);
+    });
+
+    if cmd != 0 {
+        info!("Security menu returned cmd: {}", cmd);
+        match cmd {
+            SECURITY_OPEN => open_url("windowsdefender:"),
+            SECURITY_FIREWALL => open_url("windowsdefender://network"),
+            SECURITY_REFRESH => {
+                with_renderer(|renderer| {
+                    if let Some(module) = renderer.module_registry.get_mut("security") {
+                        module.force_refresh(&config);
+                    }
+                });
+            }
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show each DDC/CI monitor's inputs with the current one checked
+fn show_monitor_input_menu(hwnd: HWND, x: i32, y: i32) {
+    let mut displays = Vec::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("monitor_input") {
+            if let Some(mm) = module.as_any().downcast_ref::<crate::modules::monitor_input::MonitorInputModule>() {
+                displays = mm.displays();
+            }
+        }
+    });
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let cfg = &config.modules.monitor_input;
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if displays.is_empty() {
+            append_disabled_item(menu, "No DDC/CI monitors found");
+        }
+        for (m, display) in displays.iter().enumerate() {
+            if m > 0 {
+                unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            }
+            append_disabled_item(menu, &format!("{}. {}", display.number, display.name));
+            // Some monitors switch inputs but won't say which one is current
+            if display.current.is_none() {
+                append_disabled_item(menu, "Current input unknown");
+            }
+            for (i, input) in display.inputs(cfg).iter().enumerate().take(MONITOR_INPUT_STRIDE as usize) {
+                let id = MONITOR_INPUT_BASE + m as u32 * MONITOR_INPUT_STRIDE + i as u32;
+                append_menu_item(menu, id, &input.name, display.current == Some(input.value));
+            }
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, MONITOR_INPUT_REFRESH, "Refresh Now", false);
+    });
+
+    if cmd == MONITOR_INPUT_REFRESH {
+        with_renderer(|renderer| {
+            if let Some(module) = renderer.module_registry.get_mut("monitor_input") {
+                module.force_refresh(&config);
+            }
+        });
+    } else if let Some((display, input)) = cmd.checked_sub(MONITOR_INPUT_BASE).and_then(|offset| {
+        let display = displays.get((offset / MONITOR_INPUT_STRIDE) as usize)?;
+        let input = display.inputs(cfg).into_iter().nth((offset % MONITOR_INPUT_STRIDE) as usize)?;
+        Some((display, input))
+    }) {
+        info!("Switching monitor {} to {}", display.number, input.name);
+        with_renderer(|renderer| {
+            if let Some(module) = renderer.module_registry.get_mut("monitor_input") {
+                if let Some(mm) = module.as_any_mut().downcast_mut::<crate::modules::monitor_input::MonitorInputModule>() {
+                    mm.switch_input(display.number, input.value);
+                }
+            }
+        });
+    } else if cmd != 0 {
         super::menus::handle_menu_command(hwnd, cmd);
     }
 }
 
+/// Show the power actions; ones that ask for confirmation end in "..."
+fn show_power_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::config::PowerAction;
+
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let mut can_hibernate = false;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("power") {
+            if let Some(pm) = module.as_any().downcast_ref::<crate::modules::power::PowerModule>() {
+                can_hibernate = pm.can_hibernate();
+            }
+        }
+    });
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        for (i, action) in PowerAction::ALL.iter().enumerate() {
+            // Restart, shut down and sign out are set apart from the rest
+            if *action == PowerAction::Restart {
+                unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            }
+            let mut label = action.label().to_string();
+            if config.modules.power.confirm.contains(action) {
+                label.push_str("...");
+            }
+            if *action == PowerAction::Hibernate && !can_hibernate {
+                append_disabled_item(menu, &label);
+            } else {
+                append_menu_item(menu, POWER_BASE + i as u32, &label, false);
+            }
+        }
+    });
+
+    if cmd != 0 {
+        info!("Power menu returned cmd: {}", cmd);
+        match PowerAction::ALL.get(cmd.wrapping_sub(POWER_BASE) as usize) {
+            Some(action) => {
+                crate::modules::power::request(*action, &config.modules.power);
+            }
+            None => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Run something against the active window module
+fn with_active_window<T>(f: impl FnOnce(&crate::modules::active_window::ActiveWindowModule) -> T) -> Option<T> {
+    with_renderer(|renderer| {
+        renderer
+            .module_registry
+            .get("active_window")
+            .and_then(|m| m.as_any().downcast_ref::<crate::modules::active_window::ActiveWindowModule>())
+            .map(f)
+    })
+    .flatten()
+}
+
+/// List the active app's windows; picking one brings it to the front
+fn show_active_window_list(hwnd: HWND, x: i32, y: i32) {
+    let Some((current, windows)) = with_active_window(|awm| (awm.window(), awm.app_windows())) else {
+        return;
+    };
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if windows.is_empty() {
+            append_disabled_item(menu, "No open windows");
+        }
+        for (i, (window, title)) in windows.iter().take(ACTIVE_WINDOW_MAX).enumerate() {
+            let title = crate::utils::truncate_string(title, 60);
+            append_menu_item(menu, ACTIVE_WINDOW_BASE + i as u32, &title, Some(*window) == current);
+        }
+    });
+
+    if cmd != 0 {
+        info!("Active window menu returned cmd: {}", cmd);
+        match windows.get(cmd.wrapping_sub(ACTIVE_WINDOW_BASE) as usize) {
+            Some((window, _)) => crate::modules::active_window::activate(*window),
+            None => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Minimize, maximize or close the active window
+fn show_active_window_actions(hwnd: HWND, x: i32, y: i32) {
+    let Some((title, maximized)) = with_active_window(|awm| {
+        awm.window().map(|_| (awm.window_title().to_string(), awm.is_maximized()))
+    })
+    .flatten() else {
+        return;
+    };
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_disabled_item(menu, &crate::utils::truncate_string(&title, 60));
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, ACTIVE_MINIMIZE, "Minimize", false);
+        append_menu_item(menu, ACTIVE_MAXIMIZE, if maximized { "Restore" } else { "Maximize" }, false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, ACTIVE_CLOSE, "Close Window", false);
+    });
+
+    if cmd != 0 {
+        info!("Active window actions returned cmd: {}", cmd);
+        match cmd {
+            ACTIVE_MINIMIZE => {
+                with_active_window(|awm| awm.minimize());
+            }
+            ACTIVE_MAXIMIZE => {
+                with_active_window(|awm| awm.toggle_maximize());
+            }
+            ACTIVE_CLOSE => {
+                with_active_window(|awm| awm.close());
+            }
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show the account menu: lock, switch user, sign out and account settings
+fn show_user_menu(hwnd: HWND, x: i32, y: i32) {
+    use crate::config::PowerAction;
+
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let mut name = String::new();
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("user") {
+            if let Some(um) = module.as_any().downcast_ref::<crate::modules::user::UserModule>() {
+                name = um.display_name().to_string();
+            }
+        }
+    });
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if !name.is_empty() {
+            append_disabled_item(menu, &name);
+            unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        }
+        append_menu_item(menu, USER_LOCK, "Lock", false);
+        append_menu_item(menu, USER_SWITCH, "Switch User", false);
+        let sign_out = if config.modules.power.confirm.contains(&PowerAction::SignOut) { "Sign Out..." } else { "Sign Out" };
+        append_menu_item(menu, USER_SIGN_OUT, sign_out, false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, USER_SETTINGS, "Account Settings...", false);
+    });
+
+    if cmd != 0 {
+        info!("User menu returned cmd: {}", cmd);
+        match cmd {
+            USER_LOCK => {
+                crate::modules::power::request(PowerAction::Lock, &config.modules.power);
+            }
+            USER_SWITCH => crate::modules::user::switch_user(),
+            USER_SIGN_OUT => {
+                crate::modules::power::request(PowerAction::SignOut, &config.modules.power);
+            }
+            USER_SETTINGS => open_url("ms-settings:yourinfo"),
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show ping statistics and the public IP, with refresh and copy actions
+fn show_connectivity_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let mut snapshot = None;
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get("connectivity") {
+            if let Some(cm) = module.as_any().downcast_ref::<crate::modules::connectivity::ConnectivityModule>() {
+                snapshot = Some((cm.target(), cm.stats(), cm.public_ip()));
+            }
+        }
+    });
+    let Some((target, stats, public_ip)) = snapshot else {
+        return;
+    };
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_disabled_item(menu, &format!("Ping {}", target));
+        match stats {
+            Some(stats) => {
+                let last = match stats.last {
+                    Some(ms) => format!("Last: {:.0} ms", ms),
+                    None => "Last: lost".to_string(),
+                };
+                append_disabled_item(menu, &last);
+                if stats.loss < 100.0 {
+                    append_disabled_item(
+                        menu,
+                        &format!("Average: {:.0} ms (min {:.0}, max {:.0})", stats.avg, stats.min, stats.max),
+                    );
+                }
+                append_disabled_item(menu, &format!("Jitter: {:.1} ms", stats.jitter));
+                append_disabled_item(menu, &format!("Packet Loss: {:.0}% of {}", stats.loss, stats.sent));
+            }
+            None => append_disabled_item(menu, "Waiting for the first ping..."),
+        }
+        if config.modules.connectivity.show_public_ip {
+            unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+            match &public_ip {
+                Some(public) => {
+                    append_disabled_item(menu, &format!("Public IP: {}", public.ip));
+                    let location = public.location();
+                    if !location.is_empty() {
+                        append_disabled_item(menu, &location);
+                    }
+                    if !public.org.is_empty() {
+                        append_disabled_item(menu, &public.org);
+                    }
+                    append_menu_item(menu, CONNECTIVITY_COPY_IP, "Copy Public IP", false);
+                }
+                None => append_disabled_item(menu, "Looking up public IP..."),
+            }
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, CONNECTIVITY_REFRESH, "Refresh Now", false);
+        append_menu_item(menu, CONNECTIVITY_SETTINGS, "Network Settings...", false);
+    });
+
+    if cmd != 0 {
+        info!("Connectivity menu returned cmd: {}", cmd);
+        match cmd {
+            CONNECTIVITY_REFRESH => {
+                with_renderer(|renderer| {
+                    if let Some(module) = renderer.module_registry.get_mut("connectivity") {
+                        module.force_refresh(&config);
+                    }
+                });
+            }
+            CONNECTIVITY_COPY_IP => {
+                if let Some(public) = public_ip {
+                    if let Err(e) = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(public.ip)) {
+                        log::warn!("Failed to copy the public IP: {}", e);
+                    }
+                }
+            }
+            CONNECTIVITY_SETTINGS => open_url("ms-settings:network-status"),
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Show scheduled tasks with their next run and run-now / enable actions
+pub fn show_scheduler_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let tasks = &config.scheduler.tasks;
+    let alarms = &config.scheduler.alarms;
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_alarm_items(menu, alarms);
+        if tasks.is_empty() {
+            append_disabled_item(menu, "No scheduled tasks");
+        }
+        for (i, task) in tasks.iter().enumerate() {
+            let schedule = match (task.every_minutes, task.at.as_deref()) {
+                (Some(m), _) => format!("every {} min", m),
+                (None, Some(at)) => format!("daily at {}", at),
+                (None, None) => "no schedule".to_string(),
+            };
+            let label = format!(
+                "{} {} — {}",
+                if task.enabled { "●" } else { "○" },
+                task.name,
+                schedule
+            );
+            unsafe {
+                let Ok(sub) = CreatePopupMenu() else { continue };
+                let known = crate::actions::registry().lock().contains(&task.action);
+                append_disabled_item(
+                    sub,
+                    &format!("Action: {}{}", task.action, if known { "" } else { " (unknown)" }),
+                );
+                let last = crate::scheduler::last_run(task)
+                    .map(|t| t.format("%H:%M").to_string())
+                    .unwrap_or_else(|| "not yet".to_string());
+                append_disabled_item(sub, &format!("Last run: {}", last));
+                if task.enabled {
+                    if let Some(next) = crate::scheduler::next_run(task) {
+                        append_disabled_item(sub, &format!("Next run: {}", next.format("%a %H:%M")));
+                    }
+                }
+                AppendMenuW(sub, MF_SEPARATOR, 0, None).ok();
+                let base = SCHEDULER_BASE + i as u32 * 2;
+                append_menu_item(sub, base, "Run Now", false);
+                append_menu_item(sub, base + 1, "Enabled", task.enabled);
+                let wide: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
+        unsafe {
+            AppendMenuW(menu, MF_SEPARATOR, 0, None).ok();
+            // Reference list for writing tasks in the config
+            if let Ok(sub) = CreatePopupMenu() {
+                for (name, description) in crate::actions::registry().lock().list() {
+                    append_disabled_item(sub, &format!("{} — {}", name, description));
+                }
+                let wide: Vec<u16> = crate::i18n::tr("Available Actions").encode_utf16().chain(std::iter::once(0)).collect();
+                AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr())).ok();
+            }
+        }
+        append_menu_item(menu, APP_SETTINGS, "Edit Tasks and Alarms...", false);
+    });
+
+    if cmd != 0 {
+        info!("Scheduler menu returned cmd: {}", cmd);
+        if (SCHEDULER_BASE..SCHEDULER_BASE + tasks.len() as u32 * 2).contains(&cmd) {
+            let offset = cmd - SCHEDULER_BASE;
+            let index = (offset / 2) as usize;
+            match offset % 2 {
+                0 => crate::scheduler::run_now(hwnd, &tasks[index]),
+                _ => super::config_handlers::toggle_config_bool(hwnd, |c| {
+                    &mut c.scheduler.tasks[index].enabled
+                }),
+            }
+        } else if !handle_alarm_command(hwnd, cmd, alarms.len()) {
+            super::menus::handle_menu_command(hwnd, cmd);
+        }
+    }
+}
+
+/// Show screenshot and recording actions
+fn show_capture_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let recording = crate::modules::screen_recorder::is_recording();
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_menu_item(menu, CAPTURE_FULL, "Capture Full Screen", false);
+        append_menu_item(menu, CAPTURE_REGION, "Capture Region...", false);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(
+            menu,
+            CAPTURE_RECORD,
+            if recording { "Stop Recording" } else { "Start Recording" },
+            false,
+        );
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, CAPTURE_CLIPBOARD, "Copy Screenshots to Clipboard", config.modules.capture.copy_to_clipboard);
+        append_menu_item(menu, CAPTURE_OPEN_FOLDER, "Open Captures Folder", false);
+    });
+
+    if cmd != 0 {
+        info!("Capture menu returned cmd: {}", cmd);
+        let capture = &config.modules.capture;
+        match cmd {
+            CAPTURE_FULL => {
+                // Give the menu time to disappear so it isn't in the shot
+                std::thread::sleep(std::time::Duration::from_millis(150));
+                if let Err(e) = crate::modules::capture::capture_full_screen(capture) {
+                    show_capture_error(&format!("Screenshot failed: {}", e));
+                }
+            }
+            CAPTURE_REGION => {
+                std::thread::sleep(std::time::Duration::from_millis(150));
+                let Some(image) = crate::modules::capture::grab_virtual_screen() else {
+                    show_capture_error("Screenshot failed: the screen could not be captured.");
+                    return;
+                };
+                let capture = capture.clone();
+                let shown = crate::render::show_region_select(hwnd, image, move |image, rect| {
+                    let result = image
+                        .crop(rect)
+                        .ok_or_else(|| anyhow::anyhow!("empty selection"))
+                        .and_then(|region| crate::modules::capture::save_screenshot(&region, &capture));
+                    if let Err(e) = result {
+                        show_capture_error(&format!("Screenshot failed: {}", e));
+                    }
+                });
+                if let Err(e) = shown {
+                    log::warn!("Failed to show region selection: {}", e);
+                }
+            }
+            CAPTURE_RECORD => toggle_recording(hwnd),
+            CAPTURE_OPEN_FOLDER => {
+                let dir = capture.output_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                open_url(&dir.to_string_lossy());
+            }
+            _ => super::menus::handle_menu_command(hwnd, cmd),
+        }
+    }
+}
+
+/// Start a screen recording, or stop the one in progress
+fn toggle_recording(hwnd: HWND) {
+    use crate::modules::screen_recorder;
+
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    if screen_recorder::is_recording() {
+        if let Some(path) = screen_recorder::stop_recording() {
+            crate::attention::request(
+                &config,
+                crate::attention::AttentionRequest::new(
+                    "capture",
+                    crate::attention::AttentionLevel::Info,
+                    "Recording saved",
+                    &path.display().to_string(),
+                ),
+            );
+        }
+    } else {
+        let capture = &config.modules.capture;
+        let result = crate::modules::capture::output_path(capture, "Recording", "mp4")
+            .and_then(|path| screen_recorder::start_recording(path, capture.record_fps));
+        if let Err(e) = result {
+            show_capture_error(&format!("Could not start recording: {}", e));
+        }
+    }
+
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get_mut("capture") {
+            module.update(&config);
+        }
+    });
+    if let Some(state) = get_window_state() {
+        state.write().needs_redraw = true;
+    }
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+}
+
+/// Show recent memos with record and folder actions
+fn show_voice_memo_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let recording = crate::modules::voice_memo::is_recording();
+    let memos = crate::modules::voice_memo::memos(&config.modules.voice_memo);
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_menu_item(
+            menu,
+            VOICE_MEMO_RECORD,
+            if recording { "Stop Recording" } else { "Record Memo" },
+            false,
+        );
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        if memos.is_empty() {
+            append_disabled_item(menu, "No memos yet");
+        }
+        for (i, memo) in memos.iter().take(VOICE_MEMO_MAX_LISTED).enumerate() {
+            let name = memo.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            append_menu_item(menu, VOICE_MEMO_BASE + i as u32, &name, false);
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, VOICE_MEMO_OPEN_FOLDER, "Open Memos Folder", false);
+    });
+
+    if cmd != 0 {
+        info!("Voice memo menu returned cmd: {}", cmd);
+        match cmd {
+            VOICE_MEMO_RECORD => toggle_voice_memo(hwnd),
+            VOICE_MEMO_OPEN_FOLDER => {
+                let dir = config.modules.voice_memo.output_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                open_url(&dir.to_string_lossy());
+            }
+            _ => {
+                if let Some(memo) = cmd.checked_sub(VOICE_MEMO_BASE).and_then(|i| memos.get(i as usize)) {
+                    open_url(&memo.to_string_lossy());
+                } else {
+                    super::menus::handle_menu_command(hwnd, cmd);
+                }
+            }
+        }
+    }
+}
+
+/// Start a voice memo, or stop and save the one being recorded
+fn toggle_voice_memo(hwnd: HWND) {
+    use crate::modules::voice_memo;
+
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    if voice_memo::is_recording() {
+        if let Some(path) = voice_memo::stop_recording() {
+            crate::attention::request(
+                &config,
+                crate::attention::AttentionRequest::new(
+                    "voice_memo",
+                    crate::attention::AttentionLevel::Info,
+                    "Voice memo saved",
+                    &path.display().to_string(),
+                ),
+            );
+        }
+    } else if let Err(e) = voice_memo::start_recording(&config.modules.voice_memo) {
+        log::warn!("Could not start voice memo: {}", e);
+        let title: Vec<u16> = "Voice Memo".encode_utf16().chain(std::iter::once(0)).collect();
+        let msg: Vec<u16> = format!("Could not start recording: {}", e)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        unsafe {
+            MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), MB_OK | MB_ICONWARNING);
+        }
+    }
+
+    with_renderer(|renderer| {
+        if let Some(module) = renderer.module_registry.get_mut("voice_memo") {
+            module.update(&config);
+        }
+    });
+    if let Some(state) = get_window_state() {
+        state.write().needs_redraw = true;
+    }
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, false);
+    }
+}
+
+fn show_capture_error(msg: &str) {
+    log::warn!("{}", msg);
+    let title: Vec<u16> = "Screen Capture".encode_utf16().chain(std::iter::once(0)).collect();
+    let msg: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        MessageBoxW(None, PCWSTR(msg.as_ptr()), PCWSTR(title.as_ptr()), MB_OK | MB_ICONWARNING);
+    }
+}
+
 fn show_keyboard_menu(hwnd: HWND, x: i32, y: i32) {
     let config = get_window_state()
         .map(|s| s.read().config.clone())
         .unwrap_or_default();
 
+    // (hkl, label, is current) for each installed layout
+    let layouts: Vec<(isize, String, bool)> = with_renderer(|renderer| {
+        renderer
+            .module_registry
+            .get_mut("keyboard_layout")
+            .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::keyboard_layout::KeyboardLayoutModule>())
+            .map(|m| {
+                let layouts = m.layouts().to_vec();
+                layouts
+                    .into_iter()
+                    .map(|l| {
+                        let label = match l.flag() {
+                            Some(flag) if config.modules.keyboard_layout.show_flag => format!("{}  {}", flag, l.name),
+                            _ => format!("{}  {}", l.code, l.name),
+                        };
+                        (l.hkl, label, m.is_current(l.hkl))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    })
+    .unwrap_or_default();
+
     let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        if layouts.is_empty() {
+            append_disabled_item(menu, "No input languages found");
+        }
+        for (i, (_, label, current)) in layouts.iter().enumerate() {
+            append_menu_item(menu, KEYBOARD_LAYOUT_BASE + i as u32, label, *current);
+        }
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
         append_menu_item(menu, KEYBOARD_SHOW_FULL, "Show Full Language Name", config.modules.keyboard_layout.show_full_name);
+        append_menu_item(menu, KEYBOARD_SHOW_FLAG, "Show Flag", config.modules.keyboard_layout.show_flag);
+    });
+
+    if cmd == 0 {
+        return;
+    }
+    info!("Keyboard menu returned cmd: {}", cmd);
+    if cmd >= KEYBOARD_LAYOUT_BASE {
+        if let Some((hkl, _, _)) = layouts.get((cmd - KEYBOARD_LAYOUT_BASE) as usize) {
+            with_renderer(|renderer| {
+                if let Some(module) = renderer
+                    .module_registry
+                    .get_mut("keyboard_layout")
+                    .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::keyboard_layout::KeyboardLayoutModule>())
+                {
+                    module.activate_layout(*hkl);
+                }
+            });
+            unsafe {
+                let _ = InvalidateRect(hwnd, None, false);
+            }
+        }
+    } else {
+        super::menus::handle_menu_command(hwnd, cmd);
+    }
+}
+
+fn show_lock_keys_menu(hwnd: HWND, x: i32, y: i32) {
+    let config = get_window_state()
+        .map(|s| s.read().config.clone())
+        .unwrap_or_default();
+    let cfg = &config.modules.lock_keys;
+
+    let cmd = show_popup_menu(hwnd, x, y, |menu| {
+        append_menu_item(menu, LOCK_KEYS_CAPS, "Show Caps Lock", cfg.show_caps_lock);
+        append_menu_item(menu, LOCK_KEYS_NUM, "Show Num Lock", cfg.show_num_lock);
+        append_menu_item(menu, LOCK_KEYS_SCROLL, "Show Scroll Lock", cfg.show_scroll_lock);
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None).ok(); }
+        append_menu_item(menu, LOCK_KEYS_ONLY_ACTIVE, "Only Show Active Locks", cfg.only_active);
+        append_menu_item(menu, LOCK_KEYS_INDICATOR, "On-Screen Indicator", cfg.on_screen_indicator);
     });
 
     if cmd != 0 {
-        info!("Keyboard menu returned cmd: {}", cmd);
+        info!("Lock keys menu returned cmd: {}", cmd);
         super::menus::handle_menu_command(hwnd, cmd);
     }
 }
@@ -525,15 +2790,44 @@ fn show_bluetooth_menu(hwnd: HWND, x: i32, y: i32) {
     }
 }
 
+/// Helper to append a greyed-out informational menu item
+fn append_disabled_item(menu: HMENU, text: &str) {
+    unsafe {
+        let text = crate::i18n::tr(text);
+        let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        AppendMenuW(menu, MF_STRING | MF_GRAYED | reading_order(&text), 0, PCWSTR(wide.as_ptr())).ok();
+    }
+}
+
 /// Helper to append a menu item
 fn append_menu_item(menu: HMENU, id: u32, text: &str, checked: bool) {
     unsafe {
+        let text = crate::i18n::tr(text);
         let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
         let flags = if checked {
             MF_STRING | MF_CHECKED
         } else {
             MF_STRING
         };
-        AppendMenuW(menu, flags, id as usize, PCWSTR(wide.as_ptr())).ok();
+        AppendMenuW(menu, flags | reading_order(&text), id as usize, PCWSTR(wide.as_ptr())).ok();
     }
-}
\ No newline at end of file
+}
+
+/// Show an alert's full text
+fn show_weather_alert(alert: &crate::modules::weather::WeatherAlert) {
+    let mut text = format!("{}\n\nSeverity: {}", alert.headline, alert.severity);
+    if let Some(expires) = alert.expires {
+        text.push_str(&format!("\nUntil: {}", expires.format("%a %d %b %H:%M")));
+    }
+    if !alert.description.is_empty() {
+        text.push_str(&format!("\n\n{}", alert.description));
+    }
+    if !alert.instruction.is_empty() {
+        text.push_str(&format!("\n\n{}", alert.instruction));
+    }
+    let title = crate::utils::to_wide_string(&alert.event);
+    let text = crate::utils::to_wide_string(&text);
+    unsafe {
+        MessageBoxW(None, PCWSTR(text.as_ptr()), PCWSTR(title.as_ptr()), MB_OK | MB_ICONWARNING);
+    }
+}
@@ -3,13 +3,16 @@
 //! Contains the main window message handler and related message processing logic.
 
 use log::{debug, info, warn};
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
 use windows::Win32::Graphics::Gdi::{BeginPaint, EndPaint, InvalidateRect, PAINTSTRUCT};
-use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, ReleaseCapture, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT, VK_CONTROL, VK_SHIFT,
+};
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::Win32::Graphics::Gdi::ClientToScreen;
+use windows::Win32::Graphics::Gdi::{ClientToScreen, ScreenToClient};
 
 use crate::render;
+use crate::sounds::SoundEvent;
 
 use super::state::get_window_state;
 use super::renderer::with_renderer;
@@ -19,12 +22,54 @@ use super::module_handlers::handle_module_click;
 /// WM_MOUSELEAVE message constant
 const WM_MOUSELEAVE: u32 = 0x02A3;
 
+/// Timer that opens the hovered module's tooltip
+const TOOLTIP_TIMER: usize = 4;
+/// Timer that paints the next animation frame
+const ANIMATION_TIMER: usize = 5;
+const ANIMATION_FRAME_MS: u32 = 16;
+/// A press on empty bar space released at least this far away (before DPI
+/// scaling), this quickly, is a flick
+const FLICK_DISTANCE: i32 = 24;
+const FLICK_TIME: std::time::Duration = std::time::Duration::from_millis(400);
+
 /// Custom window messages
 pub const WM_TOPBAR_UPDATE: u32 = WM_USER + 1;
 pub const WM_TOPBAR_THEME_CHANGED: u32 = WM_USER + 2;
 pub const WM_TOPBAR_TRAY: u32 = WM_USER + 3;
+/// Click the modules invoked through UI Automation (see `automation`)
 pub const WM_TOPBAR_MODULE_CLICK: u32 = WM_USER + 4;
 pub const WM_TOPBAR_NIGHTLIGHT_TOGGLED: u32 = WM_USER + 5;
+/// A command from the command pipe; lparam points at the request (see `ipc`)
+pub const WM_TOPBAR_IPC: u32 = WM_USER + 6;
+
+/// Repaint the modules that changed since the last check, or the whole bar
+/// when the renderer can't narrow it down
+unsafe fn invalidate_changed(hwnd: HWND) {
+    let Some(state) = get_window_state() else {
+        return;
+    };
+    let (config, bar_rect) = {
+        let state = state.read();
+        (state.config.clone(), state.bar_rect)
+    };
+    match with_renderer(|renderer| renderer.pending_repaint(&config, &bar_rect)) {
+        Some(render::Repaint::Nothing) => {}
+        Some(render::Repaint::Areas(areas)) => {
+            for area in areas {
+                let rect = RECT {
+                    left: area.x,
+                    top: area.y,
+                    right: area.right(),
+                    bottom: area.bottom(),
+                };
+                let _ = InvalidateRect(hwnd, Some(&rect), false);
+            }
+        }
+        _ => {
+            let _ = InvalidateRect(hwnd, None, false);
+        }
+    }
+}
 
 /// Window procedure for handling Windows messages
 pub unsafe extern "system" fn window_proc(
@@ -48,14 +93,38 @@ pub unsafe extern "system" fn window_proc(
 
                 let bar_rect = state_guard.bar_rect;
                 let theme = state_guard.theme_manager.theme().clone();
+                let config = state_guard.config.clone();
                 drop(state_guard);
 
-                with_renderer(|renderer| {
-                    renderer.paint(hdc, &bar_rect, &theme);
-                });
+                let (animating, layout_changed) = with_renderer(|renderer| {
+                    let painted = ps.rcPaint;
+                    let area = crate::utils::Rect::new(
+                        painted.left,
+                        painted.top,
+                        painted.right - painted.left,
+                        painted.bottom - painted.top,
+                    );
+                    renderer.paint(hdc, &bar_rect, &area, &theme);
+                    super::automation::refresh(renderer, &config, &bar_rect);
+                    (renderer.animations.is_running(), renderer.layout_changed())
+                })
+                .unwrap_or((false, false));
 
                 let _ = EndPaint(hwnd, &ps);
 
+                // Only the changed modules were copied to the screen; if anything
+                // moved, the rest of the bar needs copying too
+                let painted = ps.rcPaint;
+                let partial = painted.left > 0 || painted.top > 0 || painted.right < bar_rect.width || painted.bottom < bar_rect.height;
+                if partial && layout_changed {
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+
+                // Paint at a smooth frame rate until running animations settle
+                if animating {
+                    SetTimer(hwnd, ANIMATION_TIMER, ANIMATION_FRAME_MS, None);
+                }
+
                 if let Some(state) = get_window_state() {
                     state.write().needs_redraw = false;
                 }
@@ -67,56 +136,91 @@ pub unsafe extern "system" fn window_proc(
             let timer_id = wparam.0;
             match timer_id {
                 1 => {
-                    // Clock update (1 second)
-                    let _ = InvalidateRect(hwnd, None, false);
+                    // Clock update (1 second); only modules that changed are repainted
+                    invalidate_changed(hwnd);
+                    if let Some(state) = get_window_state() {
+                        let config = state.read().config.clone();
+                        crate::scheduler::tick(hwnd, &config);
+                        crate::alarms::tick(hwnd, &config);
+                        crate::briefing::tick(&config);
+                        crate::attention::tick(hwnd, &config);
+                        crate::demo::tick(&config);
+                        crate::share_guard::tick(hwnd, &config);
+                        // Start with Windows is checked in the background after loads
+                        super::config_handlers::report_config_problems(&config);
+                    }
+                    // Keep an open tooltip's values and sparkline current
+                    if render::tooltip_module().is_some() {
+                        super::module_handlers::show_module_tooltip(hwnd);
+                    }
                 }
                 2 => {
                     // System info update (2 seconds)
-                    let _ = InvalidateRect(hwnd, None, false);
+                    invalidate_changed(hwnd);
                 }
                 3 => {
-                    // Fast update for active window and animations (100ms)
-                    // Always invalidate to keep active window responsive
+                    // Fast update for active window and animations (100ms); only
+                    // modules that changed are repainted
+                    invalidate_changed(hwnd);
+                    if let Some(state) = get_window_state() {
+                        let config = state.read().config.clone();
+                        crate::auto_hide::tick(hwnd, &config);
+                        crate::visibility::tick(hwnd, &config);
+                    }
+                }
+                ANIMATION_TIMER => {
+                    let _ = KillTimer(hwnd, ANIMATION_TIMER);
                     let _ = InvalidateRect(hwnd, None, false);
                 }
+                TOOLTIP_TIMER => {
+                    let _ = KillTimer(hwnd, TOOLTIP_TIMER);
+                    super::module_handlers::show_module_tooltip(hwnd);
+                }
                 _ => {}
             }
             LRESULT(0)
         }
 
         WM_HOTKEY => {
-            // Global hotkeys (registered during window creation)
-            if let Some(map) = crate::hotkey::global_hotkey_map() {
-                let guard = map.lock();
-                let id = wparam.0 as i32;
-                if let Some(action) = guard.get(&id) {
-                    match action {
-                        crate::hotkey::HotkeyAction::QuickSearch => {
-                            // Show quick search popup centered under the bar
-                            let _ = render::show_quick_search(hwnd);
-                        }
-                        crate::hotkey::HotkeyAction::OpenMenu => {
-                            // Open the app menu (approximate position at left side)
-                            // Use the public helper so behavior is consistent with clicks
-                            super::module_handlers::show_module_menu(hwnd, "app_menu", 12, 28);
-                        }
-                        crate::hotkey::HotkeyAction::ToggleTheme => {
-                            // Toggle theme using the ThemeManager and reapply window style
-                            if let Some(state) = get_window_state() {
-                                let mut s = state.write();
-                                s.theme_manager.toggle();
-                                let theme = s.theme_manager.theme().clone();
-                                drop(s);
-                                let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
-                                let _ = InvalidateRect(hwnd, None, true);
-                            }
-                        }
-                        crate::hotkey::HotkeyAction::ToggleBar => {
-                            // Toggle visibility via WindowManager post message
-                            unsafe { let _ = PostMessageW(hwnd, WM_USER + 99, WPARAM(0), LPARAM(0)); }
+            // Global hotkeys (see `hotkey::register_all`); the map is released before
+            // dispatching, since a keymap action may reload the config and re-register
+            let id = wparam.0 as i32;
+            let action = crate::hotkey::global_hotkey_map().and_then(|map| map.lock().get(&id).cloned());
+            if let Some(action) = action {
+                match action {
+                    crate::hotkey::HotkeyAction::QuickSearch => {
+                        // Show quick search popup centered under the bar
+                        let _ = render::show_quick_search(hwnd);
+                    }
+                    crate::hotkey::HotkeyAction::CommandPalette => {
+                        let _ = render::show_command_palette(hwnd);
+                    }
+                    crate::hotkey::HotkeyAction::OpenMenu => {
+                        // Open the app menu (approximate position at left side)
+                        // Use the public helper so behavior is consistent with clicks
+                        super::module_handlers::show_module_menu(hwnd, "app_menu", 12, 28);
+                    }
+                    crate::hotkey::HotkeyAction::ToggleTheme => {
+                        super::config_handlers::toggle_theme(hwnd);
+                    }
+                    crate::hotkey::HotkeyAction::FocusBar => {
+                        super::focus::focus_bar(hwnd);
+                    }
+                    crate::hotkey::HotkeyAction::ToggleBar => {
+                        super::manager::WindowManager::toggle_bar(hwnd);
+                    }
+                    crate::hotkey::HotkeyAction::SnapWindow(layout) => {
+                        super::snap::snap_foreground(layout);
+                    }
+                    crate::hotkey::HotkeyAction::TileWindows(layout) => {
+                        super::tile::tile_windows(layout);
+                    }
+                    crate::hotkey::HotkeyAction::Action(spec) => {
+                        if let Err(e) = crate::actions::execute(hwnd, &spec) {
+                            log::warn!("Hotkey action {} failed: {}", spec, e);
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
 
@@ -124,8 +228,7 @@ pub unsafe extern "system" fn window_proc(
         }
 
         WM_MOUSEMOVE => {
-            let x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            let (x, y) = layout_point(lparam);
             const DRAG_THRESHOLD: i32 = 6;
 
             if let Some(state) = get_window_state() {
@@ -149,9 +252,18 @@ pub unsafe extern "system" fn window_proc(
                     if let (Some(click_id), Some((cx, _cy))) =
                         (state_guard.clicked_module.clone(), state_guard.clicked_pos)
                     {
-                        if (x - cx).abs() > DRAG_THRESHOLD {
-                            debug!("Starting drag for module: {}", click_id);
-                            state_guard.dragging_module = Some(click_id.clone());
+                        // Individual launcher icons are not reorderable by dragging
+                        let draggable = crate::modules::launcher::item_index(&click_id).is_none();
+                        if draggable && (x - cx).abs() > DRAG_THRESHOLD {
+                            // A module in a group moves its whole group
+                            let drag_id = state_guard
+                                .config
+                                .modules
+                                .group_of(&click_id)
+                                .map(|g| g.id())
+                                .unwrap_or(click_id);
+                            debug!("Starting drag for module: {}", drag_id);
+                            state_guard.dragging_module = Some(drag_id);
                             state_guard.drag_start_x = cx;
                             state_guard.drag_current_x = x;
                             state_guard.hover_module = None;
@@ -173,11 +285,20 @@ pub unsafe extern "system" fn window_proc(
                     // Update hover module based on position
                     let new_hover = with_renderer(|renderer| renderer.hit_test(x, y)).flatten();
                     if new_hover != current_hover {
+                        // The tooltip follows the hover after a short rest on the module
+                        cancel_tooltip(hwnd);
                         if let Some(state) = get_window_state() {
                             let mut state_guard = state.write();
+                            let behavior = &state_guard.config.behavior;
+                            if new_hover.is_some() && behavior.tooltips {
+                                unsafe {
+                                    SetTimer(hwnd, TOOLTIP_TIMER, behavior.tooltip_delay_ms.max(1), None);
+                                }
+                            }
                             state_guard.hover_module = new_hover;
                             state_guard.needs_redraw = true;
                         }
+                        let _ = InvalidateRect(hwnd, None, false);
                     }
                 }
             }
@@ -185,6 +306,7 @@ pub unsafe extern "system" fn window_proc(
         }
 
         WM_MOUSELEAVE => {
+            cancel_tooltip(hwnd);
             if let Some(state) = get_window_state() {
                 let mut state_guard = state.write();
                 state_guard.is_hovered = false;
@@ -196,8 +318,8 @@ pub unsafe extern "system" fn window_proc(
         }
 
         WM_LBUTTONDOWN => {
-            let x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            cancel_tooltip(hwnd);
+            let (x, y) = layout_point(lparam);
 
             let module_id = with_renderer(|renderer| renderer.hit_test(x, y)).flatten();
             if let Some(module_id) = module_id {
@@ -210,9 +332,12 @@ pub unsafe extern "system" fn window_proc(
                     s.dragging_module = None;
                     s.drag_start_x = x;
                     s.drag_current_x = x;
+                    s.needs_redraw = true;
 
-                    // Record origin (left/right and index) for later reordering
+                    // Record origin (left/right and index) for later reordering; a
+                    // module in a group is placed by its group
                     let cfg = (*s.config).clone();
+                    let module_id = cfg.modules.group_of(&module_id).map(|g| g.id()).unwrap_or(module_id);
                     if let Some(idx) = cfg
                         .modules
                         .left_modules
@@ -238,20 +363,93 @@ pub unsafe extern "system" fn window_proc(
                 // Capture mouse so we receive move/up events
                 unsafe {
                     let _ = SetCapture(hwnd);
+                    let _ = InvalidateRect(hwnd, None, false);
+                }
+            } else if let Some(state) = get_window_state() {
+                // Empty space: follow the press in case it's a flick, in window
+                // coordinates so directions stay as seen on screen
+                let (x, y) = client_point(lparam);
+                state.write().gesture_start = Some((x, y, std::time::Instant::now()));
+                let _ = SetCapture(hwnd);
+            }
+            LRESULT(0)
+        }
+
+        WM_LBUTTONDBLCLK => {
+            let (x, y) = layout_point(lparam);
+            if with_renderer(|renderer| renderer.hit_test(x, y)).flatten().is_none() {
+                if let Some(state) = get_window_state() {
+                    let spec = state.read().config.gestures.double_click.clone();
+                    run_gesture(hwnd, "Double-click", &spec);
                 }
             }
             LRESULT(0)
         }
 
         WM_LBUTTONUP => {
-            let x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let _y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            let (x, _) = layout_point(lparam);
 
             if let Some(state) = get_window_state() {
                 let mut s = state.write();
 
+                if let Some((start_x, start_y, started)) = s.gesture_start.take() {
+                    let (config, dpi) = (s.config.clone(), s.dpi);
+                    drop(s);
+                    let _ = ReleaseCapture();
+                    let (x, y) = client_point(lparam);
+                    if let Some((name, spec)) = flick(&config.gestures, x - start_x, y - start_y, started.elapsed(), dpi) {
+                        run_gesture(hwnd, name, spec);
+                    }
+                    return LRESULT(0);
+                }
+
+                // Running app icons are reordered within their strip, not in the config
+                if let Some(dragged) = s
+                    .dragging_module
+                    .as_deref()
+                    .and_then(crate::modules::tasks::item_hwnd)
+                {
+                    let drop_x = s.drag_current_x;
+                    with_renderer(|renderer| {
+                        let tm = renderer
+                            .module_registry
+                            .get("tasks")
+                            .and_then(|m| m.as_any().downcast_ref::<crate::modules::tasks::TasksModule>());
+                        // Insertion index from the icon midpoints, in strip order
+                        let insert_idx = tm
+                            .map(|tm| {
+                                let bounds = renderer.module_bounds();
+                                tm.windows()
+                                    .iter()
+                                    .position(|w| {
+                                        bounds
+                                            .get(&crate::modules::tasks::item_id(w.hwnd))
+                                            .map(|r| drop_x < r.x + r.width / 2)
+                                            .unwrap_or(false)
+                                    })
+                                    .unwrap_or(tm.windows().len())
+                            })
+                            .unwrap_or(0);
+                        if let Some(tm) = renderer
+                            .module_registry
+                            .get_mut("tasks")
+                            .and_then(|m| m.as_any_mut().downcast_mut::<crate::modules::tasks::TasksModule>())
+                        {
+                            tm.move_to(dragged.0 as isize, insert_idx);
+                        }
+                    });
+                    s.dragging_module = None;
+                    s.clicked_module = None;
+                    s.clicked_pos = None;
+                    s.drag_origin_side = None;
+                    s.drag_orig_index = None;
+                    s.needs_redraw = true;
+                    unsafe {
+                        let _ = InvalidateRect(hwnd, None, false);
+                    }
+                }
                 // If a drag was in progress, finalize reorder
-                if let Some(drag_id) = s.dragging_module.clone() {
+                else if let Some(drag_id) = s.dragging_module.clone() {
                     // Use renderer bounds to determine insertion point
                     with_renderer(|renderer| {
                         let bounds = renderer.module_bounds().clone();
@@ -322,15 +520,20 @@ pub unsafe extern "system" fn window_proc(
                         let _ = InvalidateRect(hwnd, None, false);
                     }
                 } else if let Some(click_id) = s.clicked_module.clone() {
-                    // No drag - treat as click
+                    // No drag - treat as click (Ctrl+click copies the module's value)
                     drop(s); // unlock briefly for handler
-                    handle_module_click(hwnd, &click_id, x);
+                    if GetKeyState(VK_CONTROL.0 as i32) < 0 {
+                        super::module_handlers::copy_module_value(hwnd, &click_id);
+                    } else {
+                        handle_module_click(hwnd, &click_id, x);
+                    }
                     if let Some(state) = get_window_state() {
                         let mut s2 = state.write();
                         s2.clicked_module = None;
                         s2.clicked_pos = None;
                         s2.needs_redraw = true;
                     }
+                    let _ = InvalidateRect(hwnd, None, false);
                 }
 
                 // Release mouse capture
@@ -342,17 +545,32 @@ pub unsafe extern "system" fn window_proc(
             LRESULT(0)
         }
 
+        WM_MBUTTONUP => {
+            let (x, y) = layout_point(lparam);
+            if let Some(module_id) = with_renderer(|renderer| renderer.hit_test(x, y)).flatten() {
+                super::module_handlers::handle_module_middle_click(hwnd, &module_id);
+            }
+            LRESULT(0)
+        }
+
         WM_RBUTTONDOWN => {
-            let x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            cancel_tooltip(hwnd);
+            let (x, y) = layout_point(lparam);
             debug!("Right click at ({}, {})", x, y);
 
             // Get screen coordinates
-            let mut pt = windows::Win32::Foundation::POINT { x, y };
+            let (client_x, client_y) = client_point(lparam);
+            let mut pt = windows::Win32::Foundation::POINT { x: client_x, y: client_y };
             let _ = ClientToScreen(hwnd, &mut pt);
 
-            // Show context menu
-            show_context_menu(hwnd, pt.x, pt.y);
+            // Modules with their own right-click menu take precedence over the bar menu
+            let module_id = with_renderer(|renderer| renderer.hit_test(x, y)).flatten();
+            let handled = module_id
+                .map(|id| super::module_handlers::handle_module_right_click(hwnd, &id, pt.x, pt.y))
+                .unwrap_or(false);
+            if !handled {
+                show_context_menu(hwnd, pt.x, pt.y);
+            }
             LRESULT(0)
         }
 
@@ -361,17 +579,44 @@ pub unsafe extern "system" fn window_proc(
             debug!("Mouse wheel delta: {}", delta);
 
             // Get cursor position from lparam (client coords) and dispatch to the module under cursor
-            let x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            // Wheel messages carry screen coordinates
+            let mut pt = windows::Win32::Foundation::POINT {
+                x: (lparam.0 & 0xFFFF) as i16 as i32,
+                y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+            };
+            let _ = ScreenToClient(hwnd, &mut pt);
+            let (x, y) = to_layout(pt.x, pt.y);
+
+            // Over empty bar space, Ctrl+scroll changes the opacity and Shift+scroll the height
+            let over_module = with_renderer(|renderer| renderer.hit_test(x, y).is_some()).unwrap_or(true);
+            if !over_module {
+                let steps = (delta as i32).signum();
+                if GetKeyState(VK_CONTROL.0 as i32) < 0 {
+                    super::config_handlers::adjust_opacity(hwnd, steps);
+                    return LRESULT(0);
+                }
+                if GetKeyState(VK_SHIFT.0 as i32) < 0 {
+                    super::config_handlers::adjust_bar_height(hwnd, steps);
+                    return LRESULT(0);
+                }
+            }
 
             // Forward the scroll to the module under the cursor (if any)
-            with_renderer(|renderer| {
-                if let Some(module_id) = renderer.hit_test(x, y) {
-                    if let Some(module) = renderer.module_registry.get_mut(&module_id) {
-                        module.on_scroll(delta as i32);
-                    }
+            let cue = with_renderer(|renderer| {
+                let module_id = renderer.hit_test(x, y)?;
+                let module = renderer.module_registry.get_mut(&module_id)?;
+                module.on_scroll(delta as i32);
+                match module.as_any().downcast_ref::<crate::modules::volume::VolumeModule>() {
+                    // The volume module may already play its own feedback sound
+                    Some(volume) => (!volume.sound_feedback_enabled())
+                        .then(|| SoundEvent::ValueChange(Some(volume.volume_level() as f32 / 100.0))),
+                    None => Some(SoundEvent::ValueChange(None)),
                 }
-            });
+            })
+            .flatten();
+            if let Some(cue) = cue {
+                crate::sounds::play_event(cue);
+            }
 
             // Request redraw to reflect changed volume/tooltip immediately
             if let Some(state) = get_window_state() {
@@ -384,25 +629,7 @@ pub unsafe extern "system" fn window_proc(
 
         WM_DISPLAYCHANGE => {
             // Monitor resolution changed
-            if let Some(state) = get_window_state() {
-                let mut state_guard = state.write();
-                let dpi = state_guard.dpi;
-                let config = state_guard.config.clone();
-                state_guard.bar_rect = super::manager::WindowManager::calculate_bar_rect(&config, dpi);
-
-                let rect = state_guard.bar_rect;
-                drop(state_guard);
-
-                let _ = SetWindowPos(
-                    hwnd,
-                    HWND_TOPMOST,
-                    rect.x,
-                    rect.y,
-                    rect.width,
-                    rect.height,
-                    SWP_NOACTIVATE,
-                );
-            }
+            super::manager::WindowManager::relayout(hwnd);
             LRESULT(0)
         }
 
@@ -414,16 +641,22 @@ pub unsafe extern "system" fn window_proc(
                 let config = state_guard.config.clone();
                 state_guard.bar_rect = super::manager::WindowManager::calculate_bar_rect(&config, new_dpi);
             }
+            with_renderer(|renderer| renderer.set_dpi(new_dpi));
             LRESULT(0)
         }
 
-        WM_SETTINGCHANGE => {
+        // High contrast on/off arrives as WM_SETTINGCHANGE (SPI_SETHIGHCONTRAST) plus
+        // WM_THEMECHANGED; switching between contrast themes only sends WM_SYSCOLORCHANGE.
+        // A new accent color sends WM_DWMCOLORIZATIONCOLORCHANGED.
+        WM_SETTINGCHANGE | WM_THEMECHANGED | WM_SYSCOLORCHANGE | WM_DWMCOLORIZATIONCOLORCHANGED => {
             // System settings changed (including theme)
             if let Some(state) = get_window_state() {
                 let mut state_guard = state.write();
-                if state_guard.theme_manager.check_system_theme() {
+                let preferences_changed = crate::accessibility::refresh();
+                if state_guard.theme_manager.check_system_theme() || preferences_changed {
                     let theme = state_guard.theme_manager.theme().clone();
                     drop(state_guard);
+                    crate::modules::events::publish(crate::modules::events::THEME_CHANGED);
                     let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
                     let _ = InvalidateRect(hwnd, None, true);
                 }
@@ -453,17 +686,19 @@ pub unsafe extern "system" fn window_proc(
                         }
                     }
 
+                    // Drive letters coming and going are broadcast to every top-level window
+                    let volume = lparam.0 != 0
+                        && unsafe { (*(lparam.0 as *const DEV_BROADCAST_HDR)).dbch_devicetype } == DBT_DEVTYP_VOLUME;
+                    if volume {
+                        crate::modules::events::publish(crate::modules::events::VOLUMES_CHANGED);
+                        unsafe {
+                            let _ = InvalidateRect(hwnd, None, false);
+                        }
+                    }
+
                     if should_refresh {
-                        // Trigger bluetooth module refresh immediately
-                        with_renderer(|renderer| {
-                            if let Some(module) = renderer.module_registry.get_mut("bluetooth") {
-                                if let Some(bm) = module
-                                    .as_any_mut()
-                                    .downcast_mut::<crate::modules::bluetooth::BluetoothModule>() {
-                                    bm.refresh();
-                                }
-                            }
-                        });
+                        // Modules watching devices (Bluetooth) refresh on the redraw
+                        crate::modules::events::publish(crate::modules::events::DEVICES_CHANGED);
 
                         // Request a redraw to update the UI
                         unsafe {
@@ -477,16 +712,73 @@ pub unsafe extern "system" fn window_proc(
             LRESULT(0)
         }
 
+        WM_DROPFILES => {
+            render::drop_on_shelf(hwnd, windows::Win32::UI::Shell::HDROP(wparam.0 as *mut std::ffi::c_void));
+            LRESULT(0)
+        }
+
         WM_TOPBAR_UPDATE => {
             let _ = InvalidateRect(hwnd, None, false);
             LRESULT(0)
         }
 
+        WM_TOPBAR_TRAY => {
+            // The tray icon passes the mouse message in lparam
+            match lparam.0 as u32 {
+                WM_LBUTTONUP => super::manager::WindowManager::toggle_bar(hwnd),
+                WM_RBUTTONUP | WM_CONTEXTMENU => {
+                    let mut pt = windows::Win32::Foundation::POINT::default();
+                    let _ = GetCursorPos(&mut pt);
+                    show_context_menu(hwnd, pt.x, pt.y);
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+
+        WM_TOPBAR_IPC => LRESULT(crate::ipc::handle_message(hwnd, lparam)),
+
+        // Keyboard navigation; F10 arrives as a system key
+        WM_KEYDOWN | WM_SYSKEYDOWN if super::focus::is_active() => {
+            if super::focus::handle_key(
+                hwnd,
+                windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY(wparam.0 as u16),
+            ) {
+                LRESULT(0)
+            } else {
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+        }
+
+        WM_KILLFOCUS => {
+            super::focus::focus_lost(hwnd, HWND(wparam.0 as *mut std::ffi::c_void));
+            LRESULT(0)
+        }
+
+        WM_TOPBAR_MODULE_CLICK => {
+            for (module_id, x) in super::automation::take_invoked() {
+                handle_module_click(hwnd, &module_id, x);
+            }
+            LRESULT(0)
+        }
+
+        WM_GETOBJECT if super::automation::is_root_request(lparam) => {
+            if let Some(state) = get_window_state() {
+                let (config, bar_rect) = {
+                    let s = state.read();
+                    (s.config.clone(), s.bar_rect)
+                };
+                with_renderer(|renderer| super::automation::update(renderer, &config, &bar_rect));
+            }
+            super::automation::provide(hwnd, wparam, lparam)
+        }
+
         WM_TOPBAR_THEME_CHANGED => {
             if let Some(state) = get_window_state() {
                 let state_guard = state.read();
                 let theme = state_guard.theme_manager.theme().clone();
                 drop(state_guard);
+                crate::modules::events::publish(crate::modules::events::THEME_CHANGED);
                 let _ = super::manager::WindowManager::apply_window_style(hwnd, &theme);
                 let _ = InvalidateRect(hwnd, None, true);
             }
@@ -494,14 +786,8 @@ pub unsafe extern "system" fn window_proc(
         }
 
         WM_TOPBAR_NIGHTLIGHT_TOGGLED => {
-            // Refresh night_light module state and request a redraw
-            with_renderer(|renderer| {
-                if let Some(module) = renderer.module_registry.get_mut("night_light") {
-                    if let Some(nm) = module.as_any_mut().downcast_mut::<crate::modules::night_light::NightLightModule>() {
-                        nm.refresh();
-                    }
-                }
-            });
+            // The night light module refreshes its state on the redraw
+            crate::modules::events::publish(crate::modules::events::NIGHT_LIGHT_CHANGED);
 
             if let Some(state) = get_window_state() {
                 state.write().needs_redraw = true;
@@ -534,4 +820,65 @@ pub unsafe extern "system" fn window_proc(
 
         _ => DefWindowProcW(hwnd, msg, wparam, lparam),
     }
-}
\ No newline at end of file
+}
+
+/// The flick gesture a press and release on empty bar space made, with its
+/// action, if it was quick and long enough
+fn flick(
+    gestures: &crate::config::GesturesConfig,
+    dx: i32,
+    dy: i32,
+    elapsed: std::time::Duration,
+    dpi: u32,
+) -> Option<(&'static str, &str)> {
+    let distance = crate::utils::scale_by_dpi(FLICK_DISTANCE, dpi);
+    if elapsed > FLICK_TIME || dx.abs().max(dy.abs()) < distance {
+        return None;
+    }
+    Some(if dx.abs() > dy.abs() {
+        if dx > 0 {
+            ("Flick right", gestures.flick_right.as_str())
+        } else {
+            ("Flick left", gestures.flick_left.as_str())
+        }
+    } else if dy > 0 {
+        ("Flick down", gestures.flick_down.as_str())
+    } else {
+        ("Flick up", gestures.flick_up.as_str())
+    })
+}
+
+/// Run the action configured for a gesture, if any
+fn run_gesture(hwnd: HWND, gesture: &str, spec: &str) {
+    if spec.trim().is_empty() {
+        return;
+    }
+    if let Err(e) = crate::actions::execute(hwnd, spec) {
+        warn!("{} action {} failed: {}", gesture, spec, e);
+    }
+}
+
+/// Mouse position of a message, in window coordinates
+fn client_point(lparam: LPARAM) -> (i32, i32) {
+    ((lparam.0 & 0xFFFF) as i16 as i32, ((lparam.0 >> 16) & 0xFFFF) as i16 as i32)
+}
+
+/// Mouse position of a message in the bar's layout, where module bounds are
+/// kept; the same as the window's unless the bar is vertical
+fn layout_point(lparam: LPARAM) -> (i32, i32) {
+    let (x, y) = client_point(lparam);
+    to_layout(x, y)
+}
+
+fn to_layout(x: i32, y: i32) -> (i32, i32) {
+    let bar_rect = get_window_state().map(|s| s.read().bar_rect).unwrap_or_default();
+    super::manager::WindowManager::to_layout(&bar_rect, x, y)
+}
+
+/// Hide the module tooltip and cancel a pending one
+fn cancel_tooltip(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, TOOLTIP_TIMER);
+    }
+    render::hide_tooltip();
+}
@@ -0,0 +1,185 @@
+//! Window snapping - moves another app's window to a region of its monitor
+//!
+//! Used by the app menu's "Window" submenu and the snap hotkeys, and by tiling
+//! (see `tile`). Positions are based on the monitor's work area, so the space
+//! reserved by the bar and the taskbar is respected. Windows 10/11 draw invisible
+//! resize borders around most windows; the target rectangle is expanded by them so
+//! the visible frame lines up with the edges.
+
+use log::{debug, info};
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+/// Where to place a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapLayout {
+    LeftHalf,
+    RightHalf,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Maximize,
+    Center,
+}
+
+impl SnapLayout {
+    /// All layouts, in menu order
+    pub const ALL: [SnapLayout; 8] = [
+        Self::LeftHalf,
+        Self::RightHalf,
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomLeft,
+        Self::BottomRight,
+        Self::Maximize,
+        Self::Center,
+    ];
+
+    /// Menu label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::LeftHalf => "Left Half",
+            Self::RightHalf => "Right Half",
+            Self::TopLeft => "Top Left Quarter",
+            Self::TopRight => "Top Right Quarter",
+            Self::BottomLeft => "Bottom Left Quarter",
+            Self::BottomRight => "Bottom Right Quarter",
+            Self::Maximize => "Maximize",
+            Self::Center => "Center",
+        }
+    }
+
+    /// Name used in config, e.g. "left_half"
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::LeftHalf => "left_half",
+            Self::RightHalf => "right_half",
+            Self::TopLeft => "top_left",
+            Self::TopRight => "top_right",
+            Self::BottomLeft => "bottom_left",
+            Self::BottomRight => "bottom_right",
+            Self::Maximize => "maximize",
+            Self::Center => "center",
+        }
+    }
+
+    /// Target rectangle within a work area (x, y, width, height)
+    fn target(&self, work: &RECT, current: &RECT) -> (i32, i32, i32, i32) {
+        let (x, y) = (work.left, work.top);
+        let (w, h) = (work.right - work.left, work.bottom - work.top);
+        let (half_w, half_h) = (w / 2, h / 2);
+        match self {
+            Self::LeftHalf => (x, y, half_w, h),
+            Self::RightHalf => (x + half_w, y, w - half_w, h),
+            Self::TopLeft => (x, y, half_w, half_h),
+            Self::TopRight => (x + half_w, y, w - half_w, half_h),
+            Self::BottomLeft => (x, y + half_h, half_w, h - half_h),
+            Self::BottomRight => (x + half_w, y + half_h, w - half_w, h - half_h),
+            Self::Maximize => (x, y, w, h),
+            Self::Center => {
+                // Keep the current size, capped to the work area
+                let cw = (current.right - current.left).min(w);
+                let ch = (current.bottom - current.top).min(h);
+                (x + (w - cw) / 2, y + (h - ch) / 2, cw, ch)
+            }
+        }
+    }
+}
+
+/// Widths of the invisible resize borders around a window (left, top, right, bottom)
+pub(super) fn invisible_borders(hwnd: HWND, window: &RECT) -> (i32, i32, i32, i32) {
+    let mut frame = RECT::default();
+    let ok = unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut frame as *mut _ as *mut _,
+            std::mem::size_of::<RECT>() as u32,
+        )
+        .is_ok()
+    };
+    if !ok {
+        return (0, 0, 0, 0);
+    }
+    (
+        frame.left - window.left,
+        frame.top - window.top,
+        window.right - frame.right,
+        window.bottom - frame.bottom,
+    )
+}
+
+/// Whether a window is a normal app window that can be snapped
+pub(super) fn is_snappable(hwnd: HWND) -> bool {
+    if hwnd.0.is_null() || Some(hwnd) == super::get_main_hwnd() {
+        return false;
+    }
+    unsafe {
+        if !IsWindowVisible(hwnd).as_bool() {
+            return false;
+        }
+        // The desktop and the taskbar aren't movable app windows
+        let mut class = [0u16; 64];
+        let len = GetClassNameW(hwnd, &mut class) as usize;
+        let class = String::from_utf16_lossy(&class[..len]);
+        !matches!(class.as_str(), "Progman" | "WorkerW" | "Shell_TrayWnd" | "Shell_SecondaryTrayWnd")
+    }
+}
+
+/// Move a window to the given layout on the monitor it's mostly on
+pub fn snap_window(hwnd: HWND, layout: SnapLayout) {
+    if !is_snappable(hwnd) {
+        debug!("Snap: window {:?} can't be snapped", hwnd);
+        return;
+    }
+
+    unsafe {
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return;
+        }
+
+        if layout == SnapLayout::Maximize {
+            let _ = ShowWindow(hwnd, SW_MAXIMIZE);
+            info!("Snap: maximized {:?}", hwnd);
+            return;
+        }
+        // A maximized window ignores SetWindowPos until it's restored
+        if IsZoomed(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+
+        let mut window = RECT::default();
+        let _ = GetWindowRect(hwnd, &mut window);
+        let (mut x, mut y, mut w, mut h) = layout.target(&info.rcWork, &window);
+
+        // Grow the target by the invisible borders (frame bounds are inside the window rect)
+        let (left, top, right, bottom) = invisible_borders(hwnd, &window);
+        if layout == SnapLayout::Center {
+            // Center keeps the window rect size, so only the position needs the offset
+            x -= (left - right) / 2;
+            y -= (top - bottom) / 2;
+        } else {
+            x -= left;
+            y -= top;
+            w += left + right;
+            h += top + bottom;
+        }
+
+        let _ = SetWindowPos(hwnd, None, x, y, w, h, SWP_NOZORDER | SWP_NOACTIVATE);
+        info!("Snap: moved {:?} to {:?}", hwnd, layout);
+    }
+}
+
+/// Snap the current foreground window
+pub fn snap_foreground(layout: SnapLayout) {
+    let hwnd = unsafe { GetForegroundWindow() };
+    snap_window(hwnd, layout);
+}
@@ -23,6 +23,10 @@ pub struct WindowState {
     pub active_menu: Option<String>,
     pub needs_redraw: bool,
     pub clicked_module: Option<String>,
+    /// Module whose value was just copied with Ctrl+click, and when
+    pub copied_flash: Option<(String, std::time::Instant)>,
+    /// Module with the keyboard focus ring (see `focus`)
+    pub focused_module: Option<String>,
 
     // Drag-and-drop state for rearranging modules
     pub clicked_pos: Option<(i32, i32)>,
@@ -31,11 +35,15 @@ pub struct WindowState {
     pub drag_current_x: i32,
     pub drag_origin_side: Option<String>, // "left" or "right"
     pub drag_orig_index: Option<usize>,
+
+    /// Where and when a press on empty bar space started, for flick gestures
+    pub gesture_start: Option<(i32, i32, std::time::Instant)>,
 }
 
 impl WindowState {
     pub fn new(config: Arc<Config>) -> Self {
-        let theme_manager = ThemeManager::new(config.appearance.theme_mode);
+        let mut theme_manager = ThemeManager::new(config.appearance.theme_mode);
+        theme_manager.set_appearance(&config.appearance);
 
         Self {
             config,
@@ -48,6 +56,8 @@ impl WindowState {
             active_menu: None,
             needs_redraw: true,
             clicked_module: None,
+            copied_flash: None,
+            focused_module: None,
 
             // Drag state defaults
             clicked_pos: None,
@@ -56,6 +66,8 @@ impl WindowState {
             drag_current_x: 0,
             drag_origin_side: None,
             drag_orig_index: None,
+
+            gesture_start: None,
         }
     }
 }
@@ -0,0 +1,257 @@
+//! Window tiling - arranges every app window on a monitor at once
+//!
+//! Used by the app menu's "Window" submenu and the tile hotkeys. The windows are
+//! the visible, non-minimized app windows on the monitor of the focused window,
+//! taken in Z-order, so the focused window gets the first cell (the main area in
+//! main + stack). Each cell is placed the way snapping places a window.
+
+use log::info;
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, POINT, RECT};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, MonitorFromPoint, MonitorFromWindow, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::snap::{invisible_borders, is_snappable};
+
+/// Share of the work area's width given to the main window in main + stack
+const MAIN_RATIO: f32 = 0.6;
+
+/// How to arrange the windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileLayout {
+    Grid,
+    Columns,
+    MainStack,
+}
+
+impl TileLayout {
+    /// All layouts, in menu order
+    pub const ALL: [TileLayout; 3] = [Self::Grid, Self::Columns, Self::MainStack];
+
+    /// Menu label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Grid => "Tile as Grid",
+            Self::Columns => "Tile as Columns",
+            Self::MainStack => "Tile as Main + Stack",
+        }
+    }
+
+    /// Name used in config, e.g. "main_stack"
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::Columns => "columns",
+            Self::MainStack => "main_stack",
+        }
+    }
+
+    /// Cells for `count` windows within a work area, in window order
+    fn cells(&self, count: usize, work: &RECT) -> Vec<RECT> {
+        let (x, y) = (work.left, work.top);
+        let (w, h) = (work.right - work.left, work.bottom - work.top);
+        if count == 0 {
+            return Vec::new();
+        }
+        match self {
+            Self::Columns => split(x, w, count)
+                .into_iter()
+                .map(|(left, width)| rect(left, y, width, h))
+                .collect(),
+            Self::MainStack if count > 1 => {
+                let main_w = (w as f32 * MAIN_RATIO) as i32;
+                std::iter::once(rect(x, y, main_w, h))
+                    .chain(
+                        split(y, h, count - 1)
+                            .into_iter()
+                            .map(|(top, height)| rect(x + main_w, top, w - main_w, height)),
+                    )
+                    .collect()
+            }
+            Self::MainStack => vec![rect(x, y, w, h)],
+            Self::Grid => {
+                // As square as possible; a short last row stretches across the width
+                let columns = (count as f32).sqrt().ceil() as usize;
+                let rows = count.div_ceil(columns);
+                split(y, h, rows)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(row, (top, height))| {
+                        let in_row = columns.min(count - row * columns);
+                        split(x, w, in_row)
+                            .into_iter()
+                            .map(move |(left, width)| rect(left, top, width, height))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Divide a span into `parts` nearly equal pieces (start, length); the last takes the remainder
+fn split(start: i32, length: i32, parts: usize) -> Vec<(i32, i32)> {
+    let size = length / parts as i32;
+    (0..parts as i32)
+        .map(|i| {
+            let from = start + i * size;
+            let to = if i == parts as i32 - 1 { start + length } else { from + size };
+            (from, to - from)
+        })
+        .collect()
+}
+
+fn rect(x: i32, y: i32, width: i32, height: i32) -> RECT {
+    RECT {
+        left: x,
+        top: y,
+        right: x + width,
+        bottom: y + height,
+    }
+}
+
+struct Collect {
+    monitor: HMONITOR,
+    windows: Vec<HWND>,
+}
+
+unsafe extern "system" fn collect_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let collect = &mut *(lparam.0 as *mut Collect);
+    if is_tileable(hwnd) && MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) == collect.monitor {
+        collect.windows.push(hwnd);
+    }
+    BOOL(1)
+}
+
+/// A top-level app window that's actually on screen
+unsafe fn is_tileable(hwnd: HWND) -> bool {
+    if !is_snappable(hwnd) || IsIconic(hwnd).as_bool() || GetWindowTextLengthW(hwnd) == 0 {
+        return false;
+    }
+    // Owned windows are dialogs and palettes; tool windows aren't in Alt+Tab
+    if GetWindow(hwnd, GW_OWNER).map(|owner| !owner.0.is_null()).unwrap_or(false) {
+        return false;
+    }
+    let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+    if ex_style & WS_EX_TOOLWINDOW.0 != 0 || ex_style & WS_EX_NOACTIVATE.0 != 0 {
+        return false;
+    }
+    // Windows on other virtual desktops and suspended store apps are cloaked
+    let mut cloaked = 0u32;
+    let _ = DwmGetWindowAttribute(
+        hwnd,
+        DWMWA_CLOAKED,
+        &mut cloaked as *mut _ as *mut _,
+        std::mem::size_of::<u32>() as u32,
+    );
+    cloaked == 0
+}
+
+/// Tile the windows on the focused window's monitor (or the cursor's, when
+/// nothing tileable has focus)
+pub fn tile_windows(layout: TileLayout) {
+    unsafe {
+        let foreground = GetForegroundWindow();
+        let monitor = if is_tileable(foreground) {
+            MonitorFromWindow(foreground, MONITOR_DEFAULTTONEAREST)
+        } else {
+            let mut cursor = POINT::default();
+            let _ = GetCursorPos(&mut cursor);
+            MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST)
+        };
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return;
+        }
+
+        let mut collect = Collect {
+            monitor,
+            windows: Vec::new(),
+        };
+        let _ = EnumWindows(Some(collect_window), LPARAM(&mut collect as *mut Collect as isize));
+        // EnumWindows goes top to bottom; make sure the focused window leads
+        if let Some(pos) = collect.windows.iter().position(|w| *w == foreground) {
+            collect.windows[..=pos].rotate_right(1);
+        }
+
+        let cells = layout.cells(collect.windows.len(), &info.rcWork);
+        for (hwnd, cell) in collect.windows.iter().zip(&cells) {
+            // A maximized window ignores SetWindowPos until it's restored
+            if IsZoomed(*hwnd).as_bool() {
+                let _ = ShowWindow(*hwnd, SW_RESTORE);
+            }
+            let mut window = RECT::default();
+            let _ = GetWindowRect(*hwnd, &mut window);
+            let (left, top, right, bottom) = invisible_borders(*hwnd, &window);
+            let _ = SetWindowPos(
+                *hwnd,
+                None,
+                cell.left - left,
+                cell.top - top,
+                cell.right - cell.left + left + right,
+                cell.bottom - cell.top + top + bottom,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+        info!("Tile: arranged {} windows as {:?}", cells.len(), layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (left, top, width, height) of each cell
+    fn cells(layout: TileLayout, count: usize) -> Vec<(i32, i32, i32, i32)> {
+        let work = rect(0, 40, 1000, 600);
+        layout
+            .cells(count, &work)
+            .iter()
+            .map(|r| (r.left, r.top, r.right - r.left, r.bottom - r.top))
+            .collect()
+    }
+
+    #[test]
+    fn split_gives_the_remainder_to_the_last_piece() {
+        assert_eq!(split(0, 10, 3), vec![(0, 3), (3, 3), (6, 4)]);
+        assert_eq!(split(5, 10, 1), vec![(5, 10)]);
+    }
+
+    #[test]
+    fn no_windows_no_cells() {
+        for layout in TileLayout::ALL {
+            assert!(cells(layout, 0).is_empty());
+        }
+    }
+
+    #[test]
+    fn columns_share_the_width() {
+        assert_eq!(cells(TileLayout::Columns, 3), vec![(0, 40, 333, 600), (333, 40, 333, 600), (666, 40, 334, 600)]);
+    }
+
+    #[test]
+    fn main_stack_puts_the_rest_beside_the_main_window() {
+        assert_eq!(cells(TileLayout::MainStack, 1), vec![(0, 40, 1000, 600)]);
+        assert_eq!(
+            cells(TileLayout::MainStack, 3),
+            vec![(0, 40, 600, 600), (600, 40, 400, 300), (600, 340, 400, 300)]
+        );
+    }
+
+    #[test]
+    fn grid_stretches_a_short_last_row() {
+        assert_eq!(
+            cells(TileLayout::Grid, 3),
+            vec![(0, 40, 500, 300), (500, 40, 500, 300), (0, 340, 1000, 300)]
+        );
+        assert_eq!(cells(TileLayout::Grid, 4).len(), 4);
+        // Five windows make a 3 + 2 grid
+        let five = cells(TileLayout::Grid, 5);
+        assert_eq!(five.iter().filter(|c| c.1 == 40).count(), 3);
+        assert_eq!(five[4], (500, 340, 500, 300));
+    }
+}